@@ -0,0 +1,17 @@
+use std::process::Command;
+
+// Make the git commit the binary is built from available as GIT_HASH, if the
+// source is a git checkout.
+fn main() {
+    let output = Command::new("git").args(["rev-parse", "HEAD"]).output();
+
+    if let Ok(output) = output {
+        if output.status.success() {
+            let hash = String::from_utf8_lossy(&output.stdout);
+            println!("cargo:rustc-env=GIT_HASH={}", hash.trim());
+        }
+    }
+
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
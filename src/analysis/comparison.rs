@@ -0,0 +1,184 @@
+//! Comparison of the outcomes of two run logs, for example of two strategies
+//! or of two versions of a strategy playing the same seeds.
+
+use runlog::RunRecord;
+use std::collections::HashMap;
+
+/// Win rates of two runs and the deals whose outcome differs between them.
+///
+/// Deals are matched by seed and draw count. When a run played the same deal
+/// more than once only its first record is used for matching; every record
+/// counts towards the win rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+  games: [usize; 2],
+  wins: [usize; 2],
+  matched: usize,
+  gained: Vec<u64>,
+  dropped: Vec<u64>,
+}
+
+impl Comparison {
+  pub fn new(before: &[RunRecord], after: &[RunRecord]) -> Comparison {
+    let mut outcomes = HashMap::new();
+    for r in before {
+      outcomes.entry((r.seed, r.draw_count)).or_insert(r.won);
+    }
+
+    let mut matched = HashMap::new();
+    for r in after {
+      if let Some(won) = outcomes.get(&(r.seed, r.draw_count)) {
+        matched.entry((r.seed, r.draw_count)).or_insert((*won, r.won));
+      }
+    }
+
+    let mut gained: Vec<u64> = matched.iter().filter(|&(_, o)| *o == (false, true)).map(|(k, _)| k.0).collect();
+    let mut dropped: Vec<u64> = matched.iter().filter(|&(_, o)| *o == (true, false)).map(|(k, _)| k.0).collect();
+    gained.sort();
+    dropped.sort();
+
+    Comparison {
+      games: [before.len(), after.len()],
+      wins: [wins(before), wins(after)],
+      matched: matched.len(),
+      gained: gained,
+      dropped: dropped,
+    }
+  }
+
+  /// Games played and won by the first run.
+  pub fn before(&self) -> (usize, usize) {
+    (self.wins[0], self.games[0])
+  }
+
+  /// Games played and won by the second run.
+  pub fn after(&self) -> (usize, usize) {
+    (self.wins[1], self.games[1])
+  }
+
+  /// Number of deals played by both runs.
+  pub fn matched(&self) -> usize {
+    self.matched
+  }
+
+  /// Seeds lost by the first run and won by the second.
+  pub fn gained(&self) -> &[u64] {
+    &self.gained[..]
+  }
+
+  /// Seeds won by the first run and lost by the second.
+  pub fn dropped(&self) -> &[u64] {
+    &self.dropped[..]
+  }
+
+  /// Win rate of the second run minus the win rate of the first, from -1 to 1.
+  pub fn change(&self) -> f64 {
+    rate(self.wins[1], self.games[1]) - rate(self.wins[0], self.games[0])
+  }
+
+  /// Two sided p-value of the win rate change under a two proportion z-test,
+  /// or `None` if a run is empty or the pooled win rate is 0 or 1.
+  pub fn change_p_value(&self) -> Option<f64> {
+    if self.games[0] == 0 || self.games[1] == 0 {
+      return None;
+    }
+
+    let pooled = rate(self.wins[0] + self.wins[1], self.games[0] + self.games[1]);
+    let error = (pooled * (1.0 - pooled) * (1.0 / self.games[0] as f64 + 1.0 / self.games[1] as f64)).sqrt();
+
+    if error == 0.0 {
+      return None;
+    }
+
+    let z = self.change() / error;
+    Some(erfc(z.abs() / 2f64.sqrt()))
+  }
+
+  /// P-value of McNemar's test with continuity correction on the matched
+  /// deals, or `None` if no matched deal changed outcome. Pairing the deals
+  /// makes this more sensitive than `change_p_value` when both runs played
+  /// the same seeds.
+  pub fn flip_p_value(&self) -> Option<f64> {
+    let flips = self.gained.len() + self.dropped.len();
+
+    if flips == 0 {
+      return None;
+    }
+
+    let difference = (self.gained.len() as f64 - self.dropped.len() as f64).abs();
+    let chi_squared = (difference - 1.0).max(0.0).powi(2) / flips as f64;
+    Some(erfc((chi_squared / 2.0).sqrt()))
+  }
+}
+
+fn wins(records: &[RunRecord]) -> usize {
+  records.iter().filter(|r| r.won).count()
+}
+
+fn rate(wins: usize, games: usize) -> f64 {
+  wins as f64 / ::std::cmp::max(games, 1) as f64
+}
+
+/// Complementary error function, Abramowitz and Stegun 7.1.26. The absolute
+/// error is below 1.5e-7, plenty for reporting p-values.
+fn erfc(x: f64) -> f64 {
+  let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+  let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+  let value = poly * (-x * x).exp();
+
+  if x >= 0.0 { value } else { 2.0 - value }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn record(seed: u64, won: bool) -> RunRecord {
+    RunRecord {
+      seed: seed,
+      draw_count: 1,
+      strategy: String::from("simple"),
+      won: won,
+      founded: if won { 52 } else { 0 },
+      board: String::new(),
+      moves: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn flipped_seeds() {
+    let before = vec![record(1, true), record(2, false), record(3, false), record(4, true)];
+    let after = vec![record(4, false), record(3, true), record(2, true), record(1, true), record(5, true)];
+    let comparison = Comparison::new(&before, &after);
+
+    assert_eq!(comparison.before(), (2, 4));
+    assert_eq!(comparison.after(), (4, 5));
+    assert_eq!(comparison.matched(), 4);
+    assert_eq!(comparison.gained(), &[2, 3]);
+    assert_eq!(comparison.dropped(), &[4]);
+    assert!((comparison.change() - 0.3).abs() < 1e-9);
+  }
+
+  #[test]
+  fn p_values() {
+    let before: Vec<RunRecord> = (0..1000).map(|s| record(s, s < 300)).collect();
+    let same = Comparison::new(&before, &before);
+    assert_eq!(same.flip_p_value(), None);
+    assert!((same.change_p_value().unwrap() - 1.0).abs() < 1e-6);
+
+    let after: Vec<RunRecord> = (0..1000).map(|s| record(s, s < 400)).collect();
+    let better = Comparison::new(&before, &after);
+    assert!(better.change_p_value().unwrap() < 1e-4);
+    assert!(better.flip_p_value().unwrap() < 1e-4);
+
+    let none: Vec<RunRecord> = (0..10).map(|s| record(s, false)).collect();
+    assert_eq!(Comparison::new(&none, &none).change_p_value(), None);
+  }
+
+  #[test]
+  fn erfc_values() {
+    assert!((erfc(0.0) - 1.0).abs() < 1e-6);
+    assert!((erfc(1.0) - 0.157299).abs() < 1e-6);
+    assert!((erfc(-1.0) - 1.842701).abs() < 1e-6);
+  }
+}
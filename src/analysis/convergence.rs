@@ -0,0 +1,125 @@
+/// Outcome of every game by the order it was dealt, to follow how the
+/// cumulative win rate settles as more games are played.
+///
+/// Workers record games by their global deal number; results of several
+/// workers are combined with `merge`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Convergence {
+  outcomes: Vec<Option<bool>>,
+}
+
+/// Cumulative win rate after the first `games` games.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ConvergencePoint {
+  pub games: usize,
+  pub wins: usize,
+  /// Half width of the 95% confidence interval of the win rate, using the
+  /// normal approximation.
+  pub margin: f64,
+}
+
+impl ConvergencePoint {
+  pub fn win_rate(&self) -> f64 {
+    self.wins as f64 / self.games as f64
+  }
+}
+
+impl Convergence {
+  pub fn new() -> Convergence {
+    Convergence {
+      outcomes: Vec::new(),
+    }
+  }
+
+  pub fn record(&mut self, game: usize, won: bool) {
+    if self.outcomes.len() <= game {
+      self.outcomes.resize(game + 1, None);
+    }
+
+    self.outcomes[game] = Some(won);
+  }
+
+  pub fn merge(&mut self, other: &Convergence) {
+    for (game, outcome) in other.outcomes.iter().enumerate() {
+      if let Some(won) = *outcome {
+        self.record(game, won);
+      }
+    }
+  }
+
+  /// Cumulative win rate every `step` games, and after the last game. Stops at
+  /// the first game that was not recorded.
+  pub fn series(&self, step: usize) -> Vec<ConvergencePoint> {
+    assert!(step > 0);
+
+    let mut points = Vec::new();
+    let mut wins = 0;
+    let mut games = 0;
+
+    for outcome in &self.outcomes {
+      match *outcome {
+        Some(won) => {
+          games += 1;
+          if won {
+            wins += 1;
+          }
+        },
+        None => break,
+      }
+
+      if games % step == 0 {
+        points.push(point(games, wins));
+      }
+    }
+
+    if games % step != 0 {
+      points.push(point(games, wins));
+    }
+
+    points
+  }
+}
+
+fn point(games: usize, wins: usize) -> ConvergencePoint {
+  let rate = wins as f64 / games as f64;
+
+  ConvergencePoint {
+    games: games,
+    wins: wins,
+    margin: 1.96 * (rate * (1.0 - rate) / games as f64).sqrt(),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn series() {
+    let mut a = Convergence::new();
+    let mut b = Convergence::new();
+
+    for game in 0..10 {
+      let worker = if game % 2 == 0 { &mut a } else { &mut b };
+      worker.record(game, game < 4);
+    }
+
+    a.merge(&b);
+    let points = a.series(4);
+
+    assert_eq!(points.iter().map(|p| (p.games, p.wins)).collect::<Vec<_>>(), vec![(4, 4), (8, 4), (10, 4)]);
+    assert_eq!(points[0].margin, 0.0);
+    assert!((points[2].win_rate() - 0.4).abs() < 1e-9);
+    assert!((points[2].margin - 1.96 * (0.24f64 / 10.0).sqrt()).abs() < 1e-9);
+  }
+
+  #[test]
+  fn series_stops_at_missing_game() {
+    let mut c = Convergence::new();
+    c.record(0, true);
+    c.record(2, true);
+
+    assert_eq!(c.series(1).len(), 1);
+    assert!(Convergence::new().series(5).is_empty());
+  }
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+
+/// Counts of small integer values, e.g. the number of moves in each game.
+///
+/// Percentiles use the nearest rank method, so they are always a recorded
+/// value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+  counts: BTreeMap<usize, usize>,
+  total: usize,
+}
+
+impl Histogram {
+  pub fn new() -> Histogram {
+    Histogram {
+      counts: BTreeMap::new(),
+      total: 0,
+    }
+  }
+
+  pub fn record(&mut self, value: usize) {
+    *self.counts.entry(value).or_insert(0) += 1;
+    self.total += 1;
+  }
+
+  pub fn merge(&mut self, other: &Histogram) {
+    for (value, count) in &other.counts {
+      *self.counts.entry(*value).or_insert(0) += *count;
+    }
+
+    self.total += other.total;
+  }
+
+  /// Number of recorded values.
+  pub fn len(&self) -> usize {
+    self.total
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.total == 0
+  }
+
+  /// Smallest value that at least `percent` percent of the values are less
+  /// than or equal to.
+  pub fn percentile(&self, percent: f64) -> Option<usize> {
+    if self.total == 0 {
+      return None;
+    }
+
+    let rank = ((percent / 100.0 * self.total as f64).ceil() as usize).max(1);
+    let mut seen = 0;
+
+    for (value, count) in &self.counts {
+      seen += *count;
+      if seen >= rank {
+        return Some(*value);
+      }
+    }
+
+    self.max()
+  }
+
+  /// Recorded values and how often each was recorded, smallest value first.
+  pub fn counts(&self) -> btree_map::Iter<'_, usize, usize> {
+    self.counts.iter()
+  }
+
+  pub fn median(&self) -> Option<usize> {
+    self.percentile(50.0)
+  }
+
+  pub fn max(&self) -> Option<usize> {
+    self.counts.keys().next_back().cloned()
+  }
+
+  pub fn mean(&self) -> Option<f64> {
+    if self.total == 0 {
+      return None;
+    }
+
+    let sum: usize = self.counts.iter().map(|(v, c)| v * c).sum();
+    Some(sum as f64 / self.total as f64)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn percentiles() {
+    let mut h = Histogram::new();
+    assert_eq!(h.median(), None);
+
+    for v in 1..11 {
+      h.record(v);
+    }
+
+    assert_eq!(h.len(), 10);
+    assert_eq!(h.median(), Some(5));
+    assert_eq!(h.percentile(90.0), Some(9));
+    assert_eq!(h.percentile(0.0), Some(1));
+    assert_eq!(h.max(), Some(10));
+    assert_eq!(h.mean(), Some(5.5));
+  }
+
+  #[test]
+  fn merge() {
+    let mut a = Histogram::new();
+    a.record(3);
+    a.record(3);
+
+    let mut b = Histogram::new();
+    b.record(7);
+    a.merge(&b);
+
+    assert_eq!(a.len(), 3);
+    assert_eq!(a.median(), Some(3));
+    assert_eq!(a.max(), Some(7));
+    assert_eq!(a.counts().collect::<Vec<_>>(), vec![(&3, &2), (&7, &1)]);
+  }
+}
@@ -0,0 +1,6 @@
+pub mod comparison;
+pub mod convergence;
+pub mod histogram;
+pub mod solitaire;
+pub mod streaks;
+pub mod sum;
@@ -0,0 +1,501 @@
+use games::solitaire::klondike::{KlondikeSolitaireGame, Card, GameStats, MoveKind};
+use cards::french::{Rank, Suit};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Why the bottom face up card of a pile could not be moved off of the
+/// pile's hidden cards.
+///
+/// Variants are ordered from most to least recoverable. When more than one
+/// card could accept the blocking card, the most recoverable reason is used.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BlockReason {
+  /// The card could have been moved; the strategy missed a legal move.
+  TargetPlayable,
+  /// A card it could be placed on is face up in a pile, but covered.
+  TargetCovered,
+  /// A card it could be placed on is still in the deck.
+  TargetInDeck,
+  /// Every card it could be placed on is face down in a pile.
+  TargetHidden,
+  /// Every card it could be placed on is already on a foundation.
+  TargetOnFoundation,
+  /// The card is a king and there is no empty pile to move it to.
+  NoEmptyPile,
+}
+
+impl fmt::Display for BlockReason {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let s = match *self {
+      BlockReason::TargetPlayable => "target playable",
+      BlockReason::TargetCovered => "target covered",
+      BlockReason::TargetInDeck => "target in deck",
+      BlockReason::TargetHidden => "target hidden",
+      BlockReason::TargetOnFoundation => "target on foundation",
+      BlockReason::NoEmptyPile => "no empty pile",
+    };
+
+    write!(f, "{}", s)
+  }
+}
+
+/// A face up card sitting directly on top of a pile's hidden cards at the end
+/// of a lost game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Blocker {
+  rank: Rank,
+  reason: BlockReason,
+}
+
+impl Blocker {
+  pub fn rank(&self) -> Rank {
+    self.rank
+  }
+
+  pub fn reason(&self) -> BlockReason {
+    self.reason
+  }
+}
+
+impl fmt::Display for Blocker {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}({})", self.rank, self.reason)
+  }
+}
+
+/// Suit independent description of how a game ended.
+///
+/// Only piles that still have hidden cards contribute a blocker. Blockers are
+/// sorted so that the same configuration on different piles or suits results
+/// in equal patterns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TerminalPattern {
+  blockers: Vec<Blocker>,
+}
+
+impl TerminalPattern {
+  pub fn blockers(&self) -> &[Blocker] {
+    &self.blockers[..]
+  }
+}
+
+impl fmt::Display for TerminalPattern {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.blockers.is_empty() {
+      return write!(f, "no blocked piles");
+    }
+
+    for (i, blocker) in self.blockers.iter().enumerate() {
+      if i > 0 {
+        write!(f, " ")?;
+      }
+      write!(f, "{}", blocker)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Classify the final state of a game.
+pub fn classify(game: &KlondikeSolitaireGame) -> TerminalPattern {
+  let has_empty_pile = game.piles().iter().any(|p| p.is_empty());
+  let mut blockers = Vec::new();
+
+  for pile in game.piles().iter().filter(|p| ! p.hidden_cards().is_empty()) {
+    let card = pile.visible_cards()[0];
+
+    let reason = if card.rank() == Rank::King {
+      if has_empty_pile { BlockReason::TargetPlayable } else { BlockReason::NoEmptyPile }
+    } else {
+      targets(card).iter()
+        .map(|t| locate(game, *t))
+        .min()
+        .unwrap()
+    };
+
+    blockers.push(Blocker{rank: card.rank(), reason: reason});
+  }
+
+  blockers.sort_by(|a, b| b.cmp(a));
+  TerminalPattern{blockers: blockers}
+}
+
+/// The two cards that `card` can be placed on in a pile.
+fn targets(card: Card) -> Vec<Card> {
+  let rank = *Rank::standard_iter().skip_while(|r| **r != card.rank()).nth(1).unwrap();
+
+  Suit::standard_iter()
+    .filter(|s| s.color() != card.color())
+    .map(|s| Card::new(*s, rank))
+    .collect()
+}
+
+fn locate(game: &KlondikeSolitaireGame, card: Card) -> BlockReason {
+  if game.foundation(card.suit()).cards().contains(&card) {
+    return BlockReason::TargetOnFoundation;
+  }
+
+  for pile in game.piles() {
+    if pile.top() == Some(card) {
+      return BlockReason::TargetPlayable;
+    }
+
+    if pile.visible_cards().contains(&card) {
+      return BlockReason::TargetCovered;
+    }
+
+    if pile.hidden_cards().contains(&card) {
+      return BlockReason::TargetHidden;
+    }
+  }
+
+  BlockReason::TargetInDeck
+}
+
+/// Counts of terminal patterns and individual blockers over many lost games.
+pub struct LossPatterns {
+  losses: usize,
+  patterns: HashMap<TerminalPattern, usize>,
+  blockers: HashMap<Blocker, usize>,
+}
+
+impl LossPatterns {
+  pub fn new() -> LossPatterns {
+    LossPatterns {
+      losses: 0,
+      patterns: HashMap::new(),
+      blockers: HashMap::new(),
+    }
+  }
+
+  /// Number of lost games recorded.
+  pub fn losses(&self) -> usize {
+    self.losses
+  }
+
+  /// Record the final state of a lost game.
+  pub fn record(&mut self, game: &KlondikeSolitaireGame) {
+    let pattern = classify(game);
+
+    for blocker in pattern.blockers() {
+      *self.blockers.entry(*blocker).or_insert(0) += 1;
+    }
+
+    *self.patterns.entry(pattern).or_insert(0) += 1;
+    self.losses += 1;
+  }
+
+  /// Combine the counts of another collection (e.g. from another thread).
+  pub fn merge(&mut self, other: LossPatterns) {
+    for (pattern, count) in other.patterns {
+      *self.patterns.entry(pattern).or_insert(0) += count;
+    }
+
+    for (blocker, count) in other.blockers {
+      *self.blockers.entry(blocker).or_insert(0) += count;
+    }
+
+    self.losses += other.losses;
+  }
+
+  /// The `limit` most common terminal patterns, most common first.
+  pub fn most_common_patterns(&self, limit: usize) -> Vec<(&TerminalPattern, usize)> {
+    most_common(&self.patterns, limit)
+  }
+
+  /// The `limit` most common blockers, most common first.
+  ///
+  /// A game contributes once for each blocked pile, so counts can exceed the
+  /// number of losses.
+  pub fn most_common_blockers(&self, limit: usize) -> Vec<(&Blocker, usize)> {
+    most_common(&self.blockers, limit)
+  }
+}
+
+/// How often each pile has all of its hidden cards turned face up, and how
+/// far into the game that happens.
+pub struct RevealStats {
+  games: usize,
+  revealed: [usize; 7],
+  move_sum: [usize; 7],
+}
+
+impl RevealStats {
+  pub fn new() -> RevealStats {
+    RevealStats {
+      games: 0,
+      revealed: [0; 7],
+      move_sum: [0; 7],
+    }
+  }
+
+  pub fn games(&self) -> usize {
+    self.games
+  }
+
+  /// Record the final state of a game.
+  pub fn record(&mut self, game: &KlondikeSolitaireGame) {
+    for i in 0..7 {
+      if let Some(at) = game.pile_revealed_at(i) {
+        self.revealed[i] += 1;
+        self.move_sum[i] += at;
+      }
+    }
+
+    self.games += 1;
+  }
+
+  pub fn merge(&mut self, other: &RevealStats) {
+    for i in 0..7 {
+      self.revealed[i] += other.revealed[i];
+      self.move_sum[i] += other.move_sum[i];
+    }
+
+    self.games += other.games;
+  }
+
+  /// Fraction of games, from 0 to 1, in which the pile was fully revealed.
+  pub fn reveal_rate(&self, pile: usize) -> f64 {
+    self.revealed[pile] as f64 / ::std::cmp::max(self.games, 1) as f64
+  }
+
+  /// Average move at which the pile was fully revealed, in the games it was.
+  pub fn average_reveal_move(&self, pile: usize) -> Option<f64> {
+    match self.revealed[pile] {
+      0 => None,
+      n => Some(self.move_sum[pile] as f64 / n as f64),
+    }
+  }
+}
+
+/// Total moves of each `MoveKind` and total `GameStats`, kept separately for
+/// won and lost games so the play of wins can be compared to the play of
+/// losses.
+pub struct MoveStats {
+  wins: usize,
+  losses: usize,
+  won: HashMap<MoveKind, usize>,
+  lost: HashMap<MoveKind, usize>,
+  won_stats: GameStats,
+  lost_stats: GameStats,
+}
+
+impl MoveStats {
+  pub fn new() -> MoveStats {
+    MoveStats {
+      wins: 0,
+      losses: 0,
+      won: HashMap::new(),
+      lost: HashMap::new(),
+      won_stats: GameStats::default(),
+      lost_stats: GameStats::default(),
+    }
+  }
+
+  pub fn wins(&self) -> usize {
+    self.wins
+  }
+
+  pub fn losses(&self) -> usize {
+    self.losses
+  }
+
+  /// Record the moves of a finished game.
+  pub fn record(&mut self, game: &KlondikeSolitaireGame) {
+    let (counts, stats) = if game.is_clear() {
+      self.wins += 1;
+      (&mut self.won, &mut self.won_stats)
+    } else {
+      self.losses += 1;
+      (&mut self.lost, &mut self.lost_stats)
+    };
+
+    *stats += game.stats();
+    for kind in MoveKind::all() {
+      *counts.entry(*kind).or_insert(0) += game.move_count(*kind);
+    }
+  }
+
+  pub fn merge(&mut self, other: &MoveStats) {
+    for (kind, count) in &other.won {
+      *self.won.entry(*kind).or_insert(0) += *count;
+    }
+
+    for (kind, count) in &other.lost {
+      *self.lost.entry(*kind).or_insert(0) += *count;
+    }
+
+    self.wins += other.wins;
+    self.losses += other.losses;
+    self.won_stats += other.won_stats;
+    self.lost_stats += other.lost_stats;
+  }
+
+  /// Sum of the stats of the won games.
+  pub fn won_stats(&self) -> GameStats {
+    self.won_stats
+  }
+
+  pub fn lost_stats(&self) -> GameStats {
+    self.lost_stats
+  }
+
+  /// Average number of `kind` moves per won game, or `None` without wins.
+  pub fn average_won(&self, kind: MoveKind) -> Option<f64> {
+    average(&self.won, kind, self.wins)
+  }
+
+  /// Average number of `kind` moves per lost game, or `None` without losses.
+  pub fn average_lost(&self, kind: MoveKind) -> Option<f64> {
+    average(&self.lost, kind, self.losses)
+  }
+}
+
+fn average(counts: &HashMap<MoveKind, usize>, kind: MoveKind, games: usize) -> Option<f64> {
+  match games {
+    0 => None,
+    n => Some(*counts.get(&kind).unwrap_or(&0) as f64 / n as f64),
+  }
+}
+
+fn most_common<K: Ord + ::std::hash::Hash>(counts: &HashMap<K, usize>, limit: usize) -> Vec<(&K, usize)> {
+  let mut entries: Vec<_> = counts.iter().map(|(k, c)| (k, *c)).collect();
+  entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+  entries.truncate(limit);
+  entries
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use games::solitaire::klondike::{Deck, Foundation, Pile};
+  use cards::french::{Suit, Rank, new_standard_deck};
+
+  fn unsorted_game() -> KlondikeSolitaireGame {
+    let cards = new_standard_deck();
+    KlondikeSolitaireGame::from(
+      Deck::from(3, &[], &[], &cards[28..]),
+      [
+        Foundation::new(Suit::Clubs),
+        Foundation::new(Suit::Hearts),
+        Foundation::new(Suit::Spades),
+        Foundation::new(Suit::Diamonds),
+      ], [
+        Pile::from(&cards[0..0], &cards[0..1]),
+        Pile::from(&cards[1..2], &cards[2..3]),
+        Pile::from(&cards[3..5], &cards[5..6]),
+        Pile::from(&cards[6..9], &cards[9..10]),
+        Pile::from(&cards[10..14], &cards[14..15]),
+        Pile::from(&cards[15..20], &cards[20..21]),
+        Pile::from(&cards[21..27], &cards[27..28]),
+      ]
+    )
+  }
+
+  #[test]
+  fn move_stats() {
+    let mut game = unsorted_game();
+    game.draw();
+    game.draw();
+
+    let mut stats = MoveStats::new();
+    stats.record(&game);
+    assert_eq!(stats.average_won(MoveKind::Draw), None);
+    assert_eq!(stats.average_lost(MoveKind::Draw), Some(2.0));
+
+    let mut other = MoveStats::new();
+    other.record(&unsorted_game());
+    stats.merge(&other);
+    assert_eq!(stats.losses(), 2);
+    assert_eq!(stats.wins(), 0);
+    assert_eq!(stats.average_lost(MoveKind::Draw), Some(1.0));
+    assert_eq!(stats.average_lost(MoveKind::PileToPile), Some(0.0));
+    assert_eq!(stats.lost_stats().draws, 2);
+    assert_eq!(stats.lost_stats().passes, 2);
+    assert_eq!(stats.won_stats(), GameStats::default());
+  }
+
+  #[test]
+  fn classify_blocked_piles() {
+    let pattern = classify(&unsorted_game());
+
+    // Piles 1-6 are blocked by 3D, 6D, 10D, 2H, 8H and 2C. Only 2C can be
+    // moved (onto 3D); the targets of the other cards are in the deck.
+    assert_eq!(pattern.blockers(), &[
+      Blocker{rank: Rank::Number(10), reason: BlockReason::TargetInDeck},
+      Blocker{rank: Rank::Number(8), reason: BlockReason::TargetInDeck},
+      Blocker{rank: Rank::Number(6), reason: BlockReason::TargetInDeck},
+      Blocker{rank: Rank::Number(3), reason: BlockReason::TargetInDeck},
+      Blocker{rank: Rank::Number(2), reason: BlockReason::TargetInDeck},
+      Blocker{rank: Rank::Number(2), reason: BlockReason::TargetPlayable},
+    ]);
+  }
+
+  #[test]
+  fn classify_king_without_empty_pile() {
+    let king = Card::new(Suit::Spades, Rank::King);
+    let hidden = Card::new(Suit::Hearts, Rank::Ace);
+    let mut cards = new_standard_deck();
+    cards.retain(|c| *c != king && *c != hidden && ! (c.suit() == Suit::Diamonds && c.rank() <= Rank::Number(5)));
+
+    let game = KlondikeSolitaireGame::from(
+      Deck::from(1, &[], &[], &cards[21..]),
+      [
+        Foundation::new(Suit::Clubs),
+        Foundation::new(Suit::Hearts),
+        Foundation::new(Suit::Spades),
+        Foundation::from(Suit::Diamonds, Some(Rank::Number(5))),
+      ], [
+        Pile::from(&cards[0..0], &cards[0..1]),
+        Pile::from(&cards[1..2], &cards[2..3]),
+        Pile::from(&cards[3..5], &cards[5..6]),
+        Pile::from(&cards[6..9], &cards[9..10]),
+        Pile::from(&cards[10..14], &cards[14..15]),
+        Pile::from(&cards[15..20], &cards[20..21]),
+        Pile::from(&[hidden], &[king]),
+      ]
+    );
+
+    let pattern = classify(&game);
+    assert!(pattern.blockers().contains(&Blocker{rank: Rank::King, reason: BlockReason::NoEmptyPile}));
+  }
+
+  #[test]
+  fn record_and_merge() {
+    let game = unsorted_game();
+
+    let mut a = LossPatterns::new();
+    a.record(&game);
+
+    let mut b = LossPatterns::new();
+    b.record(&game);
+    a.merge(b);
+
+    assert_eq!(a.losses(), 2);
+
+    let patterns = a.most_common_patterns(5);
+    assert_eq!(patterns.len(), 1);
+    assert_eq!(patterns[0].1, 2);
+
+    let blockers = a.most_common_blockers(1);
+    assert_eq!(blockers[0], (&Blocker{rank: Rank::Number(2), reason: BlockReason::TargetPlayable}, 2));
+  }
+
+  #[test]
+  fn reveal_stats() {
+    let game = unsorted_game();
+
+    let mut a = RevealStats::new();
+    a.record(&game);
+
+    let mut b = RevealStats::new();
+    b.record(&game);
+    a.merge(&b);
+
+    assert_eq!(a.games(), 2);
+    assert_eq!(a.reveal_rate(0), 1.0);
+    assert_eq!(a.average_reveal_move(0), Some(0.0));
+    assert_eq!(a.reveal_rate(1), 0.0);
+    assert_eq!(a.average_reveal_move(1), None);
+  }
+}
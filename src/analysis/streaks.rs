@@ -0,0 +1,199 @@
+use analysis::sum;
+use std::collections::BTreeMap;
+
+/// Longest runs of wins and losses and the spread of win rates between
+/// batches of consecutive games.
+///
+/// Each worker records its games with their global deal number; results of
+/// several workers are combined with `merge`. Streaks and batches are both
+/// by deal number, so they are the same however the games were split
+/// between workers. A deal that was not played ends a streak, and batches
+/// with games missing, such as a final partial one, are not counted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Streaks {
+  batch_size: usize,
+  /// One bit per deal number for whether it was played and whether it was
+  /// won.
+  played: Vec<u64>,
+  won: Vec<u64>,
+  /// Games and wins of each batch by batch number.
+  batches: BTreeMap<usize, (usize, usize)>,
+}
+
+impl Streaks {
+  pub fn new(batch_size: usize) -> Streaks {
+    assert!(batch_size > 0);
+
+    Streaks {
+      batch_size: batch_size,
+      played: Vec::new(),
+      won: Vec::new(),
+      batches: BTreeMap::new(),
+    }
+  }
+
+  /// Record the outcome of deal number `game`.
+  pub fn record(&mut self, game: usize, won: bool) {
+    let (word, bit) = (game / 64, 1 << (game % 64));
+    if self.played.len() <= word {
+      self.played.resize(word + 1, 0);
+      self.won.resize(word + 1, 0);
+    }
+
+    self.played[word] |= bit;
+    if won {
+      self.won[word] |= bit;
+    }
+
+    let batch = self.batches.entry(game / self.batch_size).or_insert((0, 0));
+    batch.0 += 1;
+    if won {
+      batch.1 += 1;
+    }
+  }
+
+  pub fn merge(&mut self, other: Streaks) {
+    assert_eq!(self.batch_size, other.batch_size);
+
+    if self.played.len() < other.played.len() {
+      self.played.resize(other.played.len(), 0);
+      self.won.resize(other.won.len(), 0);
+    }
+    for (word, (played, won)) in other.played.iter().zip(other.won.iter()).enumerate() {
+      self.played[word] |= played;
+      self.won[word] |= won;
+    }
+
+    for (batch, (games, wins)) in other.batches {
+      let counts = self.batches.entry(batch).or_insert((0, 0));
+      counts.0 += games;
+      counts.1 += wins;
+    }
+  }
+
+  pub fn longest_win(&self) -> usize {
+    self.longest(true)
+  }
+
+  pub fn longest_loss(&self) -> usize {
+    self.longest(false)
+  }
+
+  /// Longest run of consecutive deals all played and all won, or all lost.
+  fn longest(&self, won: bool) -> usize {
+    let (mut current, mut longest) = (0, 0);
+
+    for (played, wins) in self.played.iter().zip(self.won.iter()) {
+      let outcomes = if won { *wins } else { ! *wins };
+      for bit in 0..64 {
+        if (played & outcomes) >> bit & 1 == 1 {
+          current += 1;
+          longest = longest.max(current);
+        } else {
+          current = 0;
+        }
+      }
+    }
+
+    longest
+  }
+
+  pub fn batch_size(&self) -> usize {
+    self.batch_size
+  }
+
+  /// Win rate, from 0 to 1, of each complete batch in deal order.
+  pub fn batch_rates(&self) -> Vec<f64> {
+    self.batches.values()
+      .filter(|&&(games, _)| games == self.batch_size)
+      .map(|&(_, wins)| wins as f64 / self.batch_size as f64)
+      .collect()
+  }
+
+  /// Mean and sample variance of the batch win rates, `None` with fewer than
+  /// two batches. The sums are compensated and in deal order, so the result
+  /// is the same to the bit for the same games.
+  pub fn batch_variance(&self) -> Option<(f64, f64)> {
+    let rates = self.batch_rates();
+    let n = rates.len();
+    if n < 2 {
+      return None;
+    }
+
+    let mean = sum::sum(rates.iter().cloned()) / n as f64;
+    let variance = sum::sum(rates.iter().map(|r| (r - mean) * (r - mean))) / (n - 1) as f64;
+
+    Some((mean, variance))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn streaks() {
+    let mut s = Streaks::new(100);
+
+    for (g, won) in [true, true, false, false, false, true, true, true, false].iter().enumerate() {
+      s.record(g, *won);
+    }
+
+    assert_eq!(s.longest_win(), 3);
+    assert_eq!(s.longest_loss(), 3);
+
+    let mut other = Streaks::new(100);
+    for g in 9..14 {
+      other.record(g, false);
+    }
+
+    // The last loss of the first worker and the losses of the second are
+    // one streak, and a deal that was not played ends it
+    s.merge(other);
+    assert_eq!(s.longest_win(), 3);
+    assert_eq!(s.longest_loss(), 6);
+
+    s.record(15, false);
+    assert_eq!(s.longest_loss(), 6);
+  }
+
+  #[test]
+  fn batch_variance() {
+    let mut s = Streaks::new(2);
+    assert_eq!(s.batch_variance(), None);
+
+    // Batches: 1.0, 0.0, 0.5 and a partial batch that is not counted
+    for (g, won) in [true, true, false, false, true, false, true].iter().enumerate() {
+      s.record(g, *won);
+    }
+
+    assert_eq!(s.batch_rates(), [1.0, 0.0, 0.5]);
+    assert_eq!(s.batch_variance(), Some((0.5, 0.25)));
+  }
+
+  #[test]
+  fn batches_independent_of_workers() {
+    let outcomes: Vec<bool> = (0..1000).map(|g| (g * 7919) % 13 < 5).collect();
+
+    let mut one = Streaks::new(10);
+    for (g, won) in outcomes.iter().enumerate() {
+      one.record(g, *won);
+    }
+
+    // Three workers taking deals in turn, merged in a different order
+    let mut workers: Vec<Streaks> = (0..3).map(|_| Streaks::new(10)).collect();
+    for (g, won) in outcomes.iter().enumerate() {
+      workers[g % 3].record(g, *won);
+    }
+
+    let mut merged = workers.pop().unwrap();
+    for worker in workers {
+      merged.merge(worker);
+    }
+
+    assert_eq!(merged.batch_rates(), one.batch_rates());
+    assert_eq!(merged.batch_variance(), one.batch_variance());
+    assert_eq!(merged.longest_win(), one.longest_win());
+    assert_eq!(merged.longest_loss(), one.longest_loss());
+  }
+}
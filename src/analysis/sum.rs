@@ -0,0 +1,65 @@
+/// Compensated (Neumaier) summation of floating point values.
+///
+/// The rounding error of every addition is carried along and added back at
+/// the end, so the total of many small values is accurate to the last bit
+/// in practice and does not drift with the number of terms. Summing the
+/// same values in the same order always gives the same bits.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CompensatedSum {
+  sum: f64,
+  compensation: f64,
+}
+
+impl CompensatedSum {
+  pub fn new() -> CompensatedSum {
+    CompensatedSum {
+      sum: 0.0,
+      compensation: 0.0,
+    }
+  }
+
+  pub fn add(&mut self, value: f64) {
+    let sum = self.sum + value;
+
+    if self.sum.abs() >= value.abs() {
+      self.compensation += (self.sum - sum) + value;
+    } else {
+      self.compensation += (value - sum) + self.sum;
+    }
+
+    self.sum = sum;
+  }
+
+  pub fn value(&self) -> f64 {
+    self.sum + self.compensation
+  }
+}
+
+/// Compensated sum of `values` in order.
+pub fn sum<I: IntoIterator<Item = f64>>(values: I) -> f64 {
+  let mut total = CompensatedSum::new();
+
+  for value in values {
+    total.add(value);
+  }
+
+  total.value()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn compensated() {
+    let values = vec![0.1; 10];
+    assert_eq!(sum(values), 1.0);
+
+    let naive: f64 = vec![0.1; 10].iter().sum();
+    assert!(naive != 1.0);
+
+    // The small terms vanish when added to the large one one at a time
+    assert_eq!(sum(vec![1e16, 1.0, 1.0, -1e16]), 2.0);
+    assert_eq!(sum(Vec::new()), 0.0);
+  }
+}
@@ -0,0 +1,279 @@
+//! Compact binary archives of deals.
+//!
+//! An archive starts with the 4 byte magic `CSDA` and a version byte,
+//! followed by one 16 byte entry per deal: the seed and the layout hash, both
+//! little endian `u64`s. The deal itself is `seeded_shuffle(seed, ...)` of a
+//! standard deck; the layout hash identifies the resulting card order so that
+//! different seeds dealing the same cards are stored once.
+
+use cards::seeded_shuffle;
+use cards::french::{self, FrenchPlayingCard};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &'static [u8; 4] = b"CSDA";
+const VERSION: u8 = 1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DealEntry {
+  pub seed: u64,
+  pub layout: u64,
+}
+
+impl DealEntry {
+  pub fn new(seed: u64) -> DealEntry {
+    let mut cards = french::new_standard_deck();
+    seeded_shuffle(seed, &mut cards);
+
+    DealEntry {
+      seed: seed,
+      layout: layout_hash(&cards),
+    }
+  }
+}
+
+/// 64 bit FNV-1a hash of the cards in deal order. Unlike the standard library
+/// hashers it is stable between builds, so it can be stored.
+pub fn layout_hash(cards: &[FrenchPlayingCard]) -> u64 {
+  let mut hash: u64 = 0xcbf29ce484222325;
+
+  for card in cards {
+    for b in card.to_string().bytes().chain(Some(b' ')) {
+      hash ^= b as u64;
+      hash = hash.wrapping_mul(0x100000001b3);
+    }
+  }
+
+  hash
+}
+
+/// Deals of an archive, without duplicate layouts, in insertion order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DealArchive {
+  entries: Vec<DealEntry>,
+  layouts: HashSet<u64>,
+}
+
+impl DealArchive {
+  pub fn new() -> DealArchive {
+    DealArchive {
+      entries: Vec::new(),
+      layouts: HashSet::new(),
+    }
+  }
+
+  /// Read an archive. Duplicate layouts after the first are skipped.
+  pub fn read<R: Read>(input: &mut R) -> io::Result<DealArchive> {
+    let mut header = [0u8; 5];
+    input.read_exact(&mut header)?;
+
+    if &header[..4] != MAGIC || header[4] != VERSION {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, "not a deal archive"));
+    }
+
+    let mut archive = DealArchive::new();
+    let mut entry = [0u8; 16];
+
+    loop {
+      match read_full(input, &mut entry)? {
+        0 => break,
+        16 => (),
+        _ => { return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated deal archive entry")); },
+      }
+
+      archive.insert(DealEntry {
+        seed: from_le(&entry[..8]),
+        layout: from_le(&entry[8..]),
+      });
+    }
+
+    Ok(archive)
+  }
+
+  /// Read the archive at `path`, or an empty archive if it does not exist.
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<DealArchive> {
+    match File::open(path) {
+      Ok(file) => DealArchive::read(&mut BufReader::new(file)),
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(DealArchive::new()),
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Add an entry unless its layout is already in the archive. Returns
+  /// whether it was added.
+  pub fn insert(&mut self, entry: DealEntry) -> bool {
+    if ! self.layouts.insert(entry.layout) {
+      return false;
+    }
+
+    self.entries.push(entry);
+    true
+  }
+
+  pub fn contains(&self, entry: &DealEntry) -> bool {
+    self.layouts.contains(&entry.layout)
+  }
+
+  pub fn entries(&self) -> &[DealEntry] {
+    &self.entries[..]
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    write_header(out)?;
+
+    for entry in &self.entries {
+      write_entry(out, entry)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Appends new deals to an archive file, skipping deals already in it.
+pub struct DealArchiveWriter {
+  archive: DealArchive,
+  out: BufWriter<File>,
+}
+
+impl DealArchiveWriter {
+  /// Open `path` for appending, creating it if it does not exist.
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<DealArchiveWriter> {
+    let archive = DealArchive::open(&path)?;
+    let mut out = BufWriter::new(OpenOptions::new().create(true).append(true).open(&path)?);
+
+    if out.get_ref().metadata()?.len() == 0 {
+      write_header(&mut out)?;
+    }
+
+    Ok(DealArchiveWriter {
+      archive: archive,
+      out: out,
+    })
+  }
+
+  pub fn archive(&self) -> &DealArchive {
+    &self.archive
+  }
+
+  /// Append the deal of `seed`. Returns false if its layout was already in
+  /// the archive.
+  pub fn append(&mut self, seed: u64) -> io::Result<bool> {
+    let entry = DealEntry::new(seed);
+
+    if ! self.archive.insert(entry) {
+      return Ok(false);
+    }
+
+    write_entry(&mut self.out, &entry)?;
+    Ok(true)
+  }
+
+  pub fn flush(&mut self) -> io::Result<()> {
+    self.out.flush()
+  }
+}
+
+fn write_header<W: Write>(out: &mut W) -> io::Result<()> {
+  out.write_all(MAGIC)?;
+  out.write_all(&[VERSION])
+}
+
+fn write_entry<W: Write>(out: &mut W, entry: &DealEntry) -> io::Result<()> {
+  out.write_all(&to_le(entry.seed))?;
+  out.write_all(&to_le(entry.layout))
+}
+
+fn to_le(v: u64) -> [u8; 8] {
+  let mut bytes = [0u8; 8];
+  for (i, b) in bytes.iter_mut().enumerate() {
+    *b = (v >> (8 * i)) as u8;
+  }
+  bytes
+}
+
+fn from_le(bytes: &[u8]) -> u64 {
+  bytes.iter().enumerate().fold(0, |v, (i, b)| v | (*b as u64) << (8 * i))
+}
+
+/// Read until `buf` is full or the input ends, returning the bytes read.
+fn read_full<R: Read>(input: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+  let mut read = 0;
+
+  while read < buf.len() {
+    match input.read(&mut buf[read..])? {
+      0 => break,
+      n => read += n,
+    }
+  }
+
+  Ok(read)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::env;
+  use std::fs;
+
+  #[test]
+  fn write_read() {
+    let mut archive = DealArchive::new();
+    assert!(archive.insert(DealEntry::new(1)));
+    assert!(archive.insert(DealEntry::new(u64::max_value())));
+    assert!(! archive.insert(DealEntry::new(1)));
+
+    let mut out = Vec::new();
+    archive.write(&mut out).unwrap();
+    assert_eq!(out.len(), 5 + 2 * 16);
+    assert_eq!(DealArchive::read(&mut &out[..]).unwrap(), archive);
+
+    assert!(DealArchive::read(&mut &out[..out.len() - 1]).is_err());
+    assert!(DealArchive::read(&mut &b"CSDB\x01"[..]).is_err());
+  }
+
+  #[test]
+  fn layout_identifies_deal() {
+    let a = DealEntry::new(7);
+    assert_eq!(a, DealEntry::new(7));
+    assert!(a.layout != DealEntry::new(8).layout);
+
+    // The same cards under another seed are a duplicate
+    let mut archive = DealArchive::new();
+    archive.insert(a);
+    assert!(archive.contains(&DealEntry { seed: 99, layout: a.layout }));
+  }
+
+  #[test]
+  fn writer_appends() {
+    let path = env::temp_dir().join(format!("cardsim-archive-test-{}", ::std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    {
+      let mut writer = DealArchiveWriter::open(&path).unwrap();
+      assert!(writer.append(3).unwrap());
+      assert!(! writer.append(3).unwrap());
+      writer.flush().unwrap();
+    }
+
+    {
+      let mut writer = DealArchiveWriter::open(&path).unwrap();
+      assert!(! writer.append(3).unwrap());
+      assert!(writer.append(4).unwrap());
+      writer.flush().unwrap();
+    }
+
+    let seeds: Vec<u64> = DealArchive::open(&path).unwrap().entries().iter().map(|e| e.seed).collect();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(seeds, vec![3, 4]);
+  }
+}
@@ -0,0 +1,201 @@
+//! Built-in benchmark suite of seeded Klondike deals, to score a strategy on
+//! the same deals as everyone else in a few minutes.
+//!
+//! Every deal is `seeded_shuffle(seed, ...)` of a standard deck, dealt with
+//! the standard rules for its draw count. The verdicts come from exhaustive
+//! search with every card known: winnable deals have a known win, unwinnable
+//! deals were searched to the end without one. The difficulty of a winnable
+//! deal is how often the default mcts strategy won it in three tries when the
+//! suite was made: easy every time, medium once or twice, hard never.
+
+use cards::seeded_source;
+use games::solitaire::klondike::{KlondikeRules, KlondikeSolitaireGame};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Difficulty {
+  Easy,
+  Medium,
+  Hard,
+  Unwinnable,
+}
+
+const NUM_DIFFICULTIES: usize = 4;
+
+static DIFFICULTIES: [Difficulty; NUM_DIFFICULTIES] = [
+  Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Unwinnable,
+];
+
+impl Difficulty {
+  pub fn all() -> &'static [Difficulty] {
+    &DIFFICULTIES
+  }
+
+  fn index(&self) -> usize {
+    *self as usize
+  }
+}
+
+impl fmt::Display for Difficulty {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Difficulty::Easy => write!(f, "easy"),
+      Difficulty::Medium => write!(f, "medium"),
+      Difficulty::Hard => write!(f, "hard"),
+      Difficulty::Unwinnable => write!(f, "unwinnable"),
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BenchmarkDeal {
+  pub seed: u64,
+  pub draw_count: u8,
+  pub difficulty: Difficulty,
+}
+
+impl BenchmarkDeal {
+  pub fn is_winnable(&self) -> bool {
+    self.difficulty != Difficulty::Unwinnable
+  }
+
+  pub fn game(&self) -> KlondikeSolitaireGame {
+    KlondikeSolitaireGame::with_rules(KlondikeRules::new(self.draw_count), seeded_source(self.seed))
+  }
+}
+
+static SUITE: [BenchmarkDeal; 31] = [
+  BenchmarkDeal { seed: 12, draw_count: 1, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 16, draw_count: 1, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 23, draw_count: 1, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 26, draw_count: 1, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 34, draw_count: 1, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 36, draw_count: 1, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 12, draw_count: 3, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 26, draw_count: 3, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 34, draw_count: 3, difficulty: Difficulty::Easy },
+  BenchmarkDeal { seed: 0, draw_count: 1, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 1, draw_count: 1, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 4, draw_count: 1, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 10, draw_count: 1, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 14, draw_count: 1, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 27, draw_count: 1, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 9, draw_count: 3, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 14, draw_count: 3, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 16, draw_count: 3, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 18, draw_count: 3, difficulty: Difficulty::Medium },
+  BenchmarkDeal { seed: 22, draw_count: 1, difficulty: Difficulty::Hard },
+  BenchmarkDeal { seed: 24, draw_count: 1, difficulty: Difficulty::Hard },
+  BenchmarkDeal { seed: 3, draw_count: 3, difficulty: Difficulty::Hard },
+  BenchmarkDeal { seed: 8, draw_count: 3, difficulty: Difficulty::Hard },
+  BenchmarkDeal { seed: 19, draw_count: 3, difficulty: Difficulty::Hard },
+  BenchmarkDeal { seed: 30, draw_count: 3, difficulty: Difficulty::Hard },
+  BenchmarkDeal { seed: 11, draw_count: 1, difficulty: Difficulty::Unwinnable },
+  BenchmarkDeal { seed: 11, draw_count: 3, difficulty: Difficulty::Unwinnable },
+  BenchmarkDeal { seed: 15, draw_count: 3, difficulty: Difficulty::Unwinnable },
+  BenchmarkDeal { seed: 25, draw_count: 3, difficulty: Difficulty::Unwinnable },
+  BenchmarkDeal { seed: 29, draw_count: 3, difficulty: Difficulty::Unwinnable },
+  BenchmarkDeal { seed: 32, draw_count: 3, difficulty: Difficulty::Unwinnable },
+];
+
+/// Every deal of the suite, easiest first.
+pub fn suite() -> &'static [BenchmarkDeal] {
+  &SUITE
+}
+
+/// Games won by one strategy on the suite, by difficulty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchmarkScore {
+  won: [usize; NUM_DIFFICULTIES],
+  played: [usize; NUM_DIFFICULTIES],
+}
+
+impl BenchmarkScore {
+  pub fn new() -> BenchmarkScore {
+    BenchmarkScore {
+      won: [0; NUM_DIFFICULTIES],
+      played: [0; NUM_DIFFICULTIES],
+    }
+  }
+
+  pub fn record(&mut self, deal: &BenchmarkDeal, won: bool) {
+    self.played[deal.difficulty.index()] += 1;
+
+    if won {
+      self.won[deal.difficulty.index()] += 1;
+    }
+  }
+
+  /// Games won and played of the deals of `difficulty`.
+  pub fn wins(&self, difficulty: Difficulty) -> (usize, usize) {
+    (self.won[difficulty.index()], self.played[difficulty.index()])
+  }
+
+  /// Fraction of the winnable deals won, `None` before any was played.
+  pub fn score(&self) -> Option<f64> {
+    let winnable = Difficulty::Unwinnable.index();
+    let won: usize = self.won[..winnable].iter().sum();
+    let played: usize = self.played[..winnable].iter().sum();
+
+    if played == 0 {
+      None
+    } else {
+      Some(won as f64 / played as f64)
+    }
+  }
+
+  /// Wins of unwinnable deals, which point to a bug in the engine.
+  pub fn impossible_wins(&self) -> usize {
+    self.won[Difficulty::Unwinnable.index()]
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::collections::HashSet;
+
+  #[test]
+  fn suite_deals() {
+    let mut seen = HashSet::new();
+
+    for difficulty in Difficulty::all() {
+      assert!(suite().iter().any(|d| d.difficulty == *difficulty), "no {} deals", difficulty);
+    }
+
+    for deal in suite() {
+      assert!(seen.insert((deal.seed, deal.draw_count)), "duplicate deal {:?}", deal);
+      assert_eq!(deal.game().validate(), Ok(()));
+      assert_eq!(deal.game().deck().draw_count(), deal.draw_count);
+    }
+
+    let difficulties: Vec<Difficulty> = suite().iter().map(|d| d.difficulty).collect();
+    let mut sorted = difficulties.clone();
+    sorted.sort();
+    assert_eq!(difficulties, sorted);
+  }
+
+  #[test]
+  fn score() {
+    let easy = BenchmarkDeal { seed: 1, draw_count: 1, difficulty: Difficulty::Easy };
+    let hard = BenchmarkDeal { seed: 2, draw_count: 3, difficulty: Difficulty::Hard };
+    let unwinnable = BenchmarkDeal { seed: 3, draw_count: 1, difficulty: Difficulty::Unwinnable };
+
+    let mut score = BenchmarkScore::new();
+    assert_eq!(score.score(), None);
+
+    score.record(&easy, true);
+    score.record(&easy, true);
+    score.record(&hard, false);
+    score.record(&unwinnable, false);
+
+    assert_eq!(score.wins(Difficulty::Easy), (2, 2));
+    assert_eq!(score.wins(Difficulty::Hard), (0, 1));
+    assert_eq!(score.wins(Difficulty::Medium), (0, 0));
+    assert_eq!(score.score(), Some(2.0 / 3.0));
+    assert_eq!(score.impossible_wins(), 0);
+
+    score.record(&unwinnable, true);
+    assert_eq!(score.impossible_wins(), 1);
+  }
+}
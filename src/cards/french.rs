@@ -1,6 +1,9 @@
+use std::fmt;
+use std::str::FromStr;
 use std::slice::Iter;
 
 pub const STANDARD_DECK_SIZE: u8 = 52;
+pub const EUCHRE_DECK_SIZE: u8 = 24;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Color {
@@ -37,9 +40,45 @@ impl Suit {
       Suit::Clubs | Suit::Spades => Color::Black,
     }
   }
+
+  /// The other suit of the same color.
+  pub fn same_color(&self) -> Suit {
+    match *self {
+      Suit::Diamonds => Suit::Hearts,
+      Suit::Hearts => Suit::Diamonds,
+      Suit::Clubs => Suit::Spades,
+      Suit::Spades => Suit::Clubs,
+    }
+  }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+impl fmt::Display for Suit {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Suit::Diamonds => write!(f, "D"),
+      Suit::Hearts => write!(f, "H"),
+      Suit::Clubs => write!(f, "C"),
+      Suit::Spades => write!(f, "S"),
+    }
+  }
+}
+
+impl FromStr for Suit {
+  type Err = String;
+
+  /// Parse the letter written by `Display`, in either case.
+  fn from_str(s: &str) -> Result<Suit, String> {
+    match s {
+      "D" | "d" => Ok(Suit::Diamonds),
+      "H" | "h" => Ok(Suit::Hearts),
+      "C" | "c" => Ok(Suit::Clubs),
+      "S" | "s" => Ok(Suit::Spades),
+      _ => Err(format!("Invalid suit: {}", s)),
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Rank {
   Ace,
   Number(i8),
@@ -58,6 +97,63 @@ impl Rank {
     ];
     RANKS.into_iter()
   }
+
+  /// The ranks of a euchre deck, nine to ace.
+  pub fn euchre_iter() -> Iter<'static, Rank> {
+    static RANKS: [Rank; 6] = [Rank::Number(9), Rank::Number(10), Rank::Jack, Rank::Queen, Rank::King, Rank::Ace];
+    RANKS.into_iter()
+  }
+
+  /// Value of the rank with aces low: 1 for an ace to 13 for a king.
+  pub fn value(&self) -> u8 {
+    match *self {
+      Rank::Ace => 1,
+      Rank::Number(n) => n as u8,
+      Rank::Jack => 11,
+      Rank::Queen => 12,
+      Rank::King => 13,
+    }
+  }
+
+  /// Value of the rank with aces high: 2 for a two to 14 for an ace.
+  pub fn ace_high_value(&self) -> u8 {
+    match *self {
+      Rank::Ace => 14,
+      _ => self.value(),
+    }
+  }
+}
+
+impl fmt::Display for Rank {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Rank::Ace => write!(f, "A"),
+      Rank::Number(n) => write!(f, "{}", n),
+      Rank::Jack => write!(f, "J"),
+      Rank::Queen => write!(f, "Q"),
+      Rank::King => write!(f, "K"),
+    }
+  }
+}
+
+impl FromStr for Rank {
+  type Err = String;
+
+  /// Parse the rank written by `Display`, in either case. `T` is also
+  /// accepted for 10.
+  fn from_str(s: &str) -> Result<Rank, String> {
+    match s {
+      "A" | "a" => Ok(Rank::Ace),
+      "J" | "j" => Ok(Rank::Jack),
+      "Q" | "q" => Ok(Rank::Queen),
+      "K" | "k" => Ok(Rank::King),
+      "T" | "t" => Ok(Rank::Number(10)),
+      _ => match s.parse::<i8>() {
+        Ok(n) if n >= 2 && n <= 10 => Ok(Rank::Number(n)),
+        _ => Err(format!("Invalid rank: {}", s)),
+      },
+    }
+  }
 }
 
 /// Common French playing card.
@@ -92,6 +188,29 @@ impl FrenchPlayingCard {
   }
 }
 
+impl fmt::Display for FrenchPlayingCard {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}{}", self.rank, self.suit)
+  }
+}
+
+impl FromStr for FrenchPlayingCard {
+  type Err = String;
+
+  /// Parse a rank followed by a suit, e.g. `KS` or `10h`.
+  fn from_str(s: &str) -> Result<FrenchPlayingCard, String> {
+    if s.len() < 2 || ! s.is_char_boundary(s.len() - 1) {
+      return Err(format!("Invalid card: {}", s));
+    }
+
+    let (rank, suit) = s.split_at(s.len() - 1);
+    match (rank.parse::<Rank>(), suit.parse::<Suit>()) {
+      (Ok(rank), Ok(suit)) => Ok(FrenchPlayingCard::new(suit, rank)),
+      _ => Err(format!("Invalid card: {}", s)),
+    }
+  }
+}
+
 /// Constructs a new deck of standard French playing cards.
 ///
 /// The resulting deck has 52 red and black cards with common ranks
@@ -110,6 +229,38 @@ pub fn new_standard_deck() -> Vec<FrenchPlayingCard> {
   return deck;
 }
 
+/// The cards of Microsoft FreeCell deal number `deal`, in dealing order:
+/// the first eight are the top row of the cascades, left to right, and so
+/// on down. Games that deal from a standard deck can be given the same
+/// order to play deals by number.
+pub fn numbered_deal(deal: u32) -> Vec<FrenchPlayingCard> {
+  // Numbered by rank then suit, clubs, diamonds, hearts and spades
+  let mut deck = Vec::with_capacity(STANDARD_DECK_SIZE as usize);
+  for rank in Rank::standard_iter() {
+    for suit in &[Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades] {
+      deck.push(FrenchPlayingCard{suit: *suit, rank: *rank});
+    }
+  }
+
+  super::numbered_shuffle(deal, &mut deck);
+  deck
+}
+
+/// Constructs a new euchre deck: the 24 cards of the standard suits from nine
+/// to ace.
+pub fn new_euchre_deck() -> Vec<FrenchPlayingCard> {
+  let mut deck = Vec::with_capacity(EUCHRE_DECK_SIZE as usize);
+
+  for suit in Suit::standard_iter() {
+    for rank in Rank::euchre_iter() {
+      deck.push(FrenchPlayingCard{suit: *suit, rank: *rank});
+    }
+  }
+
+  debug_assert!(deck.len() == EUCHRE_DECK_SIZE as usize);
+  deck
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -126,6 +277,88 @@ mod test {
     assert!(card != FrenchPlayingCard::new(Suit::Spades, Rank::Number(2)));
   }
 
+  #[test]
+  fn test_numbered_deal() {
+    let cards: Vec<String> = numbered_deal(1).iter().map(|c| c.to_string()).collect();
+    assert_eq!(cards[..8].join(" "), "JD 2D 9H JC 5D 7H 7C 5H");
+    assert_eq!(cards[48..].join(" "), "6S 9C 2H 6H");
+
+    let cards: Vec<String> = numbered_deal(617).iter().map(|c| c.to_string()).collect();
+    assert_eq!(cards[..8].join(" "), "7D AD 5C 3S 5S 8C 2D AH");
+  }
+
+  #[test]
+  fn test_rank_order() {
+    let ranks: Vec<Rank> = Rank::standard_iter().cloned().collect();
+    for pair in ranks.windows(2) {
+      assert!(pair[0] < pair[1], "{:?} >= {:?}", pair[0], pair[1]);
+    }
+  }
+
+  #[test]
+  fn test_rank_value() {
+    let values: Vec<u8> = Rank::standard_iter().map(|r| r.value()).collect();
+    assert_eq!(values, (1..14).collect::<Vec<u8>>());
+
+    assert_eq!(Rank::Ace.ace_high_value(), 14);
+    assert_eq!(Rank::Number(2).ace_high_value(), 2);
+    assert_eq!(Rank::King.ace_high_value(), 13);
+  }
+
+  #[test]
+  fn test_rank_display() {
+    assert_eq!(format!("{}", Rank::Ace), "A");
+    assert_eq!(format!("{}", Rank::Number(10)), "10");
+    assert_eq!(format!("{}", Rank::King), "K");
+  }
+
+  #[test]
+  fn test_suit_display() {
+    assert_eq!(format!("{}", Suit::Diamonds), "D");
+    assert_eq!(format!("{}", Suit::Hearts), "H");
+    assert_eq!(format!("{}", Suit::Clubs), "C");
+    assert_eq!(format!("{}", Suit::Spades), "S");
+  }
+
+  #[test]
+  fn test_suit_from_str() {
+    for suit in Suit::standard_iter() {
+      assert_eq!(suit.to_string().parse::<Suit>(), Ok(*suit));
+    }
+
+    assert_eq!("h".parse::<Suit>(), Ok(Suit::Hearts));
+    assert!("X".parse::<Suit>().is_err());
+  }
+
+  #[test]
+  fn test_suit_same_color() {
+    for suit in Suit::standard_iter() {
+      assert_eq!(suit.same_color().color(), suit.color());
+      assert!(suit.same_color() != *suit);
+      assert_eq!(suit.same_color().same_color(), *suit);
+    }
+  }
+
+  #[test]
+  fn test_card_display() {
+    assert_eq!(format!("{}", FrenchPlayingCard::new(Suit::Spades, Rank::King)), "KS");
+    assert_eq!(format!("{}", FrenchPlayingCard::new(Suit::Hearts, Rank::Number(10))), "10H");
+  }
+
+  #[test]
+  fn test_card_from_str() {
+    for card in new_standard_deck() {
+      assert_eq!(card.to_string().parse::<FrenchPlayingCard>(), Ok(card));
+    }
+
+    assert_eq!("th".parse::<FrenchPlayingCard>(), Ok(FrenchPlayingCard::new(Suit::Hearts, Rank::Number(10))));
+    assert_eq!("qs".parse::<FrenchPlayingCard>(), Ok(FrenchPlayingCard::new(Suit::Spades, Rank::Queen)));
+
+    for s in &["", "K", "1S", "11S", "KX", "XS", "Ké"] {
+      assert!(s.parse::<FrenchPlayingCard>().is_err(), "{}", s);
+    }
+  }
+
   #[test]
   fn test_new_standard_deck() {
     let deck = new_standard_deck();
@@ -153,4 +386,16 @@ mod test {
     assert!(deck.iter().filter(|c| c.rank() == Rank::Queen).count() == 4);
     assert!(deck.iter().filter(|c| c.rank() == Rank::King).count() == 4);
   }
+
+  #[test]
+  fn test_new_euchre_deck() {
+    let deck = new_euchre_deck();
+    assert_eq!(deck.len(), 24);
+
+    for suit in Suit::standard_iter() {
+      assert_eq!(deck.iter().filter(|c| c.suit() == *suit).count(), 6);
+    }
+    assert!(deck.iter().all(|c| c.rank().ace_high_value() >= 9));
+    assert!(deck.iter().all(|c| new_standard_deck().contains(c)));
+  }
 }
@@ -0,0 +1,160 @@
+//! Helpers for organizing a hand of French playing cards: sorting, grouping
+//! and finding the sets and sequences that rummy, cribbage and poker score.
+//!
+//! Sets are cards of one rank. Sequences are cards of one suit with
+//! consecutive ranks; aces are low unless `ace_high` is given, in which case
+//! an ace may also follow a king.
+
+use cards::french::{FrenchPlayingCard, Rank, Suit};
+use std::collections::BTreeMap;
+
+pub type Card = FrenchPlayingCard;
+
+/// Position of `suit` in `Suit::standard_iter`.
+fn suit_index(suit: Suit) -> usize {
+  Suit::standard_iter().position(|s| *s == suit).unwrap()
+}
+
+/// Sort by suit in `Suit::standard_iter` order, then by ascending rank.
+pub fn sort_by_suit(cards: &mut [Card]) {
+  cards.sort_by_key(|c| (suit_index(c.suit()), c.rank()));
+}
+
+/// Sort by ascending rank, then by suit.
+pub fn sort_by_rank(cards: &mut [Card]) {
+  cards.sort_by_key(|c| (c.rank(), suit_index(c.suit())));
+}
+
+/// The cards of each rank in the hand, lowest rank first. The cards of a
+/// group keep their order in the hand.
+pub fn group_by_rank(cards: &[Card]) -> Vec<(Rank, Vec<Card>)> {
+  let mut groups: BTreeMap<Rank, Vec<Card>> = BTreeMap::new();
+
+  for card in cards {
+    groups.entry(card.rank()).or_insert_with(Vec::new).push(*card);
+  }
+
+  groups.into_iter().collect()
+}
+
+/// The cards of each suit in the hand, in `Suit::standard_iter` order, each
+/// sorted by rank. Suits without cards are left out.
+pub fn group_by_suit(cards: &[Card]) -> Vec<(Suit, Vec<Card>)> {
+  Suit::standard_iter()
+    .map(|suit| {
+      let mut group: Vec<Card> = cards.iter().cloned().filter(|c| c.suit() == *suit).collect();
+      sort_by_rank(&mut group);
+      (*suit, group)
+    })
+    .filter(|&(_, ref group)| ! group.is_empty())
+    .collect()
+}
+
+/// Groups of at least `min_len` cards of the same rank, lowest rank first.
+pub fn sets(cards: &[Card], min_len: usize) -> Vec<Vec<Card>> {
+  group_by_rank(cards).into_iter()
+    .map(|(_, group)| group)
+    .filter(|group| group.len() >= min_len)
+    .collect()
+}
+
+/// Longest runs of at least `min_len` cards of one suit with consecutive
+/// ranks, lowest first within each suit. Duplicate cards are counted once.
+/// With `ace_high` an ace is both below the two and above the king, so it can
+/// be part of two runs, but runs do not turn the corner from king to two.
+pub fn sequences(cards: &[Card], min_len: usize, ace_high: bool) -> Vec<Vec<Card>> {
+  let mut sequences = Vec::new();
+
+  for (_, mut group) in group_by_suit(cards) {
+    group.dedup();
+
+    let mut values: Vec<(u8, Card)> = group.iter().map(|c| (c.rank().value(), *c)).collect();
+    if ace_high {
+      values.extend(group.iter().filter(|c| c.rank() == Rank::Ace).map(|c| (14, *c)));
+    }
+
+    let mut run: Vec<Card> = Vec::new();
+    let mut last = 0;
+
+    for (value, card) in values {
+      if value != last + 1 {
+        if run.len() >= min_len {
+          sequences.push(run);
+        }
+        run = Vec::new();
+      }
+
+      run.push(card);
+      last = value;
+    }
+
+    if run.len() >= min_len {
+      sequences.push(run);
+    }
+  }
+
+  sequences
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn hand(s: &str) -> Vec<Card> {
+    s.split_whitespace().map(|c| c.parse().unwrap()).collect()
+  }
+
+  fn text(cards: &[Card]) -> String {
+    cards.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(" ")
+  }
+
+  #[test]
+  fn sorting() {
+    let mut cards = hand("KS 2H AS 10D 2D");
+
+    sort_by_suit(&mut cards);
+    assert_eq!(text(&cards), "2D 10D 2H AS KS");
+
+    sort_by_rank(&mut cards);
+    assert_eq!(text(&cards), "AS 2D 2H 10D KS");
+  }
+
+  #[test]
+  fn groups() {
+    let cards = hand("KS 2H AS 2D KD 2C");
+
+    let by_rank: Vec<String> = group_by_rank(&cards).iter().map(|&(r, ref g)| format!("{}:{}", r, text(g))).collect();
+    assert_eq!(by_rank, vec!["A:AS", "2:2H 2D 2C", "K:KS KD"]);
+
+    let by_suit: Vec<String> = group_by_suit(&cards).iter().map(|&(s, ref g)| format!("{}:{}", s, text(g))).collect();
+    assert_eq!(by_suit, vec!["D:2D KD", "H:2H", "C:2C", "S:AS KS"]);
+
+    let sets: Vec<String> = sets(&cards, 2).iter().map(|g| text(g)).collect();
+    assert_eq!(sets, vec!["2H 2D 2C", "KS KD"]);
+  }
+
+  #[test]
+  fn find_sequences() {
+    let cards = hand("3H AH 2H 5H 6H 7H 9S 10S JS 4D");
+
+    let runs: Vec<String> = sequences(&cards, 3, false).iter().map(|r| text(r)).collect();
+    assert_eq!(runs, vec!["AH 2H 3H", "5H 6H 7H", "9S 10S JS"]);
+
+    assert_eq!(sequences(&cards, 4, false).len(), 0);
+    assert_eq!(sequences(&hand("5H 5H 6H 7H"), 3, false).len(), 1);
+  }
+
+  #[test]
+  fn ace_high_sequences() {
+    let cards = hand("QC KC AC 2C 5D");
+
+    let low: Vec<String> = sequences(&cards, 2, false).iter().map(|r| text(r)).collect();
+    assert_eq!(low, vec!["AC 2C", "QC KC"]);
+
+    let high: Vec<String> = sequences(&cards, 2, true).iter().map(|r| text(r)).collect();
+    assert_eq!(high, vec!["AC 2C", "QC KC AC"]);
+
+    let both: Vec<String> = sequences(&hand("AC 2C 3C QC KC"), 3, true).iter().map(|r| text(r)).collect();
+    assert_eq!(both, vec!["AC 2C 3C", "QC KC AC"]);
+  }
+}
@@ -1,8 +1,372 @@
 pub mod french;
+pub mod hand;
+pub mod poker;
 
 use rand;
-use rand::Rng;
+use rand::{ChaChaRng, Isaac64Rng, IsaacRng, Rng, SeedableRng, StdRng, ThreadRng, XorShiftRng};
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
 
-pub fn default_shuffle<T>(mut cards: &mut Vec<T>) {
-    rand::thread_rng().shuffle(&mut cards);
+/// Where the randomness of a shuffle comes from.
+///
+/// The generators of the rand crate are sources, so cards can be shuffled
+/// with ChaCha or xorshift as well as the ISAAC generator of
+/// `seeded_shuffle`, and `Recording` and `RecordedShuffles` keep the choices
+/// of a source and play them back. `RngSource` makes a source of any other
+/// generator.
+pub trait ShuffleSource {
+    /// Number below `bound`, each equally likely. `bound` is at least 1.
+    fn below(&mut self, bound: usize) -> usize;
+
+    /// Shuffle `cards` with the Fisher-Yates shuffle of `Rng::shuffle`, so a
+    /// generator orders cards the same either way.
+    fn shuffle<T>(&mut self, cards: &mut [T]) where Self: Sized {
+        let mut i = cards.len();
+        while i >= 2 {
+            i -= 1;
+            let j = self.below(i + 1);
+            cards.swap(i, j);
+        }
+    }
+}
+
+impl<'a, S: ShuffleSource + ?Sized> ShuffleSource for &'a mut S {
+    fn below(&mut self, bound: usize) -> usize {
+        (**self).below(bound)
+    }
+}
+
+impl<S: ShuffleSource + ?Sized> ShuffleSource for Box<S> {
+    fn below(&mut self, bound: usize) -> usize {
+        (**self).below(bound)
+    }
+}
+
+macro_rules! rng_shuffle_source {
+    ($($rng:ty),*) => {
+        $(impl ShuffleSource for $rng {
+            fn below(&mut self, bound: usize) -> usize {
+                self.gen_range(0, bound)
+            }
+        })*
+    }
+}
+
+rng_shuffle_source!(ChaChaRng, Isaac64Rng, IsaacRng, StdRng, ThreadRng, XorShiftRng);
+
+/// Source of any generator, for code generic over `Rng`, such as
+/// `RngSource(&mut rng)`.
+pub struct RngSource<R>(pub R);
+
+impl<R: Rng> ShuffleSource for RngSource<R> {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0.gen_range(0, bound)
+    }
+}
+
+/// Source that passes on the choices of another and keeps them, to be
+/// played back by `RecordedShuffles`.
+pub struct Recording<S> {
+    source: S,
+    choices: Vec<usize>,
+}
+
+impl<S: ShuffleSource> Recording<S> {
+    pub fn new(source: S) -> Recording<S> {
+        Recording {
+            source: source,
+            choices: Vec::new(),
+        }
+    }
+
+    /// Every choice made so far, in order.
+    pub fn choices(&self) -> &[usize] {
+        &self.choices
+    }
+
+    pub fn into_recorded(self) -> RecordedShuffles {
+        RecordedShuffles::new(self.choices)
+    }
+}
+
+impl<S: ShuffleSource> ShuffleSource for Recording<S> {
+    fn below(&mut self, bound: usize) -> usize {
+        let choice = self.source.below(bound);
+        self.choices.push(choice);
+        choice
+    }
+}
+
+/// Source that makes the recorded choices of another again, so the same
+/// shuffles can be played whatever generator recorded them.
+///
+/// Panics when the choices run out, or when a choice does not fit the
+/// shuffle it is asked for, as the shuffles are then not the ones that were
+/// recorded.
+pub struct RecordedShuffles {
+    choices: Vec<usize>,
+    next: usize,
+}
+
+impl RecordedShuffles {
+    pub fn new(choices: Vec<usize>) -> RecordedShuffles {
+        RecordedShuffles {
+            choices: choices,
+            next: 0,
+        }
+    }
+
+    /// Number of choices not played back yet.
+    pub fn remaining(&self) -> usize {
+        self.choices.len() - self.next
+    }
+}
+
+impl ShuffleSource for RecordedShuffles {
+    fn below(&mut self, bound: usize) -> usize {
+        let choice = *self.choices.get(self.next).expect("recorded shuffles ran out");
+        assert!(choice < bound, "recorded choice {} does not fit a shuffle of {}", choice, bound);
+        self.next += 1;
+        choice
+    }
+}
+
+/// Generator family to shuffle seeded deals with: each seed gives a
+/// different source of the family, and the same seed the same source.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShuffleRng {
+    /// ISAAC-64, the generator of `seeded_shuffle`.
+    Isaac64,
+    ChaCha,
+    XorShift,
+}
+
+pub static SHUFFLE_RNGS: &'static [&'static str] = &["isaac64", "chacha", "xorshift"];
+
+impl ShuffleRng {
+    /// Name of the family, one of `SHUFFLE_RNGS`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ShuffleRng::Isaac64 => "isaac64",
+            ShuffleRng::ChaCha => "chacha",
+            ShuffleRng::XorShift => "xorshift",
+        }
+    }
+
+    pub fn source(&self, seed: u64) -> Box<dyn ShuffleSource + Send> {
+        match *self {
+            ShuffleRng::Isaac64 => Box::new(seeded_source(seed)),
+            ShuffleRng::ChaCha => Box::new(ChaChaRng::from_seed(&[seed as u32, (seed >> 32) as u32])),
+            // A xorshift seed must not be all zeros, which the ISAAC one takes care of
+            ShuffleRng::XorShift => Box::new(Isaac64Rng::from_seed(&[seed]).gen::<XorShiftRng>()),
+        }
+    }
+}
+
+impl FromStr for ShuffleRng {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ShuffleRng, String> {
+        match s {
+            "isaac64" => Ok(ShuffleRng::Isaac64),
+            "chacha" => Ok(ShuffleRng::ChaCha),
+            "xorshift" => Ok(ShuffleRng::XorShift),
+            _ => Err(format!("unknown generator {}, expected one of {}", s, SHUFFLE_RNGS.join(", "))),
+        }
+    }
+}
+
+pub fn default_shuffle<T>(cards: &mut Vec<T>) {
+    ShuffleSource::shuffle(&mut rand::thread_rng(), cards);
+}
+
+/// Shuffle deterministically: the same seed always results in the same order.
+pub fn seeded_shuffle<T>(seed: u64, cards: &mut Vec<T>) {
+    ShuffleSource::shuffle(&mut seeded_source(seed), cards);
+}
+
+/// Source of `seeded_shuffle`, the same as `ShuffleRng::Isaac64.source(seed)`.
+pub fn seeded_source(seed: u64) -> Isaac64Rng {
+    Isaac64Rng::from_seed(&[seed])
+}
+
+/// Shuffle by deal number, the scheme of Microsoft FreeCell: a linear
+/// congruential generator seeded with `deal` picks each card in turn from
+/// the cards left, and the last card fills the hole it leaves. Deals are
+/// numbered the same on every platform and by every version, so a number is
+/// enough to share a deal. `french::numbered_deal` gives the FreeCell deals
+/// themselves.
+pub fn numbered_shuffle<T>(deal: u32, cards: &mut Vec<T>) {
+    let mut state = deal;
+    let mut dealt = Vec::with_capacity(cards.len());
+
+    while ! cards.is_empty() {
+        state = state.wrapping_mul(214013).wrapping_add(2531011) & 0x7fffffff;
+        let i = (state >> 16) as usize % cards.len();
+        dealt.push(cards.swap_remove(i));
+    }
+
+    *cards = dealt;
+}
+
+/// Card of a game played with several decks, tagged with the copy it is so
+/// that equal cards of different decks compare unequal. Sets of tagged cards
+/// catch duplicates the same way as sets of cards of a single deck do, and a
+/// UI can tell the copies apart.
+///
+/// Derefs to the card, so `card.suit()` works as for an untagged card.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DeckCard<C> {
+    card: C,
+    deck: u8,
+}
+
+impl<C: Copy> DeckCard<C> {
+    pub fn new(card: C, deck: u8) -> DeckCard<C> {
+        DeckCard {
+            card: card,
+            deck: deck,
+        }
+    }
+
+    pub fn card(&self) -> C {
+        self.card
+    }
+
+    /// Index of the deck the card came from, 0 for the first.
+    pub fn deck(&self) -> u8 {
+        self.deck
+    }
+}
+
+impl<C> Deref for DeckCard<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.card
+    }
+}
+
+impl<C: fmt::Display> fmt::Display for DeckCard<C> {
+    /// The card followed by `#` and the deck index, e.g. `KS#1`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}#{}", self.card, self.deck)
+    }
+}
+
+impl<C: FromStr<Err = String> + Copy> FromStr for DeckCard<C> {
+    type Err = String;
+
+    /// Parse the format written by `Display`. A card without a deck index is
+    /// from the first deck.
+    fn from_str(s: &str) -> Result<DeckCard<C>, String> {
+        match s.find('#') {
+            Some(i) => {
+                let deck = s[i + 1..].parse::<u8>().map_err(|_| format!("Invalid deck index: {}", s))?;
+                Ok(DeckCard::new(s[..i].parse::<C>()?, deck))
+            },
+            None => Ok(DeckCard::new(s.parse::<C>()?, 0)),
+        }
+    }
+}
+
+/// `count` copies of `deck`, each card tagged with the index of its copy.
+pub fn multi_deck<C: Copy>(deck: &[C], count: u8) -> Vec<DeckCard<C>> {
+    (0..count)
+        .flat_map(|i| deck.iter().map(move |c| DeckCard::new(*c, i)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cards::french::{self, FrenchPlayingCard};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_multi_deck() {
+        let cards = multi_deck(&french::new_standard_deck(), 2);
+        assert_eq!(cards.len(), 104);
+
+        let set: HashSet<DeckCard<FrenchPlayingCard>> = cards.iter().cloned().collect();
+        assert_eq!(set.len(), 104);
+        assert_eq!(cards[0].card(), cards[52].card());
+        assert!(cards[0] != cards[52]);
+        assert_eq!(cards[52].deck(), 1);
+        assert_eq!(cards[52].suit(), cards[0].suit());
+    }
+
+    #[test]
+    fn test_deck_card_parse() {
+        let card: DeckCard<FrenchPlayingCard> = "10H#1".parse().unwrap();
+        assert_eq!(card.to_string(), "10H#1");
+        assert_eq!("KS".parse::<DeckCard<FrenchPlayingCard>>().unwrap().deck(), 0);
+        assert!("KS#x".parse::<DeckCard<FrenchPlayingCard>>().is_err());
+    }
+
+    #[test]
+    fn test_seeded_shuffle() {
+        let mut a: Vec<u32> = (0..52).collect();
+        let mut b = a.clone();
+        let mut c = a.clone();
+
+        seeded_shuffle(42, &mut a);
+        seeded_shuffle(42, &mut b);
+        seeded_shuffle(43, &mut c);
+
+        assert_eq!(a, b);
+        assert!(a != c);
+    }
+
+    #[test]
+    fn test_shuffle_source() {
+        // A generator shuffles the same as a source as it does as an Rng
+        let mut a: Vec<u32> = (0..52).collect();
+        let mut b = a.clone();
+        Rng::shuffle(&mut Isaac64Rng::from_seed(&[9]), &mut a);
+        ShuffleSource::shuffle(&mut Isaac64Rng::from_seed(&[9]), &mut b);
+        assert_eq!(a, b);
+        let mut g: Vec<u32> = (0..52).collect();
+        RngSource(Isaac64Rng::from_seed(&[9])).shuffle(&mut g);
+        assert_eq!(a, g);
+
+        let mut c = a.clone();
+        seeded_shuffle(9, &mut c);
+        let mut d = a.clone();
+        ShuffleRng::ChaCha.source(9).shuffle(&mut d);
+        assert!(c != d);
+
+        // Played back, the recorded choices shuffle the same
+        let mut recording = Recording::new(ShuffleRng::XorShift.source(9));
+        let (mut e, mut f) = (a.clone(), a.clone());
+        recording.shuffle(&mut e);
+        assert_eq!(recording.choices().len(), 51);
+
+        let mut recorded = recording.into_recorded();
+        recorded.shuffle(&mut f);
+        assert_eq!(e, f);
+        assert_eq!(recorded.remaining(), 0);
+        assert_eq!("chacha".parse::<ShuffleRng>(), Ok(ShuffleRng::ChaCha));
+        for name in SHUFFLE_RNGS {
+            assert_eq!(name.parse::<ShuffleRng>().unwrap().name(), *name);
+        }
+        assert!("mt".parse::<ShuffleRng>().is_err());
+    }
+
+    #[test]
+    fn test_numbered_shuffle() {
+        let mut a: Vec<u32> = (0..52).collect();
+        let mut b = a.clone();
+
+        numbered_shuffle(1, &mut a);
+        numbered_shuffle(2, &mut b);
+
+        assert_eq!(&a[..8], &[41, 5, 34, 40, 17, 26, 24, 18]);
+        assert!(a != b);
+
+        let mut sorted = a.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..52).collect::<Vec<u32>>());
+    }
 }
@@ -0,0 +1,199 @@
+//! Evaluation of five card poker hands.
+//!
+//! `evaluate` ranks a hand by its category, a straight flush down to a high
+//! card, and then by the values of its cards ordered for comparison, so that
+//! hands compare the way poker ranks them. Aces are high, except in the five
+//! high straight, the wheel, A-2-3-4-5.
+
+use cards::french::FrenchPlayingCard;
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+pub const POKER_HAND_LEN: usize = 5;
+
+/// Categories of poker hands, lowest first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+  HighCard,
+  Pair,
+  TwoPair,
+  ThreeOfAKind,
+  Straight,
+  Flush,
+  FullHouse,
+  FourOfAKind,
+  StraightFlush,
+}
+
+impl fmt::Display for Category {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Category::HighCard => "high card",
+      Category::Pair => "pair",
+      Category::TwoPair => "two pair",
+      Category::ThreeOfAKind => "three of a kind",
+      Category::Straight => "straight",
+      Category::Flush => "flush",
+      Category::FullHouse => "full house",
+      Category::FourOfAKind => "four of a kind",
+      Category::StraightFlush => "straight flush",
+    };
+
+    write!(f, "{}", name)
+  }
+}
+
+/// Rank of a five card hand. Hands compare by category, then by `values`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PokerHand {
+  category: Category,
+  values: [u8; POKER_HAND_LEN],
+}
+
+impl PokerHand {
+  pub fn category(&self) -> Category {
+    self.category
+  }
+
+  /// Aces high values of the ranks that decide between hands of the
+  /// category, most significant first: the ranks of the largest groups
+  /// first, then the kickers from the highest down, or only the highest card
+  /// of a straight. Unused values are 0.
+  pub fn values(&self) -> &[u8; POKER_HAND_LEN] {
+    &self.values
+  }
+
+  /// Whether the hand is a royal flush, the ace high straight flush.
+  pub fn is_royal(&self) -> bool {
+    self.category == Category::StraightFlush && self.values[0] == 14
+  }
+}
+
+/// Rank the five cards of `cards`.
+pub fn evaluate(cards: &[Card]) -> PokerHand {
+  assert_eq!(cards.len(), POKER_HAND_LEN, "poker hands have five cards");
+
+  let mut counts = [0u8; 15];
+  for card in cards {
+    counts[card.rank().ace_high_value() as usize] += 1;
+  }
+
+  let suit = cards[0].suit();
+  let flush = cards.iter().all(|c| c.suit() == suit);
+
+  // The distinct values by group size, then value, the largest first.
+  let mut values = [0u8; POKER_HAND_LEN];
+  let mut groups = [0u8; POKER_HAND_LEN];
+  let mut len = 0;
+  for size in (1..5).rev() {
+    for value in (2..15).rev() {
+      if counts[value] == size {
+        values[len] = value as u8;
+        groups[len] = size;
+        len += 1;
+      }
+    }
+  }
+
+  let straight_high = if len < POKER_HAND_LEN {
+    None
+  } else if values[0] - values[4] == 4 {
+    Some(values[0])
+  } else if values == [14, 5, 4, 3, 2] {
+    Some(5)
+  } else {
+    None
+  };
+
+  let category = match (straight_high, flush, groups[0], groups[1]) {
+    (Some(_), true, _, _) => Category::StraightFlush,
+    (_, _, 4, _) => Category::FourOfAKind,
+    (_, _, 3, 2) => Category::FullHouse,
+    (_, true, _, _) => Category::Flush,
+    (Some(_), _, _, _) => Category::Straight,
+    (_, _, 3, _) => Category::ThreeOfAKind,
+    (_, _, 2, 2) => Category::TwoPair,
+    (_, _, 2, _) => Category::Pair,
+    _ => Category::HighCard,
+  };
+
+  if let Some(high) = straight_high {
+    values = [high, 0, 0, 0, 0];
+  }
+
+  PokerHand {
+    category: category,
+    values: values,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn hand(s: &str) -> PokerHand {
+    let cards: Vec<Card> = s.split(' ').map(|c| c.parse().unwrap()).collect();
+    evaluate(&cards)
+  }
+
+  #[test]
+  fn categories() {
+    let hands = [
+      ("2S 5H 9D JC KS", Category::HighCard),
+      ("2S 2H 9D JC KS", Category::Pair),
+      ("2S 2H 9D 9C KS", Category::TwoPair),
+      ("9S 2H 9D 9C KS", Category::ThreeOfAKind),
+      ("10S JH QD KC AS", Category::Straight),
+      ("AS 2H 3D 4C 5S", Category::Straight),
+      ("2S 5S 9S JS KS", Category::Flush),
+      ("9S KH 9D 9C KS", Category::FullHouse),
+      ("9S 9H 9D 9C KS", Category::FourOfAKind),
+      ("9H 10H JH QH KH", Category::StraightFlush),
+      ("AD 2D 3D 4D 5D", Category::StraightFlush),
+    ];
+
+    for &(s, category) in &hands {
+      assert_eq!(hand(s).category(), category, "{}", s);
+    }
+
+    assert_eq!(hand("QS KS AS 2S 3S").category(), Category::Flush, "no wrapping straight");
+    assert!(hand("10C JC QC KC AC").is_royal());
+    assert!(! hand("9C 10C JC QC KC").is_royal());
+  }
+
+  #[test]
+  fn values() {
+    assert_eq!(hand("9S KH 9D 2C KS").values(), &[13, 9, 2, 0, 0]);
+    assert_eq!(hand("AS 2H 3D 4C 5S").values(), &[5, 0, 0, 0, 0]);
+    assert_eq!(hand("2S 5H 9D JC AS").values(), &[14, 11, 9, 5, 2]);
+  }
+
+  #[test]
+  fn order() {
+    let ranked = [
+      "2S 3H 4D 5C 7S",
+      "AS 3H 4D 5C 7S",
+      "2S 2H 4D 5C 7S",
+      "2S 2H AD KC QS",
+      "3S 3H 4D 5C 7S",
+      "3S 3H 4D 4C 2S",
+      "3S 3H 5D 5C 2S",
+      "2S 2H 2D 5C 7S",
+      "AS 2H 3D 4C 5S",
+      "2S 3H 4D 5C 6S",
+      "10S JH QD KC AS",
+      "2S 3S 4S 5S 7S",
+      "2S 2H 2D 3C 3S",
+      "3S 3H 3D 2C 2S",
+      "2S 2H 2D 2C 3S",
+      "AD 2D 3D 4D 5D",
+      "10C JC QC KC AC",
+    ];
+
+    for pair in ranked.windows(2) {
+      assert!(hand(pair[0]) < hand(pair[1]), "{} >= {}", pair[0], pair[1]);
+    }
+    assert_eq!(hand("2S 3H 4D 5C 7S"), hand("2H 3D 4C 5S 7H"));
+  }
+}
@@ -0,0 +1,526 @@
+//! Commands of the card games between players: crazy eights, cribbage,
+//! euchre, hearts, spades and war.
+
+use clap::{Arg, App, ArgMatches, SubCommand};
+use games::crazy_eights::{self, CrazyEightsGame, CrazyEightsOutcome, CrazyEightsRules};
+use games::cribbage;
+use games::euchre::{self, EuchreRules};
+use games::hearts::{self, HeartsGame};
+use games::spades::{self, SpadesRules};
+use games::war::{Pickup, WarGame, WarOutcome, WarRules, PICKUPS};
+use analysis::histogram::Histogram;
+use cards::seeded_shuffle;
+use strategies::crazy_eights::{self as crazy_eights_strategies, crazy_eights_strategy, CrazyEightsStrategy, CRAZY_EIGHTS_STRATEGIES};
+use strategies::cribbage::{cribbage_strategy, play_game, CribbageStrategy, CRIBBAGE_STRATEGIES};
+use strategies::euchre::{self as euchre_strategies, euchre_strategy, EuchreStrategy, EUCHRE_STRATEGIES};
+use strategies::hearts::{hearts_strategy, play_deal, HeartsStrategy, HEARTS_STRATEGIES};
+use strategies::spades::{play_match, spades_strategy, SpadesStrategy, SPADES_STRATEGIES};
+use super::{validate_num, solitaire_args, manifest_arg, distribution, with_manifest, master_seed, play_seeded};
+
+pub fn commands<'a, 'b>() -> Vec<App<'a, 'b>> {
+    vec![
+        SubCommand::with_name("crazy-eights")
+            .version(crate_version!())
+            .about("Play games of crazy eights between two to seven strategies")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("games")
+                 .long("games")
+                 .takes_value(true)
+                 .default_value("1000")
+                 .validator(|v| validate_num("games", 1, usize::max_value(), v))
+                 .help("Number of games to play"))
+            .arg(Arg::with_name("players")
+                 .long("players")
+                 .takes_value(true)
+                 .default_value("simple,eager,random")
+                 .use_delimiter(false)
+                 .validator(|v| validate_players(v, crazy_eights::MIN_PLAYERS, crazy_eights::MAX_PLAYERS, CRAZY_EIGHTS_STRATEGIES))
+                 .help("Comma separated strategies of the seats, clockwise"))
+            .arg(Arg::with_name("hand")
+                 .long("hand")
+                 .takes_value(true)
+                 .validator(|v| validate_num("hand", 1, 7, v))
+                 .help("Cards dealt to each player, by default 7 for two players and 5 for more"))
+            .arg(Arg::with_name("draw-limit")
+                 .long("draw-limit")
+                 .takes_value(true)
+                 .validator(|v| validate_num("draw-limit", 1, usize::max_value(), v))
+                 .help("Most cards drawn by a player who cannot play before passing, by default drawing until able to play"))
+            .arg(Arg::with_name("two-draws")
+                 .long("two-draws")
+                 .takes_value(true)
+                 .default_value("0")
+                 .validator(|v| validate_num("two-draws", 0, 10, v))
+                 .help("Cards the player after a two draws, 0 for plain twos"))
+            .arg(Arg::with_name("stacking")
+                 .long("stacking")
+                 .help("Let a player made to draw by a two play a two to pass the draw on"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Play the deals of consecutive seeds from this one instead of random deals")),
+        SubCommand::with_name("cribbage")
+            .version(crate_version!())
+            .about("Play games of cribbage between two strategies")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("games")
+                 .long("games")
+                 .takes_value(true)
+                 .default_value("1000")
+                 .validator(|v| validate_num("games", 1, usize::max_value(), v))
+                 .help("Number of games to play"))
+            .arg(Arg::with_name("players")
+                 .long("players")
+                 .takes_value(true)
+                 .default_value("simple,random")
+                 .use_delimiter(false)
+                 .validator(|v| validate_players(v, cribbage::NUM_PLAYERS, cribbage::NUM_PLAYERS, CRIBBAGE_STRATEGIES))
+                 .help("Comma separated strategies of the two players"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Play the deals of consecutive seeds from this one instead of random deals")),
+        SubCommand::with_name("euchre")
+            .version(crate_version!())
+            .about("Play a tournament of euchre matches between pairs of strategies")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("matches")
+                 .long("matches")
+                 .takes_value(true)
+                 .default_value("100")
+                 .validator(|v| validate_num("matches", 1, usize::max_value(), v))
+                 .help("Number of matches to play for every pairing of strategies"))
+            .arg(Arg::with_name("strategies")
+                 .long("strategies")
+                 .takes_value(true)
+                 .default_value("simple,random")
+                 .use_delimiter(false)
+                 .validator(|v| validate_players(v, 1, usize::max_value(), EUCHRE_STRATEGIES))
+                 .help("Comma separated strategies, each played by a partnership against every other"))
+            .arg(Arg::with_name("stick-the-dealer")
+                 .long("stick-the-dealer")
+                 .help("Make the dealer name trumps when everybody passes twice instead of throwing the deal in"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Play the deals of consecutive seeds from this one instead of random deals")),
+        SubCommand::with_name("hearts")
+            .version(crate_version!())
+            .about("Play deals of hearts between four strategies")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("deals")
+                 .long("deals")
+                 .takes_value(true)
+                 .default_value("1000")
+                 .validator(|v| validate_num("deals", 1, usize::max_value(), v))
+                 .help("Number of deals to play"))
+            .arg(Arg::with_name("players")
+                 .long("players")
+                 .takes_value(true)
+                 .default_value("simple,random,simple,random")
+                 .use_delimiter(false)
+                 .validator(|v| validate_players(v, hearts::NUM_PLAYERS, hearts::NUM_PLAYERS, HEARTS_STRATEGIES))
+                 .help("Comma separated strategies of the four seats, clockwise from the first dealt"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Play the deals of consecutive seeds from this one instead of random deals")),
+        SubCommand::with_name("spades")
+            .version(crate_version!())
+            .about("Play a tournament of spades matches between pairs of strategies")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("matches")
+                 .long("matches")
+                 .takes_value(true)
+                 .default_value("100")
+                 .validator(|v| validate_num("matches", 1, usize::max_value(), v))
+                 .help("Number of matches to play for every pairing of strategies"))
+            .arg(Arg::with_name("strategies")
+                 .long("strategies")
+                 .takes_value(true)
+                 .default_value("simple,random")
+                 .use_delimiter(false)
+                 .validator(|v| validate_players(v, 1, usize::max_value(), SPADES_STRATEGIES))
+                 .help("Comma separated strategies, each played by a partnership against every other"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Play the deals of consecutive seeds from this one instead of random deals")),
+        SubCommand::with_name("war")
+            .version(crate_version!())
+            .about("Play games of war and report how long they last")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(Arg::with_name("pickup")
+                 .long("pickup")
+                 .takes_value(true)
+                 .default_value("winner-first")
+                 .possible_values(PICKUPS)
+                 .help("Order won cards go to the bottom of the hand"))
+            .arg(Arg::with_name("war-cards")
+                 .long("war-cards")
+                 .takes_value(true)
+                 .default_value("3")
+                 .validator(|v| validate_num("war-cards", 0, 25, v))
+                 .help("Number of cards laid face down in a war"))
+            .arg(Arg::with_name("max-turns")
+                 .long("max-turns")
+                 .takes_value(true)
+                 .default_value("100000")
+                 .validator(|v| validate_num("max-turns", 1, usize::max_value(), v))
+                 .help("Number of turns after which a game is stopped undecided")),
+    ]
+}
+
+/// Run the command `name` with its arguments `matches`, or return false if
+/// the command is not one of this module's.
+pub fn run(name: &str, matches: &ArgMatches) -> bool {
+    match name {
+        "crazy-eights" => crazy_eights_command(matches),
+        "cribbage" => cribbage_command(matches),
+        "euchre" => euchre_command(matches),
+        "hearts" => hearts_command(matches),
+        "spades" => spades_command(matches),
+        "war" => war_command(matches),
+        _ => return false,
+    }
+
+    true
+}
+
+fn crazy_eights_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let players: Vec<&str> = matches.value_of("players").unwrap().split(',').collect();
+    let mut rules = CrazyEightsRules::new(players.len());
+    if let Some(hand) = matches.value_of("hand") {
+        rules.hand_len = hand.parse::<usize>().unwrap();
+    }
+    rules.draw_limit = matches.value_of("draw-limit").map(|v| v.parse::<usize>().unwrap());
+    rules.two_draws = matches.value_of("two-draws").unwrap().parse::<usize>().unwrap();
+    rules.stacking = matches.is_present("stacking");
+    with_manifest("crazy-eights", matches, &["games", "players", "hand", "draw-limit", "two-draws", "stacking"], Some(seed), || run_crazy_eights(rules, &players, games, seed));
+}
+
+fn cribbage_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let players: Vec<&str> = matches.value_of("players").unwrap().split(',').collect();
+    with_manifest("cribbage", matches, &["games", "players"], Some(seed), || run_cribbage(&players, games, seed));
+}
+
+fn euchre_command(matches: &ArgMatches) {
+    let count = matches.value_of("matches").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let names: Vec<&str> = matches.value_of("strategies").unwrap().split(',').collect();
+    let mut rules = EuchreRules::new();
+    rules.stick_the_dealer = matches.is_present("stick-the-dealer");
+    with_manifest("euchre", matches, &["matches", "strategies", "stick-the-dealer"], Some(seed), || run_euchre(rules, &names, count, seed));
+}
+
+fn hearts_command(matches: &ArgMatches) {
+    let deals = matches.value_of("deals").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let players: Vec<&str> = matches.value_of("players").unwrap().split(',').collect();
+    with_manifest("hearts", matches, &["deals", "players"], Some(seed), || run_hearts(&players, deals, seed));
+}
+
+fn spades_command(matches: &ArgMatches) {
+    let count = matches.value_of("matches").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let names: Vec<&str> = matches.value_of("strategies").unwrap().split(',').collect();
+    with_manifest("spades", matches, &["matches", "strategies"], Some(seed), || run_spades(&names, count, seed));
+}
+
+fn war_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let rules = WarRules {
+        pickup: matches.value_of("pickup").unwrap().parse::<Pickup>().unwrap(),
+        war_cards: matches.value_of("war-cards").unwrap().parse::<usize>().unwrap(),
+        max_turns: matches.value_of("max-turns").unwrap().parse::<usize>().unwrap(),
+    };
+    with_manifest("war", matches, &["games", "pickup", "war-cards", "max-turns"], Some(seed), || run_war(rules, games, seed));
+}
+
+/// Check the comma separated strategies `v` of `min` to `max` players are
+/// all `known` ones.
+fn validate_players(v: String, min: usize, max: usize, known: &[&str]) -> Result<(), String> {
+    let names: Vec<&str> = v.split(',').collect();
+
+    if min == max && names.len() != min {
+        return Err(format!("expected {} players, got {}", min, names.len()));
+    }
+    if names.len() < min || names.len() > max {
+        return Err(format!("expected {} to {} players, got {}", min, max, names.len()));
+    }
+
+    match names.iter().find(|name| ! known.contains(name)) {
+        Some(name) => Err(format!("unknown strategy {}, expected one of {}", name, known.join(", "))),
+        None => Ok(()),
+    }
+}
+
+/// Every pairing of two of the strategies `names`, or the only one against
+/// itself.
+fn pairings<'a>(names: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    let mut pairings = Vec::new();
+    for i in 0..names.len() {
+        for j in i + 1..names.len() {
+            pairings.push((names[i], names[j]));
+        }
+    }
+    if pairings.is_empty() {
+        pairings.push((names[0], names[0]));
+    }
+    pairings
+}
+
+/// Play `games` games of crazy eights of `rules` with the strategies `names`
+/// in the seats, the deal going round the table, and report the games won
+/// and points scored by each seat.
+fn run_crazy_eights(rules: CrazyEightsRules, names: &[&str], games: usize, first_seed: u64) {
+    let mut players: Vec<Box<dyn CrazyEightsStrategy>> = names.iter().enumerate()
+        .map(|(p, name)| crazy_eights_strategy(name, first_seed.wrapping_add(p as u64)).unwrap())
+        .collect();
+    let mut wins = vec![0; rules.players];
+    let mut points = vec![0; rules.players];
+    let mut blocked = 0;
+    let mut turn_limits = 0;
+    let mut turns = 0;
+    let seconds = play_seeded(games, first_seed, 1, |i, seed| {
+        let mut game = CrazyEightsGame::new(rules, i % rules.players, |c| seeded_shuffle(seed, c));
+
+        match crazy_eights_strategies::play_game(&mut game, &mut players) {
+            CrazyEightsOutcome::Winner(p) => {
+                wins[p] += 1;
+                points[p] += game.winner_points().unwrap();
+            },
+            CrazyEightsOutcome::Blocked => blocked += 1,
+            CrazyEightsOutcome::TurnLimit => turn_limits += 1,
+        }
+        turns += game.turns();
+    });
+
+    println!("{} games of crazy eights in {:.1}s, {:.1} turns per game, {} blocked, {} stopped after {} turns",
+             games, seconds, turns as f64 / games as f64, blocked, turn_limits, rules.max_turns);
+
+    for p in 0..rules.players {
+        let rate = wins[p] as f64 / games as f64;
+        println!("seat {} {}: {:.1}% +/- {:.1}% games won, {:.1} points per game",
+                 p, names[p], rate * 100.0, (rate * (1.0 - rate) / games as f64).sqrt() * 100.0, points[p] as f64 / games as f64);
+    }
+}
+
+/// Play `games` games of cribbage between the strategies `names`, dealing
+/// first in turn, and report the games won by each player and where their
+/// points came from.
+fn run_cribbage(names: &[&str], games: usize, first_seed: u64) {
+    let mut wins = [0; cribbage::NUM_PLAYERS];
+    let mut pegging = [0; cribbage::NUM_PLAYERS];
+    let mut hand = [0; cribbage::NUM_PLAYERS];
+    let mut crib = [0; cribbage::NUM_PLAYERS];
+    let mut total_deals = 0;
+
+    // Enough seeds for the longest possible game: every deal scores at least
+    // the last card, and the game is over before the players have 121 points
+    // each.
+    let seconds = play_seeded(games, first_seed, cribbage::NUM_PLAYERS as u64 * cribbage::WINNING_SCORE as u64, |i, seed| {
+        let mut players: Vec<Box<dyn CribbageStrategy>> = names.iter().enumerate()
+            .map(|(p, name)| cribbage_strategy(name, seed.wrapping_add(p as u64)).unwrap())
+            .collect();
+        let deals = play_game(&mut players, i % cribbage::NUM_PLAYERS, |deal, c| seeded_shuffle(seed.wrapping_add(deal as u64), c));
+
+        for game in &deals {
+            let points = game.points();
+            for p in 0..cribbage::NUM_PLAYERS {
+                pegging[p] += points.pegging[p];
+                hand[p] += points.hand[p];
+            }
+            crib[game.dealer()] += points.crib + points.heels;
+        }
+
+        wins[deals.last().unwrap().winner().unwrap()] += 1;
+        total_deals += deals.len();
+    });
+
+    println!("{} games of cribbage in {:.1}s, {:.1} deals per game", games, seconds, total_deals as f64 / games as f64);
+
+    for p in 0..cribbage::NUM_PLAYERS {
+        println!("player {} {}: {:.1}% games won, {:.2} pegging, {:.2} hand, {:.2} crib points per deal",
+                 p, names[p], wins[p] as f64 * 100.0 / games as f64, pegging[p] as f64 / total_deals as f64,
+                 hand[p] as f64 / total_deals as f64, crib[p] as f64 / total_deals as f64);
+    }
+}
+
+/// Most deals of a euchre match before it is given up as unfinished.
+const MAX_EUCHRE_DEALS: usize = 200;
+
+/// Play `matches` matches of euchre of `rules` between every pairing of two
+/// strategies of `names`, or of the only one against itself, like
+/// `run_spades`. Reports the matches won, the average scores and how often
+/// each side made trumps and was euchred.
+fn run_euchre(rules: EuchreRules, names: &[&str], matches: usize, first_seed: u64) {
+    for (a, b) in pairings(names) {
+        let mut wins = [0; 2];
+        let mut points = [0; 2];
+        let mut deals = 0;
+        let seconds = play_seeded((matches + 1) / 2, first_seed, MAX_EUCHRE_DEALS as u64, |pair, seed| {
+            // Side 0 plays strategy a, in seats 0 and 2 on the first match of
+            // the pair and in seats 1 and 3 on the second.
+            for &swapped in [false, true].iter().take(matches - pair * 2) {
+                let mut players: Vec<Box<dyn EuchreStrategy>> = (0..euchre::NUM_PLAYERS)
+                    .map(|p| {
+                        let name = if (euchre::team(p) == 1) == swapped { a } else { b };
+                        euchre_strategy(name, seed.wrapping_add(p as u64)).unwrap()
+                    })
+                    .collect();
+
+                let m = euchre_strategies::play_match(rules, &mut players, MAX_EUCHRE_DEALS, |deal, c| seeded_shuffle(seed.wrapping_add(deal as u64), c));
+                let sides = if swapped { [1, 0] } else { [0, 1] };
+
+                for side in 0..2 {
+                    points[side] += m.scores()[sides[side]];
+                }
+                if let Some(team) = m.winner() {
+                    wins[sides.iter().position(|s| *s == team).unwrap()] += 1;
+                }
+                deals += m.deals();
+            }
+        });
+
+        println!("{} vs {}: {} matches in {:.1}s, {:.1} deals per match", a, b, matches, seconds, deals as f64 / matches as f64);
+        println!("  {:.1}% won by {}, {:.1}% by {}, {} unfinished", wins[0] as f64 * 100.0 / matches as f64, a,
+                 wins[1] as f64 * 100.0 / matches as f64, b, matches - wins[0] - wins[1]);
+        println!("  average score {:.1} to {:.1}, {:.2} to {:.2} points per deal",
+                 points[0] as f64 / matches as f64, points[1] as f64 / matches as f64,
+                 points[0] as f64 / deals as f64, points[1] as f64 / deals as f64);
+    }
+}
+
+/// Play `deals` deals of hearts with the strategies `names` in the four seats
+/// and report the points and wins of each seat. The lowest points win a
+/// deal, and seats sharing the lowest share the win.
+fn run_hearts(names: &[&str], deals: usize, first_seed: u64) {
+    let mut players: Vec<Box<dyn HeartsStrategy>> = names.iter().enumerate()
+        .map(|(p, name)| hearts_strategy(name, first_seed.wrapping_add(p as u64)).unwrap())
+        .collect();
+    let mut points = [0; hearts::NUM_PLAYERS];
+    let mut wins = [0.0; hearts::NUM_PLAYERS];
+    let mut moons = [0; hearts::NUM_PLAYERS];
+    let seconds = play_seeded(deals, first_seed, 1, |deal, seed| {
+        let mut game = HeartsGame::new(deal, |c| seeded_shuffle(seed, c));
+        let deal_points = play_deal(&mut game, &mut players);
+
+        let low = *deal_points.iter().min().unwrap();
+        let winners = deal_points.iter().filter(|p| **p == low).count();
+
+        for p in 0..hearts::NUM_PLAYERS {
+            points[p] += deal_points[p];
+            if deal_points[p] == low {
+                wins[p] += 1.0 / winners as f64;
+            }
+        }
+        if let Some(shooter) = game.moon_shooter() {
+            moons[shooter] += 1;
+        }
+    });
+
+    println!("{} deals of hearts in {:.1}s, {:.1} deals/s", deals, seconds, deals as f64 / seconds);
+
+    for p in 0..hearts::NUM_PLAYERS {
+        println!("seat {} {}: {:.2} points per deal, {:.1}% deals won, {} moons",
+                 p, names[p], points[p] as f64 / deals as f64, wins[p] * 100.0 / deals as f64, moons[p]);
+    }
+}
+
+/// Most deals of a spades match before it is given up as unfinished.
+const MAX_SPADES_DEALS: usize = 200;
+
+/// Play `matches` matches of spades between every pairing of two strategies
+/// of `names`, or of the only one against itself, each partnership of two
+/// seats using one strategy. Every other match replays the deals of the one
+/// before with the partnerships swapped. Reports the matches won and the
+/// average scores of each side per pairing.
+fn run_spades(names: &[&str], matches: usize, first_seed: u64) {
+    let rules = SpadesRules::new();
+
+    for (a, b) in pairings(names) {
+        let mut wins = [0; 2];
+        let mut points = [0i64; 2];
+        let mut deals = 0;
+        let seconds = play_seeded((matches + 1) / 2, first_seed, MAX_SPADES_DEALS as u64, |pair, seed| {
+            // Side 0 plays strategy a, in seats 0 and 2 on the first match of
+            // the pair and in seats 1 and 3 on the second.
+            for &swapped in [false, true].iter().take(matches - pair * 2) {
+                let mut players: Vec<Box<dyn SpadesStrategy>> = (0..spades::NUM_PLAYERS)
+                    .map(|p| {
+                        let name = if (spades::team(p) == 1) == swapped { a } else { b };
+                        spades_strategy(name, seed.wrapping_add(p as u64)).unwrap()
+                    })
+                    .collect();
+
+                let m = play_match(rules, &mut players, MAX_SPADES_DEALS, |deal, c| seeded_shuffle(seed.wrapping_add(deal as u64), c));
+                let sides = if swapped { [1, 0] } else { [0, 1] };
+
+                for side in 0..2 {
+                    points[side] += m.scores()[sides[side]].points as i64;
+                }
+                if let Some(team) = m.winner() {
+                    wins[sides.iter().position(|s| *s == team).unwrap()] += 1;
+                }
+                deals += m.deals();
+            }
+        });
+
+        println!("{} vs {}: {} matches in {:.1}s, {:.1} deals per match", a, b, matches, seconds, deals as f64 / matches as f64);
+        println!("  {:.1}% won by {}, {:.1}% by {}, {} unfinished", wins[0] as f64 * 100.0 / matches as f64, a,
+                 wins[1] as f64 * 100.0 / matches as f64, b, matches - wins[0] - wins[1]);
+        println!("  average score {:.1} to {:.1}, {:.1} to {:.1} points per deal",
+                 points[0] as f64 / matches as f64, points[1] as f64 / matches as f64,
+                 points[0] as f64 / deals as f64, points[1] as f64 / deals as f64);
+    }
+}
+
+/// Play `games` games of war and report how they ended and how long they
+/// lasted.
+fn run_war(rules: WarRules, games: usize, first_seed: u64) {
+    let mut wins = [0; 2];
+    let mut draws = 0;
+    let mut turn_limits = 0;
+    let mut turns = Histogram::new();
+    let mut cycles = Histogram::new();
+    let mut wars = 0;
+    let seconds = play_seeded(games, first_seed, 1, |_, seed| {
+        let mut game = WarGame::new(rules, |c| seeded_shuffle(seed, c));
+
+        match game.play() {
+            WarOutcome::Winner(player) => {
+                wins[player] += 1;
+                turns.record(game.turns());
+            },
+            WarOutcome::Draw => draws += 1,
+            WarOutcome::Cycle { length, .. } => cycles.record(length),
+            WarOutcome::TurnLimit => turn_limits += 1,
+        }
+
+        wars += game.wars();
+    });
+
+    println!("{} games of war with {} pickups in {:.1}s, {:.1} games/s", games, rules.pickup, seconds, games as f64 / seconds);
+    println!("{}/{} wins for the first player, {} for the second, {} draws", wins[0], games, wins[1], draws);
+    println!("{} cycles, {} stopped after {} turns", cycles.len(), turn_limits, rules.max_turns);
+    println!("{:.2} wars per game", wars as f64 / games as f64);
+
+    if ! turns.is_empty() {
+        println!("decided games: turns {}, mean {:.1}", distribution(&turns), turns.mean().unwrap());
+    }
+    if ! cycles.is_empty() {
+        println!("cycles: length {}", distribution(&cycles));
+    }
+}
@@ -0,0 +1,241 @@
+//! Commands of the casino games: baccarat, blackjack and video poker.
+
+use clap::{Arg, App, ArgMatches, SubCommand};
+use games::baccarat::{BaccaratRules, Coup, BETS};
+use games::blackjack::{BlackjackRound, BlackjackRules};
+use games::shoe::Shoe;
+use games::video_poker::{PayTable, VideoPokerHand, PAY_TABLES, WINS};
+use analysis::sum::CompensatedSum;
+use cards::seeded_shuffle;
+use strategies::blackjack::{blackjack_strategy, BlackjackStrategy, BLACKJACK_STRATEGIES};
+use strategies::video_poker::{video_poker_strategy, VideoPokerStrategy, VIDEO_POKER_STRATEGIES};
+use super::{validate_num, manifest_arg, with_manifest, master_seed, play_seeded};
+
+pub fn commands<'a, 'b>() -> Vec<App<'a, 'b>> {
+    vec![
+        SubCommand::with_name("baccarat")
+            .version(crate_version!())
+            .about("Deal punto banco baccarat and report the house edge of each bet")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("shoes")
+                 .long("shoes")
+                 .takes_value(true)
+                 .default_value("10000")
+                 .validator(|v| validate_num("shoes", 1, usize::max_value(), v))
+                 .help("Number of shoes to deal, each down to its cut card"))
+            .arg(Arg::with_name("decks")
+                 .long("decks")
+                 .takes_value(true)
+                 .default_value("8")
+                 .validator(|v| validate_num("decks", 1, 8, v))
+                 .help("Number of decks in the shoe"))
+            .arg(Arg::with_name("tie-pays")
+                 .long("tie-pays")
+                 .takes_value(true)
+                 .default_value("8")
+                 .validator(|v| validate_num("tie-pays", 1, 100, v))
+                 .help("What a winning tie bet pays to one"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Shuffle the shoes with consecutive seeds from this one instead of randomly")),
+        SubCommand::with_name("blackjack")
+            .version(crate_version!())
+            .about("Play blackjack against the dealer and report the expected value per hand")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("shoes")
+                 .long("shoes")
+                 .takes_value(true)
+                 .default_value("10000")
+                 .validator(|v| validate_num("shoes", 1, usize::max_value(), v))
+                 .help("Number of shoes to play, each down to its cut card"))
+            .arg(Arg::with_name("decks")
+                 .long("decks")
+                 .takes_value(true)
+                 .default_value("6")
+                 .validator(|v| validate_num("decks", 1, 8, v))
+                 .help("Number of decks in the shoe"))
+            .arg(Arg::with_name("hit-soft-17")
+                 .long("hit-soft-17")
+                 .help("The dealer hits a soft 17 instead of standing"))
+            .arg(Arg::with_name("strategy")
+                 .long("strategy")
+                 .takes_value(true)
+                 .default_value("basic")
+                 .possible_values(BLACKJACK_STRATEGIES)
+                 .help("Strategy used to play each hand"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Shuffle the shoes with consecutive seeds from this one instead of randomly")),
+        SubCommand::with_name("video-poker")
+            .version(crate_version!())
+            .about("Play hands of jacks or better video poker and report the return to player")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("hands")
+                 .long("hands")
+                 .takes_value(true)
+                 .default_value("100000")
+                 .validator(|v| validate_num("hands", 1, usize::max_value(), v))
+                 .help("Number of hands to play"))
+            .arg(Arg::with_name("pay-table")
+                 .long("pay-table")
+                 .takes_value(true)
+                 .default_value("9/6")
+                 .possible_values(PAY_TABLES)
+                 .help("Pays of a full house and a flush"))
+            .arg(Arg::with_name("strategy")
+                 .long("strategy")
+                 .takes_value(true)
+                 .default_value("simple")
+                 .possible_values(VIDEO_POKER_STRATEGIES)
+                 .help("Strategy choosing the cards to hold; optimal is exact but plays a few hands a second"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Deal the hands of consecutive seeds from this one instead of random hands")),
+    ]
+}
+
+/// Run the command `name` with its arguments `matches`, or return false if
+/// the command is not one of this module's.
+pub fn run(name: &str, matches: &ArgMatches) -> bool {
+    match name {
+        "baccarat" => baccarat_command(matches),
+        "blackjack" => blackjack_command(matches),
+        "video-poker" => video_poker_command(matches),
+        _ => return false,
+    }
+
+    true
+}
+
+fn baccarat_command(matches: &ArgMatches) {
+    let shoes = matches.value_of("shoes").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let mut rules = BaccaratRules::new();
+    rules.decks = matches.value_of("decks").unwrap().parse::<usize>().unwrap();
+    rules.tie_pays = matches.value_of("tie-pays").unwrap().parse::<f64>().unwrap();
+    with_manifest("baccarat", matches, &["shoes", "decks", "tie-pays"], Some(seed), || run_baccarat(rules, shoes, seed));
+}
+
+fn blackjack_command(matches: &ArgMatches) {
+    let shoes = matches.value_of("shoes").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let mut rules = BlackjackRules::new();
+    rules.decks = matches.value_of("decks").unwrap().parse::<usize>().unwrap();
+    rules.hit_soft_17 = matches.is_present("hit-soft-17");
+    let mut strategy = blackjack_strategy(matches.value_of("strategy").unwrap()).unwrap();
+    with_manifest("blackjack", matches, &["shoes", "decks", "hit-soft-17"], Some(seed), || run_blackjack(rules, shoes, seed, &mut *strategy));
+}
+
+fn video_poker_command(matches: &ArgMatches) {
+    let hands = matches.value_of("hands").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let table = matches.value_of("pay-table").unwrap().parse::<PayTable>().unwrap();
+    let mut strategy = video_poker_strategy(matches.value_of("strategy").unwrap()).unwrap();
+    with_manifest("video-poker", matches, &["hands", "pay-table"], Some(seed), || run_video_poker(table, hands, seed, &mut *strategy));
+}
+
+/// Deal the coups of `shoes` shoes, each down to its cut card, and report
+/// how often each side wins and the house edge of each bet with its standard
+/// error.
+fn run_baccarat(rules: BaccaratRules, shoes: usize, first_seed: u64) {
+    let mut coups = 0;
+    let mut naturals = 0;
+    let mut outcomes = [0; 3];
+    let mut net = [CompensatedSum::new(), CompensatedSum::new(), CompensatedSum::new()];
+    let mut squares = [CompensatedSum::new(), CompensatedSum::new(), CompensatedSum::new()];
+    let seconds = play_seeded(shoes, first_seed, 1, |_, seed| {
+        let mut shoe = Shoe::new(rules.decks, rules.penetration, seed);
+
+        while ! shoe.is_finished() {
+            let coup = Coup::deal(&mut shoe);
+
+            coups += 1;
+            if coup.is_natural() {
+                naturals += 1;
+            }
+            outcomes[coup.outcome() as usize] += 1;
+            for (b, bet) in BETS.iter().enumerate() {
+                let win = coup.pays(*bet, &rules);
+                net[b].add(win);
+                squares[b].add(win * win);
+            }
+        }
+    });
+
+    println!("{} shoes of {} decks, {} coups in {:.1}s, {:.0} coups/s", shoes, rules.decks, coups, seconds, coups as f64 / seconds);
+    println!("{:.2}% naturals", naturals as f64 / coups as f64 * 100.0);
+
+    for (b, bet) in BETS.iter().enumerate() {
+        let mean = net[b].value() / coups as f64;
+        let variance = (squares[b].value() / coups as f64 - mean * mean).max(0.0);
+        println!("{}: wins {:.2}% of coups, house edge {:.3}% +/- {:.3}%",
+                 bet, outcomes[*bet as usize] as f64 / coups as f64 * 100.0, -mean * 100.0, (variance / coups as f64).sqrt() * 100.0);
+    }
+}
+
+/// Play the rounds of `shoes` shoes, each down to its cut card, and report
+/// the expected value per hand with its standard error.
+fn run_blackjack(rules: BlackjackRules, shoes: usize, first_seed: u64, strategy: &mut dyn BlackjackStrategy) {
+    let mut hands = 0;
+    let mut net = CompensatedSum::new();
+    let mut squares = CompensatedSum::new();
+    let seconds = play_seeded(shoes, first_seed, 1, |_, seed| {
+        let mut shoe = Shoe::new(rules.decks, rules.penetration, seed);
+
+        while ! shoe.is_finished() {
+            let mut round = BlackjackRound::deal(&rules, &mut shoe);
+            strategy.play(&mut round, &mut shoe);
+            let win = round.finish(&mut shoe).unwrap();
+
+            hands += 1;
+            net.add(win);
+            squares.add(win * win);
+        }
+    });
+
+    let mean = net.value() / hands as f64;
+    let variance = (squares.value() / hands as f64 - mean * mean).max(0.0);
+
+    println!("{} shoes of {} decks, {} hands in {:.1}s, {:.0} hands/s", shoes, rules.decks, hands, seconds, hands as f64 / seconds);
+    println!("{:+.4} units net per hand, {:+.3}% +/- {:.3}%", mean, mean * 100.0, (variance / hands as f64).sqrt() * 100.0);
+}
+
+/// Play `hands` hands of video poker on `table` with `strategy`, a credit
+/// each, and report the return to player with its standard error and how
+/// often each hand paid.
+fn run_video_poker(table: PayTable, hands: usize, first_seed: u64, strategy: &mut dyn VideoPokerStrategy) {
+    let mut paid = 0u64;
+    let mut squares = 0u64;
+    let mut wins = [0; 9];
+    let seconds = play_seeded(hands, first_seed, 1, |_, seed| {
+        let mut hand = VideoPokerHand::deal(|c| seeded_shuffle(seed, c));
+        let hold = strategy.hold(hand.cards(), &table);
+        hand.draw(hold).unwrap();
+
+        let win = hand.win();
+        let pay = table.pay(win) as u64;
+        paid += pay;
+        squares += pay * pay;
+        if let Some(win) = win {
+            wins[win as usize] += 1;
+        }
+    });
+
+    let mean = paid as f64 / hands as f64;
+    let variance = (squares as f64 / hands as f64 - mean * mean).max(0.0);
+
+    println!("{} hands of {} jacks or better in {:.1}s, {:.0} hands/s", hands, table, seconds, hands as f64 / seconds);
+    println!("{:.3}% +/- {:.3}% returned to player", mean * 100.0, (variance / hands as f64).sqrt() * 100.0);
+    for win in WINS.iter().rev() {
+        match wins[*win as usize] {
+            0 => println!("  {:>16}: {:>8} hands", win.to_string(), 0),
+            count => println!("  {:>16}: {:>8} hands, 1 in {:.1}", win.to_string(), count, hands as f64 / count as f64),
+        }
+    }
+}
@@ -0,0 +1,1129 @@
+//! The `solitaire:klondike` command: klondike strategies played in parallel
+//! and the statistics, traces and reports of the run.
+
+use clap::{Arg, App, ArgMatches, SubCommand};
+use deals;
+use json;
+use kpatience;
+use runner;
+use status;
+use training;
+use std::cmp;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use games::solitaire::klondike::{KlondikeRules, KlondikeSolitaireGame, MoveKind, MoveRecord, RULE_PRESETS};
+use games::solitaire::layout;
+use games::solitaire::replay::Replay;
+use games::solitaire::sampler::MidGameSampler;
+use analysis::convergence::Convergence;
+use analysis::histogram::Histogram;
+use analysis::solitaire::klondike::{LossPatterns, MoveStats, RevealStats};
+use analysis::streaks::Streaks;
+use archive::{DealArchive, DealArchiveWriter};
+use cards::{french, ShuffleRng, ShuffleSource, SHUFFLE_RNGS};
+use compress::{LogReader, LogWriter};
+use deals::Deal;
+use manifest::Manifest;
+use report::HtmlReport;
+use runner::{CancellationToken, Worker};
+use status::PauseGate;
+use throttle::Throttle;
+use strategies::solitaire::klondike::{GameFilter, GameStrategy, AllFilter, StrategyFactory};
+use strategies::solitaire::klondike::greedy::GreedyStrategy;
+use strategies::solitaire::klondike::lookahead::LookaheadStrategy;
+use strategies::solitaire::klondike::mcts::{DeterminizedMctsStrategy, MctsConfig};
+use strategies::solitaire::klondike::policy::ProcessPolicy;
+use strategies::solitaire::klondike::simple::SimpleKlondikeStrategy;
+use strategies::solitaire::klondike::simple_plus::SimplePlusStrategy;
+use strategies::solitaire::klondike::solver::SolverStrategy;
+use strategies::solitaire::klondike::weighted::{WeightedStrategy, Weights};
+use super::{validate_num, deal_arg, throttle_arg, move_budget_arg, move_budget, manifest_arg, throttle, distribution, start_manifest, finish_manifest, master_seed, seconds};
+
+pub fn commands<'a, 'b>() -> Vec<App<'a, 'b>> {
+    vec![
+        SubCommand::with_name("solitaire:klondike")
+            .version(crate_version!())
+            .about("Play klondike solitaire")
+            .arg(Arg::with_name("games")
+                 .long("games")
+                 .takes_value(true)
+                 .validator(|v| validate_num("games", 1, usize::max_value(), v))
+                 .help("Number of games to play [default: 1000000]"))
+            .arg(Arg::with_name("duration")
+                 .long("duration")
+                 .takes_value(true)
+                 .conflicts_with("games")
+                 .validator(|v| parse_duration(&v).map(|_| ()))
+                 .help("Play as many games as fit in a wall clock time, e.g. 90s, 10m or 2h, instead of --games"))
+            .arg(Arg::with_name("concurrency")
+                 .long("concurrency")
+                 .takes_value(true)
+                 .default_value("1")
+                 .validator(|v| validate_num("concurrency", 1, usize::max_value(), v))
+                 .help("Number of concurrent games to play"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Derive every deal and every strategy's choices from this seed, so the run can be repeated exactly"))
+            .arg(Arg::with_name("rng")
+                 .long("rng")
+                 .takes_value(true)
+                 .possible_values(SHUFFLE_RNGS)
+                 .default_value("isaac64")
+                 .help("Generator the deals are shuffled with; deal seeds only give the same deals with the same generator"))
+            .args(&strategy_args(true))
+            .arg(move_budget_arg())
+            .arg(throttle_arg())
+            .arg(manifest_arg())
+            .arg(Arg::with_name("rules")
+                 .long("rules")
+                 .takes_value(true)
+                 .default_value("standard")
+                 .possible_values(RULE_PRESETS)
+                 .help("Rule preset: draw count, passes through the deck and scoring"))
+            .arg(Arg::with_name("draw")
+                 .long("draw")
+                 .takes_value(true)
+                 .possible_values(&["1", "3"])
+                 .help("Cards turned from the stock at a time, instead of the draw count of the rule preset"))
+            .arg(Arg::with_name("passes")
+                 .long("passes")
+                 .takes_value(true)
+                 .validator(|v| validate_num("passes", 1, usize::max_value(), v))
+                 .help("Times the stock may be dealt through, instead of the pass limit of the rule preset"))
+            .arg(Arg::with_name("move-time")
+                 .long("move-time")
+                 .takes_value(true)
+                 .value_name("SECONDS")
+                 .validator(|v| parse_move_time(&v).map(|_| ()))
+                 .help("Score standard games with the classic time penalty and bonus, each move taking SECONDS of simulated time, e.g. 1.5"))
+            .arg(Arg::with_name("thoughtful")
+                 .long("thoughtful")
+                 .help("Let strategies see the hidden pile cards and the order of the deck"))
+            .arg(Arg::with_name("batch-size")
+                 .long("batch-size")
+                 .takes_value(true)
+                 .default_value("1000")
+                 .validator(|v| validate_num("batch-size", 1, usize::max_value(), v))
+                 .help("Number of consecutive games per batch when reporting the variance of win rates"))
+            .arg(Arg::with_name("reveal-stats")
+                 .long("reveal-stats")
+                 .help("Report how often and how early each pile's hidden cards are all turned up"))
+            .arg(Arg::with_name("move-stats")
+                 .long("move-stats")
+                 .help("Compare how many moves of each kind won and lost games made"))
+            .arg(Arg::with_name("loss-patterns")
+                 .long("loss-patterns")
+                 .takes_value(true)
+                 .validator(|v| validate_num("loss-patterns", 1, usize::max_value(), v))
+                 .help("Report the N most common ways lost games ended"))
+            .arg(Arg::with_name("export-training")
+                 .long("export-training")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .help("Write (state, move, outcome) examples of every move played to a CSV file, compressed if it ends in .gz or .zst"))
+            .arg(Arg::with_name("trace")
+                 .long("trace")
+                 .takes_value(true)
+                 .value_name("N")
+                 .validator(|v| validate_num("trace", 1, usize::max_value(), v))
+                 .help("Log every move of the first N games with the board before and after"))
+            .arg(Arg::with_name("deal-pool")
+                 .long("deal-pool")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .help("Play the deals of a deal archive, in order, instead of random deals"))
+            .arg(Arg::with_name("deals-from")
+                 .long("deals-from")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .conflicts_with("deal-pool")
+                 .help("Play the deals of a plain text or JSON deal file, - for stdin, in order, instead of random deals"))
+            .arg(deal_arg()
+                 .conflicts_with_all(&["deal-pool", "deals-from", "position", "midgame"]))
+            .arg(Arg::with_name("record-losses")
+                 .long("record-losses")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .conflicts_with("deals-from")
+                 .help("Append the deals no strategy won to a deal archive"))
+            .arg(Arg::with_name("dump-losses")
+                 .long("dump-losses")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .help("Write the final position of every lost game to a file, one JSON object per line"))
+            .arg(Arg::with_name("midgame")
+                 .long("midgame")
+                 .takes_value(true)
+                 .value_name("MOVES")
+                 .conflicts_with("deals-from")
+                 .validator(|v| parse_move_range(&v).map(|_| ()))
+                 .help("Start each game from a random position MOVES (a number or MIN-MAX) random moves into the deal; the move counts reported include these moves"))
+            .arg(Arg::with_name("position")
+                 .long("position")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .conflicts_with_all(&["deal-pool", "deals-from", "record-losses", "midgame"])
+                 .help("Play every game from the position of a layout, JSON or KPatience save file, - for stdin, with its face down and stock cards dealt at random, to estimate the win rate from that position"))
+            .arg(Arg::with_name("convergence")
+                 .long("convergence")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .help("Write the cumulative win rate as games are played to a CSV file"))
+            .arg(Arg::with_name("report")
+                 .long("report")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .help("Write the results and manifest of the run to a self contained HTML file")),
+    ]
+}
+
+/// Run the command `name` with its arguments `matches`, or return false if
+/// the command is not one of this module's.
+pub fn run(name: &str, matches: &ArgMatches) -> bool {
+    match name {
+        "solitaire:klondike" => klondike_command(matches),
+        _ => return false,
+    }
+
+    true
+}
+
+fn klondike_command(matches: &ArgMatches) {
+    let duration = matches.value_of("duration").map(|v| parse_duration(v).unwrap());
+    let game_count = match (matches.value_of("games"), duration) {
+        (Some(v), _) => Some(v.parse::<usize>().unwrap()),
+        (None, Some(_)) => None,
+        (None, None) => Some(1000000),
+    };
+    let thread_count = matches.value_of("concurrency").unwrap().parse::<usize>().unwrap();
+    let preset = KlondikeRules::preset(matches.value_of("rules").unwrap()).unwrap();
+    let rules = KlondikeRules {
+        draw_count: matches.value_of("draw").map_or(preset.draw_count, |v| v.parse::<u8>().unwrap()),
+        pass_limit: matches.value_of("passes").map_or(preset.pass_limit, |v| Some(v.parse::<usize>().unwrap())),
+        move_time: matches.value_of("move-time").map(|v| parse_move_time(v).unwrap()),
+        thoughtful: matches.is_present("thoughtful"),
+        .. preset
+    };
+    let options = KlondikeOptions {
+        rules: rules,
+        game_count: game_count,
+        duration: duration,
+        thread_count: thread_count,
+        throttle: throttle(matches),
+        batch_size: matches.value_of("batch-size").unwrap().parse::<usize>().unwrap(),
+        reveal_stats: matches.is_present("reveal-stats"),
+        move_stats: matches.is_present("move-stats"),
+        convergence: matches.is_present("convergence") || matches.is_present("report"),
+        pattern_limit: matches.value_of("loss-patterns").map(|v| v.parse::<usize>().unwrap()),
+        training_path: matches.value_of("export-training").map(String::from),
+        deal_pool: matches.value_of("deal-pool").map(String::from),
+        deals_from: matches.value_of("deals-from").map(String::from),
+        first_deal: matches.value_of("deal").map(|v| v.parse::<u32>().unwrap()),
+        record_losses: matches.value_of("record-losses").map(String::from),
+        dump_losses: matches.value_of("dump-losses").map(String::from),
+        midgame: matches.value_of("midgame").map(|v| {
+            let (min, max) = parse_move_range(v).unwrap();
+            MidGameSampler::new(rules, min, max)
+        }),
+        position: matches.value_of("position").map(String::from),
+        trace_count: matches.value_of("trace").map_or(0, |v| v.parse::<usize>().unwrap()),
+        seed: master_seed(matches),
+        shuffle_rng: matches.value_of("rng").unwrap().parse::<ShuffleRng>().unwrap(),
+    };
+
+    let strategies = klondike_strategies(matches);
+    let names: Vec<String> = strategies.iter().map(|&(ref name, _)| name.clone()).collect();
+    let mut manifest = start_manifest("solitaire:klondike", matches, &options.to_json());
+    let results = run_klondike::<AllFilter>(strategies, options);
+    finish_manifest(&mut manifest, matches);
+
+    if let Some(path) = matches.value_of("convergence") {
+        let mut out = BufWriter::new(File::create(path).expect("failed to create convergence file"));
+        write_convergence(&mut out, &names, &results).expect("failed to write convergence file");
+    }
+
+    if let Some(path) = matches.value_of("report") {
+        let mut out = BufWriter::new(File::create(path).expect("failed to create report file"));
+        klondike_report(&names, &results, &manifest).write(&mut out).expect("failed to write report");
+    }
+}
+
+/// Parse the simulated seconds a move takes, a positive decimal number.
+fn parse_move_time(v: &str) -> Result<Duration, String> {
+    match v.parse::<f64>() {
+        Ok(seconds) if seconds > 0.0 && seconds < 3600.0 => Ok(Duration::from_millis((seconds * 1000.0).round() as u64)),
+        _ => Err(String::from("move time must be a positive number of seconds below 3600")),
+    }
+}
+
+/// Parse a duration such as `90s`, `10m` or `2h`. A number without a unit is
+/// in seconds.
+fn parse_duration(v: &str) -> Result<Duration, String> {
+    let split = v.find(|c: char| ! c.is_digit(10)).unwrap_or(v.len());
+    let (number, unit) = v.split_at(split);
+
+    let number = match number.parse::<u64>() {
+        Ok(n) if n > 0 => n,
+        _ => { return Err(String::from("duration must be a positive number with an optional unit: s, m or h")); },
+    };
+
+    match unit {
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number * 60)),
+        "h" => Ok(Duration::from_secs(number * 60 * 60)),
+        _ => Err(format!("unknown duration unit {}, expected s, m or h", unit)),
+    }
+}
+
+/// Parse a number of moves such as `30`, or a range such as `10-60`.
+fn parse_move_range(v: &str) -> Result<(usize, usize), String> {
+    let mut parts = v.splitn(2, '-');
+    let min = parts.next().unwrap().parse::<usize>();
+    let max = parts.next().map_or(min.clone(), |m| m.parse::<usize>());
+
+    match (min, max) {
+        (Ok(min), Ok(max)) if min <= max => Ok((min, max)),
+        _ => Err(String::from("moves must be a number or a range MIN-MAX")),
+    }
+}
+
+/// Settings for a run of klondike games.
+#[derive(Debug)]
+struct KlondikeOptions {
+    rules: KlondikeRules,
+    /// Number of games to play, `None` to play until the duration has passed.
+    game_count: Option<usize>,
+    duration: Option<Duration>,
+    thread_count: usize,
+    throttle: Option<Throttle>,
+    /// Number of consecutive games in each batch of the win rate variance.
+    batch_size: usize,
+    reveal_stats: bool,
+    move_stats: bool,
+    /// Keep the outcome of every game for the win rate convergence series.
+    convergence: bool,
+    pattern_limit: Option<usize>,
+    training_path: Option<String>,
+    /// Deal archive to play instead of random deals.
+    deal_pool: Option<String>,
+    /// Deal file to play instead of random deals, `-` for stdin.
+    deals_from: Option<String>,
+    /// Number of the first of the consecutively numbered deals to play
+    /// instead of random deals, see `cards::french::numbered_deal`.
+    first_deal: Option<u32>,
+    /// Deal archive the deals lost by every strategy are appended to.
+    record_losses: Option<String>,
+    /// File the final positions of lost games are written to as JSON lines.
+    dump_losses: Option<String>,
+    /// Play from random mid-game positions of each deal instead of the deal.
+    midgame: Option<MidGameSampler>,
+    /// Layout file of the position every game starts from, `-` for stdin.
+    position: Option<String>,
+    trace_count: usize,
+    /// Seed every shuffle and strategy choice of the run derives from.
+    seed: u64,
+    /// Generator the deals are shuffled with.
+    shuffle_rng: ShuffleRng,
+}
+
+impl KlondikeOptions {
+    /// The options as a JSON object for the run manifest, with the seed as a
+    /// string as it may not fit a JSON number.
+    fn to_json(&self) -> json::Value {
+        let seconds = |d: Duration| json::Value::Number(d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9);
+        let midgame = self.midgame.map_or(json::Value::Null, |m| json::Value::Object(vec![
+            (String::from("min_moves"), json_number(m.min_moves())),
+            (String::from("max_moves"), json_number(m.max_moves())),
+        ]));
+
+        json::Value::Object(vec![
+            (String::from("rules"), self.rules.to_json()),
+            (String::from("games"), self.game_count.map_or(json::Value::Null, json_number)),
+            (String::from("duration"), self.duration.map_or(json::Value::Null, seconds)),
+            (String::from("threads"), json_number(self.thread_count)),
+            (String::from("busy_percent"), self.throttle.map_or(json::Value::Null, |t| json_number(t.busy_percent() as usize))),
+            (String::from("batch_size"), json_number(self.batch_size)),
+            (String::from("reveal_stats"), json::Value::Bool(self.reveal_stats)),
+            (String::from("move_stats"), json::Value::Bool(self.move_stats)),
+            (String::from("convergence"), json::Value::Bool(self.convergence)),
+            (String::from("pattern_limit"), self.pattern_limit.map_or(json::Value::Null, json_number)),
+            (String::from("training"), json_string(&self.training_path)),
+            (String::from("deal_pool"), json_string(&self.deal_pool)),
+            (String::from("deals_from"), json_string(&self.deals_from)),
+            (String::from("first_deal"), self.first_deal.map_or(json::Value::Null, |d| json_number(d as usize))),
+            (String::from("record_losses"), json_string(&self.record_losses)),
+            (String::from("dump_losses"), json_string(&self.dump_losses)),
+            (String::from("midgame"), midgame),
+            (String::from("position"), json_string(&self.position)),
+            (String::from("trace_count"), json_number(self.trace_count)),
+            (String::from("seed"), json::Value::String(self.seed.to_string())),
+            (String::from("shuffle_rng"), json::Value::String(String::from(self.shuffle_rng.name()))),
+        ])
+    }
+}
+
+fn json_number(n: usize) -> json::Value {
+    json::Value::Number(n as f64)
+}
+
+fn json_string(s: &Option<String>) -> json::Value {
+    s.as_ref().map_or(json::Value::Null, |s| json::Value::String(s.clone()))
+}
+
+/// Strategy selected by the `--strategy`, `--policy-command`, `--weights`
+/// and `--move-budget` arguments.
+pub fn klondike_strategy(matches: &ArgMatches) -> StrategyFactory {
+    with_move_budget(configured_strategy(matches.value_of("strategy").unwrap(), matches), move_budget(matches))
+}
+
+/// Strategies selected by a comma separated `--strategy` list, with their names.
+pub fn klondike_strategies(matches: &ArgMatches) -> Vec<(String, StrategyFactory)> {
+    matches.value_of("strategy").unwrap()
+        .split(',')
+        .map(|name| (name.to_string(), with_move_budget(configured_strategy(name, matches), move_budget(matches))))
+        .collect()
+}
+
+/// Strategy `name` with the options of `matches` that configure it.
+fn configured_strategy(name: &str, matches: &ArgMatches) -> StrategyFactory {
+    match (name, matches.value_of("weights")) {
+        ("weighted", Some(v)) => {
+            let weights = load_weights(v).unwrap();
+            Arc::new(move || Box::new(WeightedStrategy::with_weights(weights)) as Box<dyn GameStrategy>)
+        },
+        _ => strategy_factory(name, matches.value_of("policy-command")),
+    }
+}
+
+/// Weights given as `name=value` pairs, or the path of a file of them.
+pub fn load_weights(v: &str) -> Result<Weights, String> {
+    if Path::new(v).is_file() {
+        let mut text = String::new();
+        File::open(v).and_then(|mut f| f.read_to_string(&mut text)).map_err(|e| format!("{}: {}", v, e))?;
+        text.parse::<Weights>().map_err(|e| format!("{}: {}", v, e))
+    } else {
+        v.parse::<Weights>()
+    }
+}
+
+pub fn strategy_factory(name: &str, policy_command: Option<&str>) -> StrategyFactory {
+    match (name, policy_command) {
+        ("mcts", Some(command)) => {
+            let command = command.to_string();
+            Arc::new(move || {
+                let policy = ProcessPolicy::spawn(&command).expect("failed to start policy command");
+                Box::new(DeterminizedMctsStrategy::with_policy(MctsConfig::default(), Box::new(policy))) as Box<dyn GameStrategy>
+            })
+        },
+        ("mcts", None) => Arc::new(|| Box::new(DeterminizedMctsStrategy::new()) as Box<dyn GameStrategy>),
+        ("simple-plus", _) => Arc::new(|| Box::new(SimplePlusStrategy::new()) as Box<dyn GameStrategy>),
+        ("greedy", _) => Arc::new(|| Box::new(GreedyStrategy::new()) as Box<dyn GameStrategy>),
+        ("lookahead", _) => Arc::new(|| Box::new(LookaheadStrategy::new()) as Box<dyn GameStrategy>),
+        ("solver", _) => Arc::new(|| Box::new(SolverStrategy::new()) as Box<dyn GameStrategy>),
+        ("weighted", _) => Arc::new(|| Box::new(WeightedStrategy::new()) as Box<dyn GameStrategy>),
+        _ => Arc::new(|| Box::new(SimpleKlondikeStrategy::new()) as Box<dyn GameStrategy>),
+    }
+}
+
+/// Factory of the strategies of `new_strategy` with a move budget set.
+pub fn with_move_budget(new_strategy: StrategyFactory, budget: Option<Duration>) -> StrategyFactory {
+    match budget {
+        Some(budget) => Arc::new(move || {
+            let mut strategy = new_strategy();
+            strategy.set_move_budget(Some(budget));
+            strategy
+        }),
+        None => new_strategy,
+    }
+}
+
+pub static STRATEGIES: &'static [&'static str] = &["simple", "simple-plus", "mcts", "greedy", "lookahead", "solver", "weighted"];
+
+fn validate_strategies(multiple: bool, v: String) -> Result<(), String> {
+    let names: Vec<&str> = v.split(',').collect();
+
+    if ! multiple && names.len() > 1 {
+        return Err(String::from("only one strategy can be used"));
+    }
+
+    match names.iter().find(|name| ! STRATEGIES.contains(name)) {
+        Some(name) => Err(format!("unknown strategy {}, expected one of {}", name, STRATEGIES.join(", "))),
+        None => Ok(()),
+    }
+}
+
+/// `--strategy` and `--policy-command` arguments. With `multiple` the
+/// strategy may be a comma separated list.
+pub fn strategy_args<'a, 'b>(multiple: bool) -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("strategy")
+            .long("strategy")
+            .takes_value(true)
+            .default_value("simple")
+            .use_delimiter(false)
+            .validator(move |v| validate_strategies(multiple, v))
+            .help(if multiple {
+                "Strategies used to play each game, comma separated: simple, simple-plus, mcts, greedy, lookahead, solver, weighted"
+            } else {
+                "Strategy used to play each game: simple, simple-plus, mcts, greedy, lookahead, solver, weighted"
+            }),
+        Arg::with_name("policy-command")
+            .long("policy-command")
+            .takes_value(true)
+            .help("Command that answers move priors and values for the mcts strategy"),
+        Arg::with_name("weights")
+            .long("weights")
+            .takes_value(true)
+            .validator(|v| load_weights(&v).map(|_| ()))
+            .help("Weights of the weighted strategy, as name=value pairs separated by commas or a file of name = value lines"),
+    ]
+}
+
+/// Outcome of the games played by one strategy.
+struct StrategyResults {
+    wins: usize,
+    games: usize,
+    score: i64,
+    patterns: LossPatterns,
+    /// Moves and passes through the deck of each won game.
+    win_moves: Histogram,
+    win_passes: Histogram,
+    streaks: Streaks,
+    reveals: RevealStats,
+    moves: MoveStats,
+    convergence: Convergence,
+}
+
+impl StrategyResults {
+    fn new(batch_size: usize) -> StrategyResults {
+        StrategyResults {
+            wins: 0,
+            games: 0,
+            score: 0,
+            patterns: LossPatterns::new(),
+            win_moves: Histogram::new(),
+            win_passes: Histogram::new(),
+            streaks: Streaks::new(batch_size),
+            reveals: RevealStats::new(),
+            moves: MoveStats::new(),
+            convergence: Convergence::new(),
+        }
+    }
+
+    /// Add the results of another worker. Workers are merged in the order
+    /// they were started and every statistic is either a count or kept by
+    /// deal number, so the totals do not depend on how the games were split.
+    fn merge(&mut self, other: StrategyResults) {
+        self.wins += other.wins;
+        self.games += other.games;
+        self.score += other.score;
+        self.patterns.merge(other.patterns);
+        self.win_moves.merge(&other.win_moves);
+        self.win_passes.merge(&other.win_passes);
+        self.streaks.merge(other.streaks);
+        self.reveals.merge(&other.reveals);
+        self.moves.merge(&other.moves);
+        self.convergence.merge(&other.convergence);
+    }
+
+    fn average_score(&self) -> f64 {
+        self.score as f64 / cmp::max(self.games, 1) as f64
+    }
+}
+
+/// What the threads of a `run_klondike` share.
+struct KlondikeRun {
+    options: KlondikeOptions,
+    strategies: Vec<(String, StrategyFactory)>,
+    /// Games started so far, for the progress report.
+    games: AtomicUsize,
+    traced: AtomicUsize,
+    live_wins: Vec<AtomicUsize>,
+    gate: PauseGate,
+    pool: Option<DealArchive>,
+    deals: Option<Vec<Deal>>,
+    position: Option<KlondikeSolitaireGame>,
+    training: Option<Mutex<LogWriter>>,
+    losses: Option<Mutex<DealArchiveWriter>>,
+    dumps: Option<Mutex<BufWriter<File>>>,
+}
+
+/// Plays the games of one thread of a `run_klondike` with every strategy.
+struct KlondikeWorker<F> {
+    run: Arc<KlondikeRun>,
+    rng: Isaac64Rng,
+    throttle: Option<Throttle>,
+    players: Vec<Box<dyn GameStrategy>>,
+    results: Vec<StrategyResults>,
+    filter: F,
+}
+
+impl<F: GameFilter> KlondikeWorker<F> {
+    fn new(run: Arc<KlondikeRun>) -> KlondikeWorker<F> {
+        KlondikeWorker {
+            rng: Isaac64Rng::from_seed(&[run.options.seed]),
+            throttle: run.options.throttle,
+            players: run.strategies.iter().map(|&(_, ref new_strategy)| new_strategy()).collect(),
+            results: run.strategies.iter().map(|_| StrategyResults::new(run.options.batch_size)).collect(),
+            filter: F::new(),
+            run: run,
+        }
+    }
+
+    /// Deal of game `g`, with the seed it was shuffled from if it was.
+    fn deal(&mut self, g: usize) -> (Option<u64>, KlondikeSolitaireGame) {
+        let run = &self.run;
+        let rules = run.options.rules;
+
+        if let Some(ref deals) = run.deals {
+            return (None, deals[g].game(rules));
+        }
+
+        if let Some(first) = run.options.first_deal {
+            return (None, KlondikeSolitaireGame::with_deal(rules, french::numbered_deal(first + g as u32)));
+        }
+
+        // The face up cards are known, the face down ones are dealt at random
+        if let Some(ref position) = run.position {
+            let deck = position.deck();
+            let known = |c| deck.waste_cards().contains(&c) || deck.visible_cards().contains(&c);
+            let mut source = run.options.shuffle_rng.source(self.rng.gen());
+            return (None, position.determinize(known, |c| source.shuffle(c)));
+        }
+
+        loop {
+            let seed = match run.pool {
+                Some(ref pool) => pool.entries()[g].seed,
+                None => self.rng.gen::<u64>(),
+            };
+            let mut game = KlondikeSolitaireGame::with_rules(rules, run.options.shuffle_rng.source(seed));
+            let mid_game = run.options.midgame.map_or(true, |m| m.advance_seeded(&mut game, seed));
+
+            // Pool deals were chosen deliberately, play them as they are
+            if run.pool.is_some() || (mid_game && self.filter.accept(&game)) {
+                return (Some(seed), game);
+            }
+        }
+    }
+}
+
+impl<F: GameFilter> Worker for KlondikeWorker<F> {
+    type Results = Vec<StrategyResults>;
+
+    fn play(&mut self, g: usize) {
+        self.run.gate.wait();
+        let started = Instant::now();
+
+        self.run.games.fetch_add(1, Ordering::Relaxed);
+        if g % 10000 == 0 {
+            println!("{} games", g);
+        }
+
+        // Each game draws from the stream of its number, so a game is the
+        // same whichever thread plays it
+        self.rng = Isaac64Rng::from_seed(&[self.run.options.seed, g as u64]);
+        for player in self.players.iter_mut() {
+            player.set_seed(self.rng.gen());
+        }
+
+        let (seed, deal) = self.deal(g);
+        let run = &self.run;
+        let trace = run.options.trace_count > 0 && run.traced.fetch_add(1, Ordering::Relaxed) < run.options.trace_count;
+        let mut won_any = false;
+
+        for (s, strategy) in self.players.iter_mut().enumerate() {
+            let mut game = deal.clone();
+            if run.training.is_some() || trace {
+                game.record_moves();
+            }
+
+            strategy.play(&mut game);
+
+            let result = &mut self.results[s];
+            result.games += 1;
+            result.score += game.score() as i64;
+            result.streaks.record(g, game.is_clear());
+            result.reveals.record(&game);
+            result.moves.record(&game);
+            if run.options.convergence {
+                result.convergence.record(g, game.is_clear());
+            }
+
+            if game.is_clear() {
+                won_any = true;
+                result.wins += 1;
+                result.win_moves.record(game.moves_made());
+                result.win_passes.record(game.deck().passes());
+                run.live_wins[s].fetch_add(1, Ordering::Relaxed);
+            } else if run.options.pattern_limit.is_some() {
+                result.patterns.record(&game);
+            }
+
+            if let (false, Some(ref dumps)) = (game.is_clear(), &run.dumps) {
+                let line = json::Value::Object(vec![
+                    (String::from("seed"), seed.map_or(json::Value::Null, |s| json::Value::String(s.to_string()))),
+                    (String::from("strategy"), json::Value::String(run.strategies[s].0.clone())),
+                    (String::from("game"), game.to_json()),
+                ]);
+                writeln!(dumps.lock().unwrap(), "{}", line).expect("failed to write lost position");
+            }
+
+            if trace {
+                print_trace(g, &run.strategies[s].0, &deal, game.history().unwrap());
+            }
+
+            if let Some(ref training) = run.training {
+                let mut rows = Vec::new();
+                training::write_game(&mut rows, g as u64, &deal, &game.recorded_moves().unwrap()).unwrap();
+                training.lock().unwrap().write_all(&rows).expect("failed to write training data");
+            }
+        }
+
+        if let (false, Some(seed), Some(ref losses)) = (won_any, seed, &run.losses) {
+            losses.lock().unwrap().append(seed).expect("failed to write losing deal archive");
+        }
+
+        if let Some(ref mut throttle) = self.throttle {
+            throttle.pause(started);
+        }
+    }
+
+    fn finish(self) -> Vec<StrategyResults> {
+        self.run.gate.finish();
+        self.results
+    }
+}
+
+/// Play every deal with each of `strategies`, so all of them are compared on
+/// the same deals. Returns the results of each strategy, in order.
+fn run_klondike<F: GameFilter + 'static>(strategies: Vec<(String, StrategyFactory)>, options: KlondikeOptions) -> Vec<StrategyResults> {
+    let start = Instant::now();
+    let deadline = options.duration.map(|d| start + d);
+    let pool = options.deal_pool.as_ref().map(|path| DealArchive::open(path).expect("failed to read deal pool"));
+    let deals = options.deals_from.as_ref().map(|path| read_deal_file(path));
+    let position = options.position.as_ref().map(|path| read_position(path));
+    let fixed_count = pool.as_ref().map(|p| p.len()).or(deals.as_ref().map(|d| d.len()));
+    let game_count = match (fixed_count, options.game_count) {
+        (Some(fixed), Some(n)) => Some(cmp::min(n, fixed)),
+        (Some(fixed), None) => Some(fixed),
+        (None, n) => n,
+    };
+
+    let training = options.training_path.as_ref().map(|path| {
+        let mut out = LogWriter::create(path).expect("failed to create training data file");
+        training::write_header(&mut out).unwrap();
+        Mutex::new(out)
+    });
+
+    let losses = options.record_losses.as_ref().map(|path| {
+        Mutex::new(DealArchiveWriter::open(path).expect("failed to open losing deal archive"))
+    });
+
+    let dumps = options.dump_losses.as_ref().map(|path| {
+        Mutex::new(BufWriter::new(File::create(path).expect("failed to create lost position file")))
+    });
+
+    let thread_count = options.thread_count;
+    let run = Arc::new(KlondikeRun {
+        live_wins: strategies.iter().map(|_| AtomicUsize::new(0)).collect(),
+        gate: PauseGate::new(thread_count),
+        options: options,
+        strategies: strategies,
+        games: AtomicUsize::new(0),
+        traced: AtomicUsize::new(0),
+        pool: pool,
+        deals: deals,
+        position: position,
+        training: training,
+        losses: losses,
+        dumps: dumps,
+    });
+    let done = Arc::new(AtomicBool::new(false));
+
+    let monitor = {
+        let run = run.clone();
+        let done = done.clone();
+
+        status::install();
+        thread::spawn(move || {
+            while ! done.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(200));
+
+                if status::requested() {
+                    print_status(start, deadline, game_count, run.games.load(Ordering::SeqCst), &run.strategies, &run.live_wins);
+                }
+
+                if status::pause_requested() {
+                    run.gate.pause();
+                    print_status(start, deadline, game_count, run.games.load(Ordering::SeqCst), &run.strategies, &run.live_wins);
+                    println!("paused, continue with fg or kill -CONT {}", process::id());
+                    io::stdout().flush().unwrap();
+
+                    status::stop_process();
+                    println!("resumed");
+                    run.gate.resume();
+                }
+            }
+        })
+    };
+
+    let workers = {
+        let run = run.clone();
+        runner::run_games(thread_count, game_count, deadline, &CancellationToken::new(), move || KlondikeWorker::<F>::new(run.clone()))
+    };
+
+    let mut results: Vec<StrategyResults> = run.strategies.iter().map(|_| StrategyResults::new(run.options.batch_size)).collect();
+
+    for worker in workers {
+        for (total, r) in results.iter_mut().zip(worker) {
+            total.merge(r);
+        }
+    }
+
+    done.store(true, Ordering::SeqCst);
+    monitor.join().unwrap();
+
+    let run = Arc::try_unwrap(run).ok().expect("the run is still in use");
+    let strategies = run.strategies;
+    let options = run.options;
+    let pattern_limit = options.pattern_limit;
+
+    if let Some(training) = run.training {
+        training.into_inner().unwrap().finish().expect("failed to write training data");
+    }
+
+    if let Some(dumps) = run.dumps {
+        dumps.into_inner().unwrap().flush().expect("failed to write lost position");
+    }
+
+    if let Some(losses) = run.losses {
+        let mut losses = losses.into_inner().unwrap();
+        losses.flush().expect("failed to write losing deal archive");
+        println!("{} losing deals in {}", losses.archive().len(), options.record_losses.as_ref().unwrap());
+    }
+
+    let seconds = seconds(start.elapsed());
+    println!("{} games in {:.1}s, {:.1} games/s", results[0].games, seconds, results[0].games as f64 / seconds);
+
+    if results.len() == 1 {
+        println!("{}/{} wins", results[0].wins, results[0].games);
+        println!("{:.2} average score", results[0].average_score());
+    } else {
+        print_strategy_table(&strategies, &results);
+    }
+
+    for (&(ref name, _), result) in strategies.iter().zip(&results) {
+        if ! result.win_moves.is_empty() {
+            let prefix = if results.len() > 1 { format!("{} ", name) } else { String::new() };
+            println!("{}wins: moves {}, deck passes {}", prefix, distribution(&result.win_moves), distribution(&result.win_passes));
+        }
+    }
+
+    for (&(ref name, _), result) in strategies.iter().zip(&results) {
+        let prefix = if results.len() > 1 { format!("{} ", name) } else { String::new() };
+        let streaks = &result.streaks;
+        print!("{}streaks: longest win {}, longest loss {}", prefix, streaks.longest_win(), streaks.longest_loss());
+
+        match streaks.batch_variance() {
+            Some((mean, variance)) => println!("; win rate per {} games: mean {:.2}%, std dev {:.2}% over {} batches",
+                                               streaks.batch_size(), 100.0 * mean, 100.0 * variance.sqrt(), streaks.batch_rates().len()),
+            None => println!(),
+        }
+    }
+
+    if options.reveal_stats {
+        print_reveal_stats(&strategies, &results);
+    }
+
+    if options.move_stats {
+        print_move_stats(&strategies, &results);
+    }
+
+    if let Some(limit) = pattern_limit {
+        for (&(ref name, _), result) in strategies.iter().zip(&results) {
+            if results.len() > 1 {
+                println!("{}:", name);
+            }
+
+            print_loss_patterns(&result.patterns, limit);
+        }
+    }
+
+    return results;
+}
+
+/// Progress report of a running `run_klondike`, see `status`.
+fn print_status(start: Instant, deadline: Option<Instant>, game_count: Option<usize>, games: usize,
+                strategies: &[(String, StrategyFactory)], wins: &[AtomicUsize]) {
+    let elapsed = start.elapsed();
+    let seconds = seconds(elapsed);
+    let rate = games as f64 / seconds;
+
+    let progress = match game_count {
+        Some(n) => format!("{}/{} games ({:.2}%)", games, n, 100.0 * games as f64 / n as f64),
+        None => format!("{} games", games),
+    };
+
+    let eta = match (game_count, deadline) {
+        (Some(n), _) => status::eta(n.saturating_sub(games), rate),
+        (None, Some(d)) => Some(d.saturating_duration_since(Instant::now())),
+        (None, None) => None,
+    };
+
+    let win_rates: Vec<String> = strategies.iter().zip(wins).map(|(&(ref name, _), w)| {
+        format!("{} {:.2}% wins", name, 100.0 * w.load(Ordering::Relaxed) as f64 / cmp::max(games, 1) as f64)
+    }).collect();
+
+    println!("status: {}, {}, {:.1} games/s, elapsed {}, ETA {}",
+             progress, win_rates.join(", "), rate,
+             status::format_duration(elapsed),
+             eta.map_or(String::from("unknown"), status::format_duration));
+}
+
+fn print_reveal_stats(strategies: &[(String, StrategyFactory)], results: &[StrategyResults]) {
+    for (&(ref name, _), result) in strategies.iter().zip(results) {
+        if results.len() > 1 {
+            println!("{}:", name);
+        }
+
+        println!("Piles fully revealed:");
+        println!("  {:>4}  {:>8}  {:>8}", "pile", "games", "avg move");
+
+        for pile in 0..7 {
+            let average = result.reveals.average_reveal_move(pile).map_or(String::from("-"), |m| format!("{:.1}", m));
+            println!("  {:>4}  {:>7.2}%  {:>8}", pile, 100.0 * result.reveals.reveal_rate(pile), average);
+        }
+    }
+}
+
+/// Average moves of each kind and game stats in won and lost games. The ratio
+/// is above 1 for what wins make more of.
+fn print_move_stats(strategies: &[(String, StrategyFactory)], results: &[StrategyResults]) {
+    let row = |name: &str, won: Option<f64>, lost: Option<f64>| {
+        let ratio = match (won, lost) {
+            (Some(w), Some(l)) if l > 0.0 => format!("{:.2}", w / l),
+            _ => String::from("-"),
+        };
+
+        println!("  {:<16}  {:>8}  {:>8}  {:>6}", name,
+                 won.map_or(String::from("-"), |w| format!("{:.1}", w)),
+                 lost.map_or(String::from("-"), |l| format!("{:.1}", l)),
+                 ratio);
+    };
+
+    for (&(ref name, _), result) in strategies.iter().zip(results) {
+        if results.len() > 1 {
+            println!("{}:", name);
+        }
+
+        let moves = &result.moves;
+        println!("Moves per game ({} wins, {} losses):", moves.wins(), moves.losses());
+        println!("  {:<16}  {:>8}  {:>8}  {:>6}", "move", "won", "lost", "ratio");
+
+        for kind in MoveKind::all() {
+            row(&kind.to_string(), moves.average_won(*kind), moves.average_lost(*kind));
+        }
+
+        let (won, lost) = (moves.won_stats(), moves.lost_stats());
+        let average = |total: usize, games: usize| if games > 0 { Some(total as f64 / games as f64) } else { None };
+
+        for &(name, w, l) in [
+            ("moves", won.moves, lost.moves),
+            ("draws", won.draws, lost.draws),
+            ("passes", won.passes, lost.passes),
+            ("cards revealed", won.cards_revealed, lost.cards_revealed),
+            ("cards founded", won.cards_founded, lost.cards_founded),
+        ].iter() {
+            row(name, average(w, moves.wins()), average(l, moves.losses()));
+        }
+    }
+}
+
+fn print_strategy_table(strategies: &[(String, StrategyFactory)], results: &[StrategyResults]) {
+    let width = strategies.iter().map(|&(ref name, _)| name.len()).max().unwrap_or(0).max(8);
+
+    println!("{:<width$}  {:>8}  {:>8}  {:>8}  {:>10}", "strategy", "games", "wins", "win %", "avg score", width = width);
+
+    for (&(ref name, _), result) in strategies.iter().zip(results) {
+        println!("{:<width$}  {:>8}  {:>8}  {:>7.2}%  {:>10.2}",
+                 name, result.games, result.wins,
+                 100.0 * result.wins as f64 / cmp::max(result.games, 1) as f64,
+                 result.average_score(),
+                 width = width);
+    }
+}
+
+/// About 1000 points over all of the games played.
+fn convergence_step(result: &StrategyResults) -> usize {
+    cmp::max(result.games / 1000, 1)
+}
+
+/// Cumulative win rate of each strategy as CSV: `strategy,games,wins,win_rate,margin`
+/// where `margin` is the half width of the 95% confidence interval.
+fn write_convergence<W: Write>(out: &mut W, names: &[String], results: &[StrategyResults]) -> io::Result<()> {
+    writeln!(out, "strategy,games,wins,win_rate,margin")?;
+
+    for (name, result) in names.iter().zip(results) {
+        for p in result.convergence.series(convergence_step(result)) {
+            writeln!(out, "{},{},{},{:.6},{:.6}", name, p.games, p.wins, p.win_rate(), p.margin)?;
+        }
+    }
+
+    out.flush()
+}
+
+/// Everything `run_klondike` reports, plus the run's manifest, as a single
+/// HTML page.
+fn klondike_report(names: &[String], results: &[StrategyResults], manifest: &Manifest) -> HtmlReport {
+    let mut report = HtmlReport::new("Klondike results");
+    let optional = |v: Option<f64>| v.map_or(String::from("-"), |v| format!("{:.1}", v));
+
+    report.heading("Summary");
+    report.table(&["strategy", "games", "wins", "win %", "avg score", "longest win", "longest loss", "batch win % std dev"],
+                 &names.iter().zip(results).map(|(name, result)| vec![
+                     name.clone(),
+                     result.games.to_string(),
+                     result.wins.to_string(),
+                     format!("{:.2}", 100.0 * result.wins as f64 / cmp::max(result.games, 1) as f64),
+                     format!("{:.2}", result.average_score()),
+                     result.streaks.longest_win().to_string(),
+                     result.streaks.longest_loss().to_string(),
+                     result.streaks.batch_variance().map_or(String::from("-"), |(_, v)| format!("{:.2}", 100.0 * v.sqrt())),
+                 ]).collect::<Vec<_>>());
+
+    for (name, result) in names.iter().zip(results) {
+        report.heading(name);
+
+        let points = result.convergence.series(convergence_step(result));
+        report.line_chart("Cumulative win rate with 95% confidence interval", &[
+            points.iter().map(|p| (p.games as f64, p.win_rate())).collect(),
+            points.iter().map(|p| (p.games as f64, p.win_rate() + p.margin)).collect(),
+            points.iter().map(|p| (p.games as f64, (p.win_rate() - p.margin).max(0.0))).collect(),
+        ]);
+
+        report.histogram("Moves of won games", &result.win_moves);
+        report.histogram("Deck passes of won games", &result.win_passes);
+
+        report.subheading("Piles fully revealed");
+        report.table(&["pile", "games %", "avg move"],
+                     &(0..7).map(|pile| vec![
+                         pile.to_string(),
+                         format!("{:.2}", 100.0 * result.reveals.reveal_rate(pile)),
+                         optional(result.reveals.average_reveal_move(pile)),
+                     ]).collect::<Vec<_>>());
+
+        report.subheading("Moves per game");
+        report.table(&["move", "won", "lost"],
+                     &MoveKind::all().iter().map(|kind| vec![
+                         kind.to_string(),
+                         optional(result.moves.average_won(*kind)),
+                         optional(result.moves.average_lost(*kind)),
+                     ]).collect::<Vec<_>>());
+
+        if result.patterns.losses() > 0 {
+            let losses = result.patterns.losses();
+            report.subheading("Most common losing positions");
+            report.table(&["position", "% of losses"],
+                         &result.patterns.most_common_patterns(20).iter().map(|&(pattern, count)| vec![
+                             pattern.to_string(),
+                             format!("{:.2}", 100.0 * count as f64 / losses as f64),
+                         ]).collect::<Vec<_>>());
+        }
+    }
+
+    let mut text = Vec::new();
+    manifest.write(&mut text).expect("failed to write manifest");
+    report.heading("Manifest");
+    report.preformatted(&String::from_utf8_lossy(&text));
+
+    return report;
+}
+
+/// Print every move of a game with the board before and after it, and the
+/// board laid out at the start and the end.
+fn print_trace(game_id: usize, strategy: &str, initial: &KlondikeSolitaireGame, history: &[MoveRecord]) {
+    let mut replay = Replay::new(initial.clone(), history.iter().map(|r| r.m).collect());
+    let mut out = String::new();
+
+    out.push_str(&format!("trace game {} ({}): {} moves\n", game_id, strategy, history.len()));
+    out.push_str(&indent_board(initial));
+    out.push_str(&format!("  start  {}\n", initial.notation()));
+
+    for (ply, record) in history.iter().enumerate() {
+        replay.step().unwrap().expect("recorded move is not legal");
+        out.push_str(&format!("  {:>5}  {}\n", ply, record));
+        out.push_str(&format!("  after  {}\n", replay.game().notation()));
+    }
+    let game = replay.game();
+
+    out.push_str(&format!("  result {}\n", if game.is_clear() { "won" } else { "lost" }));
+    out.push_str(&indent_board(game));
+
+    let stdout = io::stdout();
+    stdout.lock().write_all(out.as_bytes()).expect("failed to write trace");
+}
+
+/// The board of `game` as `render` lays it out, each line indented.
+fn indent_board(game: &KlondikeSolitaireGame) -> String {
+    game.render().lines().map(|line| format!("    {}\n", line)).collect()
+}
+
+fn read_deal_file(path: &str) -> Vec<Deal> {
+    let deals = if path == "-" {
+        let stdin = io::stdin();
+        let input = stdin.lock();
+        deals::read_deals(input)
+    } else {
+        deals::read_deals(LogReader::open(path).expect("failed to open deal file"))
+    };
+
+    deals.unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+pub fn read_position(path: &str) -> KlondikeSolitaireGame {
+    let mut text = String::new();
+
+    if path == "-" {
+        io::stdin().read_to_string(&mut text).expect("failed to read position");
+    } else {
+        LogReader::open(path).and_then(|mut f| f.read_to_string(&mut text)).expect("failed to read position file");
+    }
+
+    // A JSON position, or a line of --dump-losses with the position in `game`
+    let position = if text.trim_start().starts_with('{') {
+        json::Value::parse(&text).and_then(|v| KlondikeSolitaireGame::from_json(v.get("game").unwrap_or(&v)))
+    } else if text.trim_start().starts_with('<') {
+        kpatience::read_game(&text)
+    } else if text.trim().lines().count() == 1 && text.contains(" / ") {
+        // The board of a trace or a golden file
+        KlondikeSolitaireGame::from_notation(&text)
+    } else {
+        layout::parse_layout(&text)
+    };
+
+    position.unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+fn print_loss_patterns(patterns: &LossPatterns, limit: usize) {
+    let losses = patterns.losses();
+    let percent = |count: usize| 100.0 * count as f64 / cmp::max(losses, 1) as f64;
+
+    println!("Most common losing positions ({} losses):", losses);
+    for (pattern, count) in patterns.most_common_patterns(limit) {
+        println!("  {:6.2}%  {}", percent(count), pattern);
+    }
+
+    println!("Most common blocking cards:");
+    for (blocker, count) in patterns.most_common_blockers(limit) {
+        println!("  {:6.2}%  {}", percent(count), blocker);
+    }
+}
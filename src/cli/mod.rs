@@ -0,0 +1,192 @@
+//! Command line of the simulator. Each module defines and runs the
+//! subcommands of a family of games; the arguments, seeding and run manifests
+//! they share are here.
+
+pub mod card_games;
+pub mod casino;
+pub mod klondike;
+pub mod solitaire;
+pub mod tools;
+
+use clap::{Arg, App, ArgMatches};
+use json;
+use std::fs::File;
+use std::io;
+use std::time::{Duration, Instant};
+use analysis::histogram::Histogram;
+use manifest::Manifest;
+use throttle::Throttle;
+use self::klondike::load_weights;
+
+/// Every subcommand of the simulator.
+pub fn commands<'a, 'b>() -> Vec<App<'a, 'b>> {
+    let mut commands = klondike::commands();
+    commands.extend(solitaire::commands());
+    commands.extend(casino::commands());
+    commands.extend(card_games::commands());
+    commands.extend(tools::commands());
+    commands
+}
+
+/// Run the subcommand `name` with its arguments `matches`.
+pub fn run(name: &str, matches: &ArgMatches) {
+    let handled = klondike::run(name, matches)
+        || solitaire::run(name, matches)
+        || casino::run(name, matches)
+        || card_games::run(name, matches)
+        || tools::run(name, matches);
+
+    if ! handled {
+        panic!("Unhandled command!");
+    }
+}
+
+pub fn validate_num(name: &str, min: usize, max: usize, v: String) -> Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(v) if v >= min && v <= max => Ok(()),
+        _ if max == usize::max_value() => Err(String::from(format!("{} must be a number greater than or equal to {}", name, min))),
+        _ => Err(String::from(format!("{} must be a number between {} and {}", name, min, max))),
+    }
+}
+
+/// Game count and seed of the solitaire commands other than klondike.
+pub fn solitaire_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("games")
+            .long("games")
+            .takes_value(true)
+            .default_value("1000")
+            .validator(|v| validate_num("games", 1, usize::max_value(), v))
+            .help("Number of games to play"),
+        Arg::with_name("seed")
+            .long("seed")
+            .takes_value(true)
+            .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+            .help("Play the deals of consecutive seeds from this one instead of random deals"),
+    ]
+}
+
+/// Deal number of the commands that can play the numbered deals of
+/// `cards::numbered_shuffle`.
+pub fn deal_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("deal")
+        .long("deal")
+        .takes_value(true)
+        .value_name("N")
+        .conflicts_with("seed")
+        .validator(|v| validate_num("deal", 1, 0x7fffffff, v))
+        .help("Play the deals numbered consecutively from N, as Microsoft FreeCell numbers them, instead of seeded deals")
+}
+
+pub fn throttle_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("throttle")
+        .long("throttle")
+        .takes_value(true)
+        .value_name("PERCENT")
+        .validator(|v| validate_num("throttle", 1, 100, v))
+        .help("Keep each worker busy only PERCENT of the time by idling between games")
+}
+
+pub fn move_budget_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("move-budget")
+        .long("move-budget")
+        .takes_value(true)
+        .value_name("MILLIS")
+        .validator(|v| validate_num("move-budget", 1, usize::max_value(), v))
+        .help("Give search strategies MILLIS milliseconds of wall clock time to choose each move; mcts searches until it runs out")
+}
+
+pub fn move_budget(matches: &ArgMatches) -> Option<Duration> {
+    matches.value_of("move-budget").map(|v| Duration::from_millis(v.parse::<u64>().unwrap()))
+}
+
+pub fn manifest_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("manifest")
+        .long("manifest")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("Write the version, options and start and end times of the run to a file")
+}
+
+pub fn throttle(matches: &ArgMatches) -> Option<Throttle> {
+    matches.value_of("throttle").map(|v| Throttle::new(v.parse::<u32>().unwrap()))
+}
+
+/// Summary of a histogram: `median 120, p90 180, max 260`.
+pub fn distribution(h: &Histogram) -> String {
+    format!("median {}, p90 {}, max {}", h.median().unwrap(), h.percentile(90.0).unwrap(), h.max().unwrap())
+}
+
+/// Manifest of a run, printed before it starts. `options` are the resolved
+/// settings of the run, written as a JSON object.
+pub fn start_manifest(command: &str, matches: &ArgMatches, options: &json::Value) -> Manifest {
+    let mut manifest = Manifest::new(command);
+    if let Some(v) = matches.value_of("strategy") {
+        manifest.add("strategy", v);
+    }
+    if let Some(v) = matches.value_of("policy-command") {
+        manifest.add("policy-command", v);
+    }
+    if let Some(v) = matches.value_of("weights") {
+        manifest.add("weights", load_weights(v).unwrap().to_string());
+    }
+    manifest.add("options", options);
+
+    let stdout = io::stdout();
+    manifest.write(&mut stdout.lock()).expect("failed to write manifest");
+    return manifest;
+}
+
+/// Complete the manifest of a run and write it to the `--manifest` file, if set.
+pub fn finish_manifest(manifest: &mut Manifest, matches: &ArgMatches) {
+    manifest.finish();
+
+    if let Some(path) = matches.value_of("manifest") {
+        let mut out = File::create(path).expect("failed to create manifest file");
+        manifest.write(&mut out).expect("failed to write manifest");
+    }
+}
+
+/// Run `run` between the start and the end of the manifest of a command
+/// without its own options type. The options are the values of the
+/// arguments `names`, `true` for the flags given and null for the arguments
+/// left out, and `seed` as a string.
+pub fn with_manifest<F: FnOnce()>(command: &str, matches: &ArgMatches, names: &[&str], seed: Option<u64>, run: F) {
+    let mut options: Vec<(String, json::Value)> = names.iter().map(|&name| {
+        let value = match matches.value_of(name) {
+            Some(v) => json::Value::String(v.to_string()),
+            None if matches.is_present(name) => json::Value::Bool(true),
+            None => json::Value::Null,
+        };
+        (name.to_string(), value)
+    }).collect();
+    if let Some(seed) = seed {
+        options.push((String::from("seed"), json::Value::String(seed.to_string())));
+    }
+
+    let mut manifest = start_manifest(command, matches, &json::Value::Object(options));
+    run();
+    finish_manifest(&mut manifest, matches);
+}
+
+/// The `--seed` of a run, or a random one so that the run can still be
+/// repeated from its manifest.
+pub fn master_seed(matches: &ArgMatches) -> u64 {
+    matches.value_of("seed").map_or_else(rand::random, |v| v.parse::<u64>().unwrap())
+}
+
+/// Play `count` games, calling `play` with the index and the seed of each:
+/// the seeds count up from `first_seed` in steps of `stride`, enough for the
+/// seeds one game takes. Returns the seconds the games took.
+pub fn play_seeded<P: FnMut(usize, u64)>(count: usize, first_seed: u64, stride: u64, mut play: P) -> f64 {
+    let start = Instant::now();
+    for i in 0..count {
+        play(i, first_seed.wrapping_add((i as u64).wrapping_mul(stride)));
+    }
+    seconds(start.elapsed())
+}
+
+/// `elapsed` in seconds.
+pub fn seconds(elapsed: Duration) -> f64 {
+    elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9
+}
@@ -0,0 +1,312 @@
+//! Commands of the solitaire games other than klondike.
+
+use clap::{Arg, App, ArgMatches, SubCommand};
+use games::solitaire::SolitaireGame;
+use games::solitaire::accordion::AccordionGame;
+use games::solitaire::bakers_dozen::BakersDozenGame;
+use games::solitaire::double_klondike::{DoubleKlondikeGame, DoubleKlondikeRules};
+use games::solitaire::easthaven::EasthavenGame;
+use games::solitaire::freecell::FreeCellGame;
+use games::solitaire::golf::{GolfGame, GolfRules};
+use games::solitaire::la_belle_lucie::{LaBelleLucieGame, LaBelleLucieRules};
+use games::solitaire::pyramid::{PyramidGame, PyramidRules};
+use games::solitaire::scorpion::ScorpionGame;
+use games::solitaire::spider::{SpiderGame, SpiderRules};
+use games::solitaire::yukon::YukonGame;
+use analysis::histogram::Histogram;
+use cards::{french, seeded_shuffle};
+use strategies::solitaire::accordion::{accordion_strategy, ACCORDION_STRATEGIES};
+use strategies::solitaire::bakers_dozen::SimpleBakersDozenStrategy;
+use strategies::solitaire::double_klondike::SimpleDoubleKlondikeStrategy;
+use strategies::solitaire::easthaven::SimpleEasthavenStrategy;
+use strategies::solitaire::freecell::SimpleFreeCellStrategy;
+use strategies::solitaire::golf::SimpleGolfStrategy;
+use strategies::solitaire::la_belle_lucie::SimpleLaBelleLucieStrategy;
+use strategies::solitaire::pyramid::{Pairing, SimplePyramidStrategy, PAIRINGS};
+use strategies::solitaire::scorpion::SimpleScorpionStrategy;
+use strategies::solitaire::spider::SimpleSpiderStrategy;
+use strategies::solitaire::yukon::SimpleYukonStrategy;
+use super::{validate_num, solitaire_args, deal_arg, manifest_arg, distribution, with_manifest, master_seed, play_seeded};
+
+pub fn commands<'a, 'b>() -> Vec<App<'a, 'b>> {
+    vec![
+        SubCommand::with_name("solitaire:spider")
+            .version(crate_version!())
+            .about("Play spider solitaire with one, two or four suits")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(Arg::with_name("suits")
+                 .long("suits")
+                 .takes_value(true)
+                 .default_value("1")
+                 .possible_values(&["1", "2", "4"])
+                 .help("Number of suits in the two decks"))
+            .arg(Arg::with_name("stock-deals")
+                 .long("stock-deals")
+                 .takes_value(true)
+                 .default_value("5")
+                 .validator(|v| validate_num("stock-deals", 0, 9, v))
+                 .help("Number of times the stock deals a card to every pile; the rest of the cards are dealt to the tableau")),
+        SubCommand::with_name("solitaire:freecell")
+            .version(crate_version!())
+            .about("Play freecell with four free cells")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(deal_arg()),
+        SubCommand::with_name("solitaire:pyramid")
+            .version(crate_version!())
+            .about("Play pyramid solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(Arg::with_name("passes")
+                 .long("passes")
+                 .takes_value(true)
+                 .default_value("3")
+                 .validator(|v| validate_num("passes", 1, 255, v))
+                 .help("Number of times the stock can be gone through"))
+            .arg(Arg::with_name("pairing")
+                 .long("pairing")
+                 .takes_value(true)
+                 .default_value("uncover")
+                 .possible_values(PAIRINGS)
+                 .help("Which removal to play when there is more than one")),
+        SubCommand::with_name("solitaire:golf")
+            .version(crate_version!())
+            .about("Play golf solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(Arg::with_name("around-the-corner")
+                 .long("around-the-corner")
+                 .help("Let kings and aces follow each other")),
+        SubCommand::with_name("solitaire:yukon")
+            .version(crate_version!())
+            .about("Play yukon solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args()),
+        SubCommand::with_name("solitaire:scorpion")
+            .version(crate_version!())
+            .about("Play scorpion solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args()),
+        SubCommand::with_name("solitaire:bakers-dozen")
+            .version(crate_version!())
+            .about("Play baker's dozen solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args()),
+        SubCommand::with_name("solitaire:la-belle-lucie")
+            .version(crate_version!())
+            .about("Play la belle lucie solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(Arg::with_name("redeals")
+                 .long("redeals")
+                 .takes_value(true)
+                 .default_value("2")
+                 .validator(|v| validate_num("redeals", 0, 255, v))
+                 .help("Number of times the fans can be gathered and dealt again")),
+        SubCommand::with_name("solitaire:double-klondike")
+            .version(crate_version!())
+            .about("Play double klondike (gargantua) solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(Arg::with_name("draw")
+                 .long("draw")
+                 .takes_value(true)
+                 .default_value("1")
+                 .validator(|v| validate_num("draw", 1, 59, v))
+                 .help("Number of cards drawn from the deck at a time"))
+            .arg(Arg::with_name("passes")
+                 .long("passes")
+                 .takes_value(true)
+                 .default_value("2")
+                 .validator(|v| validate_num("passes", 1, 255, v))
+                 .help("Number of times the deck can be dealt through")),
+        SubCommand::with_name("solitaire:easthaven")
+            .version(crate_version!())
+            .about("Play easthaven solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args()),
+        SubCommand::with_name("solitaire:accordion")
+            .version(crate_version!())
+            .about("Play accordion solitaire")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(Arg::with_name("strategy")
+                 .long("strategy")
+                 .takes_value(true)
+                 .default_value("simple")
+                 .possible_values(ACCORDION_STRATEGIES)
+                 .help("Strategy used to play each game")),
+    ]
+}
+
+/// Run the command `name` with its arguments `matches`, or return false if
+/// the command is not one of this module's.
+pub fn run(name: &str, matches: &ArgMatches) -> bool {
+    match name {
+        "solitaire:spider" => spider_command(matches),
+        "solitaire:freecell" => freecell_command(matches),
+        "solitaire:pyramid" => pyramid_command(matches),
+        "solitaire:golf" => golf_command(matches),
+        "solitaire:yukon" => yukon_command(matches),
+        "solitaire:scorpion" => scorpion_command(matches),
+        "solitaire:bakers-dozen" => bakers_dozen_command(matches),
+        "solitaire:la-belle-lucie" => la_belle_lucie_command(matches),
+        "solitaire:double-klondike" => double_klondike_command(matches),
+        "solitaire:easthaven" => easthaven_command(matches),
+        "solitaire:accordion" => accordion_command(matches),
+        _ => return false,
+    }
+
+    true
+}
+
+fn spider_command(matches: &ArgMatches) {
+    let rules = SpiderRules {
+        suits: matches.value_of("suits").unwrap().parse::<u8>().unwrap(),
+        stock_deals: matches.value_of("stock-deals").unwrap().parse::<u8>().unwrap(),
+    };
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    with_manifest("solitaire:spider", matches, &["suits", "stock-deals", "games"], Some(seed), || run_spider(rules, games, seed));
+}
+
+fn freecell_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let mut strategy = SimpleFreeCellStrategy::new();
+    with_manifest("solitaire:freecell", matches, &["games", "deal"], Some(seed), || match matches.value_of("deal").map(|v| v.parse::<u64>().unwrap()) {
+        Some(deal) => run_solitaire("freecell", games, deal, |n| FreeCellGame::new(|c| *c = french::numbered_deal(n as u32)), |g| strategy.play(g)),
+        None => run_solitaire("freecell", games, seed, |seed| FreeCellGame::new(|c| seeded_shuffle(seed, c)), |g| strategy.play(g)),
+    });
+}
+
+fn pyramid_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let rules = PyramidRules { passes: matches.value_of("passes").unwrap().parse::<u8>().unwrap() };
+    let pairing = matches.value_of("pairing").unwrap().parse::<Pairing>().unwrap();
+    let mut strategy = SimplePyramidStrategy::new(pairing);
+    with_manifest("solitaire:pyramid", matches, &["games", "passes", "pairing"], Some(seed), || run_solitaire("pyramid", games, seed, |seed| PyramidGame::new(rules, |c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+fn golf_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let rules = GolfRules { around_the_corner: matches.is_present("around-the-corner") };
+    let mut strategy = SimpleGolfStrategy::new();
+    with_manifest("solitaire:golf", matches, &["games", "around-the-corner"], Some(seed), || run_solitaire("golf", games, seed, |seed| GolfGame::new(rules, |c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+fn yukon_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let mut strategy = SimpleYukonStrategy::new();
+    with_manifest("solitaire:yukon", matches, &["games"], Some(seed), || run_solitaire("yukon", games, seed, |seed| YukonGame::new(|c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+fn scorpion_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let mut strategy = SimpleScorpionStrategy::new();
+    with_manifest("solitaire:scorpion", matches, &["games"], Some(seed), || run_solitaire("scorpion", games, seed, |seed| ScorpionGame::new(|c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+fn bakers_dozen_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let mut strategy = SimpleBakersDozenStrategy::new();
+    with_manifest("solitaire:bakers-dozen", matches, &["games"], Some(seed), || run_solitaire("baker's dozen", games, seed, |seed| BakersDozenGame::new(|c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+fn la_belle_lucie_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let rules = LaBelleLucieRules { redeals: matches.value_of("redeals").unwrap().parse::<usize>().unwrap() };
+    let mut strategy = SimpleLaBelleLucieStrategy::new();
+    with_manifest("solitaire:la-belle-lucie", matches, &["games", "redeals"], Some(seed), || run_solitaire("la belle lucie", games, seed, |seed| LaBelleLucieGame::new(rules, |c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+fn double_klondike_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let rules = DoubleKlondikeRules {
+        draw_count: matches.value_of("draw").unwrap().parse::<u8>().unwrap(),
+        pass_limit: Some(matches.value_of("passes").unwrap().parse::<usize>().unwrap()),
+    };
+    let mut strategy = SimpleDoubleKlondikeStrategy::new();
+    with_manifest("solitaire:double-klondike", matches, &["games", "draw", "passes"], Some(seed), || run_solitaire("double klondike", games, seed, |seed| DoubleKlondikeGame::new(rules, |c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+fn easthaven_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let mut strategy = SimpleEasthavenStrategy::new();
+    with_manifest("solitaire:easthaven", matches, &["games"], Some(seed), || run_solitaire("easthaven", games, seed, |seed| EasthavenGame::new(|c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+fn accordion_command(matches: &ArgMatches) {
+    let games = matches.value_of("games").unwrap().parse::<usize>().unwrap();
+    let seed = master_seed(matches);
+    let mut strategy = accordion_strategy(matches.value_of("strategy").unwrap()).unwrap();
+    with_manifest("solitaire:accordion", matches, &["games"], Some(seed), || run_solitaire("accordion", games, seed, |seed| AccordionGame::new(|c| seeded_shuffle(seed, c)), |g| strategy.play(g)));
+}
+
+/// Play `games` Spider games with the deals of consecutive seeds from
+/// `first_seed` and print the wins and completed runs.
+fn run_spider(rules: SpiderRules, games: usize, first_seed: u64) {
+    let mut strategy = SimpleSpiderStrategy::new();
+    let mut wins = 0;
+    let mut runs = Histogram::new();
+    let mut win_moves = Histogram::new();
+    let mut score = 0;
+    let seconds = play_seeded(games, first_seed, 1, |_, seed| {
+        let mut game = SpiderGame::new(rules, |c| seeded_shuffle(seed, c));
+        strategy.play(&mut game);
+
+        runs.record(game.completed().len());
+        score += game.score() as i64;
+
+        if game.is_clear() {
+            wins += 1;
+            win_moves.record(game.moves_made());
+        }
+    });
+
+    println!("{} games of {} suit spider in {:.1}s, {:.1} games/s", games, rules.suits, seconds, games as f64 / seconds);
+    println!("{}/{} wins", wins, games);
+    println!("{:.2} average score", score as f64 / games as f64);
+    println!("completed runs: mean {:.2}, {}", runs.mean().unwrap(), distribution(&runs));
+
+    if wins > 0 {
+        println!("wins: moves {}", distribution(&win_moves));
+    }
+}
+
+/// Play `games` games dealt by `deal` from the seeds counting up from
+/// `first_seed` and print the wins and scores.
+fn run_solitaire<G, D, P>(name: &str, games: usize, first_seed: u64, deal: D, mut play: P)
+    where G: SolitaireGame, D: Fn(u64) -> G, P: FnMut(&mut G) {
+    let mut wins = 0;
+    let mut win_moves = Histogram::new();
+    let mut score = 0;
+    let seconds = play_seeded(games, first_seed, 1, |_, seed| {
+        let mut game = deal(seed);
+        play(&mut game);
+
+        score += game.score() as i64;
+
+        if game.is_clear() {
+            wins += 1;
+            win_moves.record(game.moves_made());
+        }
+    });
+
+    println!("{} games of {} in {:.1}s, {:.1} games/s", games, name, seconds, games as f64 / seconds);
+    println!("{}/{} wins", wins, games);
+    println!("{:.2} average score", score as f64 / games as f64);
+
+    if wins > 0 {
+        println!("wins: moves {}", distribution(&win_moves));
+    }
+}
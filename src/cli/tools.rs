@@ -0,0 +1,577 @@
+//! Klondike commands other than plain runs: self-play, run log checks,
+//! benchmarks, the solver, weight tuning, golden files and experiments.
+
+use clap::{Arg, App, ArgMatches, SubCommand};
+use benchmark;
+use experiment;
+use golden;
+use selfplay;
+use tuning;
+use std::cmp;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Read};
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+use rand::{Isaac64Rng, SeedableRng};
+use games::solitaire::klondike::{KlondikeRules, KlondikeSolitaireGame};
+use analysis::comparison::Comparison;
+use benchmark::{BenchmarkScore, Difficulty};
+use golden::GoldenOutcome;
+use cards::{french, seeded_source};
+use compress::LogReader;
+use runlog::RunRecord;
+use search::solver::{Replacement, Solver, TranspositionTable, Verdict};
+use selfplay::SelfPlayOptions;
+use tuning::TuneConfig;
+use strategies::solitaire::klondike::StrategyFactory;
+use super::klondike::{klondike_strategy, klondike_strategies, strategy_factory, with_move_budget, STRATEGIES, strategy_args, read_position};
+use super::{validate_num, solitaire_args, deal_arg, throttle_arg, move_budget_arg, move_budget, manifest_arg, throttle, start_manifest, finish_manifest, with_manifest, master_seed, play_seeded, seconds};
+
+pub fn commands<'a, 'b>() -> Vec<App<'a, 'b>> {
+    vec![
+        SubCommand::with_name("selfplay")
+            .version(crate_version!())
+            .about("Generate batches of klondike training data")
+            .args(&strategy_args(false))
+            .arg(throttle_arg())
+            .arg(manifest_arg())
+            .arg(Arg::with_name("out-dir")
+                 .long("out-dir")
+                 .takes_value(true)
+                 .required(true)
+                 .help("Directory the batch files are written to"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .default_value("0")
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("Master seed all deals are derived from"))
+            .arg(Arg::with_name("first-batch")
+                 .long("first-batch")
+                 .takes_value(true)
+                 .default_value("0")
+                 .validator(|v| validate_num("first-batch", 0, usize::max_value(), v))
+                 .help("Number of the first batch, to resume a previous run"))
+            .arg(Arg::with_name("batches")
+                 .long("batches")
+                 .takes_value(true)
+                 .default_value("10")
+                 .validator(|v| validate_num("batches", 1, usize::max_value(), v))
+                 .help("Number of batches to generate"))
+            .arg(Arg::with_name("batch-size")
+                 .long("batch-size")
+                 .takes_value(true)
+                 .default_value("1000")
+                 .validator(|v| validate_num("batch-size", 1, usize::max_value(), v))
+                 .help("Number of games in each batch"))
+            .arg(Arg::with_name("concurrency")
+                 .long("concurrency")
+                 .takes_value(true)
+                 .default_value("1")
+                 .validator(|v| validate_num("concurrency", 1, usize::max_value(), v))
+                 .help("Number of concurrent games to play"))
+            .arg(Arg::with_name("run-log")
+                 .long("run-log")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .help("Append the seed, strategy and moves of every game to a run log for verify, compressed if it ends in .gz or .zst")),
+        SubCommand::with_name("verify")
+            .version(crate_version!())
+            .about("Replay a run log and check every game reproduces the recorded outcome")
+            .arg(Arg::with_name("run-log")
+                 .required(true)
+                 .value_name("FILE")
+                 .help("Run log written by selfplay --run-log"))
+            .arg(Arg::with_name("rerun-strategy")
+                 .long("rerun-strategy")
+                 .help("Also play each deal again with the recorded strategy and check it makes the same moves; only deterministic strategies reproduce")),
+        SubCommand::with_name("benchmark-suite")
+            .version(crate_version!())
+            .about("Score strategies on the built-in suite of easy, medium, hard and unwinnable deals")
+            .args(&strategy_args(true))
+            .arg(move_budget_arg())
+            .arg(Arg::with_name("repeat")
+                 .long("repeat")
+                 .takes_value(true)
+                 .default_value("1")
+                 .validator(|v| validate_num("repeat", 1, usize::max_value(), v))
+                 .help("Number of times each deal is played by each strategy")),
+        SubCommand::with_name("solve")
+            .version(crate_version!())
+            .about("Search klondike deals for a win with every card known and report how many are winnable")
+            .arg(manifest_arg())
+            .args(&solitaire_args())
+            .arg(deal_arg())
+            .arg(Arg::with_name("draw")
+                 .long("draw")
+                 .takes_value(true)
+                 .possible_values(&["1", "3"])
+                 .default_value("1")
+                 .help("Cards turned from the stock at a time"))
+            .arg(Arg::with_name("position")
+                 .long("position")
+                 .takes_value(true)
+                 .value_name("FILE")
+                 .conflicts_with_all(&["seed", "deal"])
+                 .help("Search the game of a KPatience save or other position file, - for stdin, instead of deals; its face down and stock cards are taken as known"))
+            .arg(Arg::with_name("max-nodes")
+                 .long("max-nodes")
+                 .takes_value(true)
+                 .default_value("1000000")
+                 .validator(|v| validate_num("max-nodes", 1, usize::max_value(), v))
+                 .help("Positions searched in a deal before it is counted as unknown"))
+            .arg(Arg::with_name("table-size")
+                 .long("table-size")
+                 .takes_value(true)
+                 .default_value("1048576")
+                 .validator(|v| validate_num("table-size", 1, usize::max_value(), v))
+                 .help("Positions the transposition table holds, about 64 bytes each"))
+            .arg(Arg::with_name("replacement")
+                 .long("replacement")
+                 .takes_value(true)
+                 .possible_values(&["always", "shallowest"])
+                 .default_value("always")
+                 .help("Which position a full table slot keeps: the newest, or the one fewer moves from the deal")),
+        SubCommand::with_name("tune")
+            .version(crate_version!())
+            .about("Evolve weighted strategy weights with a genetic algorithm and print the best found")
+            .arg(manifest_arg())
+            .arg(Arg::with_name("deals")
+                 .long("deals")
+                 .takes_value(true)
+                 .default_value("200")
+                 .validator(|v| validate_num("deals", 1, usize::max_value(), v))
+                 .help("Number of seeded deals every genome plays, its fitness being the share it wins"))
+            .arg(Arg::with_name("seed")
+                 .long("seed")
+                 .takes_value(true)
+                 .default_value("1")
+                 .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|_| String::from("seed must be a number")))
+                 .help("First seed of the deals, and the seed of the breeding"))
+            .arg(Arg::with_name("draw")
+                 .long("draw")
+                 .takes_value(true)
+                 .possible_values(&["1", "3"])
+                 .default_value("1")
+                 .help("Cards turned from the stock at a time"))
+            .arg(Arg::with_name("population")
+                 .long("population")
+                 .takes_value(true)
+                 .default_value("20")
+                 .validator(|v| validate_num("population", 2, usize::max_value(), v))
+                 .help("Genomes in each generation"))
+            .arg(Arg::with_name("generations")
+                 .long("generations")
+                 .takes_value(true)
+                 .default_value("10")
+                 .validator(|v| validate_num("generations", 0, usize::max_value(), v))
+                 .help("Generations bred after the first"))
+            .arg(Arg::with_name("crossover")
+                 .long("crossover")
+                 .takes_value(true)
+                 .default_value("0.7")
+                 .validator(|v| validate_rate("crossover", v))
+                 .help("Chance a child mixes the weights of both parents instead of copying one"))
+            .arg(Arg::with_name("mutation")
+                 .long("mutation")
+                 .takes_value(true)
+                 .default_value("0.2")
+                 .validator(|v| validate_rate("mutation", v))
+                 .help("Chance each weight of a child is changed"))
+            .arg(Arg::with_name("mutation-size")
+                 .long("mutation-size")
+                 .takes_value(true)
+                 .default_value("10")
+                 .validator(|v| match v.parse::<f64>() {
+                     Ok(v) if v > 0.0 => Ok(()),
+                     _ => Err(String::from("mutation-size must be a positive number")),
+                 })
+                 .help("Most a mutation adds to or takes off a weight")),
+        SubCommand::with_name("golden")
+            .version(crate_version!())
+            .about("Check that fixed seeds still play out to their stored golden outcomes")
+            .args(&strategy_args(false))
+            .arg(Arg::with_name("file")
+                 .required(true)
+                 .value_name("FILE")
+                 .help("Golden outcome file"))
+            .arg(Arg::with_name("write")
+                 .long("write")
+                 .help("Play the seeds with the strategy and write their outcomes to the file instead of checking it"))
+            .arg(Arg::with_name("games")
+                 .long("games")
+                 .takes_value(true)
+                 .default_value("100")
+                 .validator(|v| validate_num("games", 1, usize::max_value(), v))
+                 .help("Number of seeds written for each draw count, 1 and 3")),
+        SubCommand::with_name("experiments")
+            .version(crate_version!())
+            .about("Run declarative experiment matrices")
+            .subcommand(SubCommand::with_name("run")
+                        .about("Play every combination of a matrix of games, rules, strategies and filters on the same seeds")
+                        .arg(manifest_arg())
+                        .arg(strategy_args(false).pop().unwrap())
+                        .arg(move_budget_arg())
+                        .arg(Arg::with_name("matrix")
+                             .required(true)
+                             .value_name("MATRIX")
+                             .help("Matrix file, JSON or YAML"))
+                        .arg(Arg::with_name("out-dir")
+                             .long("out-dir")
+                             .takes_value(true)
+                             .default_value("experiment")
+                             .help("Directory of the per-cell results and summary.tsv"))),
+        SubCommand::with_name("diff-results")
+            .version(crate_version!())
+            .about("Compare the win rates of two run logs and list the seeds whose outcome changed")
+            .arg(Arg::with_name("before")
+                 .required(true)
+                 .value_name("BEFORE")
+                 .help("Run log of the baseline run"))
+            .arg(Arg::with_name("after")
+                 .required(true)
+                 .value_name("AFTER")
+                 .help("Run log compared to the baseline"))
+            .arg(Arg::with_name("seeds")
+                 .long("seeds")
+                 .takes_value(true)
+                 .default_value("20")
+                 .validator(|v| validate_num("seeds", 0, usize::max_value(), v))
+                 .help("Maximum number of changed seeds listed in each direction")),
+    ]
+}
+
+/// Run the command `name` with its arguments `matches`, or return false if
+/// the command is not one of this module's.
+pub fn run(name: &str, matches: &ArgMatches) -> bool {
+    match name {
+        "selfplay" => selfplay_command(matches),
+        "verify" => verify_command(matches),
+        "benchmark-suite" => benchmark_suite_command(matches),
+        "solve" => solve_command(matches),
+        "tune" => tune_command(matches),
+        "golden" => golden_command(matches),
+        "experiments" => experiments_command(matches),
+        "diff-results" => diff_results_command(matches),
+        _ => return false,
+    }
+
+    true
+}
+
+fn selfplay_command(matches: &ArgMatches) {
+    let options = SelfPlayOptions {
+        seed: matches.value_of("seed").unwrap().parse::<u64>().unwrap(),
+        first_batch: matches.value_of("first-batch").unwrap().parse::<usize>().unwrap(),
+        batches: matches.value_of("batches").unwrap().parse::<usize>().unwrap(),
+        batch_size: matches.value_of("batch-size").unwrap().parse::<usize>().unwrap(),
+        thread_count: matches.value_of("concurrency").unwrap().parse::<usize>().unwrap(),
+        throttle: throttle(matches),
+        draw_count: 1,
+        out_dir: PathBuf::from(matches.value_of("out-dir").unwrap()),
+        strategy: matches.value_of("strategy").unwrap().to_string(),
+        run_log: matches.value_of("run-log").map(PathBuf::from),
+    };
+
+    let mut manifest = start_manifest("selfplay", matches, &options.to_json());
+    selfplay::run(klondike_strategy(matches), &options, |batch, wins, path| {
+        println!("batch {}: {}/{} wins, {}", batch, wins, options.batch_size, path.display());
+    }).expect("failed to write self-play data");
+    finish_manifest(&mut manifest, matches);
+}
+
+fn verify_command(matches: &ArgMatches) {
+    if verify_run_log(matches.value_of("run-log").unwrap(), matches.is_present("rerun-strategy")) > 0 {
+        process::exit(1);
+    }
+}
+
+fn benchmark_suite_command(matches: &ArgMatches) {
+    let repeat = matches.value_of("repeat").unwrap().parse::<usize>().unwrap();
+    run_benchmark_suite(klondike_strategies(matches), repeat);
+}
+
+fn solve_command(matches: &ArgMatches) {
+    let replacement = match matches.value_of("replacement").unwrap() {
+        "shallowest" => Replacement::Shallowest,
+        _ => Replacement::Always,
+    };
+    let table = TranspositionTable::new(matches.value_of("table-size").unwrap().parse::<usize>().unwrap(), replacement);
+    let solver = Solver::with_table(matches.value_of("max-nodes").unwrap().parse::<usize>().unwrap(), table);
+
+    let names = ["draw", "games", "deal", "position", "max-nodes", "table-size", "replacement"];
+
+    if let Some(path) = matches.value_of("position") {
+        with_manifest("solve", matches, &names, None, || run_solve_position(&read_position(path), solver));
+        return;
+    }
+
+    let seed = master_seed(matches);
+    with_manifest("solve", matches, &names, Some(seed), || {
+        run_solve(matches.value_of("draw").unwrap().parse::<u8>().unwrap(),
+                  matches.value_of("games").unwrap().parse::<usize>().unwrap(),
+                  seed,
+                  matches.value_of("deal").map(|s| s.parse::<u32>().unwrap()),
+                  solver)
+    });
+}
+
+fn tune_command(matches: &ArgMatches) {
+    let config = TuneConfig {
+        population: matches.value_of("population").unwrap().parse::<usize>().unwrap(),
+        generations: matches.value_of("generations").unwrap().parse::<usize>().unwrap(),
+        crossover: matches.value_of("crossover").unwrap().parse::<f64>().unwrap(),
+        mutation: matches.value_of("mutation").unwrap().parse::<f64>().unwrap(),
+        mutation_size: matches.value_of("mutation-size").unwrap().parse::<f64>().unwrap(),
+    };
+
+    let seed = master_seed(matches);
+    let names = ["draw", "deals", "population", "generations", "crossover", "mutation", "mutation-size"];
+    with_manifest("tune", matches, &names, Some(seed), || {
+        run_tune(matches.value_of("draw").unwrap().parse::<u8>().unwrap(),
+                 matches.value_of("deals").unwrap().parse::<usize>().unwrap(),
+                 seed,
+                 &config)
+    });
+}
+
+fn golden_command(matches: &ArgMatches) {
+    let path = matches.value_of("file").unwrap();
+
+    if matches.is_present("write") {
+        let strategy = matches.value_of("strategy").unwrap();
+        let count = matches.value_of("games").unwrap().parse::<u64>().unwrap();
+        let outcomes = golden::record(count, &[1, 3], strategy, &strategy_factory(strategy, matches.value_of("policy-command")));
+
+        let mut out = BufWriter::new(File::create(path).expect("failed to create golden file"));
+        golden::write_golden(&mut out, &outcomes).expect("failed to write golden file");
+        println!("{} outcomes written", outcomes.len());
+        return;
+    }
+
+    let file = LogReader::open(path).expect("failed to open golden file");
+    let outcomes: Vec<GoldenOutcome> = golden::read_golden(file).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let drift = golden::check(&outcomes, |name| strategy_factory(name, matches.value_of("policy-command")));
+
+    for d in drift.iter() {
+        println!("{}", d);
+    }
+
+    println!("{}/{} outcomes unchanged", outcomes.len() - drift.len(), outcomes.len());
+    if ! drift.is_empty() {
+        process::exit(1);
+    }
+}
+
+fn experiments_command(matches: &ArgMatches) {
+    if let Some(matches) = matches.subcommand_matches("run") {
+        let policy_command = matches.value_of("policy-command");
+        let budget = move_budget(matches);
+        // The seeds of an experiment are in its matrix
+        with_manifest("experiments run", matches, &["matrix", "out-dir", "move-budget"], None, || {
+            run_experiment(matches.value_of("matrix").unwrap(), matches.value_of("out-dir").unwrap(),
+                           |name| with_move_budget(strategy_factory(name, policy_command), budget))
+        });
+        return;
+    }
+
+    println!("{}", matches.usage());
+    process::exit(1);
+}
+
+fn diff_results_command(matches: &ArgMatches) {
+    let before = read_run_log(matches.value_of("before").unwrap());
+    let after = read_run_log(matches.value_of("after").unwrap());
+    print_comparison(&Comparison::new(&before, &after), matches.value_of("seeds").unwrap().parse::<usize>().unwrap());
+}
+
+fn validate_rate(name: &str, v: String) -> Result<(), String> {
+    match v.parse::<f64>() {
+        Ok(v) if v >= 0.0 && v <= 1.0 => Ok(()),
+        _ => Err(format!("{} must be a number between 0 and 1", name)),
+    }
+}
+
+/// Play every deal of the built-in benchmark suite `repeat` times with each of
+/// `strategies` and print the wins by difficulty.
+fn run_benchmark_suite(strategies: Vec<(String, StrategyFactory)>, repeat: usize) {
+    println!("{:<12} {:>10} {:>10} {:>10} {:>10} {:>7}", "strategy", "easy", "medium", "hard", "unwinnable", "score");
+
+    for &(ref name, ref new_strategy) in strategies.iter() {
+        let mut strategy = new_strategy();
+        let mut score = BenchmarkScore::new();
+
+        for deal in benchmark::suite() {
+            for _ in 0..repeat {
+                let mut game = deal.game();
+                strategy.play(&mut game);
+                score.record(deal, game.is_clear());
+            }
+        }
+
+        let wins: Vec<String> = Difficulty::all().iter()
+            .map(|d| score.wins(*d))
+            .map(|(won, played)| format!("{}/{}", won, played))
+            .collect();
+        println!("{:<12} {:>10} {:>10} {:>10} {:>10} {:>6.1}%", name, wins[0], wins[1], wins[2], wins[3], score.score().unwrap_or(0.0) * 100.0);
+
+        if score.impossible_wins() > 0 {
+            println!("{} won {} unwinnable deals, the engine may allow illegal moves", name, score.impossible_wins());
+        }
+    }
+}
+
+/// Search `games` klondike deals drawing `draw_count` for a win with every
+/// card known, the numbered deals from `first_deal` or the deals of
+/// consecutive seeds from `first_seed`, and print how many are winnable.
+fn run_solve(draw_count: u8, games: usize, first_seed: u64, first_deal: Option<u32>, mut solver: Solver) {
+    let (mut winnable, mut unwinnable, mut nodes) = (0, 0, 0);
+
+    let seconds = play_seeded(games, first_seed, 1, |i, seed| {
+        let game = match first_deal {
+            Some(deal) => KlondikeSolitaireGame::with_deal(KlondikeRules::new(draw_count), french::numbered_deal(deal + i as u32)),
+            None => KlondikeSolitaireGame::new_shuffle(draw_count, seeded_source(seed)),
+        };
+
+        match solver.solve(&game) {
+            Verdict::Winnable(_) => winnable += 1,
+            Verdict::Unwinnable => unwinnable += 1,
+            Verdict::Unknown => {},
+        }
+        nodes += solver.nodes();
+    });
+
+    let unknown = games - winnable - unwinnable;
+    println!("{} deals drawing {} in {:.1}s, {:.0} nodes per deal", games, draw_count, seconds, nodes as f64 / games as f64);
+    println!("winnable {}, unwinnable {}, unknown {} after {} nodes", winnable, unwinnable, unknown, solver.max_nodes());
+    println!("{} positions pushed out of a table of {}", solver.table().evictions(), solver.table().capacity());
+    println!("{:.1}% to {:.1}% of deals winnable", winnable as f64 / games as f64 * 100.0, (winnable + unknown) as f64 / games as f64 * 100.0);
+}
+
+/// Evolve weights for the weighted strategy on the deals of `deals`
+/// consecutive seeds from `seed`, printing each generation and then the
+/// fittest weights in the form `--weights` reads.
+fn run_tune(draw_count: u8, deals: usize, seed: u64, config: &TuneConfig) {
+    let games: Vec<KlondikeSolitaireGame> = (0..deals as u64)
+        .map(|i| KlondikeSolitaireGame::new_shuffle(draw_count, seeded_source(seed + i)))
+        .collect();
+    let mut rng = Isaac64Rng::from_seed(&[seed]);
+    let start = Instant::now();
+
+    let (best, fitness) = tuning::evolve(config, &mut rng, |w| tuning::win_rate(w, &games), |g| {
+        println!("generation {}: best {:.1}%, mean {:.1}%", g.number, g.best_fitness * 100.0, g.mean_fitness * 100.0);
+    });
+
+    println!("{} generations of {} on {} deals drawing {} in {:.1}s",
+             config.generations + 1, config.population, deals, draw_count, seconds(start.elapsed()));
+    println!("best {:.1}% wins: {}", fitness * 100.0, best);
+}
+
+/// Search the game `game` for a win and print whether it is still winnable,
+/// with the winning moves if it is.
+fn run_solve_position(game: &KlondikeSolitaireGame, mut solver: Solver) {
+    println!("{}", game.notation());
+
+    match solver.solve(game) {
+        Verdict::Winnable(line) => {
+            println!("winnable in {} moves after {} nodes", line.len(), solver.nodes());
+            let moves: Vec<String> = line.iter().map(|m| m.to_string()).collect();
+            println!("{}", moves.join(" "));
+        },
+        Verdict::Unwinnable => println!("unwinnable after {} nodes", solver.nodes()),
+        Verdict::Unknown => println!("unknown after {} nodes", solver.max_nodes()),
+    }
+}
+
+/// Replay every record of a run log and report the ones that do not reproduce.
+/// Returns the number of failed records.
+fn verify_run_log(path: &str, rerun_strategy: bool) -> usize {
+    let file = LogReader::open(path).expect("failed to open run log");
+    let mut count = 0;
+    let mut failed = 0;
+
+    for (i, line) in file.lines().enumerate() {
+        let line = line.expect("failed to read run log");
+        if line.is_empty() {
+            continue;
+        }
+
+        let result = RunRecord::parse(&line).and_then(|record| {
+            record.verify()?;
+
+            if rerun_strategy {
+                record.verify_strategy(&strategy_factory(&record.strategy, None))?;
+            }
+
+            Ok(())
+        });
+
+        count += 1;
+        if let Err(e) = result {
+            failed += 1;
+            println!("line {}: {}", i + 1, e);
+        }
+    }
+
+    println!("{}/{} runs reproduced", count - failed, count);
+    return failed;
+}
+
+fn read_run_log(path: &str) -> Vec<RunRecord> {
+    let file = LogReader::open(path).expect("failed to open run log");
+
+    file.lines().enumerate()
+        .map(|(i, line)| (i, line.expect("failed to read run log")))
+        .filter(|&(_, ref line)| ! line.is_empty())
+        .map(|(i, line)| RunRecord::parse(&line).unwrap_or_else(|e| panic!("{} line {}: {}", path, i + 1, e)))
+        .collect()
+}
+
+fn print_comparison(comparison: &Comparison, seed_limit: usize) {
+    let p_value = |p: Option<f64>| p.map_or(String::from("-"), |p| format!("{:.4}", p));
+    let seeds = |seeds: &[u64]| {
+        let shown: Vec<String> = seeds.iter().take(seed_limit).map(|s| s.to_string()).collect();
+        let more = if seeds.len() > seed_limit { format!(" (+{} more)", seeds.len() - seed_limit) } else { String::new() };
+        format!("{}{}", shown.join(" "), more)
+    };
+
+    for &(label, (wins, games)) in &[("before", comparison.before()), ("after", comparison.after())] {
+        println!("{}: {}/{} wins ({:.2}%)", label, wins, games, 100.0 * wins as f64 / cmp::max(games, 1) as f64);
+    }
+
+    println!("change: {:+.2}% (p = {})", 100.0 * comparison.change(), p_value(comparison.change_p_value()));
+    println!("{} deals in both runs: {} gained, {} dropped (p = {})",
+             comparison.matched(), comparison.gained().len(), comparison.dropped().len(),
+             p_value(comparison.flip_p_value()));
+
+    if ! comparison.gained().is_empty() {
+        println!("gained: {}", seeds(comparison.gained()));
+    }
+
+    if ! comparison.dropped().is_empty() {
+        println!("dropped: {}", seeds(comparison.dropped()));
+    }
+}
+
+fn run_experiment<S>(path: &str, out_dir: &str, new_strategy: S) where S: Fn(&str) -> StrategyFactory {
+    let mut text = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut text)).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let matrix = experiment::Matrix::parse(&text)
+        .and_then(|m| m.check_strategies(STRATEGIES).map(|_| m))
+        .unwrap_or_else(|e| panic!("{}: {}", path, e));
+
+    std::fs::create_dir_all(out_dir).unwrap_or_else(|e| panic!("{}: {}", out_dir, e));
+
+    let results = experiment::run(&matrix, STRATEGIES, new_strategy, |r| {
+        let file = PathBuf::from(out_dir).join(format!("{}-{}-{}-{}.tsv", r.cell.game, r.cell.rules, r.cell.strategy, r.cell.filter));
+        let mut out = BufWriter::new(File::create(&file).expect("failed to create cell results"));
+        r.write(&mut out).expect("failed to write cell results");
+        println!("{}: {}/{} won", r.cell, r.wins(), r.games());
+    });
+
+    let mut out = BufWriter::new(File::create(PathBuf::from(out_dir).join("summary.tsv")).expect("failed to create summary"));
+    experiment::write_summary(&mut out, &results).expect("failed to write summary");
+    println!();
+    experiment::write_summary(&mut io::stdout(), &results).expect("failed to print summary");
+}
@@ -0,0 +1,228 @@
+//! Transparent compression of log files.
+//!
+//! Files ending in `.gz` or `.zst` are compressed with the system's `gzip` or
+//! `zstd` program; any other file is plain text. Both formats allow
+//! concatenated streams, so appending to a compressed log adds a new stream
+//! that decompresses as if the file had been written in one go.
+
+use std::ffi::OsStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+  None,
+  Gzip,
+  Zstd,
+}
+
+impl Compression {
+  pub fn from_path<P: AsRef<Path>>(path: P) -> Compression {
+    match path.as_ref().extension().and_then(OsStr::to_str) {
+      Some("gz") => Compression::Gzip,
+      Some("zst") => Compression::Zstd,
+      _ => Compression::None,
+    }
+  }
+
+  fn program(&self) -> &'static str {
+    match *self {
+      Compression::None => panic!("no program for uncompressed files"),
+      Compression::Gzip => "gzip",
+      Compression::Zstd => "zstd",
+    }
+  }
+}
+
+enum Sink {
+  File(BufWriter<File>),
+  Process(Child, BufWriter<ChildStdin>),
+  Finished,
+}
+
+/// Writer of a possibly compressed log file. Call `finish` to wait for the
+/// compressor and check that it succeeded; dropping the writer also waits but
+/// ignores failures.
+pub struct LogWriter {
+  sink: Sink,
+}
+
+impl LogWriter {
+  /// Create or truncate the file at `path`.
+  pub fn create<P: AsRef<Path>>(path: P) -> io::Result<LogWriter> {
+    LogWriter::new(path.as_ref(), File::create(path.as_ref())?)
+  }
+
+  /// Open the file at `path` for appending, creating it if needed.
+  pub fn append<P: AsRef<Path>>(path: P) -> io::Result<LogWriter> {
+    LogWriter::new(path.as_ref(), OpenOptions::new().create(true).append(true).open(path.as_ref())?)
+  }
+
+  fn new(path: &Path, file: File) -> io::Result<LogWriter> {
+    let sink = match Compression::from_path(path) {
+      Compression::None => Sink::File(BufWriter::new(file)),
+      compression => {
+        let mut child = Command::new(compression.program())
+          .arg("-c")
+          .stdin(Stdio::piped())
+          .stdout(Stdio::from(file))
+          .spawn()?;
+
+        let stdin = BufWriter::new(child.stdin.take().unwrap());
+        Sink::Process(child, stdin)
+      },
+    };
+
+    Ok(LogWriter { sink: sink })
+  }
+
+  pub fn finish(mut self) -> io::Result<()> {
+    self.close()
+  }
+
+  fn close(&mut self) -> io::Result<()> {
+    match ::std::mem::replace(&mut self.sink, Sink::Finished) {
+      Sink::File(mut out) => out.flush(),
+      Sink::Process(mut child, mut stdin) => {
+        stdin.flush()?;
+        drop(stdin);
+
+        let status = child.wait()?;
+        if ! status.success() {
+          return Err(io::Error::new(io::ErrorKind::Other, format!("compressor failed: {}", status)));
+        }
+
+        Ok(())
+      },
+      Sink::Finished => Ok(()),
+    }
+  }
+}
+
+impl Write for LogWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self.sink {
+      Sink::File(ref mut out) => out.write(buf),
+      Sink::Process(_, ref mut stdin) => stdin.write(buf),
+      Sink::Finished => Err(io::Error::new(io::ErrorKind::BrokenPipe, "log writer is finished")),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self.sink {
+      Sink::File(ref mut out) => out.flush(),
+      Sink::Process(_, ref mut stdin) => stdin.flush(),
+      Sink::Finished => Ok(()),
+    }
+  }
+}
+
+impl Drop for LogWriter {
+  fn drop(&mut self) {
+    let _ = self.close();
+  }
+}
+
+/// Reader of a possibly compressed log file.
+pub struct LogReader {
+  child: Option<Child>,
+  input: Box<dyn BufRead>,
+}
+
+impl LogReader {
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<LogReader> {
+    let path = path.as_ref();
+
+    match Compression::from_path(path) {
+      Compression::None => Ok(LogReader {
+        child: None,
+        input: Box::new(BufReader::new(File::open(path)?)),
+      }),
+      compression => {
+        // Fail like File::open on a missing file rather than through the program
+        File::open(path)?;
+
+        let mut child = Command::new(compression.program())
+          .arg("-dc")
+          .arg(path)
+          .stdout(Stdio::piped())
+          .spawn()?;
+
+        let stdout: ChildStdout = child.stdout.take().unwrap();
+        Ok(LogReader {
+          child: Some(child),
+          input: Box::new(BufReader::new(stdout)),
+        })
+      },
+    }
+  }
+}
+
+impl Read for LogReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    self.input.read(buf)
+  }
+}
+
+impl BufRead for LogReader {
+  fn fill_buf(&mut self) -> io::Result<&[u8]> {
+    self.input.fill_buf()
+  }
+
+  fn consume(&mut self, amount: usize) {
+    self.input.consume(amount)
+  }
+}
+
+impl Drop for LogReader {
+  fn drop(&mut self) {
+    if let Some(ref mut child) = self.child {
+      let _ = child.kill();
+      let _ = child.wait();
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::env;
+  use std::fs;
+
+  #[test]
+  fn compression_from_path() {
+    assert_eq!(Compression::from_path("runs.log.gz"), Compression::Gzip);
+    assert_eq!(Compression::from_path("runs.zst"), Compression::Zstd);
+    assert_eq!(Compression::from_path("runs.log"), Compression::None);
+    assert_eq!(Compression::from_path("gz"), Compression::None);
+  }
+
+  fn round_trip(name: &str) {
+    let path = env::temp_dir().join(format!("cardsim-compress-test-{}-{}", ::std::process::id(), name));
+    let _ = fs::remove_file(&path);
+
+    let mut out = LogWriter::create(&path).unwrap();
+    writeln!(out, "first").unwrap();
+    out.finish().unwrap();
+
+    let mut out = LogWriter::append(&path).unwrap();
+    writeln!(out, "second").unwrap();
+    out.finish().unwrap();
+
+    let lines: Vec<String> = LogReader::open(&path).unwrap().lines().map(|l| l.unwrap()).collect();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(lines, vec!["first", "second"]);
+  }
+
+  #[test]
+  fn plain_round_trip() {
+    round_trip("plain.log");
+  }
+
+  #[test]
+  fn gzip_round_trip() {
+    round_trip("gzip.log.gz");
+  }
+}
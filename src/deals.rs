@@ -0,0 +1,136 @@
+//! Files of given deals, to play curated or externally generated deals
+//! instead of random shuffles.
+//!
+//! A deal file has one deal per line; blank lines and lines starting with `#`
+//! are skipped. A deal lists the 52 cards of a standard deck in the order they
+//! are dealt: pile 0 gets the first card, pile 1 the next two and so on up to
+//! pile 6, the last card of each pile face up, and the remaining 24 cards form
+//! the deck, first card drawn first. Lines are plain text, cards separated by
+//! spaces or commas:
+//!
+//! ```text
+//! KS 10H AD 2C ...
+//! ```
+//!
+//! or JSON, either an array of cards or an object with a `cards` array and an
+//! optional `name`:
+//!
+//! ```text
+//! ["KS", "10H", "AD", "2C", ...]
+//! {"name": "hard-17", "cards": ["KS", "10H", "AD", "2C", ...]}
+//! ```
+
+use cards::french::{self, FrenchPlayingCard};
+use games::solitaire::klondike::{KlondikeRules, KlondikeSolitaireGame};
+use json::Value;
+use std::collections::HashSet;
+use std::io::BufRead;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deal {
+  pub name: Option<String>,
+  pub cards: Vec<FrenchPlayingCard>,
+}
+
+impl Deal {
+  /// Parse one line of a deal file.
+  pub fn parse(line: &str) -> Result<Deal, String> {
+    let line = line.trim();
+
+    let (name, cards) = if line.starts_with('[') || line.starts_with('{') {
+      let value = Value::parse(line)?;
+      let name = value.get("name").and_then(Value::as_str).map(String::from);
+      let cards = match value.get("cards").unwrap_or(&value).as_array() {
+        Some(cards) => cards.iter()
+          .map(|c| c.as_str().map(String::from).ok_or_else(|| format!("card is not a string: {}", c)))
+          .collect::<Result<Vec<String>, String>>()?,
+        None => { return Err(String::from("expected an array of cards")); },
+      };
+
+      (name, cards)
+    } else {
+      (None, line.split(|c: char| c == ',' || c.is_whitespace()).filter(|c| ! c.is_empty()).map(String::from).collect())
+    };
+
+    let cards = cards.iter().map(|c| c.parse::<FrenchPlayingCard>()).collect::<Result<Vec<_>, String>>()?;
+
+    if cards.len() != french::STANDARD_DECK_SIZE as usize {
+      return Err(format!("expected {} cards, found {}", french::STANDARD_DECK_SIZE, cards.len()));
+    }
+
+    let mut seen = HashSet::new();
+    if let Some(card) = cards.iter().find(|c| ! seen.insert(**c)) {
+      return Err(format!("duplicate card {}", card));
+    }
+
+    Ok(Deal { name: name, cards: cards })
+  }
+
+  pub fn game(&self, rules: KlondikeRules) -> KlondikeSolitaireGame {
+    KlondikeSolitaireGame::with_deal(rules, self.cards.clone())
+  }
+}
+
+/// Read every deal of a deal file, or the first error with its line number.
+pub fn read_deals<R: BufRead>(input: R) -> Result<Vec<Deal>, String> {
+  let mut deals = Vec::new();
+
+  for (i, line) in input.lines().enumerate() {
+    let line = line.map_err(|e| format!("line {}: {}", i + 1, e))?;
+
+    if line.trim().is_empty() || line.trim_start().starts_with('#') {
+      continue;
+    }
+
+    deals.push(Deal::parse(&line).map_err(|e| format!("line {}: {}", i + 1, e))?);
+  }
+
+  Ok(deals)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::french::{Rank, Suit};
+
+  fn deck_line(separator: &str) -> String {
+    french::new_standard_deck().iter().map(|c| c.to_string()).collect::<Vec<_>>().join(separator)
+  }
+
+  #[test]
+  fn parse_formats() {
+    let plain = Deal::parse(&deck_line(" ")).unwrap();
+    assert_eq!(plain.cards, french::new_standard_deck());
+    assert_eq!(plain.name, None);
+
+    assert_eq!(Deal::parse(&deck_line(", ")), Ok(plain.clone()));
+    assert_eq!(Deal::parse(&format!("[\"{}\"]", deck_line("\",\""))), Ok(plain.clone()));
+
+    let named = Deal::parse(&format!("{{\"name\": \"hard\", \"cards\": [\"{}\"]}}", deck_line("\", \""))).unwrap();
+    assert_eq!(named.name, Some(String::from("hard")));
+    assert_eq!(named.cards, plain.cards);
+  }
+
+  #[test]
+  fn parse_errors() {
+    let line = deck_line(" ");
+    assert!(Deal::parse(&line[3..]).is_err(), "51 cards");
+    assert!(Deal::parse(&format!("2D {}", &line[3..])).is_err(), "duplicate");
+    assert!(Deal::parse("[1, 2]").is_err());
+    assert!(Deal::parse("{\"cards\": ").is_err());
+
+    let input = format!("# hard deals\n\n{}\nKS\n", line);
+    assert_eq!(read_deals(input.as_bytes()), Err(String::from("line 4: expected 52 cards, found 1")));
+    assert_eq!(read_deals(&input.as_bytes()[..input.len() - 3]).unwrap().len(), 1);
+  }
+
+  #[test]
+  fn deals_game() {
+    let deal = Deal::parse(&deck_line(" ")).unwrap();
+    let game = deal.game(KlondikeRules::new(1));
+
+    assert_eq!(game.pile(0).visible_cards(), &[FrenchPlayingCard::new(Suit::Diamonds, Rank::Ace)]);
+    assert_eq!(game.pile(6).hidden_cards().len(), 6);
+    assert_eq!(game.deck().remaining_cards().len(), 24);
+  }
+}
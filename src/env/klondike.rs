@@ -0,0 +1,201 @@
+//! Klondike as a reinforcement learning environment.
+//!
+//! Moves are encoded as a fixed set of `ACTION_COUNT` discrete actions:
+//!
+//! | Actions | Move                                   |
+//! |---------|----------------------------------------|
+//! | 0       | draw                                   |
+//! | 1       | deck to foundation                     |
+//! | 2-8     | deck to pile 0-6                       |
+//! | 9-15    | pile 0-6 to foundation                 |
+//! | 16-64   | pile i to pile j, `16 + i * 7 + j`     |
+//! | 65-92   | foundation to pile, `65 + s * 7 + j`   |
+//!
+//! where `s` is the suit's position in `Suit::standard_iter`. Pile to the same
+//! pile actions exist to keep the encoding regular but are never legal.
+//!
+//! Observations are `features::klondike` vectors plus a mask of the legal
+//! actions. The API only uses plain numbers and vectors so it is straight
+//! forward to wrap for other languages.
+
+use cards::seeded_source;
+use cards::french::Suit;
+use features::klondike as features;
+use games::solitaire::klondike::{KlondikeSolitaireGame, Move, MoveSource, MoveTarget};
+
+pub const ACTION_COUNT: usize = 93;
+
+const DECK_TO_PILE: usize = 2;
+const PILE_TO_FOUNDATION: usize = 9;
+const PILE_TO_PILE: usize = 16;
+const FOUNDATION_TO_PILE: usize = 65;
+
+/// Reward for an action that is not legal. The game is left unchanged.
+pub const ILLEGAL_ACTION_REWARD: f64 = -1.0;
+
+pub fn encode_action(m: Move) -> usize {
+  match m {
+    Move::Draw => 0,
+    Move::Cards(MoveSource::Deck, MoveTarget::Foundation) => 1,
+    Move::Cards(MoveSource::Deck, MoveTarget::Pile(j)) => DECK_TO_PILE + j as usize,
+    Move::Cards(MoveSource::Pile(i), MoveTarget::Foundation) => PILE_TO_FOUNDATION + i as usize,
+    Move::Cards(MoveSource::PileRun { pile: i, .. }, MoveTarget::Foundation) => PILE_TO_FOUNDATION + i as usize,
+    Move::Cards(MoveSource::Pile(i), MoveTarget::Pile(j)) => PILE_TO_PILE + i as usize * 7 + j as usize,
+    Move::Cards(MoveSource::PileRun { pile: i, .. }, MoveTarget::Pile(j)) => PILE_TO_PILE + i as usize * 7 + j as usize,
+    Move::Cards(MoveSource::Foundation(suit), MoveTarget::Pile(j)) => {
+      let s = Suit::standard_iter().position(|x| *x == suit).unwrap();
+      FOUNDATION_TO_PILE + s * 7 + j as usize
+    },
+    Move::Cards(MoveSource::Foundation(_), MoveTarget::Foundation) => {
+      panic!("foundation to foundation is not an action")
+    },
+  }
+}
+
+pub fn decode_action(action: usize) -> Option<Move> {
+  let m = match action {
+    0 => Move::Draw,
+    1 => Move::Cards(MoveSource::Deck, MoveTarget::Foundation),
+    a if a < PILE_TO_FOUNDATION => Move::Cards(MoveSource::Deck, MoveTarget::Pile((a - DECK_TO_PILE) as u8)),
+    a if a < PILE_TO_PILE => Move::Cards(MoveSource::Pile((a - PILE_TO_FOUNDATION) as u8), MoveTarget::Foundation),
+    a if a < FOUNDATION_TO_PILE => {
+      let i = (a - PILE_TO_PILE) / 7;
+      let j = (a - PILE_TO_PILE) % 7;
+      Move::Cards(MoveSource::Pile(i as u8), MoveTarget::Pile(j as u8))
+    },
+    a if a < ACTION_COUNT => {
+      let suit = *Suit::standard_iter().nth((a - FOUNDATION_TO_PILE) / 7).unwrap();
+      Move::Cards(MoveSource::Foundation(suit), MoveTarget::Pile(((a - FOUNDATION_TO_PILE) % 7) as u8))
+    },
+    _ => { return None; },
+  };
+
+  Some(m)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+  pub features: Vec<f32>,
+  /// True for each action that is currently legal.
+  pub action_mask: Vec<bool>,
+}
+
+/// Gym style klondike environment.
+///
+/// The reward of a step is the change in the number of cards on the
+/// foundations. An episode is done when the game is won, when no action is
+/// legal or none can make progress any more, see
+/// `KlondikeSolitaireGame::is_stuck`, or after `max_steps` steps.
+pub struct KlondikeEnv {
+  game: KlondikeSolitaireGame,
+  draw_count: u8,
+  max_steps: usize,
+  steps: usize,
+}
+
+impl KlondikeEnv {
+  pub fn new(draw_count: u8, max_steps: usize) -> KlondikeEnv {
+    KlondikeEnv {
+      game: KlondikeSolitaireGame::new_shuffle(draw_count, seeded_source(0)),
+      draw_count: draw_count,
+      max_steps: max_steps,
+      steps: 0,
+    }
+  }
+
+  pub fn game(&self) -> &KlondikeSolitaireGame {
+    &self.game
+  }
+
+  /// Start a new episode with the deal for `seed`.
+  pub fn reset(&mut self, seed: u64) -> Observation {
+    self.game = KlondikeSolitaireGame::new_shuffle(self.draw_count, seeded_source(seed));
+    self.steps = 0;
+    self.observe()
+  }
+
+  /// Apply an action and return the new observation, reward and whether the
+  /// episode is done.
+  pub fn step(&mut self, action: usize) -> (Observation, f64, bool) {
+    let legal = decode_action(action).map_or(false, |m| self.game.legal_moves().contains(&m));
+
+    let reward = if legal {
+      let before = founded(&self.game);
+      self.game.apply(decode_action(action).unwrap()).unwrap();
+      founded(&self.game) as f64 - before as f64
+    } else {
+      ILLEGAL_ACTION_REWARD
+    };
+
+    self.steps += 1;
+
+    let observation = self.observe();
+    let done = self.game.is_clear()
+      || self.steps >= self.max_steps
+      || ! observation.action_mask.iter().any(|a| *a)
+      || self.game.is_stuck();
+
+    (observation, reward, done)
+  }
+
+  fn observe(&self) -> Observation {
+    let mut mask = vec![false; ACTION_COUNT];
+
+    for m in self.game.legal_moves() {
+      mask[encode_action(m)] = true;
+    }
+
+    Observation {
+      features: features::extract(&self.game),
+      action_mask: mask,
+    }
+  }
+}
+
+fn founded(game: &KlondikeSolitaireGame) -> usize {
+  Suit::standard_iter().map(|s| game.foundation(*s).len()).sum()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn action_encoding_round_trip() {
+    for action in 0..ACTION_COUNT {
+      let m = decode_action(action).unwrap();
+      assert_eq!(encode_action(m), action, "{:?}", m);
+    }
+
+    assert!(decode_action(ACTION_COUNT).is_none());
+  }
+
+  #[test]
+  fn reset_is_seeded() {
+    let mut a = KlondikeEnv::new(1, 100);
+    let mut b = KlondikeEnv::new(1, 100);
+
+    assert_eq!(a.reset(3), b.reset(3));
+    assert!(a.reset(3) != b.reset(4));
+    assert!(a.reset(3).action_mask[0]);
+  }
+
+  #[test]
+  fn step() {
+    let mut env = KlondikeEnv::new(1, 2);
+    env.reset(3);
+
+    // Pile 0 to pile 0 is never legal
+    let before = env.game().deck().visible_cards().to_vec();
+    let (_, reward, done) = env.step(PILE_TO_PILE);
+    assert_eq!(reward, ILLEGAL_ACTION_REWARD);
+    assert!(! done);
+    assert_eq!(env.game().deck().visible_cards(), &before[..]);
+
+    let (observation, reward, done) = env.step(0);
+    assert_eq!(reward, 0.0);
+    assert_eq!(env.game().deck().visible_cards().len(), 1);
+    assert_eq!(observation.features, features::extract(env.game()));
+    assert!(done, "max steps reached");
+  }
+}
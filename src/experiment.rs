@@ -0,0 +1,621 @@
+//! Declarative experiment matrices: every combination of games, rules,
+//! strategies and filters played on the same seeds.
+//!
+//! A matrix is a JSON object, or the same keys in a small subset of YAML:
+//!
+//! ```text
+//! # Draw one against vegas rules for both strategies
+//! game: klondike
+//! rules: [standard, vegas]
+//! strategy: [simple, mcts]
+//! filter: all
+//! seeds: 0..1000
+//! repetitions: 1
+//! ```
+//!
+//! `game`, `rules`, `strategy` and `filter` are a name or a list of names:
+//!
+//! * game: `klondike`, `spider`, `freecell`, `pyramid`, `golf`, `yukon`,
+//!   `scorpion`, `accordion`, `bakers-dozen`, `la-belle-lucie`,
+//!   `double-klondike` or `easthaven`
+//! * rules: a klondike rule preset, `1-suit`, `2-suit` or `4-suit` for
+//!   spider, `standard` or `around-the-corner` for golf, or `standard` for
+//!   the other games
+//! * strategy: a klondike strategy, a pyramid pairing, an accordion strategy,
+//!   or `simple` for the other games
+//! * filter: `all`, or `shallow-aces` for baker's dozen
+//!
+//! Rules, strategies and filters that do not belong to a game are skipped for
+//! it. A filter plays only the seeds whose deals it accepts.
+//! `seeds` is a count of seeds from 0 or a range `FIRST..END`, end excluded,
+//! and every deal is `seeded_shuffle(seed, ...)`. Each cell plays each seed
+//! `repetitions` times, default 1, which only differs for strategies that
+//! use random numbers. Cells with the same game and rules share their deals.
+//!
+//! The YAML subset is one `key: value` per line with `#` comments; a value is
+//! a scalar, a `[a, b]` list or a list of `- item` lines below the key.
+
+use cards::{seeded_shuffle, seeded_source};
+use games::solitaire::SolitaireGame;
+use games::solitaire::accordion::AccordionGame;
+use games::solitaire::bakers_dozen::BakersDozenGame;
+use games::solitaire::double_klondike::{DoubleKlondikeGame, DoubleKlondikeRules};
+use games::solitaire::easthaven::EasthavenGame;
+use games::solitaire::freecell::FreeCellGame;
+use games::solitaire::klondike::{KlondikeRules, KlondikeSolitaireGame};
+use games::solitaire::golf::{GolfGame, GolfRules};
+use games::solitaire::la_belle_lucie::{LaBelleLucieGame, LaBelleLucieRules};
+use games::solitaire::pyramid::{PyramidGame, PyramidRules};
+use games::solitaire::scorpion::ScorpionGame;
+use games::solitaire::spider::{SpiderGame, SpiderRules};
+use games::solitaire::yukon::YukonGame;
+use json::Value;
+use strategies::solitaire::klondike::StrategyFactory;
+use strategies::solitaire::accordion::{accordion_strategy, ACCORDION_STRATEGIES};
+use strategies::solitaire::bakers_dozen::{bakers_dozen_filter, SimpleBakersDozenStrategy, BAKERS_DOZEN_FILTERS};
+use strategies::solitaire::double_klondike::SimpleDoubleKlondikeStrategy;
+use strategies::solitaire::easthaven::SimpleEasthavenStrategy;
+use strategies::solitaire::freecell::SimpleFreeCellStrategy;
+use strategies::solitaire::golf::SimpleGolfStrategy;
+use strategies::solitaire::la_belle_lucie::SimpleLaBelleLucieStrategy;
+use strategies::solitaire::pyramid::{Pairing, SimplePyramidStrategy, PAIRINGS};
+use strategies::solitaire::scorpion::SimpleScorpionStrategy;
+use strategies::solitaire::spider::SimpleSpiderStrategy;
+use strategies::solitaire::yukon::SimpleYukonStrategy;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+
+pub static GAMES: &'static [&'static str] = &[
+  "klondike", "spider", "freecell", "pyramid", "golf", "yukon", "scorpion", "accordion", "bakers-dozen",
+  "la-belle-lucie", "double-klondike", "easthaven",
+];
+pub static FILTERS: &'static [&'static str] = &["all", "shallow-aces"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+  pub games: Vec<String>,
+  pub rules: Vec<String>,
+  pub strategies: Vec<String>,
+  pub filters: Vec<String>,
+  pub seeds: Range<u64>,
+  pub repetitions: usize,
+}
+
+/// One combination of a matrix.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cell {
+  pub game: String,
+  pub rules: String,
+  pub strategy: String,
+  pub filter: String,
+}
+
+impl fmt::Display for Cell {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} {} {} {}", self.game, self.rules, self.strategy, self.filter)
+  }
+}
+
+/// One game played in a cell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Outcome {
+  pub seed: u64,
+  pub repetition: usize,
+  pub won: bool,
+  pub score: i32,
+  pub moves: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellResults {
+  pub cell: Cell,
+  pub outcomes: Vec<Outcome>,
+}
+
+impl CellResults {
+  pub fn games(&self) -> usize {
+    self.outcomes.len()
+  }
+
+  pub fn wins(&self) -> usize {
+    self.outcomes.iter().filter(|o| o.won).count()
+  }
+
+  pub fn average_score(&self) -> f64 {
+    let total: i64 = self.outcomes.iter().map(|o| o.score as i64).sum();
+    total as f64 / ::std::cmp::max(self.games(), 1) as f64
+  }
+
+  /// One line per game: seed, repetition, won as 0 or 1, score and moves,
+  /// tab separated, after a header.
+  pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    writeln!(out, "seed\trepetition\twon\tscore\tmoves")?;
+
+    for o in self.outcomes.iter() {
+      writeln!(out, "{}\t{}\t{}\t{}\t{}", o.seed, o.repetition, if o.won { 1 } else { 0 }, o.score, o.moves)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Rules of a game by name, `None` if they do not belong to the game.
+enum Rules {
+  Klondike(KlondikeRules),
+  Spider(SpiderRules),
+  FreeCell,
+  Pyramid(PyramidRules),
+  Golf(GolfRules),
+  Yukon,
+  Scorpion,
+  Accordion,
+  BakersDozen,
+  LaBelleLucie(LaBelleLucieRules),
+  DoubleKlondike(DoubleKlondikeRules),
+  Easthaven,
+}
+
+fn rules(game: &str, name: &str) -> Option<Rules> {
+  match game {
+    "klondike" => KlondikeRules::preset(name).map(Rules::Klondike),
+    "spider" => match name {
+      "1-suit" => Some(Rules::Spider(SpiderRules::new(1))),
+      "2-suit" => Some(Rules::Spider(SpiderRules::new(2))),
+      "4-suit" => Some(Rules::Spider(SpiderRules::new(4))),
+      _ => None,
+    },
+    "freecell" if name == "standard" => Some(Rules::FreeCell),
+    "pyramid" if name == "standard" => Some(Rules::Pyramid(PyramidRules::new())),
+    "yukon" if name == "standard" => Some(Rules::Yukon),
+    "scorpion" if name == "standard" => Some(Rules::Scorpion),
+    "accordion" if name == "standard" => Some(Rules::Accordion),
+    "bakers-dozen" if name == "standard" => Some(Rules::BakersDozen),
+    "la-belle-lucie" if name == "standard" => Some(Rules::LaBelleLucie(LaBelleLucieRules::new())),
+    "double-klondike" if name == "standard" => Some(Rules::DoubleKlondike(DoubleKlondikeRules::new())),
+    "easthaven" if name == "standard" => Some(Rules::Easthaven),
+    "golf" => match name {
+      "standard" => Some(Rules::Golf(GolfRules::new())),
+      "around-the-corner" => Some(Rules::Golf(GolfRules { around_the_corner: true })),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+impl Matrix {
+  /// Parse a JSON matrix, or the YAML subset if the text is not a JSON
+  /// object.
+  pub fn parse(text: &str) -> Result<Matrix, String> {
+    let value = if text.trim_start().starts_with('{') {
+      Value::parse(text)?
+    } else {
+      parse_yaml(text)?
+    };
+
+    Matrix::from_value(&value)
+  }
+
+  pub fn from_value(value: &Value) -> Result<Matrix, String> {
+    let names = |key: &str| -> Result<Vec<String>, String> {
+      match value.get(key) {
+        Some(&Value::String(ref s)) => Ok(vec![s.clone()]),
+        Some(&Value::Array(ref values)) if ! values.is_empty() => values.iter()
+          .map(|v| v.as_str().map(String::from).ok_or_else(|| format!("{} must be names", key)))
+          .collect(),
+        Some(_) => Err(format!("{} must be a name or a list of names", key)),
+        None => Err(format!("missing {}", key)),
+      }
+    };
+
+    let filters = match value.get("filter") {
+      Some(_) => names("filter")?,
+      None => vec![String::from("all")],
+    };
+
+    let seeds = match value.get("seeds") {
+      Some(&Value::String(ref s)) => parse_seeds(s)?,
+      Some(v) => match v.as_u64() {
+        Some(count) => 0..count,
+        None => return Err(String::from("seeds must be a count or a range FIRST..END")),
+      },
+      None => return Err(String::from("missing seeds")),
+    };
+
+    let repetitions = match value.get("repetitions") {
+      Some(v) => match v.as_u64() {
+        Some(n) if n > 0 => n as usize,
+        _ => return Err(String::from("repetitions must be a positive number")),
+      },
+      None => 1,
+    };
+
+    let matrix = Matrix {
+      games: names("game")?,
+      rules: names("rules")?,
+      strategies: names("strategy")?,
+      filters: filters,
+      seeds: seeds,
+      repetitions: repetitions,
+    };
+
+    if let Some(game) = matrix.games.iter().find(|g| ! GAMES.contains(&&g[..])) {
+      return Err(format!("unknown game {}, expected one of {}", game, GAMES.join(", ")));
+    }
+
+    if let Some(filter) = matrix.filters.iter().find(|f| ! FILTERS.contains(&&f[..])) {
+      return Err(format!("unknown filter {}, expected one of {}", filter, FILTERS.join(", ")));
+    }
+
+    if let Some(name) = matrix.rules.iter().find(|r| ! matrix.games.iter().any(|g| rules(g, r).is_some())) {
+      return Err(format!("rules {} do not belong to any game of the matrix", name));
+    }
+
+    Ok(matrix)
+  }
+
+  /// Fail on a strategy that no game of the matrix knows. `strategies`
+  /// gives the strategy names known for klondike.
+  pub fn check_strategies(&self, klondike_strategies: &[&str]) -> Result<(), String> {
+    match self.strategies.iter().find(|s| ! self.games.iter().any(|g| known_strategy(g, s, klondike_strategies))) {
+      Some(name) => Err(format!("strategy {} does not belong to any game of the matrix", name)),
+      None => Ok(()),
+    }
+  }
+
+  /// Every combination that applies, in the order of the lists. `strategies`
+  /// gives the strategy names known for klondike.
+  pub fn cells(&self, klondike_strategies: &[&str]) -> Vec<Cell> {
+    let mut cells = Vec::new();
+
+    for game in self.games.iter() {
+      for rule in self.rules.iter().filter(|r| rules(game, r).is_some()) {
+        for strategy in self.strategies.iter().filter(|s| known_strategy(game, s, klondike_strategies)) {
+          let filters = self.filters.iter()
+            .filter(|f| *f == "all" || (game == "bakers-dozen" && BAKERS_DOZEN_FILTERS.contains(&&f[..])));
+
+          for filter in filters {
+            cells.push(Cell { game: game.clone(), rules: rule.clone(), strategy: strategy.clone(), filter: filter.clone() });
+          }
+        }
+      }
+    }
+
+    cells
+  }
+}
+
+fn known_strategy(game: &str, strategy: &str, klondike_strategies: &[&str]) -> bool {
+  match game {
+    "klondike" => klondike_strategies.contains(&strategy),
+    "pyramid" => PAIRINGS.contains(&strategy),
+    "accordion" => ACCORDION_STRATEGIES.contains(&strategy),
+    _ => strategy == "simple",
+  }
+}
+
+fn parse_seeds(s: &str) -> Result<Range<u64>, String> {
+  let error = || format!("seeds must be a count or a range FIRST..END, not {}", s);
+
+  match s.find("..") {
+    Some(i) => {
+      let first = s[..i].trim().parse::<u64>().map_err(|_| error())?;
+      let end = s[i + 2..].trim().parse::<u64>().map_err(|_| error())?;
+      if first >= end {
+        return Err(error());
+      }
+      Ok(first..end)
+    },
+    None => s.trim().parse::<u64>().map(|n| 0..n).map_err(|_| error()),
+  }
+}
+
+/// Read the YAML subset into the JSON value of the same object.
+pub fn parse_yaml(text: &str) -> Result<Value, String> {
+  fn scalar(s: &str) -> Value {
+    let s = s.trim();
+    let unquoted = s.trim_matches(|c| c == '"' || c == '\'');
+
+    if unquoted.len() < s.len() {
+      return Value::String(unquoted.to_string());
+    }
+
+    match s.parse::<f64>() {
+      Ok(n) => Value::Number(n),
+      Err(_) => Value::String(s.to_string()),
+    }
+  }
+
+  let mut members: Vec<(String, Value)> = Vec::new();
+
+  for (i, line) in text.lines().enumerate() {
+    let line = match line.find('#') {
+      Some(at) => &line[..at],
+      None => line,
+    };
+
+    if line.trim().is_empty() {
+      continue;
+    }
+
+    let trimmed = line.trim();
+
+    if trimmed.starts_with("- ") || trimmed == "-" {
+      match members.last_mut() {
+        Some(&mut (_, Value::Array(ref mut items))) => items.push(scalar(&trimmed[1..])),
+        _ => return Err(format!("line {}: list item without a key", i + 1)),
+      }
+      continue;
+    }
+
+    if line.starts_with(char::is_whitespace) {
+      return Err(format!("line {}: nested values are not supported", i + 1));
+    }
+
+    let colon = line.find(':').ok_or_else(|| format!("line {}: expected key: value", i + 1))?;
+    let key = line[..colon].trim().to_string();
+    let value = line[colon + 1..].trim();
+
+    let value = if value.is_empty() {
+      Value::Array(Vec::new())
+    } else if value.starts_with('[') && value.ends_with(']') {
+      Value::Array(value[1..value.len() - 1].split(',').filter(|s| ! s.trim().is_empty()).map(scalar).collect())
+    } else {
+      scalar(value)
+    };
+
+    members.push((key, value));
+  }
+
+  Ok(Value::Object(members))
+}
+
+/// Play every cell of `matrix`. `new_strategy` gives the factory of a
+/// klondike strategy by name; `progress` is told about each cell when it is
+/// done.
+pub fn run<S, P>(matrix: &Matrix, klondike_strategies: &[&str], new_strategy: S, mut progress: P) -> Vec<CellResults>
+  where S: Fn(&str) -> StrategyFactory, P: FnMut(&CellResults) {
+  let mut klondike_deals: HashMap<String, Vec<KlondikeSolitaireGame>> = HashMap::new();
+  let mut spider_deals: HashMap<String, Vec<SpiderGame>> = HashMap::new();
+  let mut freecell_deals: Vec<FreeCellGame> = Vec::new();
+  let mut pyramid_deals: Vec<PyramidGame> = Vec::new();
+  let mut golf_deals: HashMap<String, Vec<GolfGame>> = HashMap::new();
+  let mut yukon_deals: Vec<YukonGame> = Vec::new();
+  let mut scorpion_deals: Vec<ScorpionGame> = Vec::new();
+  let mut accordion_deals: Vec<AccordionGame> = Vec::new();
+  let mut bakers_dozen_deals: Vec<BakersDozenGame> = Vec::new();
+  let mut la_belle_lucie_deals: Vec<LaBelleLucieGame> = Vec::new();
+  let mut double_klondike_deals: Vec<DoubleKlondikeGame> = Vec::new();
+  let mut easthaven_deals: Vec<EasthavenGame> = Vec::new();
+  let mut results = Vec::new();
+
+  for cell in matrix.cells(klondike_strategies) {
+    let outcomes = match rules(&cell.game, &cell.rules).unwrap() {
+      Rules::Klondike(rules) => {
+        let deals = klondike_deals.entry(cell.rules.clone()).or_insert_with(|| {
+          matrix.seeds.clone().map(|seed| KlondikeSolitaireGame::with_rules(rules, seeded_source(seed))).collect()
+        });
+        let mut strategy = new_strategy(&cell.strategy)();
+        play_deals(matrix, deals, |g| strategy.play(g))
+      },
+      Rules::Spider(rules) => {
+        let deals = spider_deals.entry(cell.rules.clone()).or_insert_with(|| {
+          matrix.seeds.clone().map(|seed| SpiderGame::new(rules, |c| seeded_shuffle(seed, c))).collect()
+        });
+        let mut strategy = SimpleSpiderStrategy::new();
+        play_deals(matrix, deals, |g| strategy.play(g))
+      },
+      Rules::FreeCell => {
+        if freecell_deals.is_empty() {
+          freecell_deals = matrix.seeds.clone().map(|seed| FreeCellGame::new(|c| seeded_shuffle(seed, c))).collect();
+        }
+        let mut strategy = SimpleFreeCellStrategy::new();
+        play_deals(matrix, &freecell_deals, |g| strategy.play(g))
+      },
+      Rules::Pyramid(rules) => {
+        if pyramid_deals.is_empty() {
+          pyramid_deals = matrix.seeds.clone().map(|seed| PyramidGame::new(rules, |c| seeded_shuffle(seed, c))).collect();
+        }
+        let mut strategy = SimplePyramidStrategy::new(cell.strategy.parse::<Pairing>().unwrap());
+        play_deals(matrix, &pyramid_deals, |g| strategy.play(g))
+      },
+      Rules::Golf(rules) => {
+        let deals = golf_deals.entry(cell.rules.clone()).or_insert_with(|| {
+          matrix.seeds.clone().map(|seed| GolfGame::new(rules, |c| seeded_shuffle(seed, c))).collect()
+        });
+        let mut strategy = SimpleGolfStrategy::new();
+        play_deals(matrix, deals, |g| strategy.play(g))
+      },
+      Rules::Yukon => {
+        if yukon_deals.is_empty() {
+          yukon_deals = matrix.seeds.clone().map(|seed| YukonGame::new(|c| seeded_shuffle(seed, c))).collect();
+        }
+        let mut strategy = SimpleYukonStrategy::new();
+        play_deals(matrix, &yukon_deals, |g| strategy.play(g))
+      },
+      Rules::Scorpion => {
+        if scorpion_deals.is_empty() {
+          scorpion_deals = matrix.seeds.clone().map(|seed| ScorpionGame::new(|c| seeded_shuffle(seed, c))).collect();
+        }
+        let mut strategy = SimpleScorpionStrategy::new();
+        play_deals(matrix, &scorpion_deals, |g| strategy.play(g))
+      },
+      Rules::Accordion => {
+        if accordion_deals.is_empty() {
+          accordion_deals = matrix.seeds.clone().map(|seed| AccordionGame::new(|c| seeded_shuffle(seed, c))).collect();
+        }
+        let mut strategy = accordion_strategy(&cell.strategy).unwrap();
+        play_deals(matrix, &accordion_deals, |g| strategy.play(g))
+      },
+      Rules::BakersDozen => {
+        if bakers_dozen_deals.is_empty() {
+          bakers_dozen_deals = matrix.seeds.clone().map(|seed| BakersDozenGame::new(|c| seeded_shuffle(seed, c))).collect();
+        }
+        let filter = bakers_dozen_filter(&cell.filter).unwrap();
+        let mut strategy = SimpleBakersDozenStrategy::new();
+        play_filtered_deals(matrix, &bakers_dozen_deals, |g| filter.accept(g), |g| strategy.play(g))
+      },
+      Rules::LaBelleLucie(rules) => {
+        if la_belle_lucie_deals.is_empty() {
+          la_belle_lucie_deals = matrix.seeds.clone().map(|seed| LaBelleLucieGame::new(rules, |c| seeded_shuffle(seed, c))).collect();
+        }
+        let mut strategy = SimpleLaBelleLucieStrategy::new();
+        play_deals(matrix, &la_belle_lucie_deals, |g| strategy.play(g))
+      },
+      Rules::DoubleKlondike(rules) => {
+        if double_klondike_deals.is_empty() {
+          double_klondike_deals = matrix.seeds.clone().map(|seed| DoubleKlondikeGame::new(rules, |c| seeded_shuffle(seed, c))).collect();
+        }
+        let mut strategy = SimpleDoubleKlondikeStrategy::new();
+        play_deals(matrix, &double_klondike_deals, |g| strategy.play(g))
+      },
+      Rules::Easthaven => {
+        if easthaven_deals.is_empty() {
+          easthaven_deals = matrix.seeds.clone().map(|seed| EasthavenGame::new(|c| seeded_shuffle(seed, c))).collect();
+        }
+        let mut strategy = SimpleEasthavenStrategy::new();
+        play_deals(matrix, &easthaven_deals, |g| strategy.play(g))
+      },
+    };
+
+    let cell_results = CellResults { cell: cell, outcomes: outcomes };
+    progress(&cell_results);
+    results.push(cell_results);
+  }
+
+  results
+}
+
+/// Play each deal of the seeds of `matrix` the number of repetitions.
+fn play_deals<G, P>(matrix: &Matrix, deals: &[G], play: P) -> Vec<Outcome>
+  where G: SolitaireGame + Clone, P: FnMut(&mut G) {
+  play_filtered_deals(matrix, deals, |_| true, play)
+}
+
+/// Play the deals that `accept` keeps the number of repetitions.
+fn play_filtered_deals<G, A, P>(matrix: &Matrix, deals: &[G], accept: A, mut play: P) -> Vec<Outcome>
+  where G: SolitaireGame + Clone, A: Fn(&G) -> bool, P: FnMut(&mut G) {
+  let mut outcomes = Vec::new();
+
+  for (seed, deal) in matrix.seeds.clone().zip(deals.iter()).filter(|&(_, deal)| accept(deal)) {
+    for repetition in 0..matrix.repetitions {
+      let mut game = deal.clone();
+      play(&mut game);
+      outcomes.push(Outcome { seed: seed, repetition: repetition, won: game.is_clear(), score: game.score(), moves: game.moves_made() });
+    }
+  }
+
+  outcomes
+}
+
+/// One line per cell with its games, wins, win rate and average score, tab
+/// separated, after a header.
+pub fn write_summary<W: Write>(out: &mut W, results: &[CellResults]) -> io::Result<()> {
+  writeln!(out, "game\trules\tstrategy\tfilter\tgames\twins\twin %\tavg score")?;
+
+  for r in results {
+    writeln!(out, "{}\t{}\t{}\t{}\t{}\t{}\t{:.2}\t{:.2}",
+             r.cell.game, r.cell.rules, r.cell.strategy, r.cell.filter,
+             r.games(), r.wins(), 100.0 * r.wins() as f64 / ::std::cmp::max(r.games(), 1) as f64, r.average_score())?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::sync::Arc;
+  use strategies::solitaire::klondike::GameStrategy;
+  use strategies::solitaire::klondike::simple::SimpleKlondikeStrategy;
+
+  const YAML: &'static str = "\
+# Two games
+game: [klondike, spider]
+rules:
+  - standard
+  - vegas
+  - 1-suit
+strategy: [simple, mcts]
+seeds: 10..14
+repetitions: 2
+";
+
+  #[test]
+  fn parse_formats() {
+    let yaml = Matrix::parse(YAML).unwrap();
+    assert_eq!(yaml.games, vec!["klondike", "spider"]);
+    assert_eq!(yaml.rules, vec!["standard", "vegas", "1-suit"]);
+    assert_eq!(yaml.filters, vec!["all"]);
+    assert_eq!(yaml.seeds, 10..14);
+    assert_eq!(yaml.repetitions, 2);
+
+    let json = Matrix::parse(r#"{"rules": ["standard", "vegas", "1-suit"], "strategy": ["simple", "mcts"],
+                                "game": ["klondike", "spider"], "seeds": "10..14", "repetitions": 2}"#);
+    assert_eq!(json.unwrap().seeds, yaml.seeds);
+
+    assert_eq!(Matrix::parse("game: klondike\nrules: standard\nstrategy: simple\nseeds: 5").unwrap().seeds, 0..5);
+  }
+
+  #[test]
+  fn parse_errors() {
+    assert!(Matrix::parse("game: klondike\nrules: standard\nstrategy: simple").is_err(), "missing seeds");
+    assert!(Matrix::parse("game: chess\nrules: standard\nstrategy: simple\nseeds: 5").is_err());
+    assert!(Matrix::parse("game: klondike\nrules: 2-suit\nstrategy: simple\nseeds: 5").is_err());
+    assert!(Matrix::parse("game: klondike\nrules: standard\nstrategy: simple\nseeds: 5..5").is_err());
+    assert!(Matrix::parse("game: klondike\nrules: standard\nstrategy: simple\nseeds: 5\nfilter: odd").is_err());
+    assert!(Matrix::parse("game: klondike\n  rules: standard").is_err());
+
+    let error = Matrix::parse(r#"{"game": "klondike", "rules": "standard",}"#).unwrap_err();
+    assert!(error.contains("offset"), "JSON error, not YAML: {}", error);
+
+    let typo = Matrix::parse("game: [klondike, pyramid]\nrules: standard\nstrategy: [simple, smple]\nseeds: 5").unwrap();
+    assert!(typo.check_strategies(&["simple"]).unwrap_err().contains("smple"));
+    assert!(Matrix::parse("game: pyramid\nrules: standard\nstrategy: first\nseeds: 5").unwrap().check_strategies(&["simple"]).is_ok());
+  }
+
+  #[test]
+  fn cells_skip_other_games() {
+    let matrix = Matrix::parse(YAML).unwrap();
+    let cells: Vec<String> = matrix.cells(&["simple", "mcts"]).iter().map(|c| c.to_string()).collect();
+
+    assert_eq!(cells, vec![
+      "klondike standard simple all", "klondike standard mcts all",
+      "klondike vegas simple all", "klondike vegas mcts all",
+      "spider 1-suit simple all",
+    ]);
+
+    let pyramid = Matrix::parse("game: [pyramid, freecell]\nrules: standard\nstrategy: [simple, uncover, first]\nseeds: 1").unwrap();
+    let cells: Vec<String> = pyramid.cells(&["simple"]).iter().map(|c| c.to_string()).collect();
+    assert_eq!(cells, vec!["pyramid standard uncover all", "pyramid standard first all", "freecell standard simple all"]);
+
+    let filtered = Matrix::parse("game: [bakers-dozen, yukon]\nrules: standard\nstrategy: simple\nfilter: [all, shallow-aces]\nseeds: 1").unwrap();
+    let cells: Vec<String> = filtered.cells(&["simple"]).iter().map(|c| c.to_string()).collect();
+    assert_eq!(cells, vec!["bakers-dozen standard simple all", "bakers-dozen standard simple shallow-aces", "yukon standard simple all"]);
+  }
+
+  #[test]
+  fn run_matrix() {
+    let matrix = Matrix::parse("game: [klondike, spider]\nrules: [standard, 4-suit]\nstrategy: simple\nseeds: 3..6\nrepetitions: 2").unwrap();
+    let mut done = Vec::new();
+
+    let results = run(&matrix, &["simple"], |_| Arc::new(|| Box::new(SimpleKlondikeStrategy::new()) as Box<dyn GameStrategy>),
+                      |r| done.push(r.cell.clone()));
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(done, results.iter().map(|r| r.cell.clone()).collect::<Vec<Cell>>());
+
+    for r in results.iter() {
+      assert_eq!(r.games(), 6);
+      let seeds: Vec<u64> = r.outcomes.iter().map(|o| o.seed).collect();
+      assert_eq!(seeds, vec![3, 3, 4, 4, 5, 5]);
+    }
+
+    let mut summary = Vec::new();
+    write_summary(&mut summary, &results).unwrap();
+    let summary = String::from_utf8(summary).unwrap();
+    assert_eq!(summary.lines().count(), 3);
+    assert!(summary.lines().nth(2).unwrap().starts_with("spider\t4-suit\tsimple\tall\t6\t"));
+  }
+}
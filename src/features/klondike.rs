@@ -0,0 +1,191 @@
+//! Fixed length numeric encoding of a klondike game for machine learning.
+//!
+//! Only what a player can see is encoded: face down pile cards and undrawn
+//! deck cards are indistinguishable. The vector has `FEATURE_COUNT` values in
+//! the following order; `feature_names` returns a name for each index.
+//!
+//! | Offset | Count  | Feature                                            |
+//! |--------|--------|----------------------------------------------------|
+//! | 0      | 52 × 6 | card location, one hot (see below)                 |
+//! | 312    | 7 × 3  | per pile: hidden cards, visible cards, empty (0/1) |
+//! | 333    | 4      | per foundation: number of cards                    |
+//! | 337    | 4      | deck: remaining, visible, waste cards, draw count  |
+//!
+//! Cards are in `french::new_standard_deck` order and foundations in
+//! `Suit::standard_iter` order. Each card has exactly one of its six location
+//! values set to 1:
+//!
+//! 0. not visible (face down in a pile or not yet drawn)
+//! 1. top card of the deck (playable)
+//! 2. other drawn deck card (visible or waste)
+//! 3. face up in a pile, covered by other cards
+//! 4. top card of a pile
+//! 5. on a foundation
+//!
+//! Counts are not normalized.
+
+use games::solitaire::klondike::{KlondikeSolitaireGame, Card};
+use cards::french::{self, Suit};
+
+/// Number of location values per card.
+pub const LOCATIONS: usize = 6;
+
+pub const CARD_OFFSET: usize = 0;
+pub const PILE_OFFSET: usize = CARD_OFFSET + 52 * LOCATIONS;
+pub const FOUNDATION_OFFSET: usize = PILE_OFFSET + 7 * 3;
+pub const DECK_OFFSET: usize = FOUNDATION_OFFSET + 4;
+
+/// Length of the vector returned by `extract`.
+pub const FEATURE_COUNT: usize = DECK_OFFSET + 4;
+
+static LOCATION_NAMES: [&'static str; LOCATIONS] = ["hidden", "deck_top", "deck_drawn", "pile_covered", "pile_top", "foundation"];
+
+fn location(game: &KlondikeSolitaireGame, card: Card) -> usize {
+  if game.foundation(card.suit()).cards().contains(&card) {
+    return 5;
+  }
+
+  for pile in game.piles() {
+    if pile.top() == Some(card) {
+      return 4;
+    }
+
+    if pile.visible_cards().contains(&card) {
+      return 3;
+    }
+  }
+
+  if game.deck().top() == Some(card) {
+    return 1;
+  }
+
+  if game.deck().visible_cards().contains(&card) || game.deck().waste_cards().contains(&card) {
+    return 2;
+  }
+
+  0
+}
+
+/// Encode the visible state of `game`.
+pub fn extract(game: &KlondikeSolitaireGame) -> Vec<f32> {
+  let mut features = vec![0.0; FEATURE_COUNT];
+
+  for (i, card) in french::new_standard_deck().iter().enumerate() {
+    features[CARD_OFFSET + i * LOCATIONS + location(game, *card)] = 1.0;
+  }
+
+  for (i, pile) in game.piles().iter().enumerate() {
+    features[PILE_OFFSET + i * 3] = pile.hidden_cards().len() as f32;
+    features[PILE_OFFSET + i * 3 + 1] = pile.visible_cards().len() as f32;
+    features[PILE_OFFSET + i * 3 + 2] = if pile.is_empty() { 1.0 } else { 0.0 };
+  }
+
+  for (i, suit) in Suit::standard_iter().enumerate() {
+    features[FOUNDATION_OFFSET + i] = game.foundation(*suit).len() as f32;
+  }
+
+  let deck = game.deck();
+  features[DECK_OFFSET] = deck.remaining_cards().len() as f32;
+  features[DECK_OFFSET + 1] = deck.visible_cards().len() as f32;
+  features[DECK_OFFSET + 2] = deck.waste_cards().len() as f32;
+  features[DECK_OFFSET + 3] = deck.draw_count() as f32;
+
+  return features;
+}
+
+/// Name of each value returned by `extract`, e.g. `card.QH.pile_top`.
+pub fn feature_names() -> Vec<String> {
+  let mut names = Vec::with_capacity(FEATURE_COUNT);
+
+  for card in french::new_standard_deck() {
+    for location in LOCATION_NAMES.iter() {
+      names.push(format!("card.{}{}.{}", card.rank(), card.suit(), location));
+    }
+  }
+
+  for i in 0..7 {
+    names.push(format!("pile{}.hidden", i));
+    names.push(format!("pile{}.visible", i));
+    names.push(format!("pile{}.empty", i));
+  }
+
+  for suit in Suit::standard_iter() {
+    names.push(format!("foundation{}.cards", suit));
+  }
+
+  names.push(String::from("deck.remaining"));
+  names.push(String::from("deck.visible"));
+  names.push(String::from("deck.waste"));
+  names.push(String::from("deck.draw_count"));
+
+  return names;
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use games::solitaire::klondike::{Deck, Foundation, Pile};
+  use cards::french::{Suit, new_standard_deck};
+
+  fn unsorted_game() -> KlondikeSolitaireGame {
+    let cards = new_standard_deck();
+    KlondikeSolitaireGame::from(
+      Deck::from(3, &[], &[], &cards[28..]),
+      [
+        Foundation::new(Suit::Clubs),
+        Foundation::new(Suit::Hearts),
+        Foundation::new(Suit::Spades),
+        Foundation::new(Suit::Diamonds),
+      ], [
+        Pile::from(&cards[0..0], &cards[0..1]),
+        Pile::from(&cards[1..2], &cards[2..3]),
+        Pile::from(&cards[3..5], &cards[5..6]),
+        Pile::from(&cards[6..9], &cards[9..10]),
+        Pile::from(&cards[10..14], &cards[14..15]),
+        Pile::from(&cards[15..20], &cards[20..21]),
+        Pile::from(&cards[21..27], &cards[27..28]),
+      ]
+    )
+  }
+
+  #[test]
+  fn schema() {
+    let names = feature_names();
+    assert_eq!(names.len(), FEATURE_COUNT);
+    assert_eq!(names[0], "card.AD.hidden");
+    assert_eq!(names[PILE_OFFSET], "pile0.hidden");
+    assert_eq!(names[FOUNDATION_OFFSET], "foundationD.cards");
+    assert_eq!(names[FEATURE_COUNT - 1], "deck.draw_count");
+  }
+
+  #[test]
+  fn extract_locations() {
+    let mut game = unsorted_game();
+    game.draw();
+
+    let features = extract(&game);
+    assert_eq!(features.len(), FEATURE_COUNT);
+
+    // Each card is in exactly one location
+    for card in features[..PILE_OFFSET].chunks(LOCATIONS) {
+      assert_eq!(card.iter().sum::<f32>(), 1.0);
+    }
+
+    let names = feature_names();
+    let set: Vec<&str> = names.iter().zip(features.iter())
+      .filter(|&(_, v)| *v == 1.0)
+      .map(|(n, _)| &n[..])
+      .collect();
+
+    assert!(set.contains(&"card.AD.pile_top"));
+    assert!(set.contains(&"card.2D.hidden"));
+    assert!(set.contains(&"card.5C.deck_top"));
+    assert!(set.contains(&"card.3C.deck_drawn"));
+    assert!(set.contains(&"pile0.visible"));
+
+    assert_eq!(features[PILE_OFFSET + 6 * 3], 6.0);
+    assert_eq!(features[DECK_OFFSET], 21.0);
+    assert_eq!(features[DECK_OFFSET + 1], 3.0);
+    assert_eq!(features[DECK_OFFSET + 3], 3.0);
+  }
+}
@@ -0,0 +1,265 @@
+//! Baccarat, punto banco: the drawing is fixed by the rules and the only
+//! decision is the bet.
+//!
+//! Each coup deals two cards to the player and two to the banker, and the
+//! hand whose total is closer to 9 wins. Cards count their pips, aces one and
+//! tens and picture cards nothing, and a total is the last digit of the sum.
+//! A total of 8 or 9 on two cards is a natural and ends the coup. Otherwise
+//! the player draws a third card on 0 to 5, and the banker draws by their
+//! total and the player's third card: on 0 to 5 when the player stood, and
+//! after a player draw always on 0 to 2, on 3 unless the card was an 8, on 4
+//! against 2 to 7, on 5 against 4 to 7 and on 6 against 6 or 7.
+//!
+//! Bets on the player pay even money, bets on the banker even money less
+//! `commission`, and both push on a tie. Bets on a tie pay `tie_pays` to
+//! one.
+
+use cards::french::{FrenchPlayingCard, Rank};
+use games::shoe::Shoe;
+use std::fmt;
+use std::str::FromStr;
+
+pub type Card = FrenchPlayingCard;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BaccaratRules {
+  /// Decks in the shoe.
+  pub decks: usize,
+  /// Fraction of the shoe dealt before the cut card ends it.
+  pub penetration: f64,
+  /// Fraction of a winning banker bet kept by the house.
+  pub commission: f64,
+  /// Amount a winning tie bet pays for each unit bet.
+  pub tie_pays: f64,
+}
+
+impl BaccaratRules {
+  /// Eight decks dealt to the last few cards, 5% commission and ties paying
+  /// 8 to 1.
+  pub fn new() -> BaccaratRules {
+    BaccaratRules {
+      decks: 8,
+      penetration: 0.95,
+      commission: 0.05,
+      tie_pays: 8.0,
+    }
+  }
+}
+
+/// The three bets, and the outcomes of a coup.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Bet {
+  Player,
+  Banker,
+  Tie,
+}
+
+pub static BETS: [Bet; 3] = [Bet::Player, Bet::Banker, Bet::Tie];
+
+impl fmt::Display for Bet {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Bet::Player => "player",
+      Bet::Banker => "banker",
+      Bet::Tie => "tie",
+    };
+
+    write!(f, "{}", name)
+  }
+}
+
+impl FromStr for Bet {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Bet, String> {
+    match s {
+      "player" => Ok(Bet::Player),
+      "banker" => Ok(Bet::Banker),
+      "tie" => Ok(Bet::Tie),
+      _ => Err(format!("Invalid bet: {}", s)),
+    }
+  }
+}
+
+/// Value of a card in a total: the pips, 1 for an ace and 0 for tens and
+/// picture cards.
+pub fn card_value(card: Card) -> u8 {
+  match card.rank() {
+    Rank::Number(n) if n < 10 => n as u8,
+    Rank::Ace => 1,
+    _ => 0,
+  }
+}
+
+/// Total of `cards`, the last digit of the sum of their values.
+pub fn total(cards: &[Card]) -> u8 {
+  cards.iter().map(|c| card_value(*c)).sum::<u8>() % 10
+}
+
+/// Whether the banker draws a third card on `banker_total` when the player's
+/// third card is `player_third`, `None` if the player stood.
+pub fn banker_draws(banker_total: u8, player_third: Option<Card>) -> bool {
+  let third = match player_third {
+    Some(card) => card_value(card),
+    None => return banker_total <= 5,
+  };
+
+  match banker_total {
+    0..=2 => true,
+    3 => third != 8,
+    4 => (2..=7).contains(&third),
+    5 => (4..=7).contains(&third),
+    6 => third == 6 || third == 7,
+    _ => false,
+  }
+}
+
+/// One coup, dealt and drawn to the end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coup {
+  player: Vec<Card>,
+  banker: Vec<Card>,
+}
+
+impl Coup {
+  /// Deal the cards of a coup from `shoe`, alternately to the player and the
+  /// banker, and draw the third cards.
+  pub fn deal(shoe: &mut Shoe) -> Coup {
+    shoe.start_round();
+
+    let mut player = Vec::with_capacity(3);
+    let mut banker = Vec::with_capacity(3);
+    for _ in 0..2 {
+      player.push(shoe.draw());
+      banker.push(shoe.draw());
+    }
+
+    let (p, b) = (total(&player), total(&banker));
+    if p < 8 && b < 8 {
+      let mut third = None;
+      if p <= 5 {
+        let card = shoe.draw();
+        player.push(card);
+        third = Some(card);
+      }
+      if banker_draws(b, third) {
+        banker.push(shoe.draw());
+      }
+    }
+
+    Coup {
+      player: player,
+      banker: banker,
+    }
+  }
+
+  pub fn player(&self) -> &[Card] {
+    &self.player
+  }
+
+  pub fn banker(&self) -> &[Card] {
+    &self.banker
+  }
+
+  /// Whether either side had a natural, an 8 or 9 on the first two cards.
+  pub fn is_natural(&self) -> bool {
+    total(&self.player[..2]) >= 8 || total(&self.banker[..2]) >= 8
+  }
+
+  /// The side that won, or `Bet::Tie`.
+  pub fn outcome(&self) -> Bet {
+    let (p, b) = (total(&self.player), total(&self.banker));
+
+    if p > b {
+      Bet::Player
+    } else if b > p {
+      Bet::Banker
+    } else {
+      Bet::Tie
+    }
+  }
+
+  /// Net amount won by a unit `bet` under `rules`, negative for a loss.
+  pub fn pays(&self, bet: Bet, rules: &BaccaratRules) -> f64 {
+    match (bet, self.outcome()) {
+      (Bet::Tie, Bet::Tie) => rules.tie_pays,
+      (Bet::Tie, _) => -1.0,
+      (_, Bet::Tie) => 0.0,
+      (Bet::Player, Bet::Player) => 1.0,
+      (Bet::Banker, Bet::Banker) => 1.0 - rules.commission,
+      _ => -1.0,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  /// Coup dealt from `s`, alternately player and banker.
+  fn coup(s: &[&str]) -> Coup {
+    let mut shoe = Shoe::with_cards(cards(s), s.len(), 0);
+    Coup::deal(&mut shoe)
+  }
+
+  #[test]
+  fn totals() {
+    assert_eq!(total(&cards(&["KS", "9H"])), 9);
+    assert_eq!(total(&cards(&["7S", "8H"])), 5);
+    assert_eq!(total(&cards(&["AS", "10H", "QD"])), 1);
+  }
+
+  #[test]
+  fn banker_drawing_rules() {
+    assert!(banker_draws(5, None));
+    assert!(! banker_draws(6, None));
+
+    let card = |s: &str| Some(s.parse::<Card>().unwrap());
+    assert!(banker_draws(2, card("8S")));
+    assert!(! banker_draws(3, card("8S")));
+    assert!(banker_draws(3, card("9S")));
+    assert!(! banker_draws(4, card("AS")));
+    assert!(banker_draws(4, card("7S")));
+    assert!(! banker_draws(5, card("3S")));
+    assert!(banker_draws(6, card("6S")));
+    assert!(! banker_draws(6, card("5S")));
+    assert!(! banker_draws(7, card("7S")));
+  }
+
+  #[test]
+  fn coups() {
+    let natural = coup(&["9S", "2H", "KS", "3H", "5D"]);
+    assert!(natural.is_natural());
+    assert_eq!(natural.banker().len(), 2);
+    assert_eq!(natural.outcome(), Bet::Player);
+
+    // Player 2 draws a 7, banker 3 draws against it.
+    let drawn = coup(&["AS", "2H", "AD", "AH", "7S", "5C"]);
+    assert_eq!(drawn.player(), &cards(&["AS", "AD", "7S"])[..]);
+    assert_eq!(drawn.banker(), &cards(&["2H", "AH", "5C"])[..]);
+    assert_eq!(drawn.outcome(), Bet::Player);
+
+    // Player stands on 6, banker 5 draws.
+    let stood = coup(&["3S", "2H", "3D", "3H", "4C"]);
+    assert_eq!(stood.player().len(), 2);
+    assert_eq!(stood.banker().len(), 3);
+    assert_eq!(stood.outcome(), Bet::Banker);
+  }
+
+  #[test]
+  fn pays() {
+    let rules = BaccaratRules::new();
+    let banker = coup(&["3S", "2H", "3D", "3H", "4C"]);
+    let tie = coup(&["9S", "9H", "KS", "KH"]);
+
+    assert_eq!(banker.pays(Bet::Banker, &rules), 0.95);
+    assert_eq!(banker.pays(Bet::Player, &rules), -1.0);
+    assert_eq!(banker.pays(Bet::Tie, &rules), -1.0);
+    assert_eq!(tie.pays(Bet::Tie, &rules), 8.0);
+    assert_eq!(tie.pays(Bet::Banker, &rules), 0.0);
+  }
+}
@@ -0,0 +1,446 @@
+//! Blackjack, one player against the dealer.
+//!
+//! Rounds are dealt from a `Shoe` of several decks that is shuffled once and
+//! played down to its cut card. The player is dealt two cards and the dealer
+//! one face up and one face down. A blackjack of either side ends the round
+//! at once, as if the dealer had peeked at the hole card; otherwise the player
+//! plays each hand in turn, then the dealer draws to 17, hitting a soft 17
+//! when the rules say so.
+//!
+//! A hand may be doubled on its first two cards, drawing exactly one more card
+//! for twice the bet, and a pair of cards of the same value may be split into
+//! two hands, up to `max_hands` hands. Split aces draw one card each and stand,
+//! and a 21 of a split hand is not a blackjack. Amounts are in units of the
+//! first bet.
+
+use cards::french::{FrenchPlayingCard, Rank};
+use games::shoe::Shoe;
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BlackjackRules {
+  /// Decks in the shoe.
+  pub decks: usize,
+  /// Fraction of the shoe dealt before the cut card ends it.
+  pub penetration: f64,
+  /// Whether the dealer hits a soft 17 rather than standing on it.
+  pub hit_soft_17: bool,
+  /// Whether split hands may be doubled.
+  pub double_after_split: bool,
+  /// Most hands a player can split into.
+  pub max_hands: usize,
+  /// Win of a blackjack per unit bet.
+  pub blackjack_pays: f64,
+}
+
+impl BlackjackRules {
+  /// Six decks dealt to three quarters, dealer stands on soft 17, doubling
+  /// after splits, splits up to four hands and blackjack paying 3 to 2.
+  pub fn new() -> BlackjackRules {
+    BlackjackRules {
+      decks: 6,
+      penetration: 0.75,
+      hit_soft_17: false,
+      double_after_split: true,
+      max_hands: 4,
+      blackjack_pays: 1.5,
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BlackjackErr {
+  /// Every hand of the round has been played.
+  RoundOver,
+  /// The action is not allowed on the current hand.
+  NotAllowed,
+}
+
+pub type BlackjackResult<T> = Result<T, BlackjackErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+  Hit,
+  Stand,
+  Double,
+  Split,
+}
+
+impl fmt::Display for Action {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Action::Hit => "hit",
+      Action::Stand => "stand",
+      Action::Double => "double",
+      Action::Split => "split",
+    };
+
+    write!(f, "{}", name)
+  }
+}
+
+/// Value of a card in blackjack: 1 for an ace, which `total` may count as 11,
+/// and 10 for the picture cards.
+pub fn card_value(card: Card) -> u8 {
+  match card.rank() {
+    Rank::Ace => 1,
+    rank => ::std::cmp::min(rank.value(), 10),
+  }
+}
+
+/// Best total of `cards` and whether it is soft, that is counts an ace as 11.
+pub fn total(cards: &[Card]) -> (u8, bool) {
+  let hard: u8 = cards.iter().map(|c| card_value(*c)).sum();
+
+  if hard <= 11 && cards.iter().any(|c| c.rank() == Rank::Ace) {
+    (hard + 10, true)
+  } else {
+    (hard, false)
+  }
+}
+
+/// A hand of the player and its bet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hand {
+  cards: Vec<Card>,
+  bet: f64,
+  split: bool,
+  done: bool,
+}
+
+impl Hand {
+  fn new(cards: Vec<Card>, split: bool) -> Hand {
+    Hand {
+      cards: cards,
+      bet: 1.0,
+      split: split,
+      done: false,
+    }
+  }
+
+  pub fn cards(&self) -> &[Card] {
+    &self.cards
+  }
+
+  pub fn bet(&self) -> f64 {
+    self.bet
+  }
+
+  pub fn total(&self) -> u8 {
+    total(&self.cards).0
+  }
+
+  pub fn is_soft(&self) -> bool {
+    total(&self.cards).1
+  }
+
+  /// Whether the hand came from a split.
+  pub fn is_split(&self) -> bool {
+    self.split
+  }
+
+  pub fn is_blackjack(&self) -> bool {
+    ! self.split && self.cards.len() == 2 && self.total() == 21
+  }
+
+  pub fn is_bust(&self) -> bool {
+    self.total() > 21
+  }
+
+  /// Whether the hand is two cards of the same value.
+  pub fn is_pair(&self) -> bool {
+    self.cards.len() == 2 && card_value(self.cards[0]) == card_value(self.cards[1])
+  }
+
+  fn push(&mut self, card: Card) {
+    self.cards.push(card);
+    if self.total() >= 21 {
+      self.done = true;
+    }
+  }
+}
+
+/// A round in play: the hands of the player, the one being played and the
+/// cards of the dealer.
+#[derive(Debug, Clone)]
+pub struct BlackjackRound {
+  rules: BlackjackRules,
+  hands: Vec<Hand>,
+  current: usize,
+  dealer: Vec<Card>,
+}
+
+impl BlackjackRound {
+  /// Deal a round from `shoe`, one card to the player, one face up to the
+  /// dealer, then one more each.
+  pub fn deal(rules: &BlackjackRules, shoe: &mut Shoe) -> BlackjackRound {
+    shoe.start_round();
+    let player = shoe.draw();
+    let up = shoe.draw();
+    let mut hand = Hand::new(vec![player, shoe.draw()], false);
+    let dealer = vec![up, shoe.draw()];
+
+    if hand.is_blackjack() || total(&dealer).0 == 21 {
+      hand.done = true;
+    }
+
+    BlackjackRound {
+      rules: *rules,
+      current: if hand.done { 1 } else { 0 },
+      hands: vec![hand],
+      dealer: dealer,
+    }
+  }
+
+  pub fn hands(&self) -> &[Hand] {
+    &self.hands
+  }
+
+  /// The hand being played, `None` once every hand is done.
+  pub fn hand(&self) -> Option<&Hand> {
+    self.hands.get(self.current)
+  }
+
+  pub fn dealer_up(&self) -> Card {
+    self.dealer[0]
+  }
+
+  /// The cards of the dealer, the hole card second.
+  pub fn dealer(&self) -> &[Card] {
+    &self.dealer
+  }
+
+  pub fn is_over(&self) -> bool {
+    self.current == self.hands.len()
+  }
+
+  /// The actions allowed on the current hand, none once the round is over.
+  pub fn allowed(&self) -> Vec<Action> {
+    let mut actions = Vec::new();
+
+    if let Some(hand) = self.hand() {
+      actions.push(Action::Hit);
+      actions.push(Action::Stand);
+
+      if hand.cards.len() == 2 && (! hand.split || self.rules.double_after_split) {
+        actions.push(Action::Double);
+      }
+      if hand.is_pair() && self.hands.len() < self.rules.max_hands {
+        actions.push(Action::Split);
+      }
+    }
+
+    actions
+  }
+
+  pub fn apply(&mut self, action: Action, shoe: &mut Shoe) -> BlackjackResult<()> {
+    if self.is_over() {
+      return Err(BlackjackErr::RoundOver);
+    }
+    if ! self.allowed().contains(&action) {
+      return Err(BlackjackErr::NotAllowed);
+    }
+
+    let current = self.current;
+
+    match action {
+      Action::Hit => self.hands[current].push(shoe.draw()),
+      Action::Stand => self.hands[current].done = true,
+      Action::Double => {
+        let hand = &mut self.hands[current];
+        hand.bet *= 2.0;
+        hand.push(shoe.draw());
+        hand.done = true;
+      },
+      Action::Split => {
+        let aces = self.hands[current].cards[0].rank() == Rank::Ace;
+        let second = self.hands[current].cards.pop().unwrap();
+        self.hands[current].split = true;
+        self.hands.insert(current + 1, Hand::new(vec![second], true));
+
+        for i in current..current + 2 {
+          self.hands[i].push(shoe.draw());
+          if aces {
+            self.hands[i].done = true;
+          }
+        }
+      },
+    }
+
+    while self.current < self.hands.len() && self.hands[self.current].done {
+      self.current += 1;
+    }
+    Ok(())
+  }
+
+  /// Play the dealer's hand, unless every hand of the player is bust or the
+  /// round ended on a blackjack, and settle. Returns the net win of the
+  /// player, negative for a loss.
+  pub fn finish(&mut self, shoe: &mut Shoe) -> BlackjackResult<f64> {
+    if ! self.is_over() {
+      return Err(BlackjackErr::NotAllowed);
+    }
+
+    let dealer_blackjack = total(&self.dealer).0 == 21;
+    let player = &self.hands[0];
+
+    if self.hands.len() == 1 && (player.is_blackjack() || dealer_blackjack) {
+      return Ok(if player.is_blackjack() && dealer_blackjack {
+        0.0
+      } else if player.is_blackjack() {
+        self.rules.blackjack_pays
+      } else {
+        -player.bet
+      });
+    }
+
+    if self.hands.iter().any(|h| ! h.is_bust()) {
+      loop {
+        let (value, soft) = total(&self.dealer);
+        if value > 17 || (value == 17 && ! (soft && self.rules.hit_soft_17)) {
+          break;
+        }
+        self.dealer.push(shoe.draw());
+      }
+    }
+
+    let dealer = total(&self.dealer).0;
+
+    Ok(self.hands.iter().map(|h| {
+      let value = h.total();
+      if value > 21 {
+        -h.bet
+      } else if dealer > 21 || value > dealer {
+        h.bet
+      } else if value < dealer {
+        -h.bet
+      } else {
+        0.0
+      }
+    }).sum())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  /// Shoe dealing `s` in order after the cards of one earlier round.
+  fn shoe(s: &[&str]) -> Shoe {
+    Shoe::with_cards(cards(s), s.len(), 0)
+  }
+
+  #[test]
+  fn totals() {
+    assert_eq!(total(&cards(&["AS", "6H"])), (17, true));
+    assert_eq!(total(&cards(&["AS", "6H", "KD"])), (17, false));
+    assert_eq!(total(&cards(&["AS", "AH", "9D"])), (21, true));
+    assert_eq!(total(&cards(&["QS", "JH", "2D"])), (22, false));
+    assert_eq!(card_value("KC".parse().unwrap()), 10);
+  }
+
+  #[test]
+  fn blackjack_ends_the_round() {
+    let rules = BlackjackRules::new();
+
+    let mut s = shoe(&["AS", "9H", "KD", "8C"]);
+    let mut round = BlackjackRound::deal(&rules, &mut s);
+    assert!(round.is_over());
+    assert_eq!(round.allowed(), vec![]);
+    assert_eq!(round.apply(Action::Hit, &mut s), Err(BlackjackErr::RoundOver));
+    assert_eq!(round.finish(&mut s), Ok(1.5));
+
+    let mut s = shoe(&["5S", "AH", "KD", "KC"]);
+    let mut round = BlackjackRound::deal(&rules, &mut s);
+    assert!(round.is_over());
+    assert_eq!(round.finish(&mut s), Ok(-1.0));
+  }
+
+  #[test]
+  fn dealer_soft_17() {
+    let mut rules = BlackjackRules::new();
+
+    let mut s = shoe(&["TS", "AH", "8D", "6C", "4H"]);
+    let mut round = BlackjackRound::deal(&rules, &mut s);
+    round.apply(Action::Stand, &mut s).unwrap();
+    assert_eq!(round.finish(&mut s), Ok(1.0), "dealer stands on soft 17");
+
+    rules.hit_soft_17 = true;
+    let mut s = shoe(&["TS", "AH", "8D", "6C", "4H"]);
+    let mut round = BlackjackRound::deal(&rules, &mut s);
+    round.apply(Action::Stand, &mut s).unwrap();
+    assert_eq!(round.finish(&mut s), Ok(-1.0), "dealer hits soft 17 to 21");
+  }
+
+  #[test]
+  fn double_and_split() {
+    let rules = BlackjackRules::new();
+
+    let mut s = shoe(&["6S", "TH", "5D", "7C", "TC"]);
+    let mut round = BlackjackRound::deal(&rules, &mut s);
+    assert_eq!(round.allowed(), vec![Action::Hit, Action::Stand, Action::Double]);
+    round.apply(Action::Double, &mut s).unwrap();
+    assert!(round.is_over());
+    assert_eq!(round.finish(&mut s), Ok(2.0), "doubled 21 against 17");
+
+    let mut s = shoe(&["8S", "TH", "8D", "9C", "3H", "2C", "KS", "TD"]);
+    let mut round = BlackjackRound::deal(&rules, &mut s);
+    assert!(round.allowed().contains(&Action::Split));
+    round.apply(Action::Split, &mut s).unwrap();
+    assert_eq!(round.hands().len(), 2);
+    assert_eq!(round.hand().unwrap().cards(), &cards(&["8S", "3H"])[..]);
+    round.apply(Action::Double, &mut s).unwrap();
+    assert_eq!(round.hand().unwrap().cards(), &cards(&["8D", "2C"])[..]);
+    round.apply(Action::Hit, &mut s).unwrap();
+    round.apply(Action::Stand, &mut s).unwrap();
+    assert!(round.is_over());
+    assert_eq!(round.finish(&mut s), Ok(3.0), "doubled 21 and 20 against 19");
+  }
+
+  #[test]
+  fn split_aces_draw_one_card() {
+    let rules = BlackjackRules::new();
+    let mut s = shoe(&["AS", "9H", "AD", "8C", "KH", "5D"]);
+    let mut round = BlackjackRound::deal(&rules, &mut s);
+    round.apply(Action::Split, &mut s).unwrap();
+
+    assert!(round.is_over());
+    assert!(! round.hands()[0].is_blackjack(), "a split 21 is no blackjack");
+    assert_eq!(round.finish(&mut s), Ok(0.0), "21 wins and 16 loses against 17");
+  }
+
+  #[test]
+  fn shoe_reshuffles_discards() {
+    let rules = BlackjackRules { decks: 1, ..BlackjackRules::new() };
+    let mut s = Shoe::new(rules.decks, rules.penetration, 3);
+    assert_eq!(s.len(), 52);
+
+    let mut rounds = 0;
+    while ! s.is_finished() {
+      let mut round = BlackjackRound::deal(&rules, &mut s);
+      while ! round.is_over() {
+        round.apply(Action::Hit, &mut s).unwrap();
+      }
+      round.finish(&mut s).unwrap();
+      rounds += 1;
+    }
+    assert!(rounds > 1 && s.dealt() >= 39);
+
+    let mut s = Shoe::with_cards(cards(&["2S", "3S", "4S", "5S", "2H", "3H", "2D", "3D"]), 4, 0);
+    let mut round = BlackjackRound::deal(&rules, &mut s);
+    round.apply(Action::Stand, &mut s).unwrap();
+    assert_eq!(round.finish(&mut s), Ok(-1.0), "6 against 17");
+    assert_eq!(s.dealt(), 8);
+
+    let round = BlackjackRound::deal(&rules, &mut s);
+    assert_eq!(s.len(), 8, "discards back in the shoe");
+    assert!(s.is_finished());
+    assert_eq!(round.hands()[0].cards().len(), 2);
+  }
+}
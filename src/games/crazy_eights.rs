@@ -0,0 +1,549 @@
+//! Crazy Eights for two to seven players.
+//!
+//! Every player is dealt `hand_len` cards and the next card of the stock is
+//! turned up to start the discard pile; an eight turned up goes back under
+//! the stock for the next card. Starting left of the dealer each player in
+//! turn plays a card matching the suit or the rank of the top of the pile,
+//! or an eight, which names the suit to be followed. A player who cannot play
+//! draws from the stock until they can, or until they have drawn
+//! `draw_limit` cards and pass. The pile but its top card is shuffled into a
+//! new stock when the stock runs out. The first player out of cards wins and
+//! scores the `penalty_points` of the cards left in the other hands.
+//!
+//! With `two_draws` a two makes the next player draw that many cards instead
+//! of playing, and with `stacking` they may play a two of their own to pass
+//! the draw, increased, on to the next player.
+
+use cards::french::{self, FrenchPlayingCard, Rank, Suit};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+pub type Card = FrenchPlayingCard;
+
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 7;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CrazyEightsRules {
+  pub players: usize,
+  /// Cards dealt to each player.
+  pub hand_len: usize,
+  /// Most cards a player who cannot play draws before passing, `None` to
+  /// draw until they can play.
+  pub draw_limit: Option<usize>,
+  /// Cards the player after a two draws, 0 when twos are plain cards.
+  pub two_draws: usize,
+  /// Whether a player made to draw by a two may play a two instead.
+  pub stacking: bool,
+  /// Turns played before a game is stopped undecided.
+  pub max_turns: usize,
+}
+
+impl CrazyEightsRules {
+  /// Seven cards each for two players and five for more, drawing until
+  /// able to play, twos without effect and at most 1000 turns.
+  pub fn new(players: usize) -> CrazyEightsRules {
+    CrazyEightsRules {
+      players: players,
+      hand_len: if players == 2 { 7 } else { 5 },
+      draw_limit: None,
+      two_draws: 0,
+      stacking: false,
+      max_turns: 1000,
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CrazyEightsErr {
+  GameOver,
+  NotInHand,
+  /// The card neither matches the top of the pile nor is an eight, or a two
+  /// has to be drawn for.
+  IllegalPlay,
+  /// An eight was played without naming a suit.
+  NoSuit,
+  /// Drawing while holding a card that can be played.
+  MustPlay,
+}
+
+pub type CrazyEightsResult<T> = Result<T, CrazyEightsErr>;
+
+/// How a game of crazy eights ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CrazyEightsOutcome {
+  /// The player went out of cards.
+  Winner(usize),
+  /// Every player passed in a row with nothing left to draw.
+  Blocked,
+  /// Stopped after `max_turns` turns.
+  TurnLimit,
+}
+
+/// Penalty points of a card left in a hand at the end of a game: 50 for an
+/// eight, 10 for the picture cards, 1 for an ace and the pips otherwise.
+pub fn card_points(card: Card) -> u32 {
+  match card.rank() {
+    Rank::Number(8) => 50,
+    Rank::Jack | Rank::Queen | Rank::King => 10,
+    rank => rank.value() as u32,
+  }
+}
+
+/// Penalty points of `hand`.
+pub fn penalty_points(hand: &[Card]) -> u32 {
+  hand.iter().map(|c| card_points(*c)).sum()
+}
+
+pub fn is_eight(card: Card) -> bool {
+  card.rank() == Rank::Number(8)
+}
+
+fn is_two(card: Card) -> bool {
+  card.rank() == Rank::Number(2)
+}
+
+#[derive(Clone)]
+pub struct CrazyEightsGame {
+  rules: CrazyEightsRules,
+  hands: Vec<Vec<Card>>,
+  /// The top card last.
+  stock: Vec<Card>,
+  /// The top card last.
+  pile: Vec<Card>,
+  /// The suit to be followed, named by the last eight played.
+  suit: Suit,
+  to_play: usize,
+  /// Cards the player to play has to draw for the twos played before them.
+  pending: usize,
+  /// Cards the player to play has drawn this turn.
+  drawn: usize,
+  /// Players who passed in a row without drawing a card.
+  passes: usize,
+  turns: usize,
+  rng: Isaac64Rng,
+  outcome: Option<CrazyEightsOutcome>,
+}
+
+impl CrazyEightsGame {
+  /// New game of `rules` with a standard deck arranged by `shuffle` and dealt
+  /// one card at a time from the player after `dealer`. The stock is
+  /// reshuffled with a generator seeded from the deal, so the same deal
+  /// always plays the same way for the same plays.
+  pub fn new<F>(rules: CrazyEightsRules, dealer: usize, mut shuffle: F) -> CrazyEightsGame
+    where F: FnMut(&mut Vec<Card>) {
+    assert!(rules.players >= MIN_PLAYERS && rules.players <= MAX_PLAYERS, "unsupported number of players");
+    assert!(rules.players * rules.hand_len < 52, "not enough cards to deal");
+
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut hasher = DefaultHasher::new();
+    cards.hash(&mut hasher);
+
+    // Deal from the top of the deck, the end of the vector.
+    let mut hands = vec![Vec::with_capacity(rules.hand_len); rules.players];
+    for i in 0..rules.players * rules.hand_len {
+      hands[(dealer + 1 + i) % rules.players].push(cards.pop().unwrap());
+    }
+
+    while is_eight(*cards.last().unwrap()) {
+      let eight = cards.pop().unwrap();
+      cards.insert(0, eight);
+    }
+    let starter = cards.pop().unwrap();
+
+    CrazyEightsGame {
+      rules: rules,
+      hands: hands,
+      stock: cards,
+      pile: vec![starter],
+      suit: starter.suit(),
+      to_play: (dealer + 1) % rules.players,
+      pending: 0,
+      drawn: 0,
+      passes: 0,
+      turns: 0,
+      rng: Isaac64Rng::from_seed(&[hasher.finish()]),
+      outcome: None,
+    }
+  }
+
+  pub fn rules(&self) -> &CrazyEightsRules {
+    &self.rules
+  }
+
+  pub fn hand(&self, player: usize) -> &[Card] {
+    &self.hands[player]
+  }
+
+  pub fn stock_len(&self) -> usize {
+    self.stock.len()
+  }
+
+  /// The discard pile, the top card last.
+  pub fn pile(&self) -> &[Card] {
+    &self.pile
+  }
+
+  pub fn top(&self) -> Card {
+    *self.pile.last().unwrap()
+  }
+
+  /// The suit to be followed: that of the top card, or the one named for
+  /// an eight.
+  pub fn suit(&self) -> Suit {
+    self.suit
+  }
+
+  pub fn to_play(&self) -> usize {
+    self.to_play
+  }
+
+  /// Cards the player to play has to draw for twos, unless they stack one.
+  pub fn pending_draws(&self) -> usize {
+    self.pending
+  }
+
+  /// Cards the player to play has drawn so far this turn.
+  pub fn drawn(&self) -> usize {
+    self.drawn
+  }
+
+  /// Turns played, a pass counting as a turn.
+  pub fn turns(&self) -> usize {
+    self.turns
+  }
+
+  /// How the game ended, `None` while it goes on.
+  pub fn outcome(&self) -> Option<CrazyEightsOutcome> {
+    self.outcome
+  }
+
+  pub fn winner(&self) -> Option<usize> {
+    match self.outcome {
+      Some(CrazyEightsOutcome::Winner(p)) => Some(p),
+      _ => None,
+    }
+  }
+
+  /// Points of the winner, the penalty points of every other hand.
+  pub fn winner_points(&self) -> Option<u32> {
+    self.winner().map(|_| self.hands.iter().map(|h| penalty_points(h)).sum())
+  }
+
+  /// Whether the player to play may play `card`, ignoring whether they hold
+  /// it.
+  pub fn can_play(&self, card: Card) -> bool {
+    if self.pending > 0 {
+      return self.rules.stacking && is_two(card);
+    }
+
+    is_eight(card) || card.suit() == self.suit || card.rank() == self.top().rank()
+  }
+
+  /// The cards of the player to play they may play.
+  pub fn legal_plays(&self) -> Vec<Card> {
+    if self.outcome.is_some() {
+      return Vec::new();
+    }
+
+    self.hands[self.to_play].iter().cloned().filter(|c| self.can_play(*c)).collect()
+  }
+
+  /// Play `card` for the player to play, naming `suit` for an eight. The
+  /// suit is ignored for other cards.
+  pub fn play(&mut self, card: Card, suit: Option<Suit>) -> CrazyEightsResult<()> {
+    if self.outcome.is_some() {
+      return Err(CrazyEightsErr::GameOver);
+    }
+
+    let player = self.to_play;
+    let index = match self.hands[player].iter().position(|c| *c == card) {
+      Some(index) => index,
+      None => return Err(CrazyEightsErr::NotInHand),
+    };
+    if ! self.can_play(card) {
+      return Err(CrazyEightsErr::IllegalPlay);
+    }
+    if is_eight(card) && suit.is_none() {
+      return Err(CrazyEightsErr::NoSuit);
+    }
+
+    self.hands[player].remove(index);
+    self.pile.push(card);
+    self.suit = if is_eight(card) { suit.unwrap() } else { card.suit() };
+    if is_two(card) {
+      self.pending += self.rules.two_draws;
+    }
+
+    self.passes = 0;
+    if self.hands[player].is_empty() {
+      self.outcome = Some(CrazyEightsOutcome::Winner(player));
+      return Ok(());
+    }
+
+    self.next_turn();
+    Ok(())
+  }
+
+  /// Draw for the player to play: all the cards owed for twos, ending the
+  /// turn, or one card when they cannot play. The turn passes once they
+  /// have drawn `draw_limit` cards without one they can play, or when there
+  /// is nothing left to draw.
+  pub fn draw(&mut self) -> CrazyEightsResult<()> {
+    if self.outcome.is_some() {
+      return Err(CrazyEightsErr::GameOver);
+    }
+
+    let player = self.to_play;
+
+    if self.pending > 0 {
+      for _ in 0..self.pending {
+        match self.take() {
+          Some(card) => self.hands[player].push(card),
+          None => break,
+        }
+      }
+      self.pending = 0;
+      self.passes = 0;
+      self.next_turn();
+      return Ok(());
+    }
+
+    if ! self.legal_plays().is_empty() {
+      return Err(CrazyEightsErr::MustPlay);
+    }
+
+    let card = match self.take() {
+      Some(card) => card,
+      None => {
+        self.passes += 1;
+        if self.passes >= self.rules.players {
+          self.outcome = Some(CrazyEightsOutcome::Blocked);
+        } else {
+          self.next_turn();
+        }
+        return Ok(());
+      },
+    };
+
+    self.hands[player].push(card);
+    self.drawn += 1;
+    self.passes = 0;
+
+    let limit = self.rules.draw_limit.map_or(false, |limit| self.drawn >= limit);
+    if limit && ! self.can_play(card) {
+      self.next_turn();
+    }
+
+    Ok(())
+  }
+
+  /// The top card of the stock, shuffling the pile but its top card into a
+  /// new stock when it is empty.
+  fn take(&mut self) -> Option<Card> {
+    if self.stock.is_empty() && self.pile.len() > 1 {
+      let top = self.pile.pop().unwrap();
+      self.stock.extend(self.pile.drain(..));
+      self.rng.shuffle(&mut self.stock);
+      self.pile.push(top);
+    }
+
+    self.stock.pop()
+  }
+
+  fn next_turn(&mut self) {
+    self.to_play = (self.to_play + 1) % self.rules.players;
+    self.drawn = 0;
+    self.turns += 1;
+
+    if self.turns >= self.rules.max_turns {
+      self.outcome = Some(CrazyEightsOutcome::TurnLimit);
+    }
+  }
+
+  /// The game as `player` sees it.
+  pub fn view(&self, player: usize) -> CrazyEightsView<'_> {
+    CrazyEightsView {
+      game: self,
+      player: player,
+    }
+  }
+}
+
+/// What one player knows of a game: their own hand, the discard pile and how
+/// many cards the others and the stock hold.
+#[derive(Clone, Copy)]
+pub struct CrazyEightsView<'a> {
+  game: &'a CrazyEightsGame,
+  player: usize,
+}
+
+impl<'a> CrazyEightsView<'a> {
+  pub fn player(&self) -> usize {
+    self.player
+  }
+
+  pub fn players(&self) -> usize {
+    self.game.rules.players
+  }
+
+  pub fn rules(&self) -> &'a CrazyEightsRules {
+    &self.game.rules
+  }
+
+  pub fn hand(&self) -> &'a [Card] {
+    &self.game.hands[self.player]
+  }
+
+  /// Number of cards in the hand of `player`.
+  pub fn hand_len(&self, player: usize) -> usize {
+    self.game.hands[player].len()
+  }
+
+  pub fn stock_len(&self) -> usize {
+    self.game.stock.len()
+  }
+
+  pub fn pile(&self) -> &'a [Card] {
+    &self.game.pile
+  }
+
+  pub fn top(&self) -> Card {
+    self.game.top()
+  }
+
+  pub fn suit(&self) -> Suit {
+    self.game.suit
+  }
+
+  pub fn pending_draws(&self) -> usize {
+    self.game.pending
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| card(c)).collect()
+  }
+
+  /// Two player game with the given hands, top of the pile and stock, the
+  /// top of the stock last.
+  fn game(rules: CrazyEightsRules, hands: &[&[&str]], top: &str, stock: &[&str]) -> CrazyEightsGame {
+    let mut game = CrazyEightsGame::new(rules, 1, |_| ());
+    game.hands = hands.iter().map(|h| cards(h)).collect();
+    game.pile = vec![card(top)];
+    game.suit = card(top).suit();
+    game.stock = cards(stock);
+    game
+  }
+
+  #[test]
+  fn deal() {
+    let game = CrazyEightsGame::new(CrazyEightsRules::new(4), 3, |_| ());
+    let total: usize = (0..4).map(|p| game.hand(p).len()).sum();
+
+    assert_eq!(game.to_play(), 0);
+    assert!((0..4).all(|p| game.hand(p).len() == 5));
+    assert_eq!(total + game.stock_len() + game.pile().len(), 52);
+    assert!(! is_eight(game.top()));
+    assert_eq!(game.suit(), game.top().suit());
+  }
+
+  #[test]
+  fn points() {
+    assert_eq!(penalty_points(&cards(&["8S", "KH", "AD", "7C"])), 68);
+  }
+
+  #[test]
+  fn plays_and_eights() {
+    let mut game = game(CrazyEightsRules::new(2), &[&["5H", "9C", "8D"], &["9S", "KD"]], "5S", &["2C"]);
+
+    assert_eq!(game.legal_plays(), cards(&["5H", "8D"]));
+    assert_eq!(game.play(card("9C"), None), Err(CrazyEightsErr::IllegalPlay));
+    assert_eq!(game.play(card("KD"), None), Err(CrazyEightsErr::NotInHand));
+    assert_eq!(game.play(card("8D"), None), Err(CrazyEightsErr::NoSuit));
+    assert_eq!(game.draw(), Err(CrazyEightsErr::MustPlay));
+
+    game.play(card("8D"), Some(Suit::Clubs)).unwrap();
+    assert_eq!(game.suit(), Suit::Clubs);
+    assert_eq!(game.to_play(), 1);
+    assert!(game.legal_plays().is_empty());
+  }
+
+  #[test]
+  fn drawing() {
+    let mut rules = CrazyEightsRules::new(2);
+    let mut game1 = game(rules, &[&["KH", "AH"], &["9S"]], "5S", &["QC", "6S", "4D"]);
+
+    // Draws until able to play.
+    game1.draw().unwrap();
+    game1.draw().unwrap();
+    assert_eq!(game1.to_play(), 0);
+    assert_eq!(game1.drawn(), 2);
+    assert_eq!(game1.legal_plays(), cards(&["6S"]));
+
+    // Passes after the limit.
+    rules.draw_limit = Some(1);
+    let mut game2 = game(rules, &[&["KH", "AH"], &["9S"]], "5S", &["QC", "6S", "4D"]);
+    game2.draw().unwrap();
+    assert_eq!(game2.to_play(), 1);
+    assert_eq!(game2.hand(0).len(), 3);
+
+    // Blocked with nothing to draw.
+    let mut game3 = game(rules, &[&["KH"], &["QH"]], "5S", &[]);
+    game3.draw().unwrap();
+    assert_eq!(game3.outcome(), None);
+    game3.draw().unwrap();
+    assert_eq!(game3.outcome(), Some(CrazyEightsOutcome::Blocked));
+  }
+
+  #[test]
+  fn reshuffles_pile() {
+    let mut game = game(CrazyEightsRules::new(2), &[&["KH", "AH"], &["9S"]], "5S", &[]);
+    game.pile = cards(&["3C", "4C", "5S"]);
+
+    game.draw().unwrap();
+    assert_eq!(game.pile(), &cards(&["5S"])[..]);
+    assert_eq!(game.stock_len() + game.hand(0).len(), 4);
+  }
+
+  #[test]
+  fn twos() {
+    let mut rules = CrazyEightsRules::new(2);
+    rules.two_draws = 2;
+    rules.stacking = true;
+    let mut game = game(rules, &[&["2S", "5C", "KH"], &["2C", "9S"]], "5S", &["3D", "4D", "6D", "7D"]);
+
+    game.play(card("2S"), None).unwrap();
+    assert_eq!(game.pending_draws(), 2);
+    assert_eq!(game.legal_plays(), cards(&["2C"]));
+    game.play(card("2C"), None).unwrap();
+
+    assert_eq!(game.pending_draws(), 4);
+    assert!(game.legal_plays().is_empty(), "no two to stack");
+    game.draw().unwrap();
+    assert_eq!(game.hand(0).len(), 6);
+    assert_eq!(game.to_play(), 1);
+    assert_eq!(game.pending_draws(), 0);
+  }
+
+  #[test]
+  fn going_out() {
+    let mut game = game(CrazyEightsRules::new(2), &[&["5C"], &["8S", "KH"]], "5S", &[]);
+
+    game.play(card("5C"), None).unwrap();
+    assert_eq!(game.winner(), Some(0));
+    assert_eq!(game.winner_points(), Some(60));
+    assert_eq!(game.play(card("8S"), Some(Suit::Hearts)), Err(CrazyEightsErr::GameOver));
+  }
+}
@@ -0,0 +1,630 @@
+//! Cribbage for two players, and the scoring of cribbage hands.
+//!
+//! Each deal both players are dealt six cards and lay two of them away to the
+//! crib, which belongs to the dealer. The card under the cut is turned up as
+//! the starter, worth two points to the dealer, "his heels", when it is a
+//! jack.
+//!
+//! In the pegging the players take turns playing their cards face up, the
+//! non-dealer first, keeping a running count of their values that may not go
+//! over 31. A player who cannot play says go and the other plays on as long
+//! as they can; the last to play scores a point for the go, or two for
+//! reaching 31 exactly, and the count starts again from the player after
+//! them. Pegging also scores fifteens, pairs and runs among the last cards
+//! played, and the last card of all scores a point.
+//!
+//! In the show the four cards of each hand and the crib count with the
+//! starter: two points for every combination of cards adding up to fifteen,
+//! two for every pair, a point per card of every run of three or more, four
+//! for a flush of the hand, five with the starter, and one for "his nobs", the
+//! jack of the starter's suit. The crib only scores a flush of all five. The
+//! non-dealer shows first, then the dealer their hand and the crib. A game is
+//! won the moment a player reaches 121 points.
+
+use cards::french::{self, FrenchPlayingCard, Rank};
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_PLAYERS: usize = 2;
+pub const DEAL_LEN: usize = 6;
+pub const DISCARD_LEN: usize = 2;
+pub const HAND_LEN: usize = 4;
+pub const MAX_COUNT: u8 = 31;
+pub const WINNING_SCORE: u32 = 121;
+
+/// Value of a card in a count: 1 for an ace and 10 for the picture cards.
+pub fn card_value(card: Card) -> u8 {
+  ::std::cmp::min(card.rank().value(), 10)
+}
+
+/// Points of a hand or crib in the show, by kind.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ShowScore {
+  pub fifteens: u32,
+  pub pairs: u32,
+  pub runs: u32,
+  pub flush: u32,
+  pub nobs: u32,
+}
+
+impl ShowScore {
+  pub fn total(&self) -> u32 {
+    self.fifteens + self.pairs + self.runs + self.flush + self.nobs
+  }
+}
+
+impl fmt::Display for ShowScore {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} (fifteens {}, pairs {}, runs {}, flush {}, nobs {})",
+           self.total(), self.fifteens, self.pairs, self.runs, self.flush, self.nobs)
+  }
+}
+
+/// Points of every combination of `cards` adding up to 15, two each.
+pub fn fifteens(cards: &[Card]) -> u32 {
+  let values: Vec<u8> = cards.iter().map(|c| card_value(*c)).collect();
+
+  (1..1u32 << values.len())
+    .filter(|mask| (0..values.len()).filter(|i| mask & (1 << i) != 0).map(|i| values[i] as u32).sum::<u32>() == 15)
+    .count() as u32 * 2
+}
+
+/// Points of the pairs of `cards`, two for every two cards of a rank.
+pub fn pairs(cards: &[Card]) -> u32 {
+  let mut points = 0;
+
+  for (i, a) in cards.iter().enumerate() {
+    points += cards[i + 1..].iter().filter(|b| a.rank() == b.rank()).count() as u32 * 2;
+  }
+
+  points
+}
+
+/// Points of the runs of `cards`, in any suit and order: each run of three or
+/// more ranks in a row scores its length, once for every way of picking one
+/// card of each rank. Aces are low.
+pub fn runs(cards: &[Card]) -> u32 {
+  let mut counts = [0u32; 14];
+  for card in cards {
+    counts[card.rank().value() as usize] += 1;
+  }
+
+  let mut points = 0;
+  let mut start = 1;
+
+  while start < counts.len() {
+    let len = counts[start..].iter().take_while(|c| **c > 0).count();
+
+    if len >= 3 {
+      points += len as u32 * counts[start..start + len].iter().product::<u32>();
+    }
+    start += len + 1;
+  }
+
+  points
+}
+
+/// Points of the show of `hand`, four cards, with `starter`. A crib only
+/// scores a flush of all five cards.
+pub fn score_show(hand: &[Card], starter: Card, crib: bool) -> ShowScore {
+  let mut cards = hand.to_vec();
+  cards.push(starter);
+
+  let suit = hand[0].suit();
+  let flush = if ! hand.iter().all(|c| c.suit() == suit) {
+    0
+  } else if starter.suit() == suit {
+    5
+  } else if crib {
+    0
+  } else {
+    4
+  };
+
+  ShowScore {
+    fifteens: fifteens(&cards),
+    pairs: pairs(&cards),
+    runs: runs(&cards),
+    flush: flush,
+    nobs: if hand.iter().any(|c| c.rank() == Rank::Jack && c.suit() == starter.suit()) { 1 } else { 0 },
+  }
+}
+
+/// Points for playing the last of `count`, the cards played since the count
+/// last started: fifteen or 31, the pairs formed by the cards of its rank
+/// played just before it, and the longest run among the last cards played.
+/// Go and last card points are left to the game.
+pub fn score_peg(count: &[Card]) -> u32 {
+  let last = match count.last() {
+    Some(last) => *last,
+    None => return 0,
+  };
+
+  let total: u32 = count.iter().map(|c| card_value(*c) as u32).sum();
+  let mut points = if total == 15 || total == MAX_COUNT as u32 { 2 } else { 0 };
+
+  let same = count.iter().rev().take_while(|c| c.rank() == last.rank()).count() as u32;
+  points += same * (same - 1);
+
+  for len in (3..count.len() + 1).rev() {
+    let mut ranks: Vec<u8> = count[count.len() - len..].iter().map(|c| c.rank().value()).collect();
+    ranks.sort();
+    ranks.dedup();
+
+    if ranks.len() == len && ranks[len - 1] - ranks[0] == len as u8 - 1 {
+      points += len as u32;
+      break;
+    }
+  }
+
+  points
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CribbageErr {
+  /// Cards are laid away once by each player, before the pegging.
+  NotDiscarding,
+  /// The cards laid away are not two different cards of the hand.
+  InvalidDiscard,
+  /// Cards are pegged after both players have laid away.
+  NotPegging,
+  /// The card is not in the hand of the player to peg, or would take the
+  /// count over 31.
+  IllegalPeg,
+}
+
+pub type CribbageResult<T> = Result<T, CribbageErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Phase {
+  Discard,
+  Pegging,
+  /// The deal has been shown, or a player has won.
+  Over,
+}
+
+/// Points scored in a deal, by where they came from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DealPoints {
+  pub heels: u32,
+  pub pegging: [u32; NUM_PLAYERS],
+  pub hand: [u32; NUM_PLAYERS],
+  pub crib: u32,
+}
+
+/// One deal of a game, starting from the scores of the deals before it.
+#[derive(Debug, Clone)]
+pub struct CribbageGame {
+  dealer: usize,
+  scores: [u32; NUM_PLAYERS],
+  /// The cards kept by each player, for the show.
+  hands: Vec<Vec<Card>>,
+  /// The cards each player has not pegged yet.
+  unplayed: Vec<Vec<Card>>,
+  discarded: [bool; NUM_PLAYERS],
+  crib: Vec<Card>,
+  /// The rest of the deck, the starter on top.
+  stock: Vec<Card>,
+  starter: Option<Card>,
+  phase: Phase,
+  /// Cards played since the count last started.
+  count: Vec<Card>,
+  /// Every card pegged, in order.
+  pegged: Vec<(usize, Card)>,
+  to_play: usize,
+  points: DealPoints,
+}
+
+impl CribbageGame {
+  /// Deal a standard deck arranged by `shuffle`, one card at a time starting
+  /// with the player after `dealer`, to players who have `scores` points.
+  pub fn new<F>(dealer: usize, scores: [u32; NUM_PLAYERS], mut shuffle: F) -> CribbageGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut hands = vec![Vec::with_capacity(DEAL_LEN); NUM_PLAYERS];
+    for (i, card) in cards.drain(..NUM_PLAYERS * DEAL_LEN).enumerate() {
+      hands[(dealer + 1 + i) % NUM_PLAYERS].push(card);
+    }
+
+    CribbageGame {
+      dealer: dealer,
+      scores: scores,
+      unplayed: hands.clone(),
+      hands: hands,
+      discarded: [false; NUM_PLAYERS],
+      crib: Vec::with_capacity(HAND_LEN),
+      stock: cards,
+      starter: None,
+      phase: Phase::Discard,
+      count: Vec::new(),
+      pegged: Vec::new(),
+      to_play: (dealer + 1) % NUM_PLAYERS,
+      points: DealPoints::default(),
+    }
+  }
+
+  pub fn dealer(&self) -> usize {
+    self.dealer
+  }
+
+  pub fn phase(&self) -> Phase {
+    self.phase
+  }
+
+  pub fn scores(&self) -> [u32; NUM_PLAYERS] {
+    self.scores
+  }
+
+  /// The player who has reached 121 points, if one has.
+  pub fn winner(&self) -> Option<usize> {
+    (0..NUM_PLAYERS).find(|p| self.scores[*p] >= WINNING_SCORE)
+  }
+
+  /// The cards of `player`: all six before laying away, the four kept after.
+  pub fn hand(&self, player: usize) -> &[Card] {
+    &self.hands[player]
+  }
+
+  /// The cards `player` has still to peg.
+  pub fn unplayed(&self, player: usize) -> &[Card] {
+    &self.unplayed[player]
+  }
+
+  pub fn has_discarded(&self, player: usize) -> bool {
+    self.discarded[player]
+  }
+
+  pub fn crib(&self) -> &[Card] {
+    &self.crib
+  }
+
+  pub fn starter(&self) -> Option<Card> {
+    self.starter
+  }
+
+  /// Cards played since the count last started.
+  pub fn count(&self) -> &[Card] {
+    &self.count
+  }
+
+  pub fn count_value(&self) -> u8 {
+    self.count.iter().map(|c| card_value(*c)).sum()
+  }
+
+  /// Every card pegged so far and who played it.
+  pub fn pegged(&self) -> &[(usize, Card)] {
+    &self.pegged
+  }
+
+  /// The player to peg a card.
+  pub fn to_play(&self) -> usize {
+    self.to_play
+  }
+
+  pub fn points(&self) -> &DealPoints {
+    &self.points
+  }
+
+  /// Lay away `cards`, two of the six cards of `player`, to the crib. The
+  /// starter is cut once both players have.
+  pub fn discard(&mut self, player: usize, cards: &[Card]) -> CribbageResult<()> {
+    if self.phase != Phase::Discard || self.discarded[player] {
+      return Err(CribbageErr::NotDiscarding);
+    }
+
+    let hand = &self.hands[player];
+    if cards.len() != DISCARD_LEN || cards[0] == cards[1] || ! cards.iter().all(|c| hand.contains(c)) {
+      return Err(CribbageErr::InvalidDiscard);
+    }
+
+    self.hands[player].retain(|c| ! cards.contains(c));
+    self.unplayed[player] = self.hands[player].clone();
+    self.crib.extend(cards.iter().cloned());
+    self.discarded[player] = true;
+
+    if self.discarded.iter().all(|d| *d) {
+      let starter = self.stock.pop().unwrap();
+      self.starter = Some(starter);
+      self.phase = Phase::Pegging;
+
+      if starter.rank() == Rank::Jack {
+        self.points.heels = 2;
+        let dealer = self.dealer;
+        self.add(dealer, 2);
+      }
+    }
+
+    Ok(())
+  }
+
+  fn can_peg(&self, player: usize) -> bool {
+    let count = self.count_value();
+    self.unplayed[player].iter().any(|c| count + card_value(*c) <= MAX_COUNT)
+  }
+
+  /// The cards the player to play may peg.
+  pub fn legal_pegs(&self) -> Vec<Card> {
+    if self.phase != Phase::Pegging {
+      return Vec::new();
+    }
+
+    let count = self.count_value();
+    self.unplayed[self.to_play].iter().cloned().filter(|c| count + card_value(*c) <= MAX_COUNT).collect()
+  }
+
+  /// Peg `card` for the player to play. Goes and the end of a count are
+  /// scored as they happen, and the show follows the last card.
+  pub fn peg(&mut self, card: Card) -> CribbageResult<()> {
+    if self.phase != Phase::Pegging {
+      return Err(CribbageErr::NotPegging);
+    }
+    if ! self.legal_pegs().contains(&card) {
+      return Err(CribbageErr::IllegalPeg);
+    }
+
+    let player = self.to_play;
+    self.unplayed[player].retain(|c| *c != card);
+    self.count.push(card);
+    self.pegged.push((player, card));
+
+    let points = score_peg(&self.count);
+    self.points.pegging[player] += points;
+    self.add(player, points);
+    if self.phase == Phase::Over {
+      return Ok(());
+    }
+
+    let other = 1 - player;
+    let done = self.unplayed.iter().all(|h| h.is_empty());
+
+    if self.count_value() == MAX_COUNT {
+      self.count.clear();
+    } else if done || ! (self.can_peg(other) || self.can_peg(player)) {
+      // A go, or the last card.
+      self.points.pegging[player] += 1;
+      self.add(player, 1);
+      self.count.clear();
+    }
+
+    if self.phase == Phase::Over {
+      return Ok(());
+    }
+    if done {
+      self.show();
+      return Ok(());
+    }
+
+    self.to_play = if self.can_peg(other) { other } else { player };
+    Ok(())
+  }
+
+  /// Score the hands and the crib, stopping when a player wins.
+  fn show(&mut self) {
+    let starter = self.starter.unwrap();
+    let dealer = self.dealer;
+    let pone = 1 - dealer;
+
+    for &(player, crib) in &[(pone, false), (dealer, false), (dealer, true)] {
+      let cards = if crib { &self.crib } else { &self.hands[player] };
+      let points = score_show(cards, starter, crib).total();
+
+      if crib {
+        self.points.crib = points;
+      } else {
+        self.points.hand[player] = points;
+      }
+
+      self.add(player, points);
+      if self.phase == Phase::Over {
+        return;
+      }
+    }
+
+    self.phase = Phase::Over;
+  }
+
+  fn add(&mut self, player: usize, points: u32) {
+    self.scores[player] += points;
+    if self.scores[player] >= WINNING_SCORE {
+      self.phase = Phase::Over;
+    }
+  }
+
+  /// The deal as `player` sees it.
+  pub fn view(&self, player: usize) -> CribbageView<'_> {
+    CribbageView {
+      game: self,
+      player: player,
+    }
+  }
+}
+
+/// What one player knows of a deal: their own cards, the starter and the
+/// cards pegged, but not the other hand or the crib.
+#[derive(Debug, Copy, Clone)]
+pub struct CribbageView<'a> {
+  game: &'a CribbageGame,
+  player: usize,
+}
+
+impl<'a> CribbageView<'a> {
+  pub fn player(&self) -> usize {
+    self.player
+  }
+
+  pub fn is_dealer(&self) -> bool {
+    self.game.dealer() == self.player
+  }
+
+  pub fn scores(&self) -> [u32; NUM_PLAYERS] {
+    self.game.scores()
+  }
+
+  pub fn hand(&self) -> &'a [Card] {
+    self.game.hand(self.player)
+  }
+
+  pub fn unplayed(&self) -> &'a [Card] {
+    self.game.unplayed(self.player)
+  }
+
+  pub fn starter(&self) -> Option<Card> {
+    self.game.starter()
+  }
+
+  pub fn count(&self) -> &'a [Card] {
+    self.game.count()
+  }
+
+  pub fn count_value(&self) -> u8 {
+    self.game.count_value()
+  }
+
+  pub fn pegged(&self) -> &'a [(usize, Card)] {
+    self.game.pegged()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn show_scores() {
+    let best = score_show(&cards(&["5S", "5H", "5D", "JC"]), card("5C"), false);
+    assert_eq!(best, ShowScore { fifteens: 16, pairs: 12, runs: 0, flush: 0, nobs: 1 });
+    assert_eq!(best.total(), 29);
+
+    let double_run = score_show(&cards(&["4S", "5H", "5D", "6C"]), card("KH"), false);
+    assert_eq!(double_run, ShowScore { fifteens: 8, pairs: 2, runs: 6, flush: 0, nobs: 0 });
+
+    let flush = score_show(&cards(&["2H", "4H", "8H", "TH"]), card("QS"), false);
+    assert_eq!(flush.flush, 4);
+    assert_eq!(score_show(&cards(&["2H", "4H", "8H", "TH"]), card("QS"), true).flush, 0, "crib flushes need the starter");
+    assert_eq!(score_show(&cards(&["2H", "4H", "8H", "TH"]), card("QH"), true).flush, 5);
+
+    assert_eq!(score_show(&cards(&["AS", "2S", "3H", "3D"]), card("3C"), false).runs, 9, "triple run");
+    assert_eq!(score_show(&cards(&["9S", "TS", "JH", "QD"]), card("KC"), false).runs, 5);
+    assert_eq!(score_show(&cards(&["QS", "KS", "AH", "2D"]), card("7C"), false).runs, 0, "aces are low");
+    assert_eq!(score_show(&cards(&["2S", "4S", "6H", "8D"]), card("TC"), false).total(), 0);
+  }
+
+  #[test]
+  fn peg_scores() {
+    assert_eq!(score_peg(&cards(&["7S", "8H"])), 2, "fifteen");
+    assert_eq!(score_peg(&cards(&["7S", "7H", "7D"])), 6, "pair royal");
+    assert_eq!(score_peg(&cards(&["7S", "7H", "7D", "7C"])), 12);
+    assert_eq!(score_peg(&cards(&["4S", "6H", "5D"])), 5, "fifteen and a run");
+    assert_eq!(score_peg(&cards(&["3S", "2H", "4D", "6C", "5C"])), 5, "run of five out of order");
+    assert_eq!(score_peg(&cards(&["2S", "2H", "3D", "4C"])), 3, "the run of the last three");
+    assert_eq!(score_peg(&cards(&["KS", "QH", "7D", "AC", "2C"])), 0);
+    assert_eq!(score_peg(&cards(&["KS", "QH", "9D", "2C"])), 2, "thirty-one");
+  }
+
+  #[test]
+  fn discard_and_starter() {
+    let mut game = CribbageGame::new(1, [0, 0], |c| seeded_shuffle(1, c));
+    assert_eq!(game.hand(0).len(), DEAL_LEN);
+    assert_eq!(game.legal_pegs(), vec![]);
+
+    let bad = vec![game.hand(0)[0], game.hand(1)[0]];
+    assert_eq!(game.discard(0, &bad), Err(CribbageErr::InvalidDiscard));
+
+    let discard = game.hand(0)[..2].to_vec();
+    game.discard(0, &discard).unwrap();
+    assert_eq!(game.discard(0, &discard), Err(CribbageErr::NotDiscarding));
+    assert_eq!(game.starter(), None);
+
+    let discard = game.hand(1)[..2].to_vec();
+    game.discard(1, &discard).unwrap();
+    assert_eq!(game.phase(), Phase::Pegging);
+    assert!(game.starter().is_some());
+    assert_eq!(game.crib().len(), HAND_LEN);
+    assert_eq!(game.to_play(), 0, "the player after the dealer pegs first");
+  }
+
+  #[test]
+  fn pegging_gos_and_last_card() {
+    let mut game = CribbageGame::new(1, [0, 0], |_| ());
+    game.hands = vec![cards(&["KS", "QS", "9H", "2D"]), cards(&["KH", "KD", "3C", "AC"])];
+    game.unplayed = game.hands.clone();
+    game.crib = cards(&["5S", "6S", "7S", "8S"]);
+    game.discarded = [true, true];
+    game.starter = Some(card("4C"));
+    game.phase = Phase::Pegging;
+
+    game.peg(card("KS")).unwrap();
+    game.peg(card("KH")).unwrap();
+    assert_eq!(game.points().pegging, [0, 2], "pair");
+    game.peg(card("9H")).unwrap();
+    assert_eq!(game.legal_pegs(), cards(&["AC"]), "only the ace fits under 31");
+    game.peg(card("AC")).unwrap();
+    assert_eq!(game.points().pegging, [0, 3], "a go at 30 for the ace as neither can play");
+    assert_eq!(game.count(), &[][..], "the count starts again");
+
+    assert_eq!(game.to_play(), 0, "the player after the last to play starts the count");
+    game.peg(card("QS")).unwrap();
+    game.peg(card("KD")).unwrap();
+    game.peg(card("2D")).unwrap();
+    assert_eq!(game.to_play(), 1);
+    game.peg(card("3C")).unwrap();
+    assert_eq!(game.points().pegging, [0, 4], "last card");
+    assert_eq!(game.phase(), Phase::Over);
+    assert_eq!(game.points().hand, [score_show(&game.hands[0], card("4C"), false).total(), score_show(&game.hands[1], card("4C"), false).total()]);
+  }
+
+  #[test]
+  fn game_ends_at_121() {
+    let mut game = CribbageGame::new(0, [0, 120], |_| ());
+    game.hands = vec![cards(&["KS", "QS", "9H", "2D"]), cards(&["KH", "KD", "3C", "AC"])];
+    game.unplayed = game.hands.clone();
+    game.crib = cards(&["5S", "6S", "7S", "8S"]);
+    game.discarded = [true, true];
+    game.starter = Some(card("4C"));
+    game.phase = Phase::Pegging;
+    game.to_play = 1;
+
+    game.peg(card("KH")).unwrap();
+    game.peg(card("KS")).unwrap();
+    assert_eq!(game.phase(), Phase::Pegging);
+    game.peg(card("KD")).unwrap();
+    assert_eq!(game.winner(), Some(1), "the pair royal wins");
+    assert_eq!(game.phase(), Phase::Over);
+    assert_eq!(game.peg(card("QS")), Err(CribbageErr::NotPegging));
+  }
+
+  #[test]
+  fn random_play_scores() {
+    let mut rng = Isaac64Rng::from_seed(&[6]);
+
+    for seed in 0..50 {
+      let mut game = CribbageGame::new(seed as usize % 2, [0, 0], |c| seeded_shuffle(seed, c));
+      for p in 0..NUM_PLAYERS {
+        let discard = game.hand(p)[..DISCARD_LEN].to_vec();
+        game.discard(p, &discard).unwrap();
+      }
+
+      while game.phase() == Phase::Pegging {
+        let pegs = game.legal_pegs();
+        game.peg(*rng.choose(&pegs).unwrap()).unwrap();
+      }
+
+      assert_eq!(game.pegged().len(), 2 * HAND_LEN);
+      let p = game.points();
+      assert_eq!(game.scores()[0] + game.scores()[1], p.heels + p.pegging[0] + p.pegging[1] + p.hand[0] + p.hand[1] + p.crib);
+    }
+  }
+}
@@ -0,0 +1,762 @@
+//! Euchre, four players in two partnerships, players 0 and 2 against 1 and
+//! 3, with the 24 cards from nine to ace.
+//!
+//! Each player is dealt five cards and the next card is turned up. Starting
+//! left of the dealer each player may pass or order the dealer to pick up
+//! the turned card, making its suit trumps; the dealer then discards a card.
+//! When all four pass the card is turned down and in a second round each may
+//! name any other suit, or pass again. With `stick_the_dealer` the dealer has
+//! to name one when it comes round to them; otherwise four more passes throw
+//! the deal in. The player who makes trumps may go alone, their partner
+//! sitting the deal out.
+//!
+//! The jack of trumps is the highest trump, the right bower, and the jack of
+//! the other suit of the same color is the next, the left bower, a trump in
+//! every way rather than a card of its printed suit. The player left of the
+//! dealer leads, players must follow the suit led when they can, and the
+//! highest trump or the highest card of the suit led takes the trick.
+//!
+//! The makers score a point for three or four tricks and two for all five,
+//! four when going alone. Makers who take fewer than three are euchred and
+//! the defenders score two points. A match is played to `target` points.
+
+use cards::french::{self, FrenchPlayingCard, Rank, Suit};
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_PLAYERS: usize = 4;
+pub const HAND_LEN: usize = 5;
+
+/// The partnership of `player`, 0 or 1.
+pub fn team(player: usize) -> usize {
+  player % 2
+}
+
+pub fn partner(player: usize) -> usize {
+  (player + 2) % NUM_PLAYERS
+}
+
+pub fn is_right_bower(card: Card, trump: Suit) -> bool {
+  card.rank() == Rank::Jack && card.suit() == trump
+}
+
+pub fn is_left_bower(card: Card, trump: Suit) -> bool {
+  card.rank() == Rank::Jack && card.suit() == trump.same_color()
+}
+
+/// The suit `card` plays as with `trump` trumps, trumps for the left bower.
+pub fn effective_suit(card: Card, trump: Suit) -> Suit {
+  if is_left_bower(card, trump) { trump } else { card.suit() }
+}
+
+/// How high `card` plays in a trick led in the suit `led` with `trump`
+/// trumps, the highest taking the trick: the right bower, the left bower,
+/// the other trumps from ace down and then the cards of the suit led. Cards
+/// of the other suits cannot take the trick and are 0.
+pub fn trick_rank(card: Card, trump: Suit, led: Suit) -> u8 {
+  let value = card.rank().ace_high_value();
+
+  if is_right_bower(card, trump) {
+    32
+  } else if is_left_bower(card, trump) {
+    31
+  } else if card.suit() == trump {
+    16 + value
+  } else if card.suit() == led {
+    value
+  } else {
+    0
+  }
+}
+
+/// The player who played the card taking `trick` so far and that card,
+/// `None` before the lead.
+pub fn trick_winner(trick: &[(usize, Card)], trump: Suit) -> Option<(usize, Card)> {
+  let led = effective_suit(trick.first()?.1, trump);
+  trick.iter().cloned().max_by_key(|&(_, c)| trick_rank(c, trump, led))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EuchreRules {
+  /// Points a partnership needs to win a match.
+  pub target: u32,
+  /// Whether the dealer has to name trumps when everybody else passed in
+  /// the second round, rather than the deal being thrown in.
+  pub stick_the_dealer: bool,
+}
+
+impl EuchreRules {
+  /// Matches to 10 with deals thrown in when nobody makes trumps.
+  pub fn new() -> EuchreRules {
+    EuchreRules {
+      target: 10,
+      stick_the_dealer: false,
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EuchreErr {
+  /// Trumps have been made, or the deal thrown in.
+  NotBidding,
+  /// Ordering up a suit other than the turned card's, or naming its suit in
+  /// the second round.
+  InvalidCall,
+  /// The dealer passing when stuck with naming trumps.
+  MustCall,
+  /// Only the dealer discards, once, after picking up the turned card.
+  NotDiscarding,
+  /// Cards are played after trumps are made.
+  NotPlaying,
+  /// The card is not in the hand of the player.
+  NotInHand,
+  /// The card is in the hand but does not follow suit.
+  IllegalPlay,
+}
+
+pub type EuchreResult<T> = Result<T, EuchreErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Phase {
+  /// First round of bidding, on the suit of the turned card.
+  OrderUp,
+  /// Second round of bidding, on the other suits.
+  NameTrump,
+  /// The dealer discarding for the card they picked up.
+  Discard,
+  Playing,
+  Over,
+}
+
+#[derive(Debug, Clone)]
+pub struct EuchreGame {
+  rules: EuchreRules,
+  dealer: usize,
+  hands: Vec<Vec<Card>>,
+  up_card: Card,
+  /// The cards not dealt, and the dealer's discard.
+  kitty: Vec<Card>,
+  phase: Phase,
+  /// Passes in the current round of bidding.
+  passes: usize,
+  trump: Option<Suit>,
+  maker: Option<usize>,
+  alone: bool,
+  trick: Vec<(usize, Card)>,
+  leader: usize,
+  /// Tricks played to the end, in order.
+  tricks: Vec<Vec<(usize, Card)>>,
+  /// Tricks taken by each player.
+  taken: [usize; NUM_PLAYERS],
+}
+
+impl EuchreGame {
+  /// Deal of a euchre deck arranged by `shuffle`, dealt a card at a time
+  /// starting left of `dealer`, the next card turned up.
+  pub fn new<F>(rules: EuchreRules, dealer: usize, mut shuffle: F) -> EuchreGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_euchre_deck();
+    shuffle(&mut cards);
+
+    let mut hands = vec![Vec::with_capacity(HAND_LEN + 1); NUM_PLAYERS];
+    for (i, card) in cards.drain(..NUM_PLAYERS * HAND_LEN).enumerate() {
+      hands[(dealer + 1 + i) % NUM_PLAYERS].push(card);
+    }
+    let up_card = cards.remove(0);
+
+    EuchreGame {
+      rules: rules,
+      dealer: dealer,
+      hands: hands,
+      up_card: up_card,
+      kitty: cards,
+      phase: Phase::OrderUp,
+      passes: 0,
+      trump: None,
+      maker: None,
+      alone: false,
+      trick: Vec::with_capacity(NUM_PLAYERS),
+      leader: (dealer + 1) % NUM_PLAYERS,
+      tricks: Vec::with_capacity(HAND_LEN),
+      taken: [0; NUM_PLAYERS],
+    }
+  }
+
+  pub fn rules(&self) -> &EuchreRules {
+    &self.rules
+  }
+
+  pub fn dealer(&self) -> usize {
+    self.dealer
+  }
+
+  pub fn phase(&self) -> Phase {
+    self.phase
+  }
+
+  pub fn hand(&self, player: usize) -> &[Card] {
+    &self.hands[player]
+  }
+
+  /// The card turned up after the deal.
+  pub fn up_card(&self) -> Card {
+    self.up_card
+  }
+
+  pub fn is_bidding(&self) -> bool {
+    self.phase == Phase::OrderUp || self.phase == Phase::NameTrump
+  }
+
+  /// The player to pass or call, while bidding.
+  pub fn to_bid(&self) -> usize {
+    (self.dealer + 1 + self.passes) % NUM_PLAYERS
+  }
+
+  pub fn trump(&self) -> Option<Suit> {
+    self.trump
+  }
+
+  /// The player who made trumps.
+  pub fn maker(&self) -> Option<usize> {
+    self.maker
+  }
+
+  /// Whether the maker is going alone.
+  pub fn is_alone(&self) -> bool {
+    self.alone
+  }
+
+  /// Whether `player` sits the deal out, the partner of a maker going alone.
+  pub fn sits_out(&self, player: usize) -> bool {
+    self.alone && self.maker.map(partner) == Some(player)
+  }
+
+  /// Whether all passed twice and the deal was thrown in.
+  pub fn is_thrown_in(&self) -> bool {
+    self.phase == Phase::Over && self.trump.is_none()
+  }
+
+  pub fn is_over(&self) -> bool {
+    self.phase == Phase::Over
+  }
+
+  fn players(&self) -> usize {
+    if self.alone { NUM_PLAYERS - 1 } else { NUM_PLAYERS }
+  }
+
+  /// The player after `player` who plays the deal.
+  fn next_player(&self, player: usize) -> usize {
+    let next = (player + 1) % NUM_PLAYERS;
+    if self.sits_out(next) { (next + 1) % NUM_PLAYERS } else { next }
+  }
+
+  /// Pass for the player to bid.
+  pub fn pass(&mut self) -> EuchreResult<()> {
+    if ! self.is_bidding() {
+      return Err(EuchreErr::NotBidding);
+    }
+    if self.phase == Phase::NameTrump && self.rules.stick_the_dealer && self.to_bid() == self.dealer {
+      return Err(EuchreErr::MustCall);
+    }
+
+    self.passes += 1;
+    if self.passes == NUM_PLAYERS {
+      self.passes = 0;
+      self.phase = if self.phase == Phase::OrderUp { Phase::NameTrump } else { Phase::Over };
+    }
+
+    Ok(())
+  }
+
+  /// Make `suit` trumps for the player to bid, going alone with `alone`: in
+  /// the first round ordering up the turned card, in the second naming
+  /// another suit.
+  pub fn call(&mut self, suit: Suit, alone: bool) -> EuchreResult<()> {
+    let valid = match self.phase {
+      Phase::OrderUp => suit == self.up_card.suit(),
+      Phase::NameTrump => suit != self.up_card.suit(),
+      _ => return Err(EuchreErr::NotBidding),
+    };
+    if ! valid {
+      return Err(EuchreErr::InvalidCall);
+    }
+
+    let ordered = self.phase == Phase::OrderUp;
+    self.trump = Some(suit);
+    self.maker = Some(self.to_bid());
+    self.alone = alone;
+
+    let dealer = self.dealer;
+    if ordered && ! self.sits_out(dealer) {
+      let up_card = self.up_card;
+      self.hands[dealer].push(up_card);
+      self.phase = Phase::Discard;
+    } else {
+      self.start_play();
+    }
+
+    Ok(())
+  }
+
+  /// Discard `card` for the dealer, after picking up the turned card.
+  pub fn discard(&mut self, card: Card) -> EuchreResult<()> {
+    if self.phase != Phase::Discard {
+      return Err(EuchreErr::NotDiscarding);
+    }
+
+    let dealer = self.dealer;
+    match self.hands[dealer].iter().position(|c| *c == card) {
+      Some(i) => self.kitty.push(self.hands[dealer].remove(i)),
+      None => return Err(EuchreErr::NotInHand),
+    }
+
+    self.start_play();
+    Ok(())
+  }
+
+  fn start_play(&mut self) {
+    self.phase = Phase::Playing;
+    self.leader = self.next_player(self.dealer);
+  }
+
+  /// The trick being played, each card with the player who played it.
+  pub fn trick(&self) -> &[(usize, Card)] {
+    &self.trick
+  }
+
+  pub fn tricks(&self) -> &[Vec<(usize, Card)>] {
+    &self.tricks
+  }
+
+  /// Tricks taken by `player`.
+  pub fn taken(&self, player: usize) -> usize {
+    self.taken[player]
+  }
+
+  /// The player to play, while playing.
+  pub fn to_play(&self) -> usize {
+    match self.trick.last() {
+      Some(&(player, _)) => self.next_player(player),
+      None => self.leader,
+    }
+  }
+
+  /// Whether the player to play may play `card`.
+  pub fn can_play(&self, card: Card) -> EuchreResult<()> {
+    if self.phase != Phase::Playing {
+      return Err(EuchreErr::NotPlaying);
+    }
+
+    let hand = &self.hands[self.to_play()];
+    if ! hand.contains(&card) {
+      return Err(EuchreErr::NotInHand);
+    }
+
+    let trump = self.trump.unwrap();
+    let follows = match self.trick.first() {
+      Some(&(_, lead)) => {
+        let led = effective_suit(lead, trump);
+        effective_suit(card, trump) == led || hand.iter().all(|c| effective_suit(*c, trump) != led)
+      },
+      None => true,
+    };
+
+    if follows { Ok(()) } else { Err(EuchreErr::IllegalPlay) }
+  }
+
+  /// The cards the player to play may play, in the order of their hand.
+  pub fn legal_plays(&self) -> Vec<Card> {
+    if self.phase != Phase::Playing {
+      return Vec::new();
+    }
+
+    self.hands[self.to_play()].iter().cloned().filter(|c| self.can_play(*c).is_ok()).collect()
+  }
+
+  /// Play `card` for the player to play, ending the trick after the last
+  /// player and the deal after the fifth trick.
+  pub fn play(&mut self, card: Card) -> EuchreResult<()> {
+    self.can_play(card)?;
+
+    let player = self.to_play();
+    self.hands[player].retain(|c| *c != card);
+    self.trick.push((player, card));
+
+    if self.trick.len() == self.players() {
+      let (winner, _) = trick_winner(&self.trick, self.trump.unwrap()).unwrap();
+      self.taken[winner] += 1;
+      self.leader = winner;
+      let trick = ::std::mem::replace(&mut self.trick, Vec::with_capacity(NUM_PLAYERS));
+      self.tricks.push(trick);
+
+      if self.tricks.len() == HAND_LEN {
+        self.phase = Phase::Over;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Points of each partnership for the deal once it is over, none for a
+  /// deal thrown in.
+  pub fn points(&self) -> [u32; 2] {
+    let mut points = [0; 2];
+    let maker = match self.maker {
+      Some(maker) if self.phase == Phase::Over => maker,
+      _ => return points,
+    };
+
+    let makers = team(maker);
+    let tricks = self.taken[maker] + self.taken[partner(maker)];
+    match tricks {
+      HAND_LEN if self.alone => points[makers] = 4,
+      HAND_LEN => points[makers] = 2,
+      3..=4 => points[makers] = 1,
+      _ => points[1 - makers] = 2,
+    }
+
+    points
+  }
+
+  /// Every card played so far, the trick being played included.
+  pub fn played(&self) -> Vec<Card> {
+    self.tricks.iter().chain(Some(&self.trick)).flat_map(|t| t.iter().map(|&(_, c)| c)).collect()
+  }
+
+  /// The deal as `player` sees it.
+  pub fn view(&self, player: usize) -> EuchreView<'_> {
+    EuchreView {
+      game: self,
+      player: player,
+    }
+  }
+}
+
+/// What one player knows of a deal: their own hand, the turned card, the
+/// bidding and every card played, but not the hands of the others or the
+/// dealer's discard.
+#[derive(Debug, Copy, Clone)]
+pub struct EuchreView<'a> {
+  game: &'a EuchreGame,
+  player: usize,
+}
+
+impl<'a> EuchreView<'a> {
+  pub fn player(&self) -> usize {
+    self.player
+  }
+
+  pub fn partner(&self) -> usize {
+    partner(self.player)
+  }
+
+  pub fn hand(&self) -> &'a [Card] {
+    self.game.hand(self.player)
+  }
+
+  pub fn dealer(&self) -> usize {
+    self.game.dealer()
+  }
+
+  pub fn phase(&self) -> Phase {
+    self.game.phase()
+  }
+
+  pub fn up_card(&self) -> Card {
+    self.game.up_card()
+  }
+
+  pub fn trump(&self) -> Option<Suit> {
+    self.game.trump()
+  }
+
+  pub fn maker(&self) -> Option<usize> {
+    self.game.maker()
+  }
+
+  pub fn is_alone(&self) -> bool {
+    self.game.is_alone()
+  }
+
+  /// Whether the player has to name trumps, the dealer stuck in the second
+  /// round.
+  pub fn must_call(&self) -> bool {
+    let game = self.game;
+    game.phase == Phase::NameTrump && game.rules.stick_the_dealer && self.player == game.dealer
+  }
+
+  pub fn trick(&self) -> &'a [(usize, Card)] {
+    self.game.trick()
+  }
+
+  pub fn tricks(&self) -> &'a [Vec<(usize, Card)>] {
+    self.game.tricks()
+  }
+
+  pub fn played(&self) -> Vec<Card> {
+    self.game.played()
+  }
+
+  pub fn taken(&self, player: usize) -> usize {
+    self.game.taken(player)
+  }
+}
+
+/// The running score of a match: deals are played, the dealer moving left
+/// each deal, thrown in deals included, until a partnership reaches the
+/// target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EuchreMatch {
+  rules: EuchreRules,
+  scores: [u32; 2],
+  deals: usize,
+}
+
+impl EuchreMatch {
+  pub fn new(rules: EuchreRules) -> EuchreMatch {
+    EuchreMatch {
+      rules: rules,
+      scores: [0; 2],
+      deals: 0,
+    }
+  }
+
+  pub fn rules(&self) -> &EuchreRules {
+    &self.rules
+  }
+
+  pub fn scores(&self) -> [u32; 2] {
+    self.scores
+  }
+
+  pub fn deals(&self) -> usize {
+    self.deals
+  }
+
+  /// Dealer of the next deal.
+  pub fn dealer(&self) -> usize {
+    self.deals % NUM_PLAYERS
+  }
+
+  /// Add the points of `game`, a finished deal.
+  pub fn record(&mut self, game: &EuchreGame) {
+    let points = game.points();
+    self.scores[0] += points[0];
+    self.scores[1] += points[1];
+    self.deals += 1;
+  }
+
+  /// The partnership that won the match, `None` while it goes on. Only one
+  /// partnership scores in a deal, so they cannot reach the target together.
+  pub fn winner(&self) -> Option<usize> {
+    (0..2).find(|t| self.scores[*t] >= self.rules.target)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  /// Deal with dealer 3, so player 0 bids and leads first, and the given
+  /// hands and turned card.
+  fn game(rules: EuchreRules, hands: &[&[&str]; NUM_PLAYERS], up_card: &str) -> EuchreGame {
+    let mut game = EuchreGame::new(rules, 3, |_| ());
+    game.hands = hands.iter().map(|h| cards(h)).collect();
+    game.up_card = card(up_card);
+    game
+  }
+
+  fn hands() -> [&'static [&'static str]; NUM_PLAYERS] {
+    [
+      &["JH", "AD", "9C", "10S", "KH"],
+      &["JD", "QD", "9H", "AC", "QS"],
+      &["KD", "10D", "AS", "KC", "9S"],
+      &["9D", "10H", "QH", "JC", "JS"],
+    ]
+  }
+
+  #[test]
+  fn trick_ranks() {
+    let trump = Suit::Diamonds;
+    let order = cards(&["JD", "JH", "AD", "KD", "QD", "10D", "9D", "AC", "KC", "9C", "AH", "AS"]);
+    let ranks: Vec<u8> = order.iter().map(|c| trick_rank(*c, trump, Suit::Clubs)).collect();
+
+    for pair in ranks.windows(2) {
+      assert!(pair[0] > pair[1] || pair[1] == 0, "{:?}", ranks);
+    }
+    assert_eq!(ranks[10..], [0, 0]);
+    assert_eq!(effective_suit(card("JH"), trump), Suit::Diamonds);
+    assert_eq!(effective_suit(card("JC"), trump), Suit::Clubs);
+
+    let trick = vec![(1, card("AH")), (2, card("JH")), (3, card("KH"))];
+    assert_eq!(trick_winner(&trick, Suit::Spades), Some((1, card("AH"))));
+    assert_eq!(trick_winner(&trick, Suit::Diamonds), Some((2, card("JH"))), "left bower");
+  }
+
+  #[test]
+  fn deal() {
+    let game = EuchreGame::new(EuchreRules::new(), 1, |c| seeded_shuffle(3, c));
+    let mut all: Vec<Card> = (0..NUM_PLAYERS).flat_map(|p| game.hand(p).to_vec()).collect();
+    all.push(game.up_card());
+    all.extend(game.kitty.iter().cloned());
+
+    assert!((0..NUM_PLAYERS).all(|p| game.hand(p).len() == HAND_LEN));
+    assert_eq!(all.len(), 24);
+    assert!(french::new_euchre_deck().iter().all(|c| all.contains(c)));
+    assert_eq!(game.to_bid(), 2);
+  }
+
+  #[test]
+  fn order_up_and_discard() {
+    let mut game = game(EuchreRules::new(), &hands(), "AH");
+
+    assert_eq!(game.call(Suit::Spades, false), Err(EuchreErr::InvalidCall));
+    game.pass().unwrap();
+    game.call(Suit::Hearts, false).unwrap();
+
+    assert_eq!(game.maker(), Some(1));
+    assert_eq!(game.phase(), Phase::Discard);
+    assert_eq!(game.hand(3).len(), HAND_LEN + 1);
+    assert_eq!(game.play(card("JH")), Err(EuchreErr::NotPlaying));
+    assert_eq!(game.discard(card("AD")), Err(EuchreErr::NotInHand));
+
+    game.discard(card("9D")).unwrap();
+    assert_eq!(game.phase(), Phase::Playing);
+    assert!(game.hand(3).contains(&card("AH")));
+    assert_eq!(game.to_play(), 0);
+  }
+
+  #[test]
+  fn second_round() {
+    let mut game1 = game(EuchreRules::new(), &hands(), "AH");
+    for _ in 0..NUM_PLAYERS {
+      game1.pass().unwrap();
+    }
+
+    assert_eq!(game1.phase(), Phase::NameTrump);
+    assert_eq!(game1.call(Suit::Hearts, false), Err(EuchreErr::InvalidCall));
+    for _ in 0..NUM_PLAYERS {
+      game1.pass().unwrap();
+    }
+    assert!(game1.is_thrown_in());
+    assert_eq!(game1.points(), [0, 0]);
+
+    let mut rules = EuchreRules::new();
+    rules.stick_the_dealer = true;
+    let mut game2 = game(rules, &hands(), "AH");
+    for _ in 0..NUM_PLAYERS + 3 {
+      game2.pass().unwrap();
+    }
+    assert_eq!(game2.pass(), Err(EuchreErr::MustCall));
+    game2.call(Suit::Clubs, false).unwrap();
+    assert_eq!(game2.phase(), Phase::Playing);
+    assert_eq!(game2.hand(3).len(), HAND_LEN, "turned down card not picked up");
+  }
+
+  #[test]
+  fn left_bower_follows_trumps() {
+    let mut game = game(EuchreRules::new(), &hands(), "AH");
+    for _ in 0..NUM_PLAYERS {
+      game.pass().unwrap();
+    }
+    game.call(Suit::Diamonds, false).unwrap();
+
+    // Player 0 leads the left bower, a trump.
+    game.play(card("JH")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["JD", "QD"]));
+    game.play(card("JD")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["KD", "10D"]));
+    game.play(card("10D")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["9D"]));
+    game.play(card("9D")).unwrap();
+
+    assert_eq!(game.taken(1), 1);
+    assert_eq!(game.to_play(), 1);
+
+    // Hearts led, the left bower gone: any heart follows.
+    game.play(card("9H")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["KD", "AS", "KC", "9S"]), "void in hearts");
+  }
+
+  #[test]
+  fn alone() {
+    let mut game1 = game(EuchreRules::new(), &hands(), "AH");
+    game1.call(Suit::Hearts, true).unwrap();
+
+    assert!(game1.sits_out(2));
+    game1.discard(card("9D")).unwrap();
+    assert_eq!(game1.to_play(), 0);
+
+    game1.play(card("JH")).unwrap();
+    game1.play(card("9H")).unwrap();
+    assert_eq!(game1.to_play(), 3, "player 2 sits out");
+    game1.play(card("10H")).unwrap();
+    assert_eq!(game1.tricks().len(), 1);
+    assert_eq!(game1.taken(0), 1);
+
+    // The dealer sitting out does not pick up the turned card.
+    let mut game2 = game(EuchreRules::new(), &hands(), "AH");
+    game2.pass().unwrap();
+    game2.call(Suit::Hearts, true).unwrap();
+
+    assert!(game2.sits_out(3));
+    assert_eq!(game2.phase(), Phase::Playing);
+    assert_eq!(game2.hand(3).len(), HAND_LEN);
+    assert_eq!(game2.to_play(), 0);
+  }
+
+  #[test]
+  fn points() {
+    let mut game = game(EuchreRules::new(), &hands(), "AH");
+    game.call(Suit::Hearts, false).unwrap();
+    game.phase = Phase::Over;
+
+    for &(tricks, alone, points) in &[(5, true, [4, 0]), (5, false, [2, 0]), (3, false, [1, 0]), (2, true, [0, 2])] {
+      game.taken = [tricks, 5 - tricks, 0, 0];
+      game.alone = alone;
+      assert_eq!(game.points(), points, "{} tricks", tricks);
+    }
+  }
+
+  #[test]
+  fn random_play() {
+    let mut rng = Isaac64Rng::from_seed(&[2]);
+    let mut m = EuchreMatch::new(EuchreRules::new());
+
+    while m.winner().is_none() {
+      let mut game = EuchreGame::new(*m.rules(), m.dealer(), |c| rng.shuffle(c));
+
+      while game.is_bidding() {
+        let suit = *rng.choose(Suit::standard_iter().as_slice()).unwrap();
+        if rng.gen_range(0, 4) > 0 || game.call(suit, rng.gen()).is_err() {
+          game.pass().unwrap();
+        }
+      }
+      if game.phase() == Phase::Discard {
+        let card = game.hand(game.dealer())[0];
+        game.discard(card).unwrap();
+      }
+      while ! game.is_over() {
+        let card = *rng.choose(&game.legal_plays()).unwrap();
+        game.play(card).unwrap();
+      }
+
+      let tricks: usize = (0..NUM_PLAYERS).map(|p| game.taken(p)).sum();
+      assert_eq!(tricks, if game.is_thrown_in() { 0 } else { HAND_LEN });
+      assert!((0..NUM_PLAYERS).all(|p| game.hand(p).is_empty() || game.sits_out(p) || game.is_thrown_in()));
+      m.record(&game);
+    }
+
+    assert!(m.scores().iter().any(|s| *s >= 10));
+  }
+}
@@ -0,0 +1,484 @@
+//! Hearts, four players each for themselves.
+//!
+//! Each deal the whole deck is dealt out, thirteen cards to a player, and
+//! every player passes three cards: to the left on the first deal, to the
+//! right on the second, across on the third and not at all on the fourth,
+//! then around again. The two of clubs leads the first trick. Players must
+//! follow suit when they can and the highest card of the suit led, aces
+//! high, takes the trick and leads the next. Hearts cannot be led before one
+//! has been played, unless the leader holds nothing else, and no heart or
+//! queen of spades may be played on the first trick by a player with any
+//! other card.
+//!
+//! Every heart taken costs a point and the queen of spades thirteen. A player
+//! who takes all 26 points shoots the moon: they score nothing and every other
+//! player 26.
+
+use cards::french::{self, FrenchPlayingCard, Rank, Suit};
+use games::trick::Trick;
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_PLAYERS: usize = 4;
+pub const HAND_LEN: usize = 13;
+pub const PASS_LEN: usize = 3;
+pub const MOON_POINTS: u32 = 26;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HeartsErr {
+  /// Cards are passed once, before the first trick, on deals that pass.
+  NotPassing,
+  /// The cards to pass are not three different cards of the hand.
+  InvalidPass,
+  /// Tricks are played after the cards are passed.
+  NotPlaying,
+  /// The card is not in the hand of the player to play.
+  NotInHand,
+  /// The card is in the hand but breaks a rule of the trick.
+  IllegalPlay,
+}
+
+pub type HeartsResult<T> = Result<T, HeartsErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PassDirection {
+  Left,
+  Right,
+  Across,
+  Hold,
+}
+
+impl PassDirection {
+  /// Direction of deal `deal`, counting from 0.
+  pub fn for_deal(deal: usize) -> PassDirection {
+    match deal % 4 {
+      0 => PassDirection::Left,
+      1 => PassDirection::Right,
+      2 => PassDirection::Across,
+      _ => PassDirection::Hold,
+    }
+  }
+
+  /// The player `player` passes to. Players are seated clockwise, so the
+  /// player to the left is the next one.
+  pub fn receiver(&self, player: usize) -> usize {
+    let offset = match *self {
+      PassDirection::Left => 1,
+      PassDirection::Right => 3,
+      PassDirection::Across => 2,
+      PassDirection::Hold => 0,
+    };
+
+    (player + offset) % NUM_PLAYERS
+  }
+}
+
+impl fmt::Display for PassDirection {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      PassDirection::Left => "left",
+      PassDirection::Right => "right",
+      PassDirection::Across => "across",
+      PassDirection::Hold => "hold",
+    };
+
+    write!(f, "{}", name)
+  }
+}
+
+/// Points of a card when taken in a trick.
+pub fn card_points(card: Card) -> u32 {
+  if card.suit() == Suit::Hearts {
+    1
+  } else if card == FrenchPlayingCard::new(Suit::Spades, Rank::Queen) {
+    13
+  } else {
+    0
+  }
+}
+
+/// Points of the cards of `trick`.
+pub fn trick_points(trick: &Trick) -> u32 {
+  trick.cards().iter().map(|c| card_points(*c)).sum()
+}
+
+#[derive(Debug, Clone)]
+pub struct HeartsGame {
+  deal: usize,
+  hands: Vec<Vec<Card>>,
+  /// Whether the cards have been passed, or there is nothing to pass.
+  passed: bool,
+  trick: Trick,
+  /// Tricks played to the end, in order.
+  tricks: Vec<Trick>,
+  /// Cards taken by each player.
+  taken: Vec<Vec<Card>>,
+  hearts_broken: bool,
+}
+
+impl HeartsGame {
+  /// Deal number `deal`, counting from 0, of a standard deck arranged by
+  /// `shuffle`, dealt a card at a time starting with player 0. The deal
+  /// number decides the pass direction.
+  pub fn new<F>(deal: usize, mut shuffle: F) -> HeartsGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut hands = vec![Vec::with_capacity(HAND_LEN); NUM_PLAYERS];
+    for (i, card) in cards.into_iter().enumerate() {
+      hands[i % NUM_PLAYERS].push(card);
+    }
+
+    let mut game = HeartsGame {
+      deal: deal,
+      hands: hands,
+      passed: PassDirection::for_deal(deal) == PassDirection::Hold,
+      trick: Trick::new(0, NUM_PLAYERS),
+      tricks: Vec::new(),
+      taken: vec![Vec::new(); NUM_PLAYERS],
+      hearts_broken: false,
+    };
+    game.trick = Trick::new(game.two_of_clubs(), NUM_PLAYERS);
+    game
+  }
+
+  fn two_of_clubs(&self) -> usize {
+    let two = FrenchPlayingCard::new(Suit::Clubs, Rank::Number(2));
+    self.hands.iter().position(|h| h.contains(&two)).unwrap()
+  }
+
+  pub fn deal(&self) -> usize {
+    self.deal
+  }
+
+  pub fn pass_direction(&self) -> PassDirection {
+    PassDirection::for_deal(self.deal)
+  }
+
+  /// Whether the players still have to pass cards before the first trick.
+  pub fn is_passing(&self) -> bool {
+    ! self.passed
+  }
+
+  pub fn hand(&self, player: usize) -> &[Card] {
+    &self.hands[player]
+  }
+
+  /// The trick being played.
+  pub fn trick(&self) -> &Trick {
+    &self.trick
+  }
+
+  /// The tricks played to the end, in order.
+  pub fn tricks(&self) -> &[Trick] {
+    &self.tricks
+  }
+
+  pub fn taken(&self, player: usize) -> &[Card] {
+    &self.taken[player]
+  }
+
+  pub fn hearts_broken(&self) -> bool {
+    self.hearts_broken
+  }
+
+  pub fn is_first_trick(&self) -> bool {
+    self.tricks.is_empty()
+  }
+
+  /// The player to play a card.
+  pub fn to_play(&self) -> usize {
+    self.trick.to_play()
+  }
+
+  pub fn is_over(&self) -> bool {
+    self.tricks.len() == HAND_LEN
+  }
+
+  /// Points taken by each player, applying a shot at the moon once the deal
+  /// is over.
+  pub fn points(&self) -> [u32; NUM_PLAYERS] {
+    let mut points = [0; NUM_PLAYERS];
+    for (p, taken) in self.taken.iter().enumerate() {
+      points[p] = taken.iter().map(|c| card_points(*c)).sum();
+    }
+
+    if self.is_over() {
+      if let Some(shooter) = self.moon_shooter() {
+        for (p, points) in points.iter_mut().enumerate() {
+          *points = if p == shooter { 0 } else { MOON_POINTS };
+        }
+      }
+    }
+
+    points
+  }
+
+  /// The player who took every point, if one did.
+  pub fn moon_shooter(&self) -> Option<usize> {
+    (0..NUM_PLAYERS).find(|p| self.taken[*p].iter().map(|c| card_points(*c)).sum::<u32>() == MOON_POINTS)
+  }
+
+  /// Pass `passes[p]`, three cards of the hand of each player `p`, in the
+  /// direction of the deal.
+  pub fn pass(&mut self, passes: &[Vec<Card>]) -> HeartsResult<()> {
+    if self.passed {
+      return Err(HeartsErr::NotPassing);
+    }
+
+    if passes.len() != NUM_PLAYERS {
+      return Err(HeartsErr::InvalidPass);
+    }
+
+    for (p, cards) in passes.iter().enumerate() {
+      let distinct = cards.iter().enumerate().all(|(i, c)| ! cards[..i].contains(c));
+      if cards.len() != PASS_LEN || ! distinct || ! cards.iter().all(|c| self.hands[p].contains(c)) {
+        return Err(HeartsErr::InvalidPass);
+      }
+    }
+
+    let direction = self.pass_direction();
+    for (p, cards) in passes.iter().enumerate() {
+      self.hands[p].retain(|c| ! cards.contains(c));
+    }
+    for (p, cards) in passes.iter().enumerate() {
+      self.hands[direction.receiver(p)].extend(cards.iter().cloned());
+    }
+
+    self.passed = true;
+    self.trick = Trick::new(self.two_of_clubs(), NUM_PLAYERS);
+    Ok(())
+  }
+
+  /// Whether the player to play may play `card`.
+  pub fn can_play(&self, card: Card) -> HeartsResult<()> {
+    if ! self.passed || self.is_over() {
+      return Err(HeartsErr::NotPlaying);
+    }
+
+    let hand = &self.hands[self.to_play()];
+    if ! hand.contains(&card) {
+      return Err(HeartsErr::NotInHand);
+    }
+
+    let legal = match self.trick.suit() {
+      None if self.is_first_trick() => card == FrenchPlayingCard::new(Suit::Clubs, Rank::Number(2)),
+      None => self.hearts_broken || card.suit() != Suit::Hearts || hand.iter().all(|c| c.suit() == Suit::Hearts),
+      Some(suit) if hand.iter().any(|c| c.suit() == suit) => card.suit() == suit,
+      Some(_) if self.is_first_trick() => card_points(card) == 0 || hand.iter().all(|c| card_points(*c) > 0),
+      Some(_) => true,
+    };
+
+    if legal { Ok(()) } else { Err(HeartsErr::IllegalPlay) }
+  }
+
+  /// The cards the player to play may play, in the order of their hand.
+  pub fn legal_plays(&self) -> Vec<Card> {
+    if ! self.passed || self.is_over() {
+      return Vec::new();
+    }
+
+    self.hands[self.to_play()].iter().cloned().filter(|c| self.can_play(*c).is_ok()).collect()
+  }
+
+  /// Play `card` for the player to play, ending the trick after the fourth.
+  pub fn play(&mut self, card: Card) -> HeartsResult<()> {
+    self.can_play(card)?;
+
+    let player = self.to_play();
+    self.hands[player].retain(|c| *c != card);
+    self.trick.push(card);
+    if card.suit() == Suit::Hearts {
+      self.hearts_broken = true;
+    }
+
+    if self.trick.is_complete() {
+      let (winner, _) = self.trick.winner(None).unwrap();
+      self.taken[winner].extend(self.trick.cards().iter().cloned());
+      let trick = ::std::mem::replace(&mut self.trick, Trick::new(winner, NUM_PLAYERS));
+      self.tricks.push(trick);
+    }
+
+    Ok(())
+  }
+
+  /// Every card played so far, the trick being played included.
+  pub fn played(&self) -> Vec<Card> {
+    self.tricks.iter().chain(Some(&self.trick)).flat_map(|t| t.cards().iter().cloned()).collect()
+  }
+
+  /// The deal as `player` sees it.
+  pub fn view(&self, player: usize) -> HeartsView<'_> {
+    HeartsView {
+      game: self,
+      player: player,
+    }
+  }
+}
+
+/// What one player knows of a deal: their own hand and every card played,
+/// but not the hands of the others.
+#[derive(Debug, Copy, Clone)]
+pub struct HeartsView<'a> {
+  game: &'a HeartsGame,
+  player: usize,
+}
+
+impl<'a> HeartsView<'a> {
+  pub fn player(&self) -> usize {
+    self.player
+  }
+
+  pub fn hand(&self) -> &'a [Card] {
+    self.game.hand(self.player)
+  }
+
+  pub fn deal(&self) -> usize {
+    self.game.deal()
+  }
+
+  pub fn pass_direction(&self) -> PassDirection {
+    self.game.pass_direction()
+  }
+
+  pub fn trick(&self) -> &'a Trick {
+    self.game.trick()
+  }
+
+  pub fn tricks(&self) -> &'a [Trick] {
+    self.game.tricks()
+  }
+
+  pub fn played(&self) -> Vec<Card> {
+    self.game.played()
+  }
+
+  pub fn taken(&self, player: usize) -> &'a [Card] {
+    self.game.taken(player)
+  }
+
+  pub fn hearts_broken(&self) -> bool {
+    self.game.hearts_broken()
+  }
+
+  pub fn is_first_trick(&self) -> bool {
+    self.game.is_first_trick()
+  }
+
+  pub fn points(&self) -> [u32; NUM_PLAYERS] {
+    self.game.points()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn new_deal() {
+    let mut game = HeartsGame::new(0, |c| seeded_shuffle(1, c));
+
+    assert!((0..NUM_PLAYERS).all(|p| game.hand(p).len() == HAND_LEN));
+    assert!(game.is_passing());
+    assert_eq!(game.pass_direction(), PassDirection::Left);
+    assert_eq!(game.legal_plays(), vec![]);
+    let first = game.hand(0)[0];
+    assert_eq!(game.play(first), Err(HeartsErr::NotPlaying));
+
+    let game = HeartsGame::new(3, |c| seeded_shuffle(1, c));
+    assert!(! game.is_passing(), "no passing on the fourth deal");
+    assert_eq!(game.legal_plays(), cards(&["2C"]));
+    assert!(game.hand(game.to_play()).contains(&card("2C")));
+  }
+
+  #[test]
+  fn passing() {
+    let mut game = HeartsGame::new(1, |c| seeded_shuffle(2, c));
+    let passes: Vec<Vec<Card>> = (0..NUM_PLAYERS).map(|p| game.hand(p)[..PASS_LEN].to_vec()).collect();
+
+    let mut bad = passes.clone();
+    bad[2][1] = bad[2][0];
+    assert_eq!(game.pass(&bad), Err(HeartsErr::InvalidPass));
+    bad[2] = passes[3].clone();
+    assert_eq!(game.pass(&bad), Err(HeartsErr::InvalidPass));
+
+    game.pass(&passes).unwrap();
+    assert_eq!(game.pass(&passes), Err(HeartsErr::NotPassing));
+    assert!((0..NUM_PLAYERS).all(|p| game.hand(p).len() == HAND_LEN));
+    for card in &passes[1] {
+      assert!(game.hand(0).contains(card), "passed to the right");
+    }
+  }
+
+  #[test]
+  fn trick_rules() {
+    let mut game = HeartsGame::new(3, |_| ());
+    game.hands = vec![
+      cards(&["2C", "3C", "5H", "AD"]),
+      cards(&["QS", "4H", "6H", "7H"]),
+      cards(&["KC", "5S", "8H", "2D"]),
+      cards(&["3S", "TH", "JH", "QH"]),
+    ];
+    game.trick = Trick::new(0, NUM_PLAYERS);
+
+    assert_eq!(game.play(card("3C")), Err(HeartsErr::IllegalPlay), "the two of clubs leads");
+    assert_eq!(game.play(card("KC")), Err(HeartsErr::NotInHand));
+    game.play(card("2C")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["QS", "4H", "6H", "7H"]), "points on the first trick when there is nothing else");
+    game.play(card("QS")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["KC"]), "follow suit");
+    game.play(card("KC")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["3S"]), "no hearts on the first trick");
+    game.play(card("3S")).unwrap();
+
+    assert_eq!(game.tricks().len(), 1);
+    assert_eq!(game.to_play(), 2, "the king takes the trick and leads");
+    assert_eq!(game.points(), [0, 0, 13, 0]);
+    assert_eq!(game.legal_plays(), cards(&["5S", "2D"]), "hearts are not broken");
+  }
+
+  #[test]
+  fn shooting_the_moon() {
+    let mut game = HeartsGame::new(3, |_| ());
+    game.taken[1] = french::new_standard_deck().into_iter().filter(|c| card_points(*c) > 0).collect();
+    assert_eq!(game.points(), [0, 26, 0, 0], "not until the deal is over");
+
+    game.tricks = vec![Trick::new(0, NUM_PLAYERS); HAND_LEN];
+    assert_eq!(game.moon_shooter(), Some(1));
+    assert_eq!(game.points(), [26, 0, 26, 26]);
+  }
+
+  #[test]
+  fn random_play_takes_every_point() {
+    let mut rng = Isaac64Rng::from_seed(&[3]);
+
+    for deal in 0..4 {
+      let mut game = HeartsGame::new(deal, |c| seeded_shuffle(deal as u64, c));
+      if game.is_passing() {
+        let passes: Vec<Vec<Card>> = (0..NUM_PLAYERS).map(|p| game.hand(p)[HAND_LEN - PASS_LEN..].to_vec()).collect();
+        game.pass(&passes).unwrap();
+      }
+
+      while ! game.is_over() {
+        let plays = game.legal_plays();
+        game.play(*rng.choose(&plays).unwrap()).unwrap();
+      }
+
+      assert_eq!(game.played().len(), 52);
+      let points = game.points();
+      assert!(points.iter().sum::<u32>() == MOON_POINTS || points.iter().sum::<u32>() == 3 * MOON_POINTS);
+    }
+  }
+}
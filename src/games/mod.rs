@@ -1 +1,12 @@
+pub mod baccarat;
+pub mod blackjack;
+pub mod crazy_eights;
+pub mod cribbage;
+pub mod euchre;
+pub mod hearts;
+pub mod shoe;
 pub mod solitaire;
+pub mod spades;
+pub mod trick;
+pub mod video_poker;
+pub mod war;
@@ -0,0 +1,131 @@
+//! The shoe of the casino games: several decks shuffled together once and
+//! dealt round after round until the cut card comes out.
+
+use cards::french::{self, FrenchPlayingCard};
+use cards::seeded_shuffle;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+pub type Card = FrenchPlayingCard;
+
+/// The cards of a round: a number of decks shuffled together and dealt down
+/// to the cut card.
+#[derive(Debug, Clone)]
+pub struct Shoe {
+  cards: Vec<Card>,
+  /// Cards dealt so far.
+  dealt: usize,
+  /// Cards dealt before the current round.
+  round_start: usize,
+  cut: usize,
+  seed: u64,
+  reshuffles: usize,
+}
+
+impl Shoe {
+  /// Shoe of `decks` standard decks shuffled with `seed`, the cut card
+  /// `penetration` of the way through.
+  pub fn new(decks: usize, penetration: f64, seed: u64) -> Shoe {
+    let mut cards = Vec::with_capacity(decks * 52);
+    for _ in 0..decks {
+      cards.extend(french::new_standard_deck());
+    }
+    seeded_shuffle(seed, &mut cards);
+
+    let cut = (cards.len() as f64 * penetration) as usize;
+    Shoe::with_cards(cards, cut, seed)
+  }
+
+  /// Shoe dealing `cards` in order, the cut card after the first `cut`.
+  pub fn with_cards(cards: Vec<Card>, cut: usize, seed: u64) -> Shoe {
+    Shoe {
+      cards: cards,
+      dealt: 0,
+      round_start: 0,
+      cut: cut,
+      seed: seed,
+      reshuffles: 0,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.cards.len()
+  }
+
+  pub fn dealt(&self) -> usize {
+    self.dealt
+  }
+
+  /// Whether the cut card has come out, so no new round is dealt.
+  pub fn is_finished(&self) -> bool {
+    self.dealt >= self.cut
+  }
+
+  /// Deal the next card. A shoe that runs out in the middle of a round goes on
+  /// with the cards of the rounds before, shuffled again.
+  pub fn draw(&mut self) -> Card {
+    if self.dealt == self.cards.len() {
+      self.reshuffle_discards();
+    }
+
+    self.dealt += 1;
+    self.cards[self.dealt - 1]
+  }
+
+  /// Start a round with the next card. Only the cards of the rounds before
+  /// are reshuffled when the shoe runs out.
+  pub fn start_round(&mut self) {
+    self.round_start = self.dealt;
+  }
+
+  fn reshuffle_discards(&mut self) {
+    assert!(self.round_start > 0, "shoe of {} cards exhausted by one round", self.cards.len());
+
+    let table = self.cards.split_off(self.round_start);
+    let mut discards = ::std::mem::replace(&mut self.cards, table);
+
+    let mut hasher = DefaultHasher::new();
+    (self.seed, self.reshuffles).hash(&mut hasher);
+    seeded_shuffle(hasher.finish(), &mut discards);
+
+    self.cards.extend(discards);
+    self.dealt -= self.round_start;
+    self.cut = self.dealt;
+    self.round_start = 0;
+    self.reshuffles += 1;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn cut_card() {
+    let shoe = Shoe::new(8, 0.75, 1);
+    assert_eq!(shoe.len(), 416);
+    assert_eq!(shoe.cut, 312);
+    assert!(! shoe.is_finished());
+  }
+
+  #[test]
+  fn reshuffles_rounds_before() {
+    let mut shoe = Shoe::with_cards(cards(&["2S", "3S", "4S", "5S", "6S"]), 2, 0);
+
+    shoe.start_round();
+    shoe.draw();
+    shoe.draw();
+    assert!(shoe.is_finished());
+
+    shoe.start_round();
+    let round: Vec<Card> = (0..4).map(|_| shoe.draw()).collect();
+    assert_eq!(round[..3], cards(&["4S", "5S", "6S"])[..]);
+    assert!(cards(&["2S", "3S"]).contains(&round[3]), "drawn from the first round");
+    assert_eq!(shoe.len(), 5);
+    assert_eq!(shoe.dealt(), 4);
+  }
+}
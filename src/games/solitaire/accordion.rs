@@ -0,0 +1,255 @@
+//! Accordion solitaire.
+//!
+//! Cards are dealt from the stock one at a time into a row of piles, each new
+//! card a pile of its own at the right end. A pile can be collapsed onto the
+//! pile right to its left or the pile three to its left when their top cards
+//! have the same suit or the same rank; the piles to its right close up. The
+//! game is won when all 52 cards form one pile.
+
+use cards::french::{self, FrenchPlayingCard};
+use games::solitaire::SolitaireGame;
+use std::collections::HashSet;
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+const NUM_CARDS: usize = 52;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AccordionErr {
+  /// The stock is empty.
+  EmptyStock,
+  InvalidMove,
+}
+
+pub type AccordionResult<T> = Result<T, AccordionErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AccordionMove {
+  /// Deal the next card of the stock as a new pile.
+  Deal,
+  /// Collapse pile `pile` onto the pile `distance` to its left, 1 or 3.
+  Collapse { pile: u8, distance: u8 },
+}
+
+impl fmt::Display for AccordionMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      AccordionMove::Deal => write!(f, "deal"),
+      AccordionMove::Collapse { pile, distance } => write!(f, "{}-{}", pile, distance),
+    }
+  }
+}
+
+/// Whether the top cards `a` and `b` let their piles collapse.
+pub fn matches(a: Card, b: Card) -> bool {
+  a.suit() == b.suit() || a.rank() == b.rank()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccordionGame {
+  piles: Vec<Vec<Card>>,
+  /// Cards dealt from the end.
+  stock: Vec<Card>,
+  moves_made: usize,
+}
+
+impl AccordionGame {
+  /// New game with a standard deck arranged by `shuffle`, the first card
+  /// dealt first. The first card is dealt right away.
+  pub fn new<F>(mut shuffle: F) -> AccordionGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut stock = french::new_standard_deck();
+    shuffle(&mut stock);
+    stock.reverse();
+    let first = stock.pop().unwrap();
+
+    AccordionGame {
+      piles: vec![vec![first]],
+      stock: stock,
+      moves_made: 0,
+    }
+  }
+
+  /// The piles from left to right, each from the bottom card up.
+  pub fn piles(&self) -> &[Vec<Card>] {
+    &self.piles
+  }
+
+  pub fn top(&self, pile: usize) -> Option<Card> {
+    self.piles.get(pile).and_then(|p| p.last().cloned())
+  }
+
+  pub fn stock_len(&self) -> usize {
+    self.stock.len()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.stock.is_empty() && self.piles.len() == 1
+  }
+
+  /// Whether no move is left.
+  pub fn is_over(&self) -> bool {
+    self.stock.is_empty() && self.collapses().is_empty()
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Number of collapses so far, 51 for a won game.
+  pub fn score(&self) -> i32 {
+    (NUM_CARDS - self.piles.len() - self.stock.len()) as i32
+  }
+
+  pub fn can_apply(&self, m: AccordionMove) -> AccordionResult<()> {
+    match m {
+      AccordionMove::Deal if self.stock.is_empty() => Err(AccordionErr::EmptyStock),
+      AccordionMove::Deal => Ok(()),
+      AccordionMove::Collapse { pile, distance } => {
+        let (pile, distance) = (pile as usize, distance as usize);
+        if (distance != 1 && distance != 3) || pile < distance {
+          return Err(AccordionErr::InvalidMove);
+        }
+
+        match (self.top(pile), self.top(pile - distance)) {
+          (Some(a), Some(b)) if matches(a, b) => Ok(()),
+          _ => Err(AccordionErr::InvalidMove),
+        }
+      },
+    }
+  }
+
+  pub fn apply(&mut self, m: AccordionMove) -> AccordionResult<()> {
+    self.can_apply(m)?;
+
+    match m {
+      AccordionMove::Deal => {
+        let card = self.stock.pop().unwrap();
+        self.piles.push(vec![card]);
+      },
+      AccordionMove::Collapse { pile, distance } => {
+        let cards = self.piles.remove(pile as usize);
+        self.piles[(pile - distance) as usize].extend(cards);
+      },
+    }
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  /// Every collapse that would currently succeed, from the left.
+  pub fn collapses(&self) -> Vec<AccordionMove> {
+    let mut moves = Vec::new();
+
+    for pile in 1..self.piles.len() {
+      for distance in [1, 3].iter() {
+        let m = AccordionMove::Collapse { pile: pile as u8, distance: *distance };
+        if self.can_apply(m).is_ok() {
+          moves.push(m);
+        }
+      }
+    }
+
+    moves
+  }
+
+  /// Every move that would currently succeed: the collapses, then the deal.
+  pub fn legal_moves(&self) -> Vec<AccordionMove> {
+    let mut moves = self.collapses();
+
+    if ! self.stock.is_empty() {
+      moves.push(AccordionMove::Deal);
+    }
+
+    moves
+  }
+
+  /// Check that no card is in the game twice and that the deck is complete.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    for card in self.piles.iter().flat_map(|p| p.iter()).chain(self.stock.iter()) {
+      if ! seen.insert(*card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    if seen.len() != NUM_CARDS {
+      return Err(format!("{} cards instead of {}", seen.len(), NUM_CARDS));
+    }
+
+    Ok(())
+  }
+
+  /// The top card of each pile from the left and the stock size, e.g.
+  /// `KS 5H 9H / 40`.
+  pub fn notation(&self) -> String {
+    let tops: Vec<String> = self.piles.iter().map(|p| p.last().unwrap().to_string()).collect();
+    format!("{} / {}", tops.join(" "), self.stock.len())
+  }
+}
+
+impl SolitaireGame for AccordionGame {
+  fn is_clear(&self) -> bool {
+    AccordionGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    AccordionGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    AccordionGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn collapses() {
+    let mut game = AccordionGame::new(|_| ());
+    game.piles = cards(&["5H", "9C", "2S", "5S", "KS"]).into_iter().map(|c| vec![c]).collect();
+
+    assert_eq!(game.collapses(), vec![
+      AccordionMove::Collapse { pile: 3, distance: 1 },
+      AccordionMove::Collapse { pile: 3, distance: 3 },
+      AccordionMove::Collapse { pile: 4, distance: 1 },
+    ]);
+    assert_eq!(game.can_apply(AccordionMove::Collapse { pile: 4, distance: 3 }), Err(AccordionErr::InvalidMove), "KS on 9C");
+    assert_eq!(game.can_apply(AccordionMove::Collapse { pile: 2, distance: 2 }), Err(AccordionErr::InvalidMove));
+    assert_eq!(game.can_apply(AccordionMove::Collapse { pile: 1, distance: 3 }), Err(AccordionErr::InvalidMove));
+
+    game.apply(AccordionMove::Collapse { pile: 4, distance: 1 }).unwrap();
+    assert_eq!(game.apply(AccordionMove::Collapse { pile: 3, distance: 3 }), Err(AccordionErr::InvalidMove), "KS on 5H");
+    game.apply(AccordionMove::Collapse { pile: 3, distance: 1 }).unwrap();
+    assert_eq!(game.notation(), "5H 9C KS / 51");
+    assert_eq!(game.piles()[2], cards(&["2S", "5S", "KS"]));
+    assert_eq!(game.moves_made(), 2);
+  }
+
+  #[test]
+  fn deal_to_the_end() {
+    let mut rng = Isaac64Rng::from_seed(&[2]);
+    let mut game = AccordionGame::new(|c| seeded_shuffle(2, c));
+    assert_eq!(game.piles().len(), 1);
+    assert_eq!(game.stock_len(), 51);
+
+    while ! game.is_over() {
+      let moves = game.legal_moves();
+      game.apply(*rng.choose(&moves).unwrap()).unwrap();
+      assert_eq!(game.validate(), Ok(()));
+    }
+
+    assert_eq!(game.score() as usize, 52 - game.piles().len());
+    assert_eq!(game.apply(AccordionMove::Deal), Err(AccordionErr::EmptyStock));
+  }
+}
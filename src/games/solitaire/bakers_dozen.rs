@@ -0,0 +1,290 @@
+//! Baker's Dozen solitaire.
+//!
+//! The whole deck is dealt face up to thirteen columns of four; any king
+//! dealt to a column is moved to its bottom, under the other cards. The top
+//! card of a column can be moved to its foundation, or onto the top card of
+//! another column when it is one rank lower, regardless of suit. Only one
+//! card moves at a time and an empty column stays empty. The game is won when
+//! every card is on the foundations.
+
+use cards::french::{self, Rank, Suit};
+use games::solitaire::SolitaireGame;
+use games::solitaire::klondike::{Card, Foundation};
+use std::collections::HashSet;
+use std::fmt;
+
+pub const NUM_COLUMNS: usize = 13;
+pub const COLUMN_LEN: usize = 4;
+pub const NUM_FOUNDATIONS: usize = 4;
+const NUM_CARDS: usize = 52;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BakersDozenErr {
+  /// The column is empty or does not exist.
+  EmptyColumn,
+  /// The card does not go on the target.
+  InvalidMove,
+}
+
+pub type BakersDozenResult<T> = Result<T, BakersDozenErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BakersDozenMove {
+  /// Move the top card of column `source` onto column `target`.
+  Build { source: u8, target: u8 },
+  /// Move the top card of a column to its foundation.
+  Found(u8),
+}
+
+impl fmt::Display for BakersDozenMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      BakersDozenMove::Build { source, target } => write!(f, "{}->{}", source, target),
+      BakersDozenMove::Found(source) => write!(f, "{}->f", source),
+    }
+  }
+}
+
+/// Whether `card` can be moved onto `target` in a column.
+pub fn builds_on(card: Card, target: Card) -> bool {
+  card.rank().value() + 1 == target.rank().value()
+}
+
+#[derive(Clone)]
+pub struct BakersDozenGame {
+  /// Each from the bottom card up.
+  columns: Vec<Vec<Card>>,
+  /// In the order of `Suit::standard_iter`.
+  foundations: Vec<Foundation>,
+  moves_made: usize,
+}
+
+impl BakersDozenGame {
+  /// New game with a standard deck arranged by `shuffle`, dealt one column
+  /// after the other from the bottom card up. The kings of each column are
+  /// then moved to its bottom, keeping their order and the order of the
+  /// other cards.
+  pub fn new<F>(mut shuffle: F) -> BakersDozenGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let columns = cards.chunks(COLUMN_LEN).map(|c| {
+      let (mut kings, others): (Vec<Card>, Vec<Card>) = c.iter().cloned().partition(|c| c.rank() == Rank::King);
+      kings.extend(others);
+      kings
+    }).collect();
+
+    BakersDozenGame {
+      columns: columns,
+      foundations: Suit::standard_iter().map(|s| Foundation::new(*s)).collect(),
+      moves_made: 0,
+    }
+  }
+
+  pub fn columns(&self) -> &[Vec<Card>] {
+    &self.columns
+  }
+
+  pub fn column(&self, index: usize) -> &[Card] {
+    &self.columns[index]
+  }
+
+  pub fn foundations(&self) -> &[Foundation] {
+    &self.foundations
+  }
+
+  pub fn foundation(&self, suit: Suit) -> &Foundation {
+    self.foundations.iter().find(|f| f.suit() == suit).unwrap()
+  }
+
+  pub fn founded(&self) -> usize {
+    self.foundations.iter().map(|f| f.len()).sum()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.founded() == NUM_CARDS
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Cards on the foundations.
+  pub fn score(&self) -> i32 {
+    self.founded() as i32
+  }
+
+  pub fn can_apply(&self, m: BakersDozenMove) -> BakersDozenResult<()> {
+    let source = match m {
+      BakersDozenMove::Build { source, .. } | BakersDozenMove::Found(source) => source as usize,
+    };
+
+    let card = match self.columns.get(source).and_then(|c| c.last()) {
+      Some(card) => *card,
+      None => return Err(BakersDozenErr::EmptyColumn),
+    };
+
+    let legal = match m {
+      BakersDozenMove::Build { target, .. } => match self.columns.get(target as usize).and_then(|c| c.last()) {
+        Some(top) => builds_on(card, *top),
+        None => false,
+      },
+      BakersDozenMove::Found(_) => self.foundation(card.suit()).can_push(card),
+    };
+
+    if legal { Ok(()) } else { Err(BakersDozenErr::InvalidMove) }
+  }
+
+  pub fn apply(&mut self, m: BakersDozenMove) -> BakersDozenResult<()> {
+    self.can_apply(m)?;
+
+    match m {
+      BakersDozenMove::Build { source, target } => {
+        let card = self.columns[source as usize].pop().unwrap();
+        self.columns[target as usize].push(card);
+      },
+      BakersDozenMove::Found(source) => {
+        let card = self.columns[source as usize].pop().unwrap();
+        self.foundations.iter_mut().find(|f| f.suit() == card.suit()).unwrap().push();
+      },
+    }
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  /// Every move that would currently succeed, foundation moves first.
+  pub fn legal_moves(&self) -> Vec<BakersDozenMove> {
+    let mut moves: Vec<BakersDozenMove> = (0..NUM_COLUMNS).map(|i| BakersDozenMove::Found(i as u8)).collect();
+
+    for source in 0..NUM_COLUMNS {
+      for target in 0..NUM_COLUMNS {
+        moves.push(BakersDozenMove::Build { source: source as u8, target: target as u8 });
+      }
+    }
+
+    moves.retain(|m| self.can_apply(*m).is_ok());
+    moves
+  }
+
+  /// Check that the game holds every card of the deck once.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    let cards = self.columns.iter().flat_map(|c| c.iter().cloned())
+      .chain(self.foundations.iter().flat_map(|f| f.cards()));
+
+    for card in cards {
+      if ! seen.insert(card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    match french::new_standard_deck().into_iter().find(|c| ! seen.contains(c)) {
+      Some(card) => Err(format!("missing card {}", card)),
+      None => Ok(()),
+    }
+  }
+
+  /// The columns from bottom to top, then the top card of each foundation,
+  /// e.g. `KS 5H 2D|...|- / AD - - 3S`.
+  pub fn notation(&self) -> String {
+    let columns: Vec<String> = self.columns.iter().map(|c| {
+      let cards: Vec<String> = c.iter().map(|c| c.to_string()).collect();
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
+    }).collect();
+
+    let foundations: Vec<String> = self.foundations.iter().map(|f| f.top().map_or(String::from("-"), |c| c.to_string())).collect();
+
+    format!("{} / {}", columns.join("|"), foundations.join(" "))
+  }
+}
+
+impl SolitaireGame for BakersDozenGame {
+  fn is_clear(&self) -> bool {
+    BakersDozenGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    BakersDozenGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    BakersDozenGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = BakersDozenGame::new(|c| seeded_shuffle(1, c));
+
+    assert!(game.columns().iter().all(|c| c.len() == COLUMN_LEN));
+    assert_eq!(game.validate(), Ok(()));
+
+    for column in game.columns() {
+      let kings = column.iter().take_while(|c| c.rank() == Rank::King).count();
+      assert!(column[kings..].iter().all(|c| c.rank() != Rank::King), "kings at the bottom of {:?}", column);
+    }
+  }
+
+  #[test]
+  fn kings_to_the_bottom() {
+    let game = BakersDozenGame::new(|c| {
+      let mut front = cards(&["5H", "KS", "2D", "KC"]);
+      front.extend(c.iter().cloned().filter(|card| ! front.contains(card)).collect::<Vec<Card>>());
+      *c = front;
+    });
+
+    assert_eq!(game.column(0), &cards(&["KS", "KC", "5H", "2D"])[..]);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn moves() {
+    let mut game = BakersDozenGame::new(|_| ());
+    game.columns = vec![cards(&["KD", "AH"]), cards(&["3S"]), cards(&["2C"]), Vec::new()];
+    game.columns.extend((4..NUM_COLUMNS).map(|_| Vec::new()));
+
+    assert_eq!(game.legal_moves(), vec![
+      BakersDozenMove::Found(0),
+      BakersDozenMove::Build { source: 0, target: 2 },
+      BakersDozenMove::Build { source: 2, target: 1 },
+    ]);
+    assert_eq!(game.apply(BakersDozenMove::Found(3)), Err(BakersDozenErr::EmptyColumn));
+    assert_eq!(game.apply(BakersDozenMove::Build { source: 2, target: 3 }), Err(BakersDozenErr::InvalidMove), "empty columns stay empty");
+    assert_eq!(game.apply(BakersDozenMove::Build { source: 1, target: 2 }), Err(BakersDozenErr::InvalidMove));
+
+    game.apply(BakersDozenMove::Found(0)).unwrap();
+    game.apply(BakersDozenMove::Build { source: 2, target: 1 }).unwrap();
+    assert_eq!(game.column(1), &cards(&["3S", "2C"])[..]);
+    assert_eq!(game.foundation(Suit::Hearts).len(), 1);
+    assert_eq!(game.score(), 1);
+    assert_eq!(game.moves_made(), 2);
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[5]);
+    let mut game = BakersDozenGame::new(|c| seeded_shuffle(5, c));
+
+    for _ in 0..300 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+    }
+  }
+}
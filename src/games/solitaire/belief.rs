@@ -0,0 +1,312 @@
+//! Probability of each unknown card lying in each hidden pile slot of a
+//! Klondike game, updated as cards are turned face up.
+//!
+//! The unknown cards are the ones `CardTracker::unknown_cards` lists. They
+//! are spread over the hidden pile slots, each holding one card, and the
+//! unseen part of the deck, holding the rest. The belief is a matrix of
+//! probabilities whose rows are the slots and whose columns are the cards:
+//! every card is somewhere, so each column sums to 1, and each slot holds its
+//! number of cards, so each row sums to its size. For a shuffled deal every
+//! arrangement of the unknown cards is equally likely and the matrix stays
+//! uniform; `weigh` folds in other evidence, such as a model of how a
+//! position was reached, and the matrix is rebalanced to keep the sums.
+
+use games::solitaire::klondike::{Card, KlondikeSolitaireGame};
+use games::solitaire::tracker::CardTracker;
+use rand::Rng;
+
+/// Rebalancing passes after the matrix changes.
+const BALANCE_ITERATIONS: usize = 100;
+
+/// Place an unknown card can be in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HiddenSlot {
+  /// Hidden card `index` of a pile, 0 for the bottom card.
+  Pile(usize, usize),
+  /// Anywhere among the deck cards not seen yet.
+  Deck,
+}
+
+#[derive(Debug, Clone)]
+pub struct HiddenCardBelief {
+  /// Hidden pile slots in `determinize` order, then the deck.
+  slots: Vec<HiddenSlot>,
+  cards: Vec<Card>,
+  deck_size: usize,
+  /// `weights[slot][card]`
+  weights: Vec<Vec<f64>>,
+}
+
+impl HiddenCardBelief {
+  /// Uniform belief over the unknown cards of `game`.
+  pub fn new(game: &KlondikeSolitaireGame, tracker: &CardTracker) -> HiddenCardBelief {
+    let cards = tracker.unknown_cards(game);
+    let mut slots = Vec::new();
+
+    for (i, pile) in game.piles().iter().enumerate() {
+      for j in 0..pile.hidden_cards().len() {
+        slots.push(HiddenSlot::Pile(i, j));
+      }
+    }
+
+    let deck_size = cards.len() - slots.len();
+    slots.push(HiddenSlot::Deck);
+
+    let n = cards.len() as f64;
+    let weights = slots.iter()
+      .map(|s| vec![if *s == HiddenSlot::Deck { deck_size as f64 / n } else { 1.0 / n }; cards.len()])
+      .collect();
+
+    HiddenCardBelief {
+      slots: slots,
+      cards: cards,
+      deck_size: deck_size,
+      weights: weights,
+    }
+  }
+
+  /// The unknown cards.
+  pub fn cards(&self) -> &[Card] {
+    &self.cards
+  }
+
+  /// Slots still hidden, the pile slots first.
+  pub fn slots(&self) -> &[HiddenSlot] {
+    &self.slots
+  }
+
+  /// Probability that `card` is in `slot`, 0 if either is not unknown.
+  pub fn probability(&self, slot: HiddenSlot, card: Card) -> f64 {
+    match (self.slot_index(slot), self.card_index(card)) {
+      (Some(s), Some(c)) => self.weights[s][c],
+      _ => 0.0,
+    }
+  }
+
+  /// Probability of each unknown card being in `slot`, most likely first.
+  /// For the deck the probabilities sum to the number of unseen deck cards.
+  pub fn distribution(&self, slot: HiddenSlot) -> Vec<(Card, f64)> {
+    let mut distribution: Vec<(Card, f64)> = match self.slot_index(slot) {
+      Some(s) => self.cards.iter().cloned().zip(self.weights[s].iter().cloned()).collect(),
+      None => Vec::new(),
+    };
+
+    distribution.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    distribution
+  }
+
+  /// Multiply the chance of `card` being in `slot` by `likelihood`, relative
+  /// to the other cards, and rebalance.
+  pub fn weigh(&mut self, slot: HiddenSlot, card: Card, likelihood: f64) {
+    assert!(likelihood >= 0.0, "negative likelihood {}", likelihood);
+
+    if let (Some(s), Some(c)) = (self.slot_index(slot), self.card_index(card)) {
+      self.weights[s][c] *= likelihood;
+      self.balance();
+    }
+  }
+
+  /// Condition on the cards turned face up since the last update: flipped
+  /// pile cards fill their slot, drawn deck cards leave the deck. Call after
+  /// every move, with `tracker` already updated, so a flipped card is known
+  /// to come from the pile it was turned on.
+  pub fn observe(&mut self, game: &KlondikeSolitaireGame, tracker: &CardTracker) {
+    let unknown = tracker.unknown_cards(game);
+    let revealed: Vec<Card> = self.cards.iter().cloned().filter(|c| ! unknown.contains(c)).collect();
+
+    if revealed.is_empty() {
+      return;
+    }
+
+    for card in revealed {
+      let c = self.card_index(card).unwrap();
+
+      if tracker.has_seen(card) {
+        self.deck_size -= 1;
+      } else {
+        let flipped = self.slots.iter().position(|s| match *s {
+          HiddenSlot::Pile(i, j) => j >= game.pile(i).hidden_cards().len(),
+          HiddenSlot::Deck => false,
+        });
+
+        if let Some(s) = flipped {
+          self.slots.remove(s);
+          self.weights.remove(s);
+        }
+      }
+
+      self.cards.remove(c);
+      for row in self.weights.iter_mut() {
+        row.remove(c);
+      }
+    }
+
+    self.balance();
+  }
+
+  /// An arrangement of the unknown cards drawn from the belief, in the order
+  /// `KlondikeSolitaireGame::determinize` deals them: the hidden cards of
+  /// each pile from the bottom, then the unseen deck cards. Pile slots are
+  /// drawn in turn from the cards left, so the result follows the belief
+  /// closely but not exactly when it is far from uniform.
+  pub fn sample<R: Rng>(&self, rng: &mut R) -> Vec<Card> {
+    let mut left: Vec<usize> = (0..self.cards.len()).collect();
+    let mut arrangement = Vec::with_capacity(self.cards.len());
+
+    for (s, slot) in self.slots.iter().enumerate() {
+      if *slot == HiddenSlot::Deck {
+        continue;
+      }
+
+      let total: f64 = left.iter().map(|&c| self.weights[s][c]).sum();
+      let pick = if total > 0.0 {
+        let mut x = rng.gen::<f64>() * total;
+        left.iter().position(|&c| { x -= self.weights[s][c]; x < 0.0 }).unwrap_or(left.len() - 1)
+      } else {
+        rng.gen_range(0, left.len())
+      };
+
+      arrangement.push(self.cards[left.remove(pick)]);
+    }
+
+    let mut deck: Vec<Card> = left.into_iter().map(|c| self.cards[c]).collect();
+    rng.shuffle(&mut deck);
+    arrangement.extend(deck);
+    arrangement
+  }
+
+  fn slot_index(&self, slot: HiddenSlot) -> Option<usize> {
+    self.slots.iter().position(|s| *s == slot)
+  }
+
+  fn card_index(&self, card: Card) -> Option<usize> {
+    self.cards.iter().position(|c| *c == card)
+  }
+
+  fn capacity(&self, slot: HiddenSlot) -> f64 {
+    match slot {
+      HiddenSlot::Pile(_, _) => 1.0,
+      HiddenSlot::Deck => self.deck_size as f64,
+    }
+  }
+
+  /// Scale rows to their slot sizes and columns to 1 in turn until both
+  /// hold.
+  fn balance(&mut self) {
+    for _ in 0..BALANCE_ITERATIONS {
+      for s in 0..self.slots.len() {
+        let capacity = self.capacity(self.slots[s]);
+        let total: f64 = self.weights[s].iter().sum();
+
+        if total > 0.0 {
+          for w in self.weights[s].iter_mut() {
+            *w *= capacity / total;
+          }
+        }
+      }
+
+      for c in 0..self.cards.len() {
+        let total: f64 = self.weights.iter().map(|row| row[c]).sum();
+
+        if total > 0.0 {
+          for row in self.weights.iter_mut() {
+            row[c] /= total;
+          }
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_source;
+  use games::solitaire::klondike::{KlondikeRules, MoveSource, MoveTarget};
+  use rand::{Isaac64Rng, SeedableRng};
+
+  fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+  }
+
+  fn game(seed: u64) -> KlondikeSolitaireGame {
+    KlondikeSolitaireGame::with_rules(KlondikeRules::new(1), seeded_source(seed))
+  }
+
+  #[test]
+  fn uniform_deal() {
+    let game = game(1);
+    let belief = HiddenCardBelief::new(&game, &CardTracker::new());
+
+    assert_eq!(belief.cards().len(), 45);
+    assert_eq!(belief.slots().len(), 22);
+
+    let card = belief.cards()[0];
+    assert_close(belief.probability(HiddenSlot::Pile(6, 0), card), 1.0 / 45.0);
+    assert_close(belief.probability(HiddenSlot::Deck, card), 24.0 / 45.0);
+    assert_eq!(belief.probability(HiddenSlot::Pile(0, 0), card), 0.0);
+
+    let total: f64 = belief.distribution(HiddenSlot::Pile(3, 2)).iter().map(|&(_, p)| p).sum();
+    assert_close(total, 1.0);
+  }
+
+  #[test]
+  fn weigh_rebalances() {
+    let game = game(1);
+    let mut belief = HiddenCardBelief::new(&game, &CardTracker::new());
+    let card = belief.cards()[5];
+
+    belief.weigh(HiddenSlot::Pile(6, 5), card, 0.0);
+    assert_eq!(belief.probability(HiddenSlot::Pile(6, 5), card), 0.0);
+
+    let row: f64 = belief.distribution(HiddenSlot::Pile(6, 5)).iter().map(|&(_, p)| p).sum();
+    assert_close(row, 1.0);
+
+    let column: f64 = belief.slots().iter().map(|s| belief.probability(*s, card)).sum();
+    assert_close(column, 1.0);
+    assert!(belief.probability(HiddenSlot::Pile(6, 4), card) > 1.0 / 45.0);
+
+    let mut rng = Isaac64Rng::from_seed(&[1]);
+    let slot = belief.slots().iter().position(|s| *s == HiddenSlot::Pile(6, 5)).unwrap();
+    for _ in 0..200 {
+      let arrangement = belief.sample(&mut rng);
+      assert_eq!(arrangement.len(), 45);
+      assert!(arrangement[slot] != card);
+    }
+  }
+
+  #[test]
+  fn observe_flips_and_draws() {
+    // Find a deal with a pile to pile move, which turns a card over
+    let (mut game, source, target) = (0..200).filter_map(|seed| {
+      let game = game(seed);
+      (1..7u8).flat_map(|s| (0..7u8).map(move |t| (s, t)))
+        .find(|&(s, t)| s != t && game.clone().move_cards(MoveSource::Pile(s), MoveTarget::Pile(t)).is_ok())
+        .map(|(s, t)| (game, s, t))
+    }).next().unwrap();
+
+    let mut tracker = CardTracker::new();
+    let mut belief = HiddenCardBelief::new(&game, &tracker);
+
+    game.move_cards(MoveSource::Pile(source), MoveTarget::Pile(target)).unwrap();
+    tracker.observe(&game);
+    belief.observe(&game, &tracker);
+
+    assert_eq!(belief.cards().len(), 44);
+    assert_eq!(belief.slots().len(), 21);
+    assert!(! belief.slots().contains(&HiddenSlot::Pile(source as usize, source as usize - 1)));
+    assert_close(belief.probability(HiddenSlot::Pile(6, 0), belief.cards()[0]), 1.0 / 44.0);
+
+    game.draw();
+    tracker.observe(&game);
+    belief.observe(&game, &tracker);
+
+    assert_eq!(belief.cards().len(), 43);
+    assert_close(belief.probability(HiddenSlot::Deck, belief.cards()[0]), 23.0 / 43.0);
+
+    let mut rng = Isaac64Rng::from_seed(&[2]);
+    let arrangement = belief.sample(&mut rng);
+    let det = game.determinize(|c| tracker.has_seen(c), |c| *c = arrangement.clone());
+    assert_eq!(det.validate(), Ok(()));
+  }
+}
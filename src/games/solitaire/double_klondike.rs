@@ -0,0 +1,395 @@
+//! Double Klondike, also called Gargantua.
+//!
+//! Klondike with two decks: nine piles dealt the klondike way, one to nine
+//! cards with the top one face up, and the other 59 cards in the deck. There
+//! are eight foundations, two of each suit, and a card can go to either
+//! foundation of its suit. The deck is dealt through twice by default. The
+//! game is won when both decks are on the foundations.
+
+use cards::french;
+use games::solitaire::SolitaireGame;
+use games::solitaire::klondike::{deal_piles, Card, Deck, Foundation, KlondikeErr, KlondikeResult, Pile};
+use std::cmp;
+use std::collections::HashMap;
+use std::fmt;
+
+pub const NUM_DECKS: usize = 2;
+pub const NUM_PILES: usize = 9;
+pub const NUM_FOUNDATIONS: usize = 4 * NUM_DECKS;
+const NUM_CARDS: usize = 52 * NUM_DECKS;
+const DECK_LEN: usize = NUM_CARDS - NUM_PILES * (NUM_PILES + 1) / 2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DoubleKlondikeRules {
+  pub draw_count: u8,
+  /// Number of times the deck may be dealt through, `None` for no limit.
+  pub pass_limit: Option<usize>,
+}
+
+impl DoubleKlondikeRules {
+  /// Draw one, two passes through the deck.
+  pub fn new() -> DoubleKlondikeRules {
+    DoubleKlondikeRules {
+      draw_count: 1,
+      pass_limit: Some(2),
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Source {
+  Deck,
+  /// A foundation by index, see `DoubleKlondikeGame::foundations`.
+  Foundation(u8),
+  Pile(u8),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Target {
+  /// Whichever foundation of the card's suit takes it, the first one if
+  /// both do.
+  Foundation,
+  Pile(u8),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DoubleKlondikeMove {
+  Draw,
+  /// Move cards the klondike way: the top card, or from a pile to another
+  /// pile the visible cards from the one that fits on the target.
+  Cards(Source, Target),
+}
+
+impl fmt::Display for DoubleKlondikeMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DoubleKlondikeMove::Draw => write!(f, "draw"),
+      DoubleKlondikeMove::Cards(source, target) => {
+        match source {
+          Source::Deck => write!(f, "deck")?,
+          Source::Foundation(i) => write!(f, "foundation{}", i)?,
+          Source::Pile(i) => write!(f, "pile{}", i)?,
+        }
+
+        match target {
+          Target::Foundation => write!(f, "->foundation"),
+          Target::Pile(i) => write!(f, "->pile{}", i),
+        }
+      },
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct DoubleKlondikeGame {
+  piles: Vec<Pile>,
+  foundations: Vec<Foundation>,
+  deck: Deck,
+  moves_made: usize,
+  hidden_flips: usize,
+}
+
+impl DoubleKlondikeGame {
+  /// New game with two standard decks, one after the other, arranged by
+  /// `shuffle` and dealt with `klondike::deal_piles`.
+  pub fn new<F>(rules: DoubleKlondikeRules, mut shuffle: F) -> DoubleKlondikeGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards: Vec<Card> = (0..NUM_DECKS).flat_map(|_| french::new_standard_deck()).collect();
+    shuffle(&mut cards);
+
+    let mut piles = vec![Pile::new(); NUM_PILES];
+    let dealt = deal_piles(&cards, &mut piles);
+
+    let mut deck = Deck::with_max_len(rules.draw_count, DECK_LEN);
+    deck.set_pass_limit(rules.pass_limit);
+    deck.reset(&cards[dealt..]);
+
+    DoubleKlondikeGame {
+      piles: piles,
+      foundations: Foundation::for_decks(NUM_DECKS),
+      deck: deck,
+      moves_made: 0,
+      hidden_flips: 0,
+    }
+  }
+
+  pub fn piles(&self) -> &[Pile] {
+    &self.piles
+  }
+
+  pub fn pile(&self, index: usize) -> &Pile {
+    &self.piles[index]
+  }
+
+  /// The foundations of the first deck in the klondike suit order, then the
+  /// ones of the second deck.
+  pub fn foundations(&self) -> &[Foundation] {
+    &self.foundations
+  }
+
+  pub fn deck(&self) -> &Deck {
+    &self.deck
+  }
+
+  /// Index of the first foundation that takes `card`.
+  pub fn foundation_for(&self, card: Card) -> Option<usize> {
+    self.foundations.iter().position(|f| f.can_push(card))
+  }
+
+  pub fn founded(&self) -> usize {
+    self.foundations.iter().map(|f| f.len()).sum()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.founded() == NUM_CARDS
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Number of hidden pile cards turned face up since the deal.
+  pub fn hidden_flips(&self) -> usize {
+    self.hidden_flips
+  }
+
+  /// Cards on the foundations.
+  pub fn score(&self) -> i32 {
+    self.founded() as i32
+  }
+
+  /// Top card of `source`, if it has one.
+  fn top(&self, source: Source) -> Option<Card> {
+    match source {
+      Source::Deck => self.deck.top(),
+      Source::Foundation(i) => self.foundations.get(i as usize).and_then(|f| f.top()),
+      Source::Pile(i) => self.piles.get(i as usize).and_then(|p| p.top()),
+    }
+  }
+
+  pub fn can_apply(&self, m: DoubleKlondikeMove) -> KlondikeResult<()> {
+    let (source, target) = match m {
+      DoubleKlondikeMove::Draw if self.deck.can_draw() => return Ok(()),
+      DoubleKlondikeMove::Draw => return Err(KlondikeErr::InvalidMove),
+      DoubleKlondikeMove::Cards(source, target) => (source, target),
+    };
+
+    let card = self.top(source).ok_or(KlondikeErr::InvalidMove)?;
+
+    match (source, target) {
+      (Source::Foundation(_), Target::Foundation) => Err(KlondikeErr::InvalidMove),
+      (_, Target::Foundation) => self.foundation_for(card).map(|_| ()).ok_or(KlondikeErr::InvalidCard(card)),
+      (_, Target::Pile(t)) if t as usize >= NUM_PILES => Err(KlondikeErr::InvalidMove),
+      (Source::Pile(s), Target::Pile(t)) if s == t => Err(KlondikeErr::InvalidMove),
+      (Source::Pile(s), Target::Pile(t)) => {
+        let (source, target) = (&self.piles[s as usize], &self.piles[t as usize]);
+        if source.visible_cards().iter().any(|c| target.can_push(*c).is_ok()) {
+          Ok(())
+        } else {
+          Err(KlondikeErr::InvalidCard(source.visible_cards()[0]))
+        }
+      },
+      (_, Target::Pile(t)) => self.piles[t as usize].can_push(card),
+    }
+  }
+
+  pub fn apply(&mut self, m: DoubleKlondikeMove) -> KlondikeResult<()> {
+    self.can_apply(m)?;
+
+    let hidden = self.hidden_count();
+
+    match m {
+      DoubleKlondikeMove::Draw => { self.deck.draw(); },
+      DoubleKlondikeMove::Cards(Source::Pile(s), Target::Pile(t)) => {
+        let (s, t) = (s as usize, t as usize);
+        let (low, high) = self.piles.split_at_mut(cmp::max(s, t));
+        let (source, target) = if s < t { (&mut low[s], &mut high[0]) } else { (&mut high[0], &mut low[t]) };
+        source.move_to(target)?;
+      },
+      DoubleKlondikeMove::Cards(source, target) => {
+        let card = self.top(source).unwrap();
+        match source {
+          Source::Deck => self.deck.pop(),
+          Source::Foundation(i) => self.foundations[i as usize].pop(),
+          Source::Pile(i) => self.piles[i as usize].pop(),
+        };
+
+        match target {
+          Target::Foundation => {
+            let i = self.foundation_for(card).unwrap();
+            self.foundations[i].push();
+          },
+          Target::Pile(i) => self.piles[i as usize].push(card)?,
+        }
+      },
+    }
+
+    self.hidden_flips += hidden - self.hidden_count();
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  fn hidden_count(&self) -> usize {
+    self.piles.iter().map(|p| p.hidden_cards().len()).sum()
+  }
+
+  /// Every move that would currently succeed, the draw first.
+  pub fn legal_moves(&self) -> Vec<DoubleKlondikeMove> {
+    let mut sources = vec![Source::Deck];
+    sources.extend((0..NUM_FOUNDATIONS).map(|i| Source::Foundation(i as u8)));
+    sources.extend((0..NUM_PILES).map(|i| Source::Pile(i as u8)));
+
+    let mut targets = vec![Target::Foundation];
+    targets.extend((0..NUM_PILES).map(|i| Target::Pile(i as u8)));
+
+    let mut moves = vec![DoubleKlondikeMove::Draw];
+
+    for source in sources.iter() {
+      for target in targets.iter() {
+        moves.push(DoubleKlondikeMove::Cards(*source, *target));
+      }
+    }
+
+    moves.retain(|m| self.can_apply(*m).is_ok());
+    moves
+  }
+
+  /// Check that the game holds every card of both decks twice.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut counts: HashMap<Card, usize> = HashMap::new();
+
+    let cards = self.piles.iter()
+      .flat_map(|p| p.hidden_cards().iter().chain(p.visible_cards().iter()).cloned().collect::<Vec<Card>>())
+      .chain(self.deck.waste_cards().iter().chain(self.deck.visible_cards()).chain(self.deck.remaining_cards()).cloned())
+      .chain(self.foundations.iter().flat_map(|f| f.cards()));
+
+    for card in cards {
+      *counts.entry(card).or_insert(0) += 1;
+    }
+
+    for card in french::new_standard_deck() {
+      match counts.get(&card).cloned().unwrap_or(0) {
+        NUM_DECKS => (),
+        n => return Err(format!("{} copies of {}", n, card)),
+      }
+    }
+
+    if counts.len() != NUM_CARDS / NUM_DECKS {
+      return Err(format!("{} distinct cards", counts.len()));
+    }
+
+    Ok(())
+  }
+
+  /// The piles from bottom to top with hidden cards in lower case, the top
+  /// card of each foundation, then the waste, visible and remaining deck
+  /// cards, e.g. `5h KS|...|- / AD - ... 3S / 4C : 9H : ...`.
+  pub fn notation(&self) -> String {
+    fn group(cards: Vec<String>) -> String {
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
+    }
+
+    fn names(cards: &[Card]) -> Vec<String> {
+      cards.iter().map(|c| c.to_string()).collect()
+    }
+
+    let piles: Vec<String> = self.piles.iter().map(|p| {
+      let cards: Vec<String> = p.hidden_cards().iter().map(|c| c.to_string().to_lowercase())
+        .chain(names(p.visible_cards()))
+        .collect();
+      group(cards)
+    }).collect();
+
+    let foundations: Vec<String> = self.foundations.iter().map(|f| f.top().map_or(String::from("-"), |c| c.to_string())).collect();
+
+    format!("{} / {} / {} : {} : {}", piles.join("|"), foundations.join(" "),
+            group(names(self.deck.waste_cards())), group(names(self.deck.visible_cards())), group(names(self.deck.remaining_cards())))
+  }
+}
+
+impl SolitaireGame for DoubleKlondikeGame {
+  fn is_clear(&self) -> bool {
+    DoubleKlondikeGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    DoubleKlondikeGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    DoubleKlondikeGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::french::Suit;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = DoubleKlondikeGame::new(DoubleKlondikeRules::new(), |c| seeded_shuffle(1, c));
+
+    let hidden: Vec<usize> = game.piles().iter().map(|p| p.hidden_cards().len()).collect();
+    assert_eq!(hidden, (0..NUM_PILES).collect::<Vec<usize>>());
+    assert!(game.piles().iter().all(|p| p.visible_cards().len() == 1));
+    assert_eq!(game.deck().len(), 59);
+    assert_eq!(game.foundations().len(), 8);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn two_foundations_per_suit() {
+    let mut game = DoubleKlondikeGame::new(DoubleKlondikeRules::new(), |_| ());
+    game.piles = vec![Pile::with_cards(Vec::new(), vec![card("AH")]), Pile::with_cards(Vec::new(), vec![card("AH")])];
+    game.piles.extend((2..NUM_PILES).map(|_| Pile::new()));
+
+    game.apply(DoubleKlondikeMove::Cards(Source::Pile(0), Target::Foundation)).unwrap();
+    game.apply(DoubleKlondikeMove::Cards(Source::Pile(1), Target::Foundation)).unwrap();
+
+    let hearts: Vec<usize> = game.foundations().iter().filter(|f| f.suit() == Suit::Hearts).map(|f| f.len()).collect();
+    assert_eq!(hearts, vec![1, 1]);
+    assert_eq!(game.foundation_for(card("2H")), Some(0));
+    assert_eq!(game.foundation_for(card("AH")), None);
+    assert_eq!(game.score(), 2);
+
+    assert_eq!(game.can_apply(DoubleKlondikeMove::Cards(Source::Foundation(0), Target::Foundation)), Err(KlondikeErr::InvalidMove));
+    assert_eq!(game.can_apply(DoubleKlondikeMove::Cards(Source::Foundation(1), Target::Pile(0))), Err(KlondikeErr::InvalidMove), "empty foundation");
+  }
+
+  #[test]
+  fn pass_limit() {
+    let mut game = DoubleKlondikeGame::new(DoubleKlondikeRules::new(), |c| seeded_shuffle(2, c));
+
+    let mut draws = 0;
+    while game.can_apply(DoubleKlondikeMove::Draw).is_ok() {
+      game.apply(DoubleKlondikeMove::Draw).unwrap();
+      draws += 1;
+    }
+
+    // Through the deck, turned over, and through it again
+    assert_eq!(draws, 2 * 59 + 1);
+    assert_eq!(game.deck().passes(), 2);
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[7]);
+    let mut game = DoubleKlondikeGame::new(DoubleKlondikeRules::new(), |c| seeded_shuffle(7, c));
+
+    for _ in 0..500 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+    }
+  }
+}
@@ -0,0 +1,340 @@
+//! Easthaven solitaire.
+//!
+//! Seven piles of three cards are dealt, the top card of each face up, and
+//! the other 31 cards form the stock. Cards are built on the piles and the
+//! foundations the klondike way, and a sequence built on a pile moves as a
+//! unit. Dealing from the stock puts one card face up on every pile, empty
+//! ones included, whatever its rank; the last deal only reaches the first
+//! three piles. The game is won when every card is on the foundations.
+
+use cards::french::{self, Suit};
+use games::solitaire::SolitaireGame;
+use games::solitaire::klondike::{Card, Foundation, KlondikeErr, KlondikeResult, Pile};
+use games::solitaire::row_stock::RowStock;
+use std::cmp;
+use std::collections::HashSet;
+use std::fmt;
+
+pub const NUM_PILES: usize = 7;
+pub const PILE_LEN: usize = 3;
+pub const NUM_FOUNDATIONS: usize = 4;
+const NUM_CARDS: usize = 52;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EasthavenMove {
+  /// Deal a row from the stock.
+  Deal,
+  /// Move visible card `index` of pile `source` and the sequence on top of
+  /// it onto pile `target`.
+  Pile { source: u8, index: u8, target: u8 },
+  /// Move the top card of a pile to its foundation.
+  Found(u8),
+}
+
+impl fmt::Display for EasthavenMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      EasthavenMove::Deal => write!(f, "deal"),
+      EasthavenMove::Pile { source, index, target } => write!(f, "{}:{}->{}", source, index, target),
+      EasthavenMove::Found(source) => write!(f, "{}->f", source),
+    }
+  }
+}
+
+/// Whether `card` is built on `below` the klondike way.
+pub fn builds_on(card: Card, below: Card) -> bool {
+  card.color() != below.color() && card.rank().value() + 1 == below.rank().value()
+}
+
+/// Number of visible cards at the top of `pile` that form one sequence.
+pub fn run_len(pile: &Pile) -> usize {
+  let visible = pile.visible_cards();
+  if visible.is_empty() {
+    return 0;
+  }
+
+  1 + visible.windows(2).rev().take_while(|pair| builds_on(pair[1], pair[0])).count()
+}
+
+#[derive(Clone)]
+pub struct EasthavenGame {
+  piles: Vec<Pile>,
+  /// In the order of `Suit::standard_iter`.
+  foundations: Vec<Foundation>,
+  stock: RowStock<Card>,
+  moves_made: usize,
+  hidden_flips: usize,
+}
+
+impl EasthavenGame {
+  /// New game with a standard deck arranged by `shuffle`. The first cards go
+  /// to the piles, one pile after the other from the bottom card up, and the
+  /// rest to the stock, the first of them dealt first.
+  pub fn new<F>(mut shuffle: F) -> EasthavenGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let stock = cards.split_off(NUM_PILES * PILE_LEN);
+    let piles = cards.chunks(PILE_LEN).map(|c| Pile::with_cards(c[..PILE_LEN - 1].to_vec(), vec![c[PILE_LEN - 1]])).collect();
+
+    EasthavenGame {
+      piles: piles,
+      foundations: Suit::standard_iter().map(|s| Foundation::new(*s)).collect(),
+      stock: RowStock::new(stock),
+      moves_made: 0,
+      hidden_flips: 0,
+    }
+  }
+
+  pub fn piles(&self) -> &[Pile] {
+    &self.piles
+  }
+
+  pub fn pile(&self, index: usize) -> &Pile {
+    &self.piles[index]
+  }
+
+  pub fn foundations(&self) -> &[Foundation] {
+    &self.foundations
+  }
+
+  pub fn foundation(&self, suit: Suit) -> &Foundation {
+    self.foundations.iter().find(|f| f.suit() == suit).unwrap()
+  }
+
+  pub fn stock_len(&self) -> usize {
+    self.stock.len()
+  }
+
+  pub fn founded(&self) -> usize {
+    self.foundations.iter().map(|f| f.len()).sum()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.founded() == NUM_CARDS
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Number of hidden pile cards turned face up since the deal.
+  pub fn hidden_flips(&self) -> usize {
+    self.hidden_flips
+  }
+
+  /// Cards on the foundations.
+  pub fn score(&self) -> i32 {
+    self.founded() as i32
+  }
+
+  pub fn can_apply(&self, m: EasthavenMove) -> KlondikeResult<()> {
+    match m {
+      EasthavenMove::Deal if self.stock.is_empty() => Err(KlondikeErr::InvalidMove),
+      EasthavenMove::Deal => Ok(()),
+      EasthavenMove::Pile { source, index, target } => {
+        let (source, index, target) = (source as usize, index as usize, target as usize);
+        if source >= NUM_PILES || target >= NUM_PILES || source == target {
+          return Err(KlondikeErr::InvalidMove);
+        }
+
+        let pile = &self.piles[source];
+        if index >= pile.visible_cards().len() || index < pile.visible_cards().len() - run_len(pile) {
+          return Err(KlondikeErr::InvalidMove);
+        }
+
+        self.piles[target].can_push(pile.visible_cards()[index])
+      },
+      EasthavenMove::Found(source) => match self.piles.get(source as usize).and_then(|p| p.top()) {
+        Some(card) if self.foundation(card.suit()).can_push(card) => Ok(()),
+        Some(card) => Err(KlondikeErr::InvalidCard(card)),
+        None => Err(KlondikeErr::InvalidMove),
+      },
+    }
+  }
+
+  pub fn apply(&mut self, m: EasthavenMove) -> KlondikeResult<()> {
+    self.can_apply(m)?;
+
+    let hidden = self.hidden_count();
+
+    match m {
+      EasthavenMove::Deal => {
+        for (i, card) in self.stock.deal_row(NUM_PILES).into_iter().enumerate() {
+          self.piles[i].deal(card);
+        }
+      },
+      EasthavenMove::Pile { source, index, target } => {
+        let (source, target) = (source as usize, target as usize);
+        let (low, high) = self.piles.split_at_mut(cmp::max(source, target));
+        let (source_pile, target_pile) = if source < target {
+          (&mut low[source], &mut high[0])
+        } else {
+          (&mut high[0], &mut low[target])
+        };
+        source_pile.move_from(index as usize, target_pile)?;
+      },
+      EasthavenMove::Found(source) => {
+        let card = self.piles[source as usize].pop().unwrap();
+        self.foundations.iter_mut().find(|f| f.suit() == card.suit()).unwrap().push();
+      },
+    }
+
+    self.hidden_flips += hidden - self.hidden_count();
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  fn hidden_count(&self) -> usize {
+    self.piles.iter().map(|p| p.hidden_cards().len()).sum()
+  }
+
+  /// Every move that would currently succeed, foundation moves first and the
+  /// deal last.
+  pub fn legal_moves(&self) -> Vec<EasthavenMove> {
+    let mut moves: Vec<EasthavenMove> = (0..NUM_PILES).map(|i| EasthavenMove::Found(i as u8)).collect();
+
+    for source in 0..NUM_PILES {
+      let visible = self.piles[source].visible_cards().len();
+      for index in visible - run_len(&self.piles[source])..visible {
+        for target in 0..NUM_PILES {
+          moves.push(EasthavenMove::Pile { source: source as u8, index: index as u8, target: target as u8 });
+        }
+      }
+    }
+
+    moves.push(EasthavenMove::Deal);
+    moves.retain(|m| self.can_apply(*m).is_ok());
+    moves
+  }
+
+  /// Check that the game holds every card of the deck once.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    let cards = self.piles.iter()
+      .flat_map(|p| p.hidden_cards().iter().chain(p.visible_cards().iter()).cloned().collect::<Vec<Card>>())
+      .chain(self.stock.cards().iter().cloned())
+      .chain(self.foundations.iter().flat_map(|f| f.cards()));
+
+    for card in cards {
+      if ! seen.insert(card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    match french::new_standard_deck().into_iter().find(|c| ! seen.contains(c)) {
+      Some(card) => Err(format!("missing card {}", card)),
+      None => Ok(()),
+    }
+  }
+
+  /// The piles from bottom to top with hidden cards in lower case, the top
+  /// card of each foundation and the stock size, e.g.
+  /// `5h KS QD|...|- / AD - - 3S / 24`.
+  pub fn notation(&self) -> String {
+    let piles: Vec<String> = self.piles.iter().map(|p| {
+      let cards: Vec<String> = p.hidden_cards().iter().map(|c| c.to_string().to_lowercase())
+        .chain(p.visible_cards().iter().map(|c| c.to_string()))
+        .collect();
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
+    }).collect();
+
+    let foundations: Vec<String> = self.foundations.iter().map(|f| f.top().map_or(String::from("-"), |c| c.to_string())).collect();
+
+    format!("{} / {} / {}", piles.join("|"), foundations.join(" "), self.stock.len())
+  }
+}
+
+impl SolitaireGame for EasthavenGame {
+  fn is_clear(&self) -> bool {
+    EasthavenGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    EasthavenGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    EasthavenGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = EasthavenGame::new(|c| seeded_shuffle(1, c));
+
+    assert!(game.piles().iter().all(|p| p.hidden_cards().len() == 2 && p.visible_cards().len() == 1));
+    assert_eq!(game.stock_len(), 31);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn deal_rows() {
+    let mut game = EasthavenGame::new(|c| seeded_shuffle(2, c));
+    let next = game.stock.cards().iter().rev().take(NUM_PILES).cloned().collect::<Vec<Card>>();
+
+    game.apply(EasthavenMove::Deal).unwrap();
+    let tops: Vec<Card> = game.piles().iter().map(|p| p.top().unwrap()).collect();
+    assert_eq!(tops, next);
+
+    for _ in 0..4 {
+      game.apply(EasthavenMove::Deal).unwrap();
+    }
+    assert_eq!(game.stock_len(), 0);
+    let lens: Vec<usize> = game.piles().iter().map(|p| p.visible_cards().len()).collect();
+    assert_eq!(lens, vec![6, 6, 6, 5, 5, 5, 5]);
+    assert_eq!(game.apply(EasthavenMove::Deal), Err(KlondikeErr::InvalidMove));
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn moves_sequences_only() {
+    let mut game = EasthavenGame::new(|_| ());
+    game.piles = vec![
+      Pile::with_cards(cards(&["2C"]), cards(&["9S", "5H", "4S"])),
+      Pile::with_cards(Vec::new(), cards(&["6C"])),
+      Pile::with_cards(Vec::new(), cards(&["10D"])),
+    ];
+    game.piles.extend((3..NUM_PILES).map(|_| Pile::new()));
+
+    assert_eq!(run_len(game.pile(0)), 2);
+    assert_eq!(game.can_apply(EasthavenMove::Pile { source: 0, index: 0, target: 2 }), Err(KlondikeErr::InvalidMove), "9S is not in the sequence");
+
+    game.apply(EasthavenMove::Pile { source: 0, index: 1, target: 1 }).unwrap();
+    assert_eq!(game.pile(1).visible_cards(), &cards(&["6C", "5H", "4S"])[..]);
+    game.apply(EasthavenMove::Pile { source: 0, index: 0, target: 2 }).unwrap();
+    assert_eq!(game.pile(0).visible_cards(), &cards(&["2C"])[..]);
+    assert_eq!(game.hidden_flips(), 1);
+
+    let moves = game.legal_moves();
+    assert!(! moves.contains(&EasthavenMove::Pile { source: 1, index: 0, target: 3 }), "only kings to empty piles");
+    assert!(moves.contains(&EasthavenMove::Deal));
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[8]);
+    let mut game = EasthavenGame::new(|c| seeded_shuffle(8, c));
+
+    for _ in 0..300 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+    }
+  }
+}
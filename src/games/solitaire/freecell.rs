@@ -0,0 +1,468 @@
+//! FreeCell with four free cells, four foundations and eight cascades.
+//!
+//! The whole deck is dealt face up to the cascades, seven cards to the first
+//! four and six to the rest. Cascades are built down in alternating colors,
+//! a free cell holds any one card and the foundations are built up by suit
+//! from the ace. Only one card moves at a time, but a run of a cascade may be
+//! moved as a whole when the free cells and empty cascades could carry it one
+//! card at a time: `(free cells + 1) * 2 ^ empty cascades` cards, not
+//! counting the target if it is an empty cascade.
+
+use cards::french::{self, FrenchPlayingCard, Suit};
+use games::solitaire::SolitaireGame;
+use std::collections::HashSet;
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_CELLS: usize = 4;
+pub const NUM_FOUNDATIONS: usize = 4;
+pub const NUM_CASCADES: usize = 8;
+const NUM_CARDS: usize = 52;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FreeCellErr {
+  InvalidMove,
+  /// More cards than the free cells and empty cascades can carry.
+  TooManyCards { max: usize },
+}
+
+pub type FreeCellResult<T> = Result<T, FreeCellErr>;
+
+/// Where cards are moved from or to. The foundation of a card is the one of
+/// its suit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Place {
+  Cascade(u8),
+  Cell(u8),
+  Foundation,
+}
+
+impl fmt::Display for Place {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Place::Cascade(i) => write!(f, "{}", i),
+      Place::Cell(i) => write!(f, "c{}", i),
+      Place::Foundation => write!(f, "f"),
+    }
+  }
+}
+
+/// Move the top `count` cards of `source` onto `target`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FreeCellMove {
+  pub source: Place,
+  pub count: u8,
+  pub target: Place,
+}
+
+impl FreeCellMove {
+  pub fn new(source: Place, count: u8, target: Place) -> FreeCellMove {
+    FreeCellMove {
+      source: source,
+      count: count,
+      target: target,
+    }
+  }
+}
+
+impl fmt::Display for FreeCellMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}x{}->{}", self.source, self.count, self.target)
+  }
+}
+
+/// Whether `card` can be built on `below` in a cascade: one rank lower and
+/// of the other color.
+pub fn builds_on(card: Card, below: Card) -> bool {
+  card.color() != below.color() && card.rank().value() + 1 == below.rank().value()
+}
+
+fn foundation_index(suit: Suit) -> usize {
+  Suit::standard_iter().position(|s| *s == suit).unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreeCellGame {
+  cascades: Vec<Vec<Card>>,
+  cells: [Option<Card>; NUM_CELLS],
+  /// Indexed by the position of the suit in `Suit::standard_iter`.
+  foundations: Vec<Vec<Card>>,
+  moves_made: usize,
+}
+
+impl FreeCellGame {
+  /// New game with a standard deck arranged by `shuffle`, dealt one card to
+  /// each cascade in turn.
+  pub fn new<F>(mut shuffle: F) -> FreeCellGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut cascades = vec![Vec::new(); NUM_CASCADES];
+    for (i, card) in cards.into_iter().enumerate() {
+      cascades[i % NUM_CASCADES].push(card);
+    }
+
+    FreeCellGame {
+      cascades: cascades,
+      cells: [None; NUM_CELLS],
+      foundations: vec![Vec::new(); NUM_FOUNDATIONS],
+      moves_made: 0,
+    }
+  }
+
+  pub fn cascades(&self) -> &[Vec<Card>] {
+    &self.cascades
+  }
+
+  pub fn cascade(&self, index: usize) -> &[Card] {
+    &self.cascades[index]
+  }
+
+  pub fn cells(&self) -> &[Option<Card>] {
+    &self.cells
+  }
+
+  /// Cards on the foundation of `suit`, the ace first.
+  pub fn foundation(&self, suit: Suit) -> &[Card] {
+    &self.foundations[foundation_index(suit)]
+  }
+
+  pub fn founded(&self) -> usize {
+    self.foundations.iter().map(|f| f.len()).sum()
+  }
+
+  pub fn free_cells(&self) -> usize {
+    self.cells.iter().filter(|c| c.is_none()).count()
+  }
+
+  pub fn empty_cascades(&self) -> usize {
+    self.cascades.iter().filter(|c| c.is_empty()).count()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.founded() == NUM_CARDS
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Cards on the foundations, the usual measure of a lost game.
+  pub fn score(&self) -> i32 {
+    self.founded() as i32
+  }
+
+  /// Number of cards at the top of cascade `index` that are built down in
+  /// alternating colors.
+  pub fn run_len(&self, index: usize) -> usize {
+    let cascade = &self.cascades[index];
+    if cascade.is_empty() {
+      return 0;
+    }
+
+    let mut len = 1;
+    while len < cascade.len() && builds_on(cascade[cascade.len() - len], cascade[cascade.len() - len - 1]) {
+      len += 1;
+    }
+
+    len
+  }
+
+  /// Most cards that can be moved together onto a cascade, empty or not.
+  pub fn max_supermove(&self, to_empty_cascade: bool) -> usize {
+    let empty = self.empty_cascades() - if to_empty_cascade { 1 } else { 0 };
+    (self.free_cells() + 1) << empty
+  }
+
+  /// Card that a move from `place` takes first, the lowest of a run.
+  fn top(&self, place: Place) -> Option<Card> {
+    match place {
+      Place::Cascade(i) => self.cascades.get(i as usize).and_then(|c| c.last().cloned()),
+      Place::Cell(i) => self.cells.get(i as usize).and_then(|c| *c),
+      Place::Foundation => None,
+    }
+  }
+
+  pub fn can_move(&self, m: FreeCellMove) -> FreeCellResult<()> {
+    let count = m.count as usize;
+    if count == 0 || m.source == m.target {
+      return Err(FreeCellErr::InvalidMove);
+    }
+
+    let moved = match m.source {
+      Place::Cascade(i) if (i as usize) < NUM_CASCADES && count <= self.run_len(i as usize) => {
+        let cascade = &self.cascades[i as usize];
+        cascade[cascade.len() - count]
+      },
+      Place::Cell(_) if count == 1 => match self.top(m.source) {
+        Some(card) => card,
+        None => return Err(FreeCellErr::InvalidMove),
+      },
+      _ => return Err(FreeCellErr::InvalidMove),
+    };
+
+    match m.target {
+      Place::Cell(i) => match (m.source, self.cells.get(i as usize)) {
+        (Place::Cascade(_), Some(&None)) if count == 1 => Ok(()),
+        _ => Err(FreeCellErr::InvalidMove),
+      },
+      Place::Foundation => {
+        if count == 1 && self.foundations[foundation_index(moved.suit())].len() + 1 == moved.rank().value() as usize {
+          Ok(())
+        } else {
+          Err(FreeCellErr::InvalidMove)
+        }
+      },
+      Place::Cascade(i) => {
+        let target = match self.cascades.get(i as usize) {
+          Some(target) => target,
+          None => return Err(FreeCellErr::InvalidMove),
+        };
+
+        if let Some(top) = target.last() {
+          if ! builds_on(moved, *top) {
+            return Err(FreeCellErr::InvalidMove);
+          }
+        }
+
+        let max = self.max_supermove(target.is_empty());
+        if count > max {
+          return Err(FreeCellErr::TooManyCards { max: max });
+        }
+
+        Ok(())
+      },
+    }
+  }
+
+  pub fn apply(&mut self, m: FreeCellMove) -> FreeCellResult<()> {
+    self.can_move(m)?;
+
+    let cards = match m.source {
+      Place::Cascade(i) => {
+        let cascade = &mut self.cascades[i as usize];
+        let at = cascade.len() - m.count as usize;
+        cascade.split_off(at)
+      },
+      Place::Cell(i) => vec![self.cells[i as usize].take().unwrap()],
+      Place::Foundation => unreachable!(),
+    };
+
+    match m.target {
+      Place::Cascade(i) => self.cascades[i as usize].extend(cards),
+      Place::Cell(i) => self.cells[i as usize] = Some(cards[0]),
+      Place::Foundation => self.foundations[foundation_index(cards[0].suit())].push(cards[0]),
+    }
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  /// All moves that would currently succeed. Of the free cells only the
+  /// first empty one is a target, since they are all alike.
+  pub fn legal_moves(&self) -> Vec<FreeCellMove> {
+    let mut sources: Vec<(Place, usize)> = (0..NUM_CASCADES).map(|i| (Place::Cascade(i as u8), self.run_len(i))).collect();
+    sources.extend((0..NUM_CELLS).filter(|&i| self.cells[i].is_some()).map(|i| (Place::Cell(i as u8), 1)));
+
+    let mut targets: Vec<Place> = vec![Place::Foundation];
+    targets.extend(self.cells.iter().position(|c| c.is_none()).map(|i| Place::Cell(i as u8)));
+    targets.extend((0..NUM_CASCADES).map(|i| Place::Cascade(i as u8)));
+
+    let mut moves = Vec::new();
+
+    for &(source, len) in sources.iter() {
+      for count in 1..len + 1 {
+        for target in targets.iter() {
+          let m = FreeCellMove::new(source, count as u8, *target);
+          if self.can_move(m).is_ok() {
+            moves.push(m);
+          }
+        }
+      }
+    }
+
+    moves
+  }
+
+  /// Check that the game holds every card of the deck once and that the
+  /// foundations are built up by suit.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    let cards = self.cascades.iter().flat_map(|c| c.iter())
+      .chain(self.cells.iter().filter_map(|c| c.as_ref()))
+      .chain(self.foundations.iter().flat_map(|f| f.iter()));
+
+    for card in cards {
+      if ! seen.insert(*card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    for (suit, foundation) in Suit::standard_iter().zip(self.foundations.iter()) {
+      for (i, card) in foundation.iter().enumerate() {
+        if card.suit() != *suit || card.rank().value() as usize != i + 1 {
+          return Err(format!("{} out of place on the {} foundation", card, suit));
+        }
+      }
+    }
+
+    match french::new_standard_deck().into_iter().find(|c| ! seen.contains(c)) {
+      Some(card) => Err(format!("missing card {}", card)),
+      None => Ok(()),
+    }
+  }
+
+  /// The cascades from bottom to top, the free cells and the top card of
+  /// each foundation, e.g. `KS QH|...|4C / 5D - - - / AD - 3C -`.
+  pub fn notation(&self) -> String {
+    let cascades: Vec<String> = self.cascades.iter().map(|c| {
+      let cards: Vec<String> = c.iter().map(|c| c.to_string()).collect();
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
+    }).collect();
+
+    let cells: Vec<String> = self.cells.iter().map(|c| c.map_or(String::from("-"), |c| c.to_string())).collect();
+    let foundations: Vec<String> = self.foundations.iter().map(|f| f.last().map_or(String::from("-"), |c| c.to_string())).collect();
+
+    format!("{} / {} / {}", cascades.join("|"), cells.join(" "), foundations.join(" "))
+  }
+
+  /// Whether `card` can go to its foundation without being needed later:
+  /// every card that could be built on it is already founded.
+  pub fn is_safe_to_found(&self, card: Card) -> bool {
+    let rank = card.rank().value() as usize;
+
+    rank <= 2 || Suit::standard_iter()
+      .filter(|s| s.color() != card.color())
+      .all(|s| self.foundations[foundation_index(*s)].len() + 1 >= rank)
+  }
+}
+
+impl SolitaireGame for FreeCellGame {
+  fn is_clear(&self) -> bool {
+    FreeCellGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    FreeCellGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    FreeCellGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  /// Game with the given cascades and cells and empty foundations, which
+  /// need not hold the whole deck.
+  fn game(cascades: &[&[&str]], cells: &[&str]) -> FreeCellGame {
+    let mut game = FreeCellGame::new(|_| ());
+
+    for (i, cascade) in game.cascades.iter_mut().enumerate() {
+      *cascade = cascades.get(i).map_or(Vec::new(), |c| c.iter().map(|s| card(s)).collect());
+    }
+
+    for (i, cell) in game.cells.iter_mut().enumerate() {
+      *cell = cells.get(i).map(|s| card(s));
+    }
+
+    game
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = FreeCellGame::new(|c| seeded_shuffle(1, c));
+
+    let lens: Vec<usize> = game.cascades().iter().map(|c| c.len()).collect();
+    assert_eq!(lens, vec![7, 7, 7, 7, 6, 6, 6, 6]);
+    assert_eq!(game.free_cells(), 4);
+    assert_eq!(game.founded(), 0);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn single_moves() {
+    let mut game = game(&[&["KS", "AH"], &["3C", "2D"], &["AD"]], &["4H"]);
+
+    assert_eq!(game.can_move(FreeCellMove::new(Place::Cascade(1), 1, Place::Foundation)), Err(FreeCellErr::InvalidMove));
+    game.apply(FreeCellMove::new(Place::Cascade(2), 1, Place::Foundation)).unwrap();
+    game.apply(FreeCellMove::new(Place::Cascade(1), 1, Place::Foundation)).unwrap();
+    assert_eq!(game.foundation(Suit::Diamonds), &[card("AD"), card("2D")]);
+
+    assert_eq!(game.can_move(FreeCellMove::new(Place::Cell(0), 1, Place::Cascade(1))), Err(FreeCellErr::InvalidMove), "4H on 3C");
+    assert_eq!(game.can_move(FreeCellMove::new(Place::Cascade(0), 1, Place::Cell(0))), Err(FreeCellErr::InvalidMove), "cell taken");
+    assert_eq!(game.can_move(FreeCellMove::new(Place::Cell(0), 1, Place::Cell(1))), Err(FreeCellErr::InvalidMove));
+    game.apply(FreeCellMove::new(Place::Cascade(0), 1, Place::Cell(1))).unwrap();
+    game.apply(FreeCellMove::new(Place::Cell(0), 1, Place::Cascade(2))).unwrap();
+    assert_eq!(game.cascade(2), &[card("4H")]);
+    assert_eq!(game.cells(), &[None, Some(card("AH")), None, None]);
+    assert_eq!(game.moves_made(), 4);
+    assert_eq!(game.score(), 2);
+  }
+
+  #[test]
+  fn supermoves() {
+    let run = ["9S", "8H", "7C", "6D", "5S"];
+    let mut game = game(&[&run, &["10D"], &["2C"], &["3C"], &["4C"], &["5C"], &["6C"], &["7S"]], &["AS", "2S", "3S"]);
+
+    assert_eq!(game.run_len(0), 5);
+    assert_eq!(game.max_supermove(false), 2);
+    assert_eq!(game.can_move(FreeCellMove::new(Place::Cascade(0), 5, Place::Cascade(1))), Err(FreeCellErr::TooManyCards { max: 2 }));
+
+    game.cells[0] = None;
+    game.cascades[7].clear();
+    assert_eq!(game.max_supermove(false), 6);
+    assert_eq!(game.max_supermove(true), 3);
+    assert_eq!(game.can_move(FreeCellMove::new(Place::Cascade(0), 4, Place::Cascade(7))), Err(FreeCellErr::TooManyCards { max: 3 }));
+    assert_eq!(game.can_move(FreeCellMove::new(Place::Cascade(0), 3, Place::Cascade(7))), Ok(()));
+    assert_eq!(game.can_move(FreeCellMove::new(Place::Cascade(0), 4, Place::Cascade(1))), Err(FreeCellErr::InvalidMove), "8H on 10D");
+
+    game.apply(FreeCellMove::new(Place::Cascade(0), 5, Place::Cascade(1))).unwrap();
+    assert_eq!(game.cascade(1).len(), 6);
+    assert!(game.cascade(0).is_empty());
+
+    let moves = game.legal_moves();
+    assert!(moves.contains(&FreeCellMove::new(Place::Cascade(1), 6, Place::Cascade(0))));
+    assert!(! moves.iter().any(|m| m.source == Place::Cascade(1) && m.target == Place::Cascade(2)));
+    assert!(! moves.iter().any(|m| m.target == Place::Cell(2)), "only the first free cell is a target");
+  }
+
+  #[test]
+  fn safe_to_found() {
+    let mut game = game(&[], &[]);
+    assert!(game.is_safe_to_found(card("2H")));
+    assert!(! game.is_safe_to_found(card("3H")));
+
+    game.foundations[foundation_index(Suit::Clubs)] = vec![card("AC"), card("2C")];
+    game.foundations[foundation_index(Suit::Spades)] = vec![card("AS")];
+    assert!(! game.is_safe_to_found(card("3H")));
+    game.foundations[foundation_index(Suit::Spades)].push(card("2S"));
+    assert!(game.is_safe_to_found(card("3H")));
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[7]);
+    let mut game = FreeCellGame::new(|c| seeded_shuffle(7, c));
+
+    for _ in 0..300 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+    }
+  }
+}
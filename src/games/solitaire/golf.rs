@@ -0,0 +1,276 @@
+//! Golf solitaire.
+//!
+//! Thirty-five cards are dealt face up to seven columns of five and one card
+//! starts the waste; the rest form the stock. The top card of a column can be
+//! played onto the waste when its rank is one above or below the top of the
+//! waste, regardless of suit, and a card is drawn from the stock to the waste
+//! when nothing else is wanted. Playing a king onto an ace, or an ace onto a
+//! king, is only allowed "around the corner". The game is won when every
+//! column is cleared.
+
+use cards::french::{self, FrenchPlayingCard};
+use games::solitaire::SolitaireGame;
+use std::collections::HashSet;
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_COLUMNS: usize = 7;
+pub const COLUMN_LEN: usize = 5;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GolfRules {
+  /// Whether kings and aces follow each other.
+  pub around_the_corner: bool,
+}
+
+impl GolfRules {
+  /// Kings and aces do not follow each other.
+  pub fn new() -> GolfRules {
+    GolfRules {
+      around_the_corner: false,
+    }
+  }
+
+  /// Whether `card` can be played onto `waste`.
+  pub fn follows(&self, card: Card, waste: Card) -> bool {
+    let (a, b) = (card.rank().value(), waste.rank().value());
+
+    a + 1 == b || b + 1 == a || (self.around_the_corner && (a == 1 && b == 13 || a == 13 && b == 1))
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GolfErr {
+  /// The stock is empty.
+  EmptyStock,
+  /// The column is empty or does not exist.
+  EmptyColumn,
+  /// The top card of the column does not follow the waste.
+  InvalidMove,
+}
+
+pub type GolfResult<T> = Result<T, GolfErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GolfMove {
+  /// Draw a card from the stock to the waste.
+  Draw,
+  /// Play the top card of a column onto the waste.
+  Play(u8),
+}
+
+impl fmt::Display for GolfMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      GolfMove::Draw => write!(f, "draw"),
+      GolfMove::Play(i) => write!(f, "{}", i),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GolfGame {
+  rules: GolfRules,
+  columns: Vec<Vec<Card>>,
+  /// Cards drawn from the end.
+  stock: Vec<Card>,
+  waste: Vec<Card>,
+  moves_made: usize,
+}
+
+impl GolfGame {
+  /// New game with a standard deck arranged by `shuffle`. The first cards go
+  /// to the columns, one column after the other from the bottom card up, the
+  /// next one to the waste and the rest to the stock, the first of them
+  /// drawn first.
+  pub fn new<F>(rules: GolfRules, mut shuffle: F) -> GolfGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut stock = cards.split_off(NUM_COLUMNS * COLUMN_LEN);
+    let columns = cards.chunks(COLUMN_LEN).map(|c| c.to_vec()).collect();
+    stock.reverse();
+    let waste = vec![stock.pop().unwrap()];
+
+    GolfGame {
+      rules: rules,
+      columns: columns,
+      stock: stock,
+      waste: waste,
+      moves_made: 0,
+    }
+  }
+
+  pub fn rules(&self) -> GolfRules {
+    self.rules
+  }
+
+  pub fn columns(&self) -> &[Vec<Card>] {
+    &self.columns
+  }
+
+  pub fn column(&self, index: usize) -> &[Card] {
+    &self.columns[index]
+  }
+
+  pub fn stock_len(&self) -> usize {
+    self.stock.len()
+  }
+
+  pub fn waste(&self) -> &[Card] {
+    &self.waste
+  }
+
+  pub fn waste_top(&self) -> Card {
+    *self.waste.last().unwrap()
+  }
+
+  /// Cards left in the columns.
+  pub fn tableau_len(&self) -> usize {
+    self.columns.iter().map(|c| c.len()).sum()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.tableau_len() == 0
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Cards played from the columns.
+  pub fn score(&self) -> i32 {
+    (NUM_COLUMNS * COLUMN_LEN - self.tableau_len()) as i32
+  }
+
+  pub fn can_apply(&self, m: GolfMove) -> GolfResult<()> {
+    match m {
+      GolfMove::Draw if self.stock.is_empty() => Err(GolfErr::EmptyStock),
+      GolfMove::Draw => Ok(()),
+      GolfMove::Play(i) => match self.columns.get(i as usize).and_then(|c| c.last()) {
+        Some(card) if self.rules.follows(*card, self.waste_top()) => Ok(()),
+        Some(_) => Err(GolfErr::InvalidMove),
+        None => Err(GolfErr::EmptyColumn),
+      },
+    }
+  }
+
+  pub fn apply(&mut self, m: GolfMove) -> GolfResult<()> {
+    self.can_apply(m)?;
+
+    let card = match m {
+      GolfMove::Draw => self.stock.pop().unwrap(),
+      GolfMove::Play(i) => self.columns[i as usize].pop().unwrap(),
+    };
+    self.waste.push(card);
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  /// Every move that would currently succeed, plays first.
+  pub fn legal_moves(&self) -> Vec<GolfMove> {
+    let mut moves: Vec<GolfMove> = (0..NUM_COLUMNS).map(|i| GolfMove::Play(i as u8)).collect();
+    moves.push(GolfMove::Draw);
+    moves.retain(|m| self.can_apply(*m).is_ok());
+    moves
+  }
+
+  /// Check that no card is in the game twice and that the deck is complete.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    for card in self.columns.iter().flat_map(|c| c.iter()).chain(self.stock.iter()).chain(self.waste.iter()) {
+      if ! seen.insert(*card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    match french::new_standard_deck().into_iter().find(|c| ! seen.contains(c)) {
+      Some(card) => Err(format!("missing card {}", card)),
+      None => Ok(()),
+    }
+  }
+
+  /// The columns from bottom to top, the stock size and the top of the
+  /// waste, e.g. `KS 5H 2D|...|- / 16 / 9C`.
+  pub fn notation(&self) -> String {
+    let columns: Vec<String> = self.columns.iter().map(|c| {
+      let cards: Vec<String> = c.iter().map(|c| c.to_string()).collect();
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
+    }).collect();
+
+    format!("{} / {} / {}", columns.join("|"), self.stock.len(), self.waste_top())
+  }
+}
+
+impl SolitaireGame for GolfGame {
+  fn is_clear(&self) -> bool {
+    GolfGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    GolfGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    GolfGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = GolfGame::new(GolfRules::new(), |c| seeded_shuffle(1, c));
+
+    assert!(game.columns().iter().all(|c| c.len() == 5));
+    assert_eq!(game.stock_len(), 16);
+    assert_eq!(game.waste().len(), 1);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn follows() {
+    let rules = GolfRules::new();
+    assert!(rules.follows(card("5H"), card("6S")));
+    assert!(rules.follows(card("7H"), card("6S")));
+    assert!(! rules.follows(card("6H"), card("6S")));
+    assert!(! rules.follows(card("AH"), card("KS")));
+
+    let corner = GolfRules { around_the_corner: true };
+    assert!(corner.follows(card("AH"), card("KS")));
+    assert!(corner.follows(card("KH"), card("AS")));
+    assert!(! corner.follows(card("QH"), card("AS")));
+  }
+
+  #[test]
+  fn moves() {
+    let mut game = GolfGame::new(GolfRules::new(), |_| ());
+    game.columns = vec![vec![card("3C"), card("4D")], vec![card("5S")], vec![], vec![], vec![], vec![], vec![]];
+    game.waste = vec![card("6H")];
+
+    assert_eq!(game.legal_moves(), vec![GolfMove::Play(1), GolfMove::Draw]);
+    assert_eq!(game.apply(GolfMove::Play(0)), Err(GolfErr::InvalidMove));
+    assert_eq!(game.apply(GolfMove::Play(2)), Err(GolfErr::EmptyColumn));
+
+    game.apply(GolfMove::Play(1)).unwrap();
+    game.apply(GolfMove::Play(0)).unwrap();
+    game.apply(GolfMove::Play(0)).unwrap();
+    assert!(game.is_clear());
+    assert_eq!(game.waste_top(), card("3C"));
+    assert_eq!(game.moves_made(), 3);
+
+    game.stock.clear();
+    assert_eq!(game.apply(GolfMove::Draw), Err(GolfErr::EmptyStock));
+  }
+}
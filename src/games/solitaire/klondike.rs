@@ -1,7 +1,19 @@
-use cards::{french, default_shuffle};
+use cards::{french, ShuffleSource, RngSource};
 use cards::french::{Rank, Suit, Color};
+use games::solitaire::SolitaireGame;
+use json::Value;
 use std::cmp;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
+use std::error;
+use std::fmt;
+use std::io::Read;
+use std::mem;
+use std::ops;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use rand;
+use rand::Rng;
 
 pub type Card = french::FrenchPlayingCard;
 
@@ -9,17 +21,273 @@ const MAX_DECK_SIZE: usize = 24;
 const NUM_PILES: usize = 7;
 const NUM_FOUNDATIONS: usize = 4;
 
+/// Most positions `KlondikeSolitaireGame::is_stuck` searches.
+pub const STUCK_SEARCH_LIMIT: usize = 10000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum MoveSource {
   Deck,
   Foundation(Suit),
+  /// The cards of a pile from the deepest face up card that fits the target.
   Pile(u8),
+  /// Face up card `index` of `pile`, counted from the lowest face up card,
+  /// and the cards on top of it. The move fails unless that card fits the
+  /// target, rather than looking for another one.
+  PileRun { pile: u8, index: u8 },
+}
+
+impl MoveSource {
+  /// Pile the cards come from, if any.
+  pub fn pile(&self) -> Option<usize> {
+    match *self {
+      MoveSource::Pile(i) | MoveSource::PileRun { pile: i, .. } => Some(i as usize),
+      _ => None,
+    }
+  }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum MoveTarget {
   Foundation,
   Pile(u8),
 }
 
+/// A single action a player can take.
+///
+/// Moves are plain values that strategies can keep, compare and use as keys,
+/// and write with `Display` and read back with `FromStr`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Move {
+  Draw,
+  Cards(MoveSource, MoveTarget),
+}
+
+impl fmt::Display for Move {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Move::Draw => write!(f, "draw"),
+      Move::Cards(source, target) => {
+        match source {
+          MoveSource::Deck => write!(f, "deck")?,
+          MoveSource::Foundation(suit) => write!(f, "foundation{}", suit)?,
+          MoveSource::Pile(i) => write!(f, "pile{}", i)?,
+          MoveSource::PileRun { pile, index } => write!(f, "pile{}[{}]", pile, index)?,
+        }
+
+        match target {
+          MoveTarget::Foundation => write!(f, "->foundation"),
+          MoveTarget::Pile(i) => write!(f, "->pile{}", i),
+        }
+      },
+    }
+  }
+}
+
+/// A move as it was made, with what it did to the game.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MoveRecord {
+  pub m: Move,
+  /// Cards moved, from the bottom of the moved run up, or the cards a draw
+  /// turned face up.
+  pub cards: Vec<Card>,
+  /// The pile card turned face up by moving the cards above it away.
+  pub flipped: Option<Card>,
+  /// Whether a draw turned the waste back over into the deck.
+  pub recycled: bool,
+  /// Score after the move.
+  pub score: i32,
+}
+
+/// Hooks called as a `KlondikeSolitaireGame` is played, attached with
+/// `KlondikeSolitaireGame::add_observer`.
+///
+/// Each hook gets the game as it is after the action. A move that turns up a
+/// pile card calls `on_move` and then `on_flip`, and a move that founds the
+/// last card calls `on_win` after them. Every hook does nothing by default.
+pub trait Observer {
+  /// Cards were moved to a pile or a foundation.
+  fn on_move(&mut self, _game: &KlondikeSolitaireGame, _record: &MoveRecord) {}
+  /// `card` was turned face up on `pile`.
+  fn on_flip(&mut self, _game: &KlondikeSolitaireGame, _pile: usize, _card: Card) {}
+  /// A draw turned `cards` face up.
+  fn on_draw(&mut self, _game: &KlondikeSolitaireGame, _cards: &[Card]) {}
+  /// A draw turned the waste back over into the deck.
+  fn on_recycle(&mut self, _game: &KlondikeSolitaireGame) {}
+  fn on_win(&mut self, _game: &KlondikeSolitaireGame) {}
+}
+
+/// The observers of a game. Copies of a game start with none, so the moves
+/// strategies try on copies are not reported.
+#[derive(Default)]
+struct Observers(Vec<Arc<Mutex<dyn Observer + Send>>>);
+
+impl Clone for Observers {
+  fn clone(&self) -> Observers {
+    Observers::default()
+  }
+}
+
+impl fmt::Display for MoveRecord {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.m)?;
+
+    if self.recycled {
+      write!(f, " recycle")?;
+    }
+
+    for card in &self.cards {
+      write!(f, " {}", card)?;
+    }
+
+    if let Some(card) = self.flipped {
+      write!(f, ", flipped {}", card)?;
+    }
+
+    write!(f, ", score {}", self.score)
+  }
+}
+
+/// What was done in a game since the deal, see `KlondikeSolitaireGame::stats`.
+/// Stats add up, so the stats of many games can be summed with `+=`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct GameStats {
+  /// Draws and successful card moves.
+  pub moves: usize,
+  /// Draws from the deck, including the draws that turned the waste over.
+  pub draws: usize,
+  /// Passes through the deck begun, the first included.
+  pub passes: usize,
+  /// Face down pile cards turned up.
+  pub cards_revealed: usize,
+  /// Cards put on a foundation. A card taken back off and founded again
+  /// counts twice.
+  pub cards_founded: usize,
+}
+
+impl ops::AddAssign for GameStats {
+  fn add_assign(&mut self, other: GameStats) {
+    self.moves += other.moves;
+    self.draws += other.draws;
+    self.passes += other.passes;
+    self.cards_revealed += other.cards_revealed;
+    self.cards_founded += other.cards_founded;
+  }
+}
+
+/// Category of a move, used to count what kind of play a game consisted of.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MoveKind {
+  /// Draw that turned cards face up.
+  Draw,
+  /// Draw that turned the waste back over into the deck.
+  Recycle,
+  DeckToPile,
+  DeckToFoundation,
+  PileToPile,
+  PileToFoundation,
+  FoundationToPile,
+}
+
+const NUM_MOVE_KINDS: usize = 7;
+
+static MOVE_KINDS: &'static [MoveKind; NUM_MOVE_KINDS] = &[
+  MoveKind::Draw,
+  MoveKind::Recycle,
+  MoveKind::DeckToPile,
+  MoveKind::DeckToFoundation,
+  MoveKind::PileToPile,
+  MoveKind::PileToFoundation,
+  MoveKind::FoundationToPile,
+];
+
+impl MoveKind {
+  pub fn all() -> &'static [MoveKind] {
+    &MOVE_KINDS[..]
+  }
+
+  /// Kind of a card move, or `None` for foundation to foundation, which
+  /// does nothing.
+  pub fn of(source: MoveSource, target: MoveTarget) -> Option<MoveKind> {
+    match (source, target) {
+      (MoveSource::Deck, MoveTarget::Pile(_)) => Some(MoveKind::DeckToPile),
+      (MoveSource::Deck, MoveTarget::Foundation) => Some(MoveKind::DeckToFoundation),
+      (MoveSource::Pile(_), MoveTarget::Pile(_)) => Some(MoveKind::PileToPile),
+      (MoveSource::PileRun { .. }, MoveTarget::Pile(_)) => Some(MoveKind::PileToPile),
+      (MoveSource::Pile(_), MoveTarget::Foundation) => Some(MoveKind::PileToFoundation),
+      (MoveSource::PileRun { .. }, MoveTarget::Foundation) => Some(MoveKind::PileToFoundation),
+      (MoveSource::Foundation(_), MoveTarget::Pile(_)) => Some(MoveKind::FoundationToPile),
+      (MoveSource::Foundation(_), MoveTarget::Foundation) => None,
+    }
+  }
+
+  fn index(self) -> usize {
+    MOVE_KINDS.iter().position(|k| *k == self).unwrap()
+  }
+}
+
+impl fmt::Display for MoveKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let s = match *self {
+      MoveKind::Draw => "draw",
+      MoveKind::Recycle => "recycle",
+      MoveKind::DeckToPile => "deck->pile",
+      MoveKind::DeckToFoundation => "deck->foundation",
+      MoveKind::PileToPile => "pile->pile",
+      MoveKind::PileToFoundation => "pile->foundation",
+      MoveKind::FoundationToPile => "foundation->pile",
+    };
+
+    write!(f, "{}", s)
+  }
+}
+
+impl FromStr for Move {
+  type Err = String;
+
+  /// Parse a move written by `Display`.
+  fn from_str(s: &str) -> Result<Move, String> {
+    fn pile(s: &str) -> Option<u8> {
+      if ! s.starts_with("pile") {
+        return None;
+      }
+
+      s[4..].parse::<u8>().ok().and_then(|i| if (i as usize) < NUM_PILES { Some(i) } else { None })
+    }
+
+    if s == "draw" {
+      return Ok(Move::Draw);
+    }
+
+    let mut parts = s.splitn(2, "->");
+    let (source, target) = match (parts.next(), parts.next()) {
+      (Some(source), Some(target)) => (source, target),
+      _ => { return Err(format!("Invalid move: {}", s)); },
+    };
+
+    let source = if source == "deck" {
+      MoveSource::Deck
+    } else if source.starts_with("foundation") {
+      MoveSource::Foundation(source[10..].parse::<Suit>().map_err(|_| format!("Invalid move: {}", s))?)
+    } else if source.ends_with(']') {
+      let open = source.find('[').ok_or_else(|| format!("Invalid move: {}", s))?;
+      MoveSource::PileRun {
+        pile: pile(&source[..open]).ok_or_else(|| format!("Invalid move: {}", s))?,
+        index: source[open + 1..source.len() - 1].parse::<u8>().map_err(|_| format!("Invalid move: {}", s))?,
+      }
+    } else {
+      MoveSource::Pile(pile(source).ok_or_else(|| format!("Invalid move: {}", s))?)
+    };
+
+    let target = if target == "foundation" {
+      MoveTarget::Foundation
+    } else {
+      MoveTarget::Pile(pile(target).ok_or_else(|| format!("Invalid move: {}", s))?)
+    };
+
+    Ok(Move::Cards(source, target))
+  }
+}
+
 static RANKS: &'static [Rank; 13] = &[
     Rank::Ace,
     Rank::Number(2),
@@ -46,35 +314,360 @@ fn rank_index(rank: Rank) -> Result<usize, String> {
   return Err(format!("Unsupported rank: {:?}", rank));
 }
 
+/// Bytes of `KlondikeSolitaireGame::encode`, 6 bits for each of the 52
+/// cards after the 15 bytes of counts.
+pub const ENCODED_LEN: usize = ENCODED_CARDS + 39;
+
+/// Offset of the cards in `KlondikeSolitaireGame::encode`.
+const ENCODED_CARDS: usize = 15;
+
+/// Parts of a position told apart by `zobrist_key`. Piles are zones 0 to 6.
+const ZONE_FOUNDATION: u64 = 8;
+const ZONE_DECK: u64 = 9;
+const ZONE_DECK_STATE: u64 = 10;
+
+/// Random looking key of feature `id` of a position, the splitmix64 mix of
+/// the id, so there is no table of keys to generate and share.
+fn zobrist_key(id: u64) -> u64 {
+  let mut z = id.wrapping_add(0x9e3779b97f4a7c15);
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+  z ^ (z >> 31)
+}
+
+/// Number of `card` from 0 to 51, by foundation and then rank.
+fn card_id(card: Card) -> usize {
+  KlondikeSolitaireGame::foundation_index(card.suit()) * RANKS.len() + rank_index(card.rank()).unwrap()
+}
+
+/// Card numbered `id` by `card_id`.
+fn card_from_id(id: usize) -> Option<Card> {
+  let suits = [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs];
+  suits.get(id / RANKS.len()).map(|suit| Card::new(*suit, RANKS[id % RANKS.len()]))
+}
+
+/// Key of `card` at `position` of `zone`.
+fn card_key(card: Card, zone: u64, position: usize) -> u64 {
+  zobrist_key(zone << 40 | (position as u64) << 8 | card_id(card) as u64)
+}
+
+/// Hash of pile `index`, face down cards with even and face up cards with
+/// odd positions.
+fn pile_hash(index: usize, pile: &Pile) -> u64 {
+  let hidden = pile.hidden_cards.iter().enumerate().map(|(i, c)| card_key(*c, index as u64, 2 * i));
+  let visible = pile.visible_cards.iter().enumerate().map(|(i, c)| card_key(*c, index as u64, 2 * (pile.hidden_cards.len() + i) + 1));
+  hidden.chain(visible).fold(0, |h, k| h ^ k)
+}
+
+/// Hash of the foundations, by their top cards.
+fn foundations_hash(foundations: &[Foundation]) -> u64 {
+  foundations.iter().filter_map(|f| f.top()).fold(0, |h, c| h ^ card_key(c, ZONE_FOUNDATION, 0))
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum KlondikeErr {
+  /// Nothing can be put on the card, an ace on a pile.
   Capacity,
-  InvalidCard,
+  /// The card does not fit where it was put.
+  InvalidCard(Card),
   InvalidRank,
   InvalidSuit,
   InvalidColor,
+  /// A move of cards inside a pile, or of another game built on the klondike
+  /// piles, that can not be made.
   InvalidMove,
+  /// A klondike move that can not be made, with the card it would have
+  /// moved, `None` when there is none.
+  IllegalMove { m: Move, card: Option<Card> },
+  /// Draw count of a deck that is 0 or larger than the deck can be.
+  DrawCount(usize),
+  /// Number of cards of a deck larger than the 24 left after the deal.
+  DeckSize(usize),
+  /// More face up deck cards than the draw count.
+  TooManyVisible(usize),
+  DuplicateCard(Card),
+  /// A pile has face down cards but no face up card on top of them.
+  HiddenWithoutVisible,
+  /// More face down cards than the 6 of the largest pile.
+  TooManyHidden(usize),
+  /// Face up pile cards that do not alternate color with descending rank.
+  PileOrder { card: Card, next: Card },
+  /// A game whose parts are valid on their own but not together.
+  Invalid(ValidationError),
+}
+
+impl From<ValidationError> for KlondikeErr {
+  fn from(e: ValidationError) -> KlondikeErr {
+    KlondikeErr::Invalid(e)
+  }
+}
+
+impl fmt::Display for KlondikeErr {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      KlondikeErr::Capacity => write!(f, "no card can be put on an ace"),
+      KlondikeErr::InvalidCard(c) => write!(f, "{} does not fit there", c),
+      KlondikeErr::InvalidRank => write!(f, "invalid rank"),
+      KlondikeErr::InvalidSuit => write!(f, "invalid suit"),
+      KlondikeErr::InvalidColor => write!(f, "invalid color"),
+      KlondikeErr::InvalidMove => write!(f, "invalid move"),
+      KlondikeErr::IllegalMove { m, card: Some(c) } => write!(f, "move {} of {} is not legal", m, c),
+      KlondikeErr::IllegalMove { m, card: None } => write!(f, "move {} is not legal, there is no card to move", m),
+      KlondikeErr::DrawCount(n) => write!(f, "invalid draw count {}", n),
+      KlondikeErr::DeckSize(n) => write!(f, "deck has {} cards, max {}", n, MAX_DECK_SIZE),
+      KlondikeErr::TooManyVisible(n) => write!(f, "deck has {} visible cards, more than the draw count", n),
+      KlondikeErr::DuplicateCard(c) => write!(f, "duplicate card {}", c),
+      KlondikeErr::HiddenWithoutVisible => write!(f, "pile has hidden cards but no visible card"),
+      KlondikeErr::TooManyHidden(n) => write!(f, "pile has {} hidden cards, max 6", n),
+      KlondikeErr::PileOrder { card, next } => write!(f, "{} can not be on {}", next, card),
+      KlondikeErr::Invalid(e) => e.fmt(f),
+    }
+  }
+}
+
+impl error::Error for KlondikeErr {
+  fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+    match *self {
+      KlondikeErr::Invalid(ref e) => Some(e),
+      _ => None,
+    }
+  }
 }
 
 pub type KlondikeResult<T> = Result<T, KlondikeErr>;
 
+/// How a game is scored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scoring {
+  /// Windows style: 10 per card to a foundation, 5 for a deck card played to
+  /// a pile or a pile card turned face up, -15 for a card taken back from a
+  /// foundation, and a penalty for recycling the deck (-100 with draw one,
+  /// -20 after the third pass with draw three). Never below 0.
+  Standard,
+  /// Starts at -52 and scores 5 per card on the foundations.
+  Vegas,
+}
+
+/// Variant of the klondike rules a game is played with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KlondikeRules {
+  pub draw_count: u8,
+  /// Number of times the deck may be dealt through, `None` for no limit.
+  pub pass_limit: Option<usize>,
+  pub scoring: Scoring,
+  /// Simulated time each move takes, for the classic timed scoring of
+  /// `Scoring::Standard`. `None` for untimed games.
+  pub move_time: Option<Duration>,
+  /// Thoughtful solitaire: the hidden pile cards and the order of the whole
+  /// deck can be looked up with `Pile::peek_hidden` and `Deck::all_cards`,
+  /// for solvers and to measure how much the hidden information costs.
+  pub thoughtful: bool,
+}
+
+/// Names accepted by `KlondikeRules::preset`.
+pub static RULE_PRESETS: &'static [&'static str] = &["standard", "vegas", "one-pass-draw-1", "unlimited-draw-3"];
+
+impl KlondikeRules {
+  /// Unlimited passes with standard scoring.
+  pub fn new(draw_count: u8) -> KlondikeRules {
+    KlondikeRules {
+      draw_count: draw_count,
+      pass_limit: None,
+      scoring: Scoring::Standard,
+      move_time: None,
+      thoughtful: false,
+    }
+  }
+
+  /// Named rule set, one of `RULE_PRESETS`:
+  ///
+  /// * `standard`: draw one, unlimited passes, standard scoring
+  /// * `vegas`: draw three, three passes, vegas scoring
+  /// * `one-pass-draw-1`: draw one, a single pass, standard scoring
+  /// * `unlimited-draw-3`: draw three, unlimited passes, standard scoring
+  pub fn preset(name: &str) -> Option<KlondikeRules> {
+    match name {
+      "standard" => Some(KlondikeRules::new(1)),
+      "vegas" => Some(KlondikeRules { pass_limit: Some(3), scoring: Scoring::Vegas, .. KlondikeRules::new(3) }),
+      "one-pass-draw-1" => Some(KlondikeRules { pass_limit: Some(1), .. KlondikeRules::new(1) }),
+      "unlimited-draw-3" => Some(KlondikeRules::new(3)),
+      _ => None,
+    }
+  }
+
+  /// The rules as a JSON object, the scoring named `standard` or `vegas` and
+  /// the move time in seconds:
+  ///
+  /// ```text
+  /// {"draw_count":3,"pass_limit":3,"scoring":"vegas","move_time":null,"thoughtful":false}
+  /// ```
+  pub fn to_json(&self) -> Value {
+    let scoring = match self.scoring {
+      Scoring::Standard => "standard",
+      Scoring::Vegas => "vegas",
+    };
+    let move_time = self.move_time.map_or(Value::Null, |t| Value::Number(t.as_secs() as f64 + t.subsec_nanos() as f64 / 1e9));
+
+    Value::Object(vec![
+      (String::from("draw_count"), Value::Number(self.draw_count as f64)),
+      (String::from("pass_limit"), optional_number(self.pass_limit)),
+      (String::from("scoring"), Value::String(String::from(scoring))),
+      (String::from("move_time"), move_time),
+      (String::from("thoughtful"), Value::Bool(self.thoughtful)),
+    ])
+  }
+
+  /// Rules written by `to_json`.
+  pub fn from_json(json: &Value) -> Result<KlondikeRules, String> {
+    let scoring = match member(json, "scoring")?.as_str() {
+      Some("standard") => Scoring::Standard,
+      Some("vegas") => Scoring::Vegas,
+      _ => { return Err(String::from("scoring must be \"standard\" or \"vegas\"")); },
+    };
+    let move_time = match *member(json, "move_time")? {
+      Value::Null => None,
+      Value::Number(n) if n >= 0.0 => Some(Duration::new(n.trunc() as u64, (n.fract() * 1e9) as u32)),
+      _ => { return Err(String::from("move_time must be null or a number of seconds")); },
+    };
+
+    Ok(KlondikeRules {
+      draw_count: count(json, "draw_count")? as u8,
+      pass_limit: optional_count(json, "pass_limit")?,
+      scoring: scoring,
+      move_time: move_time,
+      thoughtful: member(json, "thoughtful")?.as_bool().ok_or_else(|| String::from("thoughtful must be true or false"))?,
+    })
+  }
+}
+
+/// Cards as a JSON array of their names, e.g. `["10H","KS"]`.
+fn cards_json(cards: &[Card]) -> Value {
+  Value::Array(cards.iter().map(|c| Value::String(c.to_string())).collect())
+}
+
+fn optional_number(n: Option<usize>) -> Value {
+  n.map_or(Value::Null, |n| Value::Number(n as f64))
+}
+
+/// Member `key` of the JSON object `json`.
+fn member<'a>(json: &'a Value, key: &str) -> Result<&'a Value, String> {
+  json.get(key).ok_or_else(|| format!("missing {}", key))
+}
+
+fn count(json: &Value, key: &str) -> Result<usize, String> {
+  member(json, key)?.as_u64().map(|n| n as usize).ok_or_else(|| format!("{} must be a count", key))
+}
+
+fn optional_count(json: &Value, key: &str) -> Result<Option<usize>, String> {
+  match *member(json, key)? {
+    Value::Null => Ok(None),
+    ref n => n.as_u64().map(|n| Some(n as usize)).ok_or_else(|| format!("{} must be null or a count", key)),
+  }
+}
+
+fn card_from_json(json: &Value) -> Result<Card, String> {
+  let card = json.as_str().ok_or_else(|| format!("card is not a string: {}", json))?.parse::<Card>()?;
+  rank_index(card.rank())?;
+  Ok(card)
+}
+
+/// The cards of the JSON array member `key` of `json`.
+fn cards_from_json(json: &Value, key: &str) -> Result<Vec<Card>, String> {
+  member(json, key)?.as_array()
+    .ok_or_else(|| format!("{} must be an array of cards", key))?
+    .iter()
+    .map(card_from_json)
+    .collect()
+}
+
+/// Standard `score` after `seconds` of play with the time penalty and, for a
+/// won game, the time bonus.
+fn timed_score(score: i32, seconds: u64, won: bool) -> i32 {
+  let penalized = cmp::max(0, score as i64 - 2 * (seconds / 10) as i64);
+  let bonus = if won && seconds >= 30 { 700000 / seconds as i64 } else { 0 };
+
+  (penalized + bonus) as i32
+}
+
+/// Broken invariant found by `KlondikeSolitaireGame::validate`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ValidationError {
+  /// The game does not hold exactly the 52 standard cards.
+  CardCount(usize),
+  DuplicateCard(Card),
+  MissingCard(Card),
+  /// Face down cards in a pile exceed the pile's index.
+  PileHiddenOverflow { pile: usize, hidden: usize },
+  /// A pile has face down cards but no face up card on top of them.
+  PileNotTurned(usize),
+  /// Face up pile cards do not alternate color with descending rank.
+  PileOrder { pile: usize, card: Card, next: Card },
+  /// Foundations are not one per suit in the standard order.
+  FoundationSuit { index: usize, suit: Suit },
+  DeckSize(usize),
+  DeckDrawCount(usize),
+  /// More visible deck cards than the draw count, or visible cards past the
+  /// end of the deck.
+  DeckVisible { index: usize, count: usize },
+}
+
+impl fmt::Display for ValidationError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ValidationError::CardCount(n) => write!(f, "game has {} cards, expected {}", n, french::STANDARD_DECK_SIZE),
+      ValidationError::DuplicateCard(c) => write!(f, "duplicate card {}", c),
+      ValidationError::MissingCard(c) => write!(f, "missing card {}", c),
+      ValidationError::PileHiddenOverflow { pile, hidden } => write!(f, "pile {} has {} hidden cards, max {}", pile, hidden, pile),
+      ValidationError::PileNotTurned(pile) => write!(f, "pile {} has hidden cards but no visible card", pile),
+      ValidationError::PileOrder { pile, card, next } => write!(f, "pile {}: {} can not be on {}", pile, next, card),
+      ValidationError::FoundationSuit { index, suit } => write!(f, "foundation {} has suit {}", index, suit),
+      ValidationError::DeckSize(n) => write!(f, "deck has {} cards, max {}", n, MAX_DECK_SIZE),
+      ValidationError::DeckDrawCount(n) => write!(f, "invalid draw count {}", n),
+      ValidationError::DeckVisible { index, count } => write!(f, "invalid visible deck cards: index={} count={}", index, count),
+    }
+  }
+}
+
+impl error::Error for ValidationError {}
+
+#[derive(Clone)]
 pub struct KlondikeSolitaireGame {
   cards: Vec<Card>,
   foundations: [Foundation; NUM_FOUNDATIONS],
   piles: [Pile; NUM_PILES],
   deck: Deck,
+  rules: KlondikeRules,
+  score: i32,
+  moves_made: usize,
+  hidden_flips: usize,
+  revealed_at: [Option<usize>; NUM_PILES],
+  move_counts: [usize; NUM_MOVE_KINDS],
+  history: Option<Vec<MoveRecord>>,
+  observers: Observers,
+  /// `full_hash` of the position, updated by each move.
+  hash: u64,
 }
 
 impl KlondikeSolitaireGame {
   pub fn new(draw_count: u8) -> KlondikeSolitaireGame {
-    KlondikeSolitaireGame::new_shuffle(draw_count, default_shuffle)
+    KlondikeSolitaireGame::new_shuffle(draw_count, rand::thread_rng())
+  }
+
+  /// Game dealt from a standard deck shuffled by `source`, such as
+  /// `seeded_source(seed)` or `shuffle_rng.source(seed)`.
+  pub fn new_shuffle<S: ShuffleSource>(draw_count: u8, source: S) -> KlondikeSolitaireGame {
+    KlondikeSolitaireGame::with_rules(KlondikeRules::new(draw_count), source)
   }
 
-  pub fn new_shuffle<F>(draw_count: u8, mut shuffle: F) -> KlondikeSolitaireGame
-    where F: FnMut(&mut Vec<Card>) {
+  pub fn with_rules<S: ShuffleSource>(rules: KlondikeRules, mut source: S) -> KlondikeSolitaireGame {
     let mut cards = french::new_standard_deck();
-    shuffle(&mut cards);
+    source.shuffle(&mut cards);
+    KlondikeSolitaireGame::with_deal(rules, cards)
+  }
 
+  /// Game dealt from `cards` in the order given, such as a numbered deal or
+  /// one read from a file.
+  pub fn with_deal(rules: KlondikeRules, cards: Vec<Card>) -> KlondikeSolitaireGame {
     let mut game = KlondikeSolitaireGame {
       cards: cards,
       foundations: [
@@ -92,54 +685,83 @@ impl KlondikeSolitaireGame {
         Pile::new(),
         Pile::new(),
       ],
-      deck: Deck::new(draw_count),
+      deck: Deck::new(rules.draw_count),
+      rules: rules,
+      score: 0,
+      moves_made: 0,
+      hidden_flips: 0,
+      revealed_at: [None; NUM_PILES],
+      move_counts: [0; NUM_MOVE_KINDS],
+      history: None,
+      observers: Observers::default(),
+      hash: 0,
     };
 
+    game.deck.pass_limit = rules.pass_limit;
+    game.deck.thoughtful = rules.thoughtful;
+    for pile in game.piles.iter_mut() {
+      pile.thoughtful = rules.thoughtful;
+    }
+
     // Deal the cards
     game.reset();
 
     return game;
   }
 
-  pub fn from(deck: Deck, mut foundations: [Foundation; NUM_FOUNDATIONS], piles: [Pile; NUM_PILES]) -> KlondikeSolitaireGame {
+  /// Game from an existing layout.
+  ///
+  /// Panics if the layout is not valid, see `try_from`.
+  pub fn from(deck: Deck, foundations: [Foundation; NUM_FOUNDATIONS], piles: [Pile; NUM_PILES]) -> KlondikeSolitaireGame {
+    match KlondikeSolitaireGame::try_from(deck, foundations, piles) {
+      Ok(game) => game,
+      Err(e) => panic!("Invalid game: {}", e),
+    }
+  }
+
+  /// Game from an existing layout, or the first constraint the layout breaks
+  /// (see `validate`). Foundations may be given in any order. The error
+  /// converts into `KlondikeErr::Invalid`, so a layout read from outside can
+  /// be checked with `?` along with building its `Deck` and `Pile`s.
+  pub fn try_from(deck: Deck, mut foundations: [Foundation; NUM_FOUNDATIONS], piles: [Pile; NUM_PILES]) -> Result<KlondikeSolitaireGame, ValidationError> {
     let mut cards = Vec::with_capacity(french::STANDARD_DECK_SIZE as usize);
     cards.extend(deck.waste_cards());
     cards.extend(deck.visible_cards());
     cards.extend(deck.remaining_cards());
 
-    // validate all cards distinct
-    {
-      let mut set: HashSet<_> = cards.iter().cloned().collect();
-
-      for f in &foundations {
-        for card in f.cards().iter() {
-          assert!(set.insert(*card), "Duplicate card in game: {:?}", card);
-          cards.push(*card);
-        }
-      }
-
-      for (i, p) in piles.iter().enumerate() {
-        assert!(p.hidden_cards().len() <= i, "Pile {} hidden cards exceeds max: max={}", i, i);
-
-        for card in p.hidden_cards().iter().chain(p.visible_cards()) {
-          assert!(set.insert(*card), "Duplicate card in game: {:?}", card);
-          cards.push(*card);
-        }
-      }
+    for f in &foundations {
+      cards.extend(f.cards());
     }
 
-    // validate 52 total cards (french::STANDARD_DECK_SIZE)
-    assert_eq!(cards.len(), french::STANDARD_DECK_SIZE as usize);
+    for p in &piles {
+      cards.extend(p.hidden_cards().iter().chain(p.visible_cards()));
+    }
 
     // ensure foundations are in expected order
     foundations.sort_by_key(|f| KlondikeSolitaireGame::foundation_index(f.suit()));
 
-    return KlondikeSolitaireGame {
+    let rules = KlondikeRules { pass_limit: deck.pass_limit, .. KlondikeRules::new(deck.draw_count()) };
+    let game = KlondikeSolitaireGame {
       cards: cards,
       deck: deck,
       foundations: foundations,
       piles: piles,
+      rules: rules,
+      score: 0,
+      moves_made: 0,
+      hidden_flips: 0,
+      revealed_at: [None; NUM_PILES],
+      move_counts: [0; NUM_MOVE_KINDS],
+      history: None,
+      observers: Observers::default(),
+      hash: 0,
     };
+
+    let mut game = game;
+    game.update_reveals();
+    game.hash = game.full_hash();
+    game.validate()?;
+    return Ok(game);
   }
 
   fn foundation_index(suit: Suit) -> usize {
@@ -163,6 +785,12 @@ impl KlondikeSolitaireGame {
     &self.deck
   }
 
+  /// Times the waste may still be turned over, `None` when the rules allow
+  /// any number of passes.
+  pub fn passes_remaining(&self) -> Option<usize> {
+    self.deck.passes_remaining()
+  }
+
   pub fn piles(&self) -> &[Pile] {
     &self.piles[..]
   }
@@ -176,635 +804,3157 @@ impl KlondikeSolitaireGame {
       foundation.clear();
     }
 
-    self.piles[0].reset(&self.cards[0..1]);
-    self.piles[1].reset(&self.cards[1..3]);
-    self.piles[2].reset(&self.cards[3..6]);
-    self.piles[3].reset(&self.cards[6..10]);
-    self.piles[4].reset(&self.cards[10..15]);
-    self.piles[5].reset(&self.cards[15..21]);
-    self.piles[6].reset(&self.cards[21..28]);
+    let dealt = deal_piles(&self.cards, &mut self.piles);
+    self.deck.reset(&self.cards[dealt..]);
+
+    self.moves_made = 0;
+    self.hidden_flips = 0;
+    self.revealed_at = [None; NUM_PILES];
+    self.move_counts = [0; NUM_MOVE_KINDS];
+    self.update_reveals();
+    self.score = match self.rules.scoring {
+      Scoring::Standard => 0,
+      Scoring::Vegas => -(french::STANDARD_DECK_SIZE as i32),
+    };
+    self.hash = self.full_hash();
+  }
 
-    self.deck.reset(&self.cards[28..]);
+  /// 64 bit Zobrist hash of the position: where every card is and whether it
+  /// is face up, the deck's draw position and, when passes are limited, its
+  /// pass. Equal positions hash equally however they were reached, so the
+  /// hash can key transposition tables. The score and move counts are not
+  /// part of it.
+  ///
+  /// The hash is kept up to date by each move rather than computed on
+  /// demand, so this is free.
+  pub fn state_hash(&self) -> u64 {
+    self.hash
   }
 
-  /// True if the table is clear (all cards are in foundation).
-  pub fn is_clear(&self) -> bool {
-    let clear = self.foundations.iter().all(|f| f.is_full());
+  /// `state_hash` computed from scratch.
+  fn full_hash(&self) -> u64 {
+    let piles = (0..NUM_PILES).fold(0, |h, i| h ^ pile_hash(i, &self.piles[i]));
+    piles ^ foundations_hash(&self.foundations) ^ self.deck.cards_hash() ^ self.deck.state_hash()
+  }
 
-    if clear {
-      debug_assert!(self.piles.iter().all(|p| p.is_empty()));
-      debug_assert!(self.deck.is_empty());
-    }
+  /// Part of `state_hash` that a move from `source` to `target` can change.
+  fn move_hash(&self, source: MoveSource, target: MoveTarget) -> u64 {
+    let source_hash = match source {
+      MoveSource::Deck => self.deck.cards_hash() ^ self.deck.state_hash(),
+      MoveSource::Pile(i) | MoveSource::PileRun { pile: i, .. } => pile_hash(i as usize, &self.piles[i as usize]),
+      MoveSource::Foundation(_) => foundations_hash(&self.foundations),
+    };
+    let target_hash = match (source, target) {
+      (_, MoveTarget::Pile(j)) if source.pile() == Some(j as usize) => 0,
+      (_, MoveTarget::Pile(j)) => pile_hash(j as usize, &self.piles[j as usize]),
+      (MoveSource::Foundation(_), MoveTarget::Foundation) => 0,
+      (_, MoveTarget::Foundation) => foundations_hash(&self.foundations),
+    };
 
-    return clear;
+    source_hash ^ target_hash
   }
 
-  pub fn draw(&mut self) {
-    self.deck.draw()
+  pub fn rules(&self) -> KlondikeRules {
+    self.rules
   }
 
-  pub fn move_cards(&mut self, source: MoveSource, target: MoveTarget) -> KlondikeResult<()> {
-    match (source, target) {
-      (MoveSource::Deck, MoveTarget::Foundation) => {
-        let visible_card = {
-          match self.deck.top() {
-            Some(c) => c,
-            None => { return Err(KlondikeErr::InvalidMove); },
-          }
-        };
+  /// Value of `moves_made` when the last hidden card of a pile was turned
+  /// face up, or `None` if the pile still has hidden cards. Piles that
+  /// started without hidden cards were revealed at move 0.
+  pub fn pile_revealed_at(&self, index: usize) -> Option<usize> {
+    self.revealed_at[index]
+  }
 
-        {
-          let foundation = self.foundation_mut(visible_card.suit());
-          if foundation.next_card() != Some(visible_card) {
-            return Err(KlondikeErr::InvalidMove);
-          }
-          foundation.push();
-        }
+  fn update_reveals(&mut self) {
+    for (i, p) in self.piles.iter().enumerate() {
+      if self.revealed_at[i].is_none() && p.hidden_cards().is_empty() {
+        self.revealed_at[i] = Some(self.moves_made);
+      }
+    }
+  }
 
-        self.deck.pop();
-        Ok(())
-      },
-      (MoveSource::Deck, MoveTarget::Pile(pile_index)) => {
-        let pile_index = pile_index as usize;
-        assert!(pile_index < NUM_PILES);
+  /// Number of draws and successful card moves since the deal.
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
 
-        let visible_card = {
-          match self.deck.top() {
-            Some(c) => c,
-            None => { return Err(KlondikeErr::InvalidMove); },
-          }
-        };
+  /// Number of draws from the deck since the deal, including the draws that
+  /// turned the waste over.
+  pub fn draws_made(&self) -> usize {
+    self.move_count(MoveKind::Draw) + self.move_count(MoveKind::Recycle)
+  }
 
-        match self.piles[pile_index].push(visible_card) {
-          Ok(_) => {
-            self.deck.pop();
-            Ok(())
-          },
-          Err(_) => Err(KlondikeErr::InvalidMove),
-        }
-      },
-      (MoveSource::Foundation(_), MoveTarget::Foundation) => {
-        // Noop
-        Ok(())
-      },
-      (MoveSource::Foundation(suit), MoveTarget::Pile(pile_index)) => {
-        let pile_index = pile_index as usize;
-        assert!(pile_index < NUM_PILES);
+  /// Number of hidden pile cards turned face up since the deal.
+  pub fn hidden_flips(&self) -> usize {
+    self.hidden_flips
+  }
 
-        let visible_card = {
-          match self.foundation(suit).top() {
-            Some(c) => c,
-            None => { return Err(KlondikeErr::InvalidMove); }
-          }
-        };
+  /// Number of moves of `kind` made since the deal.
+  pub fn move_count(&self, kind: MoveKind) -> usize {
+    self.move_counts[kind.index()]
+  }
 
-        match self.piles[pile_index].push(visible_card) {
-          Ok(_) => {
-            self.foundation_mut(suit).pop();
-            Ok(())
-          },
-          Err(_) => Err(KlondikeErr::InvalidMove),
-        }
-      },
-      (MoveSource::Pile(source_pile_index), MoveTarget::Pile(target_pile_index)) => {
-        let source_pile_index = source_pile_index as usize;
-        assert!(source_pile_index < NUM_PILES);
+  pub fn stats(&self) -> GameStats {
+    GameStats {
+      moves: self.moves_made,
+      draws: self.draws_made(),
+      passes: self.deck.passes(),
+      cards_revealed: self.hidden_flips,
+      cards_founded: self.move_count(MoveKind::DeckToFoundation) + self.move_count(MoveKind::PileToFoundation),
+    }
+  }
 
-        let target_pile_index = target_pile_index as usize;
-        assert!(target_pile_index < NUM_PILES);
+  /// Score under the game's `Scoring` rules. Timed standard games lose 2
+  /// points for every 10 seconds of `elapsed` time and a won game scores a
+  /// bonus of 700000 divided by the seconds taken, if it took at least 30.
+  /// Vegas scoring is never timed.
+  pub fn score(&self) -> i32 {
+    match (self.rules.scoring, self.elapsed()) {
+      (Scoring::Standard, Some(elapsed)) => timed_score(self.score, elapsed.as_secs(), self.is_clear()),
+      _ => self.score,
+    }
+  }
 
-        if source_pile_index == target_pile_index {
-          // Noop
-          return Ok(());
-        }
-
-        let piles_ptr = self.piles.as_mut_ptr();
-        let target_pile = &mut self.piles[target_pile_index];
-
-        // Seems like there should be a better way to do this, but can't get
-        // two mutable references to elements of `piles` in the same scope.
-        // This should be safe since we ensure the source and target indexes
-        // are different.
-        unsafe {
-          (*piles_ptr.offset(source_pile_index as isize)).move_to(target_pile)
-        }
-      },
-      (MoveSource::Pile(pile_index), MoveTarget::Foundation) => {
-        let pile_index = pile_index as usize;
-        assert!(pile_index < NUM_PILES);
-
-        let visible_card = {
-          match self.piles[pile_index].top() {
-            Some(c) => c,
-            None => { return Err(KlondikeErr::InvalidMove); },
-          }
-        };
+  /// Simulated time played, `moves_made` times the `move_time` of the rules,
+  /// or `None` if the game is not timed.
+  pub fn elapsed(&self) -> Option<Duration> {
+    self.rules.move_time.map(|t| t * self.moves_made as u32)
+  }
 
-        {
-          let foundation = self.foundation_mut(visible_card.suit());
-          if foundation.next_card() != Some(visible_card) {
-            return Err(KlondikeErr::InvalidMove);
-          }
-          foundation.push();
-        }
+  fn add_score(&mut self, points: i32) {
+    self.score += points;
 
-        self.piles[pile_index].pop();
-        Ok(())
-      }
+    if self.rules.scoring == Scoring::Standard && self.score < 0 {
+      self.score = 0;
     }
   }
-}
 
-pub struct Deck {
-  cards: Vec<Card>,
-  draw_count: usize,
-  visible_index: usize,
-  visible_count: usize,
-}
+  fn hidden_count(&self) -> usize {
+    self.piles.iter().map(|p| p.hidden_cards().len()).sum()
+  }
 
-impl Deck {
-  pub fn new(draw_count: u8) -> Deck {
-    assert!(draw_count > 0 && draw_count as usize <= MAX_DECK_SIZE);
+  /// Single line description of the whole game.
+  ///
+  /// The format is `<piles> / <foundations> / <deck>`:
+  ///
+  /// * piles: the seven piles separated by `|`, each listing its cards from
+  ///   bottom to top. Face down cards are lower case. An empty pile is `-`.
+  /// * foundations: the top card of each non-empty foundation, or `-`.
+  /// * deck: the draw count (`d1`, `d3`, ...) followed by the waste, visible
+  ///   and remaining cards, separated by `:`.
+  ///
+  /// For example `AS|2h 3C|...|KD QS / AH / d1 5D : 9C : 4H JS`.
+  pub fn notation(&self) -> String {
+    fn cards(cards: &[Card], hidden: bool) -> Vec<String> {
+      cards.iter().map(|c| {
+        let s = c.to_string();
+        if hidden { s.to_lowercase() } else { s }
+      }).collect()
+    }
 
-    Deck {
-      cards: Vec::with_capacity(MAX_DECK_SIZE),
-      draw_count: draw_count as usize,
-      visible_index: 0,
-      visible_count: 0,
+    fn group(cards: Vec<String>) -> String {
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
     }
-  }
 
-  pub fn from(draw_count: u8, waste: &[Card], visible: &[Card], remaining: &[Card]) -> Deck {
-    let deck_size = waste.len() + visible.len() + remaining.len();
-    let mut cards = Vec::with_capacity(deck_size);
+    let piles: Vec<String> = self.piles.iter().map(|p| {
+      let mut c = cards(p.hidden_cards(), true);
+      c.extend(cards(p.visible_cards(), false));
+      group(c)
+    }).collect();
+
+    let foundations: Vec<String> = self.foundations.iter()
+      .filter_map(|f| f.top())
+      .map(|c| c.to_string())
+      .collect();
+
+    format!("{} / {} / d{} {} : {} : {}",
+            piles.join("|"),
+            group(foundations),
+            self.deck.draw_count(),
+            group(cards(self.deck.waste_cards(), false)),
+            group(cards(self.deck.visible_cards(), false)),
+            group(cards(self.deck.remaining_cards(), false)))
+  }
 
-    // draw_count is valid
-    assert!(draw_count > 0 && draw_count as usize <= MAX_DECK_SIZE);
+  /// Game from a line written by `notation`, to reproduce a given position
+  /// exactly, such as a published deal or the board of a bug report:
+  ///
+  /// ```text
+  /// 8D|kd JS|as 5c QD|4s 7c 4h 7S|5h qc ks 10s KH|... / - / d1 - : - : 8C 6C ...
+  /// ```
+  ///
+  /// The face down cards of a pile, in lower case, must come before its face
+  /// up cards. The deck is on its first pass with no pass limit, and the
+  /// position is checked like `try_from` does.
+  pub fn from_notation(notation: &str) -> Result<KlondikeSolitaireGame, String> {
+    fn cards(group: &str) -> Result<Vec<Card>, String> {
+      group.split_whitespace()
+        .filter(|t| *t != "-")
+        .map(|t| t.parse::<Card>())
+        .collect()
+    }
 
-    // visible length <= draw_count
-    assert!(visible.len() <= draw_count as usize, "number of visible cards can not exceed draw count");
+    let parts: Vec<&str> = notation.trim().split(" / ").collect();
+    if parts.len() != 3 {
+      return Err(String::from("expected piles / foundations / deck"));
+    }
 
-    // deck can not exceed 24 cards (52 - initial pile contents)
-    assert!(deck_size <= MAX_DECK_SIZE, "deck size must be <= {}", MAX_DECK_SIZE);
+    let mut piles = Vec::with_capacity(NUM_PILES);
+    for (i, group) in parts[0].split('|').enumerate() {
+      let mut pile = Pile::new();
+      for token in group.split_whitespace().filter(|t| *t != "-") {
+        let card = token.parse::<Card>()?;
+        if token.chars().any(|c| c.is_uppercase()) {
+          pile.visible_cards.push(card);
+        } else if pile.visible_cards.is_empty() {
+          pile.hidden_cards.push(card);
+        } else {
+          return Err(format!("pile {}: face down {} on a face up card", i, token));
+        }
+      }
+      piles.push(pile);
+    }
+    if piles.len() != NUM_PILES {
+      return Err(format!("expected {} piles, found {}", NUM_PILES, piles.len()));
+    }
 
-    // cards are all distinct and are standard ranks
-    {
-      let mut set = HashSet::new();
-      for card in waste.iter().chain(visible).chain(remaining) {
-        assert!(set.insert(card), "Duplicate card in deck: {:?}", card);
-        rank_index(card.rank()).unwrap();
-        cards.push(*card);
+    let mut foundations = Vec::with_capacity(NUM_FOUNDATIONS);
+    for suit in [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs].iter() {
+      let tops: Vec<Card> = cards(parts[1])?.into_iter().filter(|c| c.suit() == *suit).collect();
+      if tops.len() > 1 {
+        return Err(format!("more than one top card for the {} foundation", suit));
       }
+      foundations.push(Foundation::from(*suit, tops.first().map(|c| c.rank())));
     }
 
-    return Deck {
-      cards: cards,
-      draw_count: draw_count as usize,
+    let deck_parts: Vec<&str> = parts[2].splitn(2, ' ').collect();
+    let draw_count = match deck_parts[0].trim_start_matches('d').parse::<usize>() {
+      Ok(n) if deck_parts[0].starts_with('d') && n >= 1 && n <= MAX_DECK_SIZE => n,
+      _ => { return Err(format!("invalid draw count {}", deck_parts[0])); },
+    };
+    let groups: Vec<&str> = deck_parts.get(1).map_or(Vec::new(), |d| d.split(" : ").collect());
+    if groups.len() != 3 {
+      return Err(String::from("expected waste : visible : remaining deck cards"));
+    }
+
+    let (waste, visible) = (cards(groups[0])?, cards(groups[1])?);
+    if visible.len() > draw_count {
+      return Err(format!("{} visible deck cards with a draw count of {}", visible.len(), draw_count));
+    }
+    let mut deck_cards = waste.clone();
+    deck_cards.extend(&visible);
+    deck_cards.extend(cards(groups[2])?);
+
+    let deck = Deck {
+      cards: deck_cards,
+      max_len: MAX_DECK_SIZE,
+      thoughtful: false,
+      draw_count: draw_count,
       visible_index: waste.len(),
       visible_count: visible.len(),
+      pass_limit: None,
+      passes: 1,
     };
-  }
-
-  pub fn reset(&mut self, cards: &[Card]) {
-    assert!(cards.len() <= MAX_DECK_SIZE);
 
-    self.cards.clear();
-    self.visible_index = 0;
-    self.visible_count = 0;
-    self.cards.extend_from_slice(cards);
+    let f = foundations;
+    let p = piles;
+    KlondikeSolitaireGame::try_from(
+      deck,
+      [f[0].clone(), f[1].clone(), f[2].clone(), f[3].clone()],
+      [p[0].clone(), p[1].clone(), p[2].clone(), p[3].clone(), p[4].clone(), p[5].clone(), p[6].clone()],
+    ).map_err(|e| e.to_string())
   }
 
-  pub fn is_empty(&self) -> bool {
-    self.cards.is_empty()
-  }
+  /// The board laid out as it is on a table, over several lines: the stock
+  /// and waste with the foundations and score, then the piles in aligned
+  /// columns, bottom card first, with `##` for each face down card.
+  ///
+  /// ```text
+  /// stock 16  waste 9C 4D | KD  foundations AH -- 2S --  score 15
+  ///    0    1    2    3    4    5    6
+  ///   7H   ##   ##   ##   ##   ##   ##
+  ///        QS  10D   ##   ##   ##   ##
+  /// ```
+  ///
+  /// Cards after the `|` of the waste can be played. Empty piles are `--`.
+  /// With limited passes the stock also shows the pass, as in `pass 2/3`.
+  pub fn render(&self) -> String {
+    fn cards(cards: &[Card]) -> Vec<String> {
+      cards.iter().map(|c| c.to_string()).collect()
+    }
 
-  pub fn len(&self) -> usize {
-    self.cards.len()
-  }
+    let mut waste = cards(self.deck.waste_cards());
+    if ! self.deck.visible_cards().is_empty() {
+      waste.push(String::from("|"));
+      waste.extend(cards(self.deck.visible_cards()));
+    }
+    if waste.is_empty() {
+      waste.push(String::from("--"));
+    }
 
-  pub fn draw_count(&self) -> u8 {
-    self.draw_count as u8
-  }
+    let passes = match self.deck.pass_limit() {
+      Some(limit) => format!("  pass {}/{}", self.deck.passes(), limit),
+      None => String::new(),
+    };
+    let foundations: Vec<String> = self.foundations.iter()
+      .map(|f| f.top().map_or(String::from("--"), |c| c.to_string()))
+      .collect();
+
+    let mut lines = vec![
+      format!("stock {}{}  waste {}  foundations {}  score {}",
+              self.deck.remaining_cards().len(), passes, waste.join(" "), foundations.join(" "), self.score()),
+      (0..NUM_PILES).map(|i| format!("{:>5}", i)).collect::<String>(),
+    ];
+
+    let rows = cmp::max(1, self.piles.iter().map(|p| p.len()).max().unwrap_or(0));
+    for row in 0..rows {
+      let line: String = self.piles.iter().map(|p| {
+        let hidden = p.hidden_cards().len();
+        let cell = if row < hidden {
+          String::from("##")
+        } else if let Some(card) = p.visible_cards().get(row - hidden) {
+          card.to_string()
+        } else if row == 0 {
+          String::from("--")
+        } else {
+          String::new()
+        };
 
-  pub fn top(&self) -> Option<Card> {
-    match self.visible_count {
-      0 => None,
-      count => Some(self.cards[self.visible_index+count-1]),
+        format!("{:>5}", cell)
+      }).collect();
+
+      lines.push(String::from(line.trim_end()));
     }
-  }
 
-  pub fn visible_cards(&self) -> &[Card] {
-    &self.cards[self.visible_index..self.visible_index+self.visible_count]
+    lines.join("\n")
   }
 
-  pub fn waste_cards(&self) -> &[Card] {
-    &self.cards[..self.visible_index]
+  /// The whole position as a JSON object, to dump a game from a run and
+  /// inspect or load it later. Hidden cards are included; `score` is the
+  /// score before any time penalty.
+  ///
+  /// ```text
+  /// {"rules":{...},"score":15,"moves_made":12,"hidden_flips":2,
+  ///  "revealed_at":[0,null,9,...],"move_counts":{"draw":5,...},
+  ///  "foundations":[{"suit":"H","top":"2H"},...],
+  ///  "piles":[{"hidden":[],"visible":["KS"]},...],
+  ///  "deck":{...}}
+  /// ```
+  ///
+  /// `revealed_at` is `pile_revealed_at` of each pile and `move_counts` the
+  /// `move_count` of each `MoveKind`, by its name. See
+  /// `KlondikeRules::to_json`, `Foundation::to_json`, `Pile::to_json` and
+  /// `Deck::to_json` for the other members.
+  pub fn to_json(&self) -> Value {
+    let revealed_at = self.revealed_at.iter().map(|r| r.map_or(Value::Null, |m| Value::Number(m as f64))).collect();
+    let move_counts = MoveKind::all().iter().map(|&k| (k.to_string(), Value::Number(self.move_count(k) as f64))).collect();
+
+    Value::Object(vec![
+      (String::from("rules"), self.rules.to_json()),
+      (String::from("score"), Value::Number(self.score as f64)),
+      (String::from("moves_made"), Value::Number(self.moves_made as f64)),
+      (String::from("hidden_flips"), Value::Number(self.hidden_flips as f64)),
+      (String::from("revealed_at"), Value::Array(revealed_at)),
+      (String::from("move_counts"), Value::Object(move_counts)),
+      (String::from("foundations"), Value::Array(self.foundations.iter().map(Foundation::to_json).collect())),
+      (String::from("piles"), Value::Array(self.piles.iter().map(Pile::to_json).collect())),
+      (String::from("deck"), self.deck.to_json()),
+    ])
   }
 
-  pub fn remaining_cards(&self) -> &[Card] {
-    let index = cmp::min(self.visible_index + self.visible_count, self.cards.len());
-    &self.cards[index..]
-  }
+  /// Game from a position written by `to_json`, or what is wrong with it.
+  /// The position must hold all 52 cards and pass `validate`, as for
+  /// `try_from`; the rules, score and move counts are restored as written.
+  pub fn from_json(json: &Value) -> Result<KlondikeSolitaireGame, String> {
+    let rules = KlondikeRules::from_json(member(json, "rules")?).map_err(|e| format!("rules: {}", e))?;
+    let deck = Deck::from_json(member(json, "deck")?).map_err(|e| format!("deck: {}", e))?;
+
+    let foundations = member(json, "foundations")?.as_array()
+      .ok_or_else(|| String::from("foundations must be an array"))?
+      .iter()
+      .map(Foundation::from_json)
+      .collect::<Result<Vec<Foundation>, String>>()
+      .map_err(|e| format!("foundations: {}", e))?;
+    if foundations.len() != NUM_FOUNDATIONS {
+      return Err(format!("expected {} foundations, found {}", NUM_FOUNDATIONS, foundations.len()));
+    }
 
-  pub fn pop(&mut self) -> Option<Card> {
-    match self.visible_count {
-      0 => None,
-      _ => {
-        self.visible_count -= 1;
-        Some(self.cards.remove(self.visible_index + self.visible_count))
-      }
+    let piles = member(json, "piles")?.as_array()
+      .ok_or_else(|| String::from("piles must be an array"))?
+      .iter()
+      .enumerate()
+      .map(|(i, p)| Pile::from_json(p).map_err(|e| format!("pile {}: {}", i, e)))
+      .collect::<Result<Vec<Pile>, String>>()?;
+    if piles.len() != NUM_PILES {
+      return Err(format!("expected {} piles, found {}", NUM_PILES, piles.len()));
     }
-  }
 
-  pub fn draw(&mut self) {
-    // TODO return value?
-    // boolean: true if visible cards changed
-    // &[Card]: visible cards
-    self.visible_index += self.visible_count;
+    if rules.draw_count != deck.draw_count() || rules.pass_limit != deck.pass_limit() {
+      return Err(String::from("the rules and the deck have different draw counts or pass limits"));
+    }
 
-    if self.visible_index >= self.cards.len() {
-      self.visible_index = 0;
-      self.visible_count = 0;
-    } else {
-      self.visible_count = cmp::min(self.draw_count, self.cards.len() - self.visible_index);
+    let score = member(json, "score")?.as_f64()
+      .filter(|n| n.fract() == 0.0 && n.abs() <= i32::max_value() as f64)
+      .ok_or_else(|| String::from("score must be a whole number"))?;
+
+    let f = foundations;
+    let p = piles;
+    let mut game = KlondikeSolitaireGame::try_from(
+      deck,
+      [f[0].clone(), f[1].clone(), f[2].clone(), f[3].clone()],
+      [p[0].clone(), p[1].clone(), p[2].clone(), p[3].clone(), p[4].clone(), p[5].clone(), p[6].clone()],
+    ).map_err(|e| e.to_string())?;
+
+    game.rules = rules;
+    game.deck.thoughtful = rules.thoughtful;
+    for pile in game.piles.iter_mut() {
+      pile.thoughtful = rules.thoughtful;
+    }
+    game.score = score as i32;
+    game.moves_made = count(json, "moves_made")?;
+    game.hidden_flips = count(json, "hidden_flips")?;
+
+    let revealed_at = member(json, "revealed_at")?.as_array()
+      .filter(|r| r.len() == NUM_PILES)
+      .ok_or_else(|| format!("revealed_at must be an array of {} moves", NUM_PILES))?;
+    for (i, r) in revealed_at.iter().enumerate() {
+      game.revealed_at[i] = match *r {
+        Value::Null => None,
+        ref n => Some(n.as_u64().ok_or_else(|| String::from("revealed_at must hold nulls or counts"))? as usize),
+      };
+      if game.revealed_at[i].is_some() != game.piles[i].hidden_cards().is_empty() {
+        return Err(format!("revealed_at does not match the hidden cards of pile {}", i));
+      }
     }
+
+    let move_counts = member(json, "move_counts")?;
+    for &kind in MoveKind::all() {
+      game.move_counts[kind.index()] = count(move_counts, &kind.to_string()).map_err(|e| format!("move_counts: {}", e))?;
+    }
+
+    Ok(game)
   }
-}
 
-pub struct Pile {
-  visible_cards: Vec<Card>,
-  hidden_cards: Vec<Card>,
-}
+  /// Game from the JSON text `reader` holds, see `from_json`.
+  pub fn from_reader<R: Read>(mut reader: R) -> Result<KlondikeSolitaireGame, String> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text).map_err(|e| e.to_string())?;
 
-impl Pile {
-  pub fn new() -> Pile {
-    Pile {
-      visible_cards: Vec::new(),
-      hidden_cards: Vec::with_capacity(6),
-    }
+    KlondikeSolitaireGame::from_json(&Value::parse(&text)?)
   }
 
-  pub fn from(hidden: &[Card], visible: &[Card]) -> Pile {
-    // if there are hidden cards, must be at least one visible on top
-    assert!(hidden.is_empty() || visible.len() > 0, "there must be at least one visible card with hidden cards");
+  /// The position packed into `ENCODED_LEN` bytes, for solvers and for
+  /// telling apart many positions at once:
+  ///
+  /// * bytes 0-1: the number of cards on each foundation, 4 bits each.
+  /// * bytes 2-8: the face down and face up cards of each pile, 3 and 5
+  ///   bits.
+  /// * bytes 9-14: the draw count, the pass limit (0 for none), the pass,
+  ///   the number of deck cards, and the index and count of the visible
+  ///   deck cards.
+  /// * the rest: every card off the foundations in 6 bits, the piles bottom
+  ///   card first and then the deck in drawing order.
+  ///
+  /// Like `state_hash` this is the position only, the score and move counts
+  /// are not kept, and the pass only when passes are limited. Pass limits
+  /// above 255 can not be encoded.
+  pub fn encode(&self) -> [u8; ENCODED_LEN] {
+    let mut bytes = [0; ENCODED_LEN];
+
+    for (i, f) in self.foundations.iter().enumerate() {
+      bytes[i / 2] |= (f.len() as u8) << (4 * (i % 2));
+    }
 
-    // hidden has <= 6 cards (the right-most pile)
-    assert!(hidden.len() <= 6, "no more than 6 hidden cards (the largest, right-most pile initially has 6)");
+    for (i, p) in self.piles.iter().enumerate() {
+      bytes[2 + i] = (p.hidden_cards.len() as u8) << 5 | p.visible_cards.len() as u8;
+    }
 
-    // cards are distinct
-    // card ranks only from the standard deck
-    // `visible` is in valid order (e.g. color and rank)
-    {
-      let mut set = HashSet::new();
+    let deck = &self.deck;
+    assert!(deck.pass_limit.map_or(true, |limit| limit <= u8::MAX as usize), "pass limit {:?} can not be encoded", deck.pass_limit);
+    let passes = if deck.pass_limit.is_some() { deck.passes } else { 1 };
+    bytes[9..15].copy_from_slice(&[
+      deck.draw_count as u8,
+      deck.pass_limit.map_or(0, |limit| limit as u8),
+      passes as u8,
+      deck.cards.len() as u8,
+      deck.visible_index as u8,
+      deck.visible_count as u8,
+    ]);
+
+    let cards = self.piles.iter()
+      .flat_map(|p| p.hidden_cards.iter().chain(&p.visible_cards))
+      .chain(&deck.cards);
+    for (i, card) in cards.enumerate() {
+      let (byte, shift) = (ENCODED_CARDS + 6 * i / 8, 6 * i % 8);
+      let bits = (card_id(*card) as u16) << shift;
+      bytes[byte] |= bits as u8;
+      if shift > 2 {
+        bytes[byte + 1] |= (bits >> 8) as u8;
+      }
+    }
 
-      let mut viter = visible.iter().peekable();
-      while let Some(card) = viter.next() {
-        assert!(set.insert(card), "Duplicate card in pile: {:?}", card);
-        let card_rank_index = rank_index(card.rank()).unwrap();
+    bytes
+  }
 
-        // check next card color and rank
-        if let Some(next_card) = viter.peek() {
-          assert!(card.color().other() == next_card.color(), "invalid visible card ordering");
-          assert!(card_rank_index - 1 == rank_index(next_card.rank()).unwrap(), "invalid visible card ordering");
+  /// Game from `encode`d bytes, with the rules for their draw count and
+  /// pass limit. The position is checked like `try_from` does.
+  pub fn decode(bytes: &[u8; ENCODED_LEN]) -> Result<KlondikeSolitaireGame, String> {
+    let mut next_card = 0;
+    let mut cards = |count: usize| -> Result<Vec<Card>, String> {
+      let mut cards = Vec::with_capacity(count);
+      for _ in 0..count {
+        let (byte, shift) = (ENCODED_CARDS + 6 * next_card / 8, 6 * next_card % 8);
+        if byte >= ENCODED_LEN {
+          return Err(String::from("too many cards"));
         }
-      }
 
-      for card in hidden.iter() {
-        assert!(set.insert(card), "Duplicate card in pile: {:?}", card);
-        rank_index(card.rank()).unwrap();
+        let high = if shift > 2 { bytes[byte + 1] as u16 } else { 0 };
+        let id = ((bytes[byte] as u16 | high << 8) >> shift) & 0x3f;
+        cards.push(card_from_id(id as usize).ok_or_else(|| format!("invalid card {}", id))?);
+        next_card += 1;
       }
+      Ok(cards)
+    };
+
+    let mut piles = Vec::with_capacity(NUM_PILES);
+    for i in 0..NUM_PILES {
+      let mut pile = Pile::new();
+      pile.hidden_cards = cards((bytes[2 + i] >> 5) as usize)?;
+      pile.visible_cards = cards((bytes[2 + i] & 0x1f) as usize)?;
+      piles.push(pile);
     }
 
-    return Pile {
-      visible_cards: visible.iter().cloned().collect(),
-      hidden_cards: hidden.iter().cloned().collect(),
+    let (draw_count, pass_limit, passes) = (bytes[9] as usize, bytes[10] as usize, bytes[11] as usize);
+    if draw_count == 0 || draw_count > MAX_DECK_SIZE {
+      return Err(format!("invalid draw count {}", draw_count));
+    }
+    if passes == 0 {
+      return Err(String::from("passes start at 1"));
+    }
+
+    let deck_cards = cards(bytes[12] as usize)?;
+    let (visible_index, visible_count) = (bytes[13] as usize, bytes[14] as usize);
+    if visible_index + visible_count > deck_cards.len() {
+      return Err(format!("{} waste and {} visible cards in a deck of {}", visible_index, visible_count, deck_cards.len()));
+    }
+    if visible_count > draw_count {
+      return Err(format!("{} visible cards drawing {}", visible_count, draw_count));
+    }
+
+    let deck = Deck {
+      cards: deck_cards,
+      max_len: MAX_DECK_SIZE,
+      thoughtful: false,
+      draw_count: draw_count,
+      visible_index: visible_index,
+      visible_count: visible_count,
+      pass_limit: if pass_limit == 0 { None } else { Some(pass_limit) },
+      passes: passes,
     };
-  }
 
-  pub fn top(&self) -> Option<Card> {
-    self.visible_cards.last().map(|c| *c)
-  }
+    let mut foundations = Vec::with_capacity(NUM_FOUNDATIONS);
+    for (i, suit) in [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs].iter().enumerate() {
+      let len = ((bytes[i / 2] >> (4 * (i % 2))) & 0xf) as usize;
+      if len > RANKS.len() {
+        return Err(format!("{} cards on a foundation", len));
+      }
+      foundations.push(Foundation::from(*suit, if len == 0 { None } else { Some(RANKS[len - 1]) }));
+    }
 
-  pub fn len(&self) -> usize {
-    self.visible_cards.len() + self.hidden_cards.len()
+    let f = foundations;
+    let p = piles;
+    KlondikeSolitaireGame::try_from(
+      deck,
+      [f[0].clone(), f[1].clone(), f[2].clone(), f[3].clone()],
+      [p[0].clone(), p[1].clone(), p[2].clone(), p[3].clone(), p[4].clone(), p[5].clone(), p[6].clone()],
+    ).map_err(|e| e.to_string())
   }
 
-  pub fn is_empty(&self) -> bool {
-    self.visible_cards.is_empty()
-  }
+  /// Random legal game state for fuzzing and property tests.
+  ///
+  /// A random deal with a draw count of 1 or 3 is played forward with up to
+  /// `max_moves` random legal moves, so the result is always reachable from
+  /// a fresh deal: hidden cards, partially built foundations, cycled decks
+  /// and empty piles all occur.
+  pub fn arbitrary<R: Rng>(rng: &mut R, max_moves: usize) -> KlondikeSolitaireGame {
+    let draw_count = if rng.gen() { 1 } else { 3 };
+    let mut game = KlondikeSolitaireGame::new_shuffle(draw_count, RngSource(&mut *rng));
+    let move_count = rng.gen_range(0, max_moves + 1);
+    game.play_random(rng, move_count);
 
-  pub fn visible_cards(&self) -> &[Card] {
-    &self.visible_cards[..]
+    return game;
   }
 
-  pub fn hidden_cards(&self) -> &[Card] {
-    &self.hidden_cards[..]
+  /// Play up to `move_count` random legal moves, preferring moves of cards
+  /// over drawing so the game gets past the deal. Returns the number of moves
+  /// played, less than `move_count` if the game ran out of legal moves.
+  pub fn play_random<R: Rng>(&mut self, rng: &mut R, move_count: usize) -> usize {
+    for played in 0..move_count {
+      let moves = self.legal_moves();
+
+      let cards: Vec<Move> = moves.iter().cloned().filter(|m| *m != Move::Draw).collect();
+      let m = if ! cards.is_empty() && rng.gen_weighted_bool(2) {
+        *rng.choose(&cards).unwrap()
+      } else {
+        match rng.choose(&moves) {
+          Some(m) => *m,
+          None => { return played; },
+        }
+      };
+
+      self.apply(m).unwrap();
+    }
+
+    return move_count;
   }
 
-  pub fn reset(&mut self, cards: &[Card]) {
-    assert!(cards.len() <= 7 && cards.len() > 0);
+  /// Check every invariant of the game state.
+  ///
+  /// This is checked after each move in debug builds and can be used by
+  /// tests and fuzzers after constructing or changing a game.
+  pub fn validate(&self) -> Result<(), ValidationError> {
+    // foundations are in foundation_index order
+    for (index, f) in self.foundations.iter().enumerate() {
+      if KlondikeSolitaireGame::foundation_index(f.suit()) != index {
+        return Err(ValidationError::FoundationSuit { index: index, suit: f.suit() });
+      }
+    }
 
-    self.hidden_cards.clear();
+    for (i, p) in self.piles.iter().enumerate() {
+      if p.hidden_cards().len() > i {
+        return Err(ValidationError::PileHiddenOverflow { pile: i, hidden: p.hidden_cards().len() });
+      }
 
-    if cards.len() > 1 {
-      self.hidden_cards.extend_from_slice(&cards[0..cards.len()-1]);
+      if p.visible_cards().is_empty() && ! p.hidden_cards().is_empty() {
+        return Err(ValidationError::PileNotTurned(i));
+      }
+
+      for pair in p.visible_cards().windows(2) {
+        let valid = pair[0].color().other() == pair[1].color()
+          && rank_index(pair[0].rank()).ok() == rank_index(pair[1].rank()).ok().map(|r| r + 1);
+
+        if ! valid {
+          return Err(ValidationError::PileOrder { pile: i, card: pair[0], next: pair[1] });
+        }
+      }
     }
 
-    self.visible_cards.clear();
+    let deck = &self.deck;
+    if deck.cards.len() > MAX_DECK_SIZE {
+      return Err(ValidationError::DeckSize(deck.cards.len()));
+    }
 
-    if let Some(c) = cards.last() {
-      self.visible_cards.push(*c);
+    if deck.draw_count == 0 || deck.draw_count > MAX_DECK_SIZE {
+      return Err(ValidationError::DeckDrawCount(deck.draw_count));
     }
-  }
 
-  pub fn next_card(&self) -> Option<(Option<Color>, Rank)> {
-    match self.visible_cards.last() {
-      Some(card) => match rank_index(card.rank()).unwrap() {
-        0 => None,
-        i => Some((Some(card.color().other()), RANKS[i-1])),
-      },
-      None => Some((None, Rank::King)),
+    if deck.visible_count > deck.draw_count || deck.visible_index + deck.visible_count > deck.cards.len() {
+      return Err(ValidationError::DeckVisible { index: deck.visible_index, count: deck.visible_count });
     }
-  }
 
-  pub fn can_push(&self, card: Card) -> KlondikeResult<()> {
-    match self.next_card() {
-      Some((Some(color), rank)) => {
-        if card.color() == color && card.rank() == rank {
-          Ok(())
-        } else {
-          Err(KlondikeErr::InvalidCard)
+    // every standard card is in exactly one place
+    let mut set = HashSet::new();
+    let mut count = 0;
+    {
+      let mut check = |card: Card| {
+        count += 1;
+        if set.insert(card) { Ok(()) } else { Err(ValidationError::DuplicateCard(card)) }
+      };
+
+      for card in deck.cards.iter() {
+        check(*card)?;
+      }
+
+      for f in &self.foundations {
+        for card in f.cards() {
+          check(card)?;
         }
-      },
-      Some((None, rank)) => {
-        if card.rank() == rank {
-          Ok(())
-        } else {
-          Err(KlondikeErr::InvalidCard)
+      }
+
+      for p in &self.piles {
+        for card in p.hidden_cards().iter().chain(p.visible_cards()) {
+          check(*card)?;
         }
-      },
-      None => Err(KlondikeErr::Capacity),
+      }
     }
-  }
 
-  pub fn push(&mut self, card: Card) -> KlondikeResult<()> {
-    let result = self.can_push(card);
+    if let Some(card) = french::new_standard_deck().into_iter().find(|c| ! set.contains(c)) {
+      return Err(ValidationError::MissingCard(card));
+    }
 
-    if result.is_ok() {
-      self.visible_cards.push(card);
+    if count != french::STANDARD_DECK_SIZE as usize {
+      return Err(ValidationError::CardCount(count));
     }
 
-    return result;
+    Ok(())
   }
 
-  pub fn pop(&mut self) -> Option<Card> {
-    match self.visible_cards.pop() {
-      Some(card) => {
-        self.check_visible();
-        Some(card)
-      },
-      None => None,
-    }
-  }
+  /// True if the table is clear (all cards are in foundation).
+  pub fn is_clear(&self) -> bool {
+    let clear = self.foundations.iter().all(|f| f.is_full());
 
-  pub fn move_to(&mut self, target: &mut Pile) -> KlondikeResult<()> {
-    let index = {
-      match self.visible_cards.iter().position(|c| target.can_push(*c).is_ok()) {
-        Some(i) => i,
-        None => { return Err(KlondikeErr::InvalidMove); },
+    if clear {
+      debug_assert!(self.piles.iter().all(|p| p.is_empty()));
+      debug_assert!(self.deck.is_empty());
+    }
+
+    return clear;
+  }
+
+  /// True if no move can make progress any more: no sequence of moves that
+  /// turn nothing up, between piles, from a foundation back to a pile and
+  /// draws, leads to a position where a face down card can be turned up, a
+  /// card founded or a deck card played. A stuck game is lost.
+  ///
+  /// The positions are searched exactly, up to `STUCK_SEARCH_LIMIT` of them;
+  /// a game with more is taken not to be stuck.
+  pub fn is_stuck(&self) -> bool {
+    // Drawing changes nothing but the deck and the other moves nothing but
+    // the table, so the deck can turn up the same cards whatever is done on
+    // the table. The deck repeats once a draw position comes back.
+    let mut tops = Vec::new();
+    let mut deck = self.deck.clone();
+    let mut dealt = Vec::new();
+    loop {
+      tops.extend(deck.top());
+      if ! deck.can_draw() || dealt.contains(&(deck.visible_index, deck.visible_count)) {
+        break;
       }
+      dealt.push((deck.visible_index, deck.visible_count));
+      deck.draw();
+    }
+
+    let fits = |game: &KlondikeSolitaireGame| tops.iter()
+      .any(|&card| game.foundation(card.suit()).can_push(card) || game.piles.iter().any(|p| p.can_push(card).is_ok()));
+    let founds = |m: &Move| match *m {
+      Move::Cards(MoveSource::Foundation(_), _) => false,
+      Move::Cards(_, target) => target == MoveTarget::Foundation,
+      Move::Draw => false,
     };
 
-    target.visible_cards.extend_from_slice(&self.visible_cards[index..]);
+    // Most games that are not stuck have a move to make right away
+    if fits(self) || self.legal_moves().iter().any(&founds) {
+      return false;
+    }
 
-    self.visible_cards.truncate(index);
-    self.check_visible();
+    let hidden = self.hidden_count();
+    let founded = self.founded();
+    let mut start = self.clone();
+    start.history = None;
+    let mut seen = HashSet::new();
+    seen.insert(start.table_hash());
+    let mut open = VecDeque::new();
+    open.push_back(start);
+
+    // Breadth first, as progress is mostly a move or two away
+    while let Some(game) = open.pop_front() {
+      if fits(&game) {
+        return false;
+      }
 
-    Ok(())
+      for m in game.legal_moves() {
+        match m {
+          Move::Draw | Move::Cards(MoveSource::Deck, _) => continue,
+          Move::Cards(_, _) => {},
+        }
+
+        let mut next = game.clone();
+        next.apply(m).unwrap();
+
+        // Cards taken back from a foundation are not progress until more
+        // are founded than at the start
+        if next.hidden_count() < hidden || next.founded() > founded {
+          return false;
+        }
+
+        if seen.insert(next.table_hash()) {
+          if seen.len() > STUCK_SEARCH_LIMIT {
+            return false;
+          }
+          open.push_back(next);
+        }
+      }
+    }
+
+    true
   }
 
-  fn check_visible(&mut self) {
-    if self.visible_cards.is_empty() {
-      if let Some(next_card) = self.hidden_cards.pop() {
-        self.visible_cards.push(next_card);
+  /// Hash of the piles and foundations that is the same whichever order
+  /// the piles are in, as moving a pile with nothing face down under it to
+  /// an empty one does not change what can be played.
+  fn table_hash(&self) -> u64 {
+    self.piles.iter().fold(foundations_hash(&self.foundations), |h, p| h ^ pile_hash(0, p))
+  }
+
+  /// Number of cards on the foundations.
+  fn founded(&self) -> usize {
+    self.foundations.iter().map(|f| f.len()).sum()
+  }
+
+  /// True if the game is won by founding cards in order: the deck is empty
+  /// and every pile card is face up. The lowest card not founded is then
+  /// always on top of a pile.
+  pub fn is_trivially_winnable(&self) -> bool {
+    self.deck.is_empty() && self.hidden_count() == 0
+  }
+
+  /// Whether founding `card` can not cost the game: both foundations of the
+  /// other color already hold the card a rank below, so nothing is left to
+  /// build on it, or the game is trivially winnable. Aces and twos are always
+  /// safe.
+  pub fn is_safe_to_found(&self, card: Card) -> bool {
+    let rank = rank_index(card.rank()).unwrap() + 1;
+
+    rank <= 2 || self.is_trivially_winnable() || self.foundations.iter()
+      .filter(|f| f.suit().color() != card.color())
+      .all(|f| f.len() + 1 >= rank)
+  }
+
+  /// The first foundation move that is safe, from the piles and then the
+  /// top of the deck, if any.
+  pub fn safe_move(&self) -> Option<Move> {
+    (0..NUM_PILES).map(|i| (MoveSource::Pile(i as u8), self.piles[i].top()))
+      .chain(Some((MoveSource::Deck, self.deck.top())))
+      .filter_map(|(source, card)| card.map(|c| (source, c)))
+      .find(|&(_, card)| self.foundation(card.suit()).can_push(card) && self.is_safe_to_found(card))
+      .map(|(source, _)| Move::Cards(source, MoveTarget::Foundation))
+  }
+
+  /// Make every foundation move that is safe, from the piles and the top of
+  /// the deck, until there are none. Returns the number of cards founded.
+  /// A trivially winnable game is played out to the end.
+  pub fn autoplay(&mut self) -> usize {
+    let mut founded = 0;
+
+    while let Some(m) = self.safe_move() {
+      self.apply(m).unwrap();
+      founded += 1;
+    }
+
+    founded
+  }
+
+  /// Start recording every successful move and draw, with the cards each
+  /// moved or turned up, in `history`.
+  ///
+  /// Recording is off by default so that games copied during searches stay
+  /// cheap. Any moves already recorded are discarded.
+  pub fn record_moves(&mut self) {
+    self.history = Some(Vec::new());
+  }
+
+  /// Moves made since `record_moves` was called, or `None` if not recording.
+  pub fn recorded_moves(&self) -> Option<Vec<Move>> {
+    self.history.as_ref().map(|h| h.iter().map(|r| r.m).collect())
+  }
+
+  /// Record of every move made since `record_moves` was called, the oldest
+  /// first, or `None` if not recording.
+  pub fn history(&self) -> Option<&[MoveRecord]> {
+    self.history.as_ref().map(|h| &h[..])
+  }
+
+  /// Attach `observer`, which is then told of every move made on this game
+  /// but not on its copies. Keep a clone of the `Arc` to read the observer
+  /// back.
+  pub fn add_observer<O: Observer + Send + 'static>(&mut self, observer: Arc<Mutex<O>>) {
+    self.observers.0.push(observer);
+  }
+
+  pub fn clear_observers(&mut self) {
+    self.observers.0.clear();
+  }
+
+  fn record<F>(&mut self, record: F)
+    where F: FnOnce(&KlondikeSolitaireGame) -> MoveRecord {
+    if self.history.is_none() && self.observers.0.is_empty() {
+      return;
+    }
+
+    let record = record(self);
+    self.notify(&record);
+    if let Some(ref mut history) = self.history {
+      history.push(record);
+    }
+  }
+
+  fn notify(&self, record: &MoveRecord) {
+    for observer in self.observers.0.iter() {
+      let mut observer = observer.lock().unwrap();
+
+      match record.m {
+        Move::Draw if record.recycled => observer.on_recycle(self),
+        Move::Draw => observer.on_draw(self, &record.cards),
+        Move::Cards(source, _) => {
+          observer.on_move(self, record);
+          if let (Some(pile), Some(card)) = (source.pile(), record.flipped) {
+            observer.on_flip(self, pile, card);
+          }
+          if self.is_clear() {
+            observer.on_win(self);
+          }
+        },
+      }
+    }
+  }
+
+  /// Draw from the deck, scoring the recycle of the waste if it turns it
+  /// over. See `Deck::draw` for the result. A draw from a deck that can not
+  /// be drawn from is not counted, recorded or reported to observers.
+  pub fn draw(&mut self) -> DrawResult<'_> {
+    if ! self.deck.can_draw() {
+      return DrawResult::Empty;
+    }
+
+    let passes = self.deck.passes();
+    self.hash ^= self.deck.state_hash();
+    self.deck.draw();
+    self.hash ^= self.deck.state_hash();
+
+    if self.deck.passes() > passes && self.rules.scoring == Scoring::Standard {
+      match self.deck.draw_count {
+        1 => self.add_score(-100),
+        _ if self.deck.passes() > 3 => self.add_score(-20),
+        _ => (),
+      }
+    }
+
+    let kind = if self.deck.passes() > passes { MoveKind::Recycle } else { MoveKind::Draw };
+    self.move_counts[kind.index()] += 1;
+    self.moves_made += 1;
+    self.record(|game| MoveRecord {
+      m: Move::Draw,
+      cards: game.deck.visible_cards().to_vec(),
+      flipped: None,
+      recycled: kind == MoveKind::Recycle,
+      score: game.score,
+    });
+    debug_assert_eq!(self.validate(), Ok(()));
+    debug_assert_eq!(self.hash, self.full_hash());
+
+    if kind == MoveKind::Recycle {
+      DrawResult::Recycled
+    } else {
+      DrawResult::Dealt { visible: self.deck.visible_cards() }
+    }
+  }
+
+  pub fn move_cards(&mut self, source: MoveSource, target: MoveTarget) -> KlondikeResult<()> {
+    let hidden = self.hidden_count();
+    let source_len = source.pile().map_or(0, |i| self.piles[i].len());
+    let source_top = match source {
+      MoveSource::Deck => self.deck.top(),
+      MoveSource::Foundation(suit) => self.foundation(suit).top(),
+      MoveSource::Pile(i) | MoveSource::PileRun { pile: i, .. } => self.piles[i as usize].top(),
+    };
+    let before = self.move_hash(source, target);
+    let result = self.perform_move(source, target).map_err(|_| KlondikeErr::IllegalMove {
+      m: Move::Cards(source, target),
+      card: self.moved_card(source),
+    });
+    self.hash ^= before ^ self.move_hash(source, target);
+
+    if result.is_ok() {
+      let turned = (hidden - self.hidden_count()) as i32;
+      let points = match (self.rules.scoring, source, target) {
+        (Scoring::Standard, MoveSource::Foundation(_), MoveTarget::Pile(_)) => -15,
+        (Scoring::Standard, _, MoveTarget::Foundation) => 10,
+        (Scoring::Standard, MoveSource::Deck, MoveTarget::Pile(_)) => 5,
+        (Scoring::Standard, _, MoveTarget::Pile(_)) => 0,
+        (Scoring::Vegas, MoveSource::Foundation(_), MoveTarget::Pile(_)) => -5,
+        (Scoring::Vegas, _, MoveTarget::Foundation) => 5,
+        (Scoring::Vegas, _, MoveTarget::Pile(_)) => 0,
+      };
+
+      let turned_points = if self.rules.scoring == Scoring::Standard { 5 * turned } else { 0 };
+      self.add_score(points + turned_points);
+      if let Some(kind) = MoveKind::of(source, target) {
+        self.move_counts[kind.index()] += 1;
+      }
+      self.moves_made += 1;
+      self.hidden_flips += turned as usize;
+      if turned > 0 {
+        self.update_reveals();
+      }
+      self.record(|game| {
+        let (moved, flipped) = match source.pile() {
+          Some(i) => {
+            let pile = &game.piles[i];
+            (source_len - pile.len(), if turned > 0 { pile.top() } else { None })
+          },
+          None => (1, None),
+        };
+        let cards = match target {
+          MoveTarget::Pile(t) => {
+            let visible = game.piles[t as usize].visible_cards();
+            visible[visible.len() - moved..].to_vec()
+          },
+          MoveTarget::Foundation => source_top.into_iter().collect(),
+        };
+
+        MoveRecord {
+          m: Move::Cards(source, target),
+          cards: cards,
+          flipped: flipped,
+          recycled: false,
+          score: game.score,
+        }
+      });
+    }
+
+    debug_assert_eq!(self.validate(), Ok(()));
+    debug_assert_eq!(self.hash, self.full_hash());
+    return result;
+  }
+
+  /// Card a move from `source` takes, for errors: the top card of the deck
+  /// or a foundation, the lowest face up card of a pile, as the longest run
+  /// it could move starts there, or the card of a run.
+  fn moved_card(&self, source: MoveSource) -> Option<Card> {
+    match source {
+      MoveSource::Deck => self.deck.top(),
+      MoveSource::Foundation(suit) => self.foundation(suit).top(),
+      MoveSource::Pile(i) => self.piles[i as usize].visible_cards().first().cloned(),
+      MoveSource::PileRun { pile, index } => self.piles[pile as usize].visible_cards().get(index as usize).cloned(),
+    }
+  }
+
+  fn perform_move(&mut self, source: MoveSource, target: MoveTarget) -> KlondikeResult<()> {
+    match (source, target) {
+      (MoveSource::Deck, MoveTarget::Foundation) => {
+        let visible_card = {
+          match self.deck.top() {
+            Some(c) => c,
+            None => { return Err(KlondikeErr::InvalidMove); },
+          }
+        };
+
+        {
+          let foundation = self.foundation_mut(visible_card.suit());
+          if foundation.next_card() != Some(visible_card) {
+            return Err(KlondikeErr::InvalidMove);
+          }
+          foundation.push();
+        }
+
+        self.deck.pop();
+        Ok(())
+      },
+      (MoveSource::Deck, MoveTarget::Pile(pile_index)) => {
+        let pile_index = pile_index as usize;
+        assert!(pile_index < NUM_PILES);
+
+        let visible_card = {
+          match self.deck.top() {
+            Some(c) => c,
+            None => { return Err(KlondikeErr::InvalidMove); },
+          }
+        };
+
+        match self.piles[pile_index].push(visible_card) {
+          Ok(_) => {
+            self.deck.pop();
+            Ok(())
+          },
+          Err(_) => Err(KlondikeErr::InvalidMove),
+        }
+      },
+      (MoveSource::Foundation(_), MoveTarget::Foundation) => {
+        // Moves nothing, so is not a move
+        Err(KlondikeErr::InvalidMove)
+      },
+      (MoveSource::Foundation(suit), MoveTarget::Pile(pile_index)) => {
+        let pile_index = pile_index as usize;
+        assert!(pile_index < NUM_PILES);
+
+        let visible_card = {
+          match self.foundation(suit).top() {
+            Some(c) => c,
+            None => { return Err(KlondikeErr::InvalidMove); }
+          }
+        };
+
+        match self.piles[pile_index].push(visible_card) {
+          Ok(_) => {
+            self.foundation_mut(suit).pop();
+            Ok(())
+          },
+          Err(_) => Err(KlondikeErr::InvalidMove),
+        }
+      },
+      (MoveSource::Pile(source_pile_index), MoveTarget::Pile(target_pile_index)) => {
+        let source_pile_index = source_pile_index as usize;
+        assert!(source_pile_index < NUM_PILES);
+
+        let target_pile_index = target_pile_index as usize;
+        assert!(target_pile_index < NUM_PILES);
+
+        if source_pile_index == target_pile_index {
+          return Err(KlondikeErr::InvalidMove);
+        }
+
+        let piles_ptr = self.piles.as_mut_ptr();
+        let target_pile = &mut self.piles[target_pile_index];
+
+        // Seems like there should be a better way to do this, but can't get
+        // two mutable references to elements of `piles` in the same scope.
+        // This should be safe since we ensure the source and target indexes
+        // are different.
+        unsafe {
+          (*piles_ptr.offset(source_pile_index as isize)).move_to(target_pile)
+        }
+      },
+      (MoveSource::PileRun { pile, index }, MoveTarget::Pile(target_pile_index)) => {
+        let (source_pile_index, target_pile_index) = (pile as usize, target_pile_index as usize);
+        assert!(source_pile_index < NUM_PILES);
+        assert!(target_pile_index < NUM_PILES);
+
+        if source_pile_index == target_pile_index {
+          return Err(KlondikeErr::InvalidMove);
+        }
+
+        let mut target_pile = mem::replace(&mut self.piles[target_pile_index], Pile::new());
+        let result = self.piles[source_pile_index].move_from(index as usize, &mut target_pile);
+        self.piles[target_pile_index] = target_pile;
+        result.map_err(|_| KlondikeErr::InvalidMove)
+      },
+      (MoveSource::PileRun { pile, index }, MoveTarget::Foundation) => {
+        if index as usize + 1 != self.piles[pile as usize].visible_cards().len() {
+          return Err(KlondikeErr::InvalidMove);
+        }
+
+        self.perform_move(MoveSource::Pile(pile), MoveTarget::Foundation)
+      },
+      (MoveSource::Pile(pile_index), MoveTarget::Foundation) => {
+        let pile_index = pile_index as usize;
+        assert!(pile_index < NUM_PILES);
+
+        let visible_card = {
+          match self.piles[pile_index].top() {
+            Some(c) => c,
+            None => { return Err(KlondikeErr::InvalidMove); },
+          }
+        };
+
+        {
+          let foundation = self.foundation_mut(visible_card.suit());
+          if foundation.next_card() != Some(visible_card) {
+            return Err(KlondikeErr::InvalidMove);
+          }
+          foundation.push();
+        }
+
+        self.piles[pile_index].pop();
+        Ok(())
+      }
+    }
+  }
+
+  pub fn apply(&mut self, m: Move) -> KlondikeResult<()> {
+    match m {
+      Move::Draw => {
+        self.draw();
+        Ok(())
+      },
+      Move::Cards(source, target) => self.move_cards(source, target),
+    }
+  }
+
+  /// All moves that would currently succeed, in a fixed order: the draw,
+  /// then moves from the deck, the piles and the foundations.
+  ///
+  /// `Move::Draw` is included whenever the deck can be drawn from. A pile to
+  /// pile move is included when any face up card of the source fits the
+  /// target, moving that card and the cards above it, so partial runs are
+  /// covered. As the face up cards of a pile go down in rank, at most one of
+  /// them fits, and the same move as a `MoveSource::PileRun` is not listed
+  /// again. No-op moves are not included.
+  pub fn legal_moves(&self) -> Vec<Move> {
+    let mut moves = Vec::new();
+
+    if self.deck.can_draw() {
+      moves.push(Move::Draw);
+    }
+
+    if let Some(card) = self.deck.top() {
+      if self.foundation(card.suit()).can_push(card) {
+        moves.push(Move::Cards(MoveSource::Deck, MoveTarget::Foundation));
+      }
+
+      for (i, pile) in self.piles.iter().enumerate() {
+        if pile.can_push(card).is_ok() {
+          moves.push(Move::Cards(MoveSource::Deck, MoveTarget::Pile(i as u8)));
+        }
+      }
+    }
+
+    for (i, source) in self.piles.iter().enumerate() {
+      if let Some(card) = source.top() {
+        if self.foundation(card.suit()).can_push(card) {
+          moves.push(Move::Cards(MoveSource::Pile(i as u8), MoveTarget::Foundation));
+        }
+      }
+
+      for (j, target) in self.piles.iter().enumerate() {
+        if i != j && source.visible_cards().iter().any(|c| target.can_push(*c).is_ok()) {
+          moves.push(Move::Cards(MoveSource::Pile(i as u8), MoveTarget::Pile(j as u8)));
+        }
+      }
+    }
+
+    for foundation in self.foundations.iter() {
+      if let Some(card) = foundation.top() {
+        for (i, pile) in self.piles.iter().enumerate() {
+          if pile.can_push(card).is_ok() {
+            moves.push(Move::Cards(MoveSource::Foundation(foundation.suit()), MoveTarget::Pile(i as u8)));
+          }
+        }
+      }
+    }
+
+    return moves;
+  }
+
+  /// Copy of the game with the cards a player can not know rearranged.
+  ///
+  /// The hidden pile cards and the deck cards for which `is_known` returns
+  /// false are collected, passed to `shuffle`, and dealt back into the same
+  /// positions. Everything a player could have observed is unchanged. The
+  /// copy does not record moves.
+  pub fn determinize<K, F>(&self, is_known: K, mut shuffle: F) -> KlondikeSolitaireGame
+    where K: Fn(Card) -> bool, F: FnMut(&mut Vec<Card>) {
+    let mut game = self.clone();
+    game.history = None;
+    let mut unknown = Vec::new();
+
+    for pile in game.piles.iter() {
+      unknown.extend_from_slice(&pile.hidden_cards);
+    }
+
+    unknown.extend(game.deck.cards.iter().filter(|c| ! is_known(**c)));
+    shuffle(&mut unknown);
+
+    let mut dealt = unknown.into_iter();
+
+    for pile in game.piles.iter_mut() {
+      for card in pile.hidden_cards.iter_mut() {
+        *card = dealt.next().unwrap();
       }
     }
+
+    for card in game.deck.cards.iter_mut().filter(|c| ! is_known(**c)) {
+      *card = dealt.next().unwrap();
+    }
+
+    game.hash = game.full_hash();
+    return game;
   }
 }
 
-pub struct Foundation {
-  suit: Suit,
-  current_rank_index: Option<usize>,
+impl fmt::Display for KlondikeSolitaireGame {
+  /// The board as `render` lays it out.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.render())
+  }
+}
+
+impl SolitaireGame for KlondikeSolitaireGame {
+  fn is_clear(&self) -> bool {
+    KlondikeSolitaireGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    KlondikeSolitaireGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    KlondikeSolitaireGame::moves_made(self)
+  }
+}
+
+/// Deal `cards` in order to `piles` the klondike way: `i + 1` cards to pile
+/// `i`, all but the last face down. Returns the number of cards dealt, the
+/// rest are for the deck.
+pub fn deal_piles(cards: &[Card], piles: &mut [Pile]) -> usize {
+  let mut dealt = 0;
+
+  for (i, pile) in piles.iter_mut().enumerate() {
+    pile.reset(&cards[dealt..dealt + i + 1]);
+    dealt += i + 1;
+  }
+
+  dealt
+}
+
+/// What a draw from a `Deck` did.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DrawResult<'a> {
+  /// Cards were turned face up from the stock.
+  Dealt { visible: &'a [Card] },
+  /// The stock was empty, so the waste was turned back over into it, which
+  /// starts a new pass.
+  Recycled,
+  /// Nothing happened: the deck has no cards or no passes left.
+  Empty,
+}
+
+#[derive(Clone)]
+pub struct Deck {
+  cards: Vec<Card>,
+  max_len: usize,
+  thoughtful: bool,
+  draw_count: usize,
+  visible_index: usize,
+  visible_count: usize,
+  pass_limit: Option<usize>,
+  passes: usize,
+}
+
+impl Deck {
+  pub fn new(draw_count: u8) -> Deck {
+    Deck::with_max_len(draw_count, MAX_DECK_SIZE)
+  }
+
+  /// Deck for a layout that leaves up to `max_len` cards after the deal,
+  /// instead of the 24 of klondike.
+  pub fn with_max_len(draw_count: u8, max_len: usize) -> Deck {
+    assert!(draw_count > 0 && draw_count as usize <= max_len);
+
+    Deck {
+      cards: Vec::with_capacity(max_len),
+      max_len: max_len,
+      thoughtful: false,
+      draw_count: draw_count as usize,
+      visible_index: 0,
+      visible_count: 0,
+      pass_limit: None,
+      passes: 1,
+    }
+  }
+
+  /// Deck in the middle of a game, see `try_from`.
+  ///
+  /// Panics if the deck is not valid.
+  pub fn from(draw_count: u8, waste: &[Card], visible: &[Card], remaining: &[Card]) -> Deck {
+    match Deck::try_from(draw_count, waste, visible, remaining) {
+      Ok(deck) => deck,
+      Err(e) => panic!("Invalid deck: {}", e),
+    }
+  }
+
+  /// Deck with the `waste` cards already turned, the `visible` cards on top
+  /// of them and the `remaining` cards still to draw, each in the order they
+  /// are drawn. Fails if the draw count or number of cards are not possible
+  /// after a klondike deal, or a card is repeated or not of the standard
+  /// ranks.
+  pub fn try_from(draw_count: u8, waste: &[Card], visible: &[Card], remaining: &[Card]) -> KlondikeResult<Deck> {
+    let deck_size = waste.len() + visible.len() + remaining.len();
+    let mut cards = Vec::with_capacity(deck_size);
+
+    if draw_count == 0 || draw_count as usize > MAX_DECK_SIZE {
+      return Err(KlondikeErr::DrawCount(draw_count as usize));
+    }
+
+    if visible.len() > draw_count as usize {
+      return Err(KlondikeErr::TooManyVisible(visible.len()));
+    }
+
+    // deck can not exceed 24 cards (52 - initial pile contents)
+    if deck_size > MAX_DECK_SIZE {
+      return Err(KlondikeErr::DeckSize(deck_size));
+    }
+
+    // cards are all distinct and are standard ranks
+    {
+      let mut set = HashSet::new();
+      for card in waste.iter().chain(visible).chain(remaining) {
+        if !set.insert(card) {
+          return Err(KlondikeErr::DuplicateCard(*card));
+        }
+        rank_index(card.rank()).map_err(|_| KlondikeErr::InvalidRank)?;
+        cards.push(*card);
+      }
+    }
+
+    Ok(Deck {
+      cards: cards,
+      max_len: MAX_DECK_SIZE,
+      thoughtful: false,
+      draw_count: draw_count as usize,
+      visible_index: waste.len(),
+      visible_count: visible.len(),
+      pass_limit: None,
+      passes: 1,
+    })
+  }
+
+  pub fn reset(&mut self, cards: &[Card]) {
+    assert!(cards.len() <= self.max_len);
+
+    self.cards.clear();
+    self.visible_index = 0;
+    self.visible_count = 0;
+    self.passes = 1;
+    self.cards.extend_from_slice(cards);
+  }
+
+  /// Number of times the deck may be dealt through, `None` for no limit.
+  pub fn pass_limit(&self) -> Option<usize> {
+    self.pass_limit
+  }
+
+  pub fn set_pass_limit(&mut self, pass_limit: Option<usize>) {
+    self.pass_limit = pass_limit;
+  }
+
+  /// Current pass through the deck, starting at 1.
+  pub fn passes(&self) -> usize {
+    self.passes
+  }
+
+  /// Passes through the deck left after the current one, `None` for no
+  /// limit.
+  pub fn passes_remaining(&self) -> Option<usize> {
+    self.pass_limit.map(|limit| limit.saturating_sub(self.passes))
+  }
+
+  /// True if `draw` would change the deck: there are cards left to deal, or
+  /// the waste may be turned over for another pass.
+  pub fn can_draw(&self) -> bool {
+    if self.cards.is_empty() {
+      return false;
+    }
+
+    self.visible_index + self.visible_count < self.cards.len()
+      || self.pass_limit.map_or(true, |limit| self.passes < limit)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.cards.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.cards.len()
+  }
+
+  pub fn draw_count(&self) -> u8 {
+    self.draw_count as u8
+  }
+
+  /// The deck as a JSON object, the waste bottom card first and the
+  /// remaining cards next card first:
+  ///
+  /// ```text
+  /// {"draw_count":3,"pass_limit":null,"passes":1,"waste":["5D"],"visible":["9C","2S"],"remaining":["4H","JS"]}
+  /// ```
+  pub fn to_json(&self) -> Value {
+    Value::Object(vec![
+      (String::from("draw_count"), Value::Number(self.draw_count as f64)),
+      (String::from("pass_limit"), optional_number(self.pass_limit)),
+      (String::from("passes"), Value::Number(self.passes as f64)),
+      (String::from("waste"), cards_json(self.waste_cards())),
+      (String::from("visible"), cards_json(self.visible_cards())),
+      (String::from("remaining"), cards_json(self.remaining_cards())),
+    ])
+  }
+
+  /// Hash of the cards in the deck, by position.
+  fn cards_hash(&self) -> u64 {
+    self.cards.iter().enumerate().fold(0, |h, (i, c)| h ^ card_key(*c, ZONE_DECK, i))
+  }
+
+  /// Hash of how far the deck has been dealt, and of the pass when passes
+  /// are limited.
+  fn state_hash(&self) -> u64 {
+    let passes = if self.pass_limit.is_some() { self.passes as u64 } else { 0 };
+    zobrist_key(ZONE_DECK_STATE << 40 | passes << 24 | (self.visible_index as u64) << 8 | self.visible_count as u64)
+  }
+
+  /// Deck written by `to_json`. Whether its cards fit the rest of a game is
+  /// checked by `KlondikeSolitaireGame::validate`.
+  pub fn from_json(json: &Value) -> Result<Deck, String> {
+    let waste = cards_from_json(json, "waste")?;
+    let visible = cards_from_json(json, "visible")?;
+    let passes = count(json, "passes")?;
+    if passes == 0 {
+      return Err(String::from("passes start at 1"));
+    }
+
+    let mut cards = waste.clone();
+    cards.extend(&visible);
+    cards.extend(cards_from_json(json, "remaining")?);
+
+    Ok(Deck {
+      cards: cards,
+      max_len: MAX_DECK_SIZE,
+      thoughtful: false,
+      draw_count: count(json, "draw_count")?,
+      visible_index: waste.len(),
+      visible_count: visible.len(),
+      pass_limit: optional_count(json, "pass_limit")?,
+      passes: passes,
+    })
+  }
+
+  pub fn top(&self) -> Option<Card> {
+    match self.visible_count {
+      0 => None,
+      count => Some(self.cards[self.visible_index+count-1]),
+    }
+  }
+
+  pub fn visible_cards(&self) -> &[Card] {
+    &self.cards[self.visible_index..self.visible_index+self.visible_count]
+  }
+
+  pub fn waste_cards(&self) -> &[Card] {
+    &self.cards[..self.visible_index]
+  }
+
+  pub fn remaining_cards(&self) -> &[Card] {
+    let index = cmp::min(self.visible_index + self.visible_count, self.cards.len());
+    &self.cards[index..]
+  }
+
+  /// Every deck card in the order they are drawn, the waste first, or
+  /// `None` unless the game is thoughtful.
+  pub fn all_cards(&self) -> Option<&[Card]> {
+    if self.thoughtful { Some(&self.cards) } else { None }
+  }
+
+  pub fn pop(&mut self) -> Option<Card> {
+    match self.visible_count {
+      0 => None,
+      _ => {
+        self.visible_count -= 1;
+        Some(self.cards.remove(self.visible_index + self.visible_count))
+      }
+    }
+  }
+
+  /// Turn the next cards of the stock face up, or the waste back over once
+  /// the stock is empty.
+  pub fn draw(&mut self) -> DrawResult<'_> {
+    if ! self.can_draw() {
+      return DrawResult::Empty;
+    }
+
+    self.visible_index += self.visible_count;
+
+    if self.visible_index >= self.cards.len() {
+      self.visible_index = 0;
+      self.visible_count = 0;
+      self.passes += 1;
+      DrawResult::Recycled
+    } else {
+      self.visible_count = cmp::min(self.draw_count, self.cards.len() - self.visible_index);
+      DrawResult::Dealt { visible: self.visible_cards() }
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct Pile {
+  visible_cards: Vec<Card>,
+  hidden_cards: Vec<Card>,
+  thoughtful: bool,
 }
 
-impl Foundation {
-  pub fn new(suit: Suit) -> Foundation {
-    Foundation {
-      suit: suit,
-      current_rank_index: None,
+impl Pile {
+  pub fn new() -> Pile {
+    Pile {
+      visible_cards: Vec::new(),
+      hidden_cards: Vec::with_capacity(6),
+      thoughtful: false,
+    }
+  }
+
+  /// Pile in the middle of a game, see `try_from`.
+  ///
+  /// Panics if the pile is not valid.
+  pub fn from(hidden: &[Card], visible: &[Card]) -> Pile {
+    match Pile::try_from(hidden, visible) {
+      Ok(pile) => pile,
+      Err(e) => panic!("Invalid pile: {}", e),
+    }
+  }
+
+  /// Pile of the face down `hidden` cards under the face up `visible` ones,
+  /// bottom card first. Fails if hidden cards are not covered by a visible
+  /// one, there are more than 6 of them, a card is repeated or not of the
+  /// standard ranks, or the visible cards are out of sequence.
+  pub fn try_from(hidden: &[Card], visible: &[Card]) -> KlondikeResult<Pile> {
+    if !hidden.is_empty() && visible.is_empty() {
+      return Err(KlondikeErr::HiddenWithoutVisible);
+    }
+
+    // hidden has <= 6 cards (the right-most pile)
+    if hidden.len() > 6 {
+      return Err(KlondikeErr::TooManyHidden(hidden.len()));
+    }
+
+    // cards are distinct
+    // card ranks only from the standard deck
+    // `visible` is in valid order (e.g. color and rank)
+    {
+      let mut set = HashSet::new();
+
+      let mut viter = visible.iter().peekable();
+      while let Some(card) = viter.next() {
+        if !set.insert(card) {
+          return Err(KlondikeErr::DuplicateCard(*card));
+        }
+        let card_rank_index = rank_index(card.rank()).map_err(|_| KlondikeErr::InvalidRank)?;
+
+        // check next card color and rank
+        if let Some(next_card) = viter.peek() {
+          let next_rank_index = rank_index(next_card.rank()).map_err(|_| KlondikeErr::InvalidRank)?;
+          if card.color().other() != next_card.color() || card_rank_index != next_rank_index + 1 {
+            return Err(KlondikeErr::PileOrder { card: *card, next: **next_card });
+          }
+        }
+      }
+
+      for card in hidden.iter() {
+        if !set.insert(card) {
+          return Err(KlondikeErr::DuplicateCard(*card));
+        }
+        rank_index(card.rank()).map_err(|_| KlondikeErr::InvalidRank)?;
+      }
+    }
+
+    Ok(Pile {
+      visible_cards: visible.iter().cloned().collect(),
+      hidden_cards: hidden.iter().cloned().collect(),
+      thoughtful: false,
+    })
+  }
+
+  /// Pile of any cards, for games like Yukon where the visible cards are not
+  /// dealt in sequence. A hidden card is turned up if `visible` is empty.
+  pub fn with_cards(hidden: Vec<Card>, visible: Vec<Card>) -> Pile {
+    let mut pile = Pile {
+      visible_cards: visible,
+      hidden_cards: hidden,
+      thoughtful: false,
+    };
+    pile.check_visible();
+    pile
+  }
+
+  pub fn top(&self) -> Option<Card> {
+    self.visible_cards.last().map(|c| *c)
+  }
+
+  pub fn len(&self) -> usize {
+    self.visible_cards.len() + self.hidden_cards.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.visible_cards.is_empty()
+  }
+
+  pub fn visible_cards(&self) -> &[Card] {
+    &self.visible_cards[..]
+  }
+
+  pub fn hidden_cards(&self) -> &[Card] {
+    &self.hidden_cards[..]
+  }
+
+  /// The pile as a JSON object, each list bottom card first:
+  ///
+  /// ```text
+  /// {"hidden":["2H","9S"],"visible":["KD","QS"]}
+  /// ```
+  pub fn to_json(&self) -> Value {
+    Value::Object(vec![
+      (String::from("hidden"), cards_json(&self.hidden_cards)),
+      (String::from("visible"), cards_json(&self.visible_cards)),
+    ])
+  }
+
+  /// Pile written by `to_json`. The order of its cards is checked by
+  /// `KlondikeSolitaireGame::validate`.
+  pub fn from_json(json: &Value) -> Result<Pile, String> {
+    Ok(Pile {
+      visible_cards: cards_from_json(json, "visible")?,
+      hidden_cards: cards_from_json(json, "hidden")?,
+      thoughtful: false,
+    })
+  }
+
+  /// The face down cards from the bottom up, or `None` unless the game is
+  /// thoughtful. Strategies look at hidden cards through this.
+  pub fn peek_hidden(&self) -> Option<&[Card]> {
+    if self.thoughtful { Some(&self.hidden_cards) } else { None }
+  }
+
+  pub fn reset(&mut self, cards: &[Card]) {
+    assert!(cards.len() > 0);
+
+    self.hidden_cards.clear();
+
+    if cards.len() > 1 {
+      self.hidden_cards.extend_from_slice(&cards[0..cards.len()-1]);
+    }
+
+    self.visible_cards.clear();
+
+    if let Some(c) = cards.last() {
+      self.visible_cards.push(*c);
+    }
+  }
+
+  pub fn next_card(&self) -> Option<(Option<Color>, Rank)> {
+    match self.visible_cards.last() {
+      Some(card) => match rank_index(card.rank()).unwrap() {
+        0 => None,
+        i => Some((Some(card.color().other()), RANKS[i-1])),
+      },
+      None => Some((None, Rank::King)),
+    }
+  }
+
+  pub fn can_push(&self, card: Card) -> KlondikeResult<()> {
+    match self.next_card() {
+      Some((Some(color), rank)) => {
+        if card.color() == color && card.rank() == rank {
+          Ok(())
+        } else {
+          Err(KlondikeErr::InvalidCard(card))
+        }
+      },
+      Some((None, rank)) => {
+        if card.rank() == rank {
+          Ok(())
+        } else {
+          Err(KlondikeErr::InvalidCard(card))
+        }
+      },
+      None => Err(KlondikeErr::Capacity),
+    }
+  }
+
+  pub fn push(&mut self, card: Card) -> KlondikeResult<()> {
+    let result = self.can_push(card);
+
+    if result.is_ok() {
+      self.visible_cards.push(card);
+    }
+
+    return result;
+  }
+
+  /// Put `card` face up on top whatever the building rules, as a stock
+  /// that deals a row does.
+  pub fn deal(&mut self, card: Card) {
+    self.visible_cards.push(card);
+  }
+
+  pub fn pop(&mut self) -> Option<Card> {
+    match self.visible_cards.pop() {
+      Some(card) => {
+        self.check_visible();
+        Some(card)
+      },
+      None => None,
+    }
+  }
+
+  pub fn move_to(&mut self, target: &mut Pile) -> KlondikeResult<()> {
+    match self.visible_cards.iter().position(|c| target.can_push(*c).is_ok()) {
+      Some(index) => self.move_from(index, target),
+      None => Err(KlondikeErr::InvalidMove),
+    }
+  }
+
+  /// Move visible card `index` and every card on top of it to `target`, the
+  /// moved cards in any order.
+  pub fn move_from(&mut self, index: usize, target: &mut Pile) -> KlondikeResult<()> {
+    match self.visible_cards.get(index) {
+      Some(card) => target.can_push(*card)?,
+      None => return Err(KlondikeErr::InvalidMove),
+    }
+
+    target.visible_cards.extend_from_slice(&self.visible_cards[index..]);
+
+    self.visible_cards.truncate(index);
+    self.check_visible();
+
+    Ok(())
+  }
+
+  fn check_visible(&mut self) {
+    if self.visible_cards.is_empty() {
+      if let Some(next_card) = self.hidden_cards.pop() {
+        self.visible_cards.push(next_card);
+      }
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct Foundation {
+  suit: Suit,
+  current_rank_index: Option<usize>,
+}
+
+impl Foundation {
+  pub fn new(suit: Suit) -> Foundation {
+    Foundation {
+      suit: suit,
+      current_rank_index: None,
+    }
+  }
+
+  pub fn from(suit: Suit, rank: Option<Rank>) -> Foundation {
+    let mut f = Foundation::new(suit);
+    f.current_rank_index = match rank {
+      Some(rank) => Some(rank_index(rank).unwrap()),
+      None => None,
+    };
+    return f;
+  }
+
+  pub fn new_full(suit: Suit) -> Foundation {
+    Foundation::from(suit, Some(Rank::King))
+  }
+
+  /// Empty foundations for a game with `decks` decks, one per suit and deck
+  /// in the klondike order. With more than one deck there are several
+  /// foundations of each suit and a card can go to any of them.
+  pub fn for_decks(decks: usize) -> Vec<Foundation> {
+    let suits = [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs];
+    (0..decks).flat_map(|_| suits.iter().map(|s| Foundation::new(*s))).collect()
+  }
+
+  pub fn top(&self) -> Option<Card> {
+    match self.current_rank_index {
+      Some(i) => Some(Card::new(self.suit, RANKS[i])),
+      None => None,
+    }
+  }
+
+  pub fn cards(&self) -> Vec<Card> {
+    let mut cards = Vec::new();
+
+    if let Some(rank_index) = self.current_rank_index {
+      for index in 0..rank_index+1 {
+        cards.push(Card::new(self.suit, RANKS[index]))
+      }
+    }
+
+    return cards;
+  }
+
+  /// Number of cards on the foundation.
+  pub fn len(&self) -> usize {
+    match self.current_rank_index {
+      Some(i) => i + 1,
+      None => 0,
+    }
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.current_rank_index == Some(RANKS.len() - 1)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.current_rank_index.is_none()
+  }
+
+  pub fn suit(&self) -> Suit {
+    self.suit
+  }
+
+  /// The foundation as a JSON object, `top` `null` while it is empty:
+  ///
+  /// ```text
+  /// {"suit":"H","top":"2H"}
+  /// ```
+  pub fn to_json(&self) -> Value {
+    Value::Object(vec![
+      (String::from("suit"), Value::String(self.suit.to_string())),
+      (String::from("top"), self.top().map_or(Value::Null, |c| Value::String(c.to_string()))),
+    ])
+  }
+
+  /// Foundation written by `to_json`.
+  pub fn from_json(json: &Value) -> Result<Foundation, String> {
+    let suit = member(json, "suit")?.as_str().ok_or_else(|| String::from("suit must be a string"))?.parse::<Suit>()?;
+    let top = match *member(json, "top")? {
+      Value::Null => None,
+      ref card => Some(card_from_json(card)?),
+    };
+
+    match top {
+      Some(card) if card.suit() != suit => Err(format!("{} on the {} foundation", card, suit)),
+      _ => Ok(Foundation::from(suit, top.map(|c| c.rank()))),
+    }
+  }
+
+  pub fn next_rank(&self) -> Option<Rank> {
+    match self.current_rank_index {
+      Some(i) if i == RANKS.len() - 1 => None,
+      Some(i) => Some(RANKS[i+1]),
+      None => Some(RANKS[0]),
+    }
+  }
+
+  pub fn next_card(&self) -> Option<Card> {
+    match self.next_rank() {
+      Some(r) => Some(Card::new(self.suit, r)),
+      None => None,
+    }
+  }
+
+  pub fn can_push(&self, card: Card) -> bool {
+    self.suit == card.suit() && self.next_rank() == Some(card.rank())
+  }
+
+  pub fn push(&mut self) -> Option<Card> {
+    match self.current_rank_index {
+      Some(i) if i == RANKS.len() - 1 => None,
+      Some(i) => {
+        self.current_rank_index = Some(i+1);
+        Some(Card::new(self.suit, RANKS[i]))
+      },
+      None => {
+        self.current_rank_index = Some(0);
+        Some(Card::new(self.suit, RANKS[0]))
+      }
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.current_rank_index = None;
+  }
+
+  pub fn pop(&mut self) -> Option<Card> {
+    match self.current_rank_index {
+      Some(0) => {
+        self.current_rank_index = None;
+        Some(Card::new(self.suit, RANKS[0]))
+      },
+      Some(i) => {
+        self.current_rank_index = Some(i-1);
+        Some(Card::new(self.suit, RANKS[i-1]))
+      },
+      None => None
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  pub use super::*;
+
+  macro_rules! card {
+    ($suit:expr, $rank:expr) => (Card::new($suit, $rank));
+  }
+
+  pub fn test_cards(name: &str, expected: &[Card], actual: &[Card]) {
+    assert!(actual.len() == expected.len(), "{}: {} != {}", name, expected.len(), actual.len());
+    for i in 0..expected.len() {
+      assert!(expected[i] == actual[i], "{}[{}]: {:?} != {:?}", name, i, expected[i], actual[i]);
+    }
+  }
+
+  pub fn test_deck(deck: &Deck, visible: &[Card], waste: &[Card], remaining: &[Card]) {
+    test_cards("visible", visible, deck.visible_cards());
+    test_cards("waste", waste, deck.waste_cards());
+    test_cards("remaining", remaining, deck.remaining_cards());
+  }
+
+  pub fn test_pile(name: &str, pile: &Pile, hidden: &[Card], visible: &[Card]) {
+    test_cards(&format!("{}.hidden", name), hidden, pile.hidden_cards());
+    test_cards(&format!("{}.visible", name), visible, pile.visible_cards());
+  }
+
+  mod game {
+    use super::*;
+    use cards::french::{Suit, new_standard_deck};
+    use cards::seeded_source;
+
+    #[test]
+    fn new() {
+      let game = KlondikeSolitaireGame::new(3);
+
+      // Can't test the specific ordering of cards since the cards should be
+      // shuffled randomly.
+
+      assert_eq!(game.deck().draw_count(), 3);
+      assert_eq!(game.deck().len(), 24);
+
+      assert!(game.foundation(Suit::Clubs).is_empty());
+      assert_eq!(game.foundation(Suit::Clubs).suit(), Suit::Clubs);
+      assert!(game.foundation(Suit::Spades).is_empty());
+      assert_eq!(game.foundation(Suit::Spades).suit(), Suit::Spades);
+      assert!(game.foundation(Suit::Diamonds).is_empty());
+      assert_eq!(game.foundation(Suit::Diamonds).suit(), Suit::Diamonds);
+      assert!(game.foundation(Suit::Hearts).is_empty());
+      assert_eq!(game.foundation(Suit::Hearts).suit(), Suit::Hearts);
+
+      assert_eq!(game.pile(0).len(), 1);
+      assert_eq!(game.pile(1).len(), 2);
+      assert_eq!(game.pile(2).len(), 3);
+      assert_eq!(game.pile(3).len(), 4);
+      assert_eq!(game.pile(4).len(), 5);
+      assert_eq!(game.pile(5).len(), 6);
+      assert_eq!(game.pile(6).len(), 7);
+    }
+
+    #[test]
+    fn from_new() {
+      let cards = new_standard_deck();
+      let game = KlondikeSolitaireGame::from(
+        Deck::from(3, &[], &[], &cards[28..]),
+        [
+          Foundation::new(Suit::Clubs),
+          Foundation::new(Suit::Hearts),
+          Foundation::new(Suit::Spades),
+          Foundation::new(Suit::Diamonds),
+        ], [
+          Pile::from(&cards[0..0], &cards[0..1]),
+          Pile::from(&cards[1..2], &cards[2..3]),
+          Pile::from(&cards[3..5], &cards[5..6]),
+          Pile::from(&cards[6..9], &cards[9..10]),
+          Pile::from(&cards[10..14], &cards[14..15]),
+          Pile::from(&cards[15..20], &cards[20..21]),
+          Pile::from(&cards[21..27], &cards[27..28]),
+        ]
+      );
+
+      assert_eq!(game.deck().draw_count(), 3);
+      test_deck(game.deck(), &[], &[], &cards[28..]);
+
+      assert!(game.foundation(Suit::Clubs).is_empty());
+      assert_eq!(game.foundation(Suit::Clubs).suit(), Suit::Clubs);
+      assert!(game.foundation(Suit::Spades).is_empty());
+      assert_eq!(game.foundation(Suit::Spades).suit(), Suit::Spades);
+      assert!(game.foundation(Suit::Diamonds).is_empty());
+      assert_eq!(game.foundation(Suit::Diamonds).suit(), Suit::Diamonds);
+      assert!(game.foundation(Suit::Hearts).is_empty());
+      assert_eq!(game.foundation(Suit::Hearts).suit(), Suit::Hearts);
+
+      test_pile("game.piles[0]", game.pile(0), &[], &cards[0..1]);
+      test_pile("game.piles[1]", game.pile(1), &cards[1..2], &cards[2..3]);
+      test_pile("game.piles[2]", game.pile(2), &cards[3..5], &cards[5..6]);
+      test_pile("game.piles[3]", game.pile(3), &cards[6..9], &cards[9..10]);
+      test_pile("game.piles[4]", game.pile(4), &cards[10..14], &cards[14..15]);
+      test_pile("game.piles[5]", game.pile(5), &cards[15..20], &cards[20..21]);
+      test_pile("game.piles[6]", game.pile(6), &cards[21..27], &cards[27..28]);
+
+      assert!(! game.is_clear());
+    }
+
+    #[test]
+    fn from_clear() {
+      let game = KlondikeSolitaireGame::from(
+        Deck::from(3, &[], &[], &[]),
+        [
+          Foundation::new_full(Suit::Clubs),
+          Foundation::new_full(Suit::Hearts),
+          Foundation::new_full(Suit::Spades),
+          Foundation::new_full(Suit::Diamonds),
+        ], [
+          Pile::new(),
+          Pile::new(),
+          Pile::new(),
+          Pile::new(),
+          Pile::new(),
+          Pile::new(),
+          Pile::new(),
+        ]
+      );
+
+      assert_eq!(game.deck().draw_count(), 3);
+      test_deck(game.deck(), &[], &[], &[]);
+
+      assert!(game.foundation(Suit::Clubs).is_full());
+      assert!(game.foundation(Suit::Spades).is_full());
+      assert!(game.foundation(Suit::Diamonds).is_full());
+      assert!(game.foundation(Suit::Hearts).is_full());
+
+      assert!(game.pile(0).is_empty());
+      assert!(game.pile(1).is_empty());
+      assert!(game.pile(2).is_empty());
+      assert!(game.pile(3).is_empty());
+      assert!(game.pile(4).is_empty());
+      assert!(game.pile(5).is_empty());
+      assert!(game.pile(6).is_empty());
+
+      assert!(game.is_clear());
+    }
+
+    #[test]
+    fn try_from_errors() {
+      let cards = new_standard_deck();
+      let foundations = || [
+        Foundation::new(Suit::Clubs),
+        Foundation::new(Suit::Hearts),
+        Foundation::new(Suit::Spades),
+        Foundation::new(Suit::Diamonds),
+      ];
+
+      // The last card of the deck is missing
+      let result = KlondikeSolitaireGame::try_from(
+        Deck::from(3, &[], &[], &cards[27..51]),
+        foundations(), [
+          Pile::from(&cards[0..0], &cards[0..1]),
+          Pile::from(&cards[1..2], &cards[2..3]),
+          Pile::from(&cards[3..5], &cards[5..6]),
+          Pile::from(&cards[6..9], &cards[9..10]),
+          Pile::from(&cards[10..14], &cards[14..15]),
+          Pile::from(&cards[15..20], &cards[20..21]),
+          Pile::from(&cards[21..26], &cards[26..27]),
+        ]);
+      assert_eq!(result.err(), Some(ValidationError::MissingCard(cards[51])));
+
+      let result = KlondikeSolitaireGame::try_from(
+        Deck::from(3, &[], &[], &cards[28..]),
+        foundations(), [
+          Pile::from(&cards[0..0], &cards[0..1]),
+          Pile::from(&cards[1..2], &cards[2..3]),
+          Pile::from(&cards[3..5], &cards[5..6]),
+          Pile::from(&cards[6..10], &cards[10..11]),
+          Pile::from(&cards[11..14], &cards[14..15]),
+          Pile::from(&cards[15..20], &cards[20..21]),
+          Pile::from(&cards[21..27], &cards[27..28]),
+        ]);
+      assert_eq!(result.err(), Some(ValidationError::PileHiddenOverflow { pile: 3, hidden: 4 }));
+
+      let result = KlondikeSolitaireGame::try_from(
+        Deck::from(3, &[], &[], &cards[28..]),
+        foundations(), [
+          Pile::from(&cards[0..0], &cards[0..1]),
+          Pile::from(&cards[1..2], &cards[2..3]),
+          Pile::from(&cards[3..5], &cards[5..6]),
+          Pile::from(&cards[6..9], &cards[9..10]),
+          Pile::from(&cards[10..14], &cards[14..15]),
+          Pile::from(&cards[15..20], &cards[20..21]),
+          Pile::from(&cards[21..26], &cards[1..2]),
+        ]);
+      assert_eq!(result.err(), Some(ValidationError::DuplicateCard(cards[1])));
+    }
+
+    /// Game from the cards of a standard deck dealt in order, the piles
+    /// given by their hidden and visible ranges, as read from outside.
+    fn game_from_parts(piles: &[(usize, usize, usize)]) -> KlondikeResult<KlondikeSolitaireGame> {
+      let cards = new_standard_deck();
+      let mut built = Vec::new();
+      for &(start, hidden, visible) in piles {
+        built.push(Pile::try_from(&cards[start..start + hidden], &cards[start + hidden..start + hidden + visible])?);
+      }
+      let p = built;
+
+      Ok(KlondikeSolitaireGame::try_from(
+        Deck::try_from(3, &[], &[], &cards[28..])?,
+        [
+          Foundation::new(Suit::Clubs),
+          Foundation::new(Suit::Hearts),
+          Foundation::new(Suit::Spades),
+          Foundation::new(Suit::Diamonds),
+        ],
+        [p[0].clone(), p[1].clone(), p[2].clone(), p[3].clone(), p[4].clone(), p[5].clone(), p[6].clone()],
+      )?)
+    }
+
+    #[test]
+    fn try_from_parts() {
+      let layout = [(0, 0, 1), (1, 1, 1), (3, 2, 1), (6, 3, 1), (10, 4, 1), (15, 5, 1), (21, 6, 1)];
+      assert!(game_from_parts(&layout).is_ok());
+
+      let mut layout = layout;
+      layout[3] = (6, 4, 1);
+      layout[4] = (11, 3, 1);
+      assert_eq!(game_from_parts(&layout).err(), Some(KlondikeErr::Invalid(ValidationError::PileHiddenOverflow { pile: 3, hidden: 4 })));
+      let error = game_from_parts(&layout).err().unwrap();
+      assert_eq!(error.to_string(), "pile 3 has 4 hidden cards, max 3");
+      assert_eq!(error::Error::source(&error).map(|e| e.to_string()), Some(error.to_string()));
+
+      layout[6] = (21, 6, 0);
+      assert_eq!(game_from_parts(&layout).err(), Some(KlondikeErr::HiddenWithoutVisible));
+    }
+
+    #[test]
+    #[should_panic(expected = "pile 3 has 4 hidden cards")]
+    fn from_error_hidden_overflow() {
+      let cards = new_standard_deck();
+      KlondikeSolitaireGame::from(
+        Deck::from(3, &[], &[], &cards[28..]),
+        [
+          Foundation::new(Suit::Clubs),
+          Foundation::new(Suit::Hearts),
+          Foundation::new(Suit::Spades),
+          Foundation::new(Suit::Diamonds),
+        ], [
+          Pile::from(&cards[0..0], &cards[0..1]),
+          Pile::from(&cards[1..2], &cards[2..3]),
+          Pile::from(&cards[3..5], &cards[5..6]),
+          Pile::from(&cards[6..10], &cards[10..11]),
+          Pile::from(&cards[11..14], &cards[14..15]),
+          Pile::from(&cards[15..20], &cards[20..21]),
+          Pile::from(&cards[21..27], &cards[27..28]),
+        ]);
+    }
+
+    fn unsorted_game() -> KlondikeSolitaireGame {
+      let cards = new_standard_deck();
+      KlondikeSolitaireGame::from(
+        Deck::from(3, &[], &[], &cards[28..]),
+        [
+          Foundation::new(Suit::Clubs),
+          Foundation::new(Suit::Hearts),
+          Foundation::new(Suit::Spades),
+          Foundation::new(Suit::Diamonds),
+        ], [
+          Pile::from(&cards[0..0], &cards[0..1]),
+          Pile::from(&cards[1..2], &cards[2..3]),
+          Pile::from(&cards[3..5], &cards[5..6]),
+          Pile::from(&cards[6..9], &cards[9..10]),
+          Pile::from(&cards[10..14], &cards[14..15]),
+          Pile::from(&cards[15..20], &cards[20..21]),
+          Pile::from(&cards[21..27], &cards[27..28]),
+        ]
+      )
+    }
+
+    #[test]
+    fn legal_moves() {
+      let mut game = unsorted_game();
+
+      assert_eq!(game.legal_moves(), vec![
+        Move::Draw,
+        Move::Cards(MoveSource::Pile(0), MoveTarget::Foundation),
+        Move::Cards(MoveSource::Pile(0), MoveTarget::Pile(6)),
+        Move::Cards(MoveSource::Pile(6), MoveTarget::Pile(1)),
+      ]);
+
+      for m in game.legal_moves() {
+        assert!(game.clone().apply(m).is_ok(), "{:?}", m);
+      }
+
+      game.draw();
+      assert!(game.legal_moves().contains(&Move::Draw));
+    }
+
+    #[test]
+    fn draw_results() {
+      let mut game = unsorted_game();
+      let deck = game.deck().remaining_cards().to_vec();
+
+      for turn in deck.chunks(3) {
+        assert_eq!(game.draw(), DrawResult::Dealt { visible: turn });
+      }
+      assert_eq!(game.draw(), DrawResult::Recycled);
+      assert_eq!(game.deck().passes(), 2);
+      assert_eq!(game.draw(), DrawResult::Dealt { visible: &deck[..3] });
+    }
+
+    #[test]
+    fn draw_when_empty_is_not_a_move() {
+      let mut game = unsorted_game();
+      game.deck.set_pass_limit(Some(1));
+      game.hash = game.full_hash();
+      game.record_moves();
+
+      while game.draw() != DrawResult::Empty {}
+      let (moves, draws, hash) = (game.moves_made(), game.draws_made(), game.state_hash());
+
+      assert_eq!(game.draw(), DrawResult::Empty);
+      assert_eq!(game.moves_made(), moves);
+      assert_eq!(game.draws_made(), draws);
+      assert_eq!(game.history().unwrap().len(), moves);
+      assert_eq!(game.state_hash(), hash);
+    }
+
+    #[test]
+    fn no_op_moves() {
+      let mut game = unsorted_game();
+      game.record_moves();
+      let ace = game.pile(0).top().unwrap();
+      game.apply(Move::Cards(MoveSource::Pile(0), MoveTarget::Foundation)).unwrap();
+      let (moves, hash) = (game.moves_made(), game.state_hash());
+
+      let in_place = Move::Cards(MoveSource::Pile(2), MoveTarget::Pile(2));
+      let founded = Move::Cards(MoveSource::Foundation(ace.suit()), MoveTarget::Foundation);
+      assert_eq!(game.apply(in_place), Err(KlondikeErr::IllegalMove { m: in_place, card: game.pile(2).top() }));
+      assert_eq!(game.apply(founded), Err(KlondikeErr::IllegalMove { m: founded, card: Some(ace) }));
+      assert!(! game.legal_moves().contains(&in_place));
+      assert!(! game.legal_moves().contains(&founded));
+
+      assert_eq!(game.moves_made(), moves);
+      assert_eq!(game.history().unwrap().len(), moves);
+      assert_eq!(game.state_hash(), hash);
+    }
+
+    #[test]
+    fn moves_as_keys() {
+      let game = unsorted_game();
+      let moves = game.legal_moves();
+
+      let mut seen: HashSet<Move> = moves.iter().cloned().collect();
+      assert_eq!(seen.len(), moves.len());
+      assert!(!seen.insert(Move::Cards(MoveSource::Pile(0), MoveTarget::Foundation)));
+      assert!(seen.insert(Move::Cards(MoveSource::PileRun { pile: 0, index: 0 }, MoveTarget::Foundation)));
+
+      for m in moves {
+        assert_eq!(m.to_string().parse::<Move>(), Ok(m));
+      }
+    }
+
+    #[test]
+    fn legal_partial_run() {
+      let target = [card!(Suit::Spades, Rank::Number(9))];
+      let hidden = [card!(Suit::Diamonds, Rank::King)];
+      let run = [
+        card!(Suit::Clubs, Rank::Number(9)),
+        card!(Suit::Hearts, Rank::Number(8)),
+        card!(Suit::Spades, Rank::Number(7)),
+      ];
+      let foundations = [
+        Foundation::from(Suit::Clubs, Some(Rank::Number(8))),
+        Foundation::from(Suit::Hearts, Some(Rank::Number(7))),
+        Foundation::from(Suit::Spades, Some(Rank::Number(5))),
+        Foundation::from(Suit::Diamonds, Some(Rank::Queen)),
+      ];
+      let founded: Vec<Card> = foundations.iter().flat_map(|f| f.cards()).collect();
+      let rest: Vec<Card> = new_standard_deck().into_iter()
+        .filter(|c| ! founded.contains(c) && ! target.contains(c) && ! hidden.contains(c) && ! run.contains(c))
+        .collect();
+
+      let mut game = KlondikeSolitaireGame::from(
+        Deck::from(1, &[], &[], &rest),
+        foundations, [
+          Pile::from(&[], &target),
+          Pile::from(&hidden, &run),
+          Pile::new(), Pile::new(), Pile::new(), Pile::new(), Pile::new(),
+        ]
+      );
+
+      // The eight and the seven fit the nine, leaving the other nine behind.
+      let partial = Move::Cards(MoveSource::Pile(1), MoveTarget::Pile(0));
+      assert_eq!(game.legal_moves(), vec![Move::Draw, partial]);
+
+      game.apply(partial).unwrap();
+      assert_eq!(game.piles()[1].visible_cards(), &[card!(Suit::Clubs, Rank::Number(9))]);
+      assert_eq!(game.piles()[0].visible_cards(), &[
+        card!(Suit::Spades, Rank::Number(9)),
+        card!(Suit::Hearts, Rank::Number(8)),
+        card!(Suit::Spades, Rank::Number(7)),
+      ]);
+    }
+
+    #[test]
+    fn pile_run() {
+      let king = [card!(Suit::Spades, Rank::King)];
+      let hidden = [card!(Suit::Diamonds, Rank::King)];
+      let run = [
+        card!(Suit::Hearts, Rank::Queen),
+        card!(Suit::Clubs, Rank::Jack),
+        card!(Suit::Hearts, Rank::Number(10)),
+      ];
+      let foundations = [
+        Foundation::from(Suit::Hearts, Some(Rank::Number(9))),
+        Foundation::from(Suit::Diamonds, Some(Rank::Queen)),
+        Foundation::from(Suit::Spades, Some(Rank::Queen)),
+        Foundation::from(Suit::Clubs, Some(Rank::Number(10))),
+      ];
+      let founded: Vec<Card> = foundations.iter().flat_map(|f| f.cards()).collect();
+      let rest: Vec<Card> = new_standard_deck().into_iter()
+        .filter(|c| ! founded.contains(c) && ! king.contains(c) && ! hidden.contains(c) && ! run.contains(c))
+        .collect();
+
+      let mut game = KlondikeSolitaireGame::from(
+        Deck::from(1, &[], &[], &rest),
+        foundations, [
+          Pile::from(&[], &king),
+          Pile::from(&hidden, &run),
+          Pile::new(), Pile::new(), Pile::new(), Pile::new(), Pile::new(),
+        ]
+      );
+      let hash = game.state_hash();
+
+      // Only the queen fits the king, a run can not move onto its own pile
+      // and only the top card of a run goes to a foundation, though the jack
+      // would fit there.
+      let jack = Move::Cards(MoveSource::PileRun { pile: 1, index: 1 }, MoveTarget::Pile(0));
+      assert_eq!(game.apply(jack), Err(KlondikeErr::IllegalMove { m: jack, card: Some(run[1]) }));
+      let ten = Move::Cards(MoveSource::PileRun { pile: 1, index: 2 }, MoveTarget::Pile(1));
+      assert_eq!(game.apply(ten), Err(KlondikeErr::IllegalMove { m: ten, card: Some(run[2]) }));
+      let buried = Move::Cards(MoveSource::PileRun { pile: 1, index: 1 }, MoveTarget::Foundation);
+      assert_eq!(game.apply(buried), Err(KlondikeErr::IllegalMove { m: buried, card: Some(run[1]) }));
+      assert_eq!(game.apply(buried).unwrap_err().to_string(), "move pile1[1]->foundation of JC is not legal");
+      let empty = Move::Cards(MoveSource::Pile(2), MoveTarget::Pile(0));
+      assert_eq!(game.apply(empty), Err(KlondikeErr::IllegalMove { m: empty, card: None }));
+      assert_eq!(game.state_hash(), hash);
+      assert_eq!(game.moves_made(), 0);
+
+      let queen = Move::Cards(MoveSource::PileRun { pile: 1, index: 0 }, MoveTarget::Pile(0));
+      let mut moved = game.clone();
+      moved.apply(queen).unwrap();
+      game.apply(Move::Cards(MoveSource::Pile(1), MoveTarget::Pile(0))).unwrap();
+
+      assert_eq!(moved.piles()[0].visible_cards(), game.piles()[0].visible_cards());
+      assert_eq!(moved.piles()[1].visible_cards(), &hidden);
+      assert_eq!(moved.state_hash(), game.state_hash());
+      assert_eq!(moved.score(), game.score());
+      assert_eq!(moved.move_count(MoveKind::PileToPile), 1);
+    }
+
+    #[test]
+    fn is_stuck() {
+      let c = |s: &str| s.parse::<Card>().unwrap();
+      let cards = |s: &[&str]| s.iter().map(|s| c(s)).collect::<Vec<Card>>();
+      let stuck = |draw_count: u8| KlondikeSolitaireGame::from(
+        Deck::from(draw_count, &[], &[], &cards(&["JS", "QS", "KD", "JC", "QC", "KS", "KC"])),
+        [
+          Foundation::from(Suit::Hearts, Some(Rank::Number(8))),
+          Foundation::from(Suit::Diamonds, Some(Rank::Number(8))),
+          Foundation::from(Suit::Spades, Some(Rank::Number(8))),
+          Foundation::from(Suit::Clubs, Some(Rank::Number(8))),
+        ], [
+          Pile::from(&[], &cards(&["10H"])),
+          Pile::from(&cards(&["9H"]), &cards(&["10D"])),
+          Pile::from(&cards(&["9S", "9C"]), &cards(&["JH"])),
+          Pile::from(&cards(&["9D"]), &cards(&["JD"])),
+          Pile::from(&cards(&["10S", "10C"]), &cards(&["QH"])),
+          Pile::from(&[], &cards(&["QD"])),
+          Pile::from(&[], &cards(&["KH"])),
+        ]
+      );
+
+      // Drawing three turns up only the kings, which have nowhere to go.
+      let mut game = stuck(3);
+      assert!(game.is_stuck());
+      game.draw();
+      assert!(game.is_stuck());
+
+      // Drawing one turns up the jack of spades for the queen of hearts.
+      let mut game = stuck(1);
+      assert!(! game.is_stuck());
+      game.apply(Move::Draw).unwrap();
+      game.apply(Move::Cards(MoveSource::Deck, MoveTarget::Pile(4))).unwrap();
+      assert!(! game.is_stuck());
+
+      assert!(! unsorted_game().is_stuck());
+    }
+
+    #[test]
+    fn is_stuck_with_foundation_moves() {
+      let c = |s: &str| s.parse::<Card>().unwrap();
+      let cards = |s: &[&str]| s.iter().map(|s| c(s)).collect::<Vec<Card>>();
+      let game = KlondikeSolitaireGame::from(
+        Deck::from(3, &[], &[], &cards(&["JS", "QS", "KD", "JC", "QC", "KS", "KC"])),
+        [
+          Foundation::from(Suit::Hearts, Some(Rank::Number(7))),
+          Foundation::from(Suit::Diamonds, Some(Rank::Number(8))),
+          Foundation::from(Suit::Spades, Some(Rank::Number(8))),
+          Foundation::from(Suit::Clubs, Some(Rank::Number(8))),
+        ], [
+          Pile::from(&[], &cards(&["10H"])),
+          Pile::from(&cards(&["8H"]), &cards(&["9H"])),
+          Pile::from(&cards(&["9S", "9C"]), &cards(&["JH"])),
+          Pile::from(&cards(&["9D", "10D"]), &cards(&["JD"])),
+          Pile::from(&cards(&["10S", "10C"]), &cards(&["QH"])),
+          Pile::from(&[], &cards(&["QD"])),
+          Pile::from(&[], &cards(&["KH"])),
+        ]
+      );
+
+      // The black eights can go back on the nine of hearts, but the red
+      // sevens that would go on them are founded.
+      assert!(game.legal_moves().contains(&Move::Cards(MoveSource::Foundation(Suit::Spades), MoveTarget::Pile(1))));
+      assert!(game.is_stuck());
+    }
+
+    #[test]
+    fn is_stuck_moving_back_and_forth() {
+      let c = |s: &str| s.parse::<Card>().unwrap();
+      let cards = |s: &[&str]| s.iter().map(|s| c(s)).collect::<Vec<Card>>();
+      let mut game = KlondikeSolitaireGame::from(
+        Deck::new(1),
+        [
+          Foundation::from(Suit::Hearts, Some(Rank::Number(7))),
+          Foundation::from(Suit::Diamonds, Some(Rank::Number(7))),
+          Foundation::from(Suit::Spades, Some(Rank::Number(7))),
+          Foundation::from(Suit::Clubs, Some(Rank::Number(7))),
+        ], [
+          Pile::from(&[], &cards(&["KH"])),
+          Pile::from(&cards(&["8H"]), &cards(&["10H", "9C"])),
+          Pile::from(&cards(&["8D", "8S"]), &cards(&["10D"])),
+          Pile::from(&cards(&["8C", "9H", "9D"]), &cards(&["KD"])),
+          Pile::from(&cards(&["9S", "10S", "10C", "JD"]), &cards(&["KS"])),
+          Pile::from(&cards(&["JS", "JC", "QH", "QD", "QS"]), &cards(&["KC"])),
+          Pile::from(&cards(&["QC"]), &cards(&["JH"])),
+        ]
+      );
+
+      // The nine of clubs can only go from one red ten to the other
+      let there = Move::Cards(MoveSource::Pile(1), MoveTarget::Pile(2));
+      let back = Move::Cards(MoveSource::Pile(2), MoveTarget::Pile(1));
+      assert_eq!(game.legal_moves(), vec![there]);
+      assert!(game.is_stuck());
+      game.apply(there).unwrap();
+      assert_eq!(game.legal_moves(), vec![back]);
+      assert!(game.is_stuck());
+      game.apply(back).unwrap();
+      assert!(game.is_stuck());
+    }
+
+    #[test]
+    fn autoplay() {
+      let c = |s: &str| s.parse::<Card>().unwrap();
+      let cards = |s: &[&str]| s.iter().map(|s| c(s)).collect::<Vec<Card>>();
+      let mut game = KlondikeSolitaireGame::from(
+        Deck::from(1, &[], &[c("9C")], &cards(&["10H", "JH", "QH", "KH", "10D", "JD", "QD", "KD", "9S", "10S", "JS", "QS", "KS", "10C", "JC", "QC"])),
+        [
+          Foundation::from(Suit::Hearts, Some(Rank::Number(8))),
+          Foundation::from(Suit::Diamonds, Some(Rank::Number(8))),
+          Foundation::from(Suit::Spades, Some(Rank::Number(8))),
+          Foundation::from(Suit::Clubs, Some(Rank::Number(7))),
+        ], [
+          Pile::from(&[], &cards(&["9H"])),
+          Pile::from(&cards(&["KC"]), &cards(&["8C"])),
+          Pile::from(&[], &cards(&["9D"])),
+          Pile::new(), Pile::new(), Pile::new(), Pile::new(),
+        ]
+      );
+
+      // The red nines could still take the eight of clubs, which is safe as
+      // both red sevens are founded; once it is, all three nines follow.
+      assert!(! game.is_safe_to_found(c("9H")));
+      assert!(game.is_safe_to_found(c("8C")));
+      assert_eq!(game.autoplay(), 4);
+      assert_eq!(game.foundation(Suit::Clubs).top(), Some(c("9C")));
+      assert_eq!(game.foundation(Suit::Hearts).top(), Some(c("9H")));
+      assert_eq!(game.piles()[1].visible_cards(), &[c("KC")]);
+      assert_eq!(game.autoplay(), 0);
+      assert!(! game.is_trivially_winnable());
+
+      let mut game = KlondikeSolitaireGame::from(
+        Deck::new(1),
+        [
+          Foundation::from(Suit::Hearts, Some(Rank::Jack)),
+          Foundation::from(Suit::Diamonds, Some(Rank::Jack)),
+          Foundation::from(Suit::Spades, Some(Rank::Jack)),
+          Foundation::from(Suit::Clubs, Some(Rank::Jack)),
+        ], [
+          Pile::from(&[], &cards(&["KH", "QS"])),
+          Pile::from(&[], &cards(&["KS", "QH"])),
+          Pile::from(&[], &cards(&["KD", "QC"])),
+          Pile::from(&[], &cards(&["KC", "QD"])),
+          Pile::new(), Pile::new(), Pile::new(),
+        ]
+      );
+      assert!(game.is_trivially_winnable());
+      assert_eq!(game.autoplay(), 8);
+      assert!(game.is_clear());
+    }
+
+    #[test]
+    fn render() {
+      let mut game = unsorted_game();
+      game.draw();
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+
+      assert_eq!(game.to_string(), game.render());
+      assert_eq!(game.render(), [
+        "stock 21  waste | 3C 4C 5C  foundations -- AD -- --  score 10",
+        "    0    1    2    3    4    5    6",
+        "   --   ##   ##   ##   ##   ##   ##",
+        "        3D   ##   ##   ##   ##   ##",
+        "             6D   ##   ##   ##   ##",
+        "                 10D   ##   ##   ##",
+        "                       2H   ##   ##",
+        "                            8H   ##",
+        "                                 2C",
+      ].join("\n"));
+    }
+
+    #[test]
+    fn encode() {
+      use rand::{SeedableRng, XorShiftRng};
+
+      let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+      for _ in 0..100 {
+        let mut game = KlondikeSolitaireGame::arbitrary(&mut rng, 200);
+        if rng.gen() {
+          game.deck.set_pass_limit(Some(game.deck.passes() + 1));
+          game.hash = game.full_hash();
+        }
+
+        let decoded = KlondikeSolitaireGame::decode(&game.encode()).unwrap();
+        assert_eq!(decoded.notation(), game.notation());
+        assert_eq!(decoded.deck().passes_remaining(), game.deck().passes_remaining());
+        assert_eq!(decoded.state_hash(), game.state_hash());
+        assert_eq!(decoded.encode()[..], game.encode()[..]);
+      }
+
+      let mut bytes = unsorted_game().encode();
+      bytes[0] = 14;
+      assert!(KlondikeSolitaireGame::decode(&bytes).is_err());
+
+      let mut bytes = unsorted_game().encode();
+      bytes[ENCODED_CARDS] ^= 1;
+      assert!(KlondikeSolitaireGame::decode(&bytes).is_err(), "duplicate card");
+
+      let mut bytes = unsorted_game().encode();
+      bytes[13] = 200;
+      assert!(KlondikeSolitaireGame::decode(&bytes).is_err(), "waste past the deck");
+
+      let mut bytes = unsorted_game().encode();
+      bytes[13] = 0;
+      bytes[14] = bytes[9] + 1;
+      assert!(KlondikeSolitaireGame::decode(&bytes).is_err(), "more visible than drawn");
+    }
+
+    #[test]
+    fn from_notation() {
+      use rand::{SeedableRng, XorShiftRng};
+
+      let mut rng = XorShiftRng::from_seed([9, 10, 11, 12]);
+      for _ in 0..100 {
+        let game = KlondikeSolitaireGame::arbitrary(&mut rng, 200);
+        let parsed = KlondikeSolitaireGame::from_notation(&game.notation()).unwrap();
+        assert_eq!(parsed.notation(), game.notation());
+        assert_eq!(parsed.state_hash(), game.state_hash());
+        assert_eq!(parsed.legal_moves(), game.legal_moves());
+      }
+
+      let notation = unsorted_game().notation();
+      assert_eq!(KlondikeSolitaireGame::from_notation(&notation).unwrap().notation(), notation);
+
+      let errors = [
+        notation.replacen(" / ", " ", 1),
+        notation.replacen("|", " ", 1),
+        notation.replacen("d3", "x3", 1),
+        notation.replacen("d3", "d0", 1),
+        notation.replacen("AD", "AD ad", 1),
+        notation.replacen("/ - /", "/ AS 2S /", 1),
+      ];
+      for bad in errors.iter() {
+        assert!(KlondikeSolitaireGame::from_notation(bad).is_err(), "{}", bad);
+      }
+    }
+
+    #[test]
+    fn moves_made() {
+      let mut game = unsorted_game();
+      assert_eq!(game.moves_made(), 0);
+
+      game.draw();
+      assert!(game.move_cards(MoveSource::Deck, MoveTarget::Foundation).is_err());
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      assert_eq!(game.moves_made(), 2);
+    }
+
+    #[test]
+    fn move_count() {
+      let mut game = KlondikeSolitaireGame::from(
+        Deck::from(1, &[], &[], &[card!(Suit::Diamonds, Rank::Jack)]),
+        [
+          Foundation::new_full(Suit::Clubs),
+          Foundation::new_full(Suit::Hearts),
+          Foundation::new_full(Suit::Spades),
+          Foundation::from(Suit::Diamonds, Some(Rank::Number(10))),
+        ], [
+          Pile::new(),
+          Pile::from(&[card!(Suit::Diamonds, Rank::Queen)], &[card!(Suit::Diamonds, Rank::King)]),
+          Pile::new(), Pile::new(), Pile::new(), Pile::new(), Pile::new(),
+        ]
+      );
+
+      game.draw();
+      game.draw();
+      game.draw();
+      game.move_cards(MoveSource::Deck, MoveTarget::Foundation).unwrap();
+      game.move_cards(MoveSource::Pile(1), MoveTarget::Pile(0)).unwrap();
+      game.move_cards(MoveSource::Pile(1), MoveTarget::Foundation).unwrap();
+
+      assert_eq!(game.move_count(MoveKind::Draw), 2);
+      assert_eq!(game.move_count(MoveKind::Recycle), 1);
+      assert_eq!(game.move_count(MoveKind::DeckToFoundation), 1);
+      assert_eq!(game.move_count(MoveKind::PileToPile), 1);
+      assert_eq!(game.move_count(MoveKind::PileToFoundation), 1);
+      assert_eq!(game.move_count(MoveKind::DeckToPile), 0);
+      assert_eq!(game.moves_made(), 6);
+      assert_eq!(game.draws_made(), 3);
+      assert_eq!(game.hidden_flips(), 1);
+    }
+
+    #[test]
+    fn pile_revealed_at() {
+      let mut game = KlondikeSolitaireGame::from(
+        Deck::from(1, &[], &[], &[]),
+        [
+          Foundation::new_full(Suit::Clubs),
+          Foundation::new_full(Suit::Hearts),
+          Foundation::new_full(Suit::Spades),
+          Foundation::from(Suit::Diamonds, Some(Rank::Jack)),
+        ], [
+          Pile::new(),
+          Pile::from(&[card!(Suit::Diamonds, Rank::Queen)], &[card!(Suit::Diamonds, Rank::King)]),
+          Pile::new(), Pile::new(), Pile::new(), Pile::new(), Pile::new(),
+        ]
+      );
+
+      assert_eq!(game.pile_revealed_at(0), Some(0));
+      assert_eq!(game.pile_revealed_at(1), None);
+
+      // Drawing from the empty deck is not a move
+      game.draw();
+      game.move_cards(MoveSource::Pile(1), MoveTarget::Pile(0)).unwrap();
+      assert_eq!(game.pile_revealed_at(1), Some(1));
+    }
+
+    #[test]
+    fn record_moves() {
+      let mut game = unsorted_game();
+      game.draw();
+      assert!(game.recorded_moves().is_none());
+
+      game.record_moves();
+      game.draw();
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      assert!(game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).is_err());
+      game.apply(Move::Cards(MoveSource::Pile(6), MoveTarget::Pile(1))).unwrap();
+
+      assert_eq!(game.recorded_moves().unwrap(), &[
+        Move::Draw,
+        Move::Cards(MoveSource::Pile(0), MoveTarget::Foundation),
+        Move::Cards(MoveSource::Pile(6), MoveTarget::Pile(1)),
+      ]);
+
+      let cards = new_standard_deck();
+      let history = game.history().unwrap();
+      assert_eq!(history[0].cards.len(), 3);
+      assert!(! history[0].recycled);
+      assert_eq!(history[1].cards, vec![cards[0]]);
+      assert_eq!(history[1].flipped, None);
+      assert_eq!(history[2], MoveRecord {
+        m: Move::Cards(MoveSource::Pile(6), MoveTarget::Pile(1)),
+        cards: vec![cards[27]],
+        flipped: Some(cards[26]),
+        recycled: false,
+        score: 15,
+      });
+      assert_eq!(history[2].to_string(), format!("pile6->pile1 {}, flipped {}, score 15", cards[27], cards[26]));
+
+      assert!(game.determinize(|_| false, |_| ()).recorded_moves().is_none());
+    }
+
+    #[derive(Default)]
+    struct EventLog(Vec<String>);
+
+    impl Observer for EventLog {
+      fn on_move(&mut self, _game: &KlondikeSolitaireGame, record: &MoveRecord) {
+        self.0.push(format!("move {}", record));
+      }
+
+      fn on_flip(&mut self, _game: &KlondikeSolitaireGame, pile: usize, card: Card) {
+        self.0.push(format!("flip {} {}", pile, card));
+      }
+
+      fn on_draw(&mut self, _game: &KlondikeSolitaireGame, cards: &[Card]) {
+        self.0.push(format!("draw {:?}", cards.iter().map(|c| c.to_string()).collect::<Vec<_>>()));
+      }
+
+      fn on_recycle(&mut self, game: &KlondikeSolitaireGame) {
+        self.0.push(format!("recycle {}", game.deck().passes()));
+      }
+
+      fn on_win(&mut self, game: &KlondikeSolitaireGame) {
+        self.0.push(format!("win {}", game.score()));
+      }
     }
-  }
 
-  pub fn from(suit: Suit, rank: Option<Rank>) -> Foundation {
-    let mut f = Foundation::new(suit);
-    f.current_rank_index = match rank {
-      Some(rank) => Some(rank_index(rank).unwrap()),
-      None => None,
-    };
-    return f;
-  }
+    #[test]
+    fn observers() {
+      let c = |s: &str| s.parse::<Card>().unwrap();
+      let mut game = KlondikeSolitaireGame::from(
+        Deck::from(1, &[], &[], &[c("KS")]),
+        [
+          Foundation::from(Suit::Hearts, Some(Rank::Queen)),
+          Foundation::from(Suit::Diamonds, Some(Rank::King)),
+          Foundation::from(Suit::Spades, Some(Rank::Jack)),
+          Foundation::from(Suit::Clubs, Some(Rank::King)),
+        ], [
+          Pile::new(),
+          Pile::from(&[c("QS")], &[c("KH")]),
+          Pile::new(), Pile::new(), Pile::new(), Pile::new(), Pile::new(),
+        ]
+      );
 
-  pub fn new_full(suit: Suit) -> Foundation {
-    Foundation::from(suit, Some(Rank::King))
-  }
+      let log = Arc::new(Mutex::new(EventLog::default()));
+      game.add_observer(log.clone());
+
+      // Moves tried on copies are not reported
+      let mut copy = game.clone();
+      copy.draw();
+
+      game.draw();
+      game.draw();
+      game.move_cards(MoveSource::Pile(1), MoveTarget::Pile(0)).unwrap();
+      game.move_cards(MoveSource::Pile(1), MoveTarget::Foundation).unwrap();
+      game.draw();
+      game.move_cards(MoveSource::Deck, MoveTarget::Foundation).unwrap();
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+
+      assert_eq!(log.lock().unwrap().0, vec![
+        "draw [\"KS\"]",
+        "recycle 2",
+        "move pile1->pile0 KH, flipped QS, score 5",
+        "flip 1 QS",
+        "move pile1->foundation QS, score 15",
+        "draw [\"KS\"]",
+        "move deck->foundation KS, score 25",
+        "move pile0->foundation KH, score 35",
+        "win 35",
+      ]);
+      assert!(game.recorded_moves().is_none());
 
-  pub fn top(&self) -> Option<Card> {
-    match self.current_rank_index {
-      Some(i) => Some(Card::new(self.suit, RANKS[i])),
-      None => None,
+      game.clear_observers();
+      game.move_cards(MoveSource::Foundation(Suit::Spades), MoveTarget::Pile(1)).unwrap();
+      assert_eq!(log.lock().unwrap().0.len(), 9);
     }
-  }
-
-  pub fn cards(&self) -> Vec<Card> {
-    let mut cards = Vec::new();
 
-    if let Some(rank_index) = self.current_rank_index {
-      for index in 0..rank_index+1 {
-        cards.push(Card::new(self.suit, RANKS[index]))
+    #[test]
+    fn stats() {
+      let mut game = unsorted_game();
+      assert_eq!(game.stats(), GameStats { passes: 1, .. GameStats::default() });
+
+      game.draw();
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      game.move_cards(MoveSource::Pile(6), MoveTarget::Pile(1)).unwrap();
+      for _ in 0..8 {
+        game.draw();
       }
-    }
 
-    return cards;
-  }
+      let stats = game.stats();
+      assert_eq!(stats, GameStats {
+        moves: 11,
+        draws: 9,
+        passes: 2,
+        cards_revealed: 1,
+        cards_founded: 1,
+      });
+
+      let mut total = stats;
+      total += stats;
+      assert_eq!((total.moves, total.passes), (22, 4));
+    }
 
-  pub fn is_full(&self) -> bool {
-    self.current_rank_index == Some(RANKS.len() - 1)
-  }
+    #[test]
+    fn notation() {
+      let mut game = unsorted_game();
+      game.draw();
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+
+      assert_eq!(game.notation(),
+        "-|2d 3D|4d 5d 6D|7d 8d 9d 10D|jd qd kd ah 2H|3h 4h 5h 6h 7h 8H|9h 10h jh qh kh ac 2C \
+        / AD / d3 - : 3C 4C 5C : 6C 7C 8C 9C 10C JC QC KC AS 2S 3S 4S 5S 6S 7S 8S 9S 10S JS QS KS");
+    }
 
-  pub fn is_empty(&self) -> bool {
-    self.current_rank_index.is_none()
-  }
+    #[test]
+    fn to_json() {
+      let mut game = unsorted_game();
+      game.draw();
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+
+      let json = game.to_json();
+      assert_eq!(Value::parse(&json.to_string()), Ok(json.clone()));
+      assert_eq!(json.get("score").and_then(Value::as_u64), Some(10));
+      assert_eq!(json.get("rules").unwrap().to_string(),
+        "{\"draw_count\":3,\"pass_limit\":null,\"scoring\":\"standard\",\"move_time\":null,\"thoughtful\":false}");
+      assert_eq!(json.get("foundations").unwrap().as_array().unwrap()[1].to_string(), "{\"suit\":\"D\",\"top\":\"AD\"}");
+      assert_eq!(json.get("piles").unwrap().as_array().unwrap()[1].to_string(), "{\"hidden\":[\"2D\"],\"visible\":[\"3D\"]}");
+
+      let deck = json.get("deck").unwrap();
+      assert_eq!(deck.get("passes").and_then(Value::as_u64), Some(1));
+      assert_eq!(deck.get("visible").unwrap().to_string(), "[\"3C\",\"4C\",\"5C\"]");
+      assert_eq!(deck.get("remaining").unwrap().as_array().unwrap().len(), 21);
+    }
 
-  pub fn suit(&self) -> Suit {
-    self.suit
-  }
+    #[test]
+    fn from_json() {
+      use rand::{SeedableRng, XorShiftRng};
+
+      let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+      for _ in 0..50 {
+        let mut game = KlondikeSolitaireGame::arbitrary(&mut rng, 200);
+        game.rules.pass_limit = Some(game.deck.passes() + 1);
+        game.deck.pass_limit = game.rules.pass_limit;
+
+        let json = game.to_json().to_string();
+        let loaded = KlondikeSolitaireGame::from_reader(json.as_bytes()).unwrap();
+        assert_eq!(loaded.notation(), game.notation());
+        assert_eq!(loaded.rules(), game.rules());
+        assert_eq!(loaded.score(), game.score());
+        assert_eq!(loaded.moves_made(), game.moves_made());
+        assert_eq!(loaded.hidden_flips(), game.hidden_flips());
+        for i in 0..NUM_PILES {
+          assert_eq!(loaded.pile_revealed_at(i), game.pile_revealed_at(i));
+        }
+        for &kind in MoveKind::all() {
+          assert_eq!(loaded.move_count(kind), game.move_count(kind));
+        }
+        assert_eq!(loaded.deck().passes(), game.deck().passes());
+        assert_eq!(loaded.legal_moves(), game.legal_moves());
+      }
+    }
 
-  pub fn next_rank(&self) -> Option<Rank> {
-    match self.current_rank_index {
-      Some(i) if i == RANKS.len() - 1 => None,
-      Some(i) => Some(RANKS[i+1]),
-      None => Some(RANKS[0]),
+    #[test]
+    fn from_json_errors() {
+      let json = unsorted_game().to_json().to_string();
+      let load = |text: &str| KlondikeSolitaireGame::from_reader(text.as_bytes()).err().unwrap();
+
+      assert_eq!(load(&json.replace("\"3D\"", "\"2D\"")), "duplicate card 2D");
+      assert_eq!(load(&json.replace("\"hidden\":[\"2D\"]", "\"hidden\":[]")), "missing card 2D");
+      assert_eq!(load(&json.replace("\"suit\":\"D\",\"top\":null", "\"suit\":\"D\",\"top\":\"AH\"")), "foundations: AH on the D foundation");
+      assert_eq!(load(&json.replace("\"scoring\":\"standard\"", "\"scoring\":\"timed\"")), "rules: scoring must be \"standard\" or \"vegas\"");
+      assert_eq!(load(&json.replace("\"passes\":1", "\"passes\":0")), "deck: passes start at 1");
+      assert_eq!(load(&json.replace(",\"moves_made\":0", "")), "missing moves_made");
+      assert_eq!(load(&json.replace("\"revealed_at\":[0,", "\"revealed_at\":[null,")), "revealed_at does not match the hidden cards of pile 0");
+      assert_eq!(load(&json.replace("\"pile->pile\":0", "\"pile->pile\":-1")), "move_counts: pile->pile must be a count");
+      assert_eq!(load(&json[1..]), "unexpected text at offset 7");
     }
-  }
 
-  pub fn next_card(&self) -> Option<Card> {
-    match self.next_rank() {
-      Some(r) => Some(Card::new(self.suit, r)),
-      None => None,
+    #[test]
+    fn state_hash() {
+      let mut a = unsorted_game();
+      let mut b = unsorted_game();
+      assert_eq!(a.state_hash(), b.state_hash());
+
+      // The same position reached in a different order
+      a.draw();
+      a.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      assert!(a.state_hash() != b.state_hash());
+      b.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      b.draw();
+      assert_eq!(a.state_hash(), b.state_hash());
+
+      // A failed move changes nothing
+      assert!(a.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).is_err());
+      assert_eq!(a.state_hash(), b.state_hash());
+
+      a.move_cards(MoveSource::Pile(6), MoveTarget::Pile(1)).unwrap();
+      assert_eq!(a.state_hash(), a.full_hash());
+      assert_eq!(KlondikeSolitaireGame::from_json(&a.to_json()).unwrap().state_hash(), a.state_hash());
+      assert!(a.state_hash() != b.state_hash());
     }
-  }
 
-  pub fn can_push(&self, card: Card) -> bool {
-    self.suit == card.suit() && self.next_rank() == Some(card.rank())
-  }
+    #[test]
+    fn move_display() {
+      assert_eq!(format!("{}", Move::Draw), "draw");
+      assert_eq!(format!("{}", Move::Cards(MoveSource::Deck, MoveTarget::Pile(3))), "deck->pile3");
+      assert_eq!(format!("{}", Move::Cards(MoveSource::Pile(0), MoveTarget::Foundation)), "pile0->foundation");
+      assert_eq!(format!("{}", Move::Cards(MoveSource::Foundation(Suit::Hearts), MoveTarget::Pile(6))), "foundationH->pile6");
+      assert_eq!(format!("{}", Move::Cards(MoveSource::PileRun { pile: 2, index: 1 }, MoveTarget::Pile(4))), "pile2[1]->pile4");
+    }
 
-  pub fn push(&mut self) -> Option<Card> {
-    match self.current_rank_index {
-      Some(i) if i == RANKS.len() - 1 => None,
-      Some(i) => {
-        self.current_rank_index = Some(i+1);
-        Some(Card::new(self.suit, RANKS[i]))
-      },
-      None => {
-        self.current_rank_index = Some(0);
-        Some(Card::new(self.suit, RANKS[0]))
+    #[test]
+    fn arbitrary_states_are_valid() {
+      use rand::{SeedableRng, XorShiftRng};
+
+      let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+      let mut founded = 0;
+
+      for _ in 0..100 {
+        let game = KlondikeSolitaireGame::arbitrary(&mut rng, 200);
+        assert_eq!(game.validate(), Ok(()));
+        founded += game.foundations.iter().map(|f| f.len()).sum::<usize>();
+
+        // Every legal move applies to every generated state
+        for m in game.legal_moves() {
+          let mut next = game.clone();
+          assert_eq!(next.apply(m), Ok(()), "{} in {}", m, game.notation());
+          assert_eq!(next.validate(), Ok(()));
+        }
       }
+
+      assert!(founded > 0, "states only near the deal");
     }
-  }
 
-  pub fn clear(&mut self) {
-    self.current_rank_index = None;
-  }
+    #[test]
+    fn rule_presets() {
+      for name in RULE_PRESETS.iter() {
+        assert!(KlondikeRules::preset(name).is_some(), "{}", name);
+      }
 
-  pub fn pop(&mut self) -> Option<Card> {
-    match self.current_rank_index {
-      Some(0) => {
-        self.current_rank_index = None;
-        Some(Card::new(self.suit, RANKS[0]))
-      },
-      Some(i) => {
-        self.current_rank_index = Some(i-1);
-        Some(Card::new(self.suit, RANKS[i-1]))
-      },
-      None => None
+      assert!(KlondikeRules::preset("unknown").is_none());
+
+      let vegas = KlondikeRules::preset("vegas").unwrap();
+      assert_eq!(vegas.draw_count, 3);
+      assert_eq!(vegas.pass_limit, Some(3));
+
+      let game = KlondikeSolitaireGame::with_deal(vegas, french::new_standard_deck());
+      assert_eq!(game.rules(), vegas);
+      assert_eq!(game.deck().pass_limit(), Some(3));
+      assert_eq!(game.passes_remaining(), Some(2));
+      assert_eq!(KlondikeSolitaireGame::with_deal(KlondikeRules::new(1), french::new_standard_deck()).passes_remaining(), None);
+      assert_eq!(game.deck().draw_count(), 3);
+      assert_eq!(game.score(), -52);
     }
-  }
-}
 
-#[cfg(test)]
-mod test {
-  pub use super::*;
+    #[test]
+    fn one_pass_has_no_draw_after_deck() {
+      let mut game = KlondikeSolitaireGame::with_deal(KlondikeRules::preset("one-pass-draw-1").unwrap(), french::new_standard_deck());
 
-  macro_rules! card {
-    ($suit:expr, $rank:expr) => (Card::new($suit, $rank));
-  }
+      for _ in 0..24 {
+        assert!(game.legal_moves().contains(&Move::Draw));
+        game.draw();
+      }
 
-  pub fn test_cards(name: &str, expected: &[Card], actual: &[Card]) {
-    assert!(actual.len() == expected.len(), "{}: {} != {}", name, expected.len(), actual.len());
-    for i in 0..expected.len() {
-      assert!(expected[i] == actual[i], "{}[{}]: {:?} != {:?}", name, i, expected[i], actual[i]);
+      assert!(! game.legal_moves().contains(&Move::Draw));
     }
-  }
 
-  pub fn test_deck(deck: &Deck, visible: &[Card], waste: &[Card], remaining: &[Card]) {
-    test_cards("visible", visible, deck.visible_cards());
-    test_cards("waste", waste, deck.waste_cards());
-    test_cards("remaining", remaining, deck.remaining_cards());
-  }
+    #[test]
+    fn thoughtful_reveals_hidden_cards() {
+      let game = KlondikeSolitaireGame::with_rules(KlondikeRules::new(1), seeded_source(1));
+      assert_eq!(game.pile(6).peek_hidden(), None);
+      assert_eq!(game.deck().all_cards(), None);
+
+      let rules = KlondikeRules { thoughtful: true, .. KlondikeRules::new(1) };
+      let mut game = KlondikeSolitaireGame::with_rules(rules, seeded_source(1));
+      assert_eq!(game.pile(6).peek_hidden(), Some(game.pile(6).hidden_cards()));
+      assert_eq!(game.deck().all_cards().map(|c| c.len()), Some(24));
+
+      let order = game.deck().all_cards().unwrap().to_vec();
+      game.draw();
+      game.draw();
+      assert_eq!(game.deck().all_cards(), Some(&order[..]), "drawing keeps the order");
+      assert_eq!(game.clone().pile(0).peek_hidden(), Some(&[][..]));
+    }
 
-  pub fn test_pile(name: &str, pile: &Pile, hidden: &[Card], visible: &[Card]) {
-    test_cards(&format!("{}.hidden", name), hidden, pile.hidden_cards());
-    test_cards(&format!("{}.visible", name), visible, pile.visible_cards());
-  }
+    #[test]
+    fn standard_scoring() {
+      let mut game = unsorted_game();
+      game.draw();
 
-  mod game {
-    use super::*;
-    use cards::french::{Suit, new_standard_deck};
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      assert_eq!(game.score(), 10);
+
+      // Taking AD back costs 15, but the score never goes below 0
+      game.move_cards(MoveSource::Foundation(Suit::Diamonds), MoveTarget::Pile(6)).unwrap();
+      assert_eq!(game.score(), 0);
+
+      // Recycling a draw three deck is free for the first three passes
+      for _ in 0..8 {
+        game.draw();
+      }
+      assert_eq!(game.deck().passes(), 2);
+      assert_eq!(game.score(), 0);
+    }
 
     #[test]
-    fn new() {
-      let game = KlondikeSolitaireGame::new(3);
+    fn timed_scoring() {
+      let mut game = unsorted_game();
+      game.rules.move_time = Some(Duration::from_secs(4));
 
-      // Can't test the specific ordering of cards since the cards should be
-      // shuffled randomly.
+      game.draw();
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      assert_eq!(game.elapsed(), Some(Duration::from_secs(8)));
+      assert_eq!(game.score(), 10);
 
-      assert_eq!(game.deck().draw_count(), 3);
-      assert_eq!(game.deck().len(), 24);
+      game.draw();
+      assert_eq!(game.elapsed(), Some(Duration::from_secs(12)));
+      assert_eq!(game.score(), 8);
 
-      assert!(game.foundation(Suit::Clubs).is_empty());
-      assert_eq!(game.foundation(Suit::Clubs).suit(), Suit::Clubs);
-      assert!(game.foundation(Suit::Spades).is_empty());
-      assert_eq!(game.foundation(Suit::Spades).suit(), Suit::Spades);
-      assert!(game.foundation(Suit::Diamonds).is_empty());
-      assert_eq!(game.foundation(Suit::Diamonds).suit(), Suit::Diamonds);
-      assert!(game.foundation(Suit::Hearts).is_empty());
-      assert_eq!(game.foundation(Suit::Hearts).suit(), Suit::Hearts);
+      assert_eq!(timed_score(10, 100, false), 0);
+      assert_eq!(timed_score(600, 100, true), 580 + 7000);
+      assert_eq!(timed_score(600, 29, true), 596);
 
-      assert_eq!(game.pile(0).len(), 1);
-      assert_eq!(game.pile(1).len(), 2);
-      assert_eq!(game.pile(2).len(), 3);
-      assert_eq!(game.pile(3).len(), 4);
-      assert_eq!(game.pile(4).len(), 5);
-      assert_eq!(game.pile(5).len(), 6);
-      assert_eq!(game.pile(6).len(), 7);
+      game.rules.scoring = Scoring::Vegas;
+      game.score = -47;
+      assert_eq!(game.score(), -47);
+
+      game.rules.move_time = None;
+      assert_eq!(game.elapsed(), None);
     }
 
     #[test]
-    fn from_new() {
-      let cards = new_standard_deck();
-      let game = KlondikeSolitaireGame::from(
-        Deck::from(3, &[], &[], &cards[28..]),
-        [
-          Foundation::new(Suit::Clubs),
-          Foundation::new(Suit::Hearts),
-          Foundation::new(Suit::Spades),
-          Foundation::new(Suit::Diamonds),
-        ], [
-          Pile::from(&cards[0..0], &cards[0..1]),
-          Pile::from(&cards[1..2], &cards[2..3]),
-          Pile::from(&cards[3..5], &cards[5..6]),
-          Pile::from(&cards[6..9], &cards[9..10]),
-          Pile::from(&cards[10..14], &cards[14..15]),
-          Pile::from(&cards[15..20], &cards[20..21]),
-          Pile::from(&cards[21..27], &cards[27..28]),
-        ]
-      );
+    fn vegas_scoring() {
+      let mut game = unsorted_game();
+      game.rules.scoring = Scoring::Vegas;
+      game.score = -52;
 
-      assert_eq!(game.deck().draw_count(), 3);
-      test_deck(game.deck(), &[], &[], &cards[28..]);
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      assert_eq!(game.score(), -47);
 
-      assert!(game.foundation(Suit::Clubs).is_empty());
-      assert_eq!(game.foundation(Suit::Clubs).suit(), Suit::Clubs);
-      assert!(game.foundation(Suit::Spades).is_empty());
-      assert_eq!(game.foundation(Suit::Spades).suit(), Suit::Spades);
-      assert!(game.foundation(Suit::Diamonds).is_empty());
-      assert_eq!(game.foundation(Suit::Diamonds).suit(), Suit::Diamonds);
-      assert!(game.foundation(Suit::Hearts).is_empty());
-      assert_eq!(game.foundation(Suit::Hearts).suit(), Suit::Hearts);
+      game.move_cards(MoveSource::Foundation(Suit::Diamonds), MoveTarget::Pile(6)).unwrap();
+      assert_eq!(game.score(), -52);
+    }
 
-      test_pile("game.piles[0]", game.pile(0), &[], &cards[0..1]);
-      test_pile("game.piles[1]", game.pile(1), &cards[1..2], &cards[2..3]);
-      test_pile("game.piles[2]", game.pile(2), &cards[3..5], &cards[5..6]);
-      test_pile("game.piles[3]", game.pile(3), &cards[6..9], &cards[9..10]);
-      test_pile("game.piles[4]", game.pile(4), &cards[10..14], &cards[14..15]);
-      test_pile("game.piles[5]", game.pile(5), &cards[15..20], &cards[20..21]);
-      test_pile("game.piles[6]", game.pile(6), &cards[21..27], &cards[27..28]);
+    #[test]
+    fn validate() {
+      let mut game = unsorted_game();
+      assert_eq!(game.validate(), Ok(()));
+
+      game.draw();
+      game.move_cards(MoveSource::Pile(0), MoveTarget::Foundation).unwrap();
+      assert_eq!(game.validate(), Ok(()));
+
+      let ad = game.foundations[1].top().unwrap();
+      let mut broken = game.clone();
+      broken.piles[0].visible_cards.push(ad);
+      assert_eq!(broken.validate(), Err(ValidationError::DuplicateCard(ad)));
+
+      let mut broken = game.clone();
+      broken.foundations[1].clear();
+      assert_eq!(broken.validate(), Err(ValidationError::MissingCard(ad)));
+
+      let mut broken = game.clone();
+      let card = broken.piles[1].visible_cards.pop().unwrap();
+      broken.piles[2].visible_cards.push(card);
+      assert_eq!(broken.validate(), Err(ValidationError::PileNotTurned(1)));
+
+      let mut broken = game.clone();
+      let card = broken.piles[2].visible_cards.pop().unwrap();
+      broken.piles[1].visible_cards.push(card);
+      broken.piles[2].visible_cards.push(broken.piles[2].hidden_cards.pop().unwrap());
+      assert_eq!(broken.validate(), Err(ValidationError::PileOrder { pile: 1, card: game.pile(1).top().unwrap(), next: card }));
+
+      let mut broken = game.clone();
+      broken.piles.swap(0, 1);
+      assert_eq!(broken.validate(), Err(ValidationError::PileHiddenOverflow { pile: 0, hidden: 1 }));
+
+      let mut broken = game.clone();
+      broken.foundations.swap(0, 1);
+      assert_eq!(broken.validate(), Err(ValidationError::FoundationSuit { index: 0, suit: Suit::Diamonds }));
+
+      let mut broken = game.clone();
+      broken.deck.visible_count = 4;
+      assert_eq!(broken.validate(), Err(ValidationError::DeckVisible { index: 0, count: 4 }));
+    }
 
-      assert!(! game.is_clear());
+    #[test]
+    fn move_from_str() {
+      let moves = [
+        Move::Draw,
+        Move::Cards(MoveSource::Deck, MoveTarget::Foundation),
+        Move::Cards(MoveSource::Deck, MoveTarget::Pile(3)),
+        Move::Cards(MoveSource::Pile(6), MoveTarget::Pile(0)),
+        Move::Cards(MoveSource::Foundation(Suit::Spades), MoveTarget::Pile(2)),
+        Move::Cards(MoveSource::PileRun { pile: 5, index: 3 }, MoveTarget::Foundation),
+      ];
+
+      for m in moves.iter() {
+        assert_eq!(m.to_string().parse::<Move>(), Ok(*m));
+      }
+
+      assert!("pile7->foundation".parse::<Move>().is_err());
+      assert!("deck->deck".parse::<Move>().is_err());
+      assert!("foundationX->pile0".parse::<Move>().is_err());
+      assert!("deck".parse::<Move>().is_err());
+      assert!("pile7[0]->pile1".parse::<Move>().is_err());
+      assert!("pile1[x]->pile0".parse::<Move>().is_err());
     }
 
     #[test]
-    fn from_clear() {
+    fn legal_moves_empty_deck() {
       let game = KlondikeSolitaireGame::from(
         Deck::from(3, &[], &[], &[]),
         [
@@ -813,33 +3963,48 @@ mod test {
           Foundation::new_full(Suit::Spades),
           Foundation::new_full(Suit::Diamonds),
         ], [
-          Pile::new(),
-          Pile::new(),
-          Pile::new(),
-          Pile::new(),
-          Pile::new(),
-          Pile::new(),
-          Pile::new(),
+          Pile::new(), Pile::new(), Pile::new(), Pile::new(), Pile::new(), Pile::new(), Pile::new(),
         ]
       );
 
-      assert_eq!(game.deck().draw_count(), 3);
-      test_deck(game.deck(), &[], &[], &[]);
+      // Kings can always go back to an empty pile
+      let moves = game.legal_moves();
+      assert_eq!(moves.len(), 4 * 7);
+      assert!(moves.iter().all(|m| match *m {
+        Move::Cards(MoveSource::Foundation(_), MoveTarget::Pile(_)) => true,
+        _ => false,
+      }));
+    }
 
-      assert!(game.foundation(Suit::Clubs).is_full());
-      assert!(game.foundation(Suit::Spades).is_full());
-      assert!(game.foundation(Suit::Diamonds).is_full());
-      assert!(game.foundation(Suit::Hearts).is_full());
+    #[test]
+    fn determinize() {
+      let mut game = unsorted_game();
+      game.draw();
+
+      let known: Vec<Card> = game.deck().visible_cards().to_vec();
+      let det = game.determinize(|c| known.contains(&c), |c| c.reverse());
+
+      // Observable cards are unchanged
+      test_cards("visible", game.deck().visible_cards(), det.deck().visible_cards());
+      for i in 0..NUM_PILES {
+        test_cards(&format!("piles[{}].visible", i), game.pile(i).visible_cards(), det.pile(i).visible_cards());
+        assert_eq!(game.pile(i).hidden_cards().len(), det.pile(i).hidden_cards().len());
+      }
 
-      assert!(game.pile(0).is_empty());
-      assert!(game.pile(1).is_empty());
-      assert!(game.pile(2).is_empty());
-      assert!(game.pile(3).is_empty());
-      assert!(game.pile(4).is_empty());
-      assert!(game.pile(5).is_empty());
-      assert!(game.pile(6).is_empty());
+      // Unknown cards were shuffled: reversed, the last card in the deck is
+      // now the first hidden card.
+      assert_eq!(det.pile(1).hidden_cards()[0], *game.deck().remaining_cards().last().unwrap());
+      assert_eq!(*det.deck().remaining_cards().last().unwrap(), game.pile(1).hidden_cards()[0]);
 
-      assert!(game.is_clear());
+      let all = |g: &KlondikeSolitaireGame| {
+        let mut set: HashSet<Card> = HashSet::new();
+        set.extend(g.deck().waste_cards().iter().chain(g.deck().visible_cards()).chain(g.deck().remaining_cards()));
+        for p in g.piles() {
+          set.extend(p.hidden_cards().iter().chain(p.visible_cards()));
+        }
+        set
+      };
+      assert_eq!(all(&game), all(&det));
     }
   }
 
@@ -951,6 +4116,26 @@ mod test {
         &[card!(Suit::Diamonds, Rank::Number(3)), card!(Suit::Hearts, Rank::Number(2))]);
     }
 
+    #[test]
+    fn try_from_errors() {
+      let queen = card!(Suit::Hearts, Rank::Queen);
+      let three = card!(Suit::Diamonds, Rank::Number(3));
+      assert_eq!(Pile::try_from(&[queen], &[]).err(), Some(KlondikeErr::HiddenWithoutVisible));
+      assert_eq!(Pile::try_from(&[queen; 7], &[three]).err(), Some(KlondikeErr::TooManyHidden(7)));
+      assert_eq!(Pile::try_from(&[queen], &[queen]).err(), Some(KlondikeErr::DuplicateCard(queen)));
+      assert_eq!(Pile::try_from(&[], &[three, card!(Suit::Hearts, Rank::Number(2))]).err(),
+        Some(KlondikeErr::PileOrder { card: three, next: card!(Suit::Hearts, Rank::Number(2)) }));
+      assert_eq!(Pile::try_from(&[], &[card!(Suit::Spades, Rank::Ace), three]).err(),
+        Some(KlondikeErr::PileOrder { card: card!(Suit::Spades, Rank::Ace), next: three }));
+
+      assert_eq!(Pile::try_from(&[queen; 7], &[three]).err().unwrap().to_string(), "pile has 7 hidden cards, max 6");
+      assert_eq!(Pile::try_from(&[], &[three, card!(Suit::Hearts, Rank::Number(2))]).err().unwrap().to_string(), "2H can not be on 3D");
+
+      let pile = Pile::try_from(&[queen], &[three, card!(Suit::Spades, Rank::Number(2))]).unwrap();
+      assert_eq!(pile.hidden_cards(), &[queen]);
+      assert_eq!(pile.visible_cards(), &[three, card!(Suit::Spades, Rank::Number(2))]);
+    }
+
     #[test]
     fn len() {
       let mut pile = Pile::new();
@@ -1213,7 +4398,7 @@ mod test {
         if card.rank() == Rank::King {
           assert!(pile.can_push(card).is_ok());
         } else {
-          assert!(pile.can_push(card) == Err(KlondikeErr::InvalidCard));
+          assert!(pile.can_push(card) == Err(KlondikeErr::InvalidCard(card)));
         }
       }
     }
@@ -1241,7 +4426,7 @@ mod test {
         if card.color() == Color::Red && card.rank() == Rank::Queen {
           assert!(pile.can_push(card).is_ok());
         } else {
-          assert!(pile.can_push(card) == Err(KlondikeErr::InvalidCard));
+          assert!(pile.can_push(card) == Err(KlondikeErr::InvalidCard(card)));
         }
       }
     }
@@ -1257,7 +4442,7 @@ mod test {
         if card.color() == Color::Black && card.rank() == Rank::Ace {
           assert!(pile.can_push(card).is_ok());
         } else {
-          assert!(pile.can_push(card) == Err(KlondikeErr::InvalidCard));
+          assert!(pile.can_push(card) == Err(KlondikeErr::InvalidCard(card)));
         }
       }
     }
@@ -1280,6 +4465,7 @@ mod test {
     fn new_full() {
       let f = Foundation::new_full(Suit::Hearts);
       assert!(f.is_full());
+      assert_eq!(f.len(), 13);
     }
 
     #[test]
@@ -1324,6 +4510,7 @@ mod test {
     #[test]
     fn from() {
       let f = Foundation::from(Suit::Clubs, Some(Rank::Jack));
+      assert_eq!(f.len(), 11);
       assert!(f.top() == Some(card!(Suit::Clubs, Rank::Jack)));
       assert!(f.next_rank() == Some(Rank::Queen));
     }
@@ -1331,7 +4518,7 @@ mod test {
 
   mod deck {
     use super::*;
-    use cards::french::{Suit, Rank};
+    use cards::french::{new_standard_deck, Suit, Rank};
 
     #[test]
     fn new_deck() {
@@ -1372,10 +4559,37 @@ mod test {
     #[test]
     fn draw_when_empty() {
       let mut d = Deck::new(1);
-      d.draw();
+      assert_eq!(d.draw(), DrawResult::Empty);
       assert!(d.is_empty());
     }
 
+    #[test]
+    fn draw_pass_limit() {
+      let mut deck = Deck::new(1);
+      deck.pass_limit = Some(2);
+      deck.reset(&[card!(Suit::Hearts, Rank::Jack), card!(Suit::Hearts, Rank::Queen)]);
+      assert_eq!(deck.passes(), 1);
+      assert_eq!(deck.passes_remaining(), Some(1));
+
+      assert_eq!(deck.draw(), DrawResult::Dealt { visible: &[card!(Suit::Hearts, Rank::Jack)] });
+      assert_eq!(deck.draw(), DrawResult::Dealt { visible: &[card!(Suit::Hearts, Rank::Queen)] });
+      assert!(deck.can_draw());
+
+      assert_eq!(deck.draw(), DrawResult::Recycled);
+      assert_eq!(deck.passes(), 2);
+      assert_eq!(deck.passes_remaining(), Some(0));
+      test_deck(&deck, &[], &[], &[card!(Suit::Hearts, Rank::Jack), card!(Suit::Hearts, Rank::Queen)]);
+
+      deck.draw();
+      deck.draw();
+      assert!(! deck.can_draw());
+
+      // The waste is not turned over again
+      assert_eq!(deck.draw(), DrawResult::Empty);
+      assert_eq!(deck.passes(), 2);
+      test_deck(&deck, &[card!(Suit::Hearts, Rank::Queen)], &[card!(Suit::Hearts, Rank::Jack)], &[]);
+    }
+
     #[test]
     fn draw1_with_single_card() {
       let mut deck = Deck::new(1);
@@ -1659,5 +4873,20 @@ mod test {
         card!(Suit::Clubs, Rank::Queen),
       ], &[], &[]);
     }
+
+    #[test]
+    fn try_from_errors() {
+      let cards = new_standard_deck();
+      assert_eq!(Deck::try_from(0, &[], &[], &cards[..3]).err(), Some(KlondikeErr::DrawCount(0)));
+      assert_eq!(Deck::try_from(25, &[], &[], &cards[..3]).err(), Some(KlondikeErr::DrawCount(25)));
+      assert_eq!(Deck::try_from(1, &[], &cards[..2], &[]).err(), Some(KlondikeErr::TooManyVisible(2)));
+      assert_eq!(Deck::try_from(3, &cards[..10], &[], &cards[10..25]).err(), Some(KlondikeErr::DeckSize(25)));
+      assert_eq!(Deck::try_from(3, &cards[..2], &cards[1..2], &[]).err(), Some(KlondikeErr::DuplicateCard(cards[1])));
+
+      let deck = Deck::try_from(3, &cards[..2], &cards[2..4], &cards[4..10]).unwrap();
+      assert_eq!(deck.waste_cards(), &cards[..2]);
+      assert_eq!(deck.visible_cards(), &cards[2..4]);
+      assert_eq!(deck.remaining_cards(), &cards[4..10]);
+    }
   }
 }
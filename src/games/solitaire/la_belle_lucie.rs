@@ -0,0 +1,331 @@
+//! La Belle Lucie solitaire.
+//!
+//! The whole deck is dealt face up to fans of three cards, seventeen of them
+//! and one more with the last card. The top card of a fan can be moved to
+//! its foundation, or onto the top card of another fan of the same suit and
+//! one rank higher. Only one card moves at a time and an empty fan is never
+//! filled. When stuck, the cards left in the fans can be gathered, shuffled
+//! and dealt again in fans of three, twice by default. The game is won when
+//! every card is on the foundations.
+
+use cards::french::{self, Suit};
+use games::solitaire::SolitaireGame;
+use games::solitaire::klondike::{Card, Foundation};
+use games::solitaire::redeal::{deal_piles, Redeals};
+use std::collections::HashSet;
+use std::fmt;
+
+pub const FAN_LEN: usize = 3;
+pub const NUM_FOUNDATIONS: usize = 4;
+const NUM_CARDS: usize = 52;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LaBelleLucieRules {
+  /// Number of times the fans can be gathered and dealt again.
+  pub redeals: usize,
+}
+
+impl LaBelleLucieRules {
+  /// Two redeals.
+  pub fn new() -> LaBelleLucieRules {
+    LaBelleLucieRules {
+      redeals: 2,
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LaBelleLucieErr {
+  /// The fan is empty or does not exist.
+  EmptyFan,
+  /// The card does not go on the target.
+  InvalidMove,
+  /// No redeal is left.
+  NoRedeal,
+}
+
+pub type LaBelleLucieResult<T> = Result<T, LaBelleLucieErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LaBelleLucieMove {
+  /// Move the top card of fan `source` onto fan `target`.
+  Build { source: u8, target: u8 },
+  /// Move the top card of a fan to its foundation.
+  Found(u8),
+  /// Gather the fans, shuffle them and deal them again.
+  Redeal,
+}
+
+impl fmt::Display for LaBelleLucieMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      LaBelleLucieMove::Build { source, target } => write!(f, "{}->{}", source, target),
+      LaBelleLucieMove::Found(source) => write!(f, "{}->f", source),
+      LaBelleLucieMove::Redeal => write!(f, "redeal"),
+    }
+  }
+}
+
+/// Whether `card` can be moved onto `target` in a fan.
+pub fn builds_on(card: Card, target: Card) -> bool {
+  card.suit() == target.suit() && card.rank().value() + 1 == target.rank().value()
+}
+
+#[derive(Clone)]
+pub struct LaBelleLucieGame {
+  /// Each from the bottom card up.
+  fans: Vec<Vec<Card>>,
+  /// In the order of `Suit::standard_iter`.
+  foundations: Vec<Foundation>,
+  redeals: Redeals,
+  moves_made: usize,
+}
+
+impl LaBelleLucieGame {
+  /// New game with a standard deck arranged by `shuffle`, dealt one fan
+  /// after the other from the bottom card up. The redeals shuffle with a
+  /// seed taken from the deal, so the same deal always redeals the same way.
+  pub fn new<F>(rules: LaBelleLucieRules, mut shuffle: F) -> LaBelleLucieGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+    let seed = Redeals::seed_of(&cards);
+
+    LaBelleLucieGame {
+      fans: deal_piles(cards, FAN_LEN),
+      foundations: Suit::standard_iter().map(|s| Foundation::new(*s)).collect(),
+      redeals: Redeals::new(rules.redeals, seed),
+      moves_made: 0,
+    }
+  }
+
+  /// The fans left, empty ones included until the next redeal.
+  pub fn fans(&self) -> &[Vec<Card>] {
+    &self.fans
+  }
+
+  pub fn fan(&self, index: usize) -> &[Card] {
+    &self.fans[index]
+  }
+
+  pub fn foundations(&self) -> &[Foundation] {
+    &self.foundations
+  }
+
+  pub fn foundation(&self, suit: Suit) -> &Foundation {
+    self.foundations.iter().find(|f| f.suit() == suit).unwrap()
+  }
+
+  pub fn redeals(&self) -> Redeals {
+    self.redeals
+  }
+
+  pub fn founded(&self) -> usize {
+    self.foundations.iter().map(|f| f.len()).sum()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.founded() == NUM_CARDS
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Cards on the foundations.
+  pub fn score(&self) -> i32 {
+    self.founded() as i32
+  }
+
+  pub fn can_apply(&self, m: LaBelleLucieMove) -> LaBelleLucieResult<()> {
+    let source = match m {
+      LaBelleLucieMove::Build { source, .. } | LaBelleLucieMove::Found(source) => source as usize,
+      LaBelleLucieMove::Redeal if self.redeals.left() == 0 => return Err(LaBelleLucieErr::NoRedeal),
+      LaBelleLucieMove::Redeal => return Ok(()),
+    };
+
+    let card = match self.fans.get(source).and_then(|f| f.last()) {
+      Some(card) => *card,
+      None => return Err(LaBelleLucieErr::EmptyFan),
+    };
+
+    let legal = match m {
+      LaBelleLucieMove::Build { target, .. } => match self.fans.get(target as usize).and_then(|f| f.last()) {
+        Some(top) => builds_on(card, *top),
+        None => false,
+      },
+      _ => self.foundation(card.suit()).can_push(card),
+    };
+
+    if legal { Ok(()) } else { Err(LaBelleLucieErr::InvalidMove) }
+  }
+
+  pub fn apply(&mut self, m: LaBelleLucieMove) -> LaBelleLucieResult<()> {
+    self.can_apply(m)?;
+
+    match m {
+      LaBelleLucieMove::Build { source, target } => {
+        let card = self.fans[source as usize].pop().unwrap();
+        self.fans[target as usize].push(card);
+      },
+      LaBelleLucieMove::Found(source) => {
+        let card = self.fans[source as usize].pop().unwrap();
+        self.foundations.iter_mut().find(|f| f.suit() == card.suit()).unwrap().push();
+      },
+      LaBelleLucieMove::Redeal => {
+        self.redeals.redeal(&mut self.fans, FAN_LEN);
+      },
+    }
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  /// Every move that would currently succeed, foundation moves first and
+  /// the redeal last.
+  pub fn legal_moves(&self) -> Vec<LaBelleLucieMove> {
+    let mut moves: Vec<LaBelleLucieMove> = (0..self.fans.len()).map(|i| LaBelleLucieMove::Found(i as u8)).collect();
+
+    for source in 0..self.fans.len() {
+      for target in 0..self.fans.len() {
+        moves.push(LaBelleLucieMove::Build { source: source as u8, target: target as u8 });
+      }
+    }
+
+    moves.push(LaBelleLucieMove::Redeal);
+    moves.retain(|m| self.can_apply(*m).is_ok());
+    moves
+  }
+
+  /// Check that the game holds every card of the deck once.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    let cards = self.fans.iter().flat_map(|f| f.iter().cloned())
+      .chain(self.foundations.iter().flat_map(|f| f.cards()));
+
+    for card in cards {
+      if ! seen.insert(card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    match french::new_standard_deck().into_iter().find(|c| ! seen.contains(c)) {
+      Some(card) => Err(format!("missing card {}", card)),
+      None => Ok(()),
+    }
+  }
+
+  /// The fans from bottom to top, the top card of each foundation and the
+  /// redeals left, e.g. `KS 5H 2D|...|- / AD - - 3S / 2`.
+  pub fn notation(&self) -> String {
+    let fans: Vec<String> = self.fans.iter().map(|f| {
+      let cards: Vec<String> = f.iter().map(|c| c.to_string()).collect();
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
+    }).collect();
+
+    let foundations: Vec<String> = self.foundations.iter().map(|f| f.top().map_or(String::from("-"), |c| c.to_string())).collect();
+
+    format!("{} / {} / {}", fans.join("|"), foundations.join(" "), self.redeals.left())
+  }
+}
+
+impl SolitaireGame for LaBelleLucieGame {
+  fn is_clear(&self) -> bool {
+    LaBelleLucieGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    LaBelleLucieGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    LaBelleLucieGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = LaBelleLucieGame::new(LaBelleLucieRules::new(), |c| seeded_shuffle(1, c));
+
+    let lens: Vec<usize> = game.fans().iter().map(|f| f.len()).collect();
+    assert_eq!(lens.len(), 18);
+    assert!(lens[..17].iter().all(|n| *n == 3));
+    assert_eq!(lens[17], 1);
+    assert_eq!(game.redeals().left(), 2);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn moves() {
+    let mut game = LaBelleLucieGame::new(LaBelleLucieRules::new(), |_| ());
+    game.fans = vec![cards(&["KD", "AH"]), cards(&["3S"]), cards(&["2C"]), cards(&["2S"]), Vec::new()];
+
+    assert_eq!(game.legal_moves(), vec![
+      LaBelleLucieMove::Found(0),
+      LaBelleLucieMove::Build { source: 3, target: 1 },
+      LaBelleLucieMove::Redeal,
+    ]);
+    assert_eq!(game.apply(LaBelleLucieMove::Build { source: 2, target: 1 }), Err(LaBelleLucieErr::InvalidMove), "2C on 3S");
+    assert_eq!(game.apply(LaBelleLucieMove::Build { source: 3, target: 4 }), Err(LaBelleLucieErr::InvalidMove));
+    assert_eq!(game.apply(LaBelleLucieMove::Found(4)), Err(LaBelleLucieErr::EmptyFan));
+
+    game.apply(LaBelleLucieMove::Found(0)).unwrap();
+    game.apply(LaBelleLucieMove::Build { source: 3, target: 1 }).unwrap();
+    assert_eq!(game.fan(1), &cards(&["3S", "2S"])[..]);
+    assert_eq!(game.score(), 1);
+    assert_eq!(game.moves_made(), 2);
+  }
+
+  #[test]
+  fn redeals() {
+    let mut game = LaBelleLucieGame::new(LaBelleLucieRules::new(), |c| seeded_shuffle(3, c));
+    let mut copy = game.clone();
+
+    game.apply(LaBelleLucieMove::Redeal).unwrap();
+    copy.apply(LaBelleLucieMove::Redeal).unwrap();
+    assert_eq!(game.notation(), copy.notation());
+    assert_eq!(game.validate(), Ok(()));
+
+    game.apply(LaBelleLucieMove::Redeal).unwrap();
+    assert_eq!(game.apply(LaBelleLucieMove::Redeal), Err(LaBelleLucieErr::NoRedeal));
+    assert!(! game.legal_moves().contains(&LaBelleLucieMove::Redeal));
+  }
+
+  #[test]
+  fn redeal_keeps_foundations() {
+    let mut game = LaBelleLucieGame::new(LaBelleLucieRules::new(), |_| ());
+    game.fans = vec![cards(&["KD", "AH"]), cards(&["3S"]), cards(&["2C", "5D"]), Vec::new()];
+    game.apply(LaBelleLucieMove::Found(0)).unwrap();
+    game.apply(LaBelleLucieMove::Redeal).unwrap();
+
+    assert_eq!(game.fans().iter().map(|f| f.len()).collect::<Vec<usize>>(), vec![3, 1]);
+    assert_eq!(game.foundation(Suit::Hearts).len(), 1);
+    assert_eq!(game.redeals().left(), 1);
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[6]);
+    let mut game = LaBelleLucieGame::new(LaBelleLucieRules::new(), |c| seeded_shuffle(6, c));
+
+    for _ in 0..300 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+    }
+  }
+}
@@ -0,0 +1,295 @@
+//! Loosely formatted Klondike layouts, for positions transcribed by hand from
+//! a physical deal or another program.
+//!
+//! Each line is `label: cards`. Labels are case insensitive and anything after
+//! the first word is ignored, so `Pile 3:` and `pile:` are the same:
+//!
+//! ```text
+//! # comments and blank lines are skipped
+//! draw: 3
+//! foundations: 2H AS
+//! pile: 7H
+//! pile: (QS) 10D
+//! pile: [3C 8H] 5S 4H
+//! pile: -
+//! ...
+//! waste: 9C 4D | KD
+//! stock: 2D 6S ...
+//! ```
+//!
+//! * `pile`, `column` or `tableau`: one line per pile, left to right, seven in
+//!   all; errors number them from 0 like the rest of the game. Face down cards come first, in parentheses or brackets, bottom
+//!   card first; `-` or nothing is an empty pile.
+//! * `foundation` or `foundations`: the top card of each started foundation.
+//! * `waste`: the face up cards beside the stock, bottom card first. Cards
+//!   after a `|` can be played; without a `|` only the top card can.
+//! * `stock`, `deck` or `talon`: the face down cards, next card drawn first.
+//! * `draw`: cards turned by each draw, 1 if not given.
+//!
+//! Cards are a rank (`A`, `2`-`10`, `T`, `J`, `Q`, `K`) followed by a suit
+//! letter (`S`, `H`, `D`, `C`) or symbol (`♠`, `♥`, `♦`, `♣`), in any case.
+//! Face down pile cards and stock cards that are not known can be written as
+//! `?`; they are filled with the cards missing from the layout, in no
+//! particular order, so the game should be played over determinized copies.
+
+use cards::french::{self, Rank, Suit};
+use games::solitaire::klondike::{Card, Deck, Foundation, KlondikeSolitaireGame, Pile};
+use std::collections::HashSet;
+
+/// Parse a layout into a game. The layout must hold all 52 cards and be a
+/// position `KlondikeSolitaireGame::try_from` accepts.
+pub fn parse_layout(text: &str) -> Result<KlondikeSolitaireGame, String> {
+  let mut draw_count = 1;
+  let mut foundations: Vec<Card> = Vec::new();
+  let mut piles: Vec<(Vec<Option<Card>>, Vec<Card>)> = Vec::new();
+  let mut waste: Vec<Card> = Vec::new();
+  let mut visible: Option<Vec<Card>> = None;
+  let mut stock: Vec<Option<Card>> = Vec::new();
+
+  for (i, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let error = |e: String| format!("line {}: {}", i + 1, e);
+    let (label, value) = match line.find(':') {
+      Some(colon) => (line[..colon].split_whitespace().next().unwrap_or("").to_lowercase(), line[colon + 1..].trim()),
+      None => { return Err(error(String::from("expected label: cards"))); },
+    };
+
+    match &label[..] {
+      "draw" => {
+        draw_count = match value.parse::<u8>() {
+          Ok(n) if n >= 1 && n <= 24 => n,
+          _ => { return Err(error(format!("invalid draw count {}", value))); },
+        };
+      },
+      "foundation" | "foundations" => foundations.extend(cards(value).map_err(&error)?),
+      "pile" | "column" | "tableau" => piles.push(pile(value).map_err(&error)?),
+      "waste" => {
+        let mut parts = value.splitn(2, '|');
+        waste = cards(parts.next().unwrap()).map_err(&error)?;
+        visible = parts.next().map(cards).map_or(Ok(None), |v| v.map(Some)).map_err(&error)?;
+      },
+      "stock" | "deck" | "talon" => stock = unknown_cards(value).map_err(&error)?,
+      _ => { return Err(error(format!("unknown label {}", label))); },
+    }
+  }
+
+  if piles.len() != 7 {
+    return Err(format!("expected 7 piles, found {}", piles.len()));
+  }
+
+  let visible = match visible {
+    Some(visible) => visible,
+    None => {
+      let top = waste.len().saturating_sub(1);
+      waste.split_off(top)
+    },
+  };
+
+  let mut seen = HashSet::new();
+  let all = foundation_cards(&foundations)?.into_iter()
+    .chain(piles.iter().flat_map(|&(ref h, ref v)| h.iter().filter_map(|c| *c).chain(v.iter().cloned())))
+    .chain(waste.iter().chain(&visible).cloned())
+    .chain(stock.iter().filter_map(|c| *c));
+  for card in all {
+    if ! seen.insert(card) {
+      return Err(format!("duplicate card {}", card));
+    }
+  }
+
+  // Fill the unknown cards with the missing ones
+  let missing: Vec<Card> = french::new_standard_deck().into_iter().filter(|c| ! seen.contains(c)).collect();
+  let unknown = piles.iter().flat_map(|&(ref h, _)| h).chain(&stock).filter(|c| c.is_none()).count();
+  if unknown > missing.len() {
+    return Err(format!("{} unknown cards, more than the {} missing cards", unknown, missing.len()));
+  }
+
+  let mut missing = missing.into_iter();
+  let piles: Vec<(Vec<Card>, Vec<Card>)> = piles.iter()
+    .map(|&(ref hidden, ref face_up)| (hidden.iter().map(|c| c.or_else(|| missing.next()).unwrap()).collect(), face_up.clone()))
+    .collect();
+  let stock: Vec<Card> = stock.iter().map(|c| c.or_else(|| missing.next()).unwrap()).collect();
+
+  // Deck::from and Pile::from panic on these, check them first
+  if visible.len() > draw_count as usize {
+    return Err(format!("{} playable waste cards, more than the draw count {}", visible.len(), draw_count));
+  }
+
+  if waste.len() + visible.len() + stock.len() > 24 {
+    return Err(format!("{} cards in the waste and stock, at most 24", waste.len() + visible.len() + stock.len()));
+  }
+
+  for (i, &(ref hidden, ref face_up)) in piles.iter().enumerate() {
+    if ! hidden.is_empty() && face_up.is_empty() {
+      return Err(format!("pile {} has face down cards but no face up card", i));
+    }
+
+    if hidden.len() > 6 {
+      return Err(format!("pile {} has {} face down cards, at most 6", i, hidden.len()));
+    }
+
+    if let Some(w) = face_up.windows(2).find(|w| ! builds_on(w[1], w[0])) {
+      return Err(format!("pile {}: {} can not be on {}", i, w[1], w[0]));
+    }
+  }
+
+  let deck = Deck::from(draw_count, &waste, &visible, &stock);
+  let mut suits = Suit::standard_iter().map(|suit| {
+    Foundation::from(*suit, foundations.iter().find(|c| c.suit() == *suit).map(|c| c.rank()))
+  });
+  let foundations = [suits.next().unwrap(), suits.next().unwrap(), suits.next().unwrap(), suits.next().unwrap()];
+  let mut piles = piles.iter().map(|&(ref hidden, ref face_up)| Pile::from(hidden, face_up));
+  let piles = [
+    piles.next().unwrap(), piles.next().unwrap(), piles.next().unwrap(), piles.next().unwrap(),
+    piles.next().unwrap(), piles.next().unwrap(), piles.next().unwrap(),
+  ];
+
+  KlondikeSolitaireGame::try_from(deck, foundations, piles).map_err(|e| e.to_string())
+}
+
+fn card(token: &str) -> Result<Card, String> {
+  let normalized = token.replace('♠', "S").replace('♥', "H").replace('♦', "D").replace('♣', "C");
+  normalized.parse::<Card>().map_err(|_| format!("invalid card {}", token))
+}
+
+fn cards(text: &str) -> Result<Vec<Card>, String> {
+  text.split(|c: char| c == ',' || c.is_whitespace())
+    .filter(|t| ! t.is_empty() && *t != "-")
+    .map(card)
+    .collect()
+}
+
+/// Cards that may be `?`, unknown.
+fn unknown_cards(text: &str) -> Result<Vec<Option<Card>>, String> {
+  text.split(|c: char| c == ',' || c.is_whitespace())
+    .filter(|t| ! t.is_empty() && *t != "-")
+    .map(|t| if t == "?" { Ok(None) } else { card(t).map(Some) })
+    .collect()
+}
+
+/// Face down and face up cards of a pile.
+fn pile(text: &str) -> Result<(Vec<Option<Card>>, Vec<Card>), String> {
+  let text = text.trim();
+
+  match text.find(|c| c == '(' || c == '[') {
+    Some(open) => {
+      let close = text.find(|c| c == ')' || c == ']').ok_or_else(|| String::from("unclosed face down cards"))?;
+
+      if open != 0 || close < open {
+        return Err(String::from("face down cards must come first"));
+      }
+
+      Ok((unknown_cards(&text[1..close])?, cards(&text[close + 1..])?))
+    },
+    None => Ok((Vec::new(), cards(text)?)),
+  }
+}
+
+/// Every card of each suit up to the given top cards.
+fn foundation_cards(tops: &[Card]) -> Result<Vec<Card>, String> {
+  let mut cards = Vec::new();
+  let mut suits = HashSet::new();
+
+  for top in tops {
+    if ! suits.insert(top.suit()) {
+      return Err(format!("more than one foundation for {}", top.suit()));
+    }
+
+    for rank in Rank::standard_iter().take_while(|r| **r <= top.rank()) {
+      cards.push(Card::new(top.suit(), *rank));
+    }
+  }
+
+  Ok(cards)
+}
+
+fn builds_on(card: Card, below: Card) -> bool {
+  let index = |r: Rank| Rank::standard_iter().position(|x| *x == r).unwrap();
+  card.color() != below.color() && index(card.rank()) + 1 == index(below.rank())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use games::solitaire::klondike::{MoveSource, MoveTarget};
+
+  static LAYOUT: &'static str = "
+    # pasted from a photo
+    draw: 3
+    foundations: 2h, A♠
+    Pile 1: KH QC
+    Pile 2: (QS) 10D
+    Pile 3: [3C 8H] 5S 4H
+    Pile 4: -
+    Pile 5: (2S 3S 4S) QD
+    Pile 6: (3H 5H 6H 7H 9H) JC
+    Pile 7: (AC 2C 4C 5C 6C 7C) 8C
+    Waste: 9C 10C | JH
+    Stock: AD 2D 3D 4D 5D 6D 7D 8D 9D JD KD 6S 7S 8S 9S 10S JS KS 10H QH KC
+  ";
+
+  #[test]
+  fn parse() {
+    let mut game = parse_layout(LAYOUT).unwrap();
+
+    assert_eq!(game.deck().draw_count(), 3);
+    assert_eq!(game.foundation(Suit::Hearts).top(), Some(Card::new(Suit::Hearts, Rank::Number(2))));
+    assert_eq!(game.foundation(Suit::Spades).top(), Some(Card::new(Suit::Spades, Rank::Ace)));
+    assert!(game.foundation(Suit::Clubs).is_empty());
+    assert_eq!(game.pile(2).hidden_cards(), &[Card::new(Suit::Clubs, Rank::Number(3)), Card::new(Suit::Hearts, Rank::Number(8))]);
+    assert_eq!(game.pile(2).visible_cards(), &[Card::new(Suit::Spades, Rank::Number(5)), Card::new(Suit::Hearts, Rank::Number(4))]);
+    assert!(game.pile(3).is_empty());
+    assert_eq!(game.deck().waste_cards(), &[Card::new(Suit::Clubs, Rank::Number(9)), Card::new(Suit::Clubs, Rank::Number(10))]);
+    assert_eq!(game.deck().visible_cards(), &[Card::new(Suit::Hearts, Rank::Jack)]);
+    assert_eq!(game.deck().remaining_cards()[0], Card::new(Suit::Diamonds, Rank::Ace));
+
+    assert!(game.move_cards(MoveSource::Deck, MoveTarget::Pile(0)).is_ok(), "JH on QC");
+  }
+
+  #[test]
+  fn parse_unknown() {
+    let text = LAYOUT.replace("(2S 3S 4S)", "(? ? ?)").replace("2D 3D 4D 5D", "? ? ? ?");
+    let game = parse_layout(&text).unwrap();
+
+    assert_eq!(game.pile(4).hidden_cards().len(), 3);
+    assert_eq!(game.deck().remaining_cards().len(), 21);
+    assert_eq!(game.validate(), Ok(()));
+
+    assert_eq!(parse_layout(&text.replace("Pile 1: KH QC", "Pile 1: ?")).err(), Some(String::from("line 5: invalid card ?")));
+    assert_eq!(parse_layout(&text.replace("KC", "KC ?")).err(), Some(String::from("8 unknown cards, more than the 7 missing cards")));
+  }
+
+  #[test]
+  fn parse_waste_top() {
+    let game = parse_layout(&LAYOUT.replace("draw: 3", "").replace("9C 10C | JH", "9C 10C JH")).unwrap();
+
+    assert_eq!(game.deck().draw_count(), 1);
+    assert_eq!(game.deck().visible_cards(), &[Card::new(Suit::Hearts, Rank::Jack)]);
+    assert_eq!(game.deck().waste_cards().len(), 2);
+  }
+
+  #[test]
+  fn errors() {
+    let cases = [
+      (LAYOUT.replace("# pasted", "pasted"), "line 2: expected label: cards"),
+      (LAYOUT.replace("Waste:", "Hand:"), "line 12: unknown label hand"),
+      (LAYOUT.replace("draw: 3", "draw: 0"), "line 3: invalid draw count 0"),
+      (LAYOUT.replace("5S 4H", "5S 4X"), "line 7: invalid card 4X"),
+      (LAYOUT.replace("[3C 8H]", "[3C 8H"), "line 7: unclosed face down cards"),
+      (LAYOUT.replace("Pile 4: -\n", ""), "expected 7 piles, found 6"),
+      (LAYOUT.replace("KD 6S", "KD 9C 6S"), "duplicate card 9C"),
+      (LAYOUT.replace("2h, ", "2h, 3h "), "more than one foundation for H"),
+      (LAYOUT.replace("draw: 3", "draw: 1").replace("9C 10C | JH", "9C | 10C JH"), "2 playable waste cards, more than the draw count 1"),
+      (LAYOUT.replace("Pile 2: (QS) 10D", "Pile 2: (QS 10D)"), "pile 1 has face down cards but no face up card"),
+      (LAYOUT.replace("KH QC", "QC KH"), "pile 0: KH can not be on QC"),
+      (LAYOUT.replace("Stock: AD", "Stock:"), "missing card AD"),
+    ];
+
+    for &(ref text, expected) in cases.iter() {
+      assert_eq!(parse_layout(text).err(), Some(String::from(expected)));
+    }
+  }
+}
@@ -1 +1,29 @@
+pub mod accordion;
+pub mod bakers_dozen;
+pub mod belief;
+pub mod double_klondike;
+pub mod easthaven;
+pub mod freecell;
+pub mod golf;
 pub mod klondike;
+pub mod la_belle_lucie;
+pub mod layout;
+pub mod pyramid;
+pub mod redeal;
+pub mod replay;
+pub mod row_stock;
+pub mod sampler;
+pub mod scorpion;
+pub mod spider;
+pub mod tracker;
+pub mod yukon;
+
+/// What the code that plays many games needs to know about the outcome of
+/// one solitaire game, whatever its kind.
+pub trait SolitaireGame {
+  fn is_clear(&self) -> bool;
+
+  fn score(&self) -> i32;
+
+  fn moves_made(&self) -> usize;
+}
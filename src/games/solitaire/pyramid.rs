@@ -0,0 +1,446 @@
+//! Pyramid solitaire.
+//!
+//! Twenty-eight cards are dealt face up in a pyramid of seven rows, each row
+//! overlapping the one above, and the rest form the stock. A card is free
+//! when no card of the row below covers it; the top card of the waste is free
+//! too. Two free cards whose ranks add up to 13 are removed together, and a
+//! king, worth 13 alone, is removed by itself. Cards are drawn from the stock
+//! to the waste one at a time, and the waste may be turned over into the
+//! stock a limited number of times. The game is won when the pyramid is
+//! cleared.
+
+use cards::french::{self, FrenchPlayingCard};
+use games::solitaire::SolitaireGame;
+use std::collections::HashSet;
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_ROWS: usize = 7;
+pub const PYRAMID_SIZE: usize = NUM_ROWS * (NUM_ROWS + 1) / 2;
+const NUM_CARDS: usize = 52;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PyramidRules {
+  /// Number of times the stock can be gone through, the first included.
+  pub passes: u8,
+}
+
+impl PyramidRules {
+  /// Three passes through the stock.
+  pub fn new() -> PyramidRules {
+    PyramidRules {
+      passes: 3,
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PyramidErr {
+  /// The stock is empty.
+  EmptyStock,
+  /// The stock can not be redealt: it is not empty or there are no passes
+  /// left.
+  NoRedeal,
+  /// A card is covered or missing.
+  NotFree,
+  /// The ranks do not add up to 13.
+  NotThirteen,
+}
+
+pub type PyramidResult<T> = Result<T, PyramidErr>;
+
+/// A free card to be removed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Place {
+  /// Card of the pyramid by index, row after row from the top.
+  Pyramid(u8),
+  /// Top of the waste.
+  Waste,
+}
+
+impl fmt::Display for Place {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Place::Pyramid(i) => write!(f, "{}", i),
+      Place::Waste => write!(f, "w"),
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PyramidMove {
+  /// Draw a card from the stock to the waste.
+  Draw,
+  /// Turn the waste over into the empty stock.
+  Redeal,
+  /// Remove a king.
+  King(Place),
+  /// Remove two cards adding up to 13.
+  Pair(Place, Place),
+}
+
+impl fmt::Display for PyramidMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      PyramidMove::Draw => write!(f, "draw"),
+      PyramidMove::Redeal => write!(f, "redeal"),
+      PyramidMove::King(p) => write!(f, "{}", p),
+      PyramidMove::Pair(a, b) => write!(f, "{}+{}", a, b),
+    }
+  }
+}
+
+/// Row and column of the pyramid card `index`.
+pub fn position(index: usize) -> (usize, usize) {
+  let mut row = 0;
+  while (row + 1) * (row + 2) / 2 <= index {
+    row += 1;
+  }
+
+  (row, index - row * (row + 1) / 2)
+}
+
+/// Index of the pyramid card at `row` and `column`.
+pub fn index(row: usize, column: usize) -> usize {
+  row * (row + 1) / 2 + column
+}
+
+/// Indices of the two cards of the row below that cover card `index`, none
+/// for the bottom row.
+pub fn covered_by(index: usize) -> Option<(usize, usize)> {
+  let (row, column) = position(index);
+
+  if row + 1 < NUM_ROWS {
+    Some((self::index(row + 1, column), self::index(row + 1, column + 1)))
+  } else {
+    None
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyramidGame {
+  rules: PyramidRules,
+  pyramid: [Option<Card>; PYRAMID_SIZE],
+  /// Cards drawn from the end.
+  stock: Vec<Card>,
+  waste: Vec<Card>,
+  passes: u8,
+  moves_made: usize,
+}
+
+impl PyramidGame {
+  /// New game with a standard deck arranged by `shuffle`. The first cards
+  /// go to the pyramid from the top row down and the rest to the stock, the
+  /// first of them drawn first.
+  pub fn new<F>(rules: PyramidRules, mut shuffle: F) -> PyramidGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut pyramid = [None; PYRAMID_SIZE];
+    for (slot, card) in pyramid.iter_mut().zip(cards.iter()) {
+      *slot = Some(*card);
+    }
+
+    let mut stock = cards.split_off(PYRAMID_SIZE);
+    stock.reverse();
+
+    PyramidGame {
+      rules: rules,
+      pyramid: pyramid,
+      stock: stock,
+      waste: Vec::new(),
+      passes: 1,
+      moves_made: 0,
+    }
+  }
+
+  pub fn rules(&self) -> PyramidRules {
+    self.rules
+  }
+
+  /// Cards of the pyramid by index, `None` once removed.
+  pub fn pyramid(&self) -> &[Option<Card>] {
+    &self.pyramid
+  }
+
+  pub fn stock_len(&self) -> usize {
+    self.stock.len()
+  }
+
+  pub fn waste(&self) -> &[Card] {
+    &self.waste
+  }
+
+  /// Passes through the stock started so far, the first included.
+  pub fn passes(&self) -> u8 {
+    self.passes
+  }
+
+  pub fn pyramid_len(&self) -> usize {
+    self.pyramid.iter().filter(|c| c.is_some()).count()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.pyramid_len() == 0
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Cards removed, from the pyramid, the stock or the waste.
+  pub fn score(&self) -> i32 {
+    (NUM_CARDS - self.pyramid_len() - self.stock.len() - self.waste.len()) as i32
+  }
+
+  /// Whether pyramid card `index` is there and not covered.
+  pub fn is_free(&self, index: usize) -> bool {
+    index < PYRAMID_SIZE && self.pyramid[index].is_some() && match covered_by(index) {
+      Some((left, right)) => self.pyramid[left].is_none() && self.pyramid[right].is_none(),
+      None => true,
+    }
+  }
+
+  /// The card at `place` if it is free.
+  pub fn free_card(&self, place: Place) -> Option<Card> {
+    match place {
+      Place::Pyramid(i) if self.is_free(i as usize) => self.pyramid[i as usize],
+      Place::Pyramid(_) => None,
+      Place::Waste => self.waste.last().cloned(),
+    }
+  }
+
+  /// Every free place, the pyramid first.
+  pub fn free_places(&self) -> Vec<Place> {
+    let mut places: Vec<Place> = (0..PYRAMID_SIZE).filter(|&i| self.is_free(i)).map(|i| Place::Pyramid(i as u8)).collect();
+
+    if ! self.waste.is_empty() {
+      places.push(Place::Waste);
+    }
+
+    places
+  }
+
+  pub fn can_apply(&self, m: PyramidMove) -> PyramidResult<()> {
+    match m {
+      PyramidMove::Draw if self.stock.is_empty() => Err(PyramidErr::EmptyStock),
+      PyramidMove::Draw => Ok(()),
+      PyramidMove::Redeal if self.stock.is_empty() && self.passes < self.rules.passes && ! self.waste.is_empty() => Ok(()),
+      PyramidMove::Redeal => Err(PyramidErr::NoRedeal),
+      PyramidMove::King(place) => match self.free_card(place) {
+        Some(card) if card.rank().value() == 13 => Ok(()),
+        Some(_) => Err(PyramidErr::NotThirteen),
+        None => Err(PyramidErr::NotFree),
+      },
+      PyramidMove::Pair(a, b) => match (self.free_card(a), self.free_card(b)) {
+        _ if a == b => Err(PyramidErr::NotFree),
+        (Some(x), Some(y)) if x.rank().value() + y.rank().value() == 13 => Ok(()),
+        (Some(_), Some(_)) => Err(PyramidErr::NotThirteen),
+        _ => Err(PyramidErr::NotFree),
+      },
+    }
+  }
+
+  pub fn apply(&mut self, m: PyramidMove) -> PyramidResult<()> {
+    self.can_apply(m)?;
+
+    match m {
+      PyramidMove::Draw => {
+        let card = self.stock.pop().unwrap();
+        self.waste.push(card);
+      },
+      PyramidMove::Redeal => {
+        self.stock = self.waste.drain(..).rev().collect();
+        self.passes += 1;
+      },
+      PyramidMove::King(place) => self.remove(place),
+      PyramidMove::Pair(a, b) => {
+        self.remove(a);
+        self.remove(b);
+      },
+    }
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  fn remove(&mut self, place: Place) {
+    match place {
+      Place::Pyramid(i) => self.pyramid[i as usize] = None,
+      Place::Waste => { self.waste.pop(); },
+    }
+  }
+
+  /// Every move that would currently succeed, removals first.
+  pub fn legal_moves(&self) -> Vec<PyramidMove> {
+    let places = self.free_places();
+    let mut moves = Vec::new();
+
+    for (i, a) in places.iter().enumerate() {
+      if self.can_apply(PyramidMove::King(*a)).is_ok() {
+        moves.push(PyramidMove::King(*a));
+      }
+
+      for b in places[i + 1..].iter() {
+        if self.can_apply(PyramidMove::Pair(*a, *b)).is_ok() {
+          moves.push(PyramidMove::Pair(*a, *b));
+        }
+      }
+    }
+
+    for m in [PyramidMove::Draw, PyramidMove::Redeal].iter() {
+      if self.can_apply(*m).is_ok() {
+        moves.push(*m);
+      }
+    }
+
+    moves
+  }
+
+  /// Check that no card is in the game twice.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    for card in self.pyramid.iter().filter_map(|c| c.as_ref()).chain(self.stock.iter()).chain(self.waste.iter()) {
+      if ! seen.insert(*card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    if self.passes > self.rules.passes {
+      return Err(format!("{} passes of {}", self.passes, self.rules.passes));
+    }
+
+    Ok(())
+  }
+
+  /// The rows of the pyramid from the top, `-` for removed cards, then the
+  /// stock size and the waste from the bottom, e.g. `KS|- 5H|... / 20 / 4C 9D`.
+  pub fn notation(&self) -> String {
+    let rows: Vec<String> = (0..NUM_ROWS).map(|row| {
+      let cards: Vec<String> = (0..row + 1)
+        .map(|column| self.pyramid[index(row, column)].map_or(String::from("-"), |c| c.to_string()))
+        .collect();
+      cards.join(" ")
+    }).collect();
+
+    let waste: Vec<String> = self.waste.iter().map(|c| c.to_string()).collect();
+
+    format!("{} / {} / {}", rows.join("|"), self.stock.len(), if waste.is_empty() { String::from("-") } else { waste.join(" ") })
+  }
+}
+
+impl SolitaireGame for PyramidGame {
+  fn is_clear(&self) -> bool {
+    PyramidGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    PyramidGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    PyramidGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn layout() {
+    assert_eq!(position(0), (0, 0));
+    assert_eq!(position(2), (1, 1));
+    assert_eq!(position(27), (6, 6));
+    assert_eq!(index(3, 2), 8);
+    assert_eq!(covered_by(0), Some((1, 2)));
+    assert_eq!(covered_by(4), Some((7, 8)));
+    assert_eq!(covered_by(21), None);
+
+    let game = PyramidGame::new(PyramidRules::new(), |c| seeded_shuffle(1, c));
+    assert_eq!(game.pyramid_len(), 28);
+    assert_eq!(game.stock_len(), 24);
+    assert_eq!(game.free_places(), (21..28).map(|i| Place::Pyramid(i)).collect::<Vec<Place>>());
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn removals() {
+    // The bottom row and then 6C as the first card of the stock
+    let order = ["KS", "6H", "8D", "5C", "7H", "AS", "QD", "6C"];
+    let mut game = PyramidGame::new(PyramidRules::new(), |cards| {
+      for (i, c) in order.iter().enumerate() {
+        let at = cards.iter().position(|x| *x == card(c)).unwrap();
+        cards.swap(21 + i, at);
+      }
+    });
+    game.apply(PyramidMove::Draw).unwrap();
+
+    assert_eq!(game.can_apply(PyramidMove::Pair(Place::Pyramid(22), Place::Pyramid(23))), Err(PyramidErr::NotThirteen));
+    assert_eq!(game.can_apply(PyramidMove::King(Place::Pyramid(22))), Err(PyramidErr::NotThirteen));
+    assert_eq!(game.can_apply(PyramidMove::Pair(Place::Pyramid(15), Place::Pyramid(22))), Err(PyramidErr::NotFree));
+
+    game.apply(PyramidMove::King(Place::Pyramid(21))).unwrap();
+    game.apply(PyramidMove::Pair(Place::Pyramid(23), Place::Pyramid(24))).unwrap();
+    game.apply(PyramidMove::Pair(Place::Waste, Place::Pyramid(25))).unwrap();
+    assert_eq!(game.pyramid_len(), 24);
+    assert!(game.waste().is_empty());
+    assert_eq!(game.score(), 5);
+    assert!(game.is_free(17), "both cards below were removed");
+    assert!(! game.is_free(16));
+
+    let moves = game.legal_moves();
+    assert!(moves.contains(&PyramidMove::Pair(Place::Pyramid(26), Place::Pyramid(27))));
+    assert!(moves.contains(&PyramidMove::Draw));
+  }
+
+  #[test]
+  fn redeal() {
+    let mut game = PyramidGame::new(PyramidRules { passes: 2 }, |c| seeded_shuffle(2, c));
+    assert_eq!(game.apply(PyramidMove::Redeal), Err(PyramidErr::NoRedeal));
+
+    let first = game.stock[game.stock.len() - 1];
+    while game.stock_len() > 0 {
+      game.apply(PyramidMove::Draw).unwrap();
+    }
+    assert_eq!(game.apply(PyramidMove::Draw), Err(PyramidErr::EmptyStock));
+
+    game.apply(PyramidMove::Redeal).unwrap();
+    assert_eq!(game.passes(), 2);
+    assert_eq!(game.stock_len(), 24);
+    game.apply(PyramidMove::Draw).unwrap();
+    assert_eq!(game.waste(), &[first]);
+
+    while game.stock_len() > 0 {
+      game.apply(PyramidMove::Draw).unwrap();
+    }
+    assert_eq!(game.apply(PyramidMove::Redeal), Err(PyramidErr::NoRedeal));
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[3]);
+    let mut game = PyramidGame::new(PyramidRules::new(), |c| seeded_shuffle(3, c));
+    let mut removed = 0;
+
+    for _ in 0..200 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+      assert!(game.score() >= removed);
+      removed = game.score();
+    }
+  }
+}
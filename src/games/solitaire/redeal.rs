@@ -0,0 +1,116 @@
+//! Redeals: gathering the cards left in the tableau mid-game, shuffling them
+//! and dealing them out again, as La Belle Lucie does. Cards already on the
+//! foundations stay there.
+
+use cards::seeded_shuffle;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The redeals allowed in a game and the shuffle of each one.
+///
+/// Every redeal shuffles with a seed of its own, taken from the seed of the
+/// game and the number of redeals made before, so a copy of a game redeals
+/// exactly like the original and replaying the same moves on the same deal
+/// reaches the same position.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Redeals {
+  limit: usize,
+  made: usize,
+  seed: u64,
+}
+
+impl Redeals {
+  pub fn new(limit: usize, seed: u64) -> Redeals {
+    Redeals {
+      limit: limit,
+      made: 0,
+      seed: seed,
+    }
+  }
+
+  /// Seed for the redeals of a game dealt as `cards`, so that the same deal
+  /// always redeals the same way.
+  pub fn seed_of<T: Hash>(cards: &[T]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    cards.hash(&mut hasher);
+    hasher.finish()
+  }
+
+  pub fn limit(&self) -> usize {
+    self.limit
+  }
+
+  pub fn made(&self) -> usize {
+    self.made
+  }
+
+  pub fn left(&self) -> usize {
+    self.limit - self.made
+  }
+
+  /// Gather the cards of `piles`, the first pile first and each from the
+  /// bottom card up, shuffle them and deal them again with `deal_piles`.
+  /// False, with `piles` unchanged, when no redeal is left.
+  pub fn redeal<T>(&mut self, piles: &mut Vec<Vec<T>>, size: usize) -> bool {
+    if self.left() == 0 {
+      return false;
+    }
+
+    let mut cards: Vec<T> = piles.drain(..).flat_map(|p| p.into_iter()).collect();
+
+    let mut hasher = DefaultHasher::new();
+    (self.seed, self.made).hash(&mut hasher);
+    seeded_shuffle(hasher.finish(), &mut cards);
+
+    *piles = deal_piles(cards, size);
+    self.made += 1;
+    true
+  }
+}
+
+/// Deal `cards` in order to piles of `size` cards, each from the bottom card
+/// up; the last pile has the cards left over.
+pub fn deal_piles<T>(cards: Vec<T>, size: usize) -> Vec<Vec<T>> {
+  let mut piles = Vec::new();
+  let mut cards = cards.into_iter().peekable();
+
+  while cards.peek().is_some() {
+    piles.push(cards.by_ref().take(size).collect());
+  }
+
+  piles
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn deal_piles_of_size() {
+    assert_eq!(deal_piles((0..7).collect(), 3), vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+    assert_eq!(deal_piles(Vec::<u8>::new(), 3), Vec::<Vec<u8>>::new());
+  }
+
+  #[test]
+  fn redeal_limit_and_repeatability() {
+    let mut piles = vec![vec![1, 2], vec![], vec![3, 4, 5, 6, 7]];
+    let mut redeals = Redeals::new(2, 9);
+    let mut copy = redeals;
+    let mut copy_piles = piles.clone();
+
+    assert!(redeals.redeal(&mut piles, 3));
+    assert_eq!(piles.iter().map(|p| p.len()).collect::<Vec<usize>>(), vec![3, 3, 1]);
+    let mut cards: Vec<u8> = piles.iter().flat_map(|p| p.iter().cloned()).collect();
+    cards.sort();
+    assert_eq!(cards, vec![1, 2, 3, 4, 5, 6, 7]);
+
+    assert!(copy.redeal(&mut copy_piles, 3));
+    assert_eq!(copy_piles, piles, "copies redeal alike");
+
+    assert!(redeals.redeal(&mut piles, 3));
+    assert_eq!(redeals.left(), 0);
+    let before = piles.clone();
+    assert!(! redeals.redeal(&mut piles, 3));
+    assert_eq!(piles, before);
+  }
+}
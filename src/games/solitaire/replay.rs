@@ -0,0 +1,155 @@
+//! Replaying a klondike game from its deal and the moves that were made.
+//!
+//! A `Replay` applies the moves one at a time and checks each is legal, so
+//! recorded games can be stepped through, shown or checked against what a
+//! strategy plays now:
+//!
+//! ```text
+//! for step in Replay::new(deal, moves) {
+//!   let (m, game) = step?;
+//!   println!("{}\n{}", m, game);
+//! }
+//! ```
+
+use games::solitaire::klondike::{KlondikeSolitaireGame, Move};
+use std::fmt;
+
+/// A move of a replay that was not legal in the game it was replayed on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReplayError {
+  /// Number of the move, from 0.
+  pub ply: usize,
+  pub m: Move,
+}
+
+impl fmt::Display for ReplayError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "move {} ({}) is not legal", self.ply, self.m)
+  }
+}
+
+/// The moves of a game applied in turn to its initial position.
+///
+/// As an iterator it yields each move with the game after it, and stops
+/// after the last move or the first move that is not legal.
+pub struct Replay {
+  game: KlondikeSolitaireGame,
+  moves: Vec<Move>,
+  ply: usize,
+  failed: bool,
+}
+
+impl Replay {
+  pub fn new(initial: KlondikeSolitaireGame, moves: Vec<Move>) -> Replay {
+    Replay {
+      game: initial,
+      moves: moves,
+      ply: 0,
+      failed: false,
+    }
+  }
+
+  /// The game after the moves applied so far.
+  pub fn game(&self) -> &KlondikeSolitaireGame {
+    &self.game
+  }
+
+  /// Number of moves applied so far.
+  pub fn ply(&self) -> usize {
+    self.ply
+  }
+
+  pub fn moves(&self) -> &[Move] {
+    &self.moves
+  }
+
+  /// Apply the next move, returning it, `None` when every move has been
+  /// applied. The game is left as it was if the move is not legal.
+  pub fn step(&mut self) -> Option<Result<Move, ReplayError>> {
+    if self.failed {
+      return None;
+    }
+
+    let m = *self.moves.get(self.ply)?;
+    if self.game.apply(m).is_err() {
+      self.failed = true;
+      return Some(Err(ReplayError { ply: self.ply, m: m }));
+    }
+
+    self.ply += 1;
+    Some(Ok(m))
+  }
+
+  /// Apply the remaining moves and return the final game.
+  pub fn finish(mut self) -> Result<KlondikeSolitaireGame, ReplayError> {
+    while let Some(result) = self.step() {
+      result?;
+    }
+
+    Ok(self.game)
+  }
+}
+
+impl Iterator for Replay {
+  type Item = Result<(Move, KlondikeSolitaireGame), ReplayError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.step().map(|result| result.map(|m| (m, self.game.clone())))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_source;
+  use cards::french::Suit;
+  use games::solitaire::klondike::{MoveSource, MoveTarget};
+  use strategies::solitaire::klondike::GameStrategy;
+  use strategies::solitaire::klondike::simple::SimpleKlondikeStrategy;
+
+  fn played(seed: u64) -> (KlondikeSolitaireGame, KlondikeSolitaireGame) {
+    let deal = KlondikeSolitaireGame::new_shuffle(1, seeded_source(seed));
+    let mut game = deal.clone();
+    game.record_moves();
+    SimpleKlondikeStrategy::new().play(&mut game);
+    (deal, game)
+  }
+
+  #[test]
+  fn replays_recorded_games() {
+    for seed in 0..20 {
+      let (deal, game) = played(seed);
+      let moves = game.recorded_moves().unwrap();
+
+      let states: Vec<(Move, KlondikeSolitaireGame)> = Replay::new(deal.clone(), moves.clone())
+        .collect::<Result<_, _>>()
+        .unwrap();
+      assert_eq!(states.len(), moves.len());
+      assert!(states.iter().map(|s| s.0).eq(moves.iter().cloned()));
+      if let Some(last) = states.last() {
+        assert_eq!(last.1.notation(), game.notation());
+      }
+
+      let finished = Replay::new(deal, moves).finish().unwrap();
+      assert_eq!(finished.notation(), game.notation());
+      assert_eq!(finished.score(), game.score());
+    }
+  }
+
+  #[test]
+  fn stops_at_illegal_move() {
+    let (deal, _) = played(0);
+    let illegal = Move::Cards(MoveSource::Foundation(Suit::Hearts), MoveTarget::Pile(0));
+    let mut replay = Replay::new(deal.clone(), vec![Move::Draw, illegal, Move::Draw]);
+
+    assert_eq!(replay.step(), Some(Ok(Move::Draw)));
+    let before = replay.game().notation();
+    assert_eq!(replay.step(), Some(Err(ReplayError { ply: 1, m: illegal })));
+    assert_eq!(replay.step(), None);
+    assert_eq!(replay.ply(), 1);
+    assert_eq!(replay.game().notation(), before);
+
+    let error = Replay::new(deal, vec![Move::Draw, illegal]).finish().err().unwrap();
+    assert_eq!(error.to_string(), "move 1 (foundationH->pile0) is not legal");
+  }
+}
@@ -0,0 +1,72 @@
+//! Stock that deals a row: one card to each pile of the tableau at once, as
+//! in Spider and Easthaven.
+
+/// Cards waiting to be dealt a row at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowStock<C> {
+  /// Dealt from the end.
+  cards: Vec<C>,
+}
+
+impl<C> RowStock<C> {
+  /// Stock of `cards`, the first of them dealt first.
+  pub fn new(mut cards: Vec<C>) -> RowStock<C> {
+    cards.reverse();
+
+    RowStock {
+      cards: cards,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.cards.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.cards.is_empty()
+  }
+
+  /// The cards left, the next one dealt last.
+  pub fn cards(&self) -> &[C] {
+    &self.cards
+  }
+
+  /// Number of rows left to deal to `piles` piles, the last one maybe short.
+  pub fn rows_left(&self, piles: usize) -> usize {
+    (self.cards.len() + piles - 1) / piles
+  }
+
+  /// The next row for `piles` piles, the card of the first pile first. The
+  /// last row has fewer cards when the stock does not divide evenly.
+  pub fn deal_row(&mut self, piles: usize) -> Vec<C> {
+    let len = ::std::cmp::min(piles, self.cards.len());
+    let at = self.cards.len() - len;
+    let mut row = self.cards.split_off(at);
+    row.reverse();
+    row
+  }
+
+  pub fn clear(&mut self) {
+    self.cards.clear();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn deal_rows() {
+    let mut stock = RowStock::new((0..8).collect());
+    assert_eq!(stock.rows_left(3), 3);
+
+    assert_eq!(stock.deal_row(3), vec![0, 1, 2]);
+    assert_eq!(stock.deal_row(3), vec![3, 4, 5]);
+    assert_eq!(stock.len(), 2);
+    assert_eq!(stock.cards(), &[7, 6]);
+    assert_eq!(stock.deal_row(3), vec![6, 7]);
+    assert!(stock.is_empty());
+    assert_eq!(stock.deal_row(3), Vec::<i32>::new());
+    assert_eq!(stock.rows_left(3), 0);
+  }
+}
@@ -0,0 +1,114 @@
+//! Random mid-game Klondike positions, to benchmark strategies and the
+//! solver from arbitrary positions instead of only fresh deals.
+//!
+//! A position is a random deal played forward with a random number of random
+//! legal moves, so every position is reachable in a real game. Positions that
+//! are already won or have no legal move left are skipped.
+
+use cards::{seeded_source, RngSource};
+use games::solitaire::klondike::{KlondikeRules, KlondikeSolitaireGame};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MidGameSampler {
+  rules: KlondikeRules,
+  min_moves: usize,
+  max_moves: usize,
+}
+
+impl MidGameSampler {
+  /// Sampler of positions `min_moves` to `max_moves` moves into a game,
+  /// inclusive.
+  pub fn new(rules: KlondikeRules, min_moves: usize, max_moves: usize) -> MidGameSampler {
+    assert!(min_moves <= max_moves, "min_moves > max_moves: {} > {}", min_moves, max_moves);
+
+    MidGameSampler {
+      rules: rules,
+      min_moves: min_moves,
+      max_moves: max_moves,
+    }
+  }
+
+  pub fn rules(&self) -> KlondikeRules {
+    self.rules
+  }
+
+  pub fn min_moves(&self) -> usize {
+    self.min_moves
+  }
+
+  pub fn max_moves(&self) -> usize {
+    self.max_moves
+  }
+
+  /// Play a random prefix of `game`. Returns false, leaving `game` part way
+  /// through, if it ran out of moves before `min_moves` or ended won or with
+  /// no legal move.
+  pub fn advance<R: Rng>(&self, game: &mut KlondikeSolitaireGame, rng: &mut R) -> bool {
+    let target = rng.gen_range(self.min_moves, self.max_moves + 1);
+
+    game.play_random(rng, target) >= self.min_moves && ! game.is_clear() && ! game.legal_moves().is_empty()
+  }
+
+  /// Random position from random deals.
+  pub fn sample<R: Rng>(&self, rng: &mut R) -> KlondikeSolitaireGame {
+    loop {
+      let mut game = KlondikeSolitaireGame::with_rules(self.rules, RngSource(&mut *rng));
+
+      if self.advance(&mut game, rng) {
+        return game;
+      }
+    }
+  }
+
+  /// Play the prefix of the deal of `seed` with moves chosen from the same
+  /// seed, so the position can be reproduced from the seed alone.
+  pub fn advance_seeded(&self, game: &mut KlondikeSolitaireGame, seed: u64) -> bool {
+    self.advance(game, &mut Isaac64Rng::from_seed(&[seed, 1]))
+  }
+
+  /// Position from the deal of `seed`, `None` if its prefix does not give a
+  /// mid-game position.
+  pub fn sample_seeded(&self, seed: u64) -> Option<KlondikeSolitaireGame> {
+    let mut game = KlondikeSolitaireGame::with_rules(self.rules, seeded_source(seed));
+
+    if self.advance_seeded(&mut game, seed) {
+      Some(game)
+    } else {
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use rand::{Isaac64Rng, SeedableRng};
+
+  #[test]
+  fn sample_positions() {
+    let sampler = MidGameSampler::new(KlondikeRules::new(3), 10, 40);
+    let mut rng = Isaac64Rng::from_seed(&[7]);
+
+    for _ in 0..50 {
+      let game = sampler.sample(&mut rng);
+
+      assert_eq!(game.validate(), Ok(()));
+      assert!(game.moves_made() >= 10 && game.moves_made() <= 40, "{} moves", game.moves_made());
+      assert!(! game.is_clear());
+      assert!(! game.legal_moves().is_empty());
+      assert_eq!(game.deck().draw_count(), 3);
+    }
+  }
+
+  #[test]
+  fn sample_seeded_reproducible() {
+    let sampler = MidGameSampler::new(KlondikeRules::new(1), 20, 20);
+    let seed = (0..100).find(|s| sampler.sample_seeded(*s).is_some()).unwrap();
+
+    let a = sampler.sample_seeded(seed).unwrap();
+    let b = sampler.sample_seeded(seed).unwrap();
+    assert_eq!(a.notation(), b.notation());
+    assert_eq!(a.moves_made(), 20);
+  }
+}
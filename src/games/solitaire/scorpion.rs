@@ -0,0 +1,393 @@
+//! Scorpion solitaire.
+//!
+//! Forty-nine cards are dealt to seven piles of seven: the first four piles
+//! get three hidden cards under four visible ones and the other three are all
+//! face up. The last three cards are the reserve, dealt face up to the first
+//! three piles at once whenever the player wants. Any visible card can be
+//! moved with everything on top of it onto the card of its suit one rank
+//! higher, and only a king onto an empty pile. A hidden card is turned up
+//! when it is uncovered. A run of one suit from king to ace is removed from
+//! the piles; the game is won when all four are removed.
+
+use cards::french::{self, FrenchPlayingCard, Rank, Suit};
+use games::solitaire::SolitaireGame;
+use std::collections::HashSet;
+use std::fmt;
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_PILES: usize = 7;
+pub const RESERVE_LEN: usize = 3;
+const NUM_CARDS: usize = 52;
+const PILE_LEN: usize = 7;
+const HIDDEN_PILES: usize = 4;
+const HIDDEN_LEN: usize = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ScorpionErr {
+  /// The reserve was dealt already.
+  EmptyReserve,
+  InvalidMove,
+}
+
+pub type ScorpionResult<T> = Result<T, ScorpionErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ScorpionMove {
+  /// Deal the reserve to the first three piles.
+  Reserve,
+  /// Move visible card `index` of pile `source` and the cards on top of it
+  /// onto pile `target`.
+  Cards { source: u8, index: u8, target: u8 },
+}
+
+impl fmt::Display for ScorpionMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ScorpionMove::Reserve => write!(f, "reserve"),
+      ScorpionMove::Cards { source, index, target } => write!(f, "{}:{}->{}", source, index, target),
+    }
+  }
+}
+
+/// Whether `card` can be built on `below`: the same suit and one rank lower.
+pub fn builds_on(card: Card, below: Card) -> bool {
+  card.suit() == below.suit() && card.rank().value() + 1 == below.rank().value()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScorpionPile {
+  hidden: Vec<Card>,
+  visible: Vec<Card>,
+}
+
+impl ScorpionPile {
+  pub fn hidden_cards(&self) -> &[Card] {
+    &self.hidden
+  }
+
+  pub fn visible_cards(&self) -> &[Card] {
+    &self.visible
+  }
+
+  pub fn top(&self) -> Option<Card> {
+    self.visible.last().cloned()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.visible.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.hidden.len() + self.visible.len()
+  }
+
+  /// Number of visible cards built on the card below them.
+  pub fn built_len(&self) -> usize {
+    self.visible.windows(2).filter(|w| builds_on(w[1], w[0])).count()
+  }
+
+  fn flip(&mut self) -> bool {
+    if self.visible.is_empty() {
+      if let Some(card) = self.hidden.pop() {
+        self.visible.push(card);
+        return true;
+      }
+    }
+
+    false
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScorpionGame {
+  piles: Vec<ScorpionPile>,
+  reserve: Vec<Card>,
+  completed: Vec<Suit>,
+  moves_made: usize,
+  hidden_flips: usize,
+}
+
+impl ScorpionGame {
+  /// New game with a standard deck arranged by `shuffle`, dealt one pile
+  /// after the other from the bottom card up. The last three cards are the
+  /// reserve, the first of them for the first pile.
+  pub fn new<F>(mut shuffle: F) -> ScorpionGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let reserve = cards.split_off(NUM_PILES * PILE_LEN);
+    let piles = cards.chunks(PILE_LEN).enumerate().map(|(i, c)| {
+      let hidden = if i < HIDDEN_PILES { HIDDEN_LEN } else { 0 };
+      ScorpionPile { hidden: c[..hidden].to_vec(), visible: c[hidden..].to_vec() }
+    }).collect();
+
+    ScorpionGame {
+      piles: piles,
+      reserve: reserve,
+      completed: Vec::new(),
+      moves_made: 0,
+      hidden_flips: 0,
+    }
+  }
+
+  pub fn piles(&self) -> &[ScorpionPile] {
+    &self.piles
+  }
+
+  pub fn pile(&self, index: usize) -> &ScorpionPile {
+    &self.piles[index]
+  }
+
+  /// Cards of the reserve, empty once dealt.
+  pub fn reserve(&self) -> &[Card] {
+    &self.reserve
+  }
+
+  /// Suits of the runs removed so far, in the order they were completed.
+  pub fn completed(&self) -> &[Suit] {
+    &self.completed
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.completed.len() == 4
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Number of hidden pile cards turned face up since the deal.
+  pub fn hidden_flips(&self) -> usize {
+    self.hidden_flips
+  }
+
+  /// Cards in place: built on the card of their suit one rank higher, plus
+  /// every card of the removed runs. 52 for a won game.
+  pub fn score(&self) -> i32 {
+    (self.piles.iter().map(|p| p.built_len()).sum::<usize>() + 13 * self.completed.len()) as i32
+  }
+
+  pub fn can_apply(&self, m: ScorpionMove) -> ScorpionResult<()> {
+    match m {
+      ScorpionMove::Reserve if self.reserve.is_empty() => Err(ScorpionErr::EmptyReserve),
+      ScorpionMove::Reserve => Ok(()),
+      ScorpionMove::Cards { source, index, target } => {
+        let (source, index, target) = (source as usize, index as usize, target as usize);
+        if source >= NUM_PILES || target >= NUM_PILES || source == target {
+          return Err(ScorpionErr::InvalidMove);
+        }
+
+        match (self.piles[source].visible.get(index), self.piles[target].top()) {
+          (Some(card), Some(top)) if builds_on(*card, top) => Ok(()),
+          (Some(card), None) if card.rank() == Rank::King => Ok(()),
+          _ => Err(ScorpionErr::InvalidMove),
+        }
+      },
+    }
+  }
+
+  pub fn apply(&mut self, m: ScorpionMove) -> ScorpionResult<()> {
+    self.can_apply(m)?;
+
+    match m {
+      ScorpionMove::Reserve => {
+        for (i, card) in self.reserve.drain(..).enumerate() {
+          self.piles[i].visible.push(card);
+        }
+
+        for i in 0..RESERVE_LEN {
+          self.complete_run(i);
+        }
+      },
+      ScorpionMove::Cards { source, index, target } => {
+        let (source, target) = (source as usize, target as usize);
+        let cards = self.piles[source].visible.split_off(index as usize);
+        self.piles[target].visible.extend(cards);
+
+        if self.piles[source].flip() {
+          self.hidden_flips += 1;
+        }
+        self.complete_run(target);
+      },
+    }
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  /// Remove a complete run from the top of pile `index`.
+  fn complete_run(&mut self, index: usize) {
+    let pile = &mut self.piles[index];
+    let visible = &pile.visible;
+
+    if visible.len() >= 13 && visible[visible.len() - 13].rank() == Rank::King
+      && visible[visible.len() - 13..].windows(2).all(|w| builds_on(w[1], w[0])) {
+      let at = visible.len() - 13;
+      self.completed.push(visible[at].suit());
+      pile.visible.truncate(at);
+
+      if pile.flip() {
+        self.hidden_flips += 1;
+      }
+    }
+  }
+
+  /// Every move that would currently succeed, the reserve last.
+  pub fn legal_moves(&self) -> Vec<ScorpionMove> {
+    let mut moves = Vec::new();
+
+    for source in 0..NUM_PILES {
+      for index in 0..self.piles[source].visible.len() {
+        for target in 0..NUM_PILES {
+          let m = ScorpionMove::Cards { source: source as u8, index: index as u8, target: target as u8 };
+          if self.can_apply(m).is_ok() {
+            moves.push(m);
+          }
+        }
+      }
+    }
+
+    if self.can_apply(ScorpionMove::Reserve).is_ok() {
+      moves.push(ScorpionMove::Reserve);
+    }
+
+    moves
+  }
+
+  /// Check that the game holds every card of the deck once, counting the
+  /// removed runs.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    let removed: Vec<Card> = self.completed.iter()
+      .flat_map(|s| Rank::standard_iter().map(move |r| Card::new(*s, *r)))
+      .collect();
+    let cards = self.piles.iter().flat_map(|p| p.hidden.iter().chain(p.visible.iter()))
+      .chain(self.reserve.iter())
+      .chain(removed.iter());
+
+    for card in cards {
+      if ! seen.insert(*card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    if seen.len() != NUM_CARDS {
+      return Err(format!("{} cards instead of {}", seen.len(), NUM_CARDS));
+    }
+
+    Ok(())
+  }
+
+  /// The piles from bottom to top with hidden cards in lower case, then the
+  /// reserve and the completed runs, e.g. `4s KH QH|...|- / 5C 9D 2S / H`.
+  pub fn notation(&self) -> String {
+    let list = |cards: Vec<String>| if cards.is_empty() { String::from("-") } else { cards.join(" ") };
+
+    let piles: Vec<String> = self.piles.iter().map(|p| {
+      list(p.hidden.iter().map(|c| c.to_string().to_lowercase()).chain(p.visible.iter().map(|c| c.to_string())).collect())
+    }).collect();
+
+    format!("{} / {} / {}", piles.join("|"),
+            list(self.reserve.iter().map(|c| c.to_string()).collect()),
+            list(self.completed.iter().map(|s| s.to_string()).collect()))
+  }
+}
+
+impl SolitaireGame for ScorpionGame {
+  fn is_clear(&self) -> bool {
+    ScorpionGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    ScorpionGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    ScorpionGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = ScorpionGame::new(|c| seeded_shuffle(1, c));
+
+    let hidden: Vec<usize> = game.piles().iter().map(|p| p.hidden_cards().len()).collect();
+    assert_eq!(hidden, vec![3, 3, 3, 3, 0, 0, 0]);
+    assert!(game.piles().iter().all(|p| p.len() == 7));
+    assert_eq!(game.reserve().len(), 3);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn moves() {
+    let mut game = ScorpionGame::new(|_| ());
+    for pile in game.piles.iter_mut() {
+      *pile = ScorpionPile { hidden: Vec::new(), visible: Vec::new() };
+    }
+    game.piles[0] = ScorpionPile { hidden: cards(&["2D"]), visible: cards(&["9S", "3H", "KC"]) };
+    game.piles[1] = ScorpionPile { hidden: Vec::new(), visible: cards(&["4H"]) };
+    game.piles[2] = ScorpionPile { hidden: Vec::new(), visible: cards(&["10S"]) };
+
+    assert_eq!(game.can_apply(ScorpionMove::Cards { source: 0, index: 1, target: 2 }), Err(ScorpionErr::InvalidMove));
+    assert_eq!(game.can_apply(ScorpionMove::Cards { source: 0, index: 0, target: 3 }), Err(ScorpionErr::InvalidMove), "9S to an empty pile");
+
+    game.apply(ScorpionMove::Cards { source: 0, index: 1, target: 1 }).unwrap();
+    assert_eq!(game.pile(1).visible_cards(), &cards(&["4H", "3H", "KC"])[..]);
+    assert_eq!(game.pile(1).built_len(), 1);
+
+    game.apply(ScorpionMove::Cards { source: 1, index: 2, target: 3 }).unwrap();
+    game.apply(ScorpionMove::Cards { source: 0, index: 0, target: 2 }).unwrap();
+    assert_eq!(game.pile(0).visible_cards(), &cards(&["2D"])[..]);
+    assert_eq!(game.hidden_flips(), 1);
+    assert_eq!(game.score(), 2);
+
+    game.apply(ScorpionMove::Reserve).unwrap();
+    assert_eq!(game.pile(0).visible_cards(), &cards(&["2D", "JS"])[..]);
+    assert_eq!(game.apply(ScorpionMove::Reserve), Err(ScorpionErr::EmptyReserve));
+  }
+
+  #[test]
+  fn complete_run() {
+    let mut game = ScorpionGame::new(|_| ());
+    let run: Vec<Card> = Rank::standard_iter().rev().map(|r| Card::new(Suit::Clubs, *r)).collect();
+
+    for pile in game.piles.iter_mut() {
+      *pile = ScorpionPile { hidden: Vec::new(), visible: Vec::new() };
+    }
+    game.piles[0] = ScorpionPile { hidden: cards(&["5H"]), visible: run[..12].to_vec() };
+    game.piles[1] = ScorpionPile { hidden: Vec::new(), visible: vec![run[12]] };
+
+    game.apply(ScorpionMove::Cards { source: 1, index: 0, target: 0 }).unwrap();
+    assert_eq!(game.completed(), &[Suit::Clubs]);
+    assert_eq!(game.pile(0).visible_cards(), &cards(&["5H"])[..]);
+    assert_eq!(game.score(), 13);
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[6]);
+    let mut game = ScorpionGame::new(|c| seeded_shuffle(6, c));
+
+    for _ in 0..300 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+    }
+  }
+}
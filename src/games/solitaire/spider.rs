@@ -0,0 +1,554 @@
+//! Spider solitaire with one, two or four suits.
+//!
+//! Two decks' worth of cards of the configured suits are dealt to ten piles,
+//! the top card of each face up, and the rest form the stock. Cards are built
+//! down regardless of suit, but only a run of one suit moves as a unit. A
+//! complete run of one suit from king to ace is removed from the tableau; the
+//! game is won when all eight runs are removed. Dealing from the stock puts
+//! one card face up on every pile and is only allowed without empty piles.
+
+use cards::DeckCard;
+use cards::french::{FrenchPlayingCard, Rank, Suit};
+use games::solitaire::SolitaireGame;
+use games::solitaire::row_stock::RowStock;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Card tagged with the copy of the suit it belongs to, so that every card of
+/// a game is distinct.
+pub type Card = DeckCard<FrenchPlayingCard>;
+
+pub const NUM_PILES: usize = 10;
+pub const NUM_RUNS: usize = 8;
+const NUM_CARDS: usize = NUM_RUNS * 13;
+
+/// Suit count and stock size of a Spider game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SpiderRules {
+  /// Number of suits, 1, 2 or 4.
+  pub suits: u8,
+  /// Number of times the stock can be dealt, each dealing one card to every
+  /// pile. The cards not in the stock are dealt to the tableau.
+  pub stock_deals: u8,
+}
+
+impl SpiderRules {
+  /// Standard rules for `suits`: five stock deals and 54 cards in the
+  /// tableau.
+  pub fn new(suits: u8) -> SpiderRules {
+    SpiderRules {
+      suits: suits,
+      stock_deals: 5,
+    }
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    if self.suits != 1 && self.suits != 2 && self.suits != 4 {
+      return Err(format!("suit count must be 1, 2 or 4, not {}", self.suits));
+    }
+
+    if self.stock_deals as usize * NUM_PILES > NUM_CARDS - NUM_PILES {
+      return Err(format!("at most {} stock deals leave a card for every pile", NUM_CARDS / NUM_PILES - 1));
+    }
+
+    Ok(())
+  }
+
+  /// Suits of the cards in play.
+  pub fn suit_set(&self) -> &'static [Suit] {
+    static ONE: [Suit; 1] = [Suit::Spades];
+    static TWO: [Suit; 2] = [Suit::Spades, Suit::Hearts];
+    static FOUR: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
+
+    match self.suits {
+      1 => &ONE,
+      2 => &TWO,
+      _ => &FOUR,
+    }
+  }
+
+  /// The 104 cards in play, eight runs of king to ace spread evenly over the
+  /// suits. The copies of a card are tagged 0 to `8 / suits - 1`.
+  pub fn cards(&self) -> Vec<Card> {
+    let suits = self.suit_set();
+    let mut cards = Vec::with_capacity(NUM_CARDS);
+
+    for run in 0..NUM_RUNS {
+      for rank in Rank::standard_iter() {
+        let card = FrenchPlayingCard::new(suits[run % suits.len()], *rank);
+        cards.push(Card::new(card, (run / suits.len()) as u8));
+      }
+    }
+
+    cards
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SpiderErr {
+  /// The stock is empty.
+  EmptyStock,
+  /// The stock can not be dealt while a pile is empty.
+  EmptyPile,
+  InvalidMove,
+}
+
+pub type SpiderResult<T> = Result<T, SpiderErr>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SpiderMove {
+  /// Deal a card from the stock to every pile.
+  Deal,
+  /// Move the top `count` cards of pile `source` onto pile `target`.
+  Cards { source: u8, count: u8, target: u8 },
+}
+
+impl fmt::Display for SpiderMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      SpiderMove::Deal => write!(f, "deal"),
+      SpiderMove::Cards { source, count, target } => write!(f, "{}x{}->{}", source, count, target),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpiderPile {
+  hidden: Vec<Card>,
+  visible: Vec<Card>,
+}
+
+impl SpiderPile {
+  pub fn hidden_cards(&self) -> &[Card] {
+    &self.hidden
+  }
+
+  pub fn visible_cards(&self) -> &[Card] {
+    &self.visible
+  }
+
+  pub fn top(&self) -> Option<Card> {
+    self.visible.last().cloned()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.visible.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.hidden.len() + self.visible.len()
+  }
+
+  /// Number of cards at the top of the pile that move together: a run of one
+  /// suit in descending rank.
+  pub fn run_len(&self) -> usize {
+    let visible = &self.visible;
+    if visible.is_empty() {
+      return 0;
+    }
+
+    let mut len = 1;
+    while len < visible.len() && follows(visible[visible.len() - len], visible[visible.len() - len - 1]) {
+      len += 1;
+    }
+
+    len
+  }
+
+  fn flip(&mut self) -> bool {
+    if self.visible.is_empty() {
+      if let Some(card) = self.hidden.pop() {
+        self.visible.push(card);
+        return true;
+      }
+    }
+
+    false
+  }
+}
+
+/// Whether `card` can be built on `below`: one rank lower, of any suit.
+pub fn builds_on(card: Card, below: Card) -> bool {
+  rank_index(card.rank()) + 1 == rank_index(below.rank())
+}
+
+/// Whether `card` can be built on `below` within a run of one suit.
+fn follows(card: Card, below: Card) -> bool {
+  card.suit() == below.suit() && builds_on(card, below)
+}
+
+fn rank_index(rank: Rank) -> usize {
+  Rank::standard_iter().position(|r| *r == rank).unwrap()
+}
+
+#[derive(Debug, Clone)]
+pub struct SpiderGame {
+  rules: SpiderRules,
+  piles: Vec<SpiderPile>,
+  stock: RowStock<Card>,
+  completed: Vec<Suit>,
+  moves_made: usize,
+  hidden_flips: usize,
+}
+
+impl SpiderGame {
+  /// New game with the cards of `rules` arranged by `shuffle`. The first
+  /// cards go to the tableau, one pile after the other, and the rest to the
+  /// stock, the first of them dealt first.
+  ///
+  /// Panics if the rules are not valid.
+  pub fn new<F>(rules: SpiderRules, mut shuffle: F) -> SpiderGame
+    where F: FnMut(&mut Vec<Card>) {
+    rules.validate().unwrap();
+
+    let mut cards = rules.cards();
+    shuffle(&mut cards);
+
+    let stock_len = rules.stock_deals as usize * NUM_PILES;
+    let tableau_len = NUM_CARDS - stock_len;
+    let mut dealt = cards.iter().cloned();
+    let mut piles = Vec::with_capacity(NUM_PILES);
+
+    for i in 0..NUM_PILES {
+      let len = tableau_len / NUM_PILES + if i < tableau_len % NUM_PILES { 1 } else { 0 };
+      let mut hidden: Vec<Card> = dealt.by_ref().take(len).collect();
+      let top = hidden.pop().unwrap();
+      piles.push(SpiderPile { hidden: hidden, visible: vec![top] });
+    }
+
+    SpiderGame {
+      rules: rules,
+      piles: piles,
+      stock: RowStock::new(dealt.collect()),
+      completed: Vec::new(),
+      moves_made: 0,
+      hidden_flips: 0,
+    }
+  }
+
+  pub fn rules(&self) -> SpiderRules {
+    self.rules
+  }
+
+  pub fn piles(&self) -> &[SpiderPile] {
+    &self.piles
+  }
+
+  pub fn pile(&self, index: usize) -> &SpiderPile {
+    &self.piles[index]
+  }
+
+  /// Number of cards left in the stock.
+  pub fn stock_len(&self) -> usize {
+    self.stock.len()
+  }
+
+  /// Suits of the runs removed so far, in the order they were completed.
+  pub fn completed(&self) -> &[Suit] {
+    &self.completed
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.completed.len() == NUM_RUNS
+  }
+
+  /// Number of deals and card moves since the deal.
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Number of hidden pile cards turned face up since the deal.
+  pub fn hidden_flips(&self) -> usize {
+    self.hidden_flips
+  }
+
+  /// Classic score: 500 at the start, -1 per move and 100 per completed run.
+  pub fn score(&self) -> i32 {
+    500 - self.moves_made as i32 + 100 * self.completed.len() as i32
+  }
+
+  pub fn can_deal(&self) -> SpiderResult<()> {
+    if self.stock.is_empty() {
+      return Err(SpiderErr::EmptyStock);
+    }
+
+    if self.piles.iter().any(|p| p.is_empty()) {
+      return Err(SpiderErr::EmptyPile);
+    }
+
+    Ok(())
+  }
+
+  pub fn can_move(&self, source: usize, count: usize, target: usize) -> SpiderResult<()> {
+    if source >= NUM_PILES || target >= NUM_PILES || source == target || count == 0 || count > self.piles[source].run_len() {
+      return Err(SpiderErr::InvalidMove);
+    }
+
+    let visible = &self.piles[source].visible;
+    let moved = visible[visible.len() - count];
+
+    match self.piles[target].top() {
+      Some(top) if ! builds_on(moved, top) => Err(SpiderErr::InvalidMove),
+      _ => Ok(()),
+    }
+  }
+
+  pub fn apply(&mut self, m: SpiderMove) -> SpiderResult<()> {
+    match m {
+      SpiderMove::Deal => {
+        self.can_deal()?;
+
+        for (i, card) in self.stock.deal_row(NUM_PILES).into_iter().enumerate() {
+          self.piles[i].visible.push(card);
+          self.complete_run(i);
+        }
+      },
+      SpiderMove::Cards { source, count, target } => {
+        let (source, count, target) = (source as usize, count as usize, target as usize);
+        self.can_move(source, count, target)?;
+
+        let at = self.piles[source].visible.len() - count;
+        let cards = self.piles[source].visible.split_off(at);
+        self.piles[target].visible.extend(cards);
+
+        if self.piles[source].flip() {
+          self.hidden_flips += 1;
+        }
+        self.complete_run(target);
+      },
+    }
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  /// Remove a complete run from the top of pile `index`.
+  fn complete_run(&mut self, index: usize) {
+    let pile = &mut self.piles[index];
+
+    if pile.run_len() >= 13 && pile.top().map(|c| c.rank()) == Some(Rank::Ace) {
+      let at = pile.visible.len() - 13;
+      let suit = pile.visible[at].suit();
+      pile.visible.truncate(at);
+      self.completed.push(suit);
+
+      if pile.flip() {
+        self.hidden_flips += 1;
+      }
+    }
+  }
+
+  /// All moves that would currently succeed.
+  pub fn legal_moves(&self) -> Vec<SpiderMove> {
+    let mut moves = Vec::new();
+
+    if self.can_deal().is_ok() {
+      moves.push(SpiderMove::Deal);
+    }
+
+    for source in 0..NUM_PILES {
+      for count in 1..self.piles[source].run_len() + 1 {
+        for target in 0..NUM_PILES {
+          if self.can_move(source, count, target).is_ok() {
+            moves.push(SpiderMove::Cards { source: source as u8, count: count as u8, target: target as u8 });
+          }
+        }
+      }
+    }
+
+    moves
+  }
+
+  /// Check that the piles and the stock hold every card of the rules once,
+  /// apart from the completed runs, and that each run was complete.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    for card in self.piles.iter().flat_map(|p| p.hidden.iter().chain(p.visible.iter())).chain(self.stock.cards().iter()) {
+      if ! seen.insert(*card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    let mut missing: Vec<Card> = self.rules.cards().into_iter().filter(|c| ! seen.contains(c)).collect();
+
+    if seen.len() + missing.len() != NUM_CARDS {
+      return Err(format!("{} cards that are not part of a {} suit game", seen.len() + missing.len() - NUM_CARDS, self.rules.suits));
+    }
+
+    for suit in self.completed.iter() {
+      let mut run: Vec<usize> = Rank::standard_iter()
+        .filter_map(|r| missing.iter().position(|c| c.suit() == *suit && c.rank() == *r))
+        .collect();
+
+      if run.len() != 13 {
+        return Err(format!("completed {} run is not missing from the game", suit));
+      }
+
+      run.sort();
+      for i in run.into_iter().rev() {
+        missing.remove(i);
+      }
+    }
+
+    match missing.first() {
+      Some(card) => Err(format!("missing card {}", card)),
+      None => Ok(()),
+    }
+  }
+
+  /// The piles from bottom to top with hidden cards in lower case, then the
+  /// stock size and the completed runs, e.g. `4s 9H 8H|...|KS / 40 / S`.
+  pub fn notation(&self) -> String {
+    let piles: Vec<String> = self.piles.iter().map(|p| {
+      let cards: Vec<String> = p.hidden.iter().map(|c| c.card().to_string().to_lowercase())
+        .chain(p.visible.iter().map(|c| c.card().to_string()))
+        .collect();
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
+    }).collect();
+
+    let completed: Vec<String> = self.completed.iter().map(|s| s.to_string()).collect();
+
+    format!("{} / {} / {}", piles.join("|"), self.stock.len(),
+            if completed.is_empty() { String::from("-") } else { completed.join(" ") })
+  }
+}
+
+impl SolitaireGame for SpiderGame {
+  fn is_clear(&self) -> bool {
+    SpiderGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    SpiderGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    SpiderGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  /// Game with the given piles, all cards face up, and an empty stock.
+  fn game(suits: u8, piles: &[&[&str]]) -> SpiderGame {
+    let mut game = SpiderGame::new(SpiderRules::new(suits), |_| ());
+    game.stock.clear();
+
+    for (i, pile) in game.piles.iter_mut().enumerate() {
+      pile.hidden.clear();
+      pile.visible = piles.get(i).map_or(Vec::new(), |p| p.iter().map(|c| card(c)).collect());
+    }
+
+    game
+  }
+
+  #[test]
+  fn rules_cards() {
+    for &(suits, expected) in [(1, 1), (2, 2), (4, 4)].iter() {
+      let cards = SpiderRules::new(suits).cards();
+      assert_eq!(cards.len(), 104);
+
+      let mut distinct: Vec<FrenchPlayingCard> = cards.iter().map(|c| c.card()).collect();
+      distinct.sort_by_key(|c| (c.suit() as u8, rank_index(c.rank())));
+      distinct.dedup();
+      assert_eq!(distinct.len(), 13 * expected);
+      assert_eq!(cards.iter().cloned().collect::<HashSet<Card>>().len(), 104);
+    }
+
+    assert!(SpiderRules::new(3).validate().is_err());
+    assert!(SpiderRules { stock_deals: 10, .. SpiderRules::new(1) }.validate().is_err());
+    assert!(SpiderRules { stock_deals: 9, .. SpiderRules::new(1) }.validate().is_ok());
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = SpiderGame::new(SpiderRules::new(2), |c| seeded_shuffle(1, c));
+
+    let lens: Vec<usize> = game.piles().iter().map(|p| p.len()).collect();
+    assert_eq!(lens, vec![6, 6, 6, 6, 5, 5, 5, 5, 5, 5]);
+    assert!(game.piles().iter().all(|p| p.visible_cards().len() == 1));
+    assert_eq!(game.stock_len(), 50);
+    assert_eq!(game.validate(), Ok(()));
+
+    let short = SpiderGame::new(SpiderRules { stock_deals: 3, .. SpiderRules::new(1) }, |_| ());
+    assert_eq!(short.piles().iter().map(|p| p.len()).sum::<usize>(), 74);
+    assert_eq!(short.stock_len(), 30);
+  }
+
+  #[test]
+  fn moves() {
+    let mut game = game(2, &[&["KS", "9H", "8H", "7S"], &["10S"], &["8S"], &[]]);
+
+    assert_eq!(game.pile(0).run_len(), 1);
+    assert_eq!(game.can_move(0, 2, 2), Err(SpiderErr::InvalidMove), "8H 7S are not one suit");
+    assert_eq!(game.can_move(0, 1, 1), Err(SpiderErr::InvalidMove), "7S on 10S");
+    assert_eq!(game.can_move(0, 1, 2), Ok(()));
+    game.apply(SpiderMove::Cards { source: 0, count: 1, target: 3 }).unwrap();
+    assert_eq!(game.pile(0).run_len(), 2);
+
+    game.apply(SpiderMove::Cards { source: 0, count: 2, target: 1 }).unwrap();
+    assert_eq!(game.pile(1).visible_cards(), &[card("10S"), card("9H"), card("8H")]);
+    assert_eq!(game.can_deal(), Err(SpiderErr::EmptyStock));
+    assert_eq!(game.moves_made(), 2);
+  }
+
+  #[test]
+  fn complete_run() {
+    let run: Vec<String> = Rank::standard_iter().rev().map(|r| FrenchPlayingCard::new(Suit::Spades, *r).to_string()).collect();
+    let run: Vec<&str> = run.iter().map(|s| &s[..]).collect();
+
+    let mut game = game(1, &[&run[..12], &["AS"]]);
+    game.piles[0].hidden.push(card("5S#1"));
+
+    game.apply(SpiderMove::Cards { source: 1, count: 1, target: 0 }).unwrap();
+
+    assert_eq!(game.completed(), &[Suit::Spades]);
+    assert_eq!(game.pile(0).visible_cards(), &[card("5S#1")]);
+    assert_eq!(game.hidden_flips(), 1);
+    assert_eq!(game.score(), 500 - 1 + 100);
+  }
+
+  #[test]
+  fn deal_needs_every_pile() {
+    let mut game = SpiderGame::new(SpiderRules::new(1), |c| seeded_shuffle(3, c));
+    game.apply(SpiderMove::Deal).unwrap();
+    assert_eq!(game.stock_len(), 40);
+    assert!(game.piles().iter().all(|p| p.visible_cards().len() >= 2 || game.completed().len() > 0));
+
+    assert_eq!(game.validate(), Ok(()));
+
+    let mut duplicate = game.clone();
+    let copy = duplicate.piles[1].top().unwrap();
+    duplicate.piles[0].visible.push(copy);
+    assert!(duplicate.validate().is_err());
+
+    game.piles[4].hidden.clear();
+    game.piles[4].visible.clear();
+    assert_eq!(game.apply(SpiderMove::Deal), Err(SpiderErr::EmptyPile));
+    assert!(! game.legal_moves().contains(&SpiderMove::Deal));
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[5]);
+    let mut game = SpiderGame::new(SpiderRules::new(4), |c| seeded_shuffle(5, c));
+
+    for _ in 0..300 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+    }
+  }
+}
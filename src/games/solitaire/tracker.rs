@@ -0,0 +1,185 @@
+//! What a Klondike player knows about the cards they can not see, and the
+//! chance that the stock turns up a card they can play.
+//!
+//! A player sees every card on the foundations, the face up pile cards and
+//! each deck card once it has been drawn. `CardTracker` remembers the deck
+//! cards drawn so far; the rest of the deck and the hidden pile cards are
+//! unknown, each equally likely to be any of the cards not seen.
+
+use games::solitaire::klondike::{Card, KlondikeSolitaireGame};
+use prob::Hypergeometric;
+use std::collections::HashSet;
+
+/// Deck cards a player has seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardTracker {
+  seen: HashSet<Card>,
+}
+
+impl CardTracker {
+  pub fn new() -> CardTracker {
+    CardTracker {
+      seen: HashSet::new(),
+    }
+  }
+
+  /// Remember the deck cards currently face up. Call after every move to
+  /// see every card the player would.
+  pub fn observe(&mut self, game: &KlondikeSolitaireGame) {
+    self.seen.extend(game.deck().visible_cards().iter().cloned());
+  }
+
+  /// True if `card` was seen face up in the deck.
+  pub fn has_seen(&self, card: Card) -> bool {
+    self.seen.contains(&card)
+  }
+
+  /// The cards the player can not place: the hidden pile cards and the deck
+  /// cards not seen yet. Which cards these are is known to the player, where
+  /// they are is not.
+  pub fn unknown_cards(&self, game: &KlondikeSolitaireGame) -> Vec<Card> {
+    game.piles().iter()
+      .flat_map(|p| p.hidden_cards().iter())
+      .chain(game.deck().waste_cards().iter().chain(game.deck().remaining_cards()).filter(|c| ! self.has_seen(**c)))
+      .cloned()
+      .collect()
+  }
+}
+
+/// True if `card` could be played to a foundation or a pile of `game`.
+pub fn is_playable(game: &KlondikeSolitaireGame, card: Card) -> bool {
+  game.foundation(card.suit()).can_push(card) || game.piles().iter().any(|p| p.can_push(card).is_ok())
+}
+
+/// Probability that the next draw turns up a playable top card.
+pub fn next_draw_playable(game: &KlondikeSolitaireGame, tracker: &CardTracker) -> f64 {
+  playable_probability(game, tracker, &draw_tops(game, 1))
+}
+
+/// Probability that at least one of the top cards turned up by drawing
+/// through the rest of the pass is playable on the tableau as it is now.
+/// When the pass is over, this is the next pass.
+pub fn next_pass_playable(game: &KlondikeSolitaireGame, tracker: &CardTracker) -> f64 {
+  playable_probability(game, tracker, &draw_tops(game, usize::max_value()))
+}
+
+/// Deck cards that become the top card in the next `draws` draws, ending at
+/// the end of a pass.
+fn draw_tops(game: &KlondikeSolitaireGame, draws: usize) -> Vec<Card> {
+  let deck = game.deck();
+
+  if ! deck.can_draw() {
+    return Vec::new();
+  }
+
+  let cards: Vec<Card> = deck.waste_cards().iter()
+    .chain(deck.visible_cards())
+    .chain(deck.remaining_cards())
+    .cloned()
+    .collect();
+  let draw_count = deck.draw_count() as usize;
+
+  let start = match deck.waste_cards().len() + deck.visible_cards().len() {
+    n if n >= cards.len() => 0,
+    n => n,
+  };
+
+  (0..draws)
+    .map(|i| start + draw_count * i)
+    .take_while(|&first| first < cards.len())
+    .map(|first| cards[(first + draw_count).min(cards.len()) - 1])
+    .collect()
+}
+
+/// Probability that at least one of `tops` is playable, where the cards the
+/// tracker has not seen could be any of the unknown cards.
+fn playable_probability(game: &KlondikeSolitaireGame, tracker: &CardTracker, tops: &[Card]) -> f64 {
+  let (known, unknown): (Vec<Card>, Vec<Card>) = tops.iter().partition(|c| tracker.has_seen(**c));
+
+  if known.iter().any(|c| is_playable(game, *c)) {
+    return 1.0;
+  }
+
+  if unknown.is_empty() {
+    return 0.0;
+  }
+
+  let pool = tracker.unknown_cards(game);
+  let playable = pool.iter().filter(|c| is_playable(game, **c)).count();
+
+  Hypergeometric::new(pool.len() as u64, playable as u64, unknown.len() as u64).at_least(1)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_source;
+  use games::solitaire::klondike::KlondikeRules;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn assert_close(a: f64, b: f64, tolerance: f64) {
+    assert!((a - b).abs() <= tolerance, "{} != {} within {}", a, b, tolerance);
+  }
+
+  #[test]
+  fn tracks_deck_cards() {
+    let mut game = KlondikeSolitaireGame::with_rules(KlondikeRules::new(3), seeded_source(4));
+    let mut tracker = CardTracker::new();
+
+    assert_eq!(tracker.unknown_cards(&game).len(), 21 + 24);
+
+    game.draw();
+    tracker.observe(&game);
+    let visible: Vec<Card> = game.deck().visible_cards().to_vec();
+    assert!(visible.iter().all(|c| tracker.has_seen(*c)));
+    assert_eq!(tracker.unknown_cards(&game).len(), 21 + 21);
+    assert!(visible.iter().all(|c| ! tracker.unknown_cards(&game).contains(c)));
+  }
+
+  #[test]
+  fn draw_tops_of_pass() {
+    let game = KlondikeSolitaireGame::with_rules(KlondikeRules::new(3), seeded_source(4));
+    let deck = game.deck().remaining_cards().to_vec();
+
+    assert_eq!(draw_tops(&game, 1), vec![deck[2]]);
+    assert_eq!(draw_tops(&game, usize::max_value()), vec![deck[2], deck[5], deck[8], deck[11], deck[14], deck[17], deck[20], deck[23]]);
+  }
+
+  #[test]
+  fn known_cards_decide() {
+    let mut game = KlondikeSolitaireGame::with_rules(KlondikeRules::new(1), seeded_source(9));
+    let mut tracker = CardTracker::new();
+
+    // After a full pass every deck card is known
+    for _ in 0..25 {
+      game.draw();
+      tracker.observe(&game);
+    }
+    assert_eq!(game.deck().passes(), 2);
+
+    let next = game.deck().remaining_cards()[0];
+    let expected = if is_playable(&game, next) { 1.0 } else { 0.0 };
+    assert_eq!(next_draw_playable(&game, &tracker), expected);
+
+    let any = game.deck().remaining_cards().iter().any(|c| is_playable(&game, *c));
+    assert_eq!(next_pass_playable(&game, &tracker), if any { 1.0 } else { 0.0 });
+  }
+
+  #[test]
+  fn unknown_cards_match_shuffles() {
+    let game = KlondikeSolitaireGame::with_rules(KlondikeRules::new(1), seeded_source(2));
+    let tracker = CardTracker::new();
+    let p = next_draw_playable(&game, &tracker);
+    assert!(p > 0.0 && p < 1.0, "{}", p);
+
+    // Estimate from games with the unknown cards dealt at random
+    let mut rng = Isaac64Rng::from_seed(&[3]);
+    let games = 5000;
+    let hits = (0..games).filter(|_| {
+      let det = game.determinize(|c| tracker.has_seen(c), |c| rng.shuffle(c));
+      is_playable(&det, det.deck().remaining_cards()[0])
+    }).count();
+
+    assert_close(hits as f64 / games as f64, p, 0.03);
+  }
+}
@@ -0,0 +1,296 @@
+//! Yukon solitaire.
+//!
+//! The whole deck is dealt to seven piles: one card to the first pile and,
+//! to each of the others, one to six hidden cards under five visible ones. The piles and the foundations follow the Klondike rules, but any
+//! visible card can be moved onto a pile together with all the cards on top
+//! of it, whether or not they are in sequence. The game is won when every
+//! card is on the foundations.
+
+use cards::french::{self, Suit};
+use games::solitaire::SolitaireGame;
+use games::solitaire::klondike::{Card, Foundation, KlondikeErr, KlondikeResult, Pile};
+use std::cmp;
+use std::collections::HashSet;
+use std::fmt;
+
+pub const NUM_PILES: usize = 7;
+pub const NUM_FOUNDATIONS: usize = 4;
+const NUM_CARDS: usize = 52;
+const VISIBLE_DEAL: usize = 5;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum YukonMove {
+  /// Move visible card `index` of pile `source` and the cards on top of it
+  /// onto pile `target`.
+  Pile { source: u8, index: u8, target: u8 },
+  /// Move the top card of a pile to its foundation.
+  Found(u8),
+}
+
+impl fmt::Display for YukonMove {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      YukonMove::Pile { source, index, target } => write!(f, "{}:{}->{}", source, index, target),
+      YukonMove::Found(source) => write!(f, "{}->f", source),
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct YukonGame {
+  piles: Vec<Pile>,
+  /// In the order of `Suit::standard_iter`.
+  foundations: Vec<Foundation>,
+  moves_made: usize,
+  hidden_flips: usize,
+}
+
+impl YukonGame {
+  /// New game with a standard deck arranged by `shuffle`, dealt one pile
+  /// after the other, the hidden cards of a pile first.
+  pub fn new<F>(mut shuffle: F) -> YukonGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut dealt = cards.into_iter();
+    let piles = (0..NUM_PILES).map(|i| {
+      let hidden: Vec<Card> = dealt.by_ref().take(i).collect();
+      let visible: Vec<Card> = dealt.by_ref().take(if i == 0 { 1 } else { VISIBLE_DEAL }).collect();
+      Pile::with_cards(hidden, visible)
+    }).collect();
+
+    YukonGame {
+      piles: piles,
+      foundations: Suit::standard_iter().map(|s| Foundation::new(*s)).collect(),
+      moves_made: 0,
+      hidden_flips: 0,
+    }
+  }
+
+  pub fn piles(&self) -> &[Pile] {
+    &self.piles
+  }
+
+  pub fn pile(&self, index: usize) -> &Pile {
+    &self.piles[index]
+  }
+
+  pub fn foundations(&self) -> &[Foundation] {
+    &self.foundations
+  }
+
+  pub fn foundation(&self, suit: Suit) -> &Foundation {
+    self.foundations.iter().find(|f| f.suit() == suit).unwrap()
+  }
+
+  pub fn founded(&self) -> usize {
+    self.foundations.iter().map(|f| f.len()).sum()
+  }
+
+  pub fn is_clear(&self) -> bool {
+    self.founded() == NUM_CARDS
+  }
+
+  pub fn moves_made(&self) -> usize {
+    self.moves_made
+  }
+
+  /// Number of hidden pile cards turned face up since the deal.
+  pub fn hidden_flips(&self) -> usize {
+    self.hidden_flips
+  }
+
+  /// Cards on the foundations.
+  pub fn score(&self) -> i32 {
+    self.founded() as i32
+  }
+
+  pub fn can_apply(&self, m: YukonMove) -> KlondikeResult<()> {
+    match m {
+      YukonMove::Pile { source, index, target } => {
+        let (source, index, target) = (source as usize, index as usize, target as usize);
+        if source >= NUM_PILES || target >= NUM_PILES || source == target {
+          return Err(KlondikeErr::InvalidMove);
+        }
+
+        match self.piles[source].visible_cards().get(index) {
+          Some(card) => self.piles[target].can_push(*card),
+          None => Err(KlondikeErr::InvalidMove),
+        }
+      },
+      YukonMove::Found(source) => match self.piles.get(source as usize).and_then(|p| p.top()) {
+        Some(card) if self.foundation(card.suit()).can_push(card) => Ok(()),
+        Some(card) => Err(KlondikeErr::InvalidCard(card)),
+        None => Err(KlondikeErr::InvalidMove),
+      },
+    }
+  }
+
+  pub fn apply(&mut self, m: YukonMove) -> KlondikeResult<()> {
+    self.can_apply(m)?;
+
+    let source = match m {
+      YukonMove::Pile { source, .. } | YukonMove::Found(source) => source as usize,
+    };
+    let hidden = self.piles[source].hidden_cards().len();
+
+    match m {
+      YukonMove::Pile { source, index, target } => {
+        let (source, target) = (source as usize, target as usize);
+        let (low, high) = self.piles.split_at_mut(cmp::max(source, target));
+        let (source_pile, target_pile) = if source < target {
+          (&mut low[source], &mut high[0])
+        } else {
+          (&mut high[0], &mut low[target])
+        };
+        source_pile.move_from(index as usize, target_pile)?;
+      },
+      YukonMove::Found(source) => {
+        let card = self.piles[source as usize].pop().unwrap();
+        self.foundations.iter_mut().find(|f| f.suit() == card.suit()).unwrap().push();
+      },
+    }
+
+    if self.piles[source].hidden_cards().len() < hidden {
+      self.hidden_flips += 1;
+    }
+
+    self.moves_made += 1;
+    Ok(())
+  }
+
+  /// Every move that would currently succeed, foundation moves first.
+  pub fn legal_moves(&self) -> Vec<YukonMove> {
+    let mut moves: Vec<YukonMove> = (0..NUM_PILES).map(|i| YukonMove::Found(i as u8)).collect();
+
+    for source in 0..NUM_PILES {
+      for index in 0..self.piles[source].visible_cards().len() {
+        for target in 0..NUM_PILES {
+          moves.push(YukonMove::Pile { source: source as u8, index: index as u8, target: target as u8 });
+        }
+      }
+    }
+
+    moves.retain(|m| self.can_apply(*m).is_ok());
+    moves
+  }
+
+  /// Check that the game holds every card of the deck once.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    let cards = self.piles.iter()
+      .flat_map(|p| p.hidden_cards().iter().chain(p.visible_cards().iter()).cloned().collect::<Vec<Card>>())
+      .chain(self.foundations.iter().flat_map(|f| f.cards()));
+
+    for card in cards {
+      if ! seen.insert(card) {
+        return Err(format!("duplicate card {}", card));
+      }
+    }
+
+    match french::new_standard_deck().into_iter().find(|c| ! seen.contains(c)) {
+      Some(card) => Err(format!("missing card {}", card)),
+      None => Ok(()),
+    }
+  }
+
+  /// The piles from bottom to top with hidden cards in lower case, then the
+  /// top card of each foundation, e.g. `5h KS QD|...|- / AD - - 3S`.
+  pub fn notation(&self) -> String {
+    let piles: Vec<String> = self.piles.iter().map(|p| {
+      let cards: Vec<String> = p.hidden_cards().iter().map(|c| c.to_string().to_lowercase())
+        .chain(p.visible_cards().iter().map(|c| c.to_string()))
+        .collect();
+      if cards.is_empty() { String::from("-") } else { cards.join(" ") }
+    }).collect();
+
+    let foundations: Vec<String> = self.foundations.iter().map(|f| f.top().map_or(String::from("-"), |c| c.to_string())).collect();
+
+    format!("{} / {}", piles.join("|"), foundations.join(" "))
+  }
+}
+
+impl SolitaireGame for YukonGame {
+  fn is_clear(&self) -> bool {
+    YukonGame::is_clear(self)
+  }
+
+  fn score(&self) -> i32 {
+    YukonGame::score(self)
+  }
+
+  fn moves_made(&self) -> usize {
+    YukonGame::moves_made(self)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| card(c)).collect()
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = YukonGame::new(|c| seeded_shuffle(1, c));
+
+    let hidden: Vec<usize> = game.piles().iter().map(|p| p.hidden_cards().len()).collect();
+    let visible: Vec<usize> = game.piles().iter().map(|p| p.visible_cards().len()).collect();
+    assert_eq!(hidden, vec![0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(visible, vec![1, 5, 5, 5, 5, 5, 5]);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn moves_out_of_sequence() {
+    let mut game = YukonGame::new(|_| ());
+    game.piles = vec![
+      Pile::with_cards(cards(&["2C"]), cards(&["9H", "AS", "KD"])),
+      Pile::with_cards(Vec::new(), cards(&["10S"])),
+      Pile::with_cards(Vec::new(), Vec::new()),
+    ];
+    game.piles.extend((3..NUM_PILES).map(|_| Pile::with_cards(Vec::new(), Vec::new())));
+
+    assert_eq!(game.can_apply(YukonMove::Pile { source: 0, index: 1, target: 1 }), Err(KlondikeErr::InvalidCard(card("AS"))));
+    assert_eq!(game.can_apply(YukonMove::Pile { source: 0, index: 3, target: 1 }), Err(KlondikeErr::InvalidMove));
+    assert_eq!(game.can_apply(YukonMove::Found(0)), Err(KlondikeErr::InvalidCard(card("KD"))));
+    game.apply(YukonMove::Pile { source: 0, index: 2, target: 2 }).unwrap();
+    assert_eq!(game.pile(2).visible_cards(), &cards(&["KD"])[..]);
+
+    game.apply(YukonMove::Found(0)).unwrap();
+    game.apply(YukonMove::Pile { source: 0, index: 0, target: 1 }).unwrap();
+    assert_eq!(game.pile(1).visible_cards(), &cards(&["10S", "9H"])[..]);
+    assert_eq!(game.pile(0).visible_cards(), &cards(&["2C"])[..]);
+    assert_eq!(game.hidden_flips(), 1);
+    assert_eq!(game.score(), 1);
+
+    let moves = game.legal_moves();
+    assert!(moves.contains(&YukonMove::Pile { source: 2, index: 0, target: 3 }), "KD to an empty pile");
+    assert!(! moves.contains(&YukonMove::Pile { source: 1, index: 0, target: 2 }), "10S on KD");
+  }
+
+  #[test]
+  fn random_play_keeps_cards() {
+    let mut rng = Isaac64Rng::from_seed(&[4]);
+    let mut game = YukonGame::new(|c| seeded_shuffle(4, c));
+
+    for _ in 0..300 {
+      let moves = game.legal_moves();
+      match rng.choose(&moves) {
+        Some(m) => game.apply(*m).unwrap(),
+        None => break,
+      }
+      assert_eq!(game.validate(), Ok(()));
+    }
+  }
+}
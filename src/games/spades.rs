@@ -0,0 +1,496 @@
+//! Spades, four players in two partnerships: players 0 and 2 against 1
+//! and 3.
+//!
+//! The whole deck is dealt out, thirteen cards each, and every player bids
+//! the tricks they expect to take, starting left of the dealer; a bid of zero
+//! is nil, a promise to take no trick at all. The player left of the dealer
+//! leads. Players must follow suit when they can, spades are trumps, and
+//! spades cannot be led until one has been played unless the leader holds
+//! nothing else.
+//!
+//! A partnership that takes at least the sum of its bids, nil bids aside,
+//! scores ten points a trick bid and a point for every trick over, its bags;
+//! one that takes fewer loses ten points a trick bid. A nil bid wins or loses
+//! `nil_points` on its own, and the tricks of a nil bidder count neither
+//! towards the bid of the partner nor as bags. Every `bag_limit` bags a
+//! partnership collects over the deals of a match cost it `bag_penalty`.
+
+use cards::french::{self, FrenchPlayingCard, Suit};
+use games::trick::Trick;
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_PLAYERS: usize = 4;
+pub const HAND_LEN: usize = 13;
+pub const NIL: u8 = 0;
+
+/// The partnership of `player`, 0 or 1.
+pub fn team(player: usize) -> usize {
+  player % 2
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SpadesRules {
+  /// Points a partnership needs to win a match.
+  pub target: i32,
+  /// Points at which a partnership loses a match.
+  pub losing: i32,
+  pub nil_points: i32,
+  pub bag_limit: u32,
+  pub bag_penalty: i32,
+}
+
+impl SpadesRules {
+  /// Matches to 500, lost at -200, nil for 100 and 100 points for every ten
+  /// bags.
+  pub fn new() -> SpadesRules {
+    SpadesRules {
+      target: 500,
+      losing: -200,
+      nil_points: 100,
+      bag_limit: 10,
+      bag_penalty: 100,
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SpadesErr {
+  /// Every player has bid.
+  NotBidding,
+  /// A bid of more tricks than there are.
+  InvalidBid,
+  /// Cards are played after the bidding.
+  NotPlaying,
+  /// The card is not in the hand of the player to play.
+  NotInHand,
+  /// The card is in the hand but breaks a rule of the trick.
+  IllegalPlay,
+}
+
+pub type SpadesResult<T> = Result<T, SpadesErr>;
+
+/// Points and bags of a partnership, for one deal or a whole match.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TeamScore {
+  pub points: i32,
+  pub bags: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpadesGame {
+  dealer: usize,
+  hands: Vec<Vec<Card>>,
+  bids: Vec<u8>,
+  trick: Trick,
+  /// Tricks played to the end, in order.
+  tricks: Vec<Trick>,
+  /// Tricks taken by each player.
+  taken: [usize; NUM_PLAYERS],
+  spades_broken: bool,
+}
+
+impl SpadesGame {
+  /// Deal of a standard deck arranged by `shuffle`, dealt a card at a time
+  /// starting left of `dealer`.
+  pub fn new<F>(dealer: usize, mut shuffle: F) -> SpadesGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut hands = vec![Vec::with_capacity(HAND_LEN); NUM_PLAYERS];
+    for (i, card) in cards.into_iter().enumerate() {
+      hands[(dealer + 1 + i) % NUM_PLAYERS].push(card);
+    }
+
+    SpadesGame {
+      dealer: dealer,
+      hands: hands,
+      bids: Vec::with_capacity(NUM_PLAYERS),
+      trick: Trick::new((dealer + 1) % NUM_PLAYERS, NUM_PLAYERS),
+      tricks: Vec::new(),
+      taken: [0; NUM_PLAYERS],
+      spades_broken: false,
+    }
+  }
+
+  pub fn dealer(&self) -> usize {
+    self.dealer
+  }
+
+  pub fn hand(&self, player: usize) -> &[Card] {
+    &self.hands[player]
+  }
+
+  pub fn is_bidding(&self) -> bool {
+    self.bids.len() < NUM_PLAYERS
+  }
+
+  /// The player to bid, while bidding.
+  pub fn to_bid(&self) -> usize {
+    (self.dealer + 1 + self.bids.len()) % NUM_PLAYERS
+  }
+
+  /// Bid of `player`, `None` before they have bid.
+  pub fn bid_of(&self, player: usize) -> Option<u8> {
+    let order = (player + NUM_PLAYERS - self.dealer - 1) % NUM_PLAYERS;
+    self.bids.get(order).cloned()
+  }
+
+  /// Bid `tricks` for the player to bid, `NIL` for nil.
+  pub fn bid(&mut self, tricks: u8) -> SpadesResult<()> {
+    if ! self.is_bidding() {
+      return Err(SpadesErr::NotBidding);
+    }
+    if tricks as usize > HAND_LEN {
+      return Err(SpadesErr::InvalidBid);
+    }
+
+    self.bids.push(tricks);
+    Ok(())
+  }
+
+  /// The trick being played.
+  pub fn trick(&self) -> &Trick {
+    &self.trick
+  }
+
+  pub fn tricks(&self) -> &[Trick] {
+    &self.tricks
+  }
+
+  /// Tricks taken by `player`.
+  pub fn taken(&self, player: usize) -> usize {
+    self.taken[player]
+  }
+
+  pub fn spades_broken(&self) -> bool {
+    self.spades_broken
+  }
+
+  pub fn to_play(&self) -> usize {
+    self.trick.to_play()
+  }
+
+  pub fn is_over(&self) -> bool {
+    self.tricks.len() == HAND_LEN
+  }
+
+  /// Whether the player to play may play `card`.
+  pub fn can_play(&self, card: Card) -> SpadesResult<()> {
+    if self.is_bidding() || self.is_over() {
+      return Err(SpadesErr::NotPlaying);
+    }
+
+    let hand = &self.hands[self.to_play()];
+    if ! hand.contains(&card) {
+      return Err(SpadesErr::NotInHand);
+    }
+
+    let legal = match self.trick.suit() {
+      None => self.spades_broken || card.suit() != Suit::Spades || hand.iter().all(|c| c.suit() == Suit::Spades),
+      Some(_) => self.trick.follows(hand, card),
+    };
+
+    if legal { Ok(()) } else { Err(SpadesErr::IllegalPlay) }
+  }
+
+  /// The cards the player to play may play, in the order of their hand.
+  pub fn legal_plays(&self) -> Vec<Card> {
+    if self.is_bidding() || self.is_over() {
+      return Vec::new();
+    }
+
+    self.hands[self.to_play()].iter().cloned().filter(|c| self.can_play(*c).is_ok()).collect()
+  }
+
+  /// Play `card` for the player to play, ending the trick after the fourth.
+  pub fn play(&mut self, card: Card) -> SpadesResult<()> {
+    self.can_play(card)?;
+
+    let player = self.to_play();
+    self.hands[player].retain(|c| *c != card);
+    self.trick.push(card);
+    if card.suit() == Suit::Spades {
+      self.spades_broken = true;
+    }
+
+    if self.trick.is_complete() {
+      let (winner, _) = self.trick.winner(Some(Suit::Spades)).unwrap();
+      self.taken[winner] += 1;
+      let trick = ::std::mem::replace(&mut self.trick, Trick::new(winner, NUM_PLAYERS));
+      self.tricks.push(trick);
+    }
+
+    Ok(())
+  }
+
+  /// Points and bags of each partnership for the deal, before bag penalties,
+  /// once the deal is over.
+  pub fn score(&self, rules: &SpadesRules) -> [TeamScore; 2] {
+    let mut scores = [TeamScore { points: 0, bags: 0 }; 2];
+
+    for (t, score) in scores.iter_mut().enumerate() {
+      let mut bid = 0;
+      let mut tricks = 0;
+
+      for p in (0..NUM_PLAYERS).filter(|p| team(*p) == t) {
+        match self.bid_of(p) {
+          Some(NIL) => score.points += if self.taken[p] == 0 { rules.nil_points } else { -rules.nil_points },
+          Some(b) => {
+            bid += b as usize;
+            tricks += self.taken[p];
+          },
+          None => (),
+        }
+      }
+
+      if bid > 0 {
+        if tricks >= bid {
+          score.points += 10 * bid as i32 + (tricks - bid) as i32;
+          score.bags = (tricks - bid) as u32;
+        } else {
+          score.points -= 10 * bid as i32;
+        }
+      }
+    }
+
+    scores
+  }
+
+  /// Every card played so far, the trick being played included.
+  pub fn played(&self) -> Vec<Card> {
+    self.tricks.iter().chain(Some(&self.trick)).flat_map(|t| t.cards().iter().cloned()).collect()
+  }
+
+  /// The deal as `player` sees it.
+  pub fn view(&self, player: usize) -> SpadesView<'_> {
+    SpadesView {
+      game: self,
+      player: player,
+    }
+  }
+}
+
+/// What one player knows of a deal: their own hand, the bids and every card
+/// played, but not the hands of the others.
+#[derive(Debug, Copy, Clone)]
+pub struct SpadesView<'a> {
+  game: &'a SpadesGame,
+  player: usize,
+}
+
+impl<'a> SpadesView<'a> {
+  pub fn player(&self) -> usize {
+    self.player
+  }
+
+  pub fn partner(&self) -> usize {
+    (self.player + 2) % NUM_PLAYERS
+  }
+
+  pub fn hand(&self) -> &'a [Card] {
+    self.game.hand(self.player)
+  }
+
+  pub fn dealer(&self) -> usize {
+    self.game.dealer()
+  }
+
+  pub fn bid_of(&self, player: usize) -> Option<u8> {
+    self.game.bid_of(player)
+  }
+
+  pub fn trick(&self) -> &'a Trick {
+    self.game.trick()
+  }
+
+  pub fn tricks(&self) -> &'a [Trick] {
+    self.game.tricks()
+  }
+
+  pub fn played(&self) -> Vec<Card> {
+    self.game.played()
+  }
+
+  pub fn taken(&self, player: usize) -> usize {
+    self.game.taken(player)
+  }
+
+  pub fn spades_broken(&self) -> bool {
+    self.game.spades_broken()
+  }
+}
+
+/// The running score of a match: deals are played, the dealer moving left
+/// each deal, until a partnership reaches the target or falls to the losing
+/// score.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpadesMatch {
+  rules: SpadesRules,
+  scores: [TeamScore; 2],
+  deals: usize,
+}
+
+impl SpadesMatch {
+  pub fn new(rules: SpadesRules) -> SpadesMatch {
+    SpadesMatch {
+      rules: rules,
+      scores: [TeamScore { points: 0, bags: 0 }; 2],
+      deals: 0,
+    }
+  }
+
+  pub fn rules(&self) -> &SpadesRules {
+    &self.rules
+  }
+
+  pub fn scores(&self) -> &[TeamScore; 2] {
+    &self.scores
+  }
+
+  pub fn deals(&self) -> usize {
+    self.deals
+  }
+
+  /// Dealer of the next deal.
+  pub fn dealer(&self) -> usize {
+    self.deals % NUM_PLAYERS
+  }
+
+  /// Add the score of `game`, a finished deal, taking bag penalties.
+  pub fn record(&mut self, game: &SpadesGame) {
+    let deal = game.score(&self.rules);
+
+    for (score, deal) in self.scores.iter_mut().zip(deal.iter()) {
+      score.points += deal.points;
+      score.bags += deal.bags;
+
+      while score.bags >= self.rules.bag_limit {
+        score.bags -= self.rules.bag_limit;
+        score.points -= self.rules.bag_penalty;
+      }
+    }
+
+    self.deals += 1;
+  }
+
+  /// The partnership that won the match, `None` while it goes on. When both
+  /// pass the target or the losing score in the same deal the higher score
+  /// wins, and a tie plays on.
+  pub fn winner(&self) -> Option<usize> {
+    let (a, b) = (self.scores[0].points, self.scores[1].points);
+    let ended = self.scores.iter().any(|s| s.points >= self.rules.target || s.points <= self.rules.losing);
+
+    if ! ended || a == b {
+      None
+    } else if a > b {
+      Some(0)
+    } else {
+      Some(1)
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+  use rand::{Isaac64Rng, Rng, SeedableRng};
+
+  fn card(s: &str) -> Card {
+    s.parse().unwrap()
+  }
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn bidding() {
+    let mut game = SpadesGame::new(2, |c| seeded_shuffle(1, c));
+
+    assert!((0..NUM_PLAYERS).all(|p| game.hand(p).len() == HAND_LEN));
+    assert_eq!(game.to_bid(), 3);
+    assert_eq!(game.legal_plays(), vec![]);
+    assert_eq!(game.bid(14), Err(SpadesErr::InvalidBid));
+
+    for bid in &[3, NIL, 4, 2] {
+      game.bid(*bid).unwrap();
+    }
+    assert_eq!(game.bid(1), Err(SpadesErr::NotBidding));
+    assert_eq!(game.bid_of(3), Some(3));
+    assert_eq!(game.bid_of(0), Some(NIL));
+    assert_eq!(game.bid_of(2), Some(2));
+    assert_eq!(game.to_play(), 3, "left of the dealer leads");
+  }
+
+  #[test]
+  fn trick_rules() {
+    let mut game = SpadesGame::new(3, |_| ());
+    for _ in 0..NUM_PLAYERS {
+      game.bid(1).unwrap();
+    }
+    game.hands = vec![
+      cards(&["2S", "5H"]),
+      cards(&["AH", "KS"]),
+      cards(&["3S", "4D"]),
+      cards(&["QS", "JS"]),
+    ];
+
+    assert_eq!(game.play(card("2S")), Err(SpadesErr::IllegalPlay), "spades not broken");
+    game.play(card("5H")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["AH"]));
+    game.play(card("AH")).unwrap();
+    assert_eq!(game.legal_plays(), cards(&["3S", "4D"]), "void in hearts");
+    game.play(card("3S")).unwrap();
+    game.play(card("JS")).unwrap();
+
+    assert_eq!(game.taken(3), 1, "the higher trump takes the trick");
+    assert!(game.spades_broken());
+    assert_eq!(game.legal_plays(), cards(&["QS"]));
+  }
+
+  #[test]
+  fn scoring() {
+    let rules = SpadesRules::new();
+    let mut game = SpadesGame::new(3, |_| ());
+    game.bids = vec![NIL, 3, 4, 5];
+    game.taken = [1, 3, 7, 2];
+
+    assert_eq!(game.score(&rules), [
+      TeamScore { points: -100 + 43, bags: 3 },
+      TeamScore { points: -80, bags: 0 },
+    ]);
+
+    let mut m = SpadesMatch::new(rules);
+    m.scores[0].bags = 8;
+    m.record(&game);
+    assert_eq!(m.scores()[0], TeamScore { points: -57 - 100, bags: 1 });
+    assert_eq!(m.winner(), None);
+    assert_eq!(m.dealer(), 1);
+
+    m.scores[1].points = 500;
+    assert_eq!(m.winner(), Some(1));
+  }
+
+  #[test]
+  fn random_play_takes_every_trick() {
+    let mut rng = Isaac64Rng::from_seed(&[4]);
+
+    for dealer in 0..NUM_PLAYERS {
+      let mut game = SpadesGame::new(dealer, |c| seeded_shuffle(dealer as u64, c));
+      while game.is_bidding() {
+        game.bid(rng.gen_range(0, 5)).unwrap();
+      }
+
+      while ! game.is_over() {
+        let plays = game.legal_plays();
+        game.play(*rng.choose(&plays).unwrap()).unwrap();
+      }
+
+      assert_eq!(game.played().len(), 52);
+      assert_eq!(game.taken.iter().sum::<usize>(), HAND_LEN);
+    }
+  }
+}
@@ -0,0 +1,113 @@
+//! Tricks of the trick taking games: every player in turn plays a card, and
+//! the highest trump, or the highest card of the suit led when there is no
+//! trump in the trick, takes it. Ranks are aces high.
+
+use cards::french::{FrenchPlayingCard, Suit};
+
+pub type Card = FrenchPlayingCard;
+
+/// A trick: the player who led and the cards played in order from them.
+/// Players are numbered clockwise from 0, so the player after `p` is `p + 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trick {
+  leader: usize,
+  players: usize,
+  cards: Vec<Card>,
+}
+
+impl Trick {
+  /// Empty trick of `players` players led by `leader`.
+  pub fn new(leader: usize, players: usize) -> Trick {
+    Trick {
+      leader: leader,
+      players: players,
+      cards: Vec::with_capacity(players),
+    }
+  }
+
+  pub fn leader(&self) -> usize {
+    self.leader
+  }
+
+  pub fn cards(&self) -> &[Card] {
+    &self.cards
+  }
+
+  /// Suit of the card led, `None` before the lead.
+  pub fn suit(&self) -> Option<Suit> {
+    self.cards.first().map(|c| c.suit())
+  }
+
+  pub fn is_complete(&self) -> bool {
+    self.cards.len() == self.players
+  }
+
+  /// The player to play the next card.
+  pub fn to_play(&self) -> usize {
+    (self.leader + self.cards.len()) % self.players
+  }
+
+  /// Play the card of the player to play. Panics if the trick is complete.
+  pub fn push(&mut self, card: Card) {
+    assert!(! self.is_complete(), "trick already complete");
+    self.cards.push(card);
+  }
+
+  /// Whether a player holding `hand` follows the rules of suit by playing
+  /// `card`: a card of the suit led, or any card when void in it.
+  pub fn follows(&self, hand: &[Card], card: Card) -> bool {
+    match self.suit() {
+      Some(suit) => card.suit() == suit || hand.iter().all(|c| c.suit() != suit),
+      None => true,
+    }
+  }
+
+  /// The player who played the card taking the trick so far and that card,
+  /// `None` before the lead.
+  pub fn winner(&self, trump: Option<Suit>) -> Option<(usize, Card)> {
+    let led = self.suit()?;
+    let suit = match trump {
+      Some(trump) if self.cards.iter().any(|c| c.suit() == trump) => trump,
+      _ => led,
+    };
+
+    self.cards.iter().cloned().enumerate()
+      .filter(|&(_, c)| c.suit() == suit)
+      .max_by_key(|&(_, c)| c.rank().ace_high_value())
+      .map(|(i, c)| ((self.leader + i) % self.players, c))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn trick(leader: usize, s: &[&str]) -> Trick {
+    let mut trick = Trick::new(leader, 4);
+    for card in s {
+      trick.push(card.parse().unwrap());
+    }
+    trick
+  }
+
+  #[test]
+  fn winner() {
+    let t = trick(2, &["5D", "AS", "9D", "4D"]);
+    assert!(t.is_complete());
+    assert_eq!(t.winner(None), Some((0, "9D".parse().unwrap())));
+    assert_eq!(t.winner(Some(Suit::Spades)), Some((3, "AS".parse().unwrap())));
+    assert_eq!(t.winner(Some(Suit::Clubs)), Some((0, "9D".parse().unwrap())), "no trump played");
+    assert_eq!(Trick::new(1, 4).winner(None), None);
+  }
+
+  #[test]
+  fn follows_suit() {
+    let t = trick(3, &["5D"]);
+    let hand: Vec<Card> = ["2D", "KS"].iter().map(|c| c.parse().unwrap()).collect();
+
+    assert_eq!(t.to_play(), 0);
+    assert!(t.follows(&hand, hand[0]));
+    assert!(! t.follows(&hand, hand[1]));
+    assert!(t.follows(&hand[1..], hand[1]), "void in diamonds");
+  }
+}
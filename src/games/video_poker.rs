@@ -0,0 +1,295 @@
+//! Jacks or Better video poker.
+//!
+//! Each hand costs a credit. Five cards are dealt from a freshly shuffled
+//! deck, the player holds any of them and the others are replaced from the
+//! rest of the deck. The final hand pays by the pay table when it is at
+//! least a pair of jacks. Tables differ in what a full house and a flush pay
+//! and are named after them, "9/6" paying 9 and 6 credits.
+
+use cards::french::{self, FrenchPlayingCard};
+use cards::poker::{evaluate, Category};
+use std::fmt;
+use std::str::FromStr;
+
+pub type Card = FrenchPlayingCard;
+
+pub const HAND_LEN: usize = 5;
+
+/// Which cards of the dealt hand are held, by position.
+pub type Hold = [bool; HAND_LEN];
+
+/// Paying hands, lowest first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Win {
+  JacksOrBetter,
+  TwoPair,
+  ThreeOfAKind,
+  Straight,
+  Flush,
+  FullHouse,
+  FourOfAKind,
+  StraightFlush,
+  RoyalFlush,
+}
+
+pub static WINS: [Win; 9] = [
+  Win::JacksOrBetter, Win::TwoPair, Win::ThreeOfAKind, Win::Straight, Win::Flush,
+  Win::FullHouse, Win::FourOfAKind, Win::StraightFlush, Win::RoyalFlush,
+];
+
+impl fmt::Display for Win {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Win::JacksOrBetter => "jacks or better",
+      Win::TwoPair => "two pair",
+      Win::ThreeOfAKind => "three of a kind",
+      Win::Straight => "straight",
+      Win::Flush => "flush",
+      Win::FullHouse => "full house",
+      Win::FourOfAKind => "four of a kind",
+      Win::StraightFlush => "straight flush",
+      Win::RoyalFlush => "royal flush",
+    };
+
+    write!(f, "{}", name)
+  }
+}
+
+/// The paying hand `cards` make, `None` for a losing hand.
+pub fn classify(cards: &[Card]) -> Option<Win> {
+  let hand = evaluate(cards);
+
+  match hand.category() {
+    Category::HighCard => None,
+    Category::Pair if hand.values()[0] >= 11 => Some(Win::JacksOrBetter),
+    Category::Pair => None,
+    Category::TwoPair => Some(Win::TwoPair),
+    Category::ThreeOfAKind => Some(Win::ThreeOfAKind),
+    Category::Straight => Some(Win::Straight),
+    Category::Flush => Some(Win::Flush),
+    Category::FullHouse => Some(Win::FullHouse),
+    Category::FourOfAKind => Some(Win::FourOfAKind),
+    Category::StraightFlush if hand.is_royal() => Some(Win::RoyalFlush),
+    Category::StraightFlush => Some(Win::StraightFlush),
+  }
+}
+
+/// Names of the standard Jacks or Better pay tables, from the best.
+pub static PAY_TABLES: &'static [&'static str] = &["9/6", "8/6", "8/5", "7/5", "6/5"];
+
+/// Credits paid for each win, for a bet of one credit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PayTable {
+  pays: [u32; 9],
+}
+
+impl PayTable {
+  /// Jacks or Better paying `full_house` and `flush`. The royal flush pays
+  /// 800, the rate of the 4000 credits most machines pay for a five credit
+  /// bet, a straight flush 50, four of a kind 25, a straight 4, three of a
+  /// kind 3, two pair 2 and jacks or better 1.
+  pub fn jacks_or_better(full_house: u32, flush: u32) -> PayTable {
+    PayTable {
+      pays: [1, 2, 3, 4, flush, full_house, 25, 50, 800],
+    }
+  }
+
+  /// Credits paid for `win`, 0 for a losing hand.
+  pub fn pay(&self, win: Option<Win>) -> u32 {
+    match win {
+      Some(win) => self.pays[win as usize],
+      None => 0,
+    }
+  }
+}
+
+impl fmt::Display for PayTable {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}/{}", self.pay(Some(Win::FullHouse)), self.pay(Some(Win::Flush)))
+  }
+}
+
+impl FromStr for PayTable {
+  type Err = String;
+
+  /// Parse the name of one of `PAY_TABLES`.
+  fn from_str(s: &str) -> Result<PayTable, String> {
+    if ! PAY_TABLES.contains(&s) {
+      return Err(format!("unknown pay table {}, expected one of {}", s, PAY_TABLES.join(", ")));
+    }
+
+    let mut pays = s.split('/').map(|n| n.parse::<u32>().unwrap());
+    Ok(PayTable::jacks_or_better(pays.next().unwrap(), pays.next().unwrap()))
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum VideoPokerErr {
+  /// The hand has been drawn to already.
+  AlreadyDrawn,
+}
+
+pub type VideoPokerResult<T> = Result<T, VideoPokerErr>;
+
+/// One hand: the five cards dealt, then the cards held and drawn.
+#[derive(Debug, Clone)]
+pub struct VideoPokerHand {
+  cards: [Card; HAND_LEN],
+  /// The rest of the deck, the next card to draw first.
+  stock: Vec<Card>,
+  drawn: bool,
+}
+
+impl VideoPokerHand {
+  /// Deal from a standard deck arranged by `shuffle`.
+  pub fn deal<F>(mut shuffle: F) -> VideoPokerHand
+    where F: FnMut(&mut Vec<Card>) {
+    let mut stock = french::new_standard_deck();
+    shuffle(&mut stock);
+
+    let mut cards = [stock[0]; HAND_LEN];
+    for (i, card) in stock.drain(..HAND_LEN).enumerate() {
+      cards[i] = card;
+    }
+
+    VideoPokerHand {
+      cards: cards,
+      stock: stock,
+      drawn: false,
+    }
+  }
+
+  /// The cards dealt, or the final hand after drawing.
+  pub fn cards(&self) -> &[Card] {
+    &self.cards
+  }
+
+  pub fn is_drawn(&self) -> bool {
+    self.drawn
+  }
+
+  /// Keep the cards of `hold` and replace the others, in order.
+  pub fn draw(&mut self, hold: Hold) -> VideoPokerResult<()> {
+    if self.drawn {
+      return Err(VideoPokerErr::AlreadyDrawn);
+    }
+
+    let mut stock = self.stock.iter();
+    for (card, held) in self.cards.iter_mut().zip(hold.iter()) {
+      if ! held {
+        *card = *stock.next().unwrap();
+      }
+    }
+
+    self.drawn = true;
+    Ok(())
+  }
+
+  /// The paying hand of the cards.
+  pub fn win(&self) -> Option<Win> {
+    classify(&self.cards)
+  }
+}
+
+/// Every way of holding the cards of a hand, holding nothing first.
+pub fn holds() -> Vec<Hold> {
+  (0..1 << HAND_LEN)
+    .map(|bits: usize| {
+      let mut hold = [false; HAND_LEN];
+      for (i, held) in hold.iter_mut().enumerate() {
+        *held = bits & (1 << i) != 0;
+      }
+      hold
+    })
+    .collect()
+}
+
+/// Expected credits paid for holding `hold` of the dealt `cards` and drawing
+/// the rest, averaged over every draw from the other 47 cards of the deck.
+pub fn hold_ev(cards: &[Card], hold: Hold, table: &PayTable) -> f64 {
+  let stock: Vec<Card> = french::new_standard_deck().into_iter().filter(|c| ! cards.contains(c)).collect();
+  let mut hand: Vec<Card> = cards.iter().zip(hold.iter()).filter(|&(_, h)| *h).map(|(c, _)| *c).collect();
+  let draws = HAND_LEN - hand.len();
+
+  let mut total = 0u64;
+  let mut count = 0u64;
+  sum_draws(&stock, 0, draws, &mut hand, table, &mut total, &mut count);
+
+  total as f64 / count as f64
+}
+
+/// Add the pays of every hand completing `hand` with `draws` cards of
+/// `stock` from `from` on to `total`, counting the hands.
+fn sum_draws(stock: &[Card], from: usize, draws: usize, hand: &mut Vec<Card>, table: &PayTable, total: &mut u64, count: &mut u64) {
+  if draws == 0 {
+    *total += table.pay(classify(hand)) as u64;
+    *count += 1;
+    return;
+  }
+
+  for i in from..stock.len() - draws + 1 {
+    hand.push(stock[i]);
+    sum_draws(stock, i + 1, draws - 1, hand, table, total, count);
+    hand.pop();
+  }
+}
+
+/// The hold of `cards` with the highest expected pay and that pay.
+pub fn best_hold(cards: &[Card], table: &PayTable) -> (Hold, f64) {
+  holds().into_iter()
+    .map(|hold| (hold, hold_ev(cards, hold, table)))
+    .fold(([false; HAND_LEN], ::std::f64::NEG_INFINITY), |best, h| if h.1 > best.1 { h } else { best })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+
+  fn cards(s: &str) -> Vec<Card> {
+    s.split(' ').map(|c| c.parse().unwrap()).collect()
+  }
+
+  #[test]
+  fn wins() {
+    assert_eq!(classify(&cards("JS JH 2D 5C 8S")), Some(Win::JacksOrBetter));
+    assert_eq!(classify(&cards("10S 10H 2D 5C 8S")), None);
+    assert_eq!(classify(&cards("10S JS QS KS AS")), Some(Win::RoyalFlush));
+    assert_eq!(classify(&cards("9S 10S JS QS KS")), Some(Win::StraightFlush));
+
+    let table: PayTable = "9/6".parse().unwrap();
+    assert_eq!(table.pay(Some(Win::FullHouse)), 9);
+    assert_eq!(table.pay(Some(Win::Flush)), 6);
+    assert_eq!(table.pay(Some(Win::RoyalFlush)), 800);
+    assert_eq!(table.pay(None), 0);
+    assert_eq!(table.to_string(), "9/6");
+    assert!("9/7".parse::<PayTable>().is_err());
+  }
+
+  #[test]
+  fn draw() {
+    let mut hand = VideoPokerHand::deal(|c| seeded_shuffle(4, c));
+    let dealt = hand.cards().to_vec();
+    let stock = hand.stock.clone();
+
+    hand.draw([true, false, true, false, false]).unwrap();
+    assert_eq!(hand.cards(), &[dealt[0], stock[0], dealt[2], stock[1], stock[2]][..]);
+    assert_eq!(hand.draw([true; HAND_LEN]), Err(VideoPokerErr::AlreadyDrawn));
+  }
+
+  #[test]
+  fn expected_values() {
+    let table = PayTable::jacks_or_better(9, 6);
+    let royal = cards("10S JS QS KS AS");
+    assert_eq!(hold_ev(&royal, [true; HAND_LEN], &table), 800.0);
+
+    // Four to a flush: 9 of the 47 cards complete it, and 3 pair the king.
+    let flush = cards("2H 5H 9H KH 3C");
+    assert!((hold_ev(&flush, [true, true, true, true, false], &table) - (9.0 * 6.0 + 3.0) / 47.0).abs() < 1e-9);
+
+    // Four to a royal flush beats the pat flush.
+    let four_royal = cards("10H JH QH KH 5H");
+    assert_eq!(hold_ev(&four_royal, [true; HAND_LEN], &table), 6.0);
+    assert!(hold_ev(&four_royal, [true, true, true, true, false], &table) > 6.0);
+  }
+}
@@ -0,0 +1,397 @@
+//! The card game War, two players with no decisions to make.
+//!
+//! The deck is dealt out between the players, who each turn show the top card
+//! of their hands. The higher rank, aces high, takes both cards to the bottom
+//! of its hand. On a tie the players go to war: each lays `war_cards` cards
+//! face down and shows another, and the higher of those takes every card
+//! played, going to war again on another tie. A player who cannot lay the
+//! cards for a war loses the game, as does one left without cards.
+//!
+//! The order the won cards go under the hand decides the rest of the game.
+//! With a fixed order some deals repeat a position and loop forever, so a
+//! game with a fixed pickup order remembers every position it reaches and
+//! stops at the first one it has seen before. Shuffled pickups cannot loop
+//! and are only stopped by `max_turns`.
+
+use cards::french::{self, FrenchPlayingCard};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+pub type Card = FrenchPlayingCard;
+
+pub const NUM_PLAYERS: usize = 2;
+
+/// The order the cards of a won turn go to the bottom of the winner's hand.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Pickup {
+  /// The winner's cards in the order played, then the loser's.
+  WinnerFirst,
+  /// The loser's cards in the order played, then the winner's.
+  LoserFirst,
+  /// Every card of the turn in a random order.
+  Shuffled,
+}
+
+/// Names accepted by `Pickup::from_str`.
+pub static PICKUPS: &'static [&'static str] = &["winner-first", "loser-first", "shuffled"];
+
+impl FromStr for Pickup {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Pickup, String> {
+    match s {
+      "winner-first" => Ok(Pickup::WinnerFirst),
+      "loser-first" => Ok(Pickup::LoserFirst),
+      "shuffled" => Ok(Pickup::Shuffled),
+      _ => Err(format!("Invalid pickup: {}", s)),
+    }
+  }
+}
+
+impl fmt::Display for Pickup {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Pickup::WinnerFirst => "winner-first",
+      Pickup::LoserFirst => "loser-first",
+      Pickup::Shuffled => "shuffled",
+    };
+
+    write!(f, "{}", name)
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WarRules {
+  pub pickup: Pickup,
+  /// Cards laid face down in a war before the one shown.
+  pub war_cards: usize,
+  /// Turns played before a game is stopped undecided.
+  pub max_turns: usize,
+}
+
+impl WarRules {
+  /// Winner's cards first, three cards face down in a war and at most
+  /// 100000 turns.
+  pub fn new() -> WarRules {
+    WarRules {
+      pickup: Pickup::WinnerFirst,
+      war_cards: 3,
+      max_turns: 100000,
+    }
+  }
+}
+
+/// How a game of war ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WarOutcome {
+  /// The player won every card.
+  Winner(usize),
+  /// Neither player could finish a war, and they had as many cards.
+  Draw,
+  /// The position after turn `start` came back `length` turns later, so the
+  /// game would go on forever.
+  Cycle { start: usize, length: usize },
+  /// Stopped after `max_turns` turns.
+  TurnLimit,
+}
+
+#[derive(Clone)]
+pub struct WarGame {
+  rules: WarRules,
+  /// Each from the top card down.
+  hands: [VecDeque<Card>; NUM_PLAYERS],
+  turns: usize,
+  wars: usize,
+  /// Turn after which each position was first reached, by the hash of the
+  /// hands. A hash collision could report a cycle that is not there, which
+  /// with 64 bit hashes and games of thousands of turns is not a concern.
+  seen: HashMap<u64, usize>,
+  rng: Isaac64Rng,
+  outcome: Option<WarOutcome>,
+}
+
+impl WarGame {
+  /// New game with a standard deck arranged by `shuffle` and dealt one card
+  /// at a time, the first to player 0. Shuffled pickups are seeded from the
+  /// deal, so the same deal always plays the same way.
+  pub fn new<F>(rules: WarRules, mut shuffle: F) -> WarGame
+    where F: FnMut(&mut Vec<Card>) {
+    let mut cards = french::new_standard_deck();
+    shuffle(&mut cards);
+
+    let mut hasher = DefaultHasher::new();
+    cards.hash(&mut hasher);
+
+    let mut hands = [VecDeque::new(), VecDeque::new()];
+    for (i, card) in cards.into_iter().enumerate() {
+      hands[i % NUM_PLAYERS].push_back(card);
+    }
+
+    let mut game = WarGame {
+      rules: rules,
+      hands: hands,
+      turns: 0,
+      wars: 0,
+      seen: HashMap::new(),
+      rng: Isaac64Rng::from_seed(&[hasher.finish()]),
+      outcome: None,
+    };
+    game.remember();
+    game
+  }
+
+  pub fn rules(&self) -> &WarRules {
+    &self.rules
+  }
+
+  /// Hand of `player` from the top card down.
+  pub fn hand(&self, player: usize) -> &VecDeque<Card> {
+    &self.hands[player]
+  }
+
+  pub fn turns(&self) -> usize {
+    self.turns
+  }
+
+  /// Number of ties played off, a war of wars counting twice.
+  pub fn wars(&self) -> usize {
+    self.wars
+  }
+
+  /// How the game ended, `None` while it goes on.
+  pub fn outcome(&self) -> Option<WarOutcome> {
+    self.outcome
+  }
+
+  /// Play turns until the game ends.
+  pub fn play(&mut self) -> WarOutcome {
+    loop {
+      if let Some(outcome) = self.turn() {
+        return outcome;
+      }
+    }
+  }
+
+  /// Play one turn, wars included. Returns the outcome once the game has
+  /// ended, without playing on.
+  pub fn turn(&mut self) -> Option<WarOutcome> {
+    if self.outcome.is_some() {
+      return self.outcome;
+    }
+
+    if let Some(player) = (0..NUM_PLAYERS).find(|p| self.hands[*p].is_empty()) {
+      return self.end(WarOutcome::Winner(1 - player));
+    }
+
+    let mut played: [Vec<Card>; NUM_PLAYERS] = [Vec::new(), Vec::new()];
+
+    let winner = loop {
+      let shown: Vec<Card> = (0..NUM_PLAYERS).map(|p| self.hands[p].pop_front().unwrap()).collect();
+      for p in 0..NUM_PLAYERS {
+        played[p].push(shown[p]);
+      }
+
+      let (a, b) = (shown[0].rank().ace_high_value(), shown[1].rank().ace_high_value());
+      if a != b {
+        break if a > b { 0 } else { 1 };
+      }
+
+      self.wars += 1;
+
+      let needed = self.rules.war_cards + 1;
+      let short: Vec<usize> = (0..NUM_PLAYERS).filter(|p| self.hands[*p].len() < needed).collect();
+      if ! short.is_empty() {
+        let outcome = match short.len() {
+          1 => WarOutcome::Winner(1 - short[0]),
+          _ if self.hands[0].len() == self.hands[1].len() => WarOutcome::Draw,
+          _ => WarOutcome::Winner(if self.hands[0].len() > self.hands[1].len() { 0 } else { 1 }),
+        };
+
+        // The winner of the game takes the cards of the war, and in a draw
+        // each player keeps their own.
+        for p in 0..NUM_PLAYERS {
+          let to = match outcome {
+            WarOutcome::Winner(w) => w,
+            _ => p,
+          };
+          self.hands[to].extend(played[p].drain(..));
+        }
+        return self.end(outcome);
+      }
+
+      for p in 0..NUM_PLAYERS {
+        for _ in 0..self.rules.war_cards {
+          let card = self.hands[p].pop_front().unwrap();
+          played[p].push(card);
+        }
+      }
+    };
+
+    let loser = 1 - winner;
+    let mut pickup: Vec<Card> = match self.rules.pickup {
+      Pickup::WinnerFirst | Pickup::Shuffled => played[winner].iter().chain(played[loser].iter()).cloned().collect(),
+      Pickup::LoserFirst => played[loser].iter().chain(played[winner].iter()).cloned().collect(),
+    };
+    if self.rules.pickup == Pickup::Shuffled {
+      self.rng.shuffle(&mut pickup);
+    }
+    self.hands[winner].extend(pickup);
+    self.turns += 1;
+
+    if self.hands[loser].is_empty() {
+      return self.end(WarOutcome::Winner(winner));
+    }
+    if let Some(start) = self.remember() {
+      return self.end(WarOutcome::Cycle { start: start, length: self.turns - start });
+    }
+    if self.turns >= self.rules.max_turns {
+      return self.end(WarOutcome::TurnLimit);
+    }
+
+    None
+  }
+
+  fn end(&mut self, outcome: WarOutcome) -> Option<WarOutcome> {
+    self.outcome = Some(outcome);
+    self.outcome
+  }
+
+  /// Record the position after the current turn, returning the turn it was
+  /// first reached after if it has been seen before. Shuffled pickups are not
+  /// recorded.
+  fn remember(&mut self) -> Option<usize> {
+    if self.rules.pickup == Pickup::Shuffled {
+      return None;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    self.hands.hash(&mut hasher);
+
+    let turns = self.turns;
+    match *self.seen.entry(hasher.finish()).or_insert(turns) {
+      start if start != turns => Some(start),
+      _ => None,
+    }
+  }
+
+  /// Check that the hands hold every card of the deck once.
+  pub fn validate(&self) -> Result<(), String> {
+    let mut cards: Vec<Card> = self.hands.iter().flat_map(|h| h.iter().cloned()).collect();
+    let mut deck = french::new_standard_deck();
+
+    if cards.len() != deck.len() {
+      return Err(format!("{} cards instead of {}", cards.len(), deck.len()));
+    }
+
+    cards.sort_by_key(|c| (c.suit() as u8, c.rank()));
+    deck.sort_by_key(|c| (c.suit() as u8, c.rank()));
+
+    match cards.iter().zip(deck.iter()).find(|&(a, b)| a != b) {
+      Some((card, _)) => Err(format!("unexpected card {}", card)),
+      None => Ok(()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_shuffle;
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  /// Game dealt `s` in order, the rest of the deck after it.
+  fn game(rules: WarRules, s: &[&str]) -> WarGame {
+    let front = cards(s);
+    WarGame::new(rules, |c| {
+      let mut deck = front.clone();
+      deck.extend(c.iter().cloned().filter(|card| ! front.contains(card)).collect::<Vec<Card>>());
+      *c = deck;
+    })
+  }
+
+  #[test]
+  fn new_deal() {
+    let game = WarGame::new(WarRules::new(), |c| seeded_shuffle(1, c));
+
+    assert_eq!(game.hand(0).len(), 26);
+    assert_eq!(game.hand(1).len(), 26);
+    assert_eq!(game.validate(), Ok(()));
+    assert_eq!(game.outcome(), None);
+  }
+
+  #[test]
+  fn higher_card_takes_the_turn() {
+    let mut game = game(WarRules::new(), &["AS", "KH", "2D", "3C"]);
+
+    assert_eq!(game.turn(), None);
+    assert_eq!(game.hand(0).len(), 27);
+    assert_eq!(game.hand(0).iter().rev().take(2).cloned().collect::<Vec<Card>>(), cards(&["KH", "AS"]), "winner's card first");
+
+    assert_eq!(game.turn(), None);
+    assert_eq!(game.hand(1).len(), 26);
+    assert_eq!(game.turns(), 2);
+  }
+
+  #[test]
+  fn war_takes_every_card_played() {
+    let mut game = game(WarRules::new(), &["5S", "5H", "2S", "2H", "3S", "3H", "4S", "4D", "KS", "QH"]);
+
+    assert_eq!(game.turn(), None);
+    assert_eq!(game.wars(), 1);
+    assert_eq!(game.hand(0).len(), 26 + 5);
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn short_player_loses_the_war() {
+    let mut game = part_deck(WarRules::new(), &["5S", "2S", "3S", "4S", "KS"], &["5H", "2H"]);
+
+    assert_eq!(game.turn(), Some(WarOutcome::Winner(0)));
+    assert_eq!(game.turn(), Some(WarOutcome::Winner(0)), "no turns after the end");
+  }
+
+  /// Game with the given hands instead of a deal.
+  fn part_deck(rules: WarRules, first: &[&str], second: &[&str]) -> WarGame {
+    let mut game = game(rules, &[]);
+    game.hands = [cards(first).into_iter().collect(), cards(second).into_iter().collect()];
+    game.seen.clear();
+    game.remember();
+    game
+  }
+
+  #[test]
+  fn fixed_pickup_finds_cycles() {
+    let mut game = part_deck(WarRules::new(), &["2S", "3S", "5S"], &["4S", "6S"]);
+    assert_eq!(game.play(), WarOutcome::Cycle { start: 3, length: 6 });
+    assert_eq!(game.turns(), 9);
+
+    // 4S takes 2S, then 3S takes 2S back and the hands are as dealt.
+    let rules = WarRules { pickup: Pickup::LoserFirst, ..WarRules::new() };
+    let mut game = part_deck(rules, &["2S", "3S"], &["4S"]);
+    assert_eq!(game.play(), WarOutcome::Cycle { start: 0, length: 2 });
+
+    let rules = WarRules { pickup: Pickup::Shuffled, max_turns: 50, ..WarRules::new() };
+    let mut game = part_deck(rules, &["2S", "3S"], &["4S"]);
+    assert!(game.play() != WarOutcome::Cycle { start: 0, length: 2 });
+  }
+
+  #[test]
+  fn games_end() {
+    for pickup in [Pickup::WinnerFirst, Pickup::LoserFirst, Pickup::Shuffled].iter() {
+      for seed in 0..20 {
+        let mut game = WarGame::new(WarRules { pickup: *pickup, ..WarRules::new() }, |c| seeded_shuffle(seed, c));
+        let outcome = game.play();
+
+        assert!(outcome != WarOutcome::TurnLimit, "{} pickup, seed {} reached the turn limit", pickup, seed);
+        assert_eq!(game.validate(), Ok(()));
+      }
+    }
+  }
+}
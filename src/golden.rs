@@ -0,0 +1,211 @@
+//! Golden outcomes: fixed seeds played with a fixed strategy, stored so that
+//! any change to the engine or a strategy that alters a game is flagged.
+//!
+//! A golden file has one line per game with tab separated fields:
+//!
+//! ```text
+//! seed  draw_count  strategy  won  founded  moves  score  board
+//! ```
+//!
+//! The deal is `seeded_shuffle(seed, ...)`, `won` is 0 or 1, `founded` is the
+//! number of cards on the foundations, `moves` the number of moves played and
+//! `board` the final `KlondikeSolitaireGame::notation`. Unlike a run log the
+//! moves themselves are not stored, so golden files stay small enough to
+//! keep in the repository; only deterministic strategies can be checked.
+
+use cards::seeded_source;
+use cards::french::Suit;
+use games::solitaire::klondike::{KlondikeRules, KlondikeSolitaireGame};
+use strategies::solitaire::klondike::StrategyFactory;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenOutcome {
+  pub seed: u64,
+  pub draw_count: u8,
+  pub strategy: String,
+  pub won: bool,
+  pub founded: usize,
+  pub moves: usize,
+  pub score: i32,
+  pub board: String,
+}
+
+impl GoldenOutcome {
+  /// Play the deal of `seed` with a new strategy and record the outcome.
+  pub fn play(seed: u64, draw_count: u8, strategy: &str, new_strategy: &StrategyFactory) -> GoldenOutcome {
+    let mut game = KlondikeSolitaireGame::with_rules(KlondikeRules::new(draw_count), seeded_source(seed));
+    new_strategy().play(&mut game);
+
+    GoldenOutcome {
+      seed: seed,
+      draw_count: draw_count,
+      strategy: strategy.to_string(),
+      won: game.is_clear(),
+      founded: Suit::standard_iter().map(|s| game.foundation(*s).len()).sum(),
+      moves: game.moves_made(),
+      score: game.score(),
+      board: game.notation(),
+    }
+  }
+
+  pub fn parse(line: &str) -> Result<GoldenOutcome, String> {
+    let fields: Vec<&str> = line.split('\t').collect();
+
+    if fields.len() != 8 {
+      return Err(format!("expected 8 fields, found {}", fields.len()));
+    }
+
+    Ok(GoldenOutcome {
+      seed: fields[0].parse::<u64>().map_err(|_| format!("invalid seed: {}", fields[0]))?,
+      draw_count: fields[1].parse::<u8>().map_err(|_| format!("invalid draw count: {}", fields[1]))?,
+      strategy: fields[2].to_string(),
+      won: fields[3] == "1",
+      founded: fields[4].parse::<usize>().map_err(|_| format!("invalid founded count: {}", fields[4]))?,
+      moves: fields[5].parse::<usize>().map_err(|_| format!("invalid move count: {}", fields[5]))?,
+      score: fields[6].parse::<i32>().map_err(|_| format!("invalid score: {}", fields[6]))?,
+      board: fields[7].to_string(),
+    })
+  }
+
+  /// Fields that differ from `actual`, empty if it is the same outcome.
+  pub fn differences(&self, actual: &GoldenOutcome) -> Vec<String> {
+    let mut differences = Vec::new();
+
+    if self.won != actual.won {
+      differences.push(format!("won {} -> {}", self.won, actual.won));
+    }
+
+    if self.founded != actual.founded {
+      differences.push(format!("founded {} -> {}", self.founded, actual.founded));
+    }
+
+    if self.moves != actual.moves {
+      differences.push(format!("moves {} -> {}", self.moves, actual.moves));
+    }
+
+    if self.score != actual.score {
+      differences.push(format!("score {} -> {}", self.score, actual.score));
+    }
+
+    if self.board != actual.board {
+      differences.push(format!("board {} -> {}", self.board, actual.board));
+    }
+
+    differences
+  }
+}
+
+impl fmt::Display for GoldenOutcome {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+           self.seed, self.draw_count, self.strategy,
+           if self.won { 1 } else { 0 }, self.founded,
+           self.moves, self.score, self.board)
+  }
+}
+
+/// A game whose outcome is not the golden one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+  pub expected: GoldenOutcome,
+  pub actual: GoldenOutcome,
+}
+
+impl fmt::Display for Drift {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "seed {} draw {} {}: {}", self.expected.seed, self.expected.draw_count,
+           self.expected.strategy, self.expected.differences(&self.actual).join(", "))
+  }
+}
+
+/// Outcomes of seeds `0..count` with each draw count in `draw_counts`.
+pub fn record(count: u64, draw_counts: &[u8], strategy: &str, new_strategy: &StrategyFactory) -> Vec<GoldenOutcome> {
+  draw_counts.iter()
+    .flat_map(|&draw_count| (0..count).map(move |seed| (seed, draw_count)))
+    .map(|(seed, draw_count)| GoldenOutcome::play(seed, draw_count, strategy, new_strategy))
+    .collect()
+}
+
+/// Play every golden game again and return the ones whose outcome changed.
+/// `strategy_factory` gives the strategy of each outcome by name.
+pub fn check<F>(golden: &[GoldenOutcome], strategy_factory: F) -> Vec<Drift>
+  where F: Fn(&str) -> StrategyFactory {
+  golden.iter()
+    .filter_map(|expected| {
+      let actual = GoldenOutcome::play(expected.seed, expected.draw_count, &expected.strategy, &strategy_factory(&expected.strategy));
+
+      if actual == *expected {
+        None
+      } else {
+        Some(Drift { expected: expected.clone(), actual: actual })
+      }
+    })
+    .collect()
+}
+
+pub fn read_golden<R: BufRead>(input: R) -> Result<Vec<GoldenOutcome>, String> {
+  input.lines().enumerate()
+    .filter(|&(_, ref line)| line.as_ref().map_or(true, |l| ! l.is_empty() && ! l.starts_with('#')))
+    .map(|(i, line)| line.map_err(|e| e.to_string()).and_then(|l| GoldenOutcome::parse(&l)).map_err(|e| format!("line {}: {}", i + 1, e)))
+    .collect()
+}
+
+pub fn write_golden<W: Write>(out: &mut W, golden: &[GoldenOutcome]) -> io::Result<()> {
+  for outcome in golden {
+    writeln!(out, "{}", outcome)?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::sync::Arc;
+  use strategies::solitaire::klondike::GameStrategy;
+  use strategies::solitaire::klondike::simple::SimpleKlondikeStrategy;
+
+  fn simple() -> StrategyFactory {
+    Arc::new(|| Box::new(SimpleKlondikeStrategy::new()) as Box<dyn GameStrategy>)
+  }
+
+  #[test]
+  fn parse_round_trip() {
+    let outcome = GoldenOutcome::play(7, 3, "simple", &simple());
+    let text = outcome.to_string();
+
+    assert_eq!(GoldenOutcome::parse(&text), Ok(outcome.clone()));
+    assert!(GoldenOutcome::parse("7\t3\tsimple").is_err());
+
+    let mut out = Vec::new();
+    write_golden(&mut out, &[outcome.clone(), outcome.clone()]).unwrap();
+    let input = format!("# golden\n\n{}", String::from_utf8(out).unwrap());
+    assert_eq!(read_golden(input.as_bytes()), Ok(vec![outcome.clone(), outcome]));
+  }
+
+  #[test]
+  fn check_drift() {
+    let mut golden = record(3, &[1], "simple", &simple());
+    assert_eq!(check(&golden, |_| simple()), vec![]);
+
+    golden[1].moves += 1;
+    golden[1].score = -1000;
+    let drift = check(&golden, |_| simple());
+
+    assert_eq!(drift.len(), 1);
+    assert_eq!(drift[0].expected, golden[1]);
+    assert_eq!(drift[0].to_string(), format!("seed 1 draw 1 simple: moves {} -> {}, score -1000 -> {}",
+                                             golden[1].moves, golden[1].moves - 1, drift[0].actual.score));
+  }
+
+  /// The stored outcomes of the simple strategy, see `golden --write`.
+  #[test]
+  fn simple_golden() {
+    let golden = read_golden(include_str!("../golden/simple.txt").as_bytes()).unwrap();
+    let drift: Vec<String> = check(&golden, |_| simple()).iter().map(|d| d.to_string()).collect();
+
+    assert!(drift.is_empty(), "outcomes changed, regenerate golden/simple.txt if intended:\n{}", drift.join("\n"));
+  }
+}
@@ -0,0 +1,359 @@
+//! Minimal JSON values, enough to exchange deals and game states with other
+//! programs without pulling in a serialization framework.
+
+use std::fmt;
+
+/// Deepest nesting of arrays and objects `Value::parse` reads, so that deeply
+/// nested text fails instead of overflowing the stack.
+pub const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Null,
+  Bool(bool),
+  Number(f64),
+  String(String),
+  Array(Vec<Value>),
+  /// Members in the order they were written.
+  Object(Vec<(String, Value)>),
+}
+
+impl Value {
+  pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { chars: text.char_indices().peekable(), text: text, depth: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+
+    match parser.chars.peek() {
+      None => Ok(value),
+      Some(&(i, _)) => Err(format!("unexpected text at offset {}", i)),
+    }
+  }
+
+  /// Member `key` of an object, `None` for other values or missing keys.
+  pub fn get(&self, key: &str) -> Option<&Value> {
+    match *self {
+      Value::Object(ref members) => members.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v),
+      _ => None,
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match *self {
+      Value::String(ref s) => Some(s),
+      _ => None,
+    }
+  }
+
+  pub fn as_f64(&self) -> Option<f64> {
+    match *self {
+      Value::Number(n) => Some(n),
+      _ => None,
+    }
+  }
+
+  /// Number that is a non-negative integer.
+  pub fn as_u64(&self) -> Option<u64> {
+    match *self {
+      Value::Number(n) if n >= 0.0 && n.fract() == 0.0 && n < 18446744073709551616.0 => Some(n as u64),
+      _ => None,
+    }
+  }
+
+  pub fn as_bool(&self) -> Option<bool> {
+    match *self {
+      Value::Bool(b) => Some(b),
+      _ => None,
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&[Value]> {
+    match *self {
+      Value::Array(ref values) => Some(&values[..]),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for Value {
+  /// Compact JSON text. Numbers JSON can not represent, NaN and the
+  /// infinities, are written as `null`.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Value::Null => write!(f, "null"),
+      Value::Bool(b) => write!(f, "{}", b),
+      Value::Number(n) if ! n.is_finite() => write!(f, "null"),
+      Value::Number(n) => write!(f, "{}", n),
+      Value::String(ref s) => write_string(f, s),
+      Value::Array(ref values) => {
+        write!(f, "[")?;
+        for (i, v) in values.iter().enumerate() {
+          if i > 0 {
+            write!(f, ",")?;
+          }
+          write!(f, "{}", v)?;
+        }
+        write!(f, "]")
+      },
+      Value::Object(ref members) => {
+        write!(f, "{{")?;
+        for (i, &(ref k, ref v)) in members.iter().enumerate() {
+          if i > 0 {
+            write!(f, ",")?;
+          }
+          write_string(f, k)?;
+          write!(f, ":{}", v)?;
+        }
+        write!(f, "}}")
+      },
+    }
+  }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+  write!(f, "\"")?;
+
+  for c in s.chars() {
+    match c {
+      '"' => write!(f, "\\\"")?,
+      '\\' => write!(f, "\\\\")?,
+      '\n' => write!(f, "\\n")?,
+      '\r' => write!(f, "\\r")?,
+      '\t' => write!(f, "\\t")?,
+      c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+      c => write!(f, "{}", c)?,
+    }
+  }
+
+  write!(f, "\"")
+}
+
+struct Parser<'a> {
+  chars: ::std::iter::Peekable<::std::str::CharIndices<'a>>,
+  text: &'a str,
+  /// Arrays and objects the parser is inside of.
+  depth: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn whitespace(&mut self) {
+    while let Some(&(_, c)) = self.chars.peek() {
+      if ! c.is_whitespace() {
+        break;
+      }
+      self.chars.next();
+    }
+  }
+
+  fn expect(&mut self, expected: char) -> Result<(), String> {
+    match self.chars.next() {
+      Some((_, c)) if c == expected => Ok(()),
+      Some((i, c)) => Err(format!("expected '{}' at offset {}, found '{}'", expected, i, c)),
+      None => Err(format!("expected '{}', found end of text", expected)),
+    }
+  }
+
+  fn keyword(&mut self, word: &str, value: Value) -> Result<Value, String> {
+    for expected in word.chars() {
+      self.expect(expected)?;
+    }
+
+    Ok(value)
+  }
+
+  fn value(&mut self) -> Result<Value, String> {
+    self.whitespace();
+
+    match self.chars.peek().cloned() {
+      Some((i, '{')) | Some((i, '[')) if self.depth == MAX_DEPTH => Err(format!("nested more than {} deep at offset {}", MAX_DEPTH, i)),
+      Some((_, '{')) => self.nested(Parser::object),
+      Some((_, '[')) => self.nested(Parser::array),
+      Some((_, '"')) => self.string().map(Value::String),
+      Some((_, 't')) => self.keyword("true", Value::Bool(true)),
+      Some((_, 'f')) => self.keyword("false", Value::Bool(false)),
+      Some((_, 'n')) => self.keyword("null", Value::Null),
+      Some((i, c)) if c == '-' || c.is_digit(10) => self.number(i),
+      Some((i, c)) => Err(format!("unexpected '{}' at offset {}", c, i)),
+      None => Err(String::from("unexpected end of text")),
+    }
+  }
+
+  fn nested<F>(&mut self, parse: F) -> Result<Value, String> where F: FnOnce(&mut Parser<'a>) -> Result<Value, String> {
+    self.depth += 1;
+    let value = parse(self);
+    self.depth -= 1;
+    value
+  }
+
+  fn object(&mut self) -> Result<Value, String> {
+    self.expect('{')?;
+    let mut members = Vec::new();
+
+    self.whitespace();
+    if let Some(&(_, '}')) = self.chars.peek() {
+      self.chars.next();
+      return Ok(Value::Object(members));
+    }
+
+    loop {
+      self.whitespace();
+      let key = self.string()?;
+      self.whitespace();
+      self.expect(':')?;
+      members.push((key, self.value()?));
+      self.whitespace();
+
+      match self.chars.next() {
+        Some((_, ',')) => continue,
+        Some((_, '}')) => return Ok(Value::Object(members)),
+        Some((i, c)) => return Err(format!("expected ',' or '}}' at offset {}, found '{}'", i, c)),
+        None => return Err(String::from("unterminated object")),
+      }
+    }
+  }
+
+  fn array(&mut self) -> Result<Value, String> {
+    self.expect('[')?;
+    let mut values = Vec::new();
+
+    self.whitespace();
+    if let Some(&(_, ']')) = self.chars.peek() {
+      self.chars.next();
+      return Ok(Value::Array(values));
+    }
+
+    loop {
+      values.push(self.value()?);
+      self.whitespace();
+
+      match self.chars.next() {
+        Some((_, ',')) => continue,
+        Some((_, ']')) => return Ok(Value::Array(values)),
+        Some((i, c)) => return Err(format!("expected ',' or ']' at offset {}, found '{}'", i, c)),
+        None => return Err(String::from("unterminated array")),
+      }
+    }
+  }
+
+  fn string(&mut self) -> Result<String, String> {
+    self.expect('"')?;
+    let mut s = String::new();
+
+    loop {
+      match self.chars.next() {
+        Some((_, '"')) => return Ok(s),
+        Some((i, '\\')) => {
+          let c = match self.chars.next() {
+            Some((_, '"')) => '"',
+            Some((_, '\\')) => '\\',
+            Some((_, '/')) => '/',
+            Some((_, 'b')) => '\u{8}',
+            Some((_, 'f')) => '\u{c}',
+            Some((_, 'n')) => '\n',
+            Some((_, 'r')) => '\r',
+            Some((_, 't')) => '\t',
+            Some((_, 'u')) => {
+              let error = || format!("invalid unicode escape at offset {}", i);
+              let mut code = self.hex4().ok_or_else(error)?;
+
+              // Characters outside the basic plane are escaped as a pair of
+              // UTF-16 surrogates
+              if code >= 0xd800 && code < 0xdc00 {
+                if self.chars.next().map(|(_, c)| c) != Some('\\') || self.chars.next().map(|(_, c)| c) != Some('u') {
+                  return Err(error());
+                }
+                match self.hex4() {
+                  Some(low) if low >= 0xdc00 && low < 0xe000 => code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00),
+                  _ => return Err(error()),
+                }
+              }
+
+              ::std::char::from_u32(code).ok_or_else(error)?
+            },
+            _ => return Err(format!("invalid escape at offset {}", i)),
+          };
+          s.push(c);
+        },
+        Some((_, c)) => s.push(c),
+        None => return Err(String::from("unterminated string")),
+      }
+    }
+  }
+
+  /// The four hex digits of a unicode escape.
+  fn hex4(&mut self) -> Option<u32> {
+    let mut code = 0;
+    for _ in 0..4 {
+      code = code * 16 + self.chars.next().and_then(|(_, c)| c.to_digit(16))?;
+    }
+    Some(code)
+  }
+
+  fn number(&mut self, start: usize) -> Result<Value, String> {
+    let mut end = start;
+
+    while let Some(&(i, c)) = self.chars.peek() {
+      if ! (c.is_digit(10) || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E') {
+        break;
+      }
+      end = i + c.len_utf8();
+      self.chars.next();
+    }
+
+    let text = &self.text[start..end];
+    text.parse::<f64>().map(Value::Number).map_err(|_| format!("invalid number {} at offset {}", text, start))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn parse_values() {
+    let value = Value::parse(r#" {"cards": ["KS", "10H"], "seed": 42, "won": false, "note": null, "x": -1.5e2} "#).unwrap();
+
+    assert_eq!(value.get("seed").and_then(Value::as_u64), Some(42));
+    assert_eq!(value.get("won").and_then(Value::as_bool), Some(false));
+    assert_eq!(value.get("note"), Some(&Value::Null));
+    assert_eq!(value.get("x").and_then(Value::as_f64), Some(-150.0));
+    assert_eq!(value.get("missing"), None);
+
+    let cards: Vec<&str> = value.get("cards").and_then(Value::as_array).unwrap().iter().map(|v| v.as_str().unwrap()).collect();
+    assert_eq!(cards, ["KS", "10H"]);
+
+    assert_eq!(Value::parse("[]"), Ok(Value::Array(Vec::new())));
+    assert_eq!(Value::parse("{}"), Ok(Value::Object(Vec::new())));
+    assert_eq!(Value::parse(r#""a\"A""#), Ok(Value::String(String::from("a\"A"))));
+    assert_eq!(Value::parse(r#""\u00e9\ud83d\ude00""#), Ok(Value::String(String::from("\u{e9}\u{1f600}"))));
+
+    let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+    assert!(Value::parse(&nested).is_ok());
+  }
+
+  #[test]
+  fn parse_errors() {
+    for text in &["", "[1,", "{\"a\" 1}", "[1] x", "tru", "\"abc", "{1: 2}", "-"] {
+      assert!(Value::parse(text).is_err(), "{}", text);
+    }
+
+    for text in &[r#""\ud83d""#, r#""\ud83d\u0041""#, r#""\ude00""#, r#""\u+041""#, r#""\u00""#] {
+      assert!(Value::parse(text).is_err(), "{}", text);
+    }
+
+    let nested = "[".repeat(100000);
+    assert!(Value::parse(&nested).unwrap_err().contains("nested"));
+  }
+
+  #[test]
+  fn display_round_trip() {
+    let text = r#"{"a":[1,2.5,true,null],"b":"line\nbreak \"quoted\""}"#;
+    let value = Value::parse(text).unwrap();
+
+    assert_eq!(value.to_string(), text);
+    assert_eq!(Value::parse(&value.to_string()), Ok(value));
+
+    let numbers = Value::Array(vec![Value::Number(::std::f64::NAN), Value::Number(::std::f64::INFINITY), Value::Number(0.5)]);
+    assert_eq!(numbers.to_string(), "[null,null,0.5]");
+  }
+}
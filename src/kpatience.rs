@@ -0,0 +1,388 @@
+//! Import of Klondike games saved by KPatience, so a game that is stuck in
+//! another program can be checked with `solve`.
+//!
+//! KPatience saves a game as XML: the states of the game in order, each as
+//! the cards that moved into it, every move naming the pile and position of
+//! its first card and every card whether it ends up face up, if that
+//! changes. The first state deals the cards:
+//!
+//! ```text
+//! <?xml version="1.0" encoding="UTF-8"?>
+//! <!DOCTYPE kpat>
+//! <kpat-game game-type="klondike" game-type-options="0" deal-number="12">
+//!   <state>
+//!     <move pile="play0" position="0">
+//!       <card id="000030d" suit="spades" rank="king" turn="face-up"/>
+//!     </move>
+//!     ...
+//!   </state>
+//!   <state current="true">
+//!     <move pile="target0" position="0">
+//!       <card id="0000001" suit="clubs" rank="ace"/>
+//!     </move>
+//!   </state>
+//!   ...
+//! </kpat-game>
+//! ```
+//!
+//! The klondike piles are `talon`, the stock with the card drawn next last,
+//! `pile`, the waste with its top card last, `target0` to `target3`, the
+//! foundations, and `play0` to `play6`. Options `0` are the draw one game and
+//! `1` the draw three one. The states are played up to the one marked
+//! `current="true"`, or all of them, as the states after it were undone.
+//! PySol saves are pickled Python objects and can not be read.
+
+use cards::french::{Rank, Suit};
+use games::solitaire::klondike::{Card, Deck, Foundation, KlondikeSolitaireGame, Pile};
+use std::collections::HashMap;
+
+/// Where a card is: pile, position counted from the bottom, and whether it
+/// is face up.
+type Place = (String, usize, bool);
+
+/// Klondike game of the KPatience save `text`.
+pub fn read_game(text: &str) -> Result<KlondikeSolitaireGame, String> {
+  let tags = parse_tags(text)?;
+
+  let root = tags.iter().find(|t| ! t.end).ok_or_else(|| String::from("empty save file"))?;
+  if root.name != "kpat-game" {
+    return Err(format!("expected a kpat-game element, got {}", root.name));
+  }
+  match root.attr("game-type") {
+    Some("klondike") => {},
+    other => return Err(format!("game type {} is not klondike", other.unwrap_or("missing"))),
+  }
+  let draw_count = match root.attr("game-type-options") {
+    None | Some("0") => 1,
+    Some("1") => 3,
+    Some(other) => return Err(format!("unknown klondike options {}", other)),
+  };
+
+  let mut cards: HashMap<String, Card> = HashMap::new();
+  let mut places: HashMap<String, Place> = HashMap::new();
+  let mut target: Option<(String, usize)> = None;
+  let mut current = false;
+  let mut done = false;
+
+  for tag in &tags {
+    match (tag.name.as_str(), tag.end) {
+      ("state", false) => current = tag.attr("current") == Some("true"),
+      ("state", true) => done = done || current,
+      ("move", false) if ! done => {
+        let pile = tag.required("pile")?.to_string();
+        let position = tag.required("position")?.parse::<usize>().map_err(|_| format!("invalid position of a move to {}", pile))?;
+        target = Some((pile, position));
+      },
+      ("move", true) => target = None,
+      ("card", false) if ! done => {
+        let (pile, position) = target.as_mut().ok_or_else(|| String::from("card outside of a move"))?;
+        let id = tag.required("id")?.to_string();
+        let card = Card::new(parse_suit(tag.required("suit")?)?, parse_rank(tag.required("rank")?)?);
+        if cards.get(&id).map_or(false, |c| *c != card) {
+          return Err(format!("card {} changed from {} to {}", id, cards[&id], card));
+        }
+        // A card without a turn stays as it was, so it must have been dealt.
+        let up = match (tag.attr("turn"), places.get(&id)) {
+          (Some(_), _) => face_up(tag)?,
+          (None, Some(place)) => place.2,
+          (None, None) => return Err(format!("card {} dealt without a turn", id)),
+        };
+        cards.insert(id.clone(), card);
+        places.insert(id, (pile.clone(), *position, up));
+        *position += 1;
+      },
+      _ => {},
+    }
+  }
+
+  build_game(draw_count, &cards, &places)
+}
+
+fn build_game(draw_count: u8, cards: &HashMap<String, Card>, places: &HashMap<String, Place>) -> Result<KlondikeSolitaireGame, String> {
+  let mut piles: HashMap<&str, Vec<(usize, Card, bool)>> = HashMap::new();
+  for (id, &(ref pile, position, up)) in places {
+    piles.entry(pile.as_str()).or_insert_with(Vec::new).push((position, cards[id], up));
+  }
+  for (name, pile) in piles.iter_mut() {
+    pile.sort_by_key(|c| c.0);
+    if pile.windows(2).any(|w| w[0].0 == w[1].0) {
+      return Err(format!("two cards at one position of {}", name));
+    }
+  }
+
+  let mut take = |name: &str| -> Vec<(usize, Card, bool)> { piles.remove(name).unwrap_or_default() };
+
+  let mut remaining: Vec<Card> = take("talon").into_iter().map(|c| c.1).collect();
+  remaining.reverse();
+  let waste: Vec<Card> = take("pile").into_iter().map(|c| c.1).collect();
+  let turned = waste.len() - waste.len().min(draw_count as usize);
+  let deck = Deck::try_from(draw_count, &waste[..turned], &waste[turned..], &remaining).map_err(|e| e.to_string())?;
+
+  let mut foundations: Vec<Foundation> = Vec::new();
+  for i in 0..4 {
+    let cards: Vec<Card> = take(&format!("target{}", i)).into_iter().map(|c| c.1).collect();
+    if let Some(&last) = cards.last() {
+      let in_order = cards.iter().zip(Rank::standard_iter()).all(|(c, r)| c.suit() == last.suit() && c.rank() == *r);
+      if ! in_order {
+        return Err(format!("foundation target{} is out of order", i));
+      }
+      if foundations.iter().any(|f| f.suit() == last.suit()) {
+        return Err(format!("two foundations of {}", last.suit()));
+      }
+      foundations.push(Foundation::from(last.suit(), Some(last.rank())));
+    }
+  }
+  for suit in Suit::standard_iter() {
+    if ! foundations.iter().any(|f| f.suit() == *suit) {
+      foundations.push(Foundation::new(*suit));
+    }
+  }
+
+  let mut tableau = Vec::new();
+  for i in 0..7 {
+    let pile = take(&format!("play{}", i));
+    let hidden: Vec<Card> = pile.iter().filter(|c| ! c.2).map(|c| c.1).collect();
+    let visible: Vec<Card> = pile.iter().filter(|c| c.2).map(|c| c.1).collect();
+    if pile.iter().skip(hidden.len()).any(|c| ! c.2) {
+      return Err(format!("face down card over a face up one in play{}", i));
+    }
+    tableau.push(Pile::try_from(&hidden, &visible).map_err(|e| format!("play{}: {}", i, e))?);
+  }
+
+  if let Some(name) = piles.keys().next() {
+    return Err(format!("unknown klondike pile {}", name));
+  }
+
+  let f = foundations;
+  let p = tableau;
+  KlondikeSolitaireGame::try_from(
+    deck,
+    [f[0].clone(), f[1].clone(), f[2].clone(), f[3].clone()],
+    [p[0].clone(), p[1].clone(), p[2].clone(), p[3].clone(), p[4].clone(), p[5].clone(), p[6].clone()],
+  ).map_err(|e| e.to_string())
+}
+
+fn parse_suit(s: &str) -> Result<Suit, String> {
+  match s {
+    "clubs" => Ok(Suit::Clubs),
+    "diamonds" => Ok(Suit::Diamonds),
+    "hearts" => Ok(Suit::Hearts),
+    "spades" => Ok(Suit::Spades),
+    _ => Err(format!("unknown suit {}", s)),
+  }
+}
+
+static RANK_NAMES: [&'static str; 13] = [
+  "ace", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "jack", "queen", "king",
+];
+
+fn parse_rank(s: &str) -> Result<Rank, String> {
+  RANK_NAMES.iter().position(|r| *r == s)
+    .map(|i| *Rank::standard_iter().nth(i).unwrap())
+    .ok_or_else(|| format!("unknown rank {}", s))
+}
+
+fn face_up(tag: &Tag) -> Result<bool, String> {
+  match tag.required("turn")? {
+    "face-up" => Ok(true),
+    "face-down" => Ok(false),
+    other => Err(format!("unknown turn {}", other)),
+  }
+}
+
+/// Start or end of an XML element, with its attributes.
+#[derive(Debug)]
+struct Tag {
+  name: String,
+  attrs: Vec<(String, String)>,
+  end: bool,
+}
+
+impl Tag {
+  fn attr(&self, name: &str) -> Option<&str> {
+    self.attrs.iter().find(|a| a.0 == name).map(|a| a.1.as_str())
+  }
+
+  fn required(&self, name: &str) -> Result<&str, String> {
+    self.attr(name).ok_or_else(|| format!("{} element without {}", self.name, name))
+  }
+}
+
+/// Element tags of `text` in order, an empty element giving a start and an
+/// end. Text, comments, declarations and processing instructions are left
+/// out; this is enough XML for the saves KPatience writes.
+fn parse_tags(text: &str) -> Result<Vec<Tag>, String> {
+  let mut tags = Vec::new();
+  let mut rest = text;
+
+  while let Some(start) = rest.find('<') {
+    rest = &rest[start..];
+    if rest.starts_with("<!--") {
+      let end = rest.find("-->").ok_or_else(|| String::from("unterminated comment"))?;
+      rest = &rest[end + 3..];
+      continue;
+    }
+
+    let end = rest.find('>').ok_or_else(|| String::from("unterminated tag"))?;
+    let body = &rest[1..end];
+    rest = &rest[end + 1..];
+    if body.starts_with('?') || body.starts_with('!') {
+      continue;
+    }
+
+    if body.starts_with('/') {
+      tags.push(Tag { name: body[1..].trim().to_string(), attrs: Vec::new(), end: true });
+      continue;
+    }
+
+    let empty = body.ends_with('/');
+    let body = if empty { &body[..body.len() - 1] } else { body };
+    let name_end = body.find(|c: char| c.is_whitespace()).unwrap_or(body.len());
+    let name = body[..name_end].to_string();
+    let attrs = parse_attrs(&body[name_end..]).map_err(|e| format!("{} element: {}", name, e))?;
+
+    tags.push(Tag { name: name.clone(), attrs: attrs, end: false });
+    if empty {
+      tags.push(Tag { name: name, attrs: Vec::new(), end: true });
+    }
+  }
+
+  Ok(tags)
+}
+
+fn parse_attrs(mut s: &str) -> Result<Vec<(String, String)>, String> {
+  let mut attrs = Vec::new();
+
+  loop {
+    s = s.trim_start();
+    if s.is_empty() {
+      return Ok(attrs);
+    }
+
+    let eq = s.find('=').ok_or_else(|| format!("attribute without a value: {}", s))?;
+    let name = s[..eq].trim().to_string();
+    s = s[eq + 1..].trim_start();
+    let quote = s.chars().next().filter(|c| *c == '"' || *c == '\'').ok_or_else(|| format!("unquoted value of {}", name))?;
+    let close = s[1..].find(quote).ok_or_else(|| format!("unterminated value of {}", name))?;
+    attrs.push((name, unescape(&s[1..close + 1])));
+    s = &s[close + 2..];
+  }
+}
+
+fn unescape(s: &str) -> String {
+  s.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::french::new_standard_deck;
+  use games::solitaire::klondike::KlondikeRules;
+
+  static SUIT_NAMES: [&'static str; 4] = ["clubs", "diamonds", "hearts", "spades"];
+
+  /// Card element as KPatience writes it, its id made of the suit and rank,
+  /// and with a turn if `up` is set.
+  fn card_xml(card: Card, up: Option<bool>) -> String {
+    let suit = SUIT_NAMES.iter().position(|s| parse_suit(s) == Ok(card.suit())).unwrap();
+    let rank = card.rank().value() as usize;
+    let turn = match up {
+      Some(true) => " turn=\"face-up\"",
+      Some(false) => " turn=\"face-down\"",
+      None => "",
+    };
+    format!("<card id=\"{:07x}\" suit=\"{}\" rank=\"{}\"{}/>", (suit << 8) | rank, SUIT_NAMES[suit], RANK_NAMES[rank - 1], turn)
+  }
+
+  /// Save of the deal of `cards` as `deals` lays them out, followed by
+  /// `states`.
+  fn save(options: &str, states: &str) -> String {
+    let cards = new_standard_deck();
+    let mut xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE kpat>\n<kpat-game game-type=\"klondike\" game-type-options=\"{}\" deal-number=\"7\">\n<state>\n", options);
+
+    let mut next = 0;
+    for i in 0..7 {
+      xml.push_str(&format!("<move pile=\"play{}\" position=\"0\">", i));
+      for j in 0..i + 1 {
+        xml.push_str(&card_xml(cards[next], Some(j == i)));
+        next += 1;
+      }
+      xml.push_str("</move>\n");
+    }
+
+    // The talon has the card drawn first on top, last.
+    xml.push_str("<move pile=\"talon\" position=\"0\">");
+    for id in (next..52).rev() {
+      xml.push_str(&card_xml(cards[id], Some(false)));
+    }
+    xml.push_str("</move>\n</state>\n");
+    xml.push_str(states);
+    xml.push_str("</kpat-game>\n");
+    xml
+  }
+
+  /// State of the move of `card` to `position` of `pile`.
+  fn move_xml(pile: &str, position: usize, card: Card, up: Option<bool>) -> String {
+    format!("<state><move pile=\"{}\" position=\"{}\">{}</move></state>\n", pile, position, card_xml(card, up))
+  }
+
+  #[test]
+  fn read_deal() {
+    let cards = new_standard_deck();
+    let game = read_game(&save("0", "")).unwrap();
+    let dealt = KlondikeSolitaireGame::with_deal(KlondikeRules::new(1), cards);
+
+    assert_eq!(game.notation(), dealt.notation());
+    assert_eq!(game.deck().draw_count(), 1);
+    assert_eq!(read_game(&save("1", "")).unwrap().deck().draw_count(), 3);
+  }
+
+  #[test]
+  fn read_moves() {
+    let cards = new_standard_deck();
+    // Draw the first talon card, then undo a second draw.
+    let states = move_xml("pile", 0, cards[28], Some(true)).replace("<state>", "<state current=\"true\">")
+      + &move_xml("pile", 1, cards[29], Some(true));
+    let game = read_game(&save("0", &states)).unwrap();
+
+    assert_eq!(game.deck().top(), Some(cards[28]));
+    assert_eq!(game.deck().len(), 24);
+  }
+
+  #[test]
+  fn read_saved_game() {
+    // A draw one game saved after 21 moves, three foundation moves among
+    // them, with three more moves undone: the last of those turns a card.
+    let game = read_game(include_str!("../fixtures/klondike.kpat")).unwrap();
+
+    assert_eq!(game.notation(), "KH|8d 6H|2d 6c 6D|8c 8s 2s JH 10C|4c 9s 5c 3h 7H|5s 10s as 8h qh 5H|qs 5d 2h 4s 4d 7s 3S / AH AD AC / \
+                                 d1 9H 9D QD JD JS KS 3C 4H 9C 7D 10D KC : 6S : 3D JC QC 2C 7C KD 10H");
+    assert_eq!(game.validate(), Ok(()));
+  }
+
+  #[test]
+  fn read_errors() {
+    let cards = new_standard_deck();
+    assert!(read_game("").is_err());
+    assert!(read_game("<dealer id=\"0\"/>").is_err());
+    assert!(read_game(&save("2", "")).is_err());
+    assert!(read_game(&save("0", "").replace("game-type=\"klondike\"", "game-type=\"spider\"")).is_err());
+    assert_eq!(read_game(&format!("<kpat-game game-type=\"klondike\"><state>{}</state></kpat-game>", card_xml(cards[0], Some(true)))).err(),
+               Some(String::from("card outside of a move")));
+    // The first move of a card has to turn it.
+    assert_eq!(read_game(&save("0", "").replace(&card_xml(cards[0], Some(true)), &card_xml(cards[0], None))).err(),
+               Some(String::from("card 0000101 dealt without a turn")));
+    // Two cards at the bottom of the first pile.
+    assert!(read_game(&save("0", &move_xml("play0", 0, cards[1], None))).is_err());
+    assert!(read_game(&save("0", "").replace("play6", "play7")).is_err());
+  }
+
+  #[test]
+  fn parse_escaped_attributes() {
+    let tags = parse_tags("<a b=\"x &amp; y\" c='1'><!-- <z/> --></a>").unwrap();
+    assert_eq!(tags.len(), 2);
+    assert_eq!(tags[0].attr("b"), Some("x & y"));
+    assert_eq!(tags[0].attr("c"), Some("1"));
+    assert!(tags[1].end);
+  }
+}
@@ -0,0 +1,105 @@
+//! Provenance of a run, so result files can be traced back to the exact
+//! binary and settings that produced them.
+//!
+//! A manifest is a list of `key: value` lines. The settings of a run are an
+//! `options` line holding a JSON object, so they can be read back by other
+//! tools.
+
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct Manifest {
+  command: String,
+  entries: Vec<(String, String)>,
+  start: SystemTime,
+  end: Option<SystemTime>,
+}
+
+impl Manifest {
+  /// Start the manifest of a run of `command`, recording the current time as
+  /// the start of the run.
+  pub fn new(command: &str) -> Manifest {
+    Manifest {
+      command: command.to_string(),
+      entries: Vec::new(),
+      start: SystemTime::now(),
+      end: None,
+    }
+  }
+
+  pub fn add<T: ToString>(&mut self, key: &str, value: T) {
+    self.entries.push((key.to_string(), value.to_string()));
+  }
+
+  /// Record the current time as the end of the run.
+  pub fn finish(&mut self) {
+    self.end = Some(SystemTime::now());
+  }
+
+  pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    writeln!(out, "version: {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(out, "git: {}", option_env!("GIT_HASH").unwrap_or("unknown"))?;
+    writeln!(out, "command: {}", self.command)?;
+
+    for &(ref key, ref value) in &self.entries {
+      writeln!(out, "{}: {}", key, value)?;
+    }
+
+    writeln!(out, "start: {}", format_time(self.start))?;
+
+    if let Some(end) = self.end {
+      writeln!(out, "end: {}", format_time(end))?;
+      let elapsed = end.duration_since(self.start).unwrap_or_default();
+      writeln!(out, "elapsed: {}.{:03}s", elapsed.as_secs(), elapsed.subsec_nanos() / 1_000_000)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// UTC time in RFC 3339 format, e.g. `2016-05-01T12:30:00Z`.
+pub fn format_time(time: SystemTime) -> String {
+  let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+  let (days, rem) = (secs / 86400, secs % 86400);
+
+  // Civil date from days since the epoch (Howard Hinnant's algorithm)
+  let z = days as i64 + 719468;
+  let era = z / 146097;
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::time::Duration;
+
+  #[test]
+  fn format_times() {
+    assert_eq!(format_time(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    assert_eq!(format_time(UNIX_EPOCH + Duration::from_secs(951782400)), "2000-02-29T00:00:00Z");
+    assert_eq!(format_time(UNIX_EPOCH + Duration::from_secs(1462105800)), "2016-05-01T12:30:00Z");
+  }
+
+  #[test]
+  fn write_manifest() {
+    let mut manifest = Manifest::new("selfplay");
+    manifest.add("seed", 7);
+    manifest.finish();
+
+    let mut out = Vec::new();
+    manifest.write(&mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let keys: Vec<&str> = text.lines().map(|l| l.split(':').next().unwrap()).collect();
+
+    assert_eq!(keys, ["version", "git", "command", "seed", "start", "end", "elapsed"]);
+    assert!(text.contains("seed: 7\n"));
+  }
+}
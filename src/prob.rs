@@ -0,0 +1,167 @@
+//! Exact combinatorics for analytic answers to compare Monte Carlo estimates
+//! against: binomial coefficients, hypergeometric draws and the chance of a
+//! deal.
+//!
+//! Counts are exact `u128` values while they fit, which covers every choice
+//! from a 104 card double deck up to about 30 cards; `choose_f64` and the
+//! probabilities work in floating point from logarithms for the rest.
+
+/// Number of ways to choose `k` of `n` items, `None` if it overflows a
+/// `u128`.
+pub fn choose(n: u64, k: u64) -> Option<u128> {
+  if k > n {
+    return Some(0);
+  }
+
+  let k = if k > n - k { n - k } else { k };
+  let mut result: u128 = 1;
+
+  for i in 0..k as u128 {
+    // result * (n - i) is divisible by i + 1 since result is C(n, i)
+    result = result.checked_mul(n as u128 - i)? / (i + 1);
+  }
+
+  Some(result)
+}
+
+/// Natural logarithm of `choose(n, k)`, negative infinity if `k > n`.
+pub fn ln_choose(n: u64, k: u64) -> f64 {
+  if k > n {
+    return f64::NEG_INFINITY;
+  }
+
+  ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+/// `choose(n, k)` as a float, also for counts too large for a `u128`.
+pub fn choose_f64(n: u64, k: u64) -> f64 {
+  match choose(n, k) {
+    Some(c) => c as f64,
+    None => ln_choose(n, k).exp(),
+  }
+}
+
+fn ln_factorial(n: u64) -> f64 {
+  (2..n + 1).map(|i| (i as f64).ln()).sum()
+}
+
+/// Draws without replacement from `population` items of which `successes`
+/// are of interest, as in drawing `draws` cards from a deck.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Hypergeometric {
+  population: u64,
+  successes: u64,
+  draws: u64,
+}
+
+impl Hypergeometric {
+  /// Panics if there are more successes or draws than items.
+  pub fn new(population: u64, successes: u64, draws: u64) -> Hypergeometric {
+    assert!(successes <= population, "successes > population: {} > {}", successes, population);
+    assert!(draws <= population, "draws > population: {} > {}", draws, population);
+
+    Hypergeometric {
+      population: population,
+      successes: successes,
+      draws: draws,
+    }
+  }
+
+  /// Probability of drawing exactly `k` successes.
+  pub fn pmf(&self, k: u64) -> f64 {
+    if k > self.successes || k > self.draws || self.draws - k > self.population - self.successes {
+      return 0.0;
+    }
+
+    (ln_choose(self.successes, k) + ln_choose(self.population - self.successes, self.draws - k)
+     - ln_choose(self.population, self.draws)).exp()
+  }
+
+  /// Probability of drawing at most `k` successes.
+  pub fn cdf(&self, k: u64) -> f64 {
+    let p: f64 = (0..k + 1).map(|i| self.pmf(i)).sum();
+    p.min(1.0)
+  }
+
+  /// Probability of drawing at least `k` successes.
+  pub fn at_least(&self, k: u64) -> f64 {
+    if k == 0 {
+      return 1.0;
+    }
+
+    (1.0 - self.cdf(k - 1)).max(0.0)
+  }
+
+  pub fn mean(&self) -> f64 {
+    self.draws as f64 * self.successes as f64 / self.population as f64
+  }
+}
+
+/// Probability that a random deal of `hand` cards from a deck of `deck`
+/// cards holds all `wanted` given cards.
+pub fn deal_contains_all(deck: u64, hand: u64, wanted: u64) -> f64 {
+  Hypergeometric::new(deck, wanted, hand).pmf(wanted)
+}
+
+/// Probability that a random deal of `hand` cards from a deck of `deck`
+/// cards holds at least one of `wanted` given cards.
+pub fn deal_contains_any(deck: u64, hand: u64, wanted: u64) -> f64 {
+  Hypergeometric::new(deck, wanted, hand).at_least(1)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::french::{self, Rank};
+  use cards::seeded_shuffle;
+
+  fn assert_close(a: f64, b: f64, tolerance: f64) {
+    assert!((a - b).abs() <= tolerance, "{} != {} within {}", a, b, tolerance);
+  }
+
+  #[test]
+  fn binomial() {
+    assert_eq!(choose(52, 5), Some(2598960));
+    assert_eq!(choose(52, 0), Some(1));
+    assert_eq!(choose(5, 6), Some(0));
+    assert_eq!(choose(104, 10), Some(26100986351440));
+    assert_eq!(choose(1000, 500), None);
+
+    assert_close(choose_f64(52, 13), 635013559600.0, 1.0);
+    assert_close(choose_f64(1000, 500).ln(), ln_choose(1000, 500), 1e-9);
+    assert_close(ln_choose(52, 5).exp(), 2598960.0, 1e-3);
+  }
+
+  #[test]
+  fn hypergeometric() {
+    // Aces among 5 cards
+    let aces = Hypergeometric::new(52, 4, 5);
+    let total: f64 = (0..5).map(|k| aces.pmf(k)).sum();
+    assert_close(total, 1.0, 1e-12);
+    assert_close(aces.pmf(4), 48.0 / 2598960.0, 1e-15);
+    assert_close(aces.pmf(0), choose_f64(48, 5) / choose_f64(52, 5), 1e-12);
+    assert_close(aces.cdf(4), 1.0, 1e-12);
+    assert_close(aces.at_least(1), 1.0 - aces.pmf(0), 1e-12);
+    assert_close(aces.mean(), 20.0 / 52.0, 1e-12);
+    assert_eq!(aces.pmf(5), 0.0);
+
+    assert_close(deal_contains_all(52, 2, 2), 1.0 / 1326.0, 1e-15);
+    assert_close(deal_contains_any(52, 7, 4), 1.0 - choose_f64(48, 7) / choose_f64(52, 7), 1e-12);
+  }
+
+  #[test]
+  fn matches_shuffles() {
+    // Chance of an ace among the seven face up cards of a Klondike deal
+    let games = 20000;
+    let deck = french::new_standard_deck();
+    let tops = [0, 2, 5, 9, 14, 20, 27];
+
+    let hits = (0..games).filter(|seed| {
+      let mut cards = deck.clone();
+      seeded_shuffle(*seed, &mut cards);
+      tops.iter().any(|&i| cards[i].rank() == Rank::Ace)
+    }).count();
+
+    assert_close(hits as f64 / games as f64, deal_contains_any(52, 7, 4), 0.01);
+  }
+}
@@ -0,0 +1,216 @@
+//! Self contained HTML reports of a run.
+//!
+//! A report is a single file with inline styles and SVG charts, so it can be
+//! mailed or attached to an issue and opened in any browser.
+
+use analysis::histogram::Histogram;
+use std::io::{self, Write};
+
+const CHART_WIDTH: usize = 600;
+const CHART_HEIGHT: usize = 120;
+
+static STYLE: &'static str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin: 0.5em 0 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: right; }
+th { background: #eee; }
+td:first-child, th:first-child { text-align: left; }
+pre { background: #f6f6f6; padding: 1em; overflow-x: auto; }
+svg { display: block; margin: 0.5em 0 1.5em; }
+rect { fill: #4a7ab5; }
+";
+
+pub struct HtmlReport {
+  title: String,
+  body: String,
+}
+
+impl HtmlReport {
+  pub fn new(title: &str) -> HtmlReport {
+    HtmlReport {
+      title: title.to_string(),
+      body: String::new(),
+    }
+  }
+
+  pub fn heading(&mut self, text: &str) {
+    self.body.push_str(&format!("<h2>{}</h2>\n", escape(text)));
+  }
+
+  pub fn subheading(&mut self, text: &str) {
+    self.body.push_str(&format!("<h3>{}</h3>\n", escape(text)));
+  }
+
+  pub fn paragraph(&mut self, text: &str) {
+    self.body.push_str(&format!("<p>{}</p>\n", escape(text)));
+  }
+
+  pub fn table(&mut self, headers: &[&str], rows: &[Vec<String>]) {
+    self.body.push_str("<table>\n<tr>");
+    for h in headers {
+      self.body.push_str(&format!("<th>{}</th>", escape(h)));
+    }
+    self.body.push_str("</tr>\n");
+
+    for row in rows {
+      self.body.push_str("<tr>");
+      for cell in row {
+        self.body.push_str(&format!("<td>{}</td>", escape(cell)));
+      }
+      self.body.push_str("</tr>\n");
+    }
+
+    self.body.push_str("</table>\n");
+  }
+
+  /// Bar chart of a histogram with one bar per value between the smallest and
+  /// largest recorded value. Hovering a bar shows its value and count.
+  pub fn histogram(&mut self, caption: &str, histogram: &Histogram) {
+    self.subheading(caption);
+
+    let (min, max) = match (histogram.counts().next(), histogram.max()) {
+      (Some((min, _)), Some(max)) => (*min, max),
+      _ => {
+        self.paragraph("No values.");
+        return;
+      },
+    };
+
+    let largest = histogram.counts().map(|(_, c)| *c).max().unwrap();
+    let bar_width = CHART_WIDTH as f64 / (max - min + 1) as f64;
+
+    self.body.push_str(&format!("<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">\n",
+                                CHART_WIDTH, CHART_HEIGHT + 16));
+
+    for (value, count) in histogram.counts() {
+      let height = (*count as f64 / largest as f64 * CHART_HEIGHT as f64).max(1.0);
+      self.body.push_str(&format!("<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"><title>{}: {}</title></rect>\n",
+                                  (value - min) as f64 * bar_width, CHART_HEIGHT as f64 - height,
+                                  bar_width.max(1.0), height, value, count));
+    }
+
+    self.body.push_str(&format!("<text x=\"0\" y=\"{}\" font-size=\"12\">{}</text>\n", CHART_HEIGHT + 14, min));
+    self.body.push_str(&format!("<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"end\">{}</text>\n",
+                                CHART_WIDTH, CHART_HEIGHT + 14, max));
+    self.body.push_str("</svg>\n");
+  }
+
+  /// Line chart of one or more series of `(x, y)` points, scaled to fit all
+  /// of them. The first series is drawn solid, the others dashed.
+  pub fn line_chart(&mut self, caption: &str, series: &[Vec<(f64, f64)>]) {
+    self.subheading(caption);
+
+    let points: Vec<&(f64, f64)> = series.iter().flat_map(|s| s.iter()).collect();
+    if points.is_empty() {
+      self.paragraph("No values.");
+      return;
+    }
+
+    let bounds = |values: Vec<f64>| values.iter().fold((values[0], values[0]), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+    let (x_min, x_max) = bounds(points.iter().map(|p| p.0).collect());
+    let (y_min, y_max) = bounds(points.iter().map(|p| p.1).collect());
+    let scale = |v: f64, min: f64, max: f64, size: usize| {
+      if max > min { (v - min) / (max - min) * size as f64 } else { size as f64 / 2.0 }
+    };
+
+    self.body.push_str(&format!("<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">\n",
+                                CHART_WIDTH, CHART_HEIGHT + 16));
+
+    for (i, s) in series.iter().enumerate() {
+      let coordinates: Vec<String> = s.iter()
+        .map(|&(x, y)| format!("{:.1},{:.1}", scale(x, x_min, x_max, CHART_WIDTH), CHART_HEIGHT as f64 - scale(y, y_min, y_max, CHART_HEIGHT)))
+        .collect();
+      let dash = if i == 0 { "" } else { " stroke-dasharray=\"4 3\"" };
+
+      self.body.push_str(&format!("<polyline points=\"{}\" fill=\"none\" stroke=\"#4a7ab5\"{}/>\n", coordinates.join(" "), dash));
+    }
+
+    self.body.push_str(&format!("<text x=\"0\" y=\"{}\" font-size=\"12\">{}</text>\n", CHART_HEIGHT + 14, x_min));
+    self.body.push_str(&format!("<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"end\">{}</text>\n",
+                                CHART_WIDTH, CHART_HEIGHT + 14, x_max));
+    self.body.push_str(&format!("<text x=\"{}\" y=\"12\" font-size=\"12\" text-anchor=\"end\">{:.4}</text>\n", CHART_WIDTH, y_max));
+    self.body.push_str(&format!("<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"end\">{:.4}</text>\n",
+                                CHART_WIDTH, CHART_HEIGHT, y_min));
+    self.body.push_str("</svg>\n");
+  }
+
+  pub fn preformatted(&mut self, text: &str) {
+    self.body.push_str(&format!("<pre>{}</pre>\n", escape(text)));
+  }
+
+  pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", escape(&self.title))?;
+    writeln!(out, "<style>\n{}</style>\n</head>\n<body>", STYLE)?;
+    writeln!(out, "<h1>{}</h1>", escape(&self.title))?;
+    out.write_all(self.body.as_bytes())?;
+    writeln!(out, "</body>\n</html>")
+  }
+}
+
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      _ => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn html(report: &HtmlReport) -> String {
+    let mut out = Vec::new();
+    report.write(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn escapes_text() {
+    let mut report = HtmlReport::new("a < b");
+    report.table(&["move"], &[vec![String::from("pile0->pile1")]]);
+    report.preformatted("options: \"x\" & y");
+
+    let text = html(&report);
+    assert!(text.contains("<title>a &lt; b</title>"));
+    assert!(text.contains("<td>pile0-&gt;pile1</td>"));
+    assert!(text.contains("<pre>options: &quot;x&quot; &amp; y</pre>"));
+  }
+
+  #[test]
+  fn histogram_bars() {
+    let mut h = Histogram::new();
+    h.record(10);
+    h.record(10);
+    h.record(14);
+
+    let mut report = HtmlReport::new("moves");
+    report.histogram("won games", &h);
+    report.histogram("lost games", &Histogram::new());
+
+    let text = html(&report);
+    assert_eq!(text.matches("<rect").count(), 2);
+    assert!(text.contains("<title>10: 2</title>"));
+    assert!(text.contains("<p>No values.</p>"));
+  }
+
+  #[test]
+  fn line_chart_scales_points() {
+    let mut report = HtmlReport::new("convergence");
+    report.line_chart("win rate", &[vec![(0.0, 0.5), (10.0, 1.0)], vec![(10.0, 0.0)]]);
+
+    let text = html(&report);
+    assert!(text.contains(&format!("points=\"0.0,{:.1} {:.1},0.0\"", CHART_HEIGHT as f64 / 2.0, CHART_WIDTH as f64)));
+    assert_eq!(text.matches("stroke-dasharray").count(), 1);
+  }
+}
@@ -0,0 +1,181 @@
+//! Recorded runs for deterministic replay verification.
+//!
+//! A run log has one line per game with tab separated fields:
+//!
+//! ```text
+//! seed  draw_count  strategy  won  founded  board  moves
+//! ```
+//!
+//! The deal is `seeded_shuffle(seed, ...)`, `won` is 0 or 1, `founded` is the
+//! number of cards on the foundations, `board` is the final
+//! `KlondikeSolitaireGame::notation` and `moves` is the space separated list of
+//! moves played. Replaying the moves of a record on its deal must reproduce the
+//! recorded outcome exactly; a difference means the engine changed behavior.
+
+use cards::seeded_source;
+use cards::french::Suit;
+use games::solitaire::klondike::{KlondikeSolitaireGame, Move};
+use games::solitaire::replay::Replay;
+use strategies::solitaire::klondike::StrategyFactory;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+  pub seed: u64,
+  pub draw_count: u8,
+  pub strategy: String,
+  pub won: bool,
+  pub founded: usize,
+  pub board: String,
+  pub moves: Vec<Move>,
+}
+
+impl RunRecord {
+  /// Record of a finished game dealt from `seed`.
+  ///
+  /// Panics if the game was not recording moves.
+  pub fn new(seed: u64, strategy: &str, game: &KlondikeSolitaireGame) -> RunRecord {
+    RunRecord {
+      seed: seed,
+      draw_count: game.deck().draw_count(),
+      strategy: strategy.to_string(),
+      won: game.is_clear(),
+      founded: founded(game),
+      board: game.notation(),
+      moves: game.recorded_moves().expect("game is not recording moves"),
+    }
+  }
+
+  pub fn parse(line: &str) -> Result<RunRecord, String> {
+    let fields: Vec<&str> = line.split('\t').collect();
+
+    if fields.len() != 7 {
+      return Err(format!("expected 7 fields, found {}", fields.len()));
+    }
+
+    let moves = fields[6].split_whitespace()
+      .map(|m| m.parse::<Move>())
+      .collect::<Result<Vec<Move>, String>>()?;
+
+    Ok(RunRecord {
+      seed: fields[0].parse::<u64>().map_err(|_| format!("invalid seed: {}", fields[0]))?,
+      draw_count: fields[1].parse::<u8>().map_err(|_| format!("invalid draw count: {}", fields[1]))?,
+      strategy: fields[2].to_string(),
+      won: fields[3] == "1",
+      founded: fields[4].parse::<usize>().map_err(|_| format!("invalid founded count: {}", fields[4]))?,
+      board: fields[5].to_string(),
+      moves: moves,
+    })
+  }
+
+  pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    let moves: Vec<String> = self.moves.iter().map(|m| m.to_string()).collect();
+
+    writeln!(out, "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+             self.seed, self.draw_count, self.strategy,
+             if self.won { 1 } else { 0 }, self.founded,
+             self.board, moves.join(" "))
+  }
+
+  /// The recorded deal, before any move.
+  pub fn deal(&self) -> KlondikeSolitaireGame {
+    let seed = self.seed;
+    KlondikeSolitaireGame::new_shuffle(self.draw_count, seeded_source(seed))
+  }
+
+  /// Replay the recorded moves and check the outcome matches the record.
+  pub fn verify(&self) -> Result<(), String> {
+    let game = Replay::new(self.deal(), self.moves.clone()).finish().map_err(|e| e.to_string())?;
+
+    let replayed = RunRecord {
+      won: game.is_clear(),
+      founded: founded(&game),
+      board: game.notation(),
+      .. self.clone()
+    };
+
+    compare(self, &replayed)
+  }
+
+  /// Play the deal again with `new_strategy` and check that it makes the same
+  /// moves with the same outcome. Only meaningful for deterministic strategies.
+  pub fn verify_strategy(&self, new_strategy: &StrategyFactory) -> Result<(), String> {
+    let mut game = self.deal();
+    game.record_moves();
+    new_strategy().play(&mut game);
+
+    let replayed = RunRecord::new(self.seed, &self.strategy, &game);
+
+    if replayed.moves != self.moves {
+      let ply = self.moves.iter().zip(replayed.moves.iter())
+        .position(|(a, b)| a != b)
+        .unwrap_or(self.moves.len().min(replayed.moves.len()));
+
+      return Err(format!("strategy diverged at move {} ({} moves recorded, {} played)",
+                         ply, self.moves.len(), replayed.moves.len()));
+    }
+
+    compare(self, &replayed)
+  }
+}
+
+fn compare(expected: &RunRecord, actual: &RunRecord) -> Result<(), String> {
+  if expected.won != actual.won || expected.founded != actual.founded {
+    return Err(format!("outcome changed: recorded won={} founded={}, replayed won={} founded={}",
+                       expected.won, expected.founded, actual.won, actual.founded));
+  }
+
+  if expected.board != actual.board {
+    return Err(format!("final board changed:\n  recorded {}\n  replayed {}", expected.board, actual.board));
+  }
+
+  Ok(())
+}
+
+fn founded(game: &KlondikeSolitaireGame) -> usize {
+  Suit::standard_iter().map(|s| game.foundation(*s).len()).sum()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::sync::Arc;
+  use strategies::solitaire::klondike::GameStrategy;
+  use strategies::solitaire::klondike::simple::SimpleKlondikeStrategy;
+
+  fn played(seed: u64) -> RunRecord {
+    let mut game = KlondikeSolitaireGame::new_shuffle(1, seeded_source(seed));
+    game.record_moves();
+    SimpleKlondikeStrategy::new().play(&mut game);
+    RunRecord::new(seed, "simple", &game)
+  }
+
+  #[test]
+  fn write_parse() {
+    let record = played(5);
+    let mut out = Vec::new();
+    record.write(&mut out).unwrap();
+
+    let line = String::from_utf8(out).unwrap();
+    assert_eq!(RunRecord::parse(line.trim_end_matches('\n')), Ok(record));
+    assert!(RunRecord::parse("1\t1\tsimple").is_err());
+  }
+
+  #[test]
+  fn verify() {
+    let record = played(5);
+    assert_eq!(record.verify(), Ok(()));
+
+    let simple: StrategyFactory = Arc::new(|| Box::new(SimpleKlondikeStrategy::new()) as Box<dyn GameStrategy>);
+    assert_eq!(record.verify_strategy(&simple), Ok(()));
+
+    let mut changed = record.clone();
+    changed.founded += 1;
+    assert!(changed.verify().is_err());
+
+    let mut changed = record.clone();
+    changed.moves.truncate(1);
+    assert!(changed.verify().is_err());
+    assert!(changed.verify_strategy(&simple).is_err());
+  }
+}
@@ -0,0 +1,309 @@
+//! Programmatic runner for playing many Klondike games from other code, such
+//! as a server, a GUI or a notebook, rather than from the command line.
+//!
+//! A `Runner` plays the deals of consecutive seeds on several threads and
+//! aggregates the outcomes. A `CancellationToken` stops a run in flight: the
+//! workers finish the games they are playing, start no new ones, and the
+//! results of the games played so far are returned.
+//!
+//! `run_games` is the loop of a run, for runners that deal or record games
+//! their own way: the command line one plays its deal files, filters and
+//! strategy comparisons on it.
+
+use analysis::histogram::Histogram;
+use cards::ShuffleRng;
+use games::solitaire::klondike::{KlondikeRules, KlondikeSolitaireGame};
+use strategies::solitaire::klondike::{GameStrategy, StrategyFactory};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+/// Shared flag to stop a run. Clones refer to the same flag.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+  cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+  pub fn new() -> CancellationToken {
+    CancellationToken {
+      cancelled: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  /// Ask every run using the token to stop after its current games.
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::SeqCst);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
+  }
+}
+
+/// Aggregated outcome of a run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunResults {
+  pub games: usize,
+  pub wins: usize,
+  pub score: i64,
+  /// Moves of each won game.
+  pub win_moves: Histogram,
+  /// True if the run was cancelled before every game was played.
+  pub cancelled: bool,
+}
+
+impl RunResults {
+  pub fn new() -> RunResults {
+    RunResults {
+      games: 0,
+      wins: 0,
+      score: 0,
+      win_moves: Histogram::new(),
+      cancelled: false,
+    }
+  }
+
+  pub fn record(&mut self, game: &KlondikeSolitaireGame) {
+    self.games += 1;
+    self.score += game.score() as i64;
+
+    if game.is_clear() {
+      self.wins += 1;
+      self.win_moves.record(game.moves_made());
+    }
+  }
+
+  pub fn merge(&mut self, other: &RunResults) {
+    self.games += other.games;
+    self.wins += other.wins;
+    self.score += other.score;
+    self.win_moves.merge(&other.win_moves);
+    self.cancelled |= other.cancelled;
+  }
+
+  pub fn win_rate(&self) -> Option<f64> {
+    match self.games {
+      0 => None,
+      games => Some(self.wins as f64 / games as f64),
+    }
+  }
+
+  pub fn average_score(&self) -> Option<f64> {
+    match self.games {
+      0 => None,
+      games => Some(self.score as f64 / games as f64),
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Runner {
+  pub rules: KlondikeRules,
+  /// Number of games, the deals of seeds `first_seed..first_seed + games`.
+  pub games: usize,
+  pub first_seed: u64,
+  pub thread_count: usize,
+  /// Generator the deals of the seeds are shuffled with.
+  pub shuffle_rng: ShuffleRng,
+}
+
+impl Runner {
+  /// Runner of `games` games from seed 0 on one thread, shuffled by ISAAC-64
+  /// as `seeded_shuffle` does.
+  pub fn new(rules: KlondikeRules, games: usize) -> Runner {
+    Runner {
+      rules: rules,
+      games: games,
+      first_seed: 0,
+      thread_count: 1,
+      shuffle_rng: ShuffleRng::Isaac64,
+    }
+  }
+
+  /// Play the games, each with a new strategy from `new_strategy`, until
+  /// they are done or `token` is cancelled.
+  pub fn run(&self, new_strategy: &StrategyFactory, token: &CancellationToken) -> RunResults {
+    let runner = *self;
+    let new_strategy = new_strategy.clone();
+    let mut results = RunResults::new();
+
+    let workers = run_games(self.thread_count, Some(self.games), None, token, move || SeedWorker {
+      runner: runner,
+      strategy: new_strategy(),
+      results: RunResults::new(),
+    });
+
+    for played in workers {
+      results.merge(&played);
+    }
+
+    results.cancelled = results.games < self.games;
+    results
+  }
+}
+
+/// Plays the games of one thread of `run_games`.
+pub trait Worker {
+  type Results;
+
+  /// Play game number `g` of the run.
+  fn play(&mut self, g: usize);
+
+  /// Results of the games played.
+  fn finish(self) -> Self::Results;
+}
+
+/// Play the games numbered from 0 on `thread_count` threads, each with a
+/// worker from `new_worker`, until `games` are played, `deadline` has passed
+/// or `token` is cancelled. Each number is played once, by whichever thread
+/// gets to it first. Returns the results of every worker.
+pub fn run_games<W, F>(thread_count: usize, games: Option<usize>, deadline: Option<Instant>,
+                       token: &CancellationToken, new_worker: F) -> Vec<W::Results>
+  where W: Worker, W::Results: Send + 'static, F: Fn() -> W + Send + Sync + 'static {
+  assert!(thread_count > 0);
+
+  let next = Arc::new(AtomicUsize::new(0));
+  let new_worker = Arc::new(new_worker);
+  let threads: Vec<_> = (0..thread_count).map(|_| {
+    let next = next.clone();
+    let new_worker = new_worker.clone();
+    let token = token.clone();
+
+    thread::spawn(move || {
+      let mut worker = new_worker();
+
+      while ! token.is_cancelled() && deadline.map_or(true, |d| Instant::now() < d) {
+        let g = next.fetch_add(1, Ordering::SeqCst);
+        if games.map_or(false, |n| g >= n) {
+          break;
+        }
+
+        worker.play(g);
+      }
+
+      worker.finish()
+    })
+  }).collect();
+
+  threads.into_iter().map(|t| t.join().unwrap()).collect()
+}
+
+/// Worker of a `Runner`: plays the deal of the seed of each game.
+struct SeedWorker {
+  runner: Runner,
+  strategy: Box<dyn GameStrategy>,
+  results: RunResults,
+}
+
+impl Worker for SeedWorker {
+  type Results = RunResults;
+
+  fn play(&mut self, g: usize) {
+    let seed = self.runner.first_seed + g as u64;
+    let mut game = KlondikeSolitaireGame::with_rules(self.runner.rules, self.runner.shuffle_rng.source(seed));
+    self.strategy.play(&mut game);
+    self.results.record(&game);
+  }
+
+  fn finish(self) -> RunResults {
+    self.results
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use strategies::solitaire::klondike::simple::SimpleKlondikeStrategy;
+
+  /// Plays nothing and cancels the run after a number of games.
+  struct CancelAfter {
+    token: CancellationToken,
+    games: Arc<AtomicUsize>,
+    limit: usize,
+  }
+
+  impl GameStrategy for CancelAfter {
+    /// One that never cancels a run of its own.
+    fn new() -> CancelAfter {
+      CancelAfter { token: CancellationToken::new(), games: Arc::new(AtomicUsize::new(0)), limit: usize::max_value() }
+    }
+
+    fn play(&mut self, _game: &mut KlondikeSolitaireGame) {
+      if self.games.fetch_add(1, Ordering::SeqCst) + 1 >= self.limit {
+        self.token.cancel();
+      }
+    }
+  }
+
+  /// Keeps the numbers of the games it is given.
+  struct Numbers(Vec<usize>);
+
+  impl Worker for Numbers {
+    type Results = Vec<usize>;
+
+    fn play(&mut self, g: usize) {
+      self.0.push(g);
+    }
+
+    fn finish(self) -> Vec<usize> {
+      self.0
+    }
+  }
+
+  #[test]
+  fn run_games_plays_each_number_once() {
+    let mut numbers: Vec<usize> = run_games(4, Some(100), None, &CancellationToken::new(), || Numbers(Vec::new()))
+      .into_iter()
+      .flatten()
+      .collect();
+    numbers.sort();
+    assert_eq!(numbers, (0..100).collect::<Vec<_>>());
+
+    let token = CancellationToken::new();
+    token.cancel();
+    assert!(run_games(2, None, None, &token, || Numbers(Vec::new())).iter().all(|n| n.is_empty()));
+  }
+
+  #[test]
+  fn run_all() {
+    let new_strategy: StrategyFactory = Arc::new(|| Box::new(SimpleKlondikeStrategy::new()) as Box<dyn GameStrategy>);
+    let runner = Runner { thread_count: 3, .. Runner::new(KlondikeRules::new(1), 50) };
+
+    let results = runner.run(&new_strategy, &CancellationToken::new());
+    assert_eq!(results.games, 50);
+    assert!(! results.cancelled);
+
+    let again = Runner::new(KlondikeRules::new(1), 50).run(&new_strategy, &CancellationToken::new());
+    assert_eq!(again, results);
+
+    let chacha = Runner { shuffle_rng: ShuffleRng::ChaCha, .. Runner::new(KlondikeRules::new(1), 50) };
+    assert_eq!(chacha.run(&new_strategy, &CancellationToken::new()).games, 50);
+  }
+
+  #[test]
+  fn cancel() {
+    let token = CancellationToken::new();
+    let games = Arc::new(AtomicUsize::new(0));
+    let new_strategy: StrategyFactory = {
+      let token = token.clone();
+      let games = games.clone();
+      Arc::new(move || Box::new(CancelAfter { token: token.clone(), games: games.clone(), limit: 10 }) as Box<dyn GameStrategy>)
+    };
+
+    let runner = Runner { thread_count: 2, .. Runner::new(KlondikeRules::new(3), 1000) };
+    let results = runner.run(&new_strategy, &token);
+
+    assert!(token.is_cancelled());
+    assert!(results.cancelled);
+    assert!(results.games >= 10 && results.games < 1000, "{} games", results.games);
+    assert_eq!(results.games, games.load(Ordering::SeqCst));
+
+    // A cancelled token stops a run before it starts
+    let results = runner.run(&new_strategy, &token);
+    assert_eq!(results.games, 0);
+    assert!(results.cancelled);
+    assert_eq!(results.win_rate(), None);
+  }
+}
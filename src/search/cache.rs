@@ -0,0 +1,311 @@
+//! Memory bounded transposition cache keyed by state hash.
+//!
+//! The cache is split into shards, each behind its own lock, so several
+//! threads can share one cache with little contention. Each shard keeps its
+//! entries in least recently used order and evicts the oldest entry when it
+//! is full. Lookups, insertions and evictions are counted to tune the size.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const NUM_SHARDS: usize = 16;
+const NONE: usize = usize::max_value();
+
+/// Counters of a cache since it was made.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+  pub hits: usize,
+  pub misses: usize,
+  pub insertions: usize,
+  pub evictions: usize,
+}
+
+impl CacheStats {
+  /// Fraction of lookups that found an entry, `None` before any lookup.
+  pub fn hit_rate(&self) -> Option<f64> {
+    match self.hits + self.misses {
+      0 => None,
+      lookups => Some(self.hits as f64 / lookups as f64),
+    }
+  }
+}
+
+struct Entry<V> {
+  key: u64,
+  value: V,
+  /// Neighbours in recency order, `NONE` at the ends.
+  newer: usize,
+  older: usize,
+}
+
+/// One lock's worth of entries, a doubly linked recency list over a slab.
+struct Shard<V> {
+  capacity: usize,
+  index: HashMap<u64, usize>,
+  entries: Vec<Entry<V>>,
+  newest: usize,
+  oldest: usize,
+}
+
+impl<V> Shard<V> {
+  fn new(capacity: usize) -> Shard<V> {
+    Shard {
+      capacity: capacity,
+      index: HashMap::new(),
+      entries: Vec::new(),
+      newest: NONE,
+      oldest: NONE,
+    }
+  }
+
+  fn unlink(&mut self, i: usize) {
+    let (newer, older) = (self.entries[i].newer, self.entries[i].older);
+
+    if newer == NONE { self.newest = older; } else { self.entries[newer].older = older; }
+    if older == NONE { self.oldest = newer; } else { self.entries[older].newer = newer; }
+  }
+
+  fn push_newest(&mut self, i: usize) {
+    self.entries[i].newer = NONE;
+    self.entries[i].older = self.newest;
+
+    if self.newest != NONE {
+      self.entries[self.newest].newer = i;
+    }
+    self.newest = i;
+
+    if self.oldest == NONE {
+      self.oldest = i;
+    }
+  }
+
+  fn touch(&mut self, i: usize) {
+    if self.newest != i {
+      self.unlink(i);
+      self.push_newest(i);
+    }
+  }
+
+  /// Insert or replace, returning true if an entry was evicted.
+  fn insert(&mut self, key: u64, value: V) -> bool {
+    if let Some(&i) = self.index.get(&key) {
+      self.entries[i].value = value;
+      self.touch(i);
+      return false;
+    }
+
+    if self.entries.len() < self.capacity {
+      self.entries.push(Entry { key: key, value: value, newer: NONE, older: NONE });
+      let i = self.entries.len() - 1;
+      self.index.insert(key, i);
+      self.push_newest(i);
+      return false;
+    }
+
+    // Reuse the slot of the least recently used entry
+    let i = self.oldest;
+    self.unlink(i);
+    self.index.remove(&self.entries[i].key);
+    self.entries[i].key = key;
+    self.entries[i].value = value;
+    self.index.insert(key, i);
+    self.push_newest(i);
+    true
+  }
+}
+
+pub struct TranspositionCache<V> {
+  shards: Vec<Mutex<Shard<V>>>,
+  capacity: usize,
+  hits: AtomicUsize,
+  misses: AtomicUsize,
+  insertions: AtomicUsize,
+  evictions: AtomicUsize,
+}
+
+impl<V: Clone> TranspositionCache<V> {
+  /// Cache of at most `capacity` entries, at least one per shard.
+  pub fn new(capacity: usize) -> TranspositionCache<V> {
+    let per_shard = cmp::max(1, (capacity + NUM_SHARDS - 1) / NUM_SHARDS);
+
+    TranspositionCache {
+      shards: (0..NUM_SHARDS).map(|_| Mutex::new(Shard::new(per_shard))).collect(),
+      capacity: per_shard * NUM_SHARDS,
+      hits: AtomicUsize::new(0),
+      misses: AtomicUsize::new(0),
+      insertions: AtomicUsize::new(0),
+      evictions: AtomicUsize::new(0),
+    }
+  }
+
+  /// Cache of as many entries as fit in about `bytes` bytes. Values that
+  /// own heap memory, such as vectors, use more than their size here.
+  pub fn with_memory_limit(bytes: usize) -> TranspositionCache<V> {
+    // Entry in the slab plus key and index in the hash map table
+    let per_entry = mem::size_of::<Entry<V>>() + 2 * mem::size_of::<(u64, usize)>();
+    TranspositionCache::new(bytes / per_entry)
+  }
+
+  /// Maximum number of entries.
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  pub fn len(&self) -> usize {
+    self.shards.iter().map(|s| s.lock().unwrap().entries.len()).sum()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Value of `key`, marking it recently used.
+  pub fn get(&self, key: u64) -> Option<V> {
+    let mut shard = self.shard(key).lock().unwrap();
+
+    match shard.index.get(&key).cloned() {
+      Some(i) => {
+        shard.touch(i);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(shard.entries[i].value.clone())
+      },
+      None => {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+      },
+    }
+  }
+
+  /// Store `value` for `key`, evicting the least recently used entry of its
+  /// shard if that is full.
+  pub fn insert(&self, key: u64, value: V) {
+    let evicted = self.shard(key).lock().unwrap().insert(key, value);
+
+    self.insertions.fetch_add(1, Ordering::Relaxed);
+    if evicted {
+      self.evictions.fetch_add(1, Ordering::Relaxed);
+    }
+  }
+
+  /// Value of `key`, computed with `compute` and stored if it is missing.
+  /// The shard is not locked while computing, so two threads may both
+  /// compute a missing value.
+  pub fn get_or_insert_with<F: FnOnce() -> V>(&self, key: u64, compute: F) -> V {
+    match self.get(key) {
+      Some(value) => value,
+      None => {
+        let value = compute();
+        self.insert(key, value.clone());
+        value
+      },
+    }
+  }
+
+  /// Remove every entry. The counters are kept.
+  pub fn clear(&self) {
+    for shard in self.shards.iter() {
+      let mut shard = shard.lock().unwrap();
+      let capacity = shard.capacity;
+      *shard = Shard::new(capacity);
+    }
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    CacheStats {
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      insertions: self.insertions.load(Ordering::Relaxed),
+      evictions: self.evictions.load(Ordering::Relaxed),
+    }
+  }
+
+  fn shard(&self, key: u64) -> &Mutex<Shard<V>> {
+    // The low bits of a good state hash are as random as the high ones
+    &self.shards[(key % NUM_SHARDS as u64) as usize]
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::sync::Arc;
+  use std::thread;
+
+  #[test]
+  fn get_and_insert() {
+    let cache = TranspositionCache::new(100);
+    assert_eq!(cache.get(1), None);
+
+    cache.insert(1, "one");
+    cache.insert(17, "seventeen");
+    assert_eq!(cache.get(1), Some("one"));
+    assert_eq!(cache.get(17), Some("seventeen"));
+
+    cache.insert(1, "uno");
+    assert_eq!(cache.get(1), Some("uno"));
+    assert_eq!(cache.len(), 2);
+
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.insertions, stats.evictions), (3, 1, 3, 0));
+    assert_eq!(stats.hit_rate(), Some(0.75));
+
+    cache.clear();
+    assert!(cache.is_empty());
+  }
+
+  #[test]
+  fn evicts_least_recently_used() {
+    // One entry per shard: keys 0, 16 and 32 share shard 0
+    let cache = TranspositionCache::new(1);
+    assert_eq!(cache.capacity(), NUM_SHARDS);
+
+    cache.insert(0, 0);
+    cache.insert(16, 16);
+    assert_eq!(cache.get(0), None);
+    assert_eq!(cache.get(16), Some(16));
+    assert_eq!(cache.stats().evictions, 1);
+
+    let cache = TranspositionCache::new(2 * NUM_SHARDS);
+    cache.insert(0, 0);
+    cache.insert(16, 16);
+    cache.get(0);
+    cache.insert(32, 32);
+    assert_eq!(cache.get(0), Some(0));
+    assert_eq!(cache.get(16), None);
+    assert_eq!(cache.get(32), Some(32));
+
+    assert_eq!(cache.get_or_insert_with(48, || 48), 48);
+    assert_eq!(cache.get(0), None);
+  }
+
+  #[test]
+  fn memory_limit() {
+    let cache: TranspositionCache<u64> = TranspositionCache::with_memory_limit(1 << 20);
+    assert!(cache.capacity() > 1000 && cache.capacity() < (1 << 20) / 16);
+  }
+
+  #[test]
+  fn shared_between_threads() {
+    let cache = Arc::new(TranspositionCache::new(1000));
+
+    let threads: Vec<_> = (0..4u64).map(|t| {
+      let cache = cache.clone();
+      thread::spawn(move || {
+        for i in 0..500 {
+          cache.insert(t * 1000 + i, i);
+        }
+      })
+    }).collect();
+
+    for t in threads {
+      t.join().unwrap();
+    }
+
+    assert!(cache.len() <= cache.capacity());
+    assert_eq!(cache.stats().insertions, 2000);
+    assert_eq!(cache.stats().insertions - cache.stats().evictions, cache.len());
+  }
+}
@@ -0,0 +1,5 @@
+//! Support code shared by the search strategies and solvers, and the
+//! klondike solver.
+
+pub mod cache;
+pub mod solver;
@@ -0,0 +1,391 @@
+//! Exhaustive search of klondike games for a win.
+//!
+//! The solver sees every card, face down and in the stock, so it decides
+//! thoughtful klondike: whether a deal can be won by some sequence of moves
+//! and, if so, one that wins. Strategies that play without seeing the cards
+//! can be measured against it.
+//!
+//! The search is depth first. Safe foundation moves (see
+//! `KlondikeSolitaireGame::is_safe_to_found`) are made at once, as they can
+//! not lose a won game, and the other moves are tried in a fixed order, the
+//! ones likeliest to help first:
+//!
+//! 1. moves to a foundation,
+//! 2. pile to pile moves that turn up a face down card,
+//! 3. moves from the deck to a pile,
+//! 4. other pile to pile moves,
+//! 5. drawing,
+//! 6. moves from a foundation back to a pile.
+//!
+//! Moving a king with nothing under it to another empty pile never helps
+//! and is not tried. Positions already searched are skipped, so drawing
+//! through the stock again ends the search of a line that made no progress.
+//!
+//! Searched positions are kept in a `TranspositionTable` of a fixed number
+//! of entries, keyed by their `encode`d bytes so that two positions never
+//! mistake each other. Once it is full, new positions push out old ones by
+//! its `Replacement` policy and the positions pushed out may be searched
+//! again, which costs nodes but not correctness.
+
+use games::solitaire::klondike::{KlondikeSolitaireGame, Move, MoveSource, MoveTarget, ENCODED_LEN};
+use std::cmp;
+
+/// Entries of the table of a `Solver::new`, about 64 MB.
+pub const DEFAULT_TABLE_SIZE: usize = 1 << 20;
+
+/// What a search found out about a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+  /// The game can be won by these moves.
+  Winnable(Vec<Move>),
+  /// No sequence of moves wins.
+  Unwinnable,
+  /// The node limit was reached first.
+  Unknown,
+}
+
+/// Which position a full slot of a `TranspositionTable` keeps.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Replacement {
+  /// The position stored last.
+  Always,
+  /// The position reached in fewer moves, which heads the larger search.
+  /// Ties go to the new position.
+  Shallowest,
+}
+
+#[derive(Clone)]
+struct Slot {
+  key: [u8; ENCODED_LEN],
+  depth: usize,
+}
+
+/// Set of searched positions with room for a fixed number of them.
+///
+/// Each position has one slot, picked by its `state_hash`, and a position
+/// that finds its slot taken by another replaces it or not by the
+/// `Replacement` policy.
+pub struct TranspositionTable {
+  slots: Vec<Option<Slot>>,
+  replacement: Replacement,
+  len: usize,
+  evictions: usize,
+}
+
+impl TranspositionTable {
+  /// Table of `capacity` entries, at least one.
+  pub fn new(capacity: usize, replacement: Replacement) -> TranspositionTable {
+    TranspositionTable {
+      slots: vec![None; cmp::max(1, capacity)],
+      replacement: replacement,
+      len: 0,
+      evictions: 0,
+    }
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.slots.len()
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn replacement(&self) -> Replacement {
+    self.replacement
+  }
+
+  /// Positions pushed out by others since the table was made.
+  pub fn evictions(&self) -> usize {
+    self.evictions
+  }
+
+  pub fn contains(&self, game: &KlondikeSolitaireGame) -> bool {
+    match self.slots[self.slot(game)] {
+      Some(ref slot) => slot.key[..] == game.encode()[..],
+      None => false,
+    }
+  }
+
+  /// Record `game`, reached `depth` moves into the search. False if it was
+  /// already recorded.
+  pub fn insert(&mut self, game: &KlondikeSolitaireGame, depth: usize) -> bool {
+    let (i, key) = (self.slot(game), game.encode());
+
+    match self.slots[i] {
+      Some(ref mut slot) if slot.key[..] == key[..] => {
+        slot.depth = cmp::min(slot.depth, depth);
+        return false;
+      },
+      Some(ref slot) if self.replacement == Replacement::Shallowest && slot.depth < depth => {
+        return true;
+      },
+      Some(_) => self.evictions += 1,
+      None => self.len += 1,
+    }
+
+    self.slots[i] = Some(Slot { key: key, depth: depth });
+    true
+  }
+
+  /// Remove every entry. The evictions are kept.
+  pub fn clear(&mut self) {
+    for slot in self.slots.iter_mut() {
+      *slot = None;
+    }
+    self.len = 0;
+  }
+
+  fn slot(&self, game: &KlondikeSolitaireGame) -> usize {
+    (game.state_hash() % self.slots.len() as u64) as usize
+  }
+}
+
+pub struct Solver {
+  max_nodes: usize,
+  nodes: usize,
+  table: TranspositionTable,
+}
+
+/// A position on the search path, with the moves that led to it from the
+/// one before and the moves still to try.
+struct Frame {
+  game: KlondikeSolitaireGame,
+  played: Vec<Move>,
+  moves: Vec<Move>,
+  next: usize,
+}
+
+impl Solver {
+  /// Solver that gives up after expanding `max_nodes` positions, with a
+  /// table large enough to keep them all up to `DEFAULT_TABLE_SIZE`.
+  pub fn new(max_nodes: usize) -> Solver {
+    let capacity = cmp::min(max_nodes.saturating_add(1), DEFAULT_TABLE_SIZE);
+    Solver::with_table(max_nodes, TranspositionTable::new(capacity, Replacement::Always))
+  }
+
+  pub fn with_table(max_nodes: usize, table: TranspositionTable) -> Solver {
+    Solver {
+      max_nodes: max_nodes,
+      nodes: 0,
+      table: table,
+    }
+  }
+
+  pub fn max_nodes(&self) -> usize {
+    self.max_nodes
+  }
+
+  /// Positions expanded by the last `solve`.
+  pub fn nodes(&self) -> usize {
+    self.nodes
+  }
+
+  /// The positions recorded by the last `solve`.
+  pub fn table(&self) -> &TranspositionTable {
+    &self.table
+  }
+
+  /// Search `game` for a win.
+  pub fn solve(&mut self, game: &KlondikeSolitaireGame) -> Verdict {
+    self.nodes = 0;
+    self.table.clear();
+
+    let root = self.frame(game.clone(), Vec::new(), 0);
+    if root.game.is_clear() {
+      return Verdict::Winnable(root.played);
+    }
+    let mut path = vec![root];
+
+    loop {
+      let (mut game, m) = match path.last_mut() {
+        Some(frame) if frame.next < frame.moves.len() => {
+          frame.next += 1;
+          (frame.game.clone(), frame.moves[frame.next - 1])
+        },
+        Some(_) => {
+          path.pop();
+          continue;
+        },
+        None => return Verdict::Unwinnable,
+      };
+
+      if self.nodes >= self.max_nodes {
+        return Verdict::Unknown;
+      }
+      self.nodes += 1;
+
+      game.apply(m).unwrap();
+      if ! self.table.insert(&game, path.len()) {
+        continue;
+      }
+
+      let frame = self.frame(game, vec![m], path.len());
+      if frame.game.is_clear() {
+        let mut line: Vec<Move> = path.into_iter().flat_map(|f| f.played).collect();
+        line.extend(frame.played);
+        return Verdict::Winnable(line);
+      }
+      path.push(frame);
+    }
+  }
+
+  /// Frame of `game` after `played`, `depth` frames from the root, with its
+  /// safe moves made.
+  fn frame(&mut self, mut game: KlondikeSolitaireGame, mut played: Vec<Move>, depth: usize) -> Frame {
+    while let Some(m) = game.safe_move() {
+      game.apply(m).unwrap();
+      played.push(m);
+    }
+    self.table.insert(&game, depth);
+
+    let moves = ordered_moves(&game);
+    Frame {
+      game: game,
+      played: played,
+      moves: moves,
+      next: 0,
+    }
+  }
+}
+
+/// The moves of `game` worth trying, in the order of the module comment.
+fn ordered_moves(game: &KlondikeSolitaireGame) -> Vec<Move> {
+  let mut moves: Vec<(usize, Move)> = game.legal_moves().into_iter()
+    .filter_map(|m| {
+      let rank = match m {
+        Move::Cards(_, MoveTarget::Foundation) => 0,
+        Move::Cards(MoveSource::Pile(i), MoveTarget::Pile(j)) => {
+          let (source, target) = (&game.piles()[i as usize], &game.piles()[j as usize]);
+          let whole = target.can_push(source.visible_cards()[0]).is_ok();
+
+          if whole && source.hidden_cards().is_empty() && target.is_empty() {
+            return None;
+          }
+          if whole && ! source.hidden_cards().is_empty() { 1 } else { 3 }
+        },
+        Move::Cards(MoveSource::Deck, _) => 2,
+        Move::Draw => 4,
+        _ => 5,
+      };
+
+      Some((rank, m))
+    })
+    .collect();
+
+  moves.sort_by_key(|&(rank, _)| rank);
+  moves.into_iter().map(|(_, m)| m).collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use games::solitaire::klondike::{Card, Deck, Foundation, Pile};
+  use games::solitaire::replay::Replay;
+  use cards::seeded_source;
+  use cards::french::{Rank, Suit};
+
+  fn cards(s: &[&str]) -> Vec<Card> {
+    s.iter().map(|c| c.parse().unwrap()).collect()
+  }
+
+  /// Foundations up to `rank` in every suit.
+  fn foundations(rank: Rank) -> [Foundation; 4] {
+    [
+      Foundation::from(Suit::Hearts, Some(rank)),
+      Foundation::from(Suit::Diamonds, Some(rank)),
+      Foundation::from(Suit::Spades, Some(rank)),
+      Foundation::from(Suit::Clubs, Some(rank)),
+    ]
+  }
+
+  #[test]
+  fn finds_a_win() {
+    // The queen of hearts has to move off the king of clubs to free the
+    // cards under it, and the jacks of spades and clubs come from the deck.
+    let game = KlondikeSolitaireGame::from(
+      Deck::from(1, &[], &[], &cards(&["KD", "JS", "JC"])),
+      foundations(Rank::Number(10)), [
+        Pile::new(),
+        Pile::new(),
+        Pile::from(&cards(&["JH", "KS"]), &cards(&["KC", "QH"])),
+        Pile::from(&cards(&["JD"]), &cards(&["QS"])),
+        Pile::from(&[], &cards(&["KH", "QC"])),
+        Pile::from(&[], &cards(&["QD"])),
+        Pile::new(),
+      ]
+    );
+
+    let mut solver = Solver::new(10000);
+    match solver.solve(&game) {
+      Verdict::Winnable(line) => {
+        let won = Replay::new(game.clone(), line).finish().unwrap();
+        assert!(won.is_clear());
+      },
+      verdict => panic!("{:?}", verdict),
+    }
+    assert!(solver.nodes() > 0);
+  }
+
+  #[test]
+  fn proves_a_loss() {
+    // Each queen covers the jack that a queen of the other color needs to be
+    // founded, and the kings that could take them are face down.
+    let mut foundations = foundations(Rank::Number(10));
+    foundations[0] = Foundation::from(Suit::Hearts, Some(Rank::Number(9)));
+    foundations[1] = Foundation::from(Suit::Diamonds, Some(Rank::Number(9)));
+    let game = KlondikeSolitaireGame::from(
+      Deck::from(1, &[], &[], &cards(&["10H", "10D"])),
+      foundations, [
+        Pile::new(),
+        Pile::from(&cards(&["JH"]), &cards(&["QS"])),
+        Pile::from(&cards(&["KC", "JS"]), &cards(&["QH"])),
+        Pile::from(&cards(&["KH", "JD"]), &cards(&["QC"])),
+        Pile::from(&cards(&["KD", "KS", "JC"]), &cards(&["QD"])),
+        Pile::new(),
+        Pile::new(),
+      ]
+    );
+
+    assert_eq!(Solver::new(10000).solve(&game), Verdict::Unwinnable);
+    assert_eq!(Solver::new(0).solve(&game), Verdict::Unknown);
+
+    // A table too small to hold the search forgets positions, but still
+    // ends it
+    let mut solver = Solver::with_table(10000, TranspositionTable::new(4, Replacement::Shallowest));
+    assert_eq!(solver.solve(&game), Verdict::Unwinnable);
+    assert!(solver.table().evictions() > 0);
+  }
+
+  #[test]
+  fn transposition_table() {
+    let game = KlondikeSolitaireGame::new_shuffle(1, seeded_source(0));
+    let mut drawn = game.clone();
+    drawn.apply(Move::Draw).unwrap();
+
+    let mut table = TranspositionTable::new(1, Replacement::Shallowest);
+    assert!(table.insert(&game, 3));
+    assert!(! table.insert(&game, 1));
+    assert!(table.contains(&game));
+
+    // The stored position was reached in one move, so a deeper one does not
+    // replace it
+    assert!(table.insert(&drawn, 2));
+    assert!(table.contains(&game) && ! table.contains(&drawn));
+    assert!(table.insert(&drawn, 1));
+    assert!(table.contains(&drawn) && ! table.contains(&game));
+
+    let mut table = TranspositionTable::new(1, Replacement::Always);
+    table.insert(&game, 0);
+    table.insert(&drawn, 5);
+    assert!(table.contains(&drawn) && ! table.contains(&game));
+    assert_eq!((table.len(), table.evictions()), (1, 1));
+
+    table.clear();
+    assert!(table.is_empty());
+  }
+}
@@ -0,0 +1,253 @@
+//! Batched self-play data generation for reinforcement learning workflows.
+//!
+//! Games are played in batches and each batch is written to its own training
+//! data file (see `training`) in the output directory. A batch file is only
+//! created once the whole batch has been played, so a training loop can
+//! consume finished batches, e.g. update the model behind a policy command,
+//! while later batches are still being generated.
+//!
+//! Every deal is derived from the master seed, the batch number and the index
+//! of the game in the batch, so a run can be resumed or extended by starting
+//! at a later batch with the same seed.
+//!
+//! With `run_log` set every game is also appended to a `runlog` file that the
+//! `verify` command can replay. A run log ending in `.gz` or `.zst` is
+//! compressed, see `compress`.
+
+use cards::seeded_source;
+use compress::LogWriter;
+use games::solitaire::klondike::KlondikeSolitaireGame;
+use json::Value;
+use runlog::RunRecord;
+use strategies::solitaire::klondike::StrategyFactory;
+use throttle::Throttle;
+use training;
+use std::cmp;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Instant;
+
+#[derive(Debug)]
+pub struct SelfPlayOptions {
+  pub seed: u64,
+  pub first_batch: usize,
+  pub batches: usize,
+  pub batch_size: usize,
+  pub thread_count: usize,
+  pub throttle: Option<Throttle>,
+  pub draw_count: u8,
+  pub out_dir: PathBuf,
+  /// Name of the strategy, as written to the run log.
+  pub strategy: String,
+  pub run_log: Option<PathBuf>,
+}
+
+impl SelfPlayOptions {
+  /// The options as a JSON object for the run manifest, with the seed as a
+  /// string as it may not fit a JSON number.
+  pub fn to_json(&self) -> Value {
+    let path = |p: &PathBuf| Value::String(p.to_string_lossy().into_owned());
+
+    Value::Object(vec![
+      (String::from("seed"), Value::String(self.seed.to_string())),
+      (String::from("first_batch"), Value::Number(self.first_batch as f64)),
+      (String::from("batches"), Value::Number(self.batches as f64)),
+      (String::from("batch_size"), Value::Number(self.batch_size as f64)),
+      (String::from("threads"), Value::Number(self.thread_count as f64)),
+      (String::from("busy_percent"), self.throttle.map_or(Value::Null, |t| Value::Number(t.busy_percent() as f64))),
+      (String::from("draw_count"), Value::Number(self.draw_count as f64)),
+      (String::from("out_dir"), path(&self.out_dir)),
+      (String::from("strategy"), Value::String(self.strategy.clone())),
+      (String::from("run_log"), self.run_log.as_ref().map_or(Value::Null, path)),
+    ])
+  }
+}
+
+/// Seed of the deal for a game of a self-play run.
+pub fn game_seed(seed: u64, batch: usize, index: usize) -> u64 {
+  splitmix64(splitmix64(seed ^ batch as u64) ^ index as u64)
+}
+
+fn splitmix64(x: u64) -> u64 {
+  let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+  z ^ (z >> 31)
+}
+
+/// Path of the data file for a batch.
+pub fn batch_path(options: &SelfPlayOptions, batch: usize) -> PathBuf {
+  options.out_dir.join(format!("batch-{:05}.csv", batch))
+}
+
+/// Play and write every batch of `options`. `progress` is told the number,
+/// the wins and the data file of each batch once the file is written.
+pub fn run<P>(new_strategy: StrategyFactory, options: &SelfPlayOptions, mut progress: P) -> io::Result<()>
+  where P: FnMut(usize, usize, &Path) {
+  fs::create_dir_all(&options.out_dir)?;
+
+  let mut run_log = match options.run_log {
+    Some(ref path) => Some(LogWriter::append(path)?),
+    None => None,
+  };
+
+  for batch in options.first_batch..options.first_batch + options.batches {
+    let (rows, records, wins) = play_batch(&new_strategy, options, batch);
+
+    // Write to a temporary file first so consumers never see a partial batch
+    let path = batch_path(options, batch);
+    let tmp_path = path.with_extension("csv.tmp");
+    {
+      let mut out = BufWriter::new(File::create(&tmp_path)?);
+      training::write_header(&mut out)?;
+      out.write_all(&rows)?;
+      out.flush()?;
+    }
+    fs::rename(&tmp_path, &path)?;
+
+    if let Some(ref mut run_log) = run_log {
+      for record in &records {
+        record.write(run_log)?;
+      }
+      run_log.flush()?;
+    }
+
+    progress(batch, wins, &path);
+  }
+
+  if let Some(run_log) = run_log {
+    run_log.finish()?;
+  }
+
+  Ok(())
+}
+
+/// Play one batch and return its training rows and run records, in game order,
+/// and the number of games won.
+fn play_batch(new_strategy: &StrategyFactory, options: &SelfPlayOptions, batch: usize) -> (Vec<u8>, Vec<RunRecord>, usize) {
+  let per_thread = (options.batch_size + options.thread_count - 1) / options.thread_count;
+  let mut threads = Vec::with_capacity(options.thread_count);
+
+  for t in 0..options.thread_count {
+    let start = cmp::min(t * per_thread, options.batch_size);
+    let end = cmp::min(start + per_thread, options.batch_size);
+    let new_strategy = new_strategy.clone();
+    let seed = options.seed;
+    let draw_count = options.draw_count;
+    let throttle = options.throttle;
+    let strategy_name = options.strategy.clone();
+    let keep_records = options.run_log.is_some();
+
+    threads.push(thread::spawn(move || {
+      let mut strategy = new_strategy();
+      let mut throttle = throttle;
+      let mut rows = Vec::new();
+      let mut records = Vec::new();
+      let mut wins = 0;
+
+      for index in start..end {
+        let started = Instant::now();
+        let game_seed = game_seed(seed, batch, index);
+        let mut game = KlondikeSolitaireGame::new_shuffle(draw_count, seeded_source(game_seed));
+        let initial = game.clone();
+
+        game.record_moves();
+        strategy.play(&mut game);
+
+        if game.is_clear() {
+          wins += 1;
+        }
+
+        training::write_game(&mut rows, game_seed, &initial, &game.recorded_moves().unwrap()).unwrap();
+
+        if keep_records {
+          records.push(RunRecord::new(game_seed, &strategy_name, &game));
+        }
+
+        if let Some(ref mut throttle) = throttle {
+          throttle.pause(started);
+        }
+      }
+
+      (rows, records, wins)
+    }));
+  }
+
+  let mut rows = Vec::new();
+  let mut records = Vec::new();
+  let mut wins = 0;
+
+  for t in threads {
+    let (r, l, w) = t.join().unwrap();
+    rows.extend(r);
+    records.extend(l);
+    wins += w;
+  }
+
+  (rows, records, wins)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn game_seeds_are_stable() {
+    assert_eq!(game_seed(1, 2, 3), game_seed(1, 2, 3));
+    assert!(game_seed(1, 2, 3) != game_seed(1, 2, 4));
+    assert!(game_seed(1, 2, 3) != game_seed(1, 3, 3));
+    assert!(game_seed(1, 2, 3) != game_seed(2, 2, 3));
+  }
+
+  #[test]
+  fn options_to_json() {
+    let options = SelfPlayOptions {
+      seed: u64::max_value(),
+      first_batch: 0,
+      batches: 2,
+      batch_size: 100,
+      thread_count: 1,
+      throttle: Some(Throttle::new(50)),
+      draw_count: 1,
+      out_dir: PathBuf::from("out"),
+      strategy: String::from("simple"),
+      run_log: None,
+    };
+    let json = Value::parse(&options.to_json().to_string()).unwrap();
+
+    assert_eq!(json.get("seed").and_then(|v| v.as_str()), Some("18446744073709551615"));
+    assert_eq!(json.get("busy_percent").and_then(|v| v.as_u64()), Some(50));
+    assert_eq!(json.get("out_dir").and_then(|v| v.as_str()), Some("out"));
+    assert_eq!(json.get("run_log"), Some(&Value::Null));
+  }
+
+  #[test]
+  fn run_reports_each_batch() {
+    use strategies::solitaire::klondike::GameStrategy;
+    use strategies::solitaire::klondike::simple::SimpleKlondikeStrategy;
+    use std::sync::Arc;
+
+    let out_dir = ::std::env::temp_dir().join(format!("cardsim-selfplay-test-{}", ::std::process::id()));
+    let options = SelfPlayOptions {
+      seed: 1,
+      first_batch: 3,
+      batches: 2,
+      batch_size: 4,
+      thread_count: 2,
+      throttle: None,
+      draw_count: 1,
+      out_dir: out_dir.clone(),
+      strategy: String::from("simple"),
+      run_log: None,
+    };
+    let mut done = Vec::new();
+
+    run(Arc::new(|| Box::new(SimpleKlondikeStrategy::new()) as Box<dyn GameStrategy>), &options,
+        |batch, _, path| done.push((batch, path.to_path_buf()))).unwrap();
+    fs::remove_dir_all(&out_dir).unwrap();
+
+    assert_eq!(done, vec![(3, batch_path(&options, 3)), (4, batch_path(&options, 4))]);
+  }
+}
@@ -0,0 +1,206 @@
+//! On demand progress reports and pausing of long running simulations.
+//!
+//! Sending the process `SIGUSR1` (`kill -USR1 <pid>`) requests a status
+//! report; the run prints it from a monitor thread without interrupting the
+//! workers.
+//!
+//! `SIGTSTP` (Ctrl-Z) pauses the run cleanly: workers finish the game they are
+//! playing and wait at a `PauseGate`, the status is printed and then the
+//! process stops itself. Resuming the process (`fg` or `SIGCONT`) releases the
+//! workers. Signals are only supported on unix.
+
+use std::sync::{Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+static PAUSE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: ::libc::c_int) {
+  if signal == ::libc::SIGTSTP {
+    PAUSE_REQUESTED.store(true, Ordering::SeqCst);
+  } else {
+    REQUESTED.store(true, Ordering::SeqCst);
+  }
+}
+
+/// Listen for status and pause requests.
+#[cfg(unix)]
+pub fn install() {
+  unsafe {
+    ::libc::signal(::libc::SIGUSR1, on_signal as extern "C" fn(::libc::c_int) as ::libc::sighandler_t);
+    ::libc::signal(::libc::SIGTSTP, on_signal as extern "C" fn(::libc::c_int) as ::libc::sighandler_t);
+  }
+}
+
+#[cfg(not(unix))]
+pub fn install() {
+}
+
+/// True once for each pause request.
+pub fn pause_requested() -> bool {
+  PAUSE_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+/// Stop the process until it is continued, like the default `SIGTSTP`
+/// handling. Returns immediately on other platforms.
+#[cfg(unix)]
+pub fn stop_process() {
+  unsafe {
+    ::libc::raise(::libc::SIGSTOP);
+  }
+}
+
+#[cfg(not(unix))]
+pub fn stop_process() {
+}
+
+struct GateState {
+  paused: bool,
+  /// Workers waiting at the gate or finished.
+  idle: usize,
+}
+
+/// Point between games where workers wait while a run is paused.
+pub struct PauseGate {
+  workers: usize,
+  state: Mutex<GateState>,
+  changed: Condvar,
+}
+
+impl PauseGate {
+  pub fn new(workers: usize) -> PauseGate {
+    PauseGate {
+      workers: workers,
+      state: Mutex::new(GateState { paused: false, idle: 0 }),
+      changed: Condvar::new(),
+    }
+  }
+
+  /// Called by a worker between games; blocks while paused.
+  pub fn wait(&self) {
+    let mut state = self.state.lock().unwrap();
+
+    if ! state.paused {
+      return;
+    }
+
+    state.idle += 1;
+    self.changed.notify_all();
+
+    while state.paused {
+      state = self.changed.wait(state).unwrap();
+    }
+
+    state.idle -= 1;
+  }
+
+  /// Called by a worker when it has no more games to play.
+  pub fn finish(&self) {
+    let mut state = self.state.lock().unwrap();
+    state.idle += 1;
+    self.changed.notify_all();
+  }
+
+  /// Pause and block until every worker is waiting or finished.
+  pub fn pause(&self) {
+    let mut state = self.state.lock().unwrap();
+    state.paused = true;
+
+    while state.idle < self.workers {
+      state = self.changed.wait(state).unwrap();
+    }
+  }
+
+  pub fn resume(&self) {
+    let mut state = self.state.lock().unwrap();
+    state.paused = false;
+    self.changed.notify_all();
+  }
+}
+
+/// True once for each status request.
+pub fn requested() -> bool {
+  REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+/// Request a status report as if the signal had been received.
+pub fn request() {
+  REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Estimated time left to finish `remaining` items at `rate` items per second.
+pub fn eta(remaining: usize, rate: f64) -> Option<Duration> {
+  if rate > 0.0 {
+    Some(Duration::from_secs((remaining as f64 / rate).ceil() as u64))
+  } else {
+    None
+  }
+}
+
+/// Duration as `1h02m03s`, `2m03s` or `3s`.
+pub fn format_duration(d: Duration) -> String {
+  let secs = d.as_secs();
+
+  match (secs / 3600, secs % 3600 / 60, secs % 60) {
+    (0, 0, s) => format!("{}s", s),
+    (0, m, s) => format!("{}m{:02}s", m, s),
+    (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::time::Duration;
+
+  #[test]
+  fn requests() {
+    request();
+    assert!(requested());
+    assert!(! requested());
+  }
+
+  #[test]
+  fn pause_gate() {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use std::thread;
+
+    let gate = Arc::new(PauseGate::new(2));
+    let count = Arc::new(AtomicUsize::new(0));
+    let workers: Vec<_> = (0..2).map(|_| {
+      let gate = gate.clone();
+      let count = count.clone();
+      thread::spawn(move || {
+        for _ in 0..1000 {
+          gate.wait();
+          count.fetch_add(1, Ordering::SeqCst);
+        }
+        gate.finish();
+      })
+    }).collect();
+
+    gate.pause();
+    let paused_count = count.load(Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(count.load(Ordering::SeqCst), paused_count);
+
+    gate.resume();
+    for w in workers {
+      w.join().unwrap();
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 2000);
+  }
+
+  #[test]
+  fn estimates() {
+    assert_eq!(eta(100, 10.0), Some(Duration::from_secs(10)));
+    assert_eq!(eta(100, 0.0), None);
+
+    assert_eq!(format_duration(Duration::from_secs(3)), "3s");
+    assert_eq!(format_duration(Duration::from_secs(123)), "2m03s");
+    assert_eq!(format_duration(Duration::from_secs(3723)), "1h02m03s");
+  }
+}
@@ -0,0 +1,109 @@
+use games::blackjack::*;
+use games::shoe::Shoe;
+
+/// Names accepted by `blackjack_strategy`.
+pub static BLACKJACK_STRATEGIES: &'static [&'static str] = &["basic", "dealer"];
+
+/// Chooses the actions of the player in a round of blackjack.
+pub trait BlackjackStrategy {
+  /// One of `allowed`, the actions allowed on `hand` with the dealer showing
+  /// `dealer_up`. Never called without actions.
+  fn action(&mut self, hand: &Hand, dealer_up: Card, allowed: &[Action]) -> Action;
+
+  /// Play every hand of `round`, drawing from `shoe`.
+  fn play(&mut self, round: &mut BlackjackRound, shoe: &mut Shoe) {
+    while ! round.is_over() {
+      let allowed = round.allowed();
+      let action = self.action(round.hand().unwrap(), round.dealer_up(), &allowed);
+      round.apply(action, shoe).unwrap();
+    }
+  }
+}
+
+/// Strategy by name, one of `BLACKJACK_STRATEGIES`.
+pub fn blackjack_strategy(name: &str) -> Option<Box<dyn BlackjackStrategy>> {
+  match name {
+    "basic" => Some(Box::new(BasicStrategy)),
+    "dealer" => Some(Box::new(DealerStrategy)),
+    _ => None,
+  }
+}
+
+/// Hit below 17 and stand otherwise, as the dealer does, never doubling or
+/// splitting.
+pub struct DealerStrategy;
+
+impl BlackjackStrategy for DealerStrategy {
+  fn action(&mut self, hand: &Hand, _dealer_up: Card, _allowed: &[Action]) -> Action {
+    if hand.total() < 17 { Action::Hit } else { Action::Stand }
+  }
+}
+
+/// The basic strategy of a multi-deck shoe with the dealer standing on soft
+/// 17 and doubling after splits allowed. An action that is not allowed falls
+/// back to hitting, or to standing on a soft 18 that would double.
+pub struct BasicStrategy;
+
+impl BasicStrategy {
+  /// The action for `hand` against an up card of value `up`, 11 for an ace,
+  /// when every action is allowed.
+  fn preferred(hand: &Hand, up: u8, pairs: bool) -> Action {
+    if pairs && hand.is_pair() {
+      let split = match card_value(hand.cards()[0]) {
+        1 | 8 => true,
+        9 => up != 7 && up < 10,
+        7 | 3 | 2 => up <= 7,
+        6 => up <= 6,
+        4 => up == 5 || up == 6,
+        _ => false,
+      };
+
+      if split {
+        return Action::Split;
+      }
+    }
+
+    let total = hand.total();
+
+    if hand.is_soft() {
+      return match total {
+        19..=21 => Action::Stand,
+        18 if up >= 3 && up <= 6 => Action::Double,
+        18 if up <= 8 => Action::Stand,
+        17 if up >= 3 && up <= 6 => Action::Double,
+        15 | 16 if up >= 4 && up <= 6 => Action::Double,
+        13 | 14 if up == 5 || up == 6 => Action::Double,
+        _ => Action::Hit,
+      };
+    }
+
+    match total {
+      17..=21 => Action::Stand,
+      13..=16 if up <= 6 => Action::Stand,
+      12 if up >= 4 && up <= 6 => Action::Stand,
+      11 if up != 11 => Action::Double,
+      10 if up <= 9 => Action::Double,
+      9 if up >= 3 && up <= 6 => Action::Double,
+      _ => Action::Hit,
+    }
+  }
+}
+
+impl BlackjackStrategy for BasicStrategy {
+  fn action(&mut self, hand: &Hand, dealer_up: Card, allowed: &[Action]) -> Action {
+    let up = match card_value(dealer_up) {
+      1 => 11,
+      value => value,
+    };
+
+    let action = BasicStrategy::preferred(hand, up, allowed.contains(&Action::Split));
+
+    if allowed.contains(&action) {
+      action
+    } else if action == Action::Double && hand.is_soft() && hand.total() == 18 {
+      Action::Stand
+    } else {
+      Action::Hit
+    }
+  }
+}
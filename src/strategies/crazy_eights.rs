@@ -0,0 +1,117 @@
+use games::crazy_eights::*;
+use cards::french::Suit;
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+/// Names accepted by `crazy_eights_strategy`.
+pub static CRAZY_EIGHTS_STRATEGIES: &'static [&'static str] = &["simple", "eager", "random"];
+
+/// Chooses the cards one player discards, seeing the game only through the
+/// `CrazyEightsView` of their seat. Drawing is left to the game: a player
+/// draws exactly when they have no card to play.
+pub trait CrazyEightsStrategy {
+  /// One of `legal`, the cards the player may play on the pile. Never
+  /// called without cards.
+  fn play(&mut self, view: &CrazyEightsView, legal: &[Card]) -> Card;
+
+  /// The suit to name for `eight`, about to be played from `view.hand()`.
+  fn suit(&mut self, view: &CrazyEightsView, eight: Card) -> Suit;
+}
+
+/// Strategy by name, one of `CRAZY_EIGHTS_STRATEGIES`. Random strategies
+/// draw from `seed`.
+pub fn crazy_eights_strategy(name: &str, seed: u64) -> Option<Box<dyn CrazyEightsStrategy>> {
+  match name {
+    "simple" => Some(Box::new(SimpleCrazyEightsStrategy)),
+    "eager" => Some(Box::new(EagerCrazyEightsStrategy)),
+    "random" => Some(Box::new(RandomCrazyEightsStrategy::new(seed))),
+    _ => None,
+  }
+}
+
+/// Play `game` to the end with `players[p]` in seat `p`, drawing for the
+/// player to play whenever they cannot play.
+pub fn play_game(game: &mut CrazyEightsGame, players: &mut [Box<dyn CrazyEightsStrategy>]) -> CrazyEightsOutcome {
+  assert_eq!(players.len(), game.rules().players);
+
+  loop {
+    if let Some(outcome) = game.outcome() {
+      return outcome;
+    }
+
+    let seat = game.to_play();
+    let legal = game.legal_plays();
+    if legal.is_empty() {
+      game.draw().expect("invalid draw");
+      continue;
+    }
+
+    let card = players[seat].play(&game.view(seat), &legal);
+    let suit = if is_eight(card) { Some(players[seat].suit(&game.view(seat), card)) } else { None };
+    game.play(card, suit).expect("illegal play");
+  }
+}
+
+/// The suit of which `hand` holds the most cards, eights aside, the suit of
+/// `eight` when there are none.
+fn longest_suit(hand: &[Card], eight: Card) -> Suit {
+  *Suit::standard_iter()
+    .max_by_key(|s| (hand.iter().filter(|c| ! is_eight(**c) && c.suit() == **s).count(), **s == eight.suit()))
+    .unwrap()
+}
+
+/// Play and name suits uniformly at random.
+pub struct RandomCrazyEightsStrategy {
+  rng: Isaac64Rng,
+}
+
+impl RandomCrazyEightsStrategy {
+  pub fn new(seed: u64) -> RandomCrazyEightsStrategy {
+    RandomCrazyEightsStrategy {
+      rng: Isaac64Rng::from_seed(&[seed]),
+    }
+  }
+}
+
+impl CrazyEightsStrategy for RandomCrazyEightsStrategy {
+  fn play(&mut self, _view: &CrazyEightsView, legal: &[Card]) -> Card {
+    *self.rng.choose(legal).unwrap()
+  }
+
+  fn suit(&mut self, _view: &CrazyEightsView, _eight: Card) -> Suit {
+    *self.rng.choose(Suit::standard_iter().as_slice()).unwrap()
+  }
+}
+
+/// Shed the card with the most penalty points, eights first, and name the
+/// longest suit.
+pub struct EagerCrazyEightsStrategy;
+
+impl CrazyEightsStrategy for EagerCrazyEightsStrategy {
+  fn play(&mut self, _view: &CrazyEightsView, legal: &[Card]) -> Card {
+    *legal.iter().max_by_key(|c| card_points(**c)).unwrap()
+  }
+
+  fn suit(&mut self, view: &CrazyEightsView, eight: Card) -> Suit {
+    longest_suit(view.hand(), eight)
+  }
+}
+
+/// Keep eights for when nothing else can be played, otherwise play a card
+/// of the suit the hand holds most of, the most penalty points first, so the
+/// next turn is likely to have a play too. Names the longest suit.
+pub struct SimpleCrazyEightsStrategy;
+
+impl CrazyEightsStrategy for SimpleCrazyEightsStrategy {
+  fn play(&mut self, view: &CrazyEightsView, legal: &[Card]) -> Card {
+    let hand = view.hand();
+    let held = |suit: Suit| hand.iter().filter(|c| ! is_eight(**c) && c.suit() == suit).count();
+
+    *legal.iter()
+      .max_by_key(|c| (! is_eight(**c), held(c.suit()), card_points(**c)))
+      .unwrap()
+  }
+
+  fn suit(&mut self, view: &CrazyEightsView, eight: Card) -> Suit {
+    longest_suit(view.hand(), eight)
+  }
+}
@@ -0,0 +1,141 @@
+use games::cribbage::*;
+use cards::french;
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+/// Names accepted by `cribbage_strategy`.
+pub static CRIBBAGE_STRATEGIES: &'static [&'static str] = &["simple", "random"];
+
+/// Lays away and pegs the cards of one player, seeing the deal only through
+/// the `CribbageView` of their seat.
+pub trait CribbageStrategy {
+  /// The two cards of `view.hand()` to lay away to the crib.
+  fn discard(&mut self, view: &CribbageView) -> Vec<Card>;
+
+  /// One of `legal`, the cards the player may peg without going over 31.
+  /// Never called without cards.
+  fn peg(&mut self, view: &CribbageView, legal: &[Card]) -> Card;
+}
+
+/// Strategy by name, one of `CRIBBAGE_STRATEGIES`. Random strategies draw
+/// from `seed`.
+pub fn cribbage_strategy(name: &str, seed: u64) -> Option<Box<dyn CribbageStrategy>> {
+  match name {
+    "simple" => Some(Box::new(SimpleCribbageStrategy)),
+    "random" => Some(Box::new(RandomCribbageStrategy::new(seed))),
+    _ => None,
+  }
+}
+
+/// Lay away, peg and show `game` to the end with `players[p]` as player `p`.
+pub fn play_deal(game: &mut CribbageGame, players: &mut [Box<dyn CribbageStrategy>]) {
+  assert_eq!(players.len(), NUM_PLAYERS);
+
+  for p in 0..NUM_PLAYERS {
+    let cards = players[p].discard(&game.view(p));
+    game.discard(p, &cards).expect("invalid discard");
+  }
+
+  while game.phase() == Phase::Pegging {
+    let player = game.to_play();
+    let legal = game.legal_pegs();
+    let card = players[player].peg(&game.view(player), &legal);
+    game.peg(card).expect("illegal peg");
+  }
+}
+
+/// Play a game to 121 with `players[p]` as player `p`, `first_dealer` dealing
+/// first and the deal alternating, dealing with `shuffle(deal, cards)`.
+/// Returns every deal played, the winner the one who won the last.
+pub fn play_game<F>(players: &mut [Box<dyn CribbageStrategy>], first_dealer: usize, mut shuffle: F) -> Vec<CribbageGame>
+  where F: FnMut(usize, &mut Vec<Card>) {
+  let mut deals: Vec<CribbageGame> = Vec::new();
+
+  while deals.last().and_then(|g| g.winner()).is_none() {
+    let deal = deals.len();
+    let scores = deals.last().map(|g| g.scores()).unwrap_or([0; NUM_PLAYERS]);
+    let mut game = CribbageGame::new((first_dealer + deal) % NUM_PLAYERS, scores, |c| shuffle(deal, c));
+    play_deal(&mut game, players);
+    deals.push(game);
+  }
+
+  deals
+}
+
+/// Lay away and peg uniformly at random.
+pub struct RandomCribbageStrategy {
+  rng: Isaac64Rng,
+}
+
+impl RandomCribbageStrategy {
+  pub fn new(seed: u64) -> RandomCribbageStrategy {
+    RandomCribbageStrategy {
+      rng: Isaac64Rng::from_seed(&[seed]),
+    }
+  }
+}
+
+impl CribbageStrategy for RandomCribbageStrategy {
+  fn discard(&mut self, view: &CribbageView) -> Vec<Card> {
+    let mut hand = view.hand().to_vec();
+    self.rng.shuffle(&mut hand);
+    hand.truncate(DISCARD_LEN);
+    hand
+  }
+
+  fn peg(&mut self, _view: &CribbageView, legal: &[Card]) -> Card {
+    *self.rng.choose(legal).unwrap()
+  }
+}
+
+/// Keep the four cards with the best average show over every starter, giving
+/// the crib the points of the two laid away, and peg the card scoring the
+/// most, avoiding leaving a count of 5 or 21 for the other player to make
+/// fifteen or 31 with a ten.
+pub struct SimpleCribbageStrategy;
+
+impl SimpleCribbageStrategy {
+  /// Expected show of keeping `keep` and laying away `away`, in points, for
+  /// `starters` the cards the starter may be.
+  fn keep_value(keep: &[Card], away: &[Card], starters: &[Card], dealer: bool) -> f64 {
+    let show: u32 = starters.iter().map(|s| score_show(keep, *s, false).total()).sum();
+    let crib = (fifteens(away) + pairs(away)) as f64;
+
+    show as f64 / starters.len() as f64 + if dealer { crib } else { -crib }
+  }
+}
+
+impl CribbageStrategy for SimpleCribbageStrategy {
+  fn discard(&mut self, view: &CribbageView) -> Vec<Card> {
+    let hand = view.hand();
+    let starters: Vec<Card> = french::new_standard_deck().into_iter().filter(|c| ! hand.contains(c)).collect();
+    let mut best = (::std::f64::NEG_INFINITY, Vec::new());
+
+    for i in 0..hand.len() {
+      for j in i + 1..hand.len() {
+        let away = [hand[i], hand[j]];
+        let keep: Vec<Card> = hand.iter().cloned().filter(|c| ! away.contains(c)).collect();
+        let value = SimpleCribbageStrategy::keep_value(&keep, &away, &starters, view.is_dealer());
+
+        if value > best.0 {
+          best = (value, away.to_vec());
+        }
+      }
+    }
+
+    best.1
+  }
+
+  fn peg(&mut self, view: &CribbageView, legal: &[Card]) -> Card {
+    let count = view.count();
+    let value = view.count_value();
+
+    *legal.iter()
+      .max_by_key(|c| {
+        let mut after = count.to_vec();
+        after.push(**c);
+        let total = value + card_value(**c);
+        (score_peg(&after), total != 5 && total != 21, card_value(**c))
+      })
+      .unwrap()
+  }
+}
@@ -0,0 +1,229 @@
+use games::euchre::*;
+use cards::french::{Rank, Suit};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+/// Names accepted by `euchre_strategy`.
+pub static EUCHRE_STRATEGIES: &'static [&'static str] = &["simple", "random"];
+
+/// Makes trumps and plays the cards of one seat at a euchre table, seeing the
+/// deal only through the `EuchreView` of its seat.
+pub trait EuchreStrategy {
+  /// The suit to make trumps and whether to go alone, `None` to pass. In the
+  /// first round only the suit of `view.up_card()` may be ordered up and in
+  /// the second only the others named. Never `None` when `view.must_call()`.
+  fn call(&mut self, view: &EuchreView) -> Option<(Suit, bool)>;
+
+  /// The card of `view.hand()` to discard, for the dealer after picking up
+  /// the turned card.
+  fn discard(&mut self, view: &EuchreView) -> Card;
+
+  /// One of `legal`, the cards the player may play to the current trick.
+  /// Never called without cards.
+  fn play(&mut self, view: &EuchreView, legal: &[Card]) -> Card;
+}
+
+/// Strategy by name, one of `EUCHRE_STRATEGIES`. Random strategies draw from
+/// `seed`.
+pub fn euchre_strategy(name: &str, seed: u64) -> Option<Box<dyn EuchreStrategy>> {
+  match name {
+    "simple" => Some(Box::new(SimpleEuchreStrategy)),
+    "random" => Some(Box::new(RandomEuchreStrategy::new(seed))),
+    _ => None,
+  }
+}
+
+/// Make trumps and play `game` to the end with `players[p]` in seat `p`.
+pub fn play_deal(game: &mut EuchreGame, players: &mut [Box<dyn EuchreStrategy>]) {
+  assert_eq!(players.len(), NUM_PLAYERS);
+
+  while game.is_bidding() {
+    let seat = game.to_bid();
+    match players[seat].call(&game.view(seat)) {
+      Some((suit, alone)) => game.call(suit, alone).expect("invalid call"),
+      None => game.pass().expect("invalid pass"),
+    }
+  }
+
+  if game.phase() == Phase::Discard {
+    let dealer = game.dealer();
+    let card = players[dealer].discard(&game.view(dealer));
+    game.discard(card).expect("invalid discard");
+  }
+
+  while ! game.is_over() {
+    let seat = game.to_play();
+    let legal = game.legal_plays();
+    let card = players[seat].play(&game.view(seat), &legal);
+    game.play(card).expect("illegal play");
+  }
+}
+
+/// Play a match of `rules` with `players[p]` in seat `p`, dealing with
+/// `shuffle(deal, cards)`, until a partnership wins or `max_deals` deals
+/// have been played. Returns the finished match.
+pub fn play_match<F>(rules: EuchreRules, players: &mut [Box<dyn EuchreStrategy>], max_deals: usize, mut shuffle: F) -> EuchreMatch
+  where F: FnMut(usize, &mut Vec<Card>) {
+  let mut m = EuchreMatch::new(rules);
+
+  while m.winner().is_none() && m.deals() < max_deals {
+    let deal = m.deals();
+    let mut game = EuchreGame::new(rules, m.dealer(), |c| shuffle(deal, c));
+    play_deal(&mut game, players);
+    m.record(&game);
+  }
+
+  m
+}
+
+/// The suits the player may make trumps in the current round of bidding.
+fn callable(view: &EuchreView) -> Vec<Suit> {
+  let up = view.up_card().suit();
+
+  match view.phase() {
+    Phase::OrderUp => vec![up],
+    _ => Suit::standard_iter().cloned().filter(|s| *s != up).collect(),
+  }
+}
+
+/// Make trumps a quarter of the time, never alone, and play uniformly at
+/// random.
+pub struct RandomEuchreStrategy {
+  rng: Isaac64Rng,
+}
+
+impl RandomEuchreStrategy {
+  pub fn new(seed: u64) -> RandomEuchreStrategy {
+    RandomEuchreStrategy {
+      rng: Isaac64Rng::from_seed(&[seed]),
+    }
+  }
+}
+
+impl EuchreStrategy for RandomEuchreStrategy {
+  fn call(&mut self, view: &EuchreView) -> Option<(Suit, bool)> {
+    if ! view.must_call() && self.rng.gen_range(0, 4) > 0 {
+      return None;
+    }
+
+    self.rng.choose(&callable(view)).map(|s| (*s, false))
+  }
+
+  fn discard(&mut self, view: &EuchreView) -> Card {
+    *self.rng.choose(view.hand()).unwrap()
+  }
+
+  fn play(&mut self, _view: &EuchreView, legal: &[Card]) -> Card {
+    *self.rng.choose(legal).unwrap()
+  }
+}
+
+/// Make trumps on a hand strong in them, going alone with both bowers and
+/// more, and play like a partner: lead trumps when making them with the right
+/// bower and off aces otherwise, take tricks cheaply unless the partner is
+/// winning them, and throw the lowest card away when they cannot be taken.
+pub struct SimpleEuchreStrategy;
+
+/// Points a hand needs in its trumps to make them.
+const CALL_STRENGTH: u32 = 14;
+/// Points a hand needs to go alone.
+const ALONE_STRENGTH: u32 = 22;
+
+/// How much `hand` is worth with `trump` trumps: the bowers most, the other
+/// trumps by rank and the aces of the other suits.
+fn strength(hand: &[Card], trump: Suit) -> u32 {
+  hand.iter()
+    .map(|c| {
+      if is_right_bower(*c, trump) {
+        6
+      } else if is_left_bower(*c, trump) {
+        5
+      } else if c.suit() == trump {
+        match c.rank() {
+          Rank::Ace | Rank::King => 4,
+          _ => 3,
+        }
+      } else if c.rank() == Rank::Ace {
+        3
+      } else {
+        0
+      }
+    })
+    .sum()
+}
+
+/// How high `card` plays within its own suit, trumps above the others.
+fn order(card: Card, trump: Suit) -> (bool, u8) {
+  let suit = effective_suit(card, trump);
+  (suit == trump, trick_rank(card, trump, suit))
+}
+
+impl EuchreStrategy for SimpleEuchreStrategy {
+  fn call(&mut self, view: &EuchreView) -> Option<(Suit, bool)> {
+    let hand = view.hand();
+    let up = view.up_card();
+
+    let best = callable(view).into_iter()
+      .map(|suit| {
+        let mut points = strength(hand, suit);
+
+        // The turned card goes to the dealer's side in the first round.
+        if view.phase() == Phase::OrderUp {
+          if view.player() == view.dealer() {
+            let weakest = hand.iter().map(|c| strength(&[*c], suit)).min().unwrap_or(0);
+            points = points + strength(&[up], suit) - weakest;
+          } else if view.partner() == view.dealer() {
+            points += strength(&[up], suit) / 2;
+          } else {
+            points = points.saturating_sub(strength(&[up], suit) / 2);
+          }
+        }
+
+        (points, suit)
+      })
+      .max_by_key(|&(points, _)| points);
+
+    match best {
+      Some((points, suit)) if points >= CALL_STRENGTH || view.must_call() => Some((suit, points >= ALONE_STRENGTH)),
+      _ => None,
+    }
+  }
+
+  fn discard(&mut self, view: &EuchreView) -> Card {
+    let trump = view.trump().unwrap();
+    *view.hand().iter()
+      .min_by_key(|c| order(**c, trump))
+      .unwrap()
+  }
+
+  fn play(&mut self, view: &EuchreView, legal: &[Card]) -> Card {
+    let trump = view.trump().unwrap();
+    let lowest = |cards: &mut dyn Iterator<Item = &Card>| cards.min_by_key(|c| order(**c, trump)).cloned();
+
+    let trick = view.trick();
+    let (winner, winning) = match trick_winner(trick, trump) {
+      Some(w) => w,
+      None => {
+        let makers = view.maker().map(team) == Some(team(view.player()));
+        if makers && legal.iter().any(|c| is_right_bower(*c, trump)) {
+          return *legal.iter().max_by_key(|c| order(**c, trump)).unwrap();
+        }
+
+        let ace = legal.iter().find(|c| c.rank() == Rank::Ace && c.suit() != trump);
+        return match ace {
+          Some(ace) => *ace,
+          None => lowest(&mut legal.iter()).unwrap(),
+        };
+      },
+    };
+
+    let led = effective_suit(trick[0].1, trump);
+    if winner != view.partner() {
+      let beats = legal.iter().filter(|c| trick_rank(**c, trump, led) > trick_rank(winning, trump, led));
+      if let Some(card) = beats.min_by_key(|c| trick_rank(**c, trump, led)) {
+        return *card;
+      }
+    }
+
+    lowest(&mut legal.iter()).unwrap()
+  }
+}
@@ -0,0 +1,129 @@
+use games::hearts::*;
+use cards::french::{Rank, Suit};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+/// Names accepted by `hearts_strategy`.
+pub static HEARTS_STRATEGIES: &'static [&'static str] = &["simple", "random"];
+
+/// Plays the cards of one seat at a hearts table. A strategy only sees the
+/// deal through the `HeartsView` of its seat, so four of them can be pitted
+/// against each other fairly.
+pub trait HeartsStrategy {
+  /// The three cards of `view.hand()` to pass in `view.pass_direction()`.
+  fn pass(&mut self, view: &HeartsView) -> Vec<Card>;
+
+  /// One of `legal`, the cards the player may play to the current trick.
+  /// Never called without cards.
+  fn play(&mut self, view: &HeartsView, legal: &[Card]) -> Card;
+}
+
+/// Strategy by name, one of `HEARTS_STRATEGIES`. Random strategies draw from
+/// `seed`.
+pub fn hearts_strategy(name: &str, seed: u64) -> Option<Box<dyn HeartsStrategy>> {
+  match name {
+    "simple" => Some(Box::new(SimpleHeartsStrategy)),
+    "random" => Some(Box::new(RandomHeartsStrategy::new(seed))),
+    _ => None,
+  }
+}
+
+/// Play `game` to the end with `players[p]` in seat `p`, passing first when
+/// the deal passes, and return the points of each seat.
+pub fn play_deal(game: &mut HeartsGame, players: &mut [Box<dyn HeartsStrategy>]) -> [u32; NUM_PLAYERS] {
+  assert_eq!(players.len(), NUM_PLAYERS);
+
+  if game.is_passing() {
+    let passes: Vec<Vec<Card>> = players.iter_mut().enumerate()
+      .map(|(p, player)| player.pass(&game.view(p)))
+      .collect();
+    game.pass(&passes).expect("invalid pass");
+  }
+
+  while ! game.is_over() {
+    let seat = game.to_play();
+    let legal = game.legal_plays();
+    let card = players[seat].play(&game.view(seat), &legal);
+    game.play(card).expect("illegal play");
+  }
+
+  game.points()
+}
+
+/// Pass and play uniformly at random.
+pub struct RandomHeartsStrategy {
+  rng: Isaac64Rng,
+}
+
+impl RandomHeartsStrategy {
+  pub fn new(seed: u64) -> RandomHeartsStrategy {
+    RandomHeartsStrategy {
+      rng: Isaac64Rng::from_seed(&[seed]),
+    }
+  }
+}
+
+impl HeartsStrategy for RandomHeartsStrategy {
+  fn pass(&mut self, view: &HeartsView) -> Vec<Card> {
+    let mut hand = view.hand().to_vec();
+    self.rng.shuffle(&mut hand);
+    hand.truncate(PASS_LEN);
+    hand
+  }
+
+  fn play(&mut self, _view: &HeartsView, legal: &[Card]) -> Card {
+    *self.rng.choose(legal).unwrap()
+  }
+}
+
+/// Pass the most dangerous cards and duck tricks: lead low, play the highest
+/// card that loses the trick when there is one, and shed the queen of spades
+/// and high hearts when void in the suit led.
+pub struct SimpleHeartsStrategy;
+
+/// How much a card is worth getting rid of, the highest first.
+fn danger(card: Card) -> u8 {
+  let value = card.rank().ace_high_value();
+
+  match (card.suit(), card.rank()) {
+    (Suit::Spades, Rank::Queen) => 100,
+    (Suit::Spades, Rank::King) | (Suit::Spades, Rank::Ace) => 80 + value,
+    (Suit::Hearts, _) => 40 + value,
+    _ => value,
+  }
+}
+
+fn is_queen_of_spades(card: Card) -> bool {
+  card.suit() == Suit::Spades && card.rank() == Rank::Queen
+}
+
+impl HeartsStrategy for SimpleHeartsStrategy {
+  fn pass(&mut self, view: &HeartsView) -> Vec<Card> {
+    let mut hand = view.hand().to_vec();
+    hand.sort_by_key(|c| ::std::cmp::Reverse(danger(*c)));
+    hand.truncate(PASS_LEN);
+    hand
+  }
+
+  fn play(&mut self, view: &HeartsView, legal: &[Card]) -> Card {
+    let trick = view.trick();
+    let value = |c: &Card| c.rank().ace_high_value();
+
+    let (suit, winning) = match (trick.suit(), trick.winner(None)) {
+      (Some(suit), Some((_, winning))) => (suit, winning),
+      _ => return *legal.iter().min_by_key(|c| value(c)).unwrap(),
+    };
+
+    if legal.iter().all(|c| c.suit() != suit) {
+      return *legal.iter().max_by_key(|c| danger(**c)).unwrap();
+    }
+
+    let ducks = legal.iter().filter(|c| value(c) < value(&winning));
+    if let Some(card) = ducks.max_by_key(|c| value(c)) {
+      return *card;
+    }
+
+    *legal.iter()
+      .max_by_key(|c| (! is_queen_of_spades(**c), value(c)))
+      .unwrap()
+  }
+}
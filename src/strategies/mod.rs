@@ -1 +1,8 @@
+pub mod blackjack;
+pub mod crazy_eights;
+pub mod cribbage;
+pub mod euchre;
+pub mod hearts;
 pub mod solitaire;
+pub mod spades;
+pub mod video_poker;
@@ -0,0 +1,89 @@
+use games::solitaire::accordion::*;
+
+/// Names accepted by `accordion_strategy`.
+pub static ACCORDION_STRATEGIES: &'static [&'static str] = &["simple", "lookahead"];
+
+/// Chooses the moves of an accordion game, the hook for search strategies.
+pub trait AccordionStrategy {
+  /// One of `moves`, every legal move of `game`: the collapses from the left
+  /// and the deal while the stock lasts. Never called without moves.
+  fn choose(&mut self, game: &AccordionGame, moves: &[AccordionMove]) -> AccordionMove;
+
+  /// Play `game` until no move is left.
+  fn play(&mut self, game: &mut AccordionGame) {
+    loop {
+      let moves = game.legal_moves();
+      if moves.is_empty() {
+        break;
+      }
+
+      let m = self.choose(game, &moves);
+      game.apply(m).unwrap();
+    }
+  }
+}
+
+/// Strategy by name, one of `ACCORDION_STRATEGIES`.
+pub fn accordion_strategy(name: &str) -> Option<Box<dyn AccordionStrategy>> {
+  match name {
+    "simple" => Some(Box::new(SimpleAccordionStrategy)),
+    "lookahead" => Some(Box::new(LookaheadAccordionStrategy::new(6))),
+    _ => None,
+  }
+}
+
+/// Collapse whenever possible, the rightmost pile first and onto the pile
+/// three to the left before the next one, and deal otherwise.
+pub struct SimpleAccordionStrategy;
+
+impl AccordionStrategy for SimpleAccordionStrategy {
+  fn choose(&mut self, _game: &AccordionGame, moves: &[AccordionMove]) -> AccordionMove {
+    moves.iter().cloned()
+      .filter(|m| *m != AccordionMove::Deal)
+      .max_by_key(|m| match *m {
+        AccordionMove::Collapse { pile, distance } => (pile, distance),
+        AccordionMove::Deal => (0, 0),
+      })
+      .unwrap_or(AccordionMove::Deal)
+  }
+}
+
+/// Play the collapse that allows the most collapses in a row within `depth`
+/// collapses, without looking at the stock, and deal when there is none.
+pub struct LookaheadAccordionStrategy {
+  depth: usize,
+}
+
+impl LookaheadAccordionStrategy {
+  pub fn new(depth: usize) -> LookaheadAccordionStrategy {
+    LookaheadAccordionStrategy {
+      depth: depth,
+    }
+  }
+}
+
+impl AccordionStrategy for LookaheadAccordionStrategy {
+  fn choose(&mut self, game: &AccordionGame, moves: &[AccordionMove]) -> AccordionMove {
+    let depth = self.depth;
+
+    moves.iter().cloned()
+      .filter(|m| *m != AccordionMove::Deal)
+      .max_by_key(|m| collapse_run(game, *m, depth))
+      .unwrap_or(AccordionMove::Deal)
+  }
+}
+
+/// Longest run of collapses, `m` included, up to `depth` collapses.
+fn collapse_run(game: &AccordionGame, m: AccordionMove, depth: usize) -> usize {
+  if depth <= 1 {
+    return 1;
+  }
+
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+
+  1 + next.collapses().into_iter()
+    .map(|n| collapse_run(&next, n, depth - 1))
+    .max()
+    .unwrap_or(0)
+}
@@ -0,0 +1,103 @@
+use cards::french::Rank;
+use games::solitaire::bakers_dozen::*;
+use games::solitaire::klondike::Card;
+use std::collections::HashSet;
+
+/// Most moves played in one game, a guard against wandering between
+/// positions that all look equally good.
+const MAX_MOVES: usize = 1000;
+
+/// Names accepted by `bakers_dozen_filter`.
+pub static BAKERS_DOZEN_FILTERS: &'static [&'static str] = &["all", "shallow-aces"];
+
+/// Chooses the deals that are played, like `GameFilter` for klondike.
+pub trait BakersDozenFilter {
+  fn accept(&self, game: &BakersDozenGame) -> bool;
+}
+
+/// Filter by name, one of `BAKERS_DOZEN_FILTERS`.
+pub fn bakers_dozen_filter(name: &str) -> Option<Box<dyn BakersDozenFilter>> {
+  match name {
+    "all" => Some(Box::new(AllDealsFilter)),
+    "shallow-aces" => Some(Box::new(ShallowAcesFilter)),
+    _ => None,
+  }
+}
+
+pub struct AllDealsFilter;
+
+impl BakersDozenFilter for AllDealsFilter {
+  fn accept(&self, _game: &BakersDozenGame) -> bool {
+    true
+  }
+}
+
+/// Deals where no ace is under more than one card, about one in thirteen.
+pub struct ShallowAcesFilter;
+
+impl BakersDozenFilter for ShallowAcesFilter {
+  fn accept(&self, game: &BakersDozenGame) -> bool {
+    game.columns().iter().all(|c| {
+      c.iter().rev().skip(2).all(|card| card.rank() != Rank::Ace)
+    })
+  }
+}
+
+/// Greedy Baker's Dozen strategy: move a card to its foundation whenever
+/// possible, otherwise play the move that leaves the fewest cards on top of
+/// the next card each foundation needs. Positions already seen are never
+/// played again.
+pub struct SimpleBakersDozenStrategy;
+
+impl SimpleBakersDozenStrategy {
+  pub fn new() -> SimpleBakersDozenStrategy {
+    SimpleBakersDozenStrategy
+  }
+
+  pub fn play(&mut self, game: &mut BakersDozenGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.notation());
+
+    while ! game.is_clear() && game.moves_made() < MAX_MOVES {
+      let moves = game.legal_moves();
+
+      let next = match moves.iter().cloned().find(|m| match *m { BakersDozenMove::Found(_) => true, _ => false }) {
+        Some(m) => Some(m),
+        None => {
+          let current = buried(game);
+          moves.iter().cloned()
+            .map(|m| (m, after(game, m)))
+            .filter(|&(_, ref next)| buried(next) <= current && ! seen.contains(&next.notation()))
+            .min_by_key(|&(_, ref next)| buried(next))
+            .map(|(m, _)| m)
+        },
+      };
+
+      match next {
+        Some(m) => {
+          game.apply(m).unwrap();
+          seen.insert(game.notation());
+        },
+        None => break,
+      }
+    }
+  }
+}
+
+fn after(game: &BakersDozenGame, m: BakersDozenMove) -> BakersDozenGame {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+  next
+}
+
+/// Cards on top of the next card of each foundation.
+fn buried(game: &BakersDozenGame) -> usize {
+  let wanted: Vec<Card> = game.foundations().iter().filter_map(|f| f.next_card()).collect();
+
+  game.columns().iter().map(|c| {
+    match c.iter().position(|card| wanted.contains(card)) {
+      Some(i) => c.len() - i - 1,
+      None => 0,
+    }
+  }).sum()
+}
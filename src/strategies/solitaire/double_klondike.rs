@@ -0,0 +1,75 @@
+use games::solitaire::double_klondike::*;
+use std::collections::HashSet;
+
+/// Most moves played in one game, a guard against wandering between
+/// positions that all look equally good.
+const MAX_MOVES: usize = 2000;
+
+/// Greedy Double Klondike strategy: play foundation moves first, then the
+/// pile moves that turn up a hidden card or empty a pile, then deck cards to
+/// the piles, and draw when nothing else helps. Positions already seen are
+/// never played again.
+pub struct SimpleDoubleKlondikeStrategy;
+
+impl SimpleDoubleKlondikeStrategy {
+  pub fn new() -> SimpleDoubleKlondikeStrategy {
+    SimpleDoubleKlondikeStrategy
+  }
+
+  pub fn play(&mut self, game: &mut DoubleKlondikeGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.notation());
+
+    while ! game.is_clear() && game.moves_made() < MAX_MOVES {
+      let mut moves: Vec<(i32, DoubleKlondikeMove)> = game.legal_moves().into_iter()
+        .map(|m| (rate(game, m), m))
+        .filter(|&(rating, _)| rating > 0)
+        .collect();
+      moves.sort_by(|a, b| b.0.cmp(&a.0));
+
+      let next = moves.iter()
+        .map(|&(_, m)| m)
+        .find(|m| ! seen.contains(&after(game, *m)));
+
+      match next {
+        Some(m) => {
+          game.apply(m).unwrap();
+          seen.insert(game.notation());
+        },
+        None => break,
+      }
+    }
+  }
+}
+
+fn after(game: &DoubleKlondikeGame, m: DoubleKlondikeMove) -> String {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+  next.notation()
+}
+
+/// How much a move improves the game, positive if it is worth playing.
+fn rate(game: &DoubleKlondikeGame, m: DoubleKlondikeMove) -> i32 {
+  match m {
+    DoubleKlondikeMove::Draw => 1,
+    DoubleKlondikeMove::Cards(Source::Foundation(_), _) => 0,
+    DoubleKlondikeMove::Cards(_, Target::Foundation) => 20,
+    DoubleKlondikeMove::Cards(Source::Deck, Target::Pile(_)) => 4,
+    DoubleKlondikeMove::Cards(Source::Pile(s), Target::Pile(t)) => {
+      let (source, target) = (game.pile(s as usize), game.pile(t as usize));
+      let index = source.visible_cards().iter().position(|c| target.can_push(*c).is_ok()).unwrap();
+
+      if index > 0 {
+        // Splits a built sequence
+        0
+      } else if ! source.hidden_cards().is_empty() {
+        10
+      } else if target.is_empty() {
+        // Moves a whole pile to another empty pile
+        0
+      } else {
+        5
+      }
+    },
+  }
+}
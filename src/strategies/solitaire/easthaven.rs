@@ -0,0 +1,75 @@
+use games::solitaire::easthaven::*;
+use std::collections::HashSet;
+
+/// Most moves played in one game, a guard against wandering between
+/// positions that all look equally good.
+const MAX_MOVES: usize = 1000;
+
+/// Greedy Easthaven strategy: play foundation moves first, then the pile
+/// moves that turn up a hidden card, empty a pile or uncover a dealt card,
+/// and deal a row once nothing else helps. Positions already seen are never
+/// played again.
+pub struct SimpleEasthavenStrategy;
+
+impl SimpleEasthavenStrategy {
+  pub fn new() -> SimpleEasthavenStrategy {
+    SimpleEasthavenStrategy
+  }
+
+  pub fn play(&mut self, game: &mut EasthavenGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.notation());
+
+    while ! game.is_clear() && game.moves_made() < MAX_MOVES {
+      let mut moves: Vec<(i32, EasthavenMove)> = game.legal_moves().into_iter()
+        .map(|m| (rate(game, m), m))
+        .filter(|&(rating, _)| rating > 0)
+        .collect();
+      moves.sort_by(|a, b| b.0.cmp(&a.0));
+
+      let next = moves.iter()
+        .map(|&(_, m)| m)
+        .find(|m| ! seen.contains(&after(game, *m)));
+
+      match next {
+        Some(m) => {
+          game.apply(m).unwrap();
+          seen.insert(game.notation());
+        },
+        None => break,
+      }
+    }
+  }
+}
+
+fn after(game: &EasthavenGame, m: EasthavenMove) -> String {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+  next.notation()
+}
+
+/// How much a move improves the game, positive if it is worth playing.
+fn rate(game: &EasthavenGame, m: EasthavenMove) -> i32 {
+  let (source, index, target) = match m {
+    EasthavenMove::Found(_) => return 20,
+    EasthavenMove::Deal => return 1,
+    EasthavenMove::Pile { source, index, target } => (game.pile(source as usize), index as usize, game.pile(target as usize)),
+  };
+
+  if index > 0 {
+    if builds_on(source.visible_cards()[index], source.visible_cards()[index - 1]) {
+      // Splits a sequence
+      0
+    } else {
+      // Uncovers a card dealt from the stock
+      4
+    }
+  } else if ! source.hidden_cards().is_empty() {
+    10
+  } else if target.is_empty() {
+    // A king already at the bottom of its pile
+    0
+  } else {
+    5
+  }
+}
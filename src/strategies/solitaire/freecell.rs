@@ -0,0 +1,114 @@
+use games::solitaire::freecell::*;
+use std::collections::HashSet;
+
+/// Most moves played in one game, a guard against wandering between
+/// positions that all look equally good.
+const MAX_MOVES: usize = 1000;
+
+/// Greedy FreeCell strategy: found the cards that are safe to found, then
+/// play the best looking move, preferring foundations, builds that empty or
+/// uncover a card and leaving the free cells for last. Positions already seen
+/// are never played again.
+pub struct SimpleFreeCellStrategy;
+
+impl SimpleFreeCellStrategy {
+  pub fn new() -> SimpleFreeCellStrategy {
+    SimpleFreeCellStrategy
+  }
+
+  pub fn play(&mut self, game: &mut FreeCellGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.notation());
+
+    while ! game.is_clear() && game.moves_made() < MAX_MOVES {
+      let mut moves: Vec<(i32, FreeCellMove)> = game.legal_moves().into_iter()
+        .map(|m| (rate(game, m), m))
+        .filter(|&(rating, _)| rating > -5)
+        .collect();
+      moves.sort_by(|a, b| b.0.cmp(&a.0));
+
+      let next = moves.iter()
+        .map(|&(_, m)| m)
+        .find(|m| ! seen.contains(&after(game, *m)));
+
+      match next {
+        Some(m) => {
+          game.apply(m).unwrap();
+          seen.insert(game.notation());
+        },
+        None => break,
+      }
+    }
+  }
+}
+
+fn after(game: &FreeCellGame, m: FreeCellMove) -> String {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+  next.notation()
+}
+
+/// How much a move improves the game. Moves rated -5 or less are never
+/// played.
+fn rate(game: &FreeCellGame, m: FreeCellMove) -> i32 {
+  let count = m.count as usize;
+
+  let moved = match m.source {
+    Place::Cascade(i) => {
+      let cascade = game.cascade(i as usize);
+      cascade[cascade.len() - count]
+    },
+    Place::Cell(i) => game.cells()[i as usize].unwrap(),
+    Place::Foundation => return -10,
+  };
+
+  let mut rating = match m.target {
+    Place::Foundation if game.is_safe_to_found(moved) => return 100,
+    Place::Foundation => 20,
+    Place::Cell(_) => -3,
+    Place::Cascade(i) if game.cascade(i as usize).is_empty() => match m.source {
+      Place::Cascade(s) if count == game.cascade(s as usize).len() => return -10,
+      Place::Cascade(_) => 1,
+      _ => -1,
+    },
+    Place::Cascade(_) => match m.source {
+      Place::Cell(_) => 8,
+      _ => 5,
+    },
+  };
+
+  if let Place::Cascade(s) = m.source {
+    let source = game.cascade(s as usize);
+
+    if count < game.run_len(s as usize) {
+      // The card below is already built on
+      rating -= 6;
+    } else if count == source.len() {
+      rating += 5;
+    } else if uncovers_play(game, m) {
+      rating += 4;
+    }
+  }
+
+  rating
+}
+
+/// Whether the card that `m` uncovers can then go to a foundation or onto a
+/// cascade that is not empty.
+fn uncovers_play(game: &FreeCellGame, m: FreeCellMove) -> bool {
+  let source = match m.source {
+    Place::Cascade(i) => i,
+    _ => return false,
+  };
+
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+
+  next.legal_moves().into_iter().any(|n| {
+    n.source == Place::Cascade(source) && n.count == 1 && match n.target {
+      Place::Foundation => true,
+      Place::Cascade(t) => ! next.cascade(t as usize).is_empty(),
+      Place::Cell(_) => false,
+    }
+  })
+}
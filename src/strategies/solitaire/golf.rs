@@ -0,0 +1,52 @@
+use games::solitaire::golf::*;
+
+/// How many plays ahead the strategy looks for the longest run of plays.
+const LOOKAHEAD: usize = 4;
+
+/// Greedy Golf strategy: play the column card that starts the longest run of
+/// further plays within a few cards, preferring the tallest column, and draw
+/// only when no column card can be played.
+pub struct SimpleGolfStrategy;
+
+impl SimpleGolfStrategy {
+  pub fn new() -> SimpleGolfStrategy {
+    SimpleGolfStrategy
+  }
+
+  pub fn play(&mut self, game: &mut GolfGame) {
+    while ! game.is_clear() {
+      let next = game.legal_moves().into_iter()
+        .filter(|m| *m != GolfMove::Draw)
+        .max_by_key(|m| (run_len(game, *m, LOOKAHEAD), column_len(game, *m)))
+        .or_else(|| game.legal_moves().into_iter().find(|m| *m == GolfMove::Draw));
+
+      match next {
+        Some(m) => game.apply(m).unwrap(),
+        None => break,
+      }
+    }
+  }
+}
+
+fn column_len(game: &GolfGame, m: GolfMove) -> usize {
+  match m {
+    GolfMove::Play(i) => game.column(i as usize).len(),
+    GolfMove::Draw => 0,
+  }
+}
+
+/// Longest run of column plays, `m` included, up to `depth` plays.
+fn run_len(game: &GolfGame, m: GolfMove, depth: usize) -> usize {
+  if depth <= 1 {
+    return 1;
+  }
+
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+
+  1 + next.legal_moves().into_iter()
+    .filter(|n| *n != GolfMove::Draw)
+    .map(|n| run_len(&next, n, depth - 1))
+    .max()
+    .unwrap_or(0)
+}
@@ -0,0 +1,124 @@
+use games::solitaire::klondike::*;
+use super::{GameStrategy, StuckCheck};
+
+/// Plays the legal move with the highest `priority`, drawing when no move
+/// has one, until a pass through the deck makes no move.
+///
+/// Every move it makes reveals a card, takes one from the deck or founds
+/// one, so it can not go around in circles.
+pub struct GreedyStrategy;
+
+impl GameStrategy for GreedyStrategy {
+  fn new() -> GreedyStrategy {
+    GreedyStrategy
+  }
+
+  fn play(&mut self, game: &mut KlondikeSolitaireGame) {
+    let mut moved = false;
+    let mut stuck = StuckCheck::new();
+
+    while ! game.is_clear() {
+      if let Some(m) = best_move(game) {
+        moved = true;
+        stuck.changed();
+        game.apply(m).unwrap();
+        continue;
+      }
+
+      if ! game.deck().can_draw() {
+        break;
+      }
+
+      // No move can make progress any more
+      if stuck.is_stuck(game) {
+        break;
+      }
+
+      // Exit if have iterated through deck and no moves occurred
+      if let DrawResult::Recycled = game.draw() {
+        if ! moved {
+          break;
+        }
+
+        moved = false;
+        stuck.changed();
+      }
+    }
+  }
+}
+
+/// Legal move of `game` with the highest `priority`, the first in
+/// `legal_moves` order of those that tie.
+pub fn best_move(game: &KlondikeSolitaireGame) -> Option<Move> {
+  let mut best = None;
+
+  for m in game.legal_moves() {
+    if let Some(p) = priority(game, m) {
+      if best.map_or(true, |(q, _)| p > q) {
+        best = Some((p, m));
+      }
+    }
+  }
+
+  best.map(|(_, m)| m)
+}
+
+/// How good `m` is in `game` by the usual rules of thumb, higher first, or
+/// `None` for a move not worth making:
+///
+/// 1. Moves that turn up a face down card, those from the pile with the most
+///    face down cards first.
+/// 2. Safe foundation moves, of cards no card left in play needs to be built
+///    on, by `KlondikeSolitaireGame::is_safe_to_found`.
+/// 3. Deck cards played to a pile.
+/// 4. Other foundation moves, which are delayed as the card could still hold
+///    a lower card of the other color, deck cards first and then the higher
+///    cards first, as low cards are the more likely to be needed.
+///
+/// Other moves, draws and cards taken back from the foundations are not
+/// worth making.
+pub fn priority(game: &KlondikeSolitaireGame, m: Move) -> Option<usize> {
+  let (source, target) = match m {
+    Move::Draw => return None,
+    Move::Cards(source, target) => (source, target),
+  };
+
+  if let Some(hidden) = revealed(game, source, target) {
+    return Some(4000 + hidden);
+  }
+
+  match (source, target) {
+    (MoveSource::Deck, MoveTarget::Foundation) => game.deck().top().map(|card| foundation_priority(game, card, 1900)),
+    (MoveSource::Pile(i), MoveTarget::Foundation) => game.pile(i as usize).top().map(|card| foundation_priority(game, card, 900)),
+    (MoveSource::Deck, MoveTarget::Pile(_)) => Some(2000),
+    _ => None,
+  }
+}
+
+/// Number of face down cards of the `source` pile, if moving it to `target`
+/// takes every face up card off them and turns one up.
+fn revealed(game: &KlondikeSolitaireGame, source: MoveSource, target: MoveTarget) -> Option<usize> {
+  let pile = match source {
+    MoveSource::Pile(i) => game.pile(i as usize),
+    _ => return None,
+  };
+
+  let hidden = pile.hidden_cards().len();
+  let bottom = match pile.visible_cards().first() {
+    Some(card) if hidden > 0 => *card,
+    _ => return None,
+  };
+
+  let clears = match target {
+    MoveTarget::Foundation => pile.visible_cards().len() == 1,
+    MoveTarget::Pile(t) => game.pile(t as usize).can_push(bottom).is_ok(),
+  };
+
+  if clears { Some(hidden) } else { None }
+}
+
+/// Priority of founding `card`, `unsafe_base` plus its rank for a foundation
+/// move that is not safe.
+fn foundation_priority(game: &KlondikeSolitaireGame, card: Card, unsafe_base: usize) -> usize {
+  if game.is_safe_to_found(card) { 3000 } else { unsafe_base + card.rank().value() as usize }
+}
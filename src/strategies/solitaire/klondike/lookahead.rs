@@ -0,0 +1,150 @@
+use games::solitaire::klondike::*;
+use games::solitaire::tracker::CardTracker;
+use cards::french::Suit;
+use super::GameStrategy;
+use rand::{self, Isaac64Rng, Rng, SeedableRng, XorShiftRng};
+use std::collections::HashSet;
+
+/// Moves played without founding or turning up a card before the strategy
+/// gives up.
+const STALL_LIMIT: usize = 200;
+
+/// Scores a position for `LookaheadStrategy`, higher for positions closer to
+/// a win.
+pub type Evaluation = Box<dyn Fn(&KlondikeSolitaireGame) -> f64>;
+
+/// Plays the first move of the best line of play up to `depth` moves deep.
+///
+/// Every line of legal moves, draws included, is played out on copies of the
+/// game and scored by the evaluation of the best position along it, so a line
+/// may stop short of `depth`. The copies have the cards the player has not
+/// seen shuffled, by `KlondikeSolitaireGame::determinize`, so lines that turn
+/// up a card do not give away what it is, unless the game is thoughtful.
+/// Moves back to a position the game was already in are not played, and the
+/// strategy gives up when only those are left or after `STALL_LIMIT` moves
+/// that neither found nor turn up a card.
+pub struct LookaheadStrategy {
+  depth: usize,
+  evaluate: Evaluation,
+  rng: XorShiftRng,
+}
+
+impl LookaheadStrategy {
+  pub fn with_depth(depth: usize) -> LookaheadStrategy {
+    LookaheadStrategy::with_evaluation(depth, Box::new(default_evaluation))
+  }
+
+  pub fn with_evaluation(depth: usize, evaluate: Evaluation) -> LookaheadStrategy {
+    assert!(depth > 0, "lookahead depth must be at least 1");
+
+    LookaheadStrategy {
+      depth: depth,
+      evaluate: evaluate,
+      rng: rand::thread_rng().gen(),
+    }
+  }
+
+  pub fn depth(&self) -> usize {
+    self.depth
+  }
+
+  /// Best score of the positions within `depth` moves of `game`, `game`
+  /// itself included.
+  fn search(&self, game: &KlondikeSolitaireGame, depth: usize) -> f64 {
+    let score = (self.evaluate)(game);
+    if depth == 0 || game.is_clear() {
+      return score;
+    }
+
+    game.legal_moves().into_iter()
+      .map(|m| {
+        let mut next = game.clone();
+        next.apply(m).unwrap();
+        self.search(&next, depth - 1)
+      })
+      .fold(score, f64::max)
+  }
+}
+
+impl GameStrategy for LookaheadStrategy {
+  fn new() -> LookaheadStrategy {
+    LookaheadStrategy::with_depth(2)
+  }
+
+  fn play(&mut self, game: &mut KlondikeSolitaireGame) {
+    let mut tracker = CardTracker::new();
+    let mut seen = HashSet::new();
+    seen.insert(game.state_hash());
+    let mut best_progress = progress(game);
+    let mut stalled = 0;
+
+    while ! game.is_clear() && stalled < STALL_LIMIT {
+      tracker.observe(game);
+      let searched = match game.deck().all_cards() {
+        Some(_) => game.clone(),
+        None => {
+          let rng = &mut self.rng;
+          game.determinize(|c| tracker.has_seen(c), |cards| rng.shuffle(cards))
+        },
+      };
+
+      let mut best: Option<(f64, Move, u64)> = None;
+
+      for m in game.legal_moves() {
+        let mut next = game.clone();
+        next.apply(m).unwrap();
+        if seen.contains(&next.state_hash()) {
+          continue;
+        }
+
+        let mut searched_next = searched.clone();
+        searched_next.apply(m).unwrap();
+        let score = self.search(&searched_next, self.depth - 1);
+        if best.map_or(true, |b| score > b.0) {
+          best = Some((score, m, next.state_hash()));
+        }
+      }
+
+      match best {
+        Some((_, m, hash)) => {
+          seen.insert(hash);
+          game.apply(m).unwrap();
+        },
+        None => break,
+      }
+
+      let p = progress(game);
+      if p > best_progress {
+        best_progress = p;
+        stalled = 0;
+      } else {
+        stalled += 1;
+      }
+    }
+  }
+
+  fn set_seed(&mut self, seed: u64) {
+    self.rng = Isaac64Rng::from_seed(&[seed]).gen();
+  }
+}
+
+/// Cards founded or turned up so far.
+fn progress(game: &KlondikeSolitaireGame) -> usize {
+  founded(game) + 21 - hidden(game)
+}
+
+fn founded(game: &KlondikeSolitaireGame) -> usize {
+  [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs].iter()
+    .map(|suit| game.foundation(*suit).len())
+    .sum()
+}
+
+fn hidden(game: &KlondikeSolitaireGame) -> usize {
+  game.piles().iter().map(|p| p.hidden_cards().len()).sum()
+}
+
+/// Ten points for each founded card, less eight for each face down pile card
+/// and one for each card left in the deck.
+pub fn default_evaluation(game: &KlondikeSolitaireGame) -> f64 {
+  10.0 * founded(game) as f64 - 8.0 * hidden(game) as f64 - game.deck().len() as f64
+}
@@ -0,0 +1,327 @@
+use games::solitaire::klondike::*;
+use games::solitaire::tracker::CardTracker;
+use cards::french::{Suit, STANDARD_DECK_SIZE};
+use super::GameStrategy;
+use super::policy::{Evaluation, PolicyProvider};
+use rand::{self, Isaac64Rng, Rng, SeedableRng, XorShiftRng};
+use search::cache::TranspositionCache;
+use std::time::{Duration, Instant};
+
+/// Tuning parameters for `DeterminizedMctsStrategy`.
+#[derive(Debug, Copy, Clone)]
+pub struct MctsConfig {
+  /// Number of sampled arrangements of the unknown cards searched per move.
+  pub determinizations: usize,
+  /// Search iterations run on each determinization.
+  pub iterations: usize,
+  /// Maximum number of random moves in a rollout.
+  pub rollout_depth: usize,
+  /// UCT exploration constant.
+  pub exploration: f64,
+  /// Resign after this many moves without revealing or founding a card.
+  pub stall_limit: usize,
+  /// Policy evaluations kept by position, so a position reached again in
+  /// the search is not sent to the policy twice.
+  pub policy_cache: usize,
+}
+
+impl Default for MctsConfig {
+  fn default() -> MctsConfig {
+    MctsConfig {
+      determinizations: 8,
+      iterations: 100,
+      rollout_depth: 40,
+      exploration: 0.7,
+      stall_limit: 100,
+      policy_cache: 100000,
+    }
+  }
+}
+
+/// Monte Carlo tree search over sampled deals.
+///
+/// The strategy only uses what a player could see: the face up pile cards and
+/// deck cards that have been drawn. Before each move the unknown cards are
+/// shuffled into several plausible deals, a UCT search is run on each, and the
+/// move chosen by the most searches is played.
+///
+/// With a `PolicyProvider` the search is guided by its move priors (PUCT) and
+/// positions are scored by its value estimate instead of random rollouts.
+///
+/// With a move budget the search is anytime: determinizations are searched
+/// until the budget runs out, however many that is, and the votes so far
+/// decide the move.
+pub struct DeterminizedMctsStrategy {
+  config: MctsConfig,
+  rng: XorShiftRng,
+  policy: Option<Box<dyn PolicyProvider>>,
+  evaluations: TranspositionCache<Evaluation>,
+  move_budget: Option<Duration>,
+}
+
+struct Node {
+  m: Option<Move>,
+  prior: f64,
+  parent: Option<usize>,
+  children: Vec<usize>,
+  untried: Vec<(Move, f64)>,
+  visits: f64,
+  reward: f64,
+}
+
+impl Node {
+  fn new(m: Option<Move>, prior: f64, parent: Option<usize>, untried: Vec<(Move, f64)>) -> Node {
+    Node {
+      m: m,
+      prior: prior,
+      parent: parent,
+      children: Vec::new(),
+      untried: untried,
+      visits: 0.0,
+      reward: 0.0,
+    }
+  }
+}
+
+impl DeterminizedMctsStrategy {
+  pub fn with_config(config: MctsConfig) -> DeterminizedMctsStrategy {
+    DeterminizedMctsStrategy {
+      config: config,
+      rng: rand::weak_rng(),
+      policy: None,
+      evaluations: TranspositionCache::new(0),
+      move_budget: None,
+    }
+  }
+
+  pub fn with_policy(config: MctsConfig, policy: Box<dyn PolicyProvider>) -> DeterminizedMctsStrategy {
+    DeterminizedMctsStrategy {
+      config: config,
+      rng: rand::weak_rng(),
+      policy: Some(policy),
+      evaluations: TranspositionCache::new(config.policy_cache),
+      move_budget: None,
+    }
+  }
+
+  fn choose(&mut self, game: &KlondikeSolitaireGame, tracker: &CardTracker, moves: &[Move]) -> Move {
+    let mut votes = vec![0; moves.len()];
+    let deadline = self.move_budget.map(|budget| Instant::now() + budget);
+    let mut searched = 0;
+
+    while deadline.map_or(searched < self.config.determinizations, |d| searched == 0 || Instant::now() < d) {
+      let det = {
+        let rng = &mut self.rng;
+        game.determinize(|c| tracker.has_seen(c), |cards| rng.shuffle(cards))
+      };
+
+      let best = self.search(&det, moves, deadline);
+      votes[best] += 1;
+      searched += 1;
+    }
+
+    let (best, _) = votes.iter().enumerate().max_by_key(|&(i, v)| (*v, -(i as isize))).unwrap();
+    moves[best]
+  }
+
+  /// Run UCT from `root` and return the index in `moves` of the most visited
+  /// move. Stops early, after at least one iteration, at `deadline`.
+  fn search(&mut self, root: &KlondikeSolitaireGame, moves: &[Move], deadline: Option<Instant>) -> usize {
+    let (untried, _) = self.expand(root, moves.to_vec());
+    let mut nodes = vec![Node::new(None, 1.0, None, untried)];
+
+    for _ in 0..self.config.iterations {
+      let mut state = root.clone();
+      let mut node = 0;
+
+      // Select
+      while nodes[node].untried.is_empty() && ! nodes[node].children.is_empty() {
+        node = self.select_child(&nodes, node);
+        state.apply(nodes[node].m.unwrap()).unwrap();
+      }
+
+      // Expand
+      let mut value = None;
+
+      if ! nodes[node].untried.is_empty() {
+        let i = self.next_untried(&nodes[node].untried);
+        let (m, prior) = nodes[node].untried.swap_remove(i);
+        state.apply(m).unwrap();
+
+        let (untried, v) = self.expand(&state, candidate_moves(&state));
+        value = v;
+
+        let child = nodes.len();
+        nodes.push(Node::new(Some(m), prior, Some(node), untried));
+        nodes[node].children.push(child);
+        node = child;
+      }
+
+      let reward = match value {
+        Some(v) => v,
+        None => self.rollout(&mut state),
+      };
+
+      // Backpropagate
+      let mut current = Some(node);
+      while let Some(n) = current {
+        nodes[n].visits += 1.0;
+        nodes[n].reward += reward;
+        current = nodes[n].parent;
+      }
+
+      if deadline.map_or(false, |d| Instant::now() >= d) {
+        break;
+      }
+    }
+
+    let best = nodes[0].children.iter()
+      .max_by(|a, b| nodes[**a].visits.partial_cmp(&nodes[**b].visits).unwrap())
+      .map(|c| nodes[*c].m.unwrap());
+
+    match best {
+      Some(m) => moves.iter().position(|x| *x == m).unwrap(),
+      None => 0,
+    }
+  }
+
+  /// Pair each move with its prior and, when there is a policy, estimate the
+  /// value of `state`.
+  fn expand(&mut self, state: &KlondikeSolitaireGame, moves: Vec<Move>) -> (Vec<(Move, f64)>, Option<f64>) {
+    match self.policy {
+      Some(ref mut policy) if ! moves.is_empty() => {
+        let evaluation = self.evaluations.get_or_insert_with(state_key(state), || policy.evaluate(state, &moves));
+        (moves.into_iter().zip(evaluation.priors).collect(), Some(evaluation.value))
+      },
+      _ => {
+        let prior = 1.0 / moves.len() as f64;
+        (moves.into_iter().map(|m| (m, prior)).collect(), None)
+      },
+    }
+  }
+
+  fn next_untried(&mut self, untried: &[(Move, f64)]) -> usize {
+    match self.policy {
+      Some(_) => {
+        untried.iter().enumerate()
+          .max_by(|a, b| (a.1).1.partial_cmp(&(b.1).1).unwrap())
+          .unwrap().0
+      },
+      None => self.rng.gen_range(0, untried.len()),
+    }
+  }
+
+  fn select_child(&self, nodes: &[Node], node: usize) -> usize {
+    let parent_visits = nodes[node].visits;
+    let guided = self.policy.is_some();
+    let score = |c: usize| {
+      let child = &nodes[c];
+      let exploit = child.reward / child.visits;
+
+      if guided {
+        exploit + self.config.exploration * child.prior * parent_visits.sqrt() / (1.0 + child.visits)
+      } else {
+        exploit + self.config.exploration * (parent_visits.ln() / child.visits).sqrt()
+      }
+    };
+
+    *nodes[node].children.iter()
+      .max_by(|a, b| score(**a).partial_cmp(&score(**b)).unwrap())
+      .unwrap()
+  }
+
+  fn rollout(&mut self, state: &mut KlondikeSolitaireGame) -> f64 {
+    for _ in 0..self.config.rollout_depth {
+      if state.is_clear() {
+        break;
+      }
+
+      let moves = candidate_moves(state);
+      if moves.is_empty() {
+        break;
+      }
+
+      let m = moves[self.rng.gen_range(0, moves.len())];
+      state.apply(m).unwrap();
+    }
+
+    evaluate(state)
+  }
+}
+
+impl GameStrategy for DeterminizedMctsStrategy {
+  fn new() -> DeterminizedMctsStrategy {
+    DeterminizedMctsStrategy::with_config(MctsConfig::default())
+  }
+
+  fn play(&mut self, game: &mut KlondikeSolitaireGame) {
+    let mut tracker = CardTracker::new();
+    let mut best = progress(game);
+    let mut stalled = 0;
+
+    while ! game.is_clear() && stalled < self.config.stall_limit {
+      tracker.observe(game);
+
+      let moves = candidate_moves(game);
+      let m = match moves.len() {
+        0 => break,
+        1 => moves[0],
+        _ => self.choose(game, &tracker, &moves),
+      };
+
+      game.apply(m).unwrap();
+
+      let p = progress(game);
+      if p > best {
+        best = p;
+        stalled = 0;
+      } else {
+        stalled += 1;
+      }
+    }
+  }
+
+  fn set_move_budget(&mut self, budget: Option<Duration>) {
+    self.move_budget = budget;
+  }
+
+  fn set_seed(&mut self, seed: u64) {
+    self.rng = Isaac64Rng::from_seed(&[seed]).gen();
+  }
+}
+
+/// Hash of everything that decides the candidate moves and the policy's
+/// answer about `state`.
+fn state_key(state: &KlondikeSolitaireGame) -> u64 {
+  state.state_hash()
+}
+
+/// Legal moves worth searching.
+///
+/// Moving cards back off of a foundation and moving a king between empty piles
+/// never help and make the search much wider.
+fn candidate_moves(game: &KlondikeSolitaireGame) -> Vec<Move> {
+  let mut moves = game.legal_moves();
+
+  moves.retain(|m| match *m {
+    Move::Cards(MoveSource::Foundation(_), _) => false,
+    Move::Cards(MoveSource::Pile(i), MoveTarget::Pile(j)) => {
+      ! (game.pile(j as usize).is_empty() && game.pile(i as usize).hidden_cards().is_empty())
+    },
+    _ => true,
+  });
+
+  return moves;
+}
+
+/// Founded cards count double revealed cards.
+fn progress(game: &KlondikeSolitaireGame) -> usize {
+  let founded: usize = Suit::standard_iter().map(|s| game.foundation(*s).len()).sum();
+  let hidden: usize = game.piles().iter().map(|p| p.hidden_cards().len()).sum();
+
+  2 * founded + STANDARD_DECK_SIZE as usize - hidden
+}
+
+fn evaluate(game: &KlondikeSolitaireGame) -> f64 {
+  progress(game) as f64 / (3 * STANDARD_DECK_SIZE as usize) as f64
+}
@@ -1,15 +1,64 @@
+pub mod greedy;
+pub mod lookahead;
+pub mod mcts;
+pub mod policy;
 pub mod simple;
+pub mod simple_plus;
+pub mod solver;
+pub mod weighted;
 
 use games::solitaire::klondike::KlondikeSolitaireGame;
+use std::sync::Arc;
+use std::time::Duration;
 
 pub trait GameFilter {
-    fn new() -> Self;
+    fn new() -> Self where Self: Sized;
     fn accept(&self, game: &KlondikeSolitaireGame) -> bool;
 }
 
 pub trait GameStrategy {
-    fn new() -> Self;
+    fn new() -> Self where Self: Sized;
     fn play(&mut self, game: &mut KlondikeSolitaireGame);
+
+    /// Hint of the wall clock time to spend choosing each move, `None` for
+    /// no limit. Search strategies play their best move so far when it runs
+    /// out, so strategies can be compared with equal compute; the others
+    /// ignore it.
+    fn set_move_budget(&mut self, _budget: Option<Duration>) {}
+
+    /// Seed the random choices of the strategy, so that it plays a game the
+    /// same way every time. Strategies that choose nothing at random ignore
+    /// it.
+    fn set_seed(&mut self, _seed: u64) {}
+}
+
+/// Creates a strategy for each thread of a simulation.
+pub type StrategyFactory = Arc<dyn Fn() -> Box<dyn GameStrategy> + Send + Sync>;
+
+/// Checks `KlondikeSolitaireGame::is_stuck` only when it may have changed.
+/// Drawing does not make a stuck game playable until the deck is turned over,
+/// so a strategy that draws until it finds a move need only check again after
+/// a move or a recycle.
+pub struct StuckCheck {
+  due: bool,
+}
+
+impl StuckCheck {
+  pub fn new() -> StuckCheck {
+    StuckCheck { due: true }
+  }
+
+  /// Check again next time: the game was moved in or its deck turned over.
+  pub fn changed(&mut self) {
+    self.due = true;
+  }
+
+  /// Whether `game` is stuck, false until `changed` once it was checked.
+  pub fn is_stuck(&mut self, game: &KlondikeSolitaireGame) -> bool {
+    let due = self.due;
+    self.due = false;
+    due && game.is_stuck()
+  }
 }
 
 pub struct AllFilter;
@@ -0,0 +1,179 @@
+use games::solitaire::klondike::*;
+use features;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// Output of a policy for a single position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+  /// Prior probability of each candidate move, in the order given.
+  pub priors: Vec<f64>,
+  /// Estimated value of the position, from 0 (lost) to 1 (won).
+  pub value: f64,
+}
+
+/// Source of move priors and position values for search strategies.
+///
+/// This keeps learned models out of the strategies themselves: a search asks
+/// the provider about a position and the provider decides how to answer.
+pub trait PolicyProvider {
+  fn evaluate(&mut self, game: &KlondikeSolitaireGame, moves: &[Move]) -> Evaluation;
+}
+
+/// Policy that uses no knowledge: uniform priors and a neutral value.
+pub struct UniformPolicy;
+
+impl PolicyProvider for UniformPolicy {
+  fn evaluate(&mut self, _game: &KlondikeSolitaireGame, moves: &[Move]) -> Evaluation {
+    Evaluation {
+      priors: vec![1.0 / moves.len() as f64; moves.len()],
+      value: 0.5,
+    }
+  }
+}
+
+/// Policy answered by an external program, e.g. a model inference server.
+///
+/// The command is run once with `sh -c` and kept alive. For each position one
+/// line is written to its stdin:
+///
+/// ```text
+/// <state>;<move> <move> ...
+/// ```
+///
+/// where `<state>` is the comma separated `features::klondike` encoding
+/// and each move is formatted with `Move`'s `Display`. The program must answer
+/// with one line of whitespace separated numbers: the value, from 0 to 1,
+/// followed by one prior per move, none negative.
+///
+/// Once the program fails to answer, or answers something else, the error is
+/// reported and every position after it is evaluated by `UniformPolicy`.
+pub struct ProcessPolicy {
+  child: Child,
+  stdin: ChildStdin,
+  stdout: BufReader<ChildStdout>,
+  failed: bool,
+}
+
+impl ProcessPolicy {
+  pub fn spawn(command: &str) -> ::std::io::Result<ProcessPolicy> {
+    let mut child = Command::new("sh")
+      .arg("-c")
+      .arg(command)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()?;
+
+    let stdin = child.stdin.take().unwrap();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+
+    Ok(ProcessPolicy {
+      child: child,
+      stdin: stdin,
+      stdout: stdout,
+      failed: false,
+    })
+  }
+
+  /// Ask the program about the position of `game` with the candidate moves
+  /// `moves`.
+  pub fn query(&mut self, game: &KlondikeSolitaireGame, moves: &[Move]) -> Result<Evaluation, String> {
+    let state: Vec<String> = features::klondike::extract(game).iter().map(|v| v.to_string()).collect();
+    let moves_str: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+
+    writeln!(self.stdin, "{};{}", state.join(","), moves_str.join(" "))
+      .and_then(|_| self.stdin.flush())
+      .map_err(|e| format!("failed to write to the policy process: {}", e))?;
+
+    let mut line = String::new();
+    match self.stdout.read_line(&mut line) {
+      Ok(0) => return Err(String::from("the policy process exited")),
+      Ok(_) => {},
+      Err(e) => return Err(format!("failed to read the policy process output: {}", e)),
+    }
+
+    let values = line.split_whitespace()
+      .map(|v| v.parse::<f64>().map_err(|_| format!("the policy process returned {}, not a number", v)))
+      .collect::<Result<Vec<f64>, String>>()?;
+
+    if values.len() != moves.len() + 1 {
+      return Err(format!("the policy process returned {} values for {} moves", values.len(), moves.len()));
+    }
+
+    if ! (values[0] >= 0.0 && values[0] <= 1.0) {
+      return Err(format!("the policy process returned the value {}, not one from 0 to 1", values[0]));
+    }
+
+    if let Some(prior) = values[1..].iter().find(|p| ! (**p >= 0.0 && p.is_finite())) {
+      return Err(format!("the policy process returned the prior {}, not a number of 0 or more", prior));
+    }
+
+    Ok(Evaluation {
+      priors: values[1..].to_vec(),
+      value: values[0],
+    })
+  }
+}
+
+impl PolicyProvider for ProcessPolicy {
+  fn evaluate(&mut self, game: &KlondikeSolitaireGame, moves: &[Move]) -> Evaluation {
+    if ! self.failed {
+      match self.query(game, moves) {
+        Ok(evaluation) => return evaluation,
+        Err(e) => {
+          eprintln!("{}, using uniform priors from now on", e);
+          self.failed = true;
+        },
+      }
+    }
+
+    UniformPolicy.evaluate(game, moves)
+  }
+}
+
+impl Drop for ProcessPolicy {
+  fn drop(&mut self) {
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_source;
+
+  fn stub(answer: &str) -> ProcessPolicy {
+    ProcessPolicy::spawn(&format!("while read l; do echo {}; done", answer)).unwrap()
+  }
+
+  #[test]
+  fn process_answers() {
+    let game = KlondikeSolitaireGame::new_shuffle(1, seeded_source(1));
+    let moves = [Move::Draw, Move::Cards(MoveSource::Deck, MoveTarget::Foundation)];
+
+    let mut policy = stub("0.25 3 1");
+    let evaluation = Evaluation { priors: vec![3.0, 1.0], value: 0.25 };
+    assert_eq!(policy.query(&game, &moves), Ok(evaluation.clone()));
+    assert_eq!(policy.evaluate(&game, &moves), evaluation);
+  }
+
+  #[test]
+  fn process_errors() {
+    let game = KlondikeSolitaireGame::new_shuffle(1, seeded_source(1));
+    let moves = [Move::Draw, Move::Cards(MoveSource::Deck, MoveTarget::Foundation)];
+
+    assert!(stub("0.5 1").query(&game, &moves).unwrap_err().contains("2 values for 2 moves"));
+    assert!(stub("0.5 1 x").query(&game, &moves).unwrap_err().contains("x, not a number"));
+    assert!(stub("0.5 1 -1").query(&game, &moves).unwrap_err().contains("prior -1"));
+    assert!(stub("0.5 1 NaN").query(&game, &moves).unwrap_err().contains("prior NaN"));
+    assert!(stub("2 1 1").query(&game, &moves).unwrap_err().contains("value 2"));
+
+    let mut exits = ProcessPolicy::spawn("read l").unwrap();
+    assert!(exits.query(&game, &moves).is_err());
+
+    let mut exits = ProcessPolicy::spawn("read l").unwrap();
+    assert_eq!(exits.evaluate(&game, &moves), UniformPolicy.evaluate(&game, &moves));
+    assert_eq!(exits.evaluate(&game, &moves), UniformPolicy.evaluate(&game, &moves));
+  }
+}
@@ -1,5 +1,5 @@
 use games::solitaire::klondike::*;
-use super::GameStrategy;
+use super::{GameStrategy, StuckCheck};
 
 pub struct SimpleKlondikeStrategy;
 
@@ -10,67 +10,54 @@ impl GameStrategy for SimpleKlondikeStrategy {
 
   fn play(&mut self, game: &mut KlondikeSolitaireGame) {
     let mut moved = false;
+    let mut stuck = StuckCheck::new();
 
     while ! game.is_clear() {
-      // Move pile to foundation
-      if let Some((source, target)) = check_pile_to_foundation(game) {
+      // Move pile to foundation, then deck to foundation, then deck to pile
+      let moves = game.legal_moves();
+      let next = first_of(&moves, MoveKind::PileToFoundation)
+        .or_else(|| first_of(&moves, MoveKind::DeckToFoundation))
+        .or_else(|| first_of(&moves, MoveKind::DeckToPile));
+
+      if let Some(m) = next {
         moved = true;
-        game.move_cards(source, target).unwrap();
+        stuck.changed();
+        game.apply(m).unwrap();
         continue;
       }
 
-      // Move deck to foundation or pile
-      if ! game.deck().visible_cards().is_empty() {
-        if game.move_cards(MoveSource::Deck, MoveTarget::Foundation).is_ok() {
-          moved = true;
-          continue;
-        }
+      // Moves between piles are left to SimplePlusStrategy, so this stays
+      // the baseline
 
-        if let Some((source, target)) = check_deck_to_pile(game) {
-          moved = true;
-          game.move_cards(source, target).unwrap();
-          continue;
-        }
+      // Out of passes through the deck
+      if ! game.deck().can_draw() && ! game.deck().is_empty() {
+        break;
       }
 
-      // TODO move cards between piles if it opens a move to foundation or from deck
-
-      game.draw();
+      // No move can make progress any more
+      if stuck.is_stuck(game) {
+        break;
+      }
 
       // Exit if have iterated through deck and no moves occurred
-      if is_at_start(game.deck()) {
-        if ! moved {
-          break;
-        }
-
-        moved = false;
+      if let DrawResult::Dealt { .. } = game.draw() {
+        continue;
       }
-    }
-  }
-}
-
-fn is_at_start(deck: &Deck) -> bool {
-  deck.visible_cards().is_empty() && deck.waste_cards().is_empty()
-}
+      stuck.changed();
 
-fn check_pile_to_foundation(game: &KlondikeSolitaireGame) -> Option<(MoveSource, MoveTarget)> {
-  for (index, pile) in game.piles().iter().enumerate().filter(|&(_, p)| ! p.is_empty()) {
-    let card = pile.top().unwrap();
-    let foundation = game.foundation(card.suit());
+      if ! moved {
+        break;
+      }
 
-    if foundation.next_card() == Some(card) {
-      return Some((MoveSource::Pile(index as u8), MoveTarget::Foundation));
+      moved = false;
     }
   }
-
-  return None;
 }
 
-fn check_deck_to_pile(game: &KlondikeSolitaireGame) -> Option<(MoveSource, MoveTarget)> {
-  let deck_card = game.deck().top().unwrap();
-
-  match game.piles().iter().position(|p| p.can_push(deck_card).is_ok()) {
-    Some(i) => Some((MoveSource::Deck, MoveTarget::Pile(i as u8))),
-    None => None,
-  }
+/// First of `moves` of `kind`.
+pub fn first_of(moves: &[Move], kind: MoveKind) -> Option<Move> {
+  moves.iter().cloned().find(|m| match *m {
+    Move::Cards(source, target) => MoveKind::of(source, target) == Some(kind),
+    Move::Draw => false,
+  })
 }
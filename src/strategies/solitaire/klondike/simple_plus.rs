@@ -0,0 +1,91 @@
+use games::solitaire::klondike::*;
+use super::{GameStrategy, StuckCheck};
+use super::simple::first_of;
+
+/// `SimpleKlondikeStrategy` that also moves cards between piles, when the
+/// move turns up a face down card or opens a move to a foundation or from
+/// the deck.
+///
+/// Every pile to pile move it makes turns up a card or is followed by a
+/// foundation or deck move, so it can not go around in circles.
+pub struct SimplePlusStrategy;
+
+impl GameStrategy for SimplePlusStrategy {
+  fn new() -> SimplePlusStrategy {
+    SimplePlusStrategy
+  }
+
+  fn play(&mut self, game: &mut KlondikeSolitaireGame) {
+    let mut moved = false;
+    let mut stuck = StuckCheck::new();
+
+    while ! game.is_clear() {
+      // Move pile to foundation, then deck to foundation, then deck to pile,
+      // then pile to pile if it turns up a card, then if it opens a move
+      let moves = game.legal_moves();
+      let next = first_of(&moves, MoveKind::PileToFoundation)
+        .or_else(|| first_of(&moves, MoveKind::DeckToFoundation))
+        .or_else(|| first_of(&moves, MoveKind::DeckToPile))
+        .or_else(|| pile_move(game, &moves, reveals))
+        .or_else(|| pile_move(game, &moves, opens_move));
+
+      if let Some(m) = next {
+        moved = true;
+        stuck.changed();
+        game.apply(m).unwrap();
+        continue;
+      }
+
+      // Out of passes through the deck
+      if ! game.deck().can_draw() && ! game.deck().is_empty() {
+        break;
+      }
+
+      // No move can make progress any more
+      if stuck.is_stuck(game) {
+        break;
+      }
+
+      // Exit if have iterated through deck and no moves occurred
+      if let DrawResult::Dealt { .. } = game.draw() {
+        continue;
+      }
+      stuck.changed();
+
+      if ! moved {
+        break;
+      }
+
+      moved = false;
+    }
+  }
+}
+
+/// First pile to pile move of `moves` for which `wanted` holds of the game
+/// before and after it.
+fn pile_move<F>(game: &KlondikeSolitaireGame, moves: &[Move], wanted: F) -> Option<Move>
+  where F: Fn(&KlondikeSolitaireGame, &KlondikeSolitaireGame, usize) -> bool {
+  moves.iter().cloned().find(|m| match *m {
+    Move::Cards(source, target) if MoveKind::of(source, target) == Some(MoveKind::PileToPile) => {
+      let mut next = game.clone();
+      next.apply(*m).unwrap();
+      wanted(game, &next, source.pile().unwrap())
+    },
+    _ => false,
+  })
+}
+
+/// Whether moving cards off `pile` turned up a face down card.
+fn reveals(game: &KlondikeSolitaireGame, next: &KlondikeSolitaireGame, pile: usize) -> bool {
+  next.pile(pile).hidden_cards().len() < game.pile(pile).hidden_cards().len()
+}
+
+/// Whether a card can go to a foundation or come from the deck after the
+/// move. None could before, or it would have been played instead.
+fn opens_move(_: &KlondikeSolitaireGame, next: &KlondikeSolitaireGame, _: usize) -> bool {
+  let moves = next.legal_moves();
+  first_of(&moves, MoveKind::PileToFoundation)
+    .or_else(|| first_of(&moves, MoveKind::DeckToFoundation))
+    .or_else(|| first_of(&moves, MoveKind::DeckToPile))
+    .is_some()
+}
@@ -0,0 +1,44 @@
+use games::solitaire::klondike::*;
+use search::solver::{Solver, Verdict};
+use super::GameStrategy;
+
+/// Positions searched for each game by `SolverStrategy::new`, as `solve`
+/// searches by default.
+pub const DEFAULT_MAX_NODES: usize = 1000000;
+
+/// Plays thoughtful games with `search::solver`: the winning line when the
+/// search proves the deal can be won, and nothing otherwise.
+///
+/// Its win rate is the share of deals the solver proves winnable within its
+/// node limit, a lower bound of the share that can be won. A deal it could
+/// not decide is resigned like a lost one. Games that are not thoughtful are
+/// resigned as well, since the solver needs to see every card.
+pub struct SolverStrategy {
+  solver: Solver,
+}
+
+impl SolverStrategy {
+  pub fn with_solver(solver: Solver) -> SolverStrategy {
+    SolverStrategy {
+      solver: solver,
+    }
+  }
+}
+
+impl GameStrategy for SolverStrategy {
+  fn new() -> SolverStrategy {
+    SolverStrategy::with_solver(Solver::new(DEFAULT_MAX_NODES))
+  }
+
+  fn play(&mut self, game: &mut KlondikeSolitaireGame) {
+    if game.deck().all_cards().is_none() {
+      return;
+    }
+
+    if let Verdict::Winnable(line) = self.solver.solve(game) {
+      for m in line {
+        game.apply(m).unwrap();
+      }
+    }
+  }
+}
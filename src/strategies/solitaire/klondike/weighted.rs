@@ -0,0 +1,263 @@
+use games::solitaire::klondike::*;
+use cards::french::Suit;
+use super::GameStrategy;
+use std::cmp;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Names of the `Weights`, in the order of `Weights::to_vec`.
+pub static WEIGHT_NAMES: &'static [&'static str] = &[
+  "reveal",
+  "hidden_depth",
+  "empty_pile",
+  "foundation",
+  "foundation_delay",
+  "deck_to_pile",
+  "pile_to_pile",
+  "from_foundation",
+];
+
+/// Moves played without founding or turning up a card before the strategy
+/// gives up.
+const STALL_LIMIT: usize = 200;
+
+/// What each property of a move adds to its score in `WeightedStrategy`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Weights {
+  /// The move turns up a face down card.
+  pub reveal: f64,
+  /// For each face down card of the pile a card is turned up on.
+  pub hidden_depth: f64,
+  /// For each pile the move empties, less for each it fills.
+  pub empty_pile: f64,
+  /// A card goes to a foundation.
+  pub foundation: f64,
+  /// For each rank a founded card is ahead of the lower foundation of the
+  /// other color, taken off the score.
+  pub foundation_delay: f64,
+  pub deck_to_pile: f64,
+  pub pile_to_pile: f64,
+  /// A card is taken back from a foundation.
+  pub from_foundation: f64,
+}
+
+impl Default for Weights {
+  fn default() -> Weights {
+    Weights {
+      reveal: 40.0,
+      hidden_depth: 1.0,
+      empty_pile: 0.0,
+      foundation: 30.0,
+      foundation_delay: 10.0,
+      deck_to_pile: 20.0,
+      pile_to_pile: -5.0,
+      from_foundation: -50.0,
+    }
+  }
+}
+
+impl Weights {
+  /// The weights in the order of `WEIGHT_NAMES`.
+  pub fn to_vec(&self) -> Vec<f64> {
+    vec![
+      self.reveal,
+      self.hidden_depth,
+      self.empty_pile,
+      self.foundation,
+      self.foundation_delay,
+      self.deck_to_pile,
+      self.pile_to_pile,
+      self.from_foundation,
+    ]
+  }
+
+  /// Weights from values in the order of `WEIGHT_NAMES`.
+  pub fn from_slice(values: &[f64]) -> Weights {
+    assert_eq!(values.len(), WEIGHT_NAMES.len(), "expected {} weights", WEIGHT_NAMES.len());
+
+    Weights {
+      reveal: values[0],
+      hidden_depth: values[1],
+      empty_pile: values[2],
+      foundation: values[3],
+      foundation_delay: values[4],
+      deck_to_pile: values[5],
+      pile_to_pile: values[6],
+      from_foundation: values[7],
+    }
+  }
+
+  /// Set the weight called `name`.
+  pub fn set(&mut self, name: &str, value: f64) -> Result<(), String> {
+    let index = WEIGHT_NAMES.iter().position(|n| *n == name)
+      .ok_or_else(|| format!("unknown weight {}, expected one of {}", name, WEIGHT_NAMES.join(", ")))?;
+    let mut values = self.to_vec();
+    values[index] = value;
+    *self = Weights::from_slice(&values);
+    Ok(())
+  }
+}
+
+impl fmt::Display for Weights {
+  /// The weights as `name=value` pairs separated by commas, which `FromStr`
+  /// reads back.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let pairs: Vec<String> = WEIGHT_NAMES.iter().zip(self.to_vec())
+      .map(|(name, value)| format!("{}={}", name, value))
+      .collect();
+    write!(f, "{}", pairs.join(","))
+  }
+}
+
+impl FromStr for Weights {
+  type Err = String;
+
+  /// Parse `name=value` pairs separated by commas or lines, the weights not
+  /// given keeping their default, so both `reveal=50,pile_to_pile=-2` and a
+  /// TOML file of `name = value` lines work. `#` starts a comment.
+  fn from_str(s: &str) -> Result<Weights, String> {
+    let mut weights = Weights::default();
+
+    for line in s.lines() {
+      let line = line.split('#').next().unwrap();
+      for pair in line.split(',').map(|p| p.trim()).filter(|p| ! p.is_empty()) {
+        let mut parts = pair.splitn(2, '=');
+        let name = parts.next().unwrap().trim();
+        let value = parts.next().ok_or_else(|| format!("expected name=value, got {}", pair))?.trim();
+        let value = value.parse::<f64>().map_err(|_| format!("invalid value of {}: {}", name, value))?;
+        weights.set(name, value)?;
+      }
+    }
+
+    Ok(weights)
+  }
+}
+
+/// Plays the legal move with the highest score by its `Weights`, drawing
+/// when no move scores above zero.
+///
+/// Moves back to a position the game was already in are not played, and the
+/// strategy gives up when nothing else is left or after `STALL_LIMIT` moves
+/// that neither found nor turn up a card, so any weights end every game.
+pub struct WeightedStrategy {
+  weights: Weights,
+}
+
+impl WeightedStrategy {
+  pub fn with_weights(weights: Weights) -> WeightedStrategy {
+    WeightedStrategy {
+      weights: weights,
+    }
+  }
+
+  pub fn weights(&self) -> &Weights {
+    &self.weights
+  }
+
+  /// Score of playing `m` in `game`, which leads to `next`.
+  fn score(&self, game: &KlondikeSolitaireGame, m: Move, next: &KlondikeSolitaireGame) -> f64 {
+    let w = &self.weights;
+    let (source, target) = match m {
+      Move::Draw => return 0.0,
+      Move::Cards(source, target) => (source, target),
+    };
+
+    let mut score = match (source, target) {
+      (MoveSource::Foundation(_), _) => w.from_foundation,
+      (_, MoveTarget::Foundation) => w.foundation - w.foundation_delay * foundation_lead(game, source) as f64,
+      (MoveSource::Deck, MoveTarget::Pile(_)) => w.deck_to_pile,
+      (_, MoveTarget::Pile(_)) => w.pile_to_pile,
+    };
+
+    if let Some(i) = source.pile() {
+      let hidden = game.pile(i).hidden_cards().len();
+      if next.pile(i).hidden_cards().len() < hidden {
+        score += w.reveal + w.hidden_depth * hidden as f64;
+      }
+    }
+
+    score + w.empty_pile * (empty_piles(next) as f64 - empty_piles(game) as f64)
+  }
+}
+
+impl GameStrategy for WeightedStrategy {
+  fn new() -> WeightedStrategy {
+    WeightedStrategy::with_weights(Weights::default())
+  }
+
+  fn play(&mut self, game: &mut KlondikeSolitaireGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.state_hash());
+    let mut best_progress = progress(game);
+    let mut stalled = 0;
+
+    while ! game.is_clear() && stalled < STALL_LIMIT {
+      let mut best: Option<(f64, Move, u64)> = None;
+
+      for m in game.legal_moves() {
+        let mut next = game.clone();
+        next.apply(m).unwrap();
+        if seen.contains(&next.state_hash()) {
+          continue;
+        }
+
+        // Draws score 0, so a move is only played instead of drawing if it
+        // scores above that.
+        let score = if m == Move::Draw { 0.0 } else { self.score(game, m, &next) };
+        if best.map_or(true, |b| score > b.0) && (m == Move::Draw || score > 0.0) {
+          best = Some((score, m, next.state_hash()));
+        }
+      }
+
+      match best {
+        Some((_, m, hash)) => {
+          seen.insert(hash);
+          game.apply(m).unwrap();
+        },
+        None => break,
+      }
+
+      let p = progress(game);
+      if p > best_progress {
+        best_progress = p;
+        stalled = 0;
+      } else {
+        stalled += 1;
+      }
+    }
+  }
+}
+
+/// Ranks the card `source` would found is ahead of the lower foundation of
+/// the other color, 0 for aces, twos and the cards that no card still in
+/// play could go on.
+fn foundation_lead(game: &KlondikeSolitaireGame, source: MoveSource) -> usize {
+  let card = match source {
+    MoveSource::Deck => game.deck().top(),
+    MoveSource::Pile(i) | MoveSource::PileRun { pile: i, .. } => game.pile(i as usize).top(),
+    MoveSource::Foundation(_) => None,
+  };
+
+  card.map_or(0, |card| {
+    let other = [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs].iter()
+      .filter(|suit| suit.color() != card.color())
+      .map(|suit| game.foundation(*suit).len())
+      .min()
+      .unwrap();
+    (card.rank().value() as usize).saturating_sub(cmp::max(other + 1, 2))
+  })
+}
+
+fn empty_piles(game: &KlondikeSolitaireGame) -> usize {
+  game.piles().iter().filter(|p| p.is_empty()).count()
+}
+
+/// Cards founded or turned up so far.
+fn progress(game: &KlondikeSolitaireGame) -> usize {
+  let founded: usize = [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs].iter()
+    .map(|suit| game.foundation(*suit).len())
+    .sum();
+  let hidden: usize = game.piles().iter().map(|p| p.hidden_cards().len()).sum();
+  founded + 21 - hidden
+}
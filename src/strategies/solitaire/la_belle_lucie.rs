@@ -0,0 +1,67 @@
+use games::solitaire::klondike::Card;
+use games::solitaire::la_belle_lucie::*;
+use std::collections::HashSet;
+
+/// Most moves played in one game, a guard against wandering between
+/// positions that all look equally good.
+const MAX_MOVES: usize = 1000;
+
+/// Greedy La Belle Lucie strategy: move a card to its foundation whenever
+/// possible, otherwise play the move that leaves the fewest cards on top of
+/// the next card each foundation needs, and redeal once no move helps.
+/// Positions already seen are never played again.
+pub struct SimpleLaBelleLucieStrategy;
+
+impl SimpleLaBelleLucieStrategy {
+  pub fn new() -> SimpleLaBelleLucieStrategy {
+    SimpleLaBelleLucieStrategy
+  }
+
+  pub fn play(&mut self, game: &mut LaBelleLucieGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.notation());
+
+    while ! game.is_clear() && game.moves_made() < MAX_MOVES {
+      let moves = game.legal_moves();
+      let current = buried(game);
+
+      let next = moves.iter().cloned()
+        .find(|m| match *m { LaBelleLucieMove::Found(_) => true, _ => false })
+        .or_else(|| {
+          moves.iter().cloned()
+            .filter(|m| *m != LaBelleLucieMove::Redeal)
+            .map(|m| (m, after(game, m)))
+            .filter(|&(_, ref next)| buried(next) <= current && ! seen.contains(&next.notation()))
+            .min_by_key(|&(_, ref next)| buried(next))
+            .map(|(m, _)| m)
+        })
+        .or_else(|| moves.iter().cloned().find(|m| *m == LaBelleLucieMove::Redeal));
+
+      match next {
+        Some(m) => {
+          game.apply(m).unwrap();
+          seen.insert(game.notation());
+        },
+        None => break,
+      }
+    }
+  }
+}
+
+fn after(game: &LaBelleLucieGame, m: LaBelleLucieMove) -> LaBelleLucieGame {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+  next
+}
+
+/// Cards on top of the next card of each foundation.
+fn buried(game: &LaBelleLucieGame) -> usize {
+  let wanted: Vec<Card> = game.foundations().iter().filter_map(|f| f.next_card()).collect();
+
+  game.fans().iter().map(|f| {
+    match f.iter().position(|card| wanted.contains(card)) {
+      Some(i) => f.len() - i - 1,
+      None => 0,
+    }
+  }).sum()
+}
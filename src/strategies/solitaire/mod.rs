@@ -1 +1,12 @@
+pub mod accordion;
+pub mod bakers_dozen;
+pub mod double_klondike;
+pub mod easthaven;
+pub mod freecell;
+pub mod golf;
 pub mod klondike;
+pub mod la_belle_lucie;
+pub mod pyramid;
+pub mod scorpion;
+pub mod spider;
+pub mod yukon;
@@ -0,0 +1,108 @@
+use games::solitaire::pyramid::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// Which removal the simple pyramid strategy plays when there is more than
+/// one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Pairing {
+  /// The first legal removal, pyramid cards by index, then the waste.
+  First,
+  /// Removals of pyramid cards only before the ones using the waste, to keep
+  /// the waste card for a pyramid card it may free later.
+  Pyramid,
+  /// The removal that frees the most pyramid cards, then the one higher up
+  /// the pyramid.
+  Uncover,
+}
+
+pub static PAIRINGS: &'static [&'static str] = &["first", "pyramid", "uncover"];
+
+impl fmt::Display for Pairing {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Pairing::First => write!(f, "first"),
+      Pairing::Pyramid => write!(f, "pyramid"),
+      Pairing::Uncover => write!(f, "uncover"),
+    }
+  }
+}
+
+impl FromStr for Pairing {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Pairing, String> {
+    match s {
+      "first" => Ok(Pairing::First),
+      "pyramid" => Ok(Pairing::Pyramid),
+      "uncover" => Ok(Pairing::Uncover),
+      _ => Err(format!("unknown pairing {}, expected one of {}", s, PAIRINGS.join(", "))),
+    }
+  }
+}
+
+/// Pyramid strategy that removes cards whenever it can, choosing among the
+/// removals with a `Pairing` heuristic, and draws from the stock otherwise.
+pub struct SimplePyramidStrategy {
+  pairing: Pairing,
+}
+
+impl SimplePyramidStrategy {
+  pub fn new(pairing: Pairing) -> SimplePyramidStrategy {
+    SimplePyramidStrategy {
+      pairing: pairing,
+    }
+  }
+
+  pub fn play(&mut self, game: &mut PyramidGame) {
+    while ! game.is_clear() {
+      let moves = game.legal_moves();
+      let mut removals = moves.iter().cloned().filter(|m| *m != PyramidMove::Draw && *m != PyramidMove::Redeal);
+
+      let next = match self.pairing {
+        Pairing::First => removals.next(),
+        Pairing::Pyramid => removals.min_by_key(|m| uses_waste(*m)),
+        Pairing::Uncover => removals.max_by_key(|m| (freed(game, *m), -(lowest_index(*m) as i32))),
+      };
+
+      let next = next
+        .or_else(|| moves.iter().cloned().find(|m| *m == PyramidMove::Draw))
+        .or_else(|| moves.iter().cloned().find(|m| *m == PyramidMove::Redeal));
+
+      match next {
+        Some(m) => game.apply(m).unwrap(),
+        None => break,
+      }
+    }
+  }
+}
+
+fn uses_waste(m: PyramidMove) -> bool {
+  match m {
+    PyramidMove::King(p) => p == Place::Waste,
+    PyramidMove::Pair(a, b) => a == Place::Waste || b == Place::Waste,
+    _ => false,
+  }
+}
+
+/// Smallest pyramid index removed by `m`, the highest card up.
+fn lowest_index(m: PyramidMove) -> usize {
+  let index = |p: Place| match p {
+    Place::Pyramid(i) => i as usize,
+    Place::Waste => PYRAMID_SIZE,
+  };
+
+  match m {
+    PyramidMove::King(p) => index(p),
+    PyramidMove::Pair(a, b) => ::std::cmp::min(index(a), index(b)),
+    _ => PYRAMID_SIZE,
+  }
+}
+
+/// Number of pyramid cards that `m` frees.
+fn freed(game: &PyramidGame, m: PyramidMove) -> usize {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+
+  (0..PYRAMID_SIZE).filter(|&i| next.is_free(i) && ! game.is_free(i)).count()
+}
@@ -0,0 +1,76 @@
+use games::solitaire::scorpion::*;
+use std::collections::HashSet;
+
+/// Most moves played in one game, a guard against wandering between
+/// positions that all look equally good.
+const MAX_MOVES: usize = 1000;
+
+/// Greedy Scorpion strategy: play the move that turns up a hidden card or
+/// breaks a card away from one it is not built on, never split a card from
+/// the one it is built on, and deal the reserve once nothing else helps.
+/// Positions already seen are never played again.
+pub struct SimpleScorpionStrategy;
+
+impl SimpleScorpionStrategy {
+  pub fn new() -> SimpleScorpionStrategy {
+    SimpleScorpionStrategy
+  }
+
+  pub fn play(&mut self, game: &mut ScorpionGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.notation());
+
+    while ! game.is_clear() && game.moves_made() < MAX_MOVES {
+      let mut moves: Vec<(i32, ScorpionMove)> = game.legal_moves().into_iter()
+        .filter(|m| *m != ScorpionMove::Reserve)
+        .map(|m| (rate(game, m), m))
+        .filter(|&(rating, _)| rating > 0)
+        .collect();
+      moves.sort_by(|a, b| b.0.cmp(&a.0));
+
+      let next = moves.iter()
+        .map(|&(_, m)| m)
+        .find(|m| ! seen.contains(&after(game, *m)))
+        .or_else(|| if game.reserve().is_empty() { None } else { Some(ScorpionMove::Reserve) });
+
+      match next {
+        Some(m) => {
+          game.apply(m).unwrap();
+          seen.insert(game.notation());
+        },
+        None => break,
+      }
+    }
+  }
+}
+
+fn after(game: &ScorpionGame, m: ScorpionMove) -> String {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+  next.notation()
+}
+
+/// How much a card move improves the game, positive if it is worth playing.
+fn rate(game: &ScorpionGame, m: ScorpionMove) -> i32 {
+  let (source, index, target) = match m {
+    ScorpionMove::Cards { source, index, target } => (game.pile(source as usize), index as usize, game.pile(target as usize)),
+    ScorpionMove::Reserve => return 0,
+  };
+
+  if index == 0 {
+    if ! source.hidden_cards().is_empty() {
+      10
+    } else if target.is_empty() {
+      // A king already at the bottom of its pile
+      0
+    } else {
+      6
+    }
+  } else if builds_on(source.visible_cards()[index], source.visible_cards()[index - 1]) {
+    -8
+  } else if target.is_empty() {
+    3
+  } else {
+    5
+  }
+}
@@ -0,0 +1,95 @@
+use games::solitaire::spider::*;
+use std::collections::HashSet;
+
+/// Most moves played in one game, a guard against wandering between
+/// positions that all look equally good.
+const MAX_MOVES: usize = 2000;
+
+/// Greedy Spider strategy: play the best looking card move, preferring builds
+/// in suit and moves that turn a hidden card, and deal only when no card move
+/// improves the tableau. Positions already seen are never played again.
+pub struct SimpleSpiderStrategy;
+
+impl SimpleSpiderStrategy {
+  pub fn new() -> SimpleSpiderStrategy {
+    SimpleSpiderStrategy
+  }
+
+  pub fn play(&mut self, game: &mut SpiderGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.notation());
+
+    while ! game.is_clear() && game.moves_made() < MAX_MOVES {
+      let mut moves: Vec<(i32, SpiderMove)> = game.legal_moves().into_iter()
+        .filter(|m| *m != SpiderMove::Deal)
+        .map(|m| (rate(game, m), m))
+        .collect();
+      moves.sort_by(|a, b| b.0.cmp(&a.0));
+
+      let good = moves.iter()
+        .filter(|&&(rating, _)| rating > 0)
+        .map(|&(_, m)| m)
+        .find(|m| ! seen.contains(&after(game, *m)));
+
+      let next = match good {
+        Some(m) => Some(m),
+        None if game.can_deal().is_ok() => Some(SpiderMove::Deal),
+        // Fill the empty piles so the stock can be dealt
+        None => moves.iter().map(|&(_, m)| m).find(|m| ! seen.contains(&after(game, *m))),
+      };
+
+      match next {
+        Some(m) => {
+          game.apply(m).unwrap();
+          seen.insert(game.notation());
+        },
+        None => break,
+      }
+    }
+  }
+}
+
+fn after(game: &SpiderGame, m: SpiderMove) -> String {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+  next.notation()
+}
+
+/// How much a card move improves the tableau, positive if it is worth playing
+/// before dealing.
+fn rate(game: &SpiderGame, m: SpiderMove) -> i32 {
+  let (source, count, target) = match m {
+    SpiderMove::Cards { source, count, target } => (game.pile(source as usize), count as usize, game.pile(target as usize)),
+    SpiderMove::Deal => return 0,
+  };
+
+  let visible = source.visible_cards();
+  let moved = visible[visible.len() - count];
+  let mut rating = 0;
+
+  match target.top() {
+    Some(top) if top.suit() == moved.suit() => rating += 10,
+    Some(_) => (),
+    None => rating -= 2,
+  }
+
+  if count == visible.len() {
+    if ! source.hidden_cards().is_empty() {
+      rating += 5;
+    } else if ! target.is_empty() {
+      rating += 3;
+    }
+  } else {
+    let below = visible[visible.len() - count - 1];
+
+    if count < source.run_len() {
+      // Splits a run of one suit
+      rating -= 8;
+    } else if builds_on(moved, below) {
+      // Already built on a card of the right rank
+      rating -= 4;
+    }
+  }
+
+  rating
+}
@@ -0,0 +1,86 @@
+use games::solitaire::yukon::*;
+use std::collections::HashSet;
+
+/// Most moves played in one game, a guard against wandering between
+/// positions that all look equally good.
+const MAX_MOVES: usize = 1000;
+
+/// Greedy Yukon strategy: play foundation moves first, then the pile move
+/// that turns up a hidden card or empties a pile, and never split a card
+/// from the one it is already built on. Positions already seen are never
+/// played again.
+pub struct SimpleYukonStrategy;
+
+impl SimpleYukonStrategy {
+  pub fn new() -> SimpleYukonStrategy {
+    SimpleYukonStrategy
+  }
+
+  pub fn play(&mut self, game: &mut YukonGame) {
+    let mut seen = HashSet::new();
+    seen.insert(game.notation());
+
+    while ! game.is_clear() && game.moves_made() < MAX_MOVES {
+      let mut moves: Vec<(i32, YukonMove)> = game.legal_moves().into_iter()
+        .map(|m| (rate(game, m), m))
+        .filter(|&(rating, _)| rating > 0)
+        .collect();
+      moves.sort_by(|a, b| b.0.cmp(&a.0));
+
+      let next = moves.iter()
+        .map(|&(_, m)| m)
+        .find(|m| ! seen.contains(&after(game, *m)));
+
+      match next {
+        Some(m) => {
+          game.apply(m).unwrap();
+          seen.insert(game.notation());
+        },
+        None => break,
+      }
+    }
+  }
+}
+
+fn after(game: &YukonGame, m: YukonMove) -> String {
+  let mut next = game.clone();
+  next.apply(m).unwrap();
+  next.notation()
+}
+
+/// How much a move improves the game, positive if it is worth playing.
+fn rate(game: &YukonGame, m: YukonMove) -> i32 {
+  let (source, index, target) = match m {
+    YukonMove::Found(_) => return 20,
+    YukonMove::Pile { source, index, target } => (game.pile(source as usize), index as usize, game.pile(target as usize)),
+  };
+
+  let mut rating = 1;
+
+  if index == 0 {
+    if ! source.hidden_cards().is_empty() {
+      rating += 10;
+    } else if target.is_empty() {
+      // Moves a whole pile to another empty pile
+      return 0;
+    } else {
+      rating += 5;
+    }
+  } else {
+    let below = source.visible_cards()[index - 1];
+    let moved = source.visible_cards()[index];
+
+    if moved.color() != below.color() && moved.rank().value() + 1 == below.rank().value() {
+      // Already built on the card below
+      rating -= 4;
+    } else {
+      rating += 2;
+    }
+  }
+
+  if target.is_empty() {
+    rating -= 1;
+  }
+
+  rating
+}
@@ -0,0 +1,192 @@
+use games::spades::*;
+use cards::french::{Rank, Suit};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+/// Names accepted by `spades_strategy`.
+pub static SPADES_STRATEGIES: &'static [&'static str] = &["simple", "random"];
+
+/// Bids and plays the cards of one seat at a spades table, seeing the deal
+/// only through the `SpadesView` of its seat.
+pub trait SpadesStrategy {
+  /// Tricks to bid on `view.hand()`, `NIL` for nil.
+  fn bid(&mut self, view: &SpadesView) -> u8;
+
+  /// One of `legal`, the cards the player may play to the current trick.
+  /// Never called without cards.
+  fn play(&mut self, view: &SpadesView, legal: &[Card]) -> Card;
+}
+
+/// Strategy by name, one of `SPADES_STRATEGIES`. Random strategies draw from
+/// `seed`.
+pub fn spades_strategy(name: &str, seed: u64) -> Option<Box<dyn SpadesStrategy>> {
+  match name {
+    "simple" => Some(Box::new(SimpleSpadesStrategy)),
+    "random" => Some(Box::new(RandomSpadesStrategy::new(seed))),
+    _ => None,
+  }
+}
+
+/// Bid and play `game` to the end with `players[p]` in seat `p`.
+pub fn play_deal(game: &mut SpadesGame, players: &mut [Box<dyn SpadesStrategy>]) {
+  assert_eq!(players.len(), NUM_PLAYERS);
+
+  while game.is_bidding() {
+    let seat = game.to_bid();
+    let bid = players[seat].bid(&game.view(seat));
+    game.bid(bid).expect("invalid bid");
+  }
+
+  while ! game.is_over() {
+    let seat = game.to_play();
+    let legal = game.legal_plays();
+    let card = players[seat].play(&game.view(seat), &legal);
+    game.play(card).expect("illegal play");
+  }
+}
+
+/// Play a match of `rules` with `players[p]` in seat `p`, dealing with
+/// `shuffle(deal, cards)`, until a partnership wins or `max_deals` deals
+/// have been played. Returns the finished match.
+pub fn play_match<F>(rules: SpadesRules, players: &mut [Box<dyn SpadesStrategy>], max_deals: usize, mut shuffle: F) -> SpadesMatch
+  where F: FnMut(usize, &mut Vec<Card>) {
+  let mut m = SpadesMatch::new(rules);
+
+  while m.winner().is_none() && m.deals() < max_deals {
+    let deal = m.deals();
+    let mut game = SpadesGame::new(m.dealer(), |c| shuffle(deal, c));
+    play_deal(&mut game, players);
+    m.record(&game);
+  }
+
+  m
+}
+
+/// Bid one to four tricks and play uniformly at random.
+pub struct RandomSpadesStrategy {
+  rng: Isaac64Rng,
+}
+
+impl RandomSpadesStrategy {
+  pub fn new(seed: u64) -> RandomSpadesStrategy {
+    RandomSpadesStrategy {
+      rng: Isaac64Rng::from_seed(&[seed]),
+    }
+  }
+}
+
+impl SpadesStrategy for RandomSpadesStrategy {
+  fn bid(&mut self, _view: &SpadesView) -> u8 {
+    self.rng.gen_range(1, 5)
+  }
+
+  fn play(&mut self, _view: &SpadesView, legal: &[Card]) -> Card {
+    *self.rng.choose(legal).unwrap()
+  }
+}
+
+/// Bid the sure tricks of the hand, nil on a hand without high cards, and
+/// play to make the bid: win tricks cheaply while the partnership is short,
+/// and duck once it has made its bid or when bidding nil.
+pub struct SimpleSpadesStrategy;
+
+/// Tricks `hand` can be expected to take, in quarters.
+fn quarter_tricks(hand: &[Card]) -> u32 {
+  let mut quarters = 0;
+
+  for suit in Suit::standard_iter() {
+    let cards: Vec<&Card> = hand.iter().filter(|c| c.suit() == *suit).collect();
+    let len = cards.len();
+
+    for card in &cards {
+      quarters += match (card.rank(), *suit == Suit::Spades) {
+        (Rank::Ace, _) => 4,
+        (Rank::King, true) => 4,
+        (Rank::King, false) if len >= 2 && len <= 5 => 3,
+        (Rank::Queen, true) => 3,
+        (Rank::Queen, false) if len >= 3 && len <= 4 => 1,
+        _ => 0,
+      };
+    }
+
+    if *suit == Suit::Spades && len > 3 {
+      quarters += 4 * (len as u32 - 3);
+    }
+  }
+
+  quarters
+}
+
+fn value(card: &Card) -> u8 {
+  card.rank().ace_high_value()
+}
+
+/// Whether `card` would take the trick from `winning`.
+fn beats(card: &Card, winning: &Card) -> bool {
+  if card.suit() == winning.suit() {
+    value(card) > value(winning)
+  } else {
+    card.suit() == Suit::Spades
+  }
+}
+
+impl SpadesStrategy for SimpleSpadesStrategy {
+  fn bid(&mut self, view: &SpadesView) -> u8 {
+    let hand = view.hand();
+    let tricks = (quarter_tricks(hand) + 2) / 4;
+    let high = hand.iter().any(|c| value(c) >= 12 || (c.suit() == Suit::Spades && value(c) >= 10));
+
+    if tricks == 0 && ! high {
+      NIL
+    } else {
+      ::std::cmp::max(tricks, 1) as u8
+    }
+  }
+
+  fn play(&mut self, view: &SpadesView, legal: &[Card]) -> Card {
+    let player = view.player();
+    let partner = view.partner();
+    let nil = view.bid_of(player) == Some(NIL);
+    let bid: usize = [player, partner].iter()
+      .filter_map(|p| view.bid_of(*p))
+      .map(|b| b as usize)
+      .sum();
+    let taken: usize = [player, partner].iter()
+      .filter(|p| view.bid_of(**p) != Some(NIL))
+      .map(|p| view.taken(*p))
+      .sum();
+    let wants_tricks = ! nil && taken < bid;
+
+    let lowest = |cards: &mut dyn Iterator<Item = &Card>| cards.min_by_key(|c| (c.suit() == Suit::Spades, value(c))).cloned();
+
+    let trick = view.trick();
+    let (winner, winning) = match trick.winner(Some(Suit::Spades)) {
+      Some(w) => w,
+      None => {
+        // Lead an ace when short of tricks, and the lowest card otherwise.
+        let ace = legal.iter().find(|c| c.rank() == Rank::Ace && c.suit() != Suit::Spades);
+        return match ace {
+          Some(ace) if wants_tricks => *ace,
+          _ => lowest(&mut legal.iter()).unwrap(),
+        };
+      },
+    };
+
+    let partner_winning = winner == partner && view.bid_of(partner) != Some(NIL);
+
+    if wants_tricks && ! partner_winning {
+      if let Some(card) = lowest(&mut legal.iter().filter(|c| beats(c, &winning))) {
+        return card;
+      }
+    }
+
+    if ! wants_tricks {
+      // Duck with the highest card that loses.
+      let ducks = legal.iter().filter(|c| ! beats(c, &winning));
+      if let Some(card) = ducks.max_by_key(|c| (c.suit() != Suit::Spades, c.suit() == winning.suit(), value(c))) {
+        return *card;
+      }
+    }
+
+    lowest(&mut legal.iter()).unwrap()
+  }
+}
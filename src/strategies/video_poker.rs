@@ -0,0 +1,140 @@
+use games::video_poker::*;
+
+/// Names accepted by `video_poker_strategy`.
+pub static VIDEO_POKER_STRATEGIES: &'static [&'static str] = &["simple", "optimal"];
+
+/// Chooses the cards to hold of a dealt video poker hand.
+pub trait VideoPokerStrategy {
+  /// Cards of `cards`, the five dealt, to hold when playing `table`.
+  fn hold(&mut self, cards: &[Card], table: &PayTable) -> Hold;
+}
+
+/// Strategy by name, one of `VIDEO_POKER_STRATEGIES`.
+pub fn video_poker_strategy(name: &str) -> Option<Box<dyn VideoPokerStrategy>> {
+  match name {
+    "simple" => Some(Box::new(SimpleVideoPokerStrategy)),
+    "optimal" => Some(Box::new(OptimalVideoPokerStrategy)),
+    _ => None,
+  }
+}
+
+/// Hold whatever pays the most on average over every draw, by `best_hold`.
+/// Exact but slow, a few hands a second.
+pub struct OptimalVideoPokerStrategy;
+
+impl VideoPokerStrategy for OptimalVideoPokerStrategy {
+  fn hold(&mut self, cards: &[Card], table: &PayTable) -> Hold {
+    best_hold(cards, table).0
+  }
+}
+
+/// The simplified Jacks or Better strategy: hold the first of a ranked list
+/// of hands and draws found in the deal, from made hands down to a single
+/// high card. Returns within a few tenths of a percent of optimal play on the
+/// 9/6 table.
+pub struct SimpleVideoPokerStrategy;
+
+fn value(card: &Card) -> u8 {
+  card.rank().ace_high_value()
+}
+
+fn is_high(card: &Card) -> bool {
+  value(card) >= 11
+}
+
+fn suited(cards: &[Card]) -> bool {
+  cards.iter().all(|c| c.suit() == cards[0].suit())
+}
+
+fn same_rank(cards: &[Card]) -> bool {
+  cards.iter().all(|c| c.rank() == cards[0].rank())
+}
+
+fn is_two_pair(cards: &[Card]) -> bool {
+  let mut values: Vec<u8> = cards.iter().map(value).collect();
+  values.sort();
+  values.len() == 4 && values[0] == values[1] && values[2] == values[3] && values[1] != values[2]
+}
+
+fn to_royal(cards: &[Card]) -> bool {
+  suited(cards) && cards.iter().all(|c| value(c) >= 10)
+}
+
+/// Whether `cards` are of distinct ranks that fit in one straight, the ace
+/// high or low.
+fn to_straight(cards: &[Card]) -> bool {
+  let mut values: Vec<u8> = cards.iter().map(value).collect();
+  values.sort();
+  values.dedup();
+  if values.len() < cards.len() {
+    return false;
+  }
+
+  // An ace counting low fits with cards up to five.
+  let high = values[values.len() - 1];
+  let below_ace = if values.len() > 1 { values[values.len() - 2] } else { 1 };
+  high - values[0] <= 4 || (high == 14 && below_ace <= 5)
+}
+
+/// Four ranks in a row that a card at either end makes a straight with.
+fn outside_straight(cards: &[Card]) -> bool {
+  let mut values: Vec<u8> = cards.iter().map(value).collect();
+  values.sort();
+  values.dedup();
+  values.len() == 4 && values[3] - values[0] == 3 && values[3] < 14
+}
+
+/// Holds of `size` cards of `cards` whose cards satisfy `rule`, with the sum
+/// of the values of their cards.
+fn matching<F>(cards: &[Card], size: usize, rule: F) -> Vec<(u32, Hold)>
+  where F: Fn(&[Card]) -> bool {
+  holds().into_iter()
+    .filter(|hold| hold.iter().filter(|h| **h).count() == size)
+    .filter_map(|hold| {
+      let held: Vec<Card> = cards.iter().zip(hold.iter()).filter(|&(_, h)| *h).map(|(c, _)| *c).collect();
+      if rule(&held) {
+        Some((held.iter().map(|c| value(c) as u32).sum(), hold))
+      } else {
+        None
+      }
+    })
+    .collect()
+}
+
+impl VideoPokerStrategy for SimpleVideoPokerStrategy {
+  fn hold(&mut self, cards: &[Card], _table: &PayTable) -> Hold {
+    let pat = [true; HAND_LEN];
+    let made = classify(cards);
+    if made >= Some(Win::FourOfAKind) {
+      return pat;
+    }
+
+    // Each draw by the number of cards held, the best first.
+    let rules: Vec<(usize, Box<dyn Fn(&[Card]) -> bool>)> = vec![
+      (4, Box::new(to_royal)),
+      (5, Box::new(move |_: &[Card]| made >= Some(Win::Straight))),
+      (3, Box::new(same_rank)),
+      (4, Box::new(|c: &[Card]| suited(c) && to_straight(c))),
+      (4, Box::new(is_two_pair)),
+      (2, Box::new(|c: &[Card]| same_rank(c) && is_high(&c[0]))),
+      (3, Box::new(to_royal)),
+      (4, Box::new(suited)),
+      (2, Box::new(same_rank)),
+      (4, Box::new(outside_straight)),
+      (2, Box::new(|c: &[Card]| suited(c) && c.iter().all(is_high))),
+      (3, Box::new(|c: &[Card]| suited(c) && to_straight(c))),
+      (2, Box::new(|c: &[Card]| c.iter().all(is_high))),
+      (2, Box::new(|c: &[Card]| suited(c) && c.iter().any(|c| value(c) == 10) && c.iter().any(|c| is_high(c) && value(c) < 14))),
+      (1, Box::new(|c: &[Card]| is_high(&c[0]))),
+    ];
+
+    for (size, rule) in rules {
+      // The lowest cards of a kind leave the most straights open.
+      if let Some(&(_, hold)) = matching(cards, size, rule).iter().min_by_key(|&&(sum, _)| sum) {
+        return hold;
+      }
+    }
+
+    [false; HAND_LEN]
+  }
+}
@@ -0,0 +1,75 @@
+//! Idle time between games so long runs leave the machine usable.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Duty cycle for a worker thread: after doing some work the worker sleeps
+/// long enough that it is busy only `busy_percent` of the time.
+///
+/// Idle time is accumulated and slept in chunks of at least `MIN_SLEEP`, so
+/// very short games are throttled accurately. Each worker needs its own copy.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Throttle {
+  busy_percent: u32,
+  owed: Duration,
+}
+
+const MIN_SLEEP_MILLIS: u64 = 1;
+
+impl Throttle {
+  pub fn new(busy_percent: u32) -> Throttle {
+    assert!(busy_percent > 0 && busy_percent <= 100, "busy percent must be between 1 and 100");
+
+    Throttle {
+      busy_percent: busy_percent,
+      owed: Duration::from_millis(0),
+    }
+  }
+
+  pub fn busy_percent(&self) -> u32 {
+    self.busy_percent
+  }
+
+  /// Idle time that balances `worked`.
+  pub fn idle_time(&self, worked: Duration) -> Duration {
+    let idle_percent = (100 - self.busy_percent) as u64;
+    let nanos = worked.as_secs() * 1_000_000_000 + worked.subsec_nanos() as u64;
+    let idle = nanos * idle_percent / self.busy_percent as u64;
+
+    Duration::new(idle / 1_000_000_000, (idle % 1_000_000_000) as u32)
+  }
+
+  /// Sleep to balance the work done since `started`.
+  pub fn pause(&mut self, started: Instant) {
+    if self.busy_percent == 100 {
+      return;
+    }
+
+    self.owed += self.idle_time(started.elapsed());
+
+    if self.owed >= Duration::from_millis(MIN_SLEEP_MILLIS) {
+      let slept = Instant::now();
+      thread::sleep(self.owed);
+      self.owed = self.owed.checked_sub(slept.elapsed()).unwrap_or(Duration::from_millis(0));
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::time::Duration;
+
+  #[test]
+  fn idle_time() {
+    assert_eq!(Throttle::new(100).idle_time(Duration::from_millis(30)), Duration::from_millis(0));
+    assert_eq!(Throttle::new(50).idle_time(Duration::from_millis(30)), Duration::from_millis(30));
+    assert_eq!(Throttle::new(25).idle_time(Duration::from_millis(30)), Duration::from_millis(90));
+  }
+
+  #[test]
+  #[should_panic]
+  fn zero_busy() {
+    Throttle::new(0);
+  }
+}
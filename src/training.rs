@@ -0,0 +1,88 @@
+//! Export of (state, move, outcome) examples for training policies outside of
+//! the simulator.
+//!
+//! Examples are written as CSV with one row per move:
+//!
+//! ```text
+//! game,ply,<features::klondike::feature_names()...>,move,won,founded
+//! ```
+//!
+//! `move` is formatted with `Move`'s `Display`, `won` is 0 or 1 and `founded`
+//! is the number of cards on the foundations when the game ended. Every row of
+//! a game has the same outcome.
+
+use features::klondike as features;
+use games::solitaire::klondike::{KlondikeSolitaireGame, Move};
+use games::solitaire::replay::Replay;
+use cards::french::Suit;
+use std::io::{self, Write};
+
+pub fn write_header<W: Write>(out: &mut W) -> io::Result<()> {
+  writeln!(out, "game,ply,{},move,won,founded", features::feature_names().join(","))
+}
+
+/// Write one row for each of `moves`, replayed from `initial`.
+///
+/// Panics if a move is not legal in the replayed game.
+pub fn write_game<W: Write>(out: &mut W, game_id: u64, initial: &KlondikeSolitaireGame, moves: &[Move]) -> io::Result<()> {
+  let mut replay = Replay::new(initial.clone(), moves.to_vec());
+  let mut rows = Vec::with_capacity(moves.len());
+
+  for _ in moves {
+    rows.push(features::extract(replay.game()));
+    replay.step().unwrap().expect("recorded move is not legal");
+  }
+  let game = replay.game();
+
+  let won = if game.is_clear() { 1 } else { 0 };
+  let founded: usize = Suit::standard_iter().map(|s| game.foundation(*s).len()).sum();
+
+  for (ply, (row, m)) in rows.iter().zip(moves).enumerate() {
+    write!(out, "{},{}", game_id, ply)?;
+
+    for value in row {
+      write!(out, ",{}", value)?;
+    }
+
+    writeln!(out, ",{},{},{}", m, won, founded)?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use games::solitaire::klondike::{MoveSource, MoveTarget};
+  use features::klondike::FEATURE_COUNT;
+
+  #[test]
+  fn write_rows() {
+    let game = KlondikeSolitaireGame::new(1);
+    let moves = [Move::Draw, Move::Draw];
+
+    let mut out = Vec::new();
+    write_header(&mut out).unwrap();
+    write_game(&mut out, 7, &game, &moves).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 3);
+
+    for line in &lines {
+      assert_eq!(line.split(',').count(), FEATURE_COUNT + 5);
+    }
+
+    assert!(lines[1].starts_with("7,0,"));
+    assert!(lines[2].starts_with("7,1,"));
+    assert!(lines[2].ends_with(",draw,0,0"));
+  }
+
+  #[test]
+  #[should_panic]
+  fn write_illegal_move() {
+    let game = KlondikeSolitaireGame::new(1);
+    let moves = [Move::Cards(MoveSource::Deck, MoveTarget::Foundation)];
+    write_game(&mut Vec::new(), 0, &game, &moves).unwrap();
+  }
+}
@@ -0,0 +1,190 @@
+//! Genetic search for `WeightedStrategy` weights.
+//!
+//! A population of weight vectors, genomes, is bred for a number of
+//! generations. Each generation keeps its fittest genome as it is and fills
+//! the rest of the population with children of parents picked by two-way
+//! tournaments: a uniform crossover of both parents, or a copy of the first,
+//! with each weight then nudged by a random amount at the mutation rate.
+//!
+//! Fitness is up to the caller; `win_rate` is the share of a fixed set of
+//! deals the weights win, so every genome is scored on the same deals and a
+//! fitness is the same every time it is computed.
+
+use games::solitaire::klondike::KlondikeSolitaireGame;
+use strategies::solitaire::klondike::GameStrategy;
+use strategies::solitaire::klondike::weighted::{WeightedStrategy, Weights};
+use rand::Rng;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TuneConfig {
+  pub population: usize,
+  pub generations: usize,
+  /// Chance a child is a crossover of its parents rather than a copy of one.
+  pub crossover: f64,
+  /// Chance each weight of a child is mutated.
+  pub mutation: f64,
+  /// Largest amount a mutation adds to or takes off a weight.
+  pub mutation_size: f64,
+}
+
+impl Default for TuneConfig {
+  fn default() -> TuneConfig {
+    TuneConfig {
+      population: 20,
+      generations: 10,
+      crossover: 0.7,
+      mutation: 0.2,
+      mutation_size: 10.0,
+    }
+  }
+}
+
+/// What `evolve` reports after scoring a generation.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Generation {
+  /// 0 for the first population.
+  pub number: usize,
+  pub best: Weights,
+  pub best_fitness: f64,
+  pub mean_fitness: f64,
+}
+
+/// Breed `config.generations` generations after a first population of the
+/// default weights and mutations of them, and return the fittest genome with
+/// its fitness. `report` is called as each generation is scored.
+pub fn evolve<R, F, P>(config: &TuneConfig, rng: &mut R, mut fitness: F, mut report: P) -> (Weights, f64)
+  where R: Rng, F: FnMut(&Weights) -> f64, P: FnMut(&Generation) {
+  assert!(config.population > 1, "population must hold at least 2 genomes");
+
+  let default = Weights::default().to_vec();
+  let mut population: Vec<(Vec<f64>, f64)> = (0..config.population)
+    .map(|i| {
+      let mut genome = default.clone();
+      if i > 0 {
+        for gene in genome.iter_mut() {
+          mutate(gene, config, rng);
+        }
+      }
+      let score = fitness(&Weights::from_slice(&genome));
+      (genome, score)
+    })
+    .collect();
+
+  for number in 0.. {
+    sort_fittest_first(&mut population);
+    let mean = population.iter().map(|g| g.1).sum::<f64>() / population.len() as f64;
+    report(&Generation {
+      number: number,
+      best: Weights::from_slice(&population[0].0),
+      best_fitness: population[0].1,
+      mean_fitness: mean,
+    });
+
+    if number == config.generations {
+      break;
+    }
+
+    let mut next = vec![population[0].clone()];
+    while next.len() < config.population {
+      let first = tournament(&population, rng);
+      let second = tournament(&population, rng);
+      let mut child = if rng.gen::<f64>() < config.crossover {
+        first.iter().zip(second).map(|(a, b)| if rng.gen() { *a } else { *b }).collect()
+      } else {
+        first.clone()
+      };
+
+      for gene in child.iter_mut() {
+        if rng.gen::<f64>() < config.mutation {
+          mutate(gene, config, rng);
+        }
+      }
+
+      let score = fitness(&Weights::from_slice(&child));
+      next.push((child, score));
+    }
+    population = next;
+  }
+
+  (Weights::from_slice(&population[0].0), population[0].1)
+}
+
+/// Share of `deals` `WeightedStrategy` wins with `weights`.
+pub fn win_rate(weights: &Weights, deals: &[KlondikeSolitaireGame]) -> f64 {
+  let mut strategy = WeightedStrategy::with_weights(*weights);
+  let wins = deals.iter()
+    .filter(|deal| {
+      let mut game = (*deal).clone();
+      strategy.play(&mut game);
+      game.is_clear()
+    })
+    .count();
+
+  wins as f64 / deals.len() as f64
+}
+
+/// Add up to `config.mutation_size` to `gene` either way, rounded to a tenth
+/// so the weights print short.
+fn mutate<R: Rng>(gene: &mut f64, config: &TuneConfig, rng: &mut R) {
+  *gene = ((*gene + rng.gen_range(-config.mutation_size, config.mutation_size)) * 10.0).round() / 10.0;
+}
+
+fn sort_fittest_first(population: &mut [(Vec<f64>, f64)]) {
+  population.sort_by(|a, b| b.1.partial_cmp(&a.1).expect("fitness is not a number"));
+}
+
+/// Genome of the fitter of two picked at random.
+fn tournament<'a, R: Rng>(population: &'a [(Vec<f64>, f64)], rng: &mut R) -> &'a Vec<f64> {
+  let a = &population[rng.gen_range(0, population.len())];
+  let b = &population[rng.gen_range(0, population.len())];
+  if a.1 >= b.1 { &a.0 } else { &b.0 }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cards::seeded_source;
+  use rand::{Isaac64Rng, SeedableRng};
+
+  fn distance(weights: &Weights, target: f64) -> f64 {
+    -weights.to_vec().iter().map(|w| (w - target).abs()).sum::<f64>()
+  }
+
+  #[test]
+  fn evolve_keeps_the_fittest() {
+    let mut rng = Isaac64Rng::from_seed(&[1]);
+    let mut generations = Vec::new();
+    let (best, fitness) = evolve(&TuneConfig::default(), &mut rng, |w| distance(w, 0.0), |g| generations.push(*g));
+
+    assert_eq!(generations.len(), TuneConfig::default().generations + 1);
+    assert_eq!(generations.last().unwrap().best, best);
+    assert_eq!(fitness, distance(&best, 0.0));
+    for pair in generations.windows(2) {
+      assert!(pair[1].best_fitness >= pair[0].best_fitness);
+      assert!(pair[0].best_fitness >= pair[0].mean_fitness);
+    }
+    assert!(fitness > generations[0].best_fitness);
+  }
+
+  #[test]
+  fn evolve_is_seeded() {
+    let run = || {
+      let mut rng = Isaac64Rng::from_seed(&[7]);
+      evolve(&TuneConfig::default(), &mut rng, |w| distance(w, 3.0), |_| {})
+    };
+
+    assert_eq!(run(), run());
+  }
+
+  #[test]
+  fn win_rate_of_deals() {
+    let deals: Vec<KlondikeSolitaireGame> = (0..20)
+      .map(|seed| KlondikeSolitaireGame::new_shuffle(1, seeded_source(seed)))
+      .collect();
+    let rate = win_rate(&Weights::default(), &deals);
+
+    assert!(rate <= 1.0);
+    assert_eq!(rate * 20.0, (rate * 20.0).round());
+    assert_eq!(rate, win_rate(&Weights::default(), &deals));
+  }
+}